@@ -1,15 +1,33 @@
-const { asyncHandler, createError } = require('../middleware/errorHandler')
+const { asyncHandler, createError, rethrowRateLimit } = require('../middleware/errorHandler')
 
 /**
  * Memory Controller - Handles all memory-related operations
  */
 
+/**
+ * Whether to answer with CBOR: the server must be configured for it and the
+ * client must prefer application/cbor over JSON
+ */
+const wantsCbor = (req) =>
+  req.app.locals.rustBridge.config.response_format === 'cbor' &&
+  req.accepts(['application/json', 'application/cbor']) === 'application/cbor'
+
+/**
+ * Who a save records as `ingestedBy`: the authenticated API key, whatever
+ * the body claims, else the body's value
+ */
+const ingestedByFor = (req, ingestedBy) =>
+  req.principal ? req.principal.name : (ingestedBy || null)
+
 /**
  * Save a new memory
  * @route POST /api/memory/save
  */
 const saveMemory = asyncHandler(async (req, res) => {
-  const { userId, sessionId, content, metadata, importance, ttlHours } = req.body
+  const {
+    userId, sessionId, content, metadata, importance, confidence, ttlHours, state,
+    sourceType, sourceRef, ingestedBy, idempotencyKey
+  } = req.body
   const rustBridge = req.app.locals.rustBridge
 
   try {
@@ -25,7 +43,13 @@ const saveMemory = asyncHandler(async (req, res) => {
       content,
       metadata: metadata || {},
       importance: importance || 0.5,
-      ttlHours: ttlHours || null
+      confidence: confidence ?? 1,
+      ttlHours: ttlHours || null,
+      state: state || 'active',
+      sourceType: sourceType || null,
+      sourceRef: sourceRef || null,
+      ingestedBy: ingestedByFor(req, ingestedBy),
+      idempotencyKey: req.get('Idempotency-Key') || idempotencyKey || null
     })
 
     res.status(201).json({
@@ -34,11 +58,13 @@ const saveMemory = asyncHandler(async (req, res) => {
         memoryId,
         userId,
         sessionId,
+        state: state || 'active',
         timestamp: new Date().toISOString()
       },
-      message: 'Memory saved successfully'
+      message: state === 'draft' ? 'Draft memory saved' : 'Memory saved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error saving memory:', error)
     throw createError(
       error.message || 'Failed to save memory',
@@ -62,7 +88,21 @@ const recallMemories = asyncHandler(async (req, res) => {
     dateTo,
     limit,
     minImportance,
-    keywords
+    minConfidence,
+    maxConfidence,
+    keywords,
+    states,
+    sourceTypes,
+    sourceRef,
+    ingestedBy,
+    semanticQuery,
+    excludeKeywords,
+    excludeSessionIds,
+    excludeTags,
+    contentType,
+    minContentLength,
+    maxContentLength,
+    explain
   } = req.body
 
   const rustBridge = req.app.locals.rustBridge
@@ -77,7 +117,27 @@ const recallMemories = asyncHandler(async (req, res) => {
       dateTo: dateTo ? new Date(dateTo) : null,
       limit: limit || 50,
       minImportance: minImportance || null,
-      keywords: keywords || null
+      minConfidence: minConfidence ?? null,
+      maxConfidence: maxConfidence ?? null,
+      keywords: keywords || null,
+      states: states || [],
+      sourceTypes: sourceTypes || [],
+      sourceRef: sourceRef || null,
+      ingestedBy: ingestedBy || null,
+      semanticQuery: semanticQuery || null,
+      excludeKeywords: excludeKeywords || [],
+      excludeSessionIds: excludeSessionIds || [],
+      excludeTags: excludeTags || [],
+      contentType: contentType || null,
+      minContentLength: minContentLength ?? null,
+      maxContentLength: maxContentLength ?? null,
+      explain: explain === true
+    }
+
+    // CBOR clients get the recall page as-is, without the JSON envelope
+    if (wantsCbor(req)) {
+      const encoded = await rustBridge.recallMemoriesEncoded(filter)
+      return res.type('application/cbor').send(encoded)
     }
 
     // Recall memories using Rust bridge
@@ -97,6 +157,7 @@ const recallMemories = asyncHandler(async (req, res) => {
       message: `Found ${memories.data ? memories.data.length : 0} memories`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error recalling memories:', error)
     throw createError(
       error.message || 'Failed to recall memories',
@@ -129,6 +190,7 @@ const summarizeSession = asyncHandler(async (req, res) => {
       message: 'Session summary generated successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error generating summary:', error)
 
     // Handle specific case where session has no memories
@@ -159,6 +221,12 @@ const exportUserMemories = asyncHandler(async (req, res) => {
   const rustBridge = req.app.locals.rustBridge
 
   try {
+    if (wantsCbor(req)) {
+      const encoded = await rustBridge.exportUserMemoriesEncoded(userId)
+      res.setHeader('Content-Disposition', `attachment; filename="memex-export-${userId}-${Date.now()}.cbor"`)
+      return res.type('application/cbor').send(encoded)
+    }
+
     // Export memories using Rust bridge
     const exportData = await rustBridge.exportUserMemories(userId)
 
@@ -176,6 +244,7 @@ const exportUserMemories = asyncHandler(async (req, res) => {
       message: 'Memories exported successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error exporting memories:', error)
     throw createError(
       error.message || 'Failed to export memories',
@@ -207,6 +276,7 @@ const runDecayProcess = asyncHandler(async (req, res) => {
       message: 'Memory decay process completed successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error running decay process:', error)
     throw createError(
       error.message || 'Failed to run decay process',
@@ -229,7 +299,8 @@ const searchMemories = asyncHandler(async (req, res) => {
     to: dateTo,
     limit,
     importance: minImportance,
-    tags
+    tags,
+    explain
   } = req.query
 
   const rustBridge = req.app.locals.rustBridge
@@ -247,7 +318,8 @@ const searchMemories = asyncHandler(async (req, res) => {
       dateTo: dateTo ? new Date(dateTo) : null,
       limit: parseInt(limit) || 50,
       minImportance: parseFloat(minImportance) || null,
-      keywords
+      keywords,
+      explain: explain === true
     }
 
     // Search memories using Rust bridge
@@ -268,6 +340,7 @@ const searchMemories = asyncHandler(async (req, res) => {
       message: `Found ${memories.data ? memories.data.length : 0} memories matching search criteria`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error searching memories:', error)
     throw createError(
       error.message || 'Failed to search memories',
@@ -300,7 +373,11 @@ const bulkSaveMemories = asyncHandler(async (req, res) => {
           content: memory.content,
           metadata: memory.metadata || {},
           importance: memory.importance || 0.5,
-          ttlHours: memory.ttlHours || null
+          ttlHours: memory.ttlHours || null,
+          sourceType: memory.sourceType || null,
+          sourceRef: memory.sourceRef || null,
+          ingestedBy: ingestedByFor(req, memory.ingestedBy),
+          idempotencyKey: memory.idempotencyKey || null
         })
 
         results.push({
@@ -311,6 +388,7 @@ const bulkSaveMemories = asyncHandler(async (req, res) => {
           status: 'success'
         })
       } catch (error) {
+        rethrowRateLimit(error)
         console.error(`Error saving memory at index ${i}:`, error)
         errors.push({
           index: i,
@@ -344,6 +422,7 @@ const bulkSaveMemories = asyncHandler(async (req, res) => {
 
     res.status(statusCode).json(response)
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error in bulk save operation:', error)
     throw createError(
       error.message || 'Failed to perform bulk save operation',
@@ -354,6 +433,167 @@ const bulkSaveMemories = asyncHandler(async (req, res) => {
   }
 })
 
+/**
+ * Confirm, archive or restore a memory
+ * @route PUT /api/memory/:memoryId/state
+ */
+const setMemoryState = asyncHandler(async (req, res) => {
+  const { memoryId } = req.params
+  const { state } = req.body
+  const rustBridge = req.app.locals.rustBridge
+
+  try {
+    const previousState = await rustBridge.setMemoryState(memoryId, state)
+
+    if (previousState === null) {
+      throw createError(
+        'Memory not found',
+        'MEMORY_NOT_FOUND',
+        404,
+        { memoryId }
+      )
+    }
+
+    res.json({
+      success: true,
+      data: {
+        memoryId,
+        state,
+        previousState
+      },
+      message: `Memory is now ${state}`
+    })
+  } catch (error) {
+    rethrowRateLimit(error)
+    console.error('Error setting memory state:', error)
+
+    if (error.code === 'MEMORY_NOT_FOUND') {
+      throw error
+    }
+
+    if (error.conflict) {
+      throw createError(
+        error.message,
+        'INVALID_STATE_TRANSITION',
+        409,
+        { memoryId, state }
+      )
+    }
+
+    throw createError(
+      error.message || 'Failed to set memory state',
+      'MEMORY_STATE_ERROR',
+      500,
+      { memoryId, state }
+    )
+  }
+})
+
+/**
+ * Memories of a user that may contradict one of theirs
+ * @route GET /api/memory/conflicts/:userId/:memoryId
+ */
+const findConflicts = asyncHandler(async (req, res) => {
+  const { userId, memoryId } = req.params
+  const rustBridge = req.app.locals.rustBridge
+
+  try {
+    const conflicts = await rustBridge.findConflicts(userId, memoryId)
+
+    if (conflicts === null) {
+      throw createError(
+        'Memory not found',
+        'MEMORY_NOT_FOUND',
+        404,
+        { userId, memoryId }
+      )
+    }
+
+    res.json({
+      success: true,
+      data: {
+        memoryId,
+        conflicts
+      },
+      message: `Found ${conflicts.length} possible conflicts`
+    })
+  } catch (error) {
+    rethrowRateLimit(error)
+    console.error('Error finding conflicts:', error)
+
+    if (error.code === 'MEMORY_NOT_FOUND') {
+      throw error
+    }
+
+    throw createError(
+      error.message || 'Failed to find conflicts',
+      'CONFLICTS_ERROR',
+      500,
+      { userId, memoryId }
+    )
+  }
+})
+
+/**
+ * Link two memories, or remove the link between them
+ */
+function linkHandler (unlink) {
+  return asyncHandler(async (req, res) => {
+    const { memoryId } = req.params
+    const { otherId, linkType } = req.body
+    const rustBridge = req.app.locals.rustBridge
+
+    try {
+      const linked = await rustBridge.linkMemories(memoryId, otherId, linkType, unlink)
+
+      if (!linked) {
+        throw createError(
+          unlink ? 'Link not found' : 'Memory not found',
+          unlink ? 'LINK_NOT_FOUND' : 'MEMORY_NOT_FOUND',
+          404,
+          { memoryId, otherId, linkType }
+        )
+      }
+
+      res.json({
+        success: true,
+        data: {
+          memoryId,
+          otherId,
+          linkType
+        },
+        message: unlink ? 'Link removed' : `Memories linked as ${linkType}`
+      })
+    } catch (error) {
+      rethrowRateLimit(error)
+      console.error('Error linking memories:', error)
+
+      if (error.code === 'LINK_NOT_FOUND' || error.code === 'MEMORY_NOT_FOUND') {
+        throw error
+      }
+
+      throw createError(
+        error.message || 'Failed to link memories',
+        'MEMORY_LINK_ERROR',
+        500,
+        { memoryId, otherId, linkType }
+      )
+    }
+  })
+}
+
+/**
+ * Confirm or dismiss a conflict between two memories
+ * @route PUT /api/memory/:memoryId/links
+ */
+const linkMemories = linkHandler(false)
+
+/**
+ * Remove a link between two memories
+ * @route DELETE /api/memory/:memoryId/links
+ */
+const unlinkMemories = linkHandler(true)
+
 /**
  * Get memory statistics for a specific user
  * @route GET /api/memory/stats/:userId
@@ -391,6 +631,7 @@ const getUserMemoryStats = asyncHandler(async (req, res) => {
       message: 'User memory statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting user memory stats:', error)
     throw createError(
       error.message || 'Failed to get user memory statistics',
@@ -471,5 +712,9 @@ module.exports = {
   runDecayProcess,
   searchMemories,
   bulkSaveMemories,
+  setMemoryState,
+  findConflicts,
+  linkMemories,
+  unlinkMemories,
   getUserMemoryStats
 }
@@ -1,4 +1,4 @@
-const { asyncHandler, createError } = require('../middleware/errorHandler')
+const { asyncHandler, createError, rethrowRateLimit } = require('../middleware/errorHandler')
 
 /**
  * Session Controller - Handles all session-related operations
@@ -28,6 +28,7 @@ const createSession = asyncHandler(async (req, res) => {
       message: 'Session created successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error creating session:', error)
     throw createError(
       error.message || 'Failed to create session',
@@ -60,6 +61,7 @@ const getUserSessions = asyncHandler(async (req, res) => {
       message: `Found ${sessions.length} sessions for user`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting user sessions:', error)
     throw createError(
       error.message || 'Failed to get user sessions',
@@ -109,6 +111,7 @@ const getSession = asyncHandler(async (req, res) => {
       message: 'Session retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting session:', error)
 
     if (error.code === 'SESSION_NOT_FOUND' || error.code === 'SESSION_ACCESS_DENIED') {
@@ -164,6 +167,7 @@ const getSessionMemories = asyncHandler(async (req, res) => {
       message: `Retrieved ${paginatedMemories.length} memories from session`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting session memories:', error)
     throw createError(
       error.message || 'Failed to get session memories',
@@ -199,6 +203,7 @@ const searchSessions = asyncHandler(async (req, res) => {
       message: `Found ${sessions.length} sessions matching search criteria`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error searching sessions:', error)
     throw createError(
       error.message || 'Failed to search sessions',
@@ -256,6 +261,7 @@ const updateSession = asyncHandler(async (req, res) => {
       message: 'Session updated successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error updating session:', error)
 
     if (error.code === 'SESSION_NOT_FOUND' || error.code === 'SESSION_ACCESS_DENIED') {
@@ -326,6 +332,7 @@ const deleteSession = asyncHandler(async (req, res) => {
       message: `Session and ${deletionResult.memoriesDeleted || 0} memories deleted successfully`
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error deleting session:', error)
 
     if (error.code === 'SESSION_NOT_FOUND' ||
@@ -365,6 +372,7 @@ const summarizeSession = asyncHandler(async (req, res) => {
       message: 'Session summary generated successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error generating session summary:', error)
 
     // Handle specific case where session has no memories
@@ -1,4 +1,4 @@
-const { asyncHandler, createError } = require('../middleware/errorHandler')
+const { asyncHandler, createError, rethrowRateLimit } = require('../middleware/errorHandler')
 const os = require('os')
 
 /**
@@ -37,6 +37,7 @@ const getSystemStats = asyncHandler(async (req, res) => {
       message: 'System statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting system stats:', error)
     throw createError(
       error.message || 'Failed to get system statistics',
@@ -54,6 +55,38 @@ const getUserStats = asyncHandler(async (req, res) => {
   const { userId } = req.params
   const rustBridge = req.app.locals.rustBridge
 
+  // Keys in privacy mode get the core's aggregated figures instead of ones
+  // computed from the raw memories
+  if (req.principal && req.principal.analytics_privacy) {
+    try {
+      const [memories, analytics, sessions] = await Promise.all([
+        rustBridge.getUserStats(userId),
+        rustBridge.getUserAnalytics(userId),
+        rustBridge.getSessionAnalytics(userId)
+      ])
+
+      return res.json({
+        success: true,
+        data: {
+          userId,
+          stats: { memories, analytics, sessions },
+          aggregated: true,
+          calculatedAt: new Date().toISOString()
+        },
+        message: 'User statistics retrieved successfully'
+      })
+    } catch (error) {
+      rethrowRateLimit(error)
+      console.error('Error getting user stats:', error)
+      throw createError(
+        error.message || 'Failed to get user statistics',
+        'USER_STATS_ERROR',
+        500,
+        { userId }
+      )
+    }
+  }
+
   try {
     // Get user-specific memories
     const userMemories = await rustBridge.recallMemories({
@@ -77,6 +110,7 @@ const getUserStats = asyncHandler(async (req, res) => {
       message: 'User statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting user stats:', error)
     throw createError(
       error.message || 'Failed to get user statistics',
@@ -131,6 +165,7 @@ const getMemoryStats = asyncHandler(async (req, res) => {
       message: 'Memory statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting memory stats:', error)
     throw createError(
       error.message || 'Failed to get memory statistics',
@@ -164,6 +199,7 @@ const getSessionStats = asyncHandler(async (req, res) => {
       message: 'Session statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting session stats:', error)
     throw createError(
       error.message || 'Failed to get session statistics',
@@ -194,6 +230,7 @@ const getDecayStats = asyncHandler(async (req, res) => {
       message: 'Decay statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting decay stats:', error)
     throw createError(
       error.message || 'Failed to get decay statistics',
@@ -229,6 +266,7 @@ const getPerformanceStats = asyncHandler(async (req, res) => {
       message: 'Performance statistics retrieved successfully'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting performance stats:', error)
     throw createError(
       error.message || 'Failed to get performance statistics',
@@ -253,6 +291,7 @@ const getHealthStats = asyncHandler(async (req, res) => {
     try {
       await rustBridge.getStats()
     } catch (error) {
+      rethrowRateLimit(error)
       rustHealth = 'unhealthy'
       rustError = error.message
     }
@@ -297,6 +336,7 @@ const getHealthStats = asyncHandler(async (req, res) => {
       message: isHealthy ? 'System is healthy' : 'System health degraded'
     })
   } catch (error) {
+    rethrowRateLimit(error)
     console.error('Error getting health stats:', error)
 
     res.status(503).json({
@@ -554,6 +594,34 @@ function getGCStats () {
   }
 }
 
+/**
+* Get audited writes, newest first
+* @route GET /api/stats/audit
+*/
+const getAuditLog = asyncHandler(async (req, res) => {
+  const rustBridge = req.app.locals.rustBridge
+  const { actor, traceId, targetId } = req.query
+  const limit = Math.min(parseInt(req.query.limit, 10) || 100, 1000)
+
+  try {
+    const entries = await rustBridge.getAuditLog({ actor, traceId, targetId }, limit)
+
+    res.json({
+      success: true,
+      data: entries,
+      message: 'Audit log retrieved successfully'
+    })
+  } catch (error) {
+    rethrowRateLimit(error)
+    console.error('Error getting audit log:', error)
+    throw createError(
+      error.message || 'Failed to get audit log',
+      'AUDIT_LOG_ERROR',
+      500
+    )
+  }
+})
+
 module.exports = {
   getSystemStats,
   getUserStats,
@@ -561,5 +629,6 @@ module.exports = {
   getSessionStats,
   getDecayStats,
   getPerformanceStats,
-  getHealthStats
+  getHealthStats,
+  getAuditLog
 }
@@ -2,6 +2,93 @@ const ffi = require('ffi-napi');
 const ref = require('ref-napi');
 const path = require('path');
 const fs = require('fs');
+const { AsyncLocalStorage } = require('async_hooks');
+
+/**
+ * Request context ({ actor, trace_id, source }) of the HTTP request being
+ * served; writes made while one is set are recorded in the audit log with it
+ */
+const requestContext = new AsyncLocalStorage();
+
+/**
+ * Calls that must not change the Rust thread's request context: setting it
+ * clears the last error these read, and the rest never write
+ */
+const CONTEXT_FREE_CALLS = new Set([
+  'memex_set_request_context',
+  'memex_get_last_error',
+  'memex_error_message',
+  'memex_last_error_json',
+  'memex_free_string',
+  'memex_free_buffer',
+  'memex_version',
+  'memex_is_valid'
+]);
+
+/**
+ * FFI error code for a token that matches no API key
+ */
+const PERMISSION_DENIED = 14;
+
+/**
+ * FFI error codes for a missing memory and a state change it can't make
+ */
+const NOT_FOUND = 7;
+const CONFLICT = 13;
+
+/**
+ * FFI error code for a request the core's rate limiter turned away
+ */
+const RATE_LIMITED = 10;
+
+/**
+ * Code of the error thrown for a rate-limited call, which the methods below
+ * let through unwrapped so the server can answer 429
+ */
+const RATE_LIMIT_EXCEEDED = 'RATE_LIMIT_EXCEEDED';
+
+/**
+ * Error for a call the core rate limited, carrying the seconds to wait
+ * before retrying as `retryAfter` for the Retry-After header
+ */
+function rateLimitError(lib) {
+  const details = JSON.parse(lib.memex_last_error_json() || '{}');
+  const error = new Error(details.message || 'Rate limit exceeded');
+  error.code = RATE_LIMIT_EXCEEDED;
+  error.status = 429;
+  error.retryAfter = Math.max(1, Math.ceil((details.retry_after_ms || 0) / 1000));
+  return error;
+}
+
+/**
+ * Convert a camelCase recall filter to the Rust QueryFilter format
+ */
+function toQueryFilter(filter, defaultLimit) {
+  return {
+    user_id: filter.userId || null,
+    session_id: filter.sessionId || null,
+    keywords: filter.query ? filter.query.split(' ') : null,
+    date_from: filter.dateFrom || null,
+    date_to: filter.dateTo || null,
+    limit: filter.limit || defaultLimit,
+    offset: filter.offset || 0,
+    min_importance: filter.minImportance || null,
+    min_confidence: filter.minConfidence ?? null,
+    max_confidence: filter.maxConfidence ?? null,
+    source_types: filter.sourceTypes || [],
+    source_ref: filter.sourceRef || null,
+    ingested_by: filter.ingestedBy || null,
+    semantic_query: filter.semanticQuery || null,
+    exclude_keywords: filter.excludeKeywords || [],
+    exclude_session_ids: filter.excludeSessionIds || [],
+    exclude_tags: filter.excludeTags || [],
+    content_type: filter.contentType || null,
+    min_content_length: filter.minContentLength ?? null,
+    max_content_length: filter.maxContentLength ?? null,
+    explain: filter.explain === true,
+    states: filter.states || []
+  };
+}
 
 /**
  * Rust Bridge - FFI interface to Memex Rust core
@@ -20,8 +107,14 @@ class RustBridge {
       enable_compression: config.enable_compression !== false,
       max_memories_per_user: config.max_memories_per_user || 10000,
       importance_threshold: config.importance_threshold || 0.3,
+      auto_create_sessions: config.auto_create_sessions !== false,
+      lenient_filters: config.lenient_filters === true,
+      response_format: config.response_format || 'json',
+      maintenance: config.maintenance || {},
+      access: config.access || {},
       enable_request_limits: config.enable_request_limits !== false,
       max_requests_per_minute: config.max_requests_per_minute || 1000,
+      rate_limit_wait_ms: config.rate_limit_wait_ms || 0,
       max_batch_size: config.max_batch_size || 100
     };
 
@@ -75,16 +168,38 @@ class RustBridge {
 
       // Memory operations - Updated signatures
       memex_save: ['string', ['size_t', 'string', 'string', 'string', 'float', 'int', 'string']],
+      memex_save_idempotent: ['string', ['size_t', 'string', 'string', 'string', 'float', 'int', 'string', 'string']],
+      memex_save_with_receipt: ['string', ['size_t', 'string', 'string', 'string', 'float', 'int', 'string']],
       memex_save_batch: ['string', ['size_t', 'string', 'bool']],
+      memex_save_batch_with_policy: ['string', ['size_t', 'string', 'bool', 'string']],
+      memex_set_memory_state: ['string', ['size_t', 'string', 'string']],
+      memex_find_conflicts: ['string', ['size_t', 'string', 'string']],
+      memex_link_memories: ['bool', ['size_t', 'string', 'string', 'string', 'bool']],
+      memex_set_json_schema: ['bool', ['size_t', 'string', 'string']],
+      memex_add_synonym: ['bool', ['size_t', 'string', 'string', 'string']],
+      memex_remove_synonym: ['bool', ['size_t', 'string', 'string', 'string']],
+      memex_list_synonyms: ['string', ['size_t', 'string']],
+      memex_set_template: ['bool', ['size_t', 'string']],
+      memex_list_templates: ['string', ['size_t']],
+      memex_save_from_template: ['string', ['size_t', 'string', 'string', 'string', 'string']],
       memex_recall: ['string', ['size_t', 'string']],
+      memex_recall_begin: ['size_t', ['size_t', 'string', 'int']],
+      memex_recall_next_chunk: ['string', ['size_t']],
+      memex_recall_end: ['bool', ['size_t']],
+      memex_recall_encoded: ['pointer', ['size_t', 'string', 'pointer']],
+      memex_recall_next_chunk_encoded: ['pointer', ['size_t', 'pointer']],
       memex_search: ['string', ['size_t', 'string', 'string', 'int', 'int']],
+      memex_search_grouped: ['string', ['size_t', 'string', 'string', 'int']],
       memex_get_memory: ['string', ['size_t', 'string']],
+      memex_get_memories: ['string', ['size_t', 'string']],
       memex_update_memory: ['bool', ['size_t', 'string', 'string']],
+      memex_extend_ttl: ['bool', ['size_t', 'string', 'uint32']],
       memex_delete_memory: ['bool', ['size_t', 'string']],
 
       // Session operations
       memex_create_session: ['string', ['size_t', 'string', 'string']],
       memex_get_user_sessions: ['string', ['size_t', 'string', 'int', 'int']],
+      memex_set_session_status: ['bool', ['size_t', 'string', 'string']],
       memex_summarize_session: ['string', ['size_t', 'string']],
       memex_search_sessions: ['string', ['size_t', 'string', 'string']],
       memex_delete_session: ['bool', ['size_t', 'string', 'bool']],
@@ -94,24 +209,72 @@ class RustBridge {
       memex_decay_analyze: ['string', ['size_t']],
       memex_update_decay_policy: ['bool', ['size_t', 'string']],
 
+      // Maintenance jobs
+      memex_maintenance_run: ['string', ['size_t', 'string']],
+      memex_maintenance_run_due: ['string', ['size_t']],
+      memex_maintenance_history: ['string', ['size_t', 'string', 'int']],
+
       // Statistics and utilities
       memex_get_stats: ['string', ['size_t']],
+      memex_get_counters: ['string', ['size_t']],
+      memex_get_audit_log: ['string', ['size_t', 'string', 'int']],
+      memex_authenticate: ['string', ['size_t', 'string']],
+      memex_set_request_context: ['bool', ['string']],
+      memex_health: ['string', ['size_t']],
+      memex_checkpoint: ['string', ['size_t', 'string']],
       memex_export_user_memories: ['string', ['size_t', 'string']],
+      memex_export_user_memories_encoded: ['pointer', ['size_t', 'string', 'pointer']],
       memex_get_user_stats: ['string', ['size_t', 'string']],
       memex_get_session_analytics: ['string', ['size_t', 'string']],
+      memex_get_user_analytics: ['string', ['size_t', 'string']],
 
       // Error handling
       memex_get_last_error: ['int', []],
       memex_error_message: ['string', ['int']],
+      memex_last_error_json: ['string', []],
 
       // Utility functions
       memex_free_string: ['void', ['string']],
+      memex_free_buffer: ['void', ['pointer', 'size_t']],
       memex_version: ['string', []]
     });
+    this.applyRequestContext();
 
     console.log('✅ Rust library loaded successfully');
   }
 
+  /**
+   * Make every call run inside the current request's context, so the Rust
+   * core attributes its writes. The context is per thread on the Rust side
+   * and calls are synchronous, so setting it just before each call is enough.
+   * Calls the core rate limits throw a RATE_LIMIT_EXCEEDED error
+   */
+  applyRequestContext() {
+    const lib = this.rustLib;
+    let applied = null;
+
+    for (const name of Object.keys(lib)) {
+      const call = lib[name];
+      if (CONTEXT_FREE_CALLS.has(name) || typeof call !== 'function') {
+        continue;
+      }
+
+      lib[name] = (...args) => {
+        const context = requestContext.getStore();
+        const json = context ? JSON.stringify(context) : null;
+        if (json !== applied) {
+          lib.memex_set_request_context(json);
+          applied = json;
+        }
+        const result = call(...args);
+        if (lib.memex_get_last_error() === RATE_LIMITED) {
+          throw rateLimitError(lib);
+        }
+        return result;
+      };
+    }
+  }
+
   /**
    * Get the correct library path for the current platform
    */
@@ -190,8 +353,13 @@ class RustBridge {
       enable_compression: this.config.enable_compression,
       max_memories_per_user: this.config.max_memories_per_user,
       importance_threshold: this.config.importance_threshold,
+      auto_create_sessions: this.config.auto_create_sessions,
+      lenient_filters: this.config.lenient_filters,
+      response_format: this.config.response_format,
+      maintenance: this.config.maintenance,
       enable_request_limits: this.config.enable_request_limits,
       max_requests_per_minute: this.config.max_requests_per_minute,
+      rate_limit_wait_ms: this.config.rate_limit_wait_ms,
       max_batch_size: this.config.max_batch_size
     };
 
@@ -223,25 +391,70 @@ class RustBridge {
 
   /**
    * Save a memory item - Updated to match new API
+   *
+   * With state 'draft' the memory isn't recalled until setMemoryState
+   * confirms it. Confidence (0-1, default 1) is how sure the caller is the
+   * memory is true; unsure memories decay sooner. sourceType ('user',
+   * 'agent', 'tool' or 'import'), sourceRef and ingestedBy record where the
+   * memory came from. A retried save with the same idempotencyKey returns
+   * the first save's id instead of saving again
    */
-  async saveMemory({ userId, sessionId, content, metadata = {}, importance = 0.5, ttlHours = null }) {
+  async saveMemory({
+    userId, sessionId, content, metadata = {}, importance = 0.5, confidence = 1, ttlHours = null,
+    state = 'active', sourceType = null, sourceRef = null, ingestedBy = null, idempotencyKey = null
+  }) {
     this.ensureInitialized();
 
+    if (state !== 'active' || confidence !== 1 || sourceType || sourceRef || ingestedBy) {
+      // memex_save has no state, confidence or provenance arguments; a batch
+      // of one takes a full item
+      const response = await this.saveMemoriesBatch([{
+        user_id: userId,
+        session_id: sessionId,
+        content,
+        metadata,
+        importance,
+        confidence,
+        ttl_hours: ttlHours,
+        state,
+        source_type: sourceType,
+        source_ref: sourceRef,
+        ingested_by: ingestedBy,
+        idempotency_key: idempotencyKey
+      }], true);
+      const [saved] = response.results;
+      if (!saved.success) {
+        throw new Error(`Failed to save memory: ${saved.error}`);
+      }
+      return saved.result;
+    }
+
     try {
       const metadataJson = JSON.stringify(metadata);
       const ttl = ttlHours || -1; // -1 means no TTL
 
       console.log(`💾 Saving memory for user ${userId}, session ${sessionId}`);
 
-      const result = this.rustLib.memex_save(
-        this.handle,
-        userId,
-        sessionId,
-        content,
-        importance,
-        ttl,
-        metadataJson
-      );
+      const result = idempotencyKey
+        ? this.rustLib.memex_save_idempotent(
+          this.handle,
+          userId,
+          sessionId,
+          content,
+          importance,
+          ttl,
+          metadataJson,
+          idempotencyKey
+        )
+        : this.rustLib.memex_save(
+          this.handle,
+          userId,
+          sessionId,
+          content,
+          importance,
+          ttl,
+          metadataJson
+        );
 
       if (!result) {
         const errorCode = this.rustLib.memex_get_last_error();
@@ -252,15 +465,143 @@ class RustBridge {
       console.log(`✅ Memory saved with ID: ${result}`);
       return result;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error saving memory:', error);
       throw new Error(`Failed to save memory: ${error.message}`);
     }
   }
 
   /**
-   * Save multiple memories in batch
+   * Save a memory item and return its receipt: id, created_at, written_at,
+   * deduplicated (an existing id was replaced), session_created and
+   * bytes_written
+   */
+  async saveMemoryWithReceipt({ userId, sessionId, content, metadata = {}, importance = 0.5, ttlHours = null }) {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_save_with_receipt(
+      this.handle,
+      userId,
+      sessionId,
+      content,
+      importance,
+      ttlHours || -1, // -1 means no TTL
+      JSON.stringify(metadata)
+    );
+
+    if (!result) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to save memory: ${errorMessage || 'Unknown error'}`);
+    }
+    return JSON.parse(result);
+  }
+
+  /**
+   * Register the JSON Schema that JSON memories (content_type "json") with
+   * this namespace metadata must match
+   */
+  async setJsonSchema(namespace, schema) {
+    this.ensureInitialized();
+
+    if (!this.rustLib.memex_set_json_schema(this.handle, namespace, JSON.stringify(schema))) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to set JSON schema: ${errorMessage || 'Unknown error'}`);
+    }
+    return true;
+  }
+
+  /**
+   * Make keyword searches in namespace (default: "default") find either
+   * word when asked for the other
+   */
+  async addSynonym(term, synonym, namespace = null) {
+    this.ensureInitialized();
+
+    if (!this.rustLib.memex_add_synonym(this.handle, namespace, term, synonym)) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to add synonym: ${errorMessage || 'Unknown error'}`);
+    }
+    return true;
+  }
+
+  /**
+   * Remove a synonym pair; returns false if there was none
+   */
+  async removeSynonym(term, synonym, namespace = null) {
+    this.ensureInitialized();
+
+    return this.rustLib.memex_remove_synonym(this.handle, namespace, term, synonym);
+  }
+
+  /**
+   * List synonym pairs of one namespace, or of all of them
+   */
+  async listSynonyms(namespace = null) {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_list_synonyms(this.handle, namespace);
+    return result ? JSON.parse(result) : [];
+  }
+
+  /**
+   * Add or replace a memory template; content and metadata values may use
+   * {{name}} placeholders
+   */
+  async setTemplate({ id, content, metadata = {}, importance = 0.5, ttlHours = null }) {
+    this.ensureInitialized();
+
+    const templateJson = JSON.stringify({ id, content, metadata, importance, ttl_hours: ttlHours });
+    if (!this.rustLib.memex_set_template(this.handle, templateJson)) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to set template: ${errorMessage || 'Unknown error'}`);
+    }
+    return true;
+  }
+
+  /**
+   * List memory templates
+   */
+  async listTemplates() {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_list_templates(this.handle);
+    return result ? JSON.parse(result) : [];
+  }
+
+  /**
+   * Render a template with vars and save it as a memory; returns its ID
+   */
+  async saveFromTemplate(templateId, { userId, sessionId, vars = {} }) {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_save_from_template(
+      this.handle,
+      templateId,
+      userId,
+      sessionId,
+      JSON.stringify(vars)
+    );
+
+    if (!result) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to save from template: ${errorMessage || 'Unknown error'}`);
+    }
+    return result;
+  }
+
+  /**
+   * Save multiple memories in batch; quotaPolicy (reject, stop_at_quota,
+   * evict_lowest_importance or overflow_to_archive) decides what happens to
+   * memories over their user's storage quota
    */
-  async saveMemoriesBatch(memories, failOnError = false) {
+  async saveMemoriesBatch(memories, failOnError = false, quotaPolicy = null) {
     this.ensureInitialized();
 
     try {
@@ -268,10 +609,11 @@ class RustBridge {
 
       console.log(`💾 Batch saving ${memories.length} memories`);
 
-      const result = this.rustLib.memex_save_batch(
+      const result = this.rustLib.memex_save_batch_with_policy(
         this.handle,
         memoriesJson,
-        failOnError
+        failOnError,
+        quotaPolicy
       );
 
       if (!result) {
@@ -283,6 +625,9 @@ class RustBridge {
       
       return batchResponse;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error in batch save:', error);
       throw new Error(`Failed to save memory batch: ${error.message}`);
     }
@@ -295,17 +640,7 @@ class RustBridge {
     this.ensureInitialized();
 
     try {
-      // Convert to new filter format
-      const queryFilter = {
-        user_id: filter.userId || null,
-        session_id: filter.sessionId || null,
-        keywords: filter.query ? filter.query.split(' ') : null,
-        date_from: filter.dateFrom || null,
-        date_to: filter.dateTo || null,
-        limit: filter.limit || 50,
-        offset: filter.offset || 0,
-        min_importance: filter.minImportance || null
-      };
+      const queryFilter = toQueryFilter(filter, 50);
 
       const filterJson = JSON.stringify(queryFilter);
 
@@ -314,7 +649,9 @@ class RustBridge {
       const result = this.rustLib.memex_recall(this.handle, filterJson);
 
       if (!result) {
-        return { data: [], total_count: 0, page: 0, per_page: 50, total_pages: 0, has_next: false, has_prev: false };
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
       }
 
       const response = JSON.parse(result);
@@ -322,11 +659,50 @@ class RustBridge {
 
       return response;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error recalling memories:', error);
       throw new Error(`Failed to recall memories: ${error.message}`);
     }
   }
 
+  /**
+   * Recall memories chunk by chunk, yielding arrays of at most `chunkSize`
+   * memories so large result sets never cross the FFI as one string.
+   * `filter.limit` caps the whole stream; without it every match is yielded.
+   */
+  async *streamMemories(filter, chunkSize = 500) {
+    this.ensureInitialized();
+
+    const filterJson = JSON.stringify(toQueryFilter(filter, null));
+    const streamId = this.rustLib.memex_recall_begin(this.handle, filterJson, chunkSize);
+    if (!streamId) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to recall memories: ${errorMessage || 'Unknown error'}`);
+    }
+
+    try {
+      while (true) {
+        const result = this.rustLib.memex_recall_next_chunk(streamId);
+        if (!result) {
+          const errorCode = this.rustLib.memex_get_last_error();
+          const errorMessage = this.rustLib.memex_error_message(errorCode);
+          throw new Error(`Failed to recall memories: ${errorMessage || 'Unknown error'}`);
+        }
+
+        const chunk = JSON.parse(result);
+        if (chunk.length === 0) {
+          return;
+        }
+        yield chunk;
+      }
+    } finally {
+      this.rustLib.memex_recall_end(streamId);
+    }
+  }
+
   /**
    * Search memories with full-text search
    */
@@ -353,11 +729,43 @@ class RustBridge {
 
       return response;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error searching memories:', error);
       throw new Error(`Failed to search memories: ${error.message}`);
     }
   }
 
+  /**
+   * Search memories and group the hits by session, with each session's
+   * match count and up to perSessionLimit memories
+   */
+  async searchGroupedBySession(userId, query, perSessionLimit = 5) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_search_grouped(
+        this.handle,
+        userId,
+        query,
+        perSessionLimit
+      );
+
+      if (!result) {
+        return [];
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error searching memories by session:', error);
+      throw new Error(`Failed to search memories by session: ${error.message}`);
+    }
+  }
+
   /**
    * Get a memory by ID
    */
@@ -374,11 +782,37 @@ class RustBridge {
       const memory = JSON.parse(result);
       return memory;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error getting memory:', error);
       throw new Error(`Failed to get memory: ${error.message}`);
     }
   }
 
+  /**
+   * Get several memories by ID in one call, in the order given
+   */
+  async getMemories(memoryIds) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_get_memories(this.handle, JSON.stringify(memoryIds));
+
+      if (!result) {
+        return [];
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting memories:', error);
+      throw new Error(`Failed to get memories: ${error.message}`);
+    }
+  }
+
   /**
    * Update a memory
    */
@@ -401,11 +835,100 @@ class RustBridge {
       console.log(`✅ Memory ${memoryId} updated successfully`);
       return true;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error updating memory:', error);
       throw new Error(`Failed to update memory: ${error.message}`);
     }
   }
 
+  /**
+   * Move a memory to 'draft', 'active' or 'archived'; returns the state it
+   * left, or null if there is no such memory. Drafts can be confirmed or
+   * archived, active memories archived and archived ones made active again;
+   * other moves throw with `conflict` set
+   */
+  async setMemoryState(memoryId, state) {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_set_memory_state(this.handle, memoryId, state);
+
+    if (!result) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      if (errorCode === NOT_FOUND) {
+        return null;
+      }
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      const error = new Error(`Failed to set memory state: ${errorMessage || 'Unknown error'}`);
+      error.conflict = errorCode === CONFLICT;
+      throw error;
+    }
+    return JSON.parse(result);
+  }
+
+  /**
+   * Memories of `userId` that may contradict `memoryId`, confirmed conflicts
+   * first, or null if the user has no such memory
+   */
+  async findConflicts(userId, memoryId) {
+    this.ensureInitialized();
+
+    const result = this.rustLib.memex_find_conflicts(this.handle, userId, memoryId);
+
+    if (!result) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      if (errorCode === NOT_FOUND) {
+        return null;
+      }
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to find conflicts: ${errorMessage || 'Unknown error'}`);
+    }
+    return JSON.parse(result);
+  }
+
+  /**
+   * Link two memories as 'contradicts' (a confirmed conflict) or
+   * 'compatible' (a dismissed one), or remove that link with `unlink`;
+   * returns false if a memory or the link to remove doesn't exist
+   */
+  async linkMemories(memoryId, otherId, linkType, unlink = false) {
+    this.ensureInitialized();
+
+    if (!this.rustLib.memex_link_memories(this.handle, memoryId, otherId, linkType, unlink)) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      if (errorCode === NOT_FOUND) {
+        return false;
+      }
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to link memories: ${errorMessage || 'Unknown error'}`);
+    }
+    return true;
+  }
+
+  /**
+   * Push a memory's expiry back; memories without a TTL are left as they are
+   */
+  async extendTtl(memoryId, hours) {
+    this.ensureInitialized();
+
+    try {
+      const success = this.rustLib.memex_extend_ttl(this.handle, memoryId, hours);
+
+      if (!success) {
+        throw new Error('Memory not found');
+      }
+
+      return true;
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error extending memory TTL:', error);
+      throw new Error(`Failed to extend memory TTL: ${error.message}`);
+    }
+  }
+
   /**
    * Delete a memory
    */
@@ -422,6 +945,9 @@ class RustBridge {
       console.log(`✅ Memory ${memoryId} deleted successfully`);
       return true;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error deleting memory:', error);
       throw new Error(`Failed to delete memory: ${error.message}`);
     }
@@ -449,6 +975,9 @@ class RustBridge {
       console.log(`✅ Created session ${result} for user ${userId}`);
       return result;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error creating session:', error);
       throw new Error(`Failed to create session: ${error.message}`);
     }
@@ -479,11 +1008,29 @@ class RustBridge {
 
       return response;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error getting user sessions:', error);
       throw new Error(`Failed to get user sessions: ${error.message}`);
     }
   }
 
+  /**
+   * Mark a session 'active', 'closed' or 'archived'; archived sessions are
+   * left out of getUserSessions and session searches
+   */
+  async setSessionStatus(sessionId, status) {
+    this.ensureInitialized();
+
+    if (!this.rustLib.memex_set_session_status(this.handle, sessionId, status)) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to set session status: ${errorMessage || 'Unknown error'}`);
+    }
+    return true;
+  }
+
   /**
    * Generate session summary
    */
@@ -504,6 +1051,9 @@ class RustBridge {
 
       return summary;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error generating summary:', error);
       throw new Error(`Failed to generate summary: ${error.message}`);
     }
@@ -535,6 +1085,9 @@ class RustBridge {
 
       return sessions;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error searching sessions:', error);
       throw new Error(`Failed to search sessions: ${error.message}`);
     }
@@ -560,6 +1113,9 @@ class RustBridge {
       console.log(`✅ Session ${sessionId} deleted successfully`);
       return { sessionId, memoriesDeleted: deleteMemories };
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error deleting session:', error);
       throw new Error(`Failed to delete session: ${error.message}`);
     }
@@ -585,6 +1141,9 @@ class RustBridge {
 
       return decayStats;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error running decay process:', error);
       throw new Error(`Failed to run decay process: ${error.message}`);
     }
@@ -610,6 +1169,9 @@ class RustBridge {
 
       return analysis;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error analyzing decay:', error);
       throw new Error(`Failed to analyze decay: ${error.message}`);
     }
@@ -635,11 +1197,200 @@ class RustBridge {
 
       return stats;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error getting statistics:', error);
       throw new Error(`Failed to get statistics: ${error.message}`);
     }
   }
 
+  /**
+   * Get in-process operation counters (cheap; no database queries)
+   */
+  async getCounters() {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_get_counters(this.handle);
+
+      if (!result) {
+        return {};
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting counters:', error);
+      throw new Error(`Failed to get counters: ${error.message}`);
+    }
+  }
+
+  /**
+   * Structured health from the Rust core: database connectivity, pool
+   * health, WAL size, last decay run and error counts
+   */
+  async getHealth() {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_health(this.handle);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting health:', error);
+      throw new Error(`Failed to get health: ${error.message}`);
+    }
+  }
+
+  /**
+   * Most recent audited writes, newest first; `filter` may hold `actor`,
+   * `traceId` or `targetId`
+   */
+  async getAuditLog(filter = {}, limit = 100) {
+    this.ensureInitialized();
+
+    try {
+      const filterJson = JSON.stringify({
+        actor: filter.actor || null,
+        trace_id: filter.traceId || null,
+        target_id: filter.targetId || null
+      });
+      const result = this.rustLib.memex_get_audit_log(this.handle, filterJson, limit);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting audit log:', error);
+      throw new Error(`Failed to get audit log: ${error.message}`);
+    }
+  }
+
+  /**
+   * The configured API key whose secret is `token`, as { name, role,
+   * namespaces }, or null if none matches
+   */
+  async authenticate(token) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_authenticate(this.handle, token);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        if (errorCode === PERMISSION_DENIED) {
+          return null;
+        }
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error authenticating API key:', error);
+      throw new Error(`Failed to authenticate API key: ${error.message}`);
+    }
+  }
+
+  /**
+   * Run a maintenance job now (decay, checkpoint, analyze, stats_snapshot,
+   * embedding_backfill or backup), returning the recorded run
+   */
+  async runMaintenanceJob(job) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_maintenance_run(this.handle, job);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error running maintenance job:', error);
+      throw new Error(`Failed to run maintenance job: ${error.message}`);
+    }
+  }
+
+  /**
+   * Recent maintenance runs, newest first; job narrows them to one job
+   */
+  async getMaintenanceHistory(job = null, limit = 50) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_maintenance_history(this.handle, job, limit);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting maintenance history:', error);
+      throw new Error(`Failed to get maintenance history: ${error.message}`);
+    }
+  }
+
+  /**
+   * Copy the write-ahead log into the database file; mode is passive, full,
+   * restart or truncate (the default, which also empties the WAL file)
+   */
+  async checkpoint(mode = null) {
+    this.ensureInitialized();
+
+    try {
+      const result = this.rustLib.memex_checkpoint(this.handle, mode);
+
+      if (!result) {
+        const errorCode = this.rustLib.memex_get_last_error();
+        const errorMessage = this.rustLib.memex_error_message(errorCode);
+        throw new Error(errorMessage || 'Unknown error');
+      }
+
+      return JSON.parse(result);
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error checkpointing WAL:', error);
+      throw new Error(`Failed to checkpoint WAL: ${error.message}`);
+    }
+  }
+
   /**
    * Export user memories
    */
@@ -658,6 +1409,9 @@ class RustBridge {
       console.log(`✅ Exported memories for user ${userId}`);
       return result;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error exporting memories:', error);
       throw new Error(`Failed to export memories: ${error.message}`);
     }
@@ -683,6 +1437,9 @@ class RustBridge {
 
       return stats;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error getting user statistics:', error);
       throw new Error(`Failed to get user statistics: ${error.message}`);
     }
@@ -708,11 +1465,42 @@ class RustBridge {
 
       return analytics;
     } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
       console.error('❌ Error getting session analytics:', error);
       throw new Error(`Failed to get session analytics: ${error.message}`);
     }
   }
 
+  /**
+   * Get storage, growth, decay and topic analytics for a user
+   */
+  async getUserAnalytics(userId) {
+    this.ensureInitialized();
+
+    try {
+      console.log(`📊 Getting user analytics for user ${userId}`);
+
+      const result = this.rustLib.memex_get_user_analytics(this.handle, userId);
+
+      if (!result) {
+        return null;
+      }
+
+      const analytics = JSON.parse(result);
+      console.log(`✅ Retrieved user analytics for user ${userId}`);
+
+      return analytics;
+    } catch (error) {
+      if (error.code === RATE_LIMIT_EXCEEDED) {
+        throw error;
+      }
+      console.error('❌ Error getting user analytics:', error);
+      throw new Error(`Failed to get user analytics: ${error.message}`);
+    }
+  }
+
   /**
    * Get library version
    */
@@ -729,6 +1517,50 @@ class RustBridge {
     }
   }
 
+  /**
+   * Recall memories encoded in the configured response_format (JSON or
+   * CBOR), as a Buffer that can be sent on without parsing
+   */
+  async recallMemoriesEncoded(filter) {
+    this.ensureInitialized();
+
+    const filterJson = JSON.stringify(toQueryFilter(filter, 50));
+    return this.takeEncoded('recall memories', (outLen) =>
+      this.rustLib.memex_recall_encoded(this.handle, filterJson, outLen)
+    );
+  }
+
+  /**
+   * Export a user's memories encoded in the configured response_format
+   */
+  async exportUserMemoriesEncoded(userId) {
+    this.ensureInitialized();
+
+    return this.takeEncoded('export memories', (outLen) =>
+      this.rustLib.memex_export_user_memories_encoded(this.handle, userId, outLen)
+    );
+  }
+
+  /**
+   * Call a memex_*_encoded function and copy its buffer into a Buffer owned
+   * by Node, freeing the Rust one
+   */
+  takeEncoded(operation, call) {
+    const outLen = ref.alloc('size_t');
+    const pointer = call(outLen);
+
+    if (pointer.isNull()) {
+      const errorCode = this.rustLib.memex_get_last_error();
+      const errorMessage = this.rustLib.memex_error_message(errorCode);
+      throw new Error(`Failed to ${operation}: ${errorMessage || 'Unknown error'}`);
+    }
+
+    const length = outLen.deref();
+    const encoded = Buffer.from(ref.reinterpret(pointer, length, 0));
+    this.rustLib.memex_free_buffer(pointer, length);
+    return encoded;
+  }
+
   /**
    * Ensure the bridge is initialized
    */
@@ -791,4 +1623,5 @@ class RustBridge {
   }
 }
 
-module.exports = RustBridge;
\ No newline at end of file
+module.exports = RustBridge;
+module.exports.requestContext = requestContext;
\ No newline at end of file
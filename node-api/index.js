@@ -15,6 +15,7 @@ const statsRoutes = require('./routes/statsRoutes')
 // Import middleware
 const { errorHandler } = require('./middleware/errorHandler')
 const validateRequest = require('./middleware/validateRequest')
+const { validateApiKey, authorize } = validateRequest
 
 // Import Rust bridge
 const RustBridge = require('./dbBridge')
@@ -41,7 +42,30 @@ async function initializeServer () {
       default_memory_ttl_hours: parseInt(process.env.DEFAULT_TTL_HOURS) || 720, // 30 days
       enable_compression: process.env.ENABLE_COMPRESSION !== 'false',
       max_memories_per_user: parseInt(process.env.MAX_MEMORIES_PER_USER) || 10000,
-      importance_threshold: parseFloat(process.env.IMPORTANCE_THRESHOLD) || 0.3
+      importance_threshold: parseFloat(process.env.IMPORTANCE_THRESHOLD) || 0.3,
+      auto_create_sessions: process.env.AUTO_CREATE_SESSIONS !== 'false',
+      lenient_filters: process.env.LENIENT_FILTERS === 'true',
+      rate_limit_wait_ms: parseInt(process.env.RATE_LIMIT_WAIT_MS) || 0,
+      response_format: process.env.RESPONSE_FORMAT || 'json',
+      maintenance: {
+        enabled: process.env.MAINTENANCE_ENABLED === 'true',
+        backup: process.env.BACKUP_DIR
+          ? {
+              directory: process.env.BACKUP_DIR,
+              encrypt: !!process.env.MEMEX_BACKUP_PASSPHRASE,
+              keep_daily: parseInt(process.env.BACKUP_KEEP_DAILY) || 7,
+              keep_weekly: parseInt(process.env.BACKUP_KEEP_WEEKLY) || 4,
+              upload_to: process.env.BACKUP_UPLOAD_TO || undefined
+            }
+          : undefined,
+        wal_archive: process.env.WAL_ARCHIVE_DIR
+          ? { directory: process.env.WAL_ARCHIVE_DIR }
+          : undefined
+      },
+      // { "api_keys": [{ "name", "key_sha256", "role", "namespaces" }] }
+      access: process.env.MEMEX_API_KEYS_FILE
+        ? JSON.parse(fs.readFileSync(process.env.MEMEX_API_KEYS_FILE, 'utf8'))
+        : undefined
     }
 
     console.log('⚙️ Configuration:', {
@@ -58,6 +82,8 @@ async function initializeServer () {
 
     // Make bridge available to routes
     app.locals.rustBridge = rustBridge
+    app.locals.accessEnabled = Boolean(config.access && config.access.api_keys &&
+      config.access.api_keys.length)
 
     return true
   } catch (error) {
@@ -105,7 +131,7 @@ function configureMiddleware () {
       }
     },
     methods: ['GET', 'POST', 'PUT', 'DELETE'],
-    allowedHeaders: ['Content-Type', 'Authorization', 'X-Requested-With'],
+    allowedHeaders: ['Content-Type', 'Authorization', 'X-Requested-With', 'X-Memex-Actor', 'X-Trace-Id', 'traceparent', 'X-API-Key'],
     credentials: true
   }
 
@@ -144,6 +170,31 @@ function configureMiddleware () {
     limit: '10mb'
   }))
 
+  // With API keys configured, every API request needs a key whose role
+  // allows it
+  app.use('/api/', validateApiKey, authorize)
+
+  // Attribute writes to the caller: X-Memex-Actor names who is writing and
+  // X-Trace-Id (or the trace ID of a W3C traceparent) ties them to a trace.
+  // Requests made with an API key are attributed to the key instead, and
+  // carry its analytics privacy mode so the core aggregates what it returns
+  app.use((req, res, next) => {
+    const actor = req.principal ? req.principal.name : req.get('X-Memex-Actor')
+    const privacy = req.principal ? req.principal.analytics_privacy : undefined
+    const traceparent = req.get('traceparent')
+    const traceId = req.get('X-Trace-Id') ||
+      (traceparent ? traceparent.split('-')[1] : undefined)
+
+    if (!actor && !traceId) {
+      return next()
+    }
+    const context = { actor: actor || null, trace_id: traceId || null, source: 'node-api' }
+    if (privacy) {
+      context.analytics_privacy = privacy
+    }
+    RustBridge.requestContext.run(context, next)
+  })
+
   // Request validation middleware
   // app.use('/api/', validateRequest);
 
@@ -167,6 +218,30 @@ function configureRoutes () {
     })
   })
 
+  // Liveness probe: the process is up and holds a valid Rust handle
+  app.get('/healthz', (req, res) => {
+    const alive = Boolean(rustBridge && rustBridge.isInitialized &&
+      rustBridge.rustLib.memex_is_valid(rustBridge.handle))
+    res.status(alive ? 200 : 503).json({
+      status: alive ? 'alive' : 'dead',
+      timestamp: new Date().toISOString()
+    })
+  })
+
+  // Readiness probe: the database answers; "degraded" still takes traffic
+  app.get('/readyz', async (req, res) => {
+    try {
+      const health = await rustBridge.getHealth()
+      res.status(health.status === 'unhealthy' ? 503 : 200).json(health)
+    } catch (error) {
+      res.status(503).json({
+        status: 'unhealthy',
+        error: error.message,
+        timestamp: new Date().toISOString()
+      })
+    }
+  })
+
   // API documentation endpoint
   app.get('/api', (req, res) => {
     res.json({
@@ -223,7 +298,9 @@ function configureRoutes () {
       message: 'Welcome to Memex API',
       version: '1.0.0',
       documentation: '/api',
-      health: '/health'
+      health: '/health',
+      liveness: '/healthz',
+      readiness: '/readyz'
     })
   })
 
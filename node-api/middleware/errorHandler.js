@@ -89,6 +89,9 @@ function errorHandler (error, req, res, next) {
     statusCode = 409
   } else if (error.code === 'RATE_LIMIT_EXCEEDED') {
     statusCode = 429
+    if (error.retryAfter) {
+      res.set('Retry-After', String(error.retryAfter))
+    }
   } else if (error.name === 'SyntaxError' && error.status === 400) {
     // JSON parsing error
     statusCode = 400
@@ -145,11 +148,22 @@ function createError (message, code = 'CUSTOM_ERROR', statusCode = 500, details
   return error
 }
 
+/**
+ * Let a rate limit error from the core through a handler's catch block
+ * unchanged, so it is answered with 429 and Retry-After
+ */
+function rethrowRateLimit (error) {
+  if (error.code === 'RATE_LIMIT_EXCEEDED') {
+    throw error
+  }
+}
+
 module.exports = {
   errorHandler,
   notFoundHandler,
   asyncHandler,
   createError,
+  rethrowRateLimit,
   formatErrorResponse,
   logError
 }
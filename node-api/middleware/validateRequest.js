@@ -73,18 +73,74 @@ function validateRequestSize (req, res, next) {
 }
 
 /**
- * API key validation (if enabled)
+ * Roles in increasing order of what they allow
  */
-function validateApiKey (req, res, next) {
-  // Skip if API key validation is disabled
-  if (process.env.REQUIRE_API_KEY !== 'true') {
+const ROLE_RANK = { read_only: 0, writer: 1, admin: 2 }
+
+/**
+ * Role each API route needs; anything not listed, including every DELETE,
+ * needs admin
+ */
+const ROUTE_ROLES = [
+  ['GET', /^\/api\/stats\/audit$/, 'admin'],
+  ['GET', /^\/api\//, 'read_only'],
+  ['POST', /^\/api\/memory\/(recall|summarize)$/, 'read_only'],
+  ['POST', /^\/api\/sessions\/search$/, 'read_only'],
+  ['POST', /^\/api\/sessions\/[^/]+\/summarize$/, 'read_only'],
+  ['POST', /^\/api\/memory\/(save|bulk)$/, 'writer'],
+  ['PUT', /^\/api\/memory\/[^/]+\/state$/, 'writer'],
+  ['PUT', /^\/api\/memory\/[^/]+\/links$/, 'writer'],
+  ['DELETE', /^\/api\/memory\/[^/]+\/links$/, 'writer'],
+  ['POST', /^\/api\/sessions\/?$/, 'writer'],
+  ['PUT', /^\/api\/sessions\/[^/]+\/[^/]+$/, 'writer']
+]
+
+/**
+ * Routes open to keys with `analytics_privacy`, which only see aggregates
+ */
+const ANALYTICS_ROUTES = /^\/api\/stats(\/(user\/[^/]+|memory|sessions|decay|performance|health))?\/?$/
+
+/**
+ * Role the request needs
+ */
+function requiredRole (req) {
+  const path = req.baseUrl + req.path
+  const route = ROUTE_ROLES.find(([method, pattern]) =>
+    method === req.method && pattern.test(path))
+  return route ? route[2] : 'admin'
+}
+
+/**
+ * Namespaces of the memories the request saves
+ */
+function requestNamespaces (req) {
+  const body = req.body || {}
+  const memories = Array.isArray(body.memories) ? body.memories : [body]
+  return memories.map(memory => (memory.metadata && memory.metadata.namespace) || 'default')
+}
+
+/**
+ * API key authentication, once `access.api_keys` are configured
+ *
+ * The key comes from `Authorization: Bearer <key>` or `X-API-Key`. The
+ * matching key is left on `req.principal` for `authorize`.
+ */
+async function validateApiKey (req, res, next) {
+  if (!req.app.locals.accessEnabled) {
     return next()
   }
 
-  const apiKey = req.get('X-API-Key') || req.query.apiKey
-  const validApiKeys = (process.env.VALID_API_KEYS || '').split(',')
+  const authorization = req.get('Authorization') || ''
+  const apiKey = req.get('X-API-Key') ||
+    (authorization.startsWith('Bearer ') ? authorization.slice(7) : null)
 
-  if (!apiKey || !validApiKeys.includes(apiKey)) {
+  try {
+    req.principal = apiKey ? await req.app.locals.rustBridge.authenticate(apiKey) : null
+  } catch (error) {
+    return next(error)
+  }
+
+  if (!req.principal) {
     const error = new Error('Invalid or missing API key')
     error.code = 'INVALID_API_KEY'
     error.status = 401
@@ -94,6 +150,41 @@ function validateApiKey (req, res, next) {
   next()
 }
 
+/**
+ * Deny requests the authenticated key's role doesn't allow; saves are
+ * checked against the key's role in each namespace they write
+ */
+function authorize (req, res, next) {
+  const principal = req.principal
+  if (!principal) {
+    return next()
+  }
+
+  if (principal.analytics_privacy &&
+    !(req.method === 'GET' && ANALYTICS_ROUTES.test(req.baseUrl + req.path))) {
+    const error = new Error(`${principal.name} may only read aggregated analytics`)
+    error.code = 'PERMISSION_DENIED'
+    error.status = 403
+    return next(error)
+  }
+
+  const required = requiredRole(req)
+  const namespaced = req.method === 'POST' && /^\/api\/memory\/(save|bulk)$/.test(req.baseUrl + req.path)
+  const namespaces = namespaced ? requestNamespaces(req) : [null]
+
+  for (const namespace of namespaces) {
+    const role = (namespace && principal.namespaces[namespace]) || principal.role
+    if (ROLE_RANK[role] < ROLE_RANK[required]) {
+      const error = new Error(`${principal.name} has role ${role}${namespace ? ` in namespace ${namespace}` : ''}, but ${required} is required`)
+      error.code = 'PERMISSION_DENIED'
+      error.status = 403
+      return next(error)
+    }
+  }
+
+  next()
+}
+
 /**
  * Request timeout middleware
  */
@@ -131,6 +222,7 @@ function responseTime (req, res, next) {
 module.exports = {
   validateRequest,
   validateApiKey,
+  authorize,
   requestTimeout,
   responseTime,
   generateRequestId,
@@ -10,7 +10,13 @@ const schemas = {
     content: joi.string().required().min(1).max(100000), // 100KB max content
     metadata: joi.object().optional(),
     importance: joi.number().min(0).max(1).optional(),
-    ttlHours: joi.number().integer().min(1).max(8760).optional() // Max 1 year
+    confidence: joi.number().min(0).max(1).optional(),
+    ttlHours: joi.number().integer().min(1).max(8760).optional(), // Max 1 year
+    state: joi.string().valid('draft', 'active').optional(),
+    sourceType: joi.string().valid('user', 'agent', 'tool', 'import').optional(),
+    sourceRef: joi.string().min(1).max(2048).optional(),
+    ingestedBy: joi.string().min(1).max(255).optional(),
+    idempotencyKey: joi.string().min(1).max(255).optional()
   }),
 
   memoryRecall: joi.object({
@@ -21,7 +27,30 @@ const schemas = {
     dateTo: joi.string().isoDate().optional(),
     limit: joi.number().integer().min(1).max(1000).optional(),
     minImportance: joi.number().min(0).max(1).optional(),
-    keywords: joi.array().items(joi.string().max(100)).max(10).optional()
+    minConfidence: joi.number().min(0).max(1).optional(),
+    maxConfidence: joi.number().min(0).max(1).optional(),
+    keywords: joi.array().items(joi.string().max(100)).max(10).optional(),
+    states: joi.array().items(joi.string().valid('draft', 'active', 'archived')).max(3).optional(),
+    sourceTypes: joi.array().items(joi.string().valid('user', 'agent', 'tool', 'import')).max(4).optional(),
+    sourceRef: joi.string().max(2048).optional(),
+    ingestedBy: joi.string().max(255).optional(),
+    semanticQuery: joi.string().min(1).max(1000).optional(),
+    excludeKeywords: joi.array().items(joi.string().max(100)).max(100).optional(),
+    excludeSessionIds: joi.array().items(joi.string().max(255)).max(100).optional(),
+    excludeTags: joi.array().items(joi.string().max(100)).max(100).optional(),
+    contentType: joi.string().valid('text', 'json', 'markdown').optional(),
+    minContentLength: joi.number().integer().min(0).optional(),
+    maxContentLength: joi.number().integer().min(1).optional(),
+    explain: joi.boolean().optional()
+  }),
+
+  memoryState: joi.object({
+    state: joi.string().valid('draft', 'active', 'archived').required()
+  }),
+
+  memoryLink: joi.object({
+    otherId: joi.string().required().min(1).max(255),
+    linkType: joi.string().valid('contradicts', 'compatible').required()
   }),
 
   memorySummarize: joi.object({
@@ -47,7 +76,11 @@ const schemas = {
         content: joi.string().required().min(1).max(100000),
         metadata: joi.object().optional(),
         importance: joi.number().min(0).max(1).optional(),
-        ttlHours: joi.number().integer().min(1).max(8760).optional()
+        ttlHours: joi.number().integer().min(1).max(8760).optional(),
+        sourceType: joi.string().valid('user', 'agent', 'tool', 'import').optional(),
+        sourceRef: joi.string().min(1).max(2048).optional(),
+        ingestedBy: joi.string().min(1).max(255).optional(),
+        idempotencyKey: joi.string().min(1).max(255).optional()
       })
     ).min(1).max(100).required() // Max 100 memories per batch
   })
@@ -97,7 +130,8 @@ function validateSearchQuery (req, res, next) {
     to: joi.string().isoDate().optional(),
     limit: joi.number().integer().min(1).max(1000).optional(),
     importance: joi.number().min(0).max(1).optional(),
-    tags: joi.string().optional().max(1000) // Comma-separated tags
+    tags: joi.string().optional().max(1000), // Comma-separated tags
+    explain: joi.boolean().optional()
   })
 
   const { error, value } = querySchema.validate(req.query, {
@@ -188,6 +222,8 @@ function validateContentType (req, res, next) {
 module.exports = {
   validateMemorySave: createValidator(schemas.memorySave),
   validateMemoryRecall: createValidator(schemas.memoryRecall),
+  validateMemoryState: createValidator(schemas.memoryState),
+  validateMemoryLink: createValidator(schemas.memoryLink),
   validateMemorySummarize: createValidator(schemas.memorySummarize),
   validateSessionCreate: createValidator(schemas.sessionCreate),
   validateSessionSearch: createValidator(schemas.sessionSearch),
@@ -1,7 +1,7 @@
 const express = require('express')
 const router = express.Router()
 const memoryController = require('../controllers/memoryController')
-const { validateMemorySave, validateMemoryRecall, validateMemorySummarize, validateBulkMemories, validateUserId, validateSearchQuery } = require('../middleware/validation')
+const { validateMemorySave, validateMemoryRecall, validateMemoryState, validateMemoryLink, validateMemorySummarize, validateBulkMemories, validateUserId, validateSearchQuery } = require('../middleware/validation')
 
 /**
  * @route POST /api/memory/save
@@ -13,7 +13,14 @@ const { validateMemorySave, validateMemoryRecall, validateMemorySummarize, valid
  *   content: string,
  *   metadata?: object,
  *   importance?: number (0-1),
- *   ttlHours?: number
+ *   confidence?: number (0-1, default 1; unsure memories decay sooner),
+ *   ttlHours?: number,
+ *   state?: 'draft' | 'active' (drafts stay out of recall until confirmed),
+ *   sourceType?: 'user' | 'agent' | 'tool' | 'import',
+ *   sourceRef?: string (URL, message id or file path),
+ *   ingestedBy?: string (replaced by the API key's name when one is used),
+ *   idempotencyKey?: string (or an Idempotency-Key header; a retried save
+ *     with the same key returns the first save's memoryId)
  * }
  */
 router.post('/save', validateMemorySave, memoryController.saveMemory)
@@ -30,7 +37,21 @@ router.post('/save', validateMemorySave, memoryController.saveMemory)
  *   dateTo?: string (ISO date),
  *   limit?: number,
  *   minImportance?: number (0-1),
- *   keywords?: string[]
+ *   minConfidence?: number (0-1),
+ *   maxConfidence?: number (0-1),
+ *   keywords?: string[],
+ *   states?: ('draft' | 'active' | 'archived')[] (default active only),
+ *   sourceTypes?: ('user' | 'agent' | 'tool' | 'import')[],
+ *   sourceRef?: string,
+ *   ingestedBy?: string,
+ *   semanticQuery?: string (ranks by meaning when embeddings are configured),
+ *   excludeKeywords?: string[] (leaves out memories containing any of them),
+ *   excludeSessionIds?: string[],
+ *   excludeTags?: string[],
+ *   contentType?: 'text' | 'json' | 'markdown',
+ *   minContentLength?: number (bytes),
+ *   maxContentLength?: number (bytes),
+ *   explain?: boolean (adds each memory's ranking explanation)
  * }
  */
 router.post('/recall', validateMemoryRecall, memoryController.recallMemories)
@@ -75,7 +96,8 @@ router.delete('/decay', memoryController.runDecayProcess)
  *   to?: string (ISO date),
  *   limit?: number,
  *   importance?: number,
- *   tags?: string (comma-separated)
+ *   tags?: string (comma-separated),
+ *   explain?: boolean
  * }
  */
 router.get('/search', validateSearchQuery, memoryController.searchMemories)
@@ -91,12 +113,56 @@ router.get('/search', validateSearchQuery, memoryController.searchMemories)
  *     content: string,
  *     metadata?: object,
  *     importance?: number,
- *     ttlHours?: number
+ *     ttlHours?: number,
+ *     idempotencyKey?: string
  *   }]
  * }
  */
 router.post('/bulk', validateBulkMemories, memoryController.bulkSaveMemories)
 
+/**
+ * @route PUT /api/memory/:memoryId/state
+ * @desc Confirm a draft, archive a memory or restore an archived one
+ * @access Public
+ * @params memoryId: string
+ * @body {
+ *   state: 'draft' | 'active' | 'archived'
+ * }
+ */
+router.put('/:memoryId/state', validateMemoryState, memoryController.setMemoryState)
+
+/**
+ * @route GET /api/memory/conflicts/:userId/:memoryId
+ * @desc Memories of the user that may contradict a memory
+ * @access Public
+ * @params userId: string, memoryId: string
+ */
+router.get('/conflicts/:userId/:memoryId', validateUserId, memoryController.findConflicts)
+
+/**
+ * @route PUT /api/memory/:memoryId/links
+ * @desc Confirm a conflict (contradicts) or dismiss it (compatible)
+ * @access Public
+ * @params memoryId: string
+ * @body {
+ *   otherId: string,
+ *   linkType: 'contradicts' | 'compatible'
+ * }
+ */
+router.put('/:memoryId/links', validateMemoryLink, memoryController.linkMemories)
+
+/**
+ * @route DELETE /api/memory/:memoryId/links
+ * @desc Remove a link between two memories
+ * @access Public
+ * @params memoryId: string
+ * @body {
+ *   otherId: string,
+ *   linkType: 'contradicts' | 'compatible'
+ * }
+ */
+router.delete('/:memoryId/links', validateMemoryLink, memoryController.unlinkMemories)
+
 /**
  * @route GET /api/memory/stats/:userId
  * @desc Get memory statistics for a specific user
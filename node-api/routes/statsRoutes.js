@@ -53,4 +53,12 @@ router.get('/performance', statsController.getPerformanceStats)
  */
 router.get('/health', statsController.getHealthStats)
 
+/**
+ * @route GET /api/stats/audit
+ * @desc Get writes recorded in the audit log, newest first
+ * @access Public
+ * @query actor, traceId, targetId, limit
+ */
+router.get('/audit', statsController.getAuditLog)
+
 module.exports = router
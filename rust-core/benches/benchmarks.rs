@@ -1,6 +1,13 @@
 //! Performance benchmarks for Memex
 
-use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+//!
+//! Run with `cargo bench`; `scripts/bench_baseline.sh` saves and compares
+//! named baselines and writes a JSON summary of each run.
+
+use criterion::{
+    black_box, criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput,
+};
+use memex_core::core::decay::DecayEngine;
 use memex_core::core::memory::MemoryManager;
 use memex_core::core::session::SessionManager;
 use memex_core::core::{MemexConfig, RequestValidator};
@@ -9,7 +16,7 @@ use memex_core::database::{Database, DatabaseConfig};
 use std::collections::HashMap;
 use tempfile::TempDir;
 
-fn setup_benchmark_env() -> (MemoryManager, SessionManager, TempDir) {
+fn setup_database(db_config: DatabaseConfig) -> (Database, RequestValidator, TempDir) {
     let temp_dir = TempDir::new().unwrap();
     let db_config = DatabaseConfig {
         path: temp_dir
@@ -18,15 +25,21 @@ fn setup_benchmark_env() -> (MemoryManager, SessionManager, TempDir) {
             .to_string_lossy()
             .to_string(),
         cache_size: -64000, // 64MB cache for benchmarks
-        ..Default::default()
+        ..db_config
     };
 
     let database = Database::new(db_config).unwrap();
     let config = MemexConfig {
         enable_request_limits: false, // Disable for accurate benchmarking
+        max_batch_size: 1000,
         ..Default::default()
     };
-    let validator = RequestValidator::new(&config);
+
+    (database, RequestValidator::new(&config), temp_dir)
+}
+
+fn setup_benchmark_env() -> (MemoryManager, SessionManager, TempDir) {
+    let (database, validator, temp_dir) = setup_database(DatabaseConfig::default());
 
     let memory_manager = MemoryManager::new(database.clone(), validator.clone());
     let session_manager = SessionManager::new(database, validator);
@@ -53,6 +66,7 @@ fn bench_memory_save(c: &mut Criterion) {
     let (memory_manager, _, _temp_dir) = setup_benchmark_env();
 
     let mut group = c.benchmark_group("memory_save");
+    group.throughput(Throughput::Elements(1));
 
     // Benchmark different content sizes
     for content_size in [100, 1000, 10000].iter() {
@@ -180,7 +194,7 @@ fn bench_full_text_search(c: &mut Criterion) {
     let (memory_manager, _, _temp_dir) = setup_benchmark_env();
 
     // Pre-populate with diverse content
-    let content_templates = vec![
+    let content_templates = [
         "Apple Inc. stock analysis shows strong quarterly growth",
         "Bitcoin cryptocurrency market volatility creates opportunities",
         "Tesla electric vehicle deliveries exceed expectations",
@@ -227,6 +241,77 @@ fn bench_full_text_search(c: &mut Criterion) {
     group.finish();
 }
 
+fn bench_recall_fts(c: &mut Criterion) {
+    let (memory_manager, _, _temp_dir) = setup_benchmark_env();
+
+    let topics = ["trading", "weather", "recipes", "travel", "music"];
+    for i in 0..10_000 {
+        let memory = create_test_memory(
+            "fts_user",
+            &format!("fts_session_{}", i % 20),
+            &format!(
+                "Memory {} about {} with some filler text",
+                i,
+                topics[i % topics.len()]
+            ),
+            0.1 + (i % 9) as f32 * 0.1,
+        );
+        memory_manager.save_memory(memory).unwrap();
+    }
+
+    let mut group = c.benchmark_group("recall_fts");
+
+    let base = QueryFilter {
+        user_id: Some("fts_user".to_string()),
+        limit: Some(50),
+        ..Default::default()
+    };
+    let filters = vec![
+        ("without_fts", base.clone()),
+        (
+            "without_fts_importance",
+            QueryFilter {
+                min_importance: Some(0.8),
+                ..base.clone()
+            },
+        ),
+        (
+            "fts_one_keyword",
+            QueryFilter {
+                keywords: Some(vec!["weather".to_string()]),
+                ..base.clone()
+            },
+        ),
+        (
+            "fts_three_keywords",
+            QueryFilter {
+                keywords: Some(vec![
+                    "weather".to_string(),
+                    "recipes".to_string(),
+                    "travel".to_string(),
+                ]),
+                ..base.clone()
+            },
+        ),
+        (
+            "fts_deep_page",
+            QueryFilter {
+                keywords: Some(vec!["music".to_string()]),
+                offset: Some(1500),
+                ..base
+            },
+        ),
+    ];
+
+    for (filter_name, filter) in filters {
+        group.bench_function(filter_name, |b| {
+            b.iter(|| black_box(memory_manager.recall_memories(filter.clone()).unwrap()))
+        });
+    }
+
+    group.finish();
+}
+
 fn bench_session_operations(c: &mut Criterion) {
     let (memory_manager, session_manager, _temp_dir) = setup_benchmark_env();
 
@@ -294,7 +379,7 @@ fn bench_batch_operations(c: &mut Criterion) {
     let mut group = c.benchmark_group("batch_operations");
 
     // Benchmark different batch sizes
-    for batch_size in [10, 50, 100].iter() {
+    for batch_size in [10, 100, 1000].iter() {
         let memories: Vec<MemoryItem> = (0..*batch_size)
             .map(|i| {
                 create_test_memory(
@@ -309,8 +394,10 @@ fn bench_batch_operations(c: &mut Criterion) {
         let batch_request = memex_core::core::BatchRequest {
             items: memories,
             fail_on_error: false,
+            quota_policy: Default::default(),
         };
 
+        group.throughput(Throughput::Elements(*batch_size as u64));
         group.bench_with_input(
             BenchmarkId::new("batch_size", batch_size),
             batch_size,
@@ -438,9 +525,7 @@ fn bench_memory_update_operations(c: &mut Criterion) {
         b.iter(|| {
             let update = memex_core::core::memory::MemoryUpdate {
                 content: Some(format!("Updated content {}", counter)),
-                importance: None,
-                metadata: None,
-                ttl_hours: None,
+                ..Default::default()
             };
             let id = &memory_ids[counter % memory_ids.len()];
             black_box(memory_manager.update_memory(id, update).unwrap());
@@ -452,10 +537,8 @@ fn bench_memory_update_operations(c: &mut Criterion) {
         let mut counter = 0;
         b.iter(|| {
             let update = memex_core::core::memory::MemoryUpdate {
-                content: None,
                 importance: Some(0.3 + (counter % 7) as f32 * 0.1),
-                metadata: None,
-                ttl_hours: None,
+                ..Default::default()
             };
             let id = &memory_ids[counter % memory_ids.len()];
             black_box(memory_manager.update_memory(id, update).unwrap());
@@ -470,10 +553,8 @@ fn bench_memory_update_operations(c: &mut Criterion) {
             metadata.insert("updated".to_string(), counter.to_string());
 
             let update = memex_core::core::memory::MemoryUpdate {
-                content: None,
-                importance: None,
                 metadata: Some(metadata),
-                ttl_hours: None,
+                ..Default::default()
             };
             let id = &memory_ids[counter % memory_ids.len()];
             black_box(memory_manager.update_memory(id, update).unwrap());
@@ -484,6 +565,157 @@ fn bench_memory_update_operations(c: &mut Criterion) {
     group.finish();
 }
 
+/// Deterministic, roughly uniform embedding so runs are comparable
+#[cfg(feature = "vector-search")]
+fn bench_embedding(seed: usize, dimension: usize) -> Vec<f32> {
+    (0..dimension)
+        .map(|j| {
+            let x = (seed.wrapping_mul(2654435761) ^ j.wrapping_mul(40503)) % 1000;
+            x as f32 / 1000.0 - 0.5
+        })
+        .collect()
+}
+
+#[cfg(feature = "vector-search")]
+fn bench_vector_search(c: &mut Criterion) {
+    use memex_core::database::vector::{VectorConfig, VectorFilter, VectorSearchEngine};
+
+    const DIMENSION: usize = 384;
+    const MODEL: &str = "bench-model";
+
+    let mut group = c.benchmark_group("vector_search");
+    group.sample_size(10);
+
+    for embedding_count in [10_000usize, 100_000].iter() {
+        // vector_similarity is registered on the connection that initializes
+        // the schema, so keep the pool to that one connection
+        let (database, _, _temp_dir) = setup_database(DatabaseConfig {
+            max_connections: 1,
+            min_connections: 1,
+            ..Default::default()
+        });
+        let engine = VectorSearchEngine::new(
+            database.get_connection_pool(),
+            VectorConfig {
+                dimension: DIMENSION,
+                similarity_threshold: 0.0,
+                max_results: 50,
+                enable_approximate_search: false,
+                metric: Default::default(),
+            },
+        );
+        engine.initialize_schema().unwrap();
+
+        database
+            .get_connection_pool()
+            .with_write_transaction(|tx| {
+                tx.execute(
+                    "INSERT OR IGNORE INTO users (id) VALUES ('vector_user')",
+                    [],
+                )?;
+                tx.execute(
+                    "INSERT OR IGNORE INTO sessions (id, user_id) VALUES ('vector_session', 'vector_user')",
+                    [],
+                )?;
+                let mut stmt = tx.prepare(
+                    "INSERT INTO memories (id, user_id, session_id, content, importance)
+                     VALUES (?1, 'vector_user', 'vector_session', ?2, 0.5)",
+                )?;
+                for i in 0..*embedding_count {
+                    stmt.execute(rusqlite::params![
+                        format!("vector-{}", i),
+                        format!("Vector benchmark memory {}", i)
+                    ])?;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        for i in 0..*embedding_count {
+            engine
+                .store_embedding(
+                    &format!("vector-{}", i),
+                    &bench_embedding(i, DIMENSION),
+                    MODEL,
+                )
+                .unwrap();
+        }
+
+        let query = bench_embedding(usize::MAX / 3, DIMENSION);
+
+        group.throughput(Throughput::Elements(*embedding_count as u64));
+        group.bench_with_input(
+            BenchmarkId::new("search_similar", embedding_count),
+            embedding_count,
+            |b, _| {
+                b.iter(|| {
+                    black_box(
+                        engine
+                            .search_similar(&query, MODEL, Some(10), &VectorFilter::default())
+                            .unwrap(),
+                    )
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn bench_decay(c: &mut Criterion) {
+    let mut group = c.benchmark_group("decay");
+    group.sample_size(10);
+
+    for memory_count in [1_000usize, 10_000].iter() {
+        let (database, validator, _temp_dir) = setup_database(DatabaseConfig::default());
+        let memory_manager = MemoryManager::new(database.clone(), validator.clone());
+        let decay_engine = DecayEngine::new(database, validator, DecayPolicy::default());
+
+        // Old enough to be compressed (older than half of max_age_hours)
+        let aged = chrono::Utc::now() - chrono::Duration::days(20);
+        let seed = |run: usize| {
+            for i in 0..*memory_count {
+                let mut memory = create_test_memory(
+                    "decay_user",
+                    &format!("decay_session_{}", i % 50),
+                    &format!("Run {} decay candidate {} about routine events", run, i),
+                    0.1 + (i % 9) as f32 * 0.1,
+                );
+                memory.created_at = aged;
+                memory_manager.save_memory(memory).unwrap();
+            }
+        };
+
+        // Nothing to do: the cost of scanning a steady-state database
+        seed(0);
+        decay_engine.run_decay().unwrap();
+        group.bench_with_input(
+            BenchmarkId::new("steady_state", memory_count),
+            memory_count,
+            |b, _| b.iter(|| black_box(decay_engine.run_decay().unwrap())),
+        );
+
+        // A fresh batch of aged, low-importance memories before every run
+        let mut run = 0;
+        group.bench_with_input(
+            BenchmarkId::new("compress_aged", memory_count),
+            memory_count,
+            |b, _| {
+                b.iter_batched(
+                    || {
+                        run += 1;
+                        seed(run)
+                    },
+                    |_| black_box(decay_engine.run_decay().unwrap()),
+                    BatchSize::PerIteration,
+                )
+            },
+        );
+    }
+
+    group.finish();
+}
+
 fn bench_ffi_operations(c: &mut Criterion) {
     use std::ffi::CString;
 
@@ -548,13 +780,22 @@ criterion_group!(
     benches,
     bench_memory_save,
     bench_memory_recall,
+    bench_recall_fts,
     bench_full_text_search,
     bench_session_operations,
     bench_batch_operations,
     bench_concurrent_operations,
     bench_data_scaling,
     bench_memory_update_operations,
+    bench_decay,
     bench_ffi_operations
 );
 
+#[cfg(feature = "vector-search")]
+criterion_group!(vector_benches, bench_vector_search);
+
+#[cfg(feature = "vector-search")]
+criterion_main!(benches, vector_benches);
+
+#[cfg(not(feature = "vector-search"))]
 criterion_main!(benches);
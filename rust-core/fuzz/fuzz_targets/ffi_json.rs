@@ -0,0 +1,118 @@
+//! Fuzz the FFI entry points that parse JSON
+//!
+//! Every call must either succeed or fail with a recorded error code; a
+//! panic (reported as `FfiErrorCode::Panic`) or a crash is a bug.
+//!
+//! Run from `rust-core` with `cargo +nightly fuzz run ffi_json`.
+
+#![no_main]
+
+use std::ffi::CString;
+use std::os::raw::c_char;
+
+use libfuzzer_sys::fuzz_target;
+use memex_core::ffi::error::FfiErrorCode;
+use once_cell::sync::Lazy;
+use tempfile::TempDir;
+
+struct Instance {
+    handle: usize,
+    memory_id: CString,
+    _temp_dir: TempDir,
+}
+
+static INSTANCE: Lazy<Instance> = Lazy::new(|| {
+    let temp_dir = TempDir::new().unwrap();
+    let config = format!(
+        r#"{{"database_path": {:?}, "enable_request_limits": false}}"#,
+        temp_dir.path().join("fuzz.db").to_string_lossy()
+    );
+    let config = CString::new(config).unwrap();
+    let handle = memex_core::memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0, "failed to open the fuzz database");
+
+    let user_id = CString::new("fuzz_user").unwrap();
+    let session_id = CString::new("fuzz_session").unwrap();
+    let content = CString::new("Memory the fuzzer updates").unwrap();
+    let memory_id = memex_core::memex_save(
+        handle,
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        content.as_ptr(),
+        0.5,
+        -1,
+        std::ptr::null(),
+    );
+    assert!(!memory_id.is_null(), "failed to seed the fuzz database");
+    let memory_id = unsafe { CString::from_raw(memory_id) };
+
+    Instance {
+        handle,
+        memory_id,
+        _temp_dir: temp_dir,
+    }
+});
+
+fn check_no_panic(name: &str) {
+    assert_ne!(
+        memex_core::memex_get_last_error(),
+        FfiErrorCode::Panic as i32,
+        "{} panicked: {:?}",
+        name,
+        memex_core::ffi::error::last_error()
+    );
+}
+
+fn check_string(name: &str, result: *mut c_char) {
+    if result.is_null() {
+        check_no_panic(name);
+    } else {
+        memex_core::memex_free_string(result);
+    }
+}
+
+fuzz_target!(|data: &[u8]| {
+    // C strings can't carry interior NULs
+    let json = match CString::new(data) {
+        Ok(json) => json,
+        Err(_) => return,
+    };
+    let instance = &*INSTANCE;
+    let handle = instance.handle;
+    let user_id = CString::new("fuzz_user").unwrap();
+
+    // Config parsing only; opening an instance from fuzzed JSON would create
+    // databases at arbitrary paths
+    if let Ok(text) = json.to_str() {
+        let _ = memex_core::ffi::config::parse_config(text);
+    }
+
+    check_string("memex_recall", memex_core::memex_recall(handle, json.as_ptr()));
+    check_string(
+        "memex_save_batch",
+        memex_core::memex_save_batch(handle, json.as_ptr(), false),
+    );
+    check_string(
+        "memex_save",
+        memex_core::memex_save(
+            handle,
+            user_id.as_ptr(),
+            user_id.as_ptr(),
+            user_id.as_ptr(),
+            0.5,
+            -1,
+            json.as_ptr(),
+        ),
+    );
+    check_string(
+        "memex_search_sessions",
+        memex_core::memex_search_sessions(handle, user_id.as_ptr(), json.as_ptr()),
+    );
+
+    if !memex_core::memex_update_memory(handle, instance.memory_id.as_ptr(), json.as_ptr()) {
+        check_no_panic("memex_update_memory");
+    }
+    if !memex_core::memex_update_decay_policy(handle, json.as_ptr()) {
+        check_no_panic("memex_update_decay_policy");
+    }
+});
@@ -0,0 +1,82 @@
+#!/bin/bash
+
+# Save or compare criterion baselines for the Memex benchmark suite
+#
+#   ./scripts/bench_baseline.sh save [name]      record a baseline (default: main)
+#   ./scripts/bench_baseline.sh compare [name]   run against a saved baseline
+#
+# Either way the run's estimates are summarized in benches/baselines/<name>.json
+# (save) or target/criterion/<name>-compare.json (compare). Extra arguments
+# after the name are passed to criterion, e.g. a benchmark filter:
+#
+#   ./scripts/bench_baseline.sh compare main recall_fts
+
+set -e
+
+FEATURES="${BENCH_FEATURES:-vector-search}"
+
+if [ ! -f "Cargo.toml" ]; then
+    echo "Please run this script from the rust-core directory" >&2
+    exit 1
+fi
+
+if ! command -v python3 &> /dev/null; then
+    echo "python3 is required to write the JSON summary" >&2
+    exit 1
+fi
+
+mode="$1"
+name="${2:-main}"
+shift $(( $# > 2 ? 2 : $# ))
+
+case "$mode" in
+    save)
+        cargo bench --features "$FEATURES" --bench benchmarks -- --save-baseline "$name" "$@"
+        estimates_dir="$name"
+        output="benches/baselines/$name.json"
+        ;;
+    compare)
+        cargo bench --features "$FEATURES" --bench benchmarks -- --baseline "$name" "$@"
+        estimates_dir="new"
+        output="target/criterion/$name-compare.json"
+        ;;
+    *)
+        echo "Usage: $0 save|compare [name] [criterion args...]" >&2
+        exit 1
+        ;;
+esac
+
+mkdir -p "$(dirname "$output")"
+
+# One entry per benchmark: mean and median in nanoseconds, plus throughput
+python3 - "$estimates_dir" "$output" <<'PY'
+import json, os, subprocess, sys
+
+estimates_dir, output = sys.argv[1], sys.argv[2]
+results = {}
+
+for root, _, files in os.walk("target/criterion"):
+    if os.path.basename(root) != estimates_dir or "estimates.json" not in files:
+        continue
+    with open(os.path.join(root, "benchmark.json")) as f:
+        benchmark = json.load(f)
+    with open(os.path.join(root, "estimates.json")) as f:
+        estimates = json.load(f)
+
+    results[benchmark["full_id"]] = {
+        "mean_ns": estimates["mean"]["point_estimate"],
+        "median_ns": estimates["median"]["point_estimate"],
+        "std_dev_ns": estimates["std_dev"]["point_estimate"],
+        "throughput": benchmark.get("throughput"),
+    }
+
+commit = subprocess.run(
+    ["git", "rev-parse", "--short", "HEAD"], capture_output=True, text=True
+).stdout.strip()
+
+with open(output, "w") as f:
+    json.dump({"commit": commit, "benchmarks": dict(sorted(results.items()))}, f, indent=2)
+    f.write("\n")
+
+print(f"Wrote {len(results)} benchmark estimates to {output}")
+PY
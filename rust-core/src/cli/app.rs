@@ -0,0 +1,428 @@
+//! Command-line entry point: argument parsing, context setup and dispatch
+
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand};
+use colored::*;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::cli::{
+    database, decay, export, import, maintenance, memory, search, session, shard, summaries,
+    system, user,
+};
+use crate::core::context::RequestContext;
+use crate::core::decay::DecayEngine;
+use crate::core::maintenance::MaintenanceScheduler;
+use crate::core::memory::MemoryManager;
+use crate::core::session::SessionManager;
+use crate::core::{MemexConfig, RequestValidator};
+use crate::database::pool::TuningPreset;
+use crate::database::sharded::{ShardStrategy, ShardedDatabase, ShardedDatabaseConfig};
+use crate::database::{Database, DatabaseConfig};
+
+#[cfg(feature = "vector-search")]
+use crate::cli::vector;
+#[cfg(feature = "vector-search")]
+use crate::database::vector::{DistanceMetric, VectorConfig, VectorSearchEngine};
+
+#[derive(Parser, Debug)]
+#[command(name = "memex")]
+#[command(about = "A lightweight, local-first memory engine for AI applications")]
+#[command(version)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+
+    #[command(flatten)]
+    pub global: GlobalArgs,
+}
+
+/// Options every command accepts, built-in or registered through
+/// [`crate::cli::CliBuilder`]
+#[derive(Args, Debug, Clone)]
+pub struct GlobalArgs {
+    /// Database path
+    #[arg(short, long, default_value = "memex.db")]
+    pub database: String,
+
+    /// Enable verbose logging
+    #[arg(short, long)]
+    pub verbose: bool,
+
+    /// Configuration file path
+    #[arg(short, long)]
+    pub config: Option<String>,
+
+    /// Connection tuning preset: low_memory, high_throughput or durable
+    #[arg(long)]
+    pub preset: Option<TuningPreset>,
+
+    /// SQLite extension to load into every connection; repeat for more
+    #[arg(long = "extension", value_name = "PATH")]
+    pub extensions: Vec<PathBuf>,
+
+    /// Record writes in the audit log as made by this actor
+    #[arg(long)]
+    pub actor: Option<String>,
+
+    /// Record writes in the audit log under this trace ID
+    #[arg(long)]
+    pub trace_id: Option<String>,
+
+    /// Count tokens with this tiktoken encoding or model, e.g. cl100k_base
+    /// or gpt-4o, instead of estimating a token per four characters
+    #[cfg(feature = "tiktoken")]
+    #[arg(long)]
+    pub tokenizer: Option<String>,
+
+    /// Enable vector search
+    #[cfg(feature = "vector-search")]
+    #[arg(long)]
+    pub enable_vector: bool,
+
+    /// Vector dimension
+    #[cfg(feature = "vector-search")]
+    #[arg(long, default_value = "384")]
+    pub vector_dimension: usize,
+
+    /// Vector distance metric (cosine, dot or l2)
+    #[cfg(feature = "vector-search")]
+    #[arg(long, default_value = "cosine")]
+    pub vector_metric: DistanceMetric,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+    /// Memory operations
+    Memory {
+        #[command(subcommand)]
+        action: Box<memory::MemoryCommands>,
+    },
+    /// Search configuration
+    Search {
+        #[command(subcommand)]
+        action: search::SearchCommands,
+    },
+    /// Session operations
+    Session {
+        #[command(subcommand)]
+        action: session::SessionCommands,
+    },
+    /// Session, day and topic summaries
+    Summaries {
+        #[command(subcommand)]
+        action: summaries::SummaryCommands,
+    },
+    /// Per-user analytics
+    User {
+        #[command(subcommand)]
+        action: user::UserCommands,
+    },
+    /// Decay operations
+    Decay {
+        #[command(subcommand)]
+        action: decay::DecayCommands,
+    },
+    /// Scheduled maintenance jobs and their run history
+    Maintenance {
+        #[command(subcommand)]
+        action: maintenance::MaintenanceCommands,
+    },
+    /// Import memories exported from Chroma, LangChain or Mem0
+    Import(import::ImportArgs),
+    /// Export a user's memories for other tools
+    Export {
+        #[command(subcommand)]
+        action: export::ExportCommands,
+    },
+    /// Database operations
+    Database {
+        #[command(subcommand)]
+        action: database::DatabaseCommands,
+    },
+    /// System information and health checks
+    System {
+        #[command(subcommand)]
+        action: system::SystemCommands,
+    },
+    /// Manage per-user or hashed shard databases
+    Shard {
+        /// Directory holding the shard files
+        #[arg(long, default_value = "shards")]
+        dir: String,
+        /// How users map to shards: per-user or hashed:<count>
+        #[arg(long, default_value = "per-user")]
+        strategy: ShardStrategy,
+        #[command(subcommand)]
+        action: shard::ShardCommands,
+    },
+    /// Vector search operations
+    #[cfg(feature = "vector-search")]
+    Vector {
+        #[command(subcommand)]
+        action: vector::VectorCommands,
+    },
+}
+
+/// Everything a command handler may need, built once per invocation
+pub struct CliContext {
+    pub config: MemexConfig,
+    pub database: Database,
+    pub memory: MemoryManager,
+    pub sessions: SessionManager,
+    pub decay: DecayEngine,
+    #[cfg(feature = "vector-search")]
+    pub vector: Option<VectorSearchEngine>,
+}
+
+impl CliContext {
+    /// Wire up managers around an already opened database
+    pub fn new(config: MemexConfig, database: Database) -> Self {
+        let validator = RequestValidator::new(&config);
+        let policy = config.decay_policy();
+
+        Self {
+            memory: MemoryManager::new(database.clone(), validator.clone()),
+            sessions: SessionManager::new(database.clone(), validator.clone()),
+            decay: DecayEngine::new(database.clone(), validator, policy),
+            #[cfg(feature = "vector-search")]
+            vector: None,
+            config,
+            database,
+        }
+    }
+
+    /// Scheduler for the configured maintenance jobs
+    pub fn maintenance_scheduler(&self) -> MaintenanceScheduler {
+        let decay = DecayEngine::new(
+            self.database.clone(),
+            RequestValidator::new(&self.config),
+            self.decay.policy().clone(),
+        );
+        let mut scheduler = MaintenanceScheduler::new(
+            self.database.clone(),
+            std::sync::Arc::new(std::sync::Mutex::new(decay)),
+            self.config.maintenance_schedules(),
+            self.config.maintenance.embedding_backfill.clone(),
+        );
+        if let Some(backup) = &self.config.maintenance.backup {
+            scheduler.enable_backups(backup.clone());
+        }
+        #[cfg(feature = "vector-search")]
+        if let Some(engine) = &self.vector {
+            scheduler.enable_vector_search(engine.clone());
+        }
+        scheduler
+    }
+
+    /// Open the database and build the context described by the command line
+    pub fn open(global: &GlobalArgs, out: &mut dyn Write) -> Result<Self> {
+        let config = load_config(global, out)?;
+
+        let mut db_config = DatabaseConfig {
+            path: global.database.clone(),
+            extensions: global.extensions.clone(),
+            ..Default::default()
+        };
+        if let Some(preset) = global.preset {
+            db_config.apply_preset(preset);
+        }
+        let database = Database::new(db_config).context("Failed to initialize database")?;
+        if let Some(wal_archive) = &config.maintenance.wal_archive {
+            database.enable_wal_archive(wal_archive.clone())?;
+        }
+        if config.seal_memories {
+            database.enable_integrity()?;
+        }
+
+        #[cfg(feature = "tiktoken")]
+        if let Some(tokenizer) = &global.tokenizer {
+            crate::core::tokens::set_tokenizer(std::sync::Arc::new(
+                crate::core::tokens::TiktokenTokenizer::new(tokenizer)?,
+            ));
+        }
+
+        #[allow(unused_mut)]
+        let mut context = Self::new(config, database);
+
+        #[cfg(feature = "vector-search")]
+        if global.enable_vector {
+            let vector_config = VectorConfig {
+                dimension: global.vector_dimension,
+                metric: global.vector_metric,
+                ..Default::default()
+            };
+            let engine =
+                VectorSearchEngine::new(context.database.get_connection_pool(), vector_config);
+            engine
+                .initialize_schema()
+                .context("Failed to initialize vector search")?;
+
+            writeln!(out, "{}", "✓ Vector search enabled".green())?;
+            context.memory.enable_vector_search(engine.clone());
+            if let Some(backfill) = &context.config.maintenance.embedding_backfill {
+                context
+                    .memory
+                    .set_query_embedder(std::sync::Arc::new(backfill.embedder()));
+            }
+            context.sessions.enable_vector_search(engine.clone());
+            context.vector = Some(engine);
+        }
+
+        Ok(context)
+    }
+}
+
+/// Parse-independent entry point used by the `memex` binary
+pub fn run(cli: Cli, out: &mut dyn Write) -> Result<()> {
+    // Restore replaces the database file, so it runs before anything opens it
+    if let Commands::Database {
+        action:
+            database::DatabaseCommands::Restore {
+                input,
+                at,
+                archive_dir,
+            },
+    } = &cli.command
+    {
+        return database::restore(
+            input.as_deref(),
+            *at,
+            archive_dir.as_deref(),
+            &cli.global.database,
+            out,
+        );
+    }
+    let mut context = CliContext::open(&cli.global, out)?;
+    let request = request_context(&cli.global);
+    let _scope = request.as_ref().map(RequestContext::enter);
+    dispatch(cli.command, &mut context, out)
+}
+
+/// The context writes are audited under, if `--actor` or `--trace-id` is set
+fn request_context(global: &GlobalArgs) -> Option<RequestContext> {
+    if global.actor.is_none() && global.trace_id.is_none() {
+        return None;
+    }
+    Some(RequestContext {
+        actor: global.actor.clone(),
+        trace_id: global.trace_id.clone(),
+        source: Some("cli".to_string()),
+    })
+}
+
+/// Route a parsed command to its handler module
+pub fn dispatch(command: Commands, context: &mut CliContext, out: &mut dyn Write) -> Result<()> {
+    match command {
+        Commands::Memory { action } => {
+            memory::handle(*action, &context.memory, &context.decay, out)
+        }
+        Commands::Search { action } => search::handle(action, &context.memory, out),
+        Commands::Session { action } => session::handle(action, &context.sessions, out),
+        Commands::Summaries { action } => summaries::handle(action, &context.sessions, out),
+        Commands::User { action } => user::handle(action, &context.memory, out),
+        Commands::Decay { action } => decay::handle(action, &mut context.decay, out),
+        Commands::Maintenance { action } => {
+            maintenance::handle(action, &context.maintenance_scheduler(), out)
+        }
+        Commands::Import(args) => import::handle(args, &context.memory, out),
+        Commands::Export { action } => export::handle(action, &context.memory, out),
+        Commands::Database { action } => database::handle(action, &context.database, out),
+        Commands::System { action } => system::handle(action, context, out),
+        Commands::Shard {
+            dir,
+            strategy,
+            action,
+        } => {
+            let sharded = ShardedDatabase::new(ShardedDatabaseConfig {
+                directory: dir,
+                strategy,
+                ..Default::default()
+            })?;
+            shard::handle(action, &sharded, out)
+        }
+        #[cfg(feature = "vector-search")]
+        Commands::Vector { action } => match &context.vector {
+            Some(engine) => vector::handle(action, engine, out),
+            None => Err(anyhow::anyhow!(
+                "Vector search not enabled. Use --enable-vector"
+            )),
+        },
+    }
+}
+
+fn load_config(global: &GlobalArgs, out: &mut dyn Write) -> Result<MemexConfig> {
+    if let Some(config_path) = &global.config {
+        let config_content = std::fs::read_to_string(config_path)
+            .with_context(|| format!("Failed to read config file: {}", config_path))?;
+
+        let config: MemexConfig =
+            serde_json::from_str(&config_content).context("Failed to parse config file")?;
+
+        writeln!(out, "{}", "✓ Loaded configuration from file".green())?;
+        Ok(config)
+    } else {
+        Ok(MemexConfig {
+            database_path: global.database.clone(),
+            ..Default::default()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_cli_parsing() {
+        let args = vec![
+            "memex",
+            "memory",
+            "save",
+            "--user",
+            "test_user",
+            "--session",
+            "test_session",
+            "Test content",
+        ];
+
+        let cli = Cli::try_parse_from(args).unwrap();
+        let Commands::Memory { action } = cli.command else {
+            panic!("Wrong command parsed: {:?}", cli.command);
+        };
+        match *action {
+            memory::MemoryCommands::Save {
+                user,
+                session,
+                content,
+                ..
+            } => {
+                assert_eq!(user, "test_user");
+                assert_eq!(session, "test_session");
+                assert_eq!(content, "Test content");
+            }
+            other => panic!("Wrong command parsed: {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_cli_definition_is_consistent() {
+        use clap::CommandFactory;
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_run_end_to_end() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cli.db").to_string_lossy().to_string();
+
+        let cli = Cli::try_parse_from(["memex", "--database", &db_path, "system", "info"]).unwrap();
+
+        let mut out = Vec::new();
+        run(cli, &mut out).unwrap();
+
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains(&db_path));
+    }
+}
@@ -5,7 +5,7 @@ use colored::*;
 use std::collections::HashMap;
 use std::path::Path;
 
-use crate::cli::{format_bytes, format_duration, InteractiveCli};
+use crate::cli::{format_bytes, format_duration, format_signed_bytes, InteractiveCli};
 use crate::core::decay::DecayEngine;
 use crate::core::memory::MemoryManager;
 use crate::core::session::SessionManager;
@@ -335,6 +335,12 @@ impl InteractiveSessionCommands {
             summary.memory_count.to_string().bright_green()
         );
         println!("Importance score: {:.2}", summary.importance_score);
+        println!(
+            "Version: {} ({}, generated {})",
+            summary.version,
+            summary.generator,
+            summary.generated_at.format("%Y-%m-%d %H:%M")
+        );
         println!(
             "Date range: {} to {}",
             summary.date_range.0.format("%Y-%m-%d"),
@@ -384,6 +390,8 @@ impl InteractiveDecayCommands {
             max_memories_per_user: max_memories,
             compression_enabled: compression,
             auto_summarize_sessions: auto_summarize,
+            archive_sessions_after_days: engine.policy().archive_sessions_after_days,
+            confidence_threshold: engine.policy().confidence_threshold,
         };
 
         engine.update_policy(new_policy)?;
@@ -477,6 +485,7 @@ impl InteractiveDecayCommands {
             DecayStatus::Completed => "✓ Completed".green(),
             DecayStatus::Failed => "✗ Failed".red(),
             DecayStatus::Running => "⏳ Running".yellow(),
+            DecayStatus::Skipped => "⏭ Skipped".yellow(),
         };
         println!("Status: {}", status_display);
 
@@ -497,10 +506,15 @@ impl InteractiveDecayCommands {
             ("Memories expired", stats.memories_expired.to_string()),
             ("Memories compressed", stats.memories_compressed.to_string()),
             ("Sessions summarized", stats.sessions_summarized.to_string()),
+            ("Sessions archived", stats.sessions_archived.to_string()),
             (
                 "Storage saved",
                 format_bytes(stats.storage_saved_bytes as u64),
             ),
+            (
+                "Physically freed",
+                format_signed_bytes(stats.physical_bytes_saved),
+            ),
         ];
 
         for (label, value) in summary_data {
@@ -616,31 +630,29 @@ impl DatabaseMaintenanceCommands {
                 // Cleanup temporary files
                 println!("{}", "✓ Cleanup complete".green());
             }
-            4 => {
-                if InteractiveCli::confirm("This will run all optimizations. Continue?", false)? {
-                    println!("🚀 Running full optimization...");
-
-                    // Run all optimizations with progress
-                    let steps = vec![
-                        "Vacuuming database",
-                        "Rebuilding indexes",
-                        "Analyzing performance",
-                        "Cleaning temporary files",
-                        "Updating statistics",
-                    ];
-
-                    for (i, step) in steps.iter().enumerate() {
-                        InteractiveCli::show_progress(i, steps.len(), step);
-                        std::thread::sleep(std::time::Duration::from_millis(1000));
-                        // Simulate work
-                    }
-                    InteractiveCli::show_progress(steps.len(), steps.len(), "Complete");
-
-                    println!(
-                        "\n{}",
-                        "✅ Full optimization complete!".bright_green().bold()
-                    );
+            4 if InteractiveCli::confirm("This will run all optimizations. Continue?", false)? => {
+                println!("🚀 Running full optimization...");
+
+                // Run all optimizations with progress
+                let steps = [
+                    "Vacuuming database",
+                    "Rebuilding indexes",
+                    "Analyzing performance",
+                    "Cleaning temporary files",
+                    "Updating statistics",
+                ];
+
+                for (i, step) in steps.iter().enumerate() {
+                    InteractiveCli::show_progress(i, steps.len(), step);
+                    std::thread::sleep(std::time::Duration::from_millis(1000));
+                    // Simulate work
                 }
+                InteractiveCli::show_progress(steps.len(), steps.len(), "Complete");
+
+                println!(
+                    "\n{}",
+                    "✅ Full optimization complete!".bright_green().bold()
+                );
             }
             5 => {} // Exit
             _ => {}
@@ -710,9 +722,10 @@ impl DatabaseMaintenanceCommands {
                 println!("{}", "✓ Backup integrity verified".green());
             }
             4 => {
-                println!("⏰ Automatic backup scheduling not implemented");
-                println!("Consider using cron or system scheduler to run:");
-                println!("  memex database backup /path/to/backup.db");
+                println!("⏰ Automatic backups run as the maintenance `backup` job");
+                println!("Add to the config file's \"maintenance\" section:");
+                println!("  \"backup\": {{\"directory\": \"backups\", \"keep_daily\": 7, \"keep_weekly\": 4}}");
+                println!("Then check them with `memex system health`");
             }
             5 => {} // Exit
             _ => {}
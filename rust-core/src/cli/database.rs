@@ -0,0 +1,1114 @@
+//! `memex database ...` command handlers
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::cli::format_bytes;
+use crate::database::backup::{self, BackupManifest, BackupOptions};
+use crate::database::pool::CheckpointMode;
+use crate::database::{schema, wal_archive, Database};
+
+#[derive(Subcommand, Debug)]
+pub enum DatabaseCommands {
+    /// Initialize database schema
+    Init,
+    /// Run database migrations
+    Migrate,
+    /// Show database statistics
+    Stats,
+    /// Vacuum database (optimize storage)
+    Vacuum,
+    /// Copy the write-ahead log into the database file
+    Checkpoint {
+        /// passive, full, restart or truncate (which also empties the WAL file)
+        #[arg(long, default_value = "truncate")]
+        mode: CheckpointMode,
+    },
+    /// Rebuild the full-text search index from stored memories
+    ReindexFts,
+    /// Check memories against their integrity hashes to find changes made
+    /// to the database file outside Memex; reads MEMEX_INTEGRITY_KEY
+    VerifyIntegrity {
+        /// Only check this user's memories
+        #[arg(long)]
+        user: Option<String>,
+        /// First seal memories that have no hash yet, e.g. ones saved
+        /// before seal_memories was turned on
+        #[arg(long)]
+        seal: bool,
+    },
+    /// Backup database
+    Backup {
+        /// Backup file path
+        #[arg(required_unless_present = "to", conflicts_with = "to")]
+        output: Option<String>,
+        /// Upload an archive to S3-compatible storage at s3://bucket/prefix
+        /// instead, using the standard AWS_* credentials and AWS_ENDPOINT_URL
+        #[arg(long, value_name = "URL")]
+        to: Option<String>,
+        /// Write a zstd-compressed archive
+        #[arg(long)]
+        compress: bool,
+        /// Write an AES-256-GCM encrypted archive; the passphrase is read
+        /// from MEMEX_BACKUP_PASSPHRASE
+        #[arg(long)]
+        encrypt: bool,
+    },
+    /// Restore from a backup or archive, checking it before the database is
+    /// replaced; encrypted archives read MEMEX_BACKUP_PASSPHRASE
+    Restore {
+        /// Backup file path, s3:// URL or https:// (e.g. presigned) URL
+        #[arg(required_unless_present = "at", conflicts_with = "at")]
+        input: Option<String>,
+        /// Rebuild the database as of this time (e.g. 2026-03-07T04:05:06Z)
+        /// from the WAL archive instead
+        #[arg(long, value_name = "TIMESTAMP")]
+        at: Option<DateTime<Utc>>,
+        /// WAL archive directory; <database>.wal-archive by default
+        #[arg(long, requires = "at")]
+        archive_dir: Option<String>,
+    },
+    /// Continuous WAL archiving for point-in-time recovery
+    WalArchive {
+        #[command(subcommand)]
+        action: WalArchiveCommands,
+    },
+    /// Point-in-time snapshots and what changed between them
+    Snapshot {
+        #[command(subcommand)]
+        action: SnapshotCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum WalArchiveCommands {
+    /// Archive WAL frames committed since the last run, taking a base
+    /// backup when one is due
+    Run,
+    /// Show what the archive holds and the window it can restore
+    Status {
+        /// Archive directory; the configured one or <database>.wal-archive
+        #[arg(long)]
+        dir: Option<String>,
+    },
+    /// Check every file in the archive against its checksum
+    Verify {
+        /// Archive directory; the configured one or <database>.wal-archive
+        #[arg(long)]
+        dir: Option<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SnapshotCommands {
+    /// Take a snapshot of the database
+    Create {
+        /// Name for the snapshot (letters, digits, '-', '_' and '.')
+        label: String,
+    },
+    /// List snapshots, oldest first
+    List,
+    /// Show memories added, removed and changed between two snapshots
+    Diff {
+        /// Earlier snapshot
+        from: String,
+        /// Later snapshot
+        to: String,
+    },
+}
+
+pub fn handle(action: DatabaseCommands, database: &Database, out: &mut dyn Write) -> Result<()> {
+    match action {
+        DatabaseCommands::Init => {
+            // The schema is created when the database is opened
+            writeln!(
+                out,
+                "{}",
+                "🔧 Database already initialized during startup".green()
+            )?;
+        }
+
+        DatabaseCommands::Migrate => {
+            writeln!(out, "{}", "🔄 Running database migrations...".blue().bold())?;
+
+            let pool = database.get_connection_pool();
+            let conn = pool.get_connection()?;
+            schema::run_migrations(&conn)?;
+            let version = schema::get_schema_version(&conn)?;
+
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Schema is at version {}", version).green()
+            )?;
+        }
+
+        DatabaseCommands::Stats => {
+            writeln!(out, "{}", "📊 Database Statistics".green().bold())?;
+
+            let stats = database.get_stats()?;
+            writeln!(out, "{}", serde_json::to_string_pretty(&stats)?)?;
+        }
+
+        DatabaseCommands::Vacuum => {
+            writeln!(out, "{}", "🧹 Vacuuming database...".blue().bold())?;
+            database.vacuum()?;
+            writeln!(out, "{}", "✓ Database vacuumed successfully".green())?;
+        }
+
+        DatabaseCommands::Checkpoint { mode } => {
+            writeln!(
+                out,
+                "{}",
+                format!("📝 Checkpointing WAL ({})...", mode).blue().bold()
+            )?;
+            let checkpoint = database.checkpoint(mode)?;
+            if checkpoint.log_frames < 0 {
+                writeln!(
+                    out,
+                    "{}",
+                    "Database is not in WAL mode; nothing to checkpoint".yellow()
+                )?;
+            } else if checkpoint.busy {
+                writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "✗ Checkpoint incomplete: {} of {} frames copied while readers or writers were active",
+                        checkpoint.checkpointed_frames, checkpoint.log_frames
+                    )
+                    .yellow()
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Checkpointed {} frames", checkpoint.checkpointed_frames).green()
+                )?;
+            }
+            writeln!(
+                out,
+                "WAL size: {} -> {}",
+                format_bytes(checkpoint.wal_size_before),
+                format_bytes(checkpoint.wal_size_after)
+            )?;
+        }
+
+        DatabaseCommands::ReindexFts => {
+            writeln!(out, "{}", "🔎 Rebuilding full-text index...".blue().bold())?;
+            database.rebuild_fts_index()?;
+
+            let consistency = database.check_fts_consistency()?;
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Indexed {} memories", consistency.indexed_rows).green()
+            )?;
+        }
+
+        DatabaseCommands::VerifyIntegrity { user, seal } => {
+            if seal {
+                let sealed = database.seal_unsealed_memories()?;
+                writeln!(out, "Sealed {} memories", sealed)?;
+            }
+
+            let report = database.verify_integrity(user.as_deref())?;
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "🔏 Checked {} memories ({})",
+                    report.checked, report.algorithm
+                )
+                .bold()
+            )?;
+            for violation in &report.mismatched {
+                let detail = match &violation.sealed_with {
+                    Some(algorithm) => format!(" (sealed with {})", algorithm),
+                    None => String::new(),
+                };
+                writeln!(
+                    out,
+                    "  {} {} of {} was changed outside Memex{}",
+                    "✗".red(),
+                    violation.memory_id,
+                    violation.user_id,
+                    detail
+                )?;
+            }
+            if !report.unsealed.is_empty() {
+                writeln!(
+                    out,
+                    "  {} {} memories have no hash; seal them with --seal if they are known to be good",
+                    "!".yellow(),
+                    report.unsealed.len()
+                )?;
+            }
+            if !report.mismatched.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "{} memories failed their integrity check",
+                    report.mismatched.len()
+                ));
+            }
+            writeln!(out, "{}", "✓ Every sealed memory matches its hash".green())?;
+        }
+
+        DatabaseCommands::Backup {
+            output,
+            to,
+            compress,
+            encrypt,
+        } => {
+            let output = match (output, to) {
+                (_, Some(to)) => return upload_backup(database, &to, compress, encrypt, out),
+                (Some(output), None) => output,
+                (None, None) => return Err(anyhow::anyhow!("Give a backup path or --to")),
+            };
+            writeln!(
+                out,
+                "{}",
+                format!("💾 Creating backup: {}", output).blue().bold()
+            )?;
+            if !compress && !encrypt {
+                database.backup_to(&output)?;
+                writeln!(out, "{}", "✓ Backup completed".green())?;
+                return Ok(());
+            }
+
+            write_archive(database, &output, compress, encrypt, out)?;
+        }
+
+        DatabaseCommands::Snapshot { action } => handle_snapshot(action, database, out)?,
+
+        DatabaseCommands::WalArchive { action } => handle_wal_archive(action, database, out)?,
+
+        DatabaseCommands::Restore { .. } => {
+            // Overwriting the file under an open connection pool would corrupt it
+            return Err(anyhow::anyhow!(
+                "Restore cannot run against an open database; run `memex database restore` with no other Memex process using it"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Write an archive to `output` and report what it holds
+fn write_archive(
+    database: &Database,
+    output: &str,
+    compress: bool,
+    encrypt: bool,
+    out: &mut dyn Write,
+) -> Result<BackupManifest> {
+    let passphrase = if encrypt {
+        Some(backup_passphrase()?)
+    } else {
+        None
+    };
+    let manifest = database.backup_archive(
+        output,
+        &BackupOptions {
+            compress,
+            passphrase,
+        },
+    )?;
+    let mut format = Vec::new();
+    if manifest.compressed {
+        format.push("zstd");
+    }
+    if manifest.is_encrypted() {
+        format.push("aes-256-gcm");
+    }
+    if format.is_empty() {
+        format.push("uncompressed");
+    }
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "✓ Backup completed: {} memories, {} -> {} ({})",
+            manifest.memory_count,
+            format_bytes(manifest.database_size),
+            format_bytes(manifest.payload_size),
+            format.join(", ")
+        )
+        .green()
+    )?;
+    writeln!(out, "  sha256: {}", manifest.database_sha256.dimmed())?;
+    Ok(manifest)
+}
+
+/// Archive the database next to it, upload the archive under the
+/// `s3://bucket/prefix` URL `to` and delete the local copy
+fn upload_backup(
+    database: &Database,
+    to: &str,
+    compress: bool,
+    encrypt: bool,
+    out: &mut dyn Write,
+) -> Result<()> {
+    writeln!(
+        out,
+        "{}",
+        format!("💾 Creating backup for {}", to).blue().bold()
+    )?;
+    let staging = format!("{}.upload", database.path());
+    let uploaded = write_archive(database, &staging, compress, encrypt, out).and_then(|manifest| {
+        backup::upload(
+            std::path::Path::new(&staging),
+            database.path(),
+            manifest.created_at,
+            true,
+            to,
+        )
+    });
+    let _ = std::fs::remove_file(&staging);
+
+    let receipt = uploaded?;
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "✓ Uploaded {} to {} ({} part{})",
+            format_bytes(receipt.size_bytes),
+            receipt.url,
+            receipt.parts,
+            if receipt.parts == 1 { "" } else { "s" }
+        )
+        .green()
+    )?;
+    Ok(())
+}
+
+/// Replace the database file at `database_path` with the backup at `input`,
+/// a local path or a URL, or with its state `at` a time rebuilt from the
+/// WAL archive; runs before the database is opened
+pub fn restore(
+    input: Option<&str>,
+    at: Option<DateTime<Utc>>,
+    archive_dir: Option<&str>,
+    database_path: &str,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match (input, at) {
+        (_, Some(at)) => restore_point_in_time(at, archive_dir, database_path, out),
+        (Some(input), None) => {
+            let passphrase = std::env::var(backup::PASSPHRASE_ENV).ok();
+            restore_with(input, database_path, passphrase.as_deref(), out)
+        }
+        (None, None) => Err(anyhow::anyhow!("Give a backup to restore or --at")),
+    }
+}
+
+fn restore_point_in_time(
+    at: DateTime<Utc>,
+    archive_dir: Option<&str>,
+    database_path: &str,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let dir = archive_dir.map_or_else(
+        || wal_archive::default_directory(database_path),
+        std::path::PathBuf::from,
+    );
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "📥 Restoring {} as of {} from {}",
+            database_path,
+            at.format("%Y-%m-%d %H:%M:%S UTC"),
+            dir.display()
+        )
+        .blue()
+        .bold()
+    )?;
+    let (rebuilt, outcome) = wal_archive::restore(&dir, database_path, at)?;
+
+    writeln!(
+        out,
+        "✓ Replayed {} WAL segments ({} frames) onto the base backup from {}",
+        rebuilt.segments_applied,
+        rebuilt.frames_applied,
+        rebuilt.base_created_at.format("%Y-%m-%d %H:%M:%S")
+    )?;
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "✓ Restored {} memories as of {} (schema version {})",
+            outcome.memory_count,
+            rebuilt.recovered_to.format("%Y-%m-%d %H:%M:%S UTC"),
+            outcome.schema_version
+        )
+        .green()
+    )?;
+    if let Some(previous) = &outcome.previous {
+        writeln!(
+            out,
+            "  Previous database kept at {}",
+            previous.display().to_string().dimmed()
+        )?;
+    }
+    Ok(())
+}
+
+fn handle_wal_archive(
+    action: WalArchiveCommands,
+    database: &Database,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let directory = |dir: Option<String>| {
+        dir.map(std::path::PathBuf::from)
+            .or_else(|| database.wal_archive_dir())
+            .unwrap_or_else(|| wal_archive::default_directory(database.path()))
+    };
+    match action {
+        WalArchiveCommands::Run => {
+            if database.wal_archive_dir().is_none() {
+                database.enable_wal_archive(Default::default())?;
+            }
+            let run = database
+                .archive_wal()?
+                .expect("WAL archiving was just enabled");
+            if run.new_chain {
+                writeln!(
+                    out,
+                    "{}",
+                    "⚠ The WAL changed since the last run; started a new chain".yellow()
+                )?;
+            }
+            match &run.segment {
+                Some(segment) => writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "✓ Archived {} of WAL ({} frames)",
+                        format_bytes(segment.end - segment.start),
+                        segment.frames
+                    )
+                    .green()
+                )?,
+                None => writeln!(out, "✓ No new WAL frames")?,
+            }
+            if let Some(base) = &run.base {
+                writeln!(
+                    out,
+                    "✓ Took base backup {} ({})",
+                    base.file,
+                    format_bytes(base.size_bytes)
+                )?;
+            }
+        }
+        WalArchiveCommands::Status { dir } => {
+            let report = wal_archive::verify(&directory(dir), false)?;
+            write_archive_report(&report, out)?;
+        }
+        WalArchiveCommands::Verify { dir } => {
+            let report = wal_archive::verify(&directory(dir), true)?;
+            write_archive_report(&report, out)?;
+            if !report.problems.is_empty() {
+                return Err(anyhow::anyhow!(
+                    "WAL archive has {} problem(s)",
+                    report.problems.len()
+                ));
+            }
+            writeln!(out, "{}", "✓ Every file matches its checksum".green())?;
+        }
+    }
+    Ok(())
+}
+
+fn write_archive_report(report: &wal_archive::ArchiveReport, out: &mut dyn Write) -> Result<()> {
+    writeln!(
+        out,
+        "{}",
+        format!("WAL archive: {}", report.directory).bold()
+    )?;
+    writeln!(
+        out,
+        "  {} base backups, {} segments, {}",
+        report.bases,
+        report.segments,
+        format_bytes(report.size_bytes)
+    )?;
+    match (report.earliest, report.latest) {
+        (Some(earliest), Some(latest)) => writeln!(
+            out,
+            "  Restorable from {} to {}",
+            earliest.format("%Y-%m-%d %H:%M:%S UTC"),
+            latest.format("%Y-%m-%d %H:%M:%S UTC")
+        )?,
+        _ => writeln!(out, "  Nothing to restore yet")?,
+    }
+    for problem in &report.problems {
+        writeln!(out, "  {} {}", "✗".red(), problem)?;
+    }
+    Ok(())
+}
+
+fn restore_with(
+    input: &str,
+    database_path: &str,
+    passphrase: Option<&str>,
+    out: &mut dyn Write,
+) -> Result<()> {
+    writeln!(
+        out,
+        "{}",
+        format!("📥 Restoring {} from {}", database_path, input)
+            .blue()
+            .bold()
+    )?;
+    let outcome = if backup::is_remote(input) {
+        let download = format!("{}.download", database_path);
+        let size = backup::download(input, std::path::Path::new(&download));
+        let outcome = size.and_then(|size| {
+            writeln!(out, "✓ Downloaded {}", format_bytes(size))?;
+            backup::restore(std::path::Path::new(&download), database_path, passphrase)
+        });
+        let _ = std::fs::remove_file(&download);
+        outcome?
+    } else {
+        backup::restore(std::path::Path::new(input), database_path, passphrase)?
+    };
+
+    if let Some(manifest) = &outcome.manifest {
+        writeln!(
+            out,
+            "✓ Archive verified: created {} from {}",
+            manifest.created_at.format("%Y-%m-%d %H:%M:%S"),
+            manifest.source
+        )?;
+    }
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "✓ Restored {} memories (schema version {})",
+            outcome.memory_count, outcome.schema_version
+        )
+        .green()
+    )?;
+    if let Some(previous) = &outcome.previous {
+        writeln!(
+            out,
+            "  Previous database kept at {}",
+            previous.display().to_string().dimmed()
+        )?;
+    }
+    Ok(())
+}
+
+fn backup_passphrase() -> Result<String> {
+    match std::env::var(backup::PASSPHRASE_ENV) {
+        Ok(passphrase) if !passphrase.is_empty() => Ok(passphrase),
+        _ => Err(anyhow::anyhow!(
+            "Set {} to encrypt the backup",
+            backup::PASSPHRASE_ENV
+        )),
+    }
+}
+
+fn handle_snapshot(
+    action: SnapshotCommands,
+    database: &Database,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        SnapshotCommands::Create { label } => {
+            let manifest = database.snapshot(&label)?;
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "✓ Snapshot {}: {} memories, {}",
+                    manifest.label,
+                    manifest.memory_count,
+                    format_bytes(manifest.size_bytes)
+                )
+                .green()
+            )?;
+            writeln!(out, "  {}", manifest.file.dimmed())?;
+        }
+
+        SnapshotCommands::List => {
+            let snapshots = database.list_snapshots()?;
+            if snapshots.is_empty() {
+                writeln!(out, "No snapshots")?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "Snapshots:".green().bold())?;
+            for manifest in &snapshots {
+                writeln!(
+                    out,
+                    "  {} {} ({} memories, {})",
+                    manifest.label.bright_blue(),
+                    manifest.created_at.format("%Y-%m-%d %H:%M:%S"),
+                    manifest.memory_count,
+                    format_bytes(manifest.size_bytes)
+                )?;
+            }
+        }
+
+        SnapshotCommands::Diff { from, to } => {
+            let diff = database.diff_snapshots(&from, &to)?;
+            if diff.is_empty() {
+                writeln!(out, "No memories changed from {} to {}", from, to)?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("Changes from {} to {}:", from, to).green().bold()
+            )?;
+            for (user_id, user_diff) in &diff.users {
+                writeln!(
+                    out,
+                    "  {} ({} added, {} removed, {} changed)",
+                    user_id.bright_blue(),
+                    user_diff.added.len(),
+                    user_diff.removed.len(),
+                    user_diff.changed.len()
+                )?;
+                for id in &user_diff.added {
+                    writeln!(out, "    {} {}", "+".green(), id)?;
+                }
+                for id in &user_diff.removed {
+                    writeln!(out, "    {} {}", "-".red(), id)?;
+                }
+                for id in &user_diff.changed {
+                    writeln!(out, "    {} {}", "~".yellow(), id)?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::DatabaseConfig;
+    use tempfile::TempDir;
+
+    fn setup_test_database() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        (Database::new(db_config).unwrap(), temp_dir)
+    }
+
+    fn run(database: &Database, action: DatabaseCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(action, database, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_stats_and_vacuum() {
+        let (database, _temp_dir) = setup_test_database();
+
+        let output = run(&database, DatabaseCommands::Stats).unwrap();
+        assert!(output.contains("\"total_memories\": 0"));
+
+        let output = run(&database, DatabaseCommands::Vacuum).unwrap();
+        assert!(output.contains("vacuumed successfully"));
+    }
+
+    #[test]
+    fn test_migrate_records_schema_version() {
+        let (database, _temp_dir) = setup_test_database();
+
+        let output = run(&database, DatabaseCommands::Migrate).unwrap();
+        assert!(output.contains("Schema is at version 2"));
+    }
+
+    #[test]
+    fn test_verify_integrity_finds_edits_outside_memex() {
+        let (database, _temp_dir) = setup_test_database();
+        let save = |content: &str| {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let verify = |seal: bool| {
+            run(
+                &database,
+                DatabaseCommands::VerifyIntegrity { user: None, seal },
+            )
+        };
+
+        let before = save("Saved before sealing");
+        database.enable_integrity().unwrap();
+        let tampered = save("Transfer 100 to Bob");
+        save("Left alone");
+
+        let output = verify(false).unwrap();
+        assert!(output.contains("Checked 3 memories"), "{}", output);
+        assert!(output.contains("1 memories have no hash"), "{}", output);
+        let output = verify(true).unwrap();
+        assert!(output.contains("Sealed 1 memories"), "{}", output);
+        assert!(database.verify_integrity(None).unwrap().is_intact());
+
+        database
+            .get_connection_pool()
+            .get_connection()
+            .unwrap()
+            .execute(
+                "UPDATE memories SET content = 'Transfer 900 to Eve' WHERE id = ?1",
+                [&tampered],
+            )
+            .unwrap();
+        let error = verify(false).unwrap_err();
+        assert!(error.to_string().contains("1 memories failed"), "{}", error);
+
+        let report = database.verify_integrity(Some("user1")).unwrap();
+        assert_eq!(report.mismatched.len(), 1);
+        assert_eq!(report.mismatched[0].memory_id, tampered);
+        assert!(report.unsealed.is_empty());
+        assert!(database
+            .update_memory_with(&before, None, |memory| {
+                memory.importance = 0.9;
+                Ok(())
+            })
+            .unwrap()
+            .is_some());
+        assert_eq!(database.verify_integrity(None).unwrap().mismatched.len(), 1);
+    }
+
+    #[test]
+    fn test_reindex_fts_repairs_drift() {
+        let (database, _temp_dir) = setup_test_database();
+        let session_id = database.create_session("user1", None).unwrap();
+        database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id,
+                content: "Reindex me".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(database.check_fts_consistency().unwrap().is_consistent());
+
+        // Simulate drift by dropping the row from the index behind the trigger's back
+        let pool = database.get_connection_pool();
+        pool.get_connection()
+            .unwrap()
+            .execute_batch("DELETE FROM memories_fts_docsize")
+            .unwrap();
+        let consistency = database.check_fts_consistency().unwrap();
+        assert_eq!(consistency.missing, 1);
+        assert!(!consistency.is_consistent());
+
+        let output = run(&database, DatabaseCommands::ReindexFts).unwrap();
+        assert!(output.contains("Indexed 1 memories"));
+        assert!(database.check_fts_consistency().unwrap().is_consistent());
+    }
+
+    #[test]
+    fn test_resave_keeps_fts_consistent() {
+        let (database, _temp_dir) = setup_test_database();
+        let session_id = database.create_session("user1", None).unwrap();
+        let mut memory = MemoryItem {
+            id: "resaved".to_string(),
+            user_id: "user1".to_string(),
+            session_id,
+            content: "Original wording".to_string(),
+            ..Default::default()
+        };
+        database.save_memory(&memory).unwrap();
+        memory.content = "Rewritten entirely".to_string();
+        database.save_memory(&memory).unwrap();
+
+        let consistency = database.check_fts_consistency().unwrap();
+        assert_eq!((consistency.memory_rows, consistency.indexed_rows), (1, 1));
+        assert_eq!(consistency.orphaned, 0);
+        assert!(consistency.integrity_ok);
+    }
+
+    #[test]
+    fn test_backup_writes_file_once() {
+        let (database, temp_dir) = setup_test_database();
+        let backup_path = temp_dir
+            .path()
+            .join("backup.db")
+            .to_string_lossy()
+            .to_string();
+
+        run(
+            &database,
+            DatabaseCommands::Backup {
+                output: Some(backup_path.clone()),
+                to: None,
+                compress: false,
+                encrypt: false,
+            },
+        )
+        .unwrap();
+        assert!(std::path::Path::new(&backup_path).exists());
+
+        // Refuses to overwrite an existing backup
+        assert!(run(
+            &database,
+            DatabaseCommands::Backup {
+                output: Some(backup_path),
+                to: None,
+                compress: false,
+                encrypt: false,
+            }
+        )
+        .is_err());
+    }
+
+    #[test]
+    #[cfg(not(feature = "s3-backup"))]
+    fn test_remote_backup_requires_feature() {
+        let (database, temp_dir) = setup_test_database();
+
+        let error = run(
+            &database,
+            DatabaseCommands::Backup {
+                output: None,
+                to: Some("s3://bucket/memex".to_string()),
+                compress: false,
+                encrypt: false,
+            },
+        )
+        .unwrap_err();
+        assert!(error.to_string().contains("'s3-backup'"), "{}", error);
+        assert!(!std::path::Path::new(&format!("{}.upload", database.path())).exists());
+
+        let target = temp_dir.path().join("restored.db");
+        let target = target.to_string_lossy();
+        let error =
+            restore_with("s3://bucket/memex/x.memex", &target, None, &mut Vec::new()).unwrap_err();
+        assert!(error.to_string().contains("'s3-backup'"), "{}", error);
+    }
+
+    #[test]
+    #[cfg(feature = "compression")]
+    fn test_compressed_backup_and_restore() {
+        let (database, temp_dir) = setup_test_database();
+        database
+            .save_memory(&MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "session1".to_string(),
+                content: "Backed up".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let archive = temp_dir
+            .path()
+            .join("backup.memex")
+            .to_string_lossy()
+            .to_string();
+
+        let output = run(
+            &database,
+            DatabaseCommands::Backup {
+                output: Some(archive.clone()),
+                to: None,
+                compress: true,
+                encrypt: false,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("1 memories"), "{}", output);
+        assert!(output.contains("(zstd)"), "{}", output);
+
+        let target = temp_dir
+            .path()
+            .join("restored.db")
+            .to_string_lossy()
+            .to_string();
+        let mut out = Vec::new();
+        restore_with(&archive, &target, None, &mut out).unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Archive verified"), "{}", output);
+        assert!(output.contains("Restored 1 memories"), "{}", output);
+    }
+
+    #[test]
+    fn test_snapshot_commands() {
+        let (database, _temp_dir) = setup_test_database();
+        let snapshot =
+            |action: SnapshotCommands| run(&database, DatabaseCommands::Snapshot { action });
+        assert!(snapshot(SnapshotCommands::List)
+            .unwrap()
+            .contains("No snapshots"));
+
+        snapshot(SnapshotCommands::Create {
+            label: "before".to_string(),
+        })
+        .unwrap();
+        let id = database
+            .save_memory(&MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "session1".to_string(),
+                content: "Saved between snapshots".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let output = snapshot(SnapshotCommands::Create {
+            label: "after".to_string(),
+        })
+        .unwrap();
+        assert!(
+            output.contains("✓ Snapshot after: 1 memories"),
+            "{}",
+            output
+        );
+
+        let output = snapshot(SnapshotCommands::List).unwrap();
+        assert!(
+            output.contains("before") && output.contains("after"),
+            "{}",
+            output
+        );
+
+        let output = snapshot(SnapshotCommands::Diff {
+            from: "before".to_string(),
+            to: "after".to_string(),
+        })
+        .unwrap();
+        assert!(
+            output.contains("alice (1 added, 0 removed, 0 changed)"),
+            "{}",
+            output
+        );
+        assert!(output.contains(&id), "{}", output);
+
+        assert!(snapshot(SnapshotCommands::Diff {
+            from: "after".to_string(),
+            to: "after".to_string(),
+        })
+        .unwrap()
+        .contains("No memories changed"));
+    }
+
+    #[test]
+    fn test_checkpoint_truncates_wal() {
+        let (database, _temp_dir) = setup_test_database();
+        for i in 0..20 {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "session1".to_string(),
+                    content: format!("Memory {}", i),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert!(database.wal_size_bytes() > 0);
+
+        let output = run(
+            &database,
+            DatabaseCommands::Checkpoint {
+                mode: CheckpointMode::Truncate,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("✓ Checkpointed"), "{}", output);
+        assert!(output.contains("-> 0 B"), "{}", output);
+        assert_eq!(database.wal_size_bytes(), 0);
+
+        let stats = database.get_stats().unwrap();
+        assert_eq!(stats["wal_size_bytes"], 0);
+    }
+
+    #[test]
+    fn test_wal_archive_and_point_in_time_restore() {
+        let (database, temp_dir) = setup_test_database();
+        let save = |content: &str| {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        };
+
+        save("Before");
+        let output = run(
+            &database,
+            DatabaseCommands::WalArchive {
+                action: WalArchiveCommands::Run,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("✓ Archived"), "{}", output);
+        assert!(output.contains("✓ Took base backup"), "{}", output);
+        let at = Utc::now();
+
+        save("After");
+        let output = run(
+            &database,
+            DatabaseCommands::WalArchive {
+                action: WalArchiveCommands::Run,
+            },
+        )
+        .unwrap();
+        assert!(!output.contains("base backup"), "{}", output);
+
+        let output = run(
+            &database,
+            DatabaseCommands::WalArchive {
+                action: WalArchiveCommands::Verify { dir: None },
+            },
+        )
+        .unwrap();
+        assert!(output.contains("1 base backups, 2 segments"), "{}", output);
+        assert!(output.contains("✓ Every file matches"), "{}", output);
+
+        let archive_dir = database.wal_archive_dir().unwrap();
+        let target = temp_dir.path().join("pitr.db");
+        let mut out = Vec::new();
+        restore(
+            None,
+            Some(at),
+            Some(&archive_dir.to_string_lossy()),
+            &target.to_string_lossy(),
+            &mut out,
+        )
+        .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(output.contains("Restored 1 memories"), "{}", output);
+    }
+
+    #[test]
+    fn test_restore_is_refused() {
+        let (database, _temp_dir) = setup_test_database();
+
+        assert!(run(
+            &database,
+            DatabaseCommands::Restore {
+                input: Some("backup.db".to_string()),
+                at: None,
+                archive_dir: None,
+            }
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,577 @@
+//! `memex decay ...` command handlers
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::{IsTerminal, Write};
+
+use crate::cli::{format_bytes, format_signed_bytes};
+use crate::core::decay::{DecayEngine, DecayPhase};
+use crate::database::models::{DecayPolicy, DecayStatus, RetentionRule};
+
+#[derive(Subcommand, Debug)]
+pub enum DecayCommands {
+    /// Run decay process
+    Run {
+        /// Dry run (report what would be cleaned up without changing anything)
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Show decay recommendations
+    Analyze,
+    /// Validate and preview a decay policy change
+    Policy {
+        /// Max age in hours
+        #[arg(long)]
+        max_age: Option<u32>,
+        /// Importance threshold
+        #[arg(long)]
+        threshold: Option<f32>,
+        /// Max memories per user
+        #[arg(long)]
+        max_memories: Option<usize>,
+        /// Enable compression
+        #[arg(long)]
+        compression: Option<bool>,
+        /// Archive sessions inactive for this many days
+        #[arg(long, value_name = "DAYS")]
+        archive_after: Option<u32>,
+        /// Memories less confident than this expire sooner (0 turns it off)
+        #[arg(long)]
+        confidence_threshold: Option<f32>,
+    },
+    /// Show age distribution
+    Distribution,
+    /// Manage per-category retention rules
+    Rules {
+        #[command(subcommand)]
+        action: RuleCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RuleCommands {
+    /// List retention rules
+    List,
+    /// Add a rule, replacing any existing rule for the category
+    Add {
+        /// Value of the memories' `category` metadata
+        category: String,
+        /// Expire the category's memories after this many hours
+        #[arg(long, required_unless_present = "never_expire")]
+        max_age: Option<u32>,
+        /// Never expire the category's memories
+        #[arg(long, conflicts_with = "max_age")]
+        never_expire: bool,
+    },
+    /// Remove the rule for a category
+    Remove {
+        /// Category to remove the rule for
+        category: String,
+    },
+}
+
+pub fn handle(action: DecayCommands, engine: &mut DecayEngine, out: &mut dyn Write) -> Result<()> {
+    match action {
+        DecayCommands::Run { dry_run: true } => {
+            writeln!(
+                out,
+                "{}",
+                "🧪 Running decay process in DRY RUN mode".yellow().bold()
+            )?;
+            writeln!(out, "No changes will be made to the database")?;
+
+            let recommendations = engine.get_decay_recommendations()?;
+            writeln!(
+                out,
+                "\nTotal memories: {}",
+                recommendations.total_memories.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Would clean up: {} memories",
+                recommendations
+                    .estimated_cleanup_count
+                    .to_string()
+                    .bright_cyan()
+            )?;
+        }
+
+        DecayCommands::Run { dry_run: false } => {
+            writeln!(out, "{}", "🧹 Running decay process...".green().bold())?;
+
+            // Progress goes to stderr, as the run happens before any of the
+            // output is written
+            let show_progress = std::io::stderr().is_terminal();
+            if show_progress {
+                engine.on_progress(|progress| {
+                    let (phase, processed) = match progress.phase {
+                        DecayPhase::Expiring => ("Expiring", "expired"),
+                        DecayPhase::Compressing => ("Compressing", "compressed"),
+                    };
+                    eprint!(
+                        "\r  {}: {} scanned, {} {}    ",
+                        phase, progress.scanned, progress.processed, processed
+                    );
+                });
+            }
+            let stats = engine.run_decay()?;
+            if show_progress {
+                eprintln!();
+            }
+
+            writeln!(out, "\n{}", "Decay Results:".green().bold())?;
+            writeln!(out, "Run ID: {}", stats.run_id.bright_blue())?;
+            writeln!(
+                out,
+                "Status: {}",
+                match stats.status {
+                    DecayStatus::Completed => "✓ Completed".green(),
+                    DecayStatus::Failed => "✗ Failed".red(),
+                    DecayStatus::Running => "⏳ Running".yellow(),
+                    DecayStatus::Skipped => "⏭ Skipped".yellow(),
+                }
+            )?;
+
+            if let Some(completed_at) = stats.completed_at {
+                let duration = completed_at - stats.started_at;
+                writeln!(out, "Duration: {}ms", duration.num_milliseconds())?;
+            }
+
+            writeln!(out, "\n{}", "Statistics:".bold())?;
+            writeln!(
+                out,
+                "  Memories before: {}",
+                stats.total_memories_before.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "  Memories after: {}",
+                stats.total_memories_after.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "  Memories expired: {}",
+                stats.memories_expired.to_string().bright_red()
+            )?;
+            writeln!(
+                out,
+                "  Memories compressed: {}",
+                stats.memories_compressed.to_string().bright_yellow()
+            )?;
+            writeln!(
+                out,
+                "  Sessions summarized: {}",
+                stats.sessions_summarized.to_string().bright_green()
+            )?;
+            writeln!(
+                out,
+                "  Sessions archived: {}",
+                stats.sessions_archived.to_string().bright_green()
+            )?;
+            writeln!(
+                out,
+                "  Storage saved: {} logical, {} physical",
+                format_bytes(stats.storage_saved_bytes as u64).bright_cyan(),
+                format_signed_bytes(stats.physical_bytes_saved).bright_cyan()
+            )?;
+
+            if let Some(error) = stats.error_message {
+                writeln!(out, "\n{}", format!("Error: {}", error).red())?;
+            }
+        }
+
+        DecayCommands::Analyze => {
+            writeln!(
+                out,
+                "{}",
+                "📊 Analyzing memory decay recommendations...".blue().bold()
+            )?;
+
+            let recommendations = engine.get_decay_recommendations()?;
+
+            writeln!(out, "\n{}", "Memory Analysis:".green().bold())?;
+            writeln!(
+                out,
+                "Total memories: {}",
+                recommendations.total_memories.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Old memory percentage: {:.1}%",
+                recommendations.old_memory_percentage
+            )?;
+
+            writeln!(out, "\n{}", "Age Distribution:".bold())?;
+            for (age_bucket, count) in &recommendations.age_distribution {
+                let percentage = if recommendations.total_memories > 0 {
+                    (*count as f32 / recommendations.total_memories as f32) * 100.0
+                } else {
+                    0.0
+                };
+                writeln!(out, "  {}: {} ({:.1}%)", age_bucket, count, percentage)?;
+            }
+
+            if !recommendations.recommendations.is_empty() {
+                writeln!(out, "\n{}", "Recommendations:".yellow().bold())?;
+                for rec in &recommendations.recommendations {
+                    writeln!(out, "  • {}", rec)?;
+                }
+            }
+
+            if let Some(suggested_age) = recommendations.suggested_max_age_hours {
+                writeln!(
+                    out,
+                    "\n{}",
+                    format!(
+                        "💡 Suggested max age: {} hours ({} days)",
+                        suggested_age,
+                        suggested_age / 24
+                    )
+                    .bright_yellow()
+                )?;
+            }
+
+            writeln!(
+                out,
+                "\n{}",
+                format!(
+                    "🧹 Estimated cleanup: {} memories",
+                    recommendations.estimated_cleanup_count
+                )
+                .bright_cyan()
+            )?;
+        }
+
+        DecayCommands::Policy {
+            max_age,
+            threshold,
+            max_memories,
+            compression,
+            archive_after,
+            confidence_threshold,
+        } => {
+            let current = engine.policy().clone();
+
+            writeln!(out, "Current policy:")?;
+            print_policy(out, &current)?;
+
+            if max_age.is_none()
+                && threshold.is_none()
+                && max_memories.is_none()
+                && compression.is_none()
+                && archive_after.is_none()
+                && confidence_threshold.is_none()
+            {
+                writeln!(out, "\n{}", "No updates specified".dimmed())?;
+                return Ok(());
+            }
+
+            let updated = DecayPolicy {
+                max_age_hours: max_age.unwrap_or(current.max_age_hours),
+                importance_threshold: threshold.unwrap_or(current.importance_threshold),
+                max_memories_per_user: max_memories.unwrap_or(current.max_memories_per_user),
+                compression_enabled: compression.unwrap_or(current.compression_enabled),
+                auto_summarize_sessions: current.auto_summarize_sessions,
+                archive_sessions_after_days: archive_after.or(current.archive_sessions_after_days),
+                confidence_threshold: confidence_threshold.unwrap_or(current.confidence_threshold),
+            };
+
+            // Validates the new values; the policy itself lives in the config file
+            engine.update_policy(updated)?;
+
+            writeln!(out, "\n{}", "Updated policy:".yellow())?;
+            print_policy(out, engine.policy())?;
+            writeln!(
+                out,
+                "\n{}",
+                "Note: set these values in the config file (-c) to keep them".dimmed()
+            )?;
+        }
+
+        DecayCommands::Distribution => {
+            writeln!(
+                out,
+                "{}",
+                "📈 Analyzing memory age distribution...".blue().bold()
+            )?;
+
+            let distribution = engine.analyze_memory_age_distribution()?;
+            let total: usize = distribution.values().sum();
+
+            if total == 0 {
+                writeln!(out, "{}", "No memories found".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(out, "\n{}", "Age Distribution:".green().bold())?;
+
+            // Sort age buckets in logical order
+            let ordered_buckets = ["0-24h", "1-7d", "1-4w", "1-3m", "3m-1y", "1y+"];
+
+            for bucket in &ordered_buckets {
+                if let Some(count) = distribution.get(*bucket) {
+                    let percentage = (*count as f32 / total as f32) * 100.0;
+                    let bar_length = (percentage / 2.0) as usize; // Scale to 50 chars max
+                    let bar = "█".repeat(bar_length);
+
+                    writeln!(
+                        out,
+                        "  {:>6} │ {:4} │ {:5.1}% │ {}",
+                        bucket,
+                        count,
+                        percentage,
+                        bar.bright_blue()
+                    )?;
+                }
+            }
+
+            writeln!(out, "\nTotal memories: {}", total.to_string().bright_blue())?;
+        }
+
+        DecayCommands::Rules { action } => handle_rules(action, engine, out)?,
+    }
+
+    Ok(())
+}
+
+fn handle_rules(action: RuleCommands, engine: &DecayEngine, out: &mut dyn Write) -> Result<()> {
+    match action {
+        RuleCommands::List => {
+            let rules = engine.retention_rules()?;
+            if rules.is_empty() {
+                writeln!(
+                    out,
+                    "No retention rules; the decay policy applies to all memories"
+                )?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "Retention Rules:".green().bold())?;
+            for rule in &rules {
+                writeln!(
+                    out,
+                    "  {}: {}",
+                    rule.category.bright_blue(),
+                    describe_rule(rule)
+                )?;
+            }
+        }
+
+        RuleCommands::Add {
+            category,
+            max_age,
+            never_expire,
+        } => {
+            let rule = RetentionRule {
+                category,
+                max_age_hours: if never_expire { None } else { max_age },
+            };
+            engine.set_retention_rule(rule.clone())?;
+
+            writeln!(
+                out,
+                "{}",
+                format!("✓ {}: {}", rule.category, describe_rule(&rule)).green()
+            )?;
+        }
+
+        RuleCommands::Remove { category } => {
+            if !engine.remove_retention_rule(&category)? {
+                return Err(anyhow::anyhow!(
+                    "No retention rule for category: {}",
+                    category
+                ));
+            }
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Removed retention rule for {}", category).green()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn describe_rule(rule: &RetentionRule) -> String {
+    match rule.max_age_hours {
+        Some(hours) => format!("expire after {} hours", hours),
+        None => "never expire".to_string(),
+    }
+}
+
+fn print_policy(out: &mut dyn Write, policy: &DecayPolicy) -> Result<()> {
+    writeln!(out, "  Max age: {} hours", policy.max_age_hours)?;
+    writeln!(
+        out,
+        "  Importance threshold: {}",
+        policy.importance_threshold
+    )?;
+    writeln!(
+        out,
+        "  Max memories per user: {}",
+        policy.max_memories_per_user
+    )?;
+    writeln!(
+        out,
+        "  Confidence threshold: {}",
+        policy.confidence_threshold
+    )?;
+    writeln!(out, "  Compression enabled: {}", policy.compression_enabled)?;
+    match policy.archive_sessions_after_days {
+        Some(days) => writeln!(out, "  Archive sessions after: {} days inactive", days)?,
+        None => writeln!(out, "  Archive sessions after: never")?,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_engine() -> (DecayEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        let engine = DecayEngine::new(database, validator, DecayPolicy::default());
+        (engine, temp_dir)
+    }
+
+    fn run(engine: &mut DecayEngine, action: DecayCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(action, engine, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_run_and_dry_run() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+
+        let output = run(&mut engine, DecayCommands::Run { dry_run: true }).unwrap();
+        assert!(output.contains("DRY RUN"));
+        assert!(output.contains("Would clean up: 0 memories"));
+
+        let output = run(&mut engine, DecayCommands::Run { dry_run: false }).unwrap();
+        assert!(output.contains("Decay Results"));
+    }
+
+    #[test]
+    fn test_policy_update() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+
+        let output = run(
+            &mut engine,
+            DecayCommands::Policy {
+                max_age: Some(48),
+                threshold: None,
+                max_memories: None,
+                compression: Some(false),
+                archive_after: None,
+                confidence_threshold: None,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("Updated policy"));
+        assert_eq!(engine.policy().max_age_hours, 48);
+        assert!(!engine.policy().compression_enabled);
+    }
+
+    #[test]
+    fn test_policy_rejects_invalid_values() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+
+        let result = run(
+            &mut engine,
+            DecayCommands::Policy {
+                max_age: None,
+                threshold: Some(1.5),
+                max_memories: None,
+                compression: None,
+                archive_after: None,
+                confidence_threshold: None,
+            },
+        );
+        assert!(result.is_err());
+        assert_eq!(
+            engine.policy().importance_threshold,
+            DecayPolicy::default().importance_threshold
+        );
+    }
+
+    #[test]
+    fn test_distribution_empty() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+
+        let output = run(&mut engine, DecayCommands::Distribution).unwrap();
+        assert!(output.contains("No memories found"));
+    }
+
+    #[test]
+    fn test_retention_rules() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+        let mut rules = |action| run(&mut engine, DecayCommands::Rules { action });
+
+        let output = rules(RuleCommands::List).unwrap();
+        assert!(output.contains("No retention rules"));
+
+        let output = rules(RuleCommands::Add {
+            category: "trading".to_string(),
+            max_age: Some(168),
+            never_expire: false,
+        })
+        .unwrap();
+        assert!(output.contains("trading: expire after 168 hours"));
+        rules(RuleCommands::Add {
+            category: "personal".to_string(),
+            max_age: None,
+            never_expire: true,
+        })
+        .unwrap();
+
+        let output = rules(RuleCommands::List).unwrap();
+        assert!(output.contains("personal: never expire"));
+        assert!(output.contains("trading: expire after 168 hours"));
+
+        rules(RuleCommands::Remove {
+            category: "trading".to_string(),
+        })
+        .unwrap();
+        assert!(rules(RuleCommands::Remove {
+            category: "trading".to_string(),
+        })
+        .is_err());
+        assert_eq!(engine.retention_rules().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_rule_add_needs_exactly_one_retention() {
+        use crate::cli::Cli;
+        use clap::Parser;
+
+        let parse = |args: &[&str]| {
+            Cli::try_parse_from(
+                ["memex", "decay", "rules", "add", "trading"]
+                    .iter()
+                    .chain(args),
+            )
+        };
+
+        assert!(parse(&[]).is_err());
+        assert!(parse(&["--max-age", "24", "--never-expire"]).is_err());
+        assert!(parse(&["--max-age", "24"]).is_ok());
+        assert!(parse(&["--never-expire"]).is_ok());
+    }
+}
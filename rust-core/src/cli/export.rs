@@ -0,0 +1,135 @@
+//! `memex export ...` command handlers
+
+use anyhow::{Context, Result};
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::core::graph::GraphFormat;
+use crate::core::memory::MemoryManager;
+
+#[derive(Subcommand, Debug)]
+pub enum ExportCommands {
+    /// Sessions, memories and compression lineage as a graph
+    Graph {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// dot (Graphviz) or graphml (Gephi, yEd)
+        #[arg(short, long, default_value = "dot")]
+        format: GraphFormat,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+pub fn handle(action: ExportCommands, manager: &MemoryManager, out: &mut dyn Write) -> Result<()> {
+    match action {
+        ExportCommands::Graph {
+            user,
+            format,
+            output,
+        } => {
+            let graph = manager.export_user_graph(&user)?;
+            let rendered = graph.render(format);
+
+            match output {
+                Some(file_path) => {
+                    std::fs::write(&file_path, rendered)
+                        .with_context(|| format!("Failed to write to file: {}", file_path))?;
+                    writeln!(
+                        out,
+                        "{}",
+                        format!(
+                            "✓ Exported a graph of {} nodes and {} edges to {}",
+                            graph.nodes.len(),
+                            graph.edges.len(),
+                            file_path
+                        )
+                        .green()
+                    )?;
+                }
+                None => write!(out, "{}", rendered)?,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (MemoryManager::new(database, validator), temp_dir)
+    }
+
+    fn run(manager: &MemoryManager, action: ExportCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, manager, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_export_graph() {
+        let (manager, temp_dir) = setup_test_manager();
+        let memory_id = manager
+            .save_memory(MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "session1".to_string(),
+                content: "Graphed memory".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let output = run(
+            &manager,
+            ExportCommands::Graph {
+                user: "alice".to_string(),
+                format: GraphFormat::Dot,
+                output: None,
+            },
+        );
+        assert!(output.starts_with("digraph"), "{}", output);
+        assert!(
+            output.contains(&format!("\"session:session1\" -> \"memory:{}\"", memory_id)),
+            "{}",
+            output
+        );
+
+        let path = temp_dir.path().join("alice.graphml");
+        let output = run(
+            &manager,
+            ExportCommands::Graph {
+                user: "alice".to_string(),
+                format: GraphFormat::GraphMl,
+                output: Some(path.to_string_lossy().to_string()),
+            },
+        );
+        assert!(
+            output.contains("Exported a graph of 2 nodes and 1 edges"),
+            "{}",
+            output
+        );
+        let xml = std::fs::read_to_string(path).unwrap();
+        assert!(xml.contains("<data key=\"label\">Graphed memory</data>"));
+    }
+}
@@ -0,0 +1,161 @@
+//! `memex import ...` command handler
+
+use anyhow::{Context, Result};
+use clap::Args;
+use colored::*;
+use std::io::Write;
+
+use crate::core::interop::{self, ImportDefaults, ImportFormat, DEFAULT_IMPORT_SESSION};
+use crate::core::memory::MemoryManager;
+
+/// Errors listed after an import; the rest are only counted
+const SHOWN_IMPORT_ERRORS: usize = 10;
+
+#[derive(Args, Debug)]
+pub struct ImportArgs {
+    /// Export format: chroma, langchain or mem0
+    #[arg(long)]
+    pub from: ImportFormat,
+
+    /// Export file (JSON)
+    pub path: String,
+
+    /// User for records that don't name one
+    #[arg(short, long)]
+    pub user: Option<String>,
+
+    /// Session for records that don't name one
+    #[arg(short, long, default_value = DEFAULT_IMPORT_SESSION)]
+    pub session: String,
+
+    /// Model name to store imported embeddings under (defaults to the format)
+    #[arg(long)]
+    pub embedding_model: Option<String>,
+}
+
+pub fn handle(args: ImportArgs, manager: &MemoryManager, out: &mut dyn Write) -> Result<()> {
+    let json = std::fs::read_to_string(&args.path)
+        .with_context(|| format!("Failed to read {}", args.path))?;
+    let defaults = ImportDefaults {
+        user_id: args.user,
+        session_id: args.session,
+    };
+    let records = interop::parse(args.from, &json, &defaults)?;
+
+    let model = args
+        .embedding_model
+        .unwrap_or_else(|| args.from.to_string());
+    let report = manager.import_memories(records, &model)?;
+
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "✓ Imported {} of {} {} records",
+            report.imported, report.records, args.from
+        )
+        .green()
+    )?;
+    if report.embeddings_stored > 0 {
+        writeln!(
+            out,
+            "  {} embeddings stored as {}",
+            report.embeddings_stored, model
+        )?;
+    }
+    if report.embeddings_skipped > 0 {
+        writeln!(
+            out,
+            "{}",
+            format!(
+                "  {} embeddings not stored (vector search is off or their dimension differs)",
+                report.embeddings_skipped
+            )
+            .yellow()
+        )?;
+    }
+
+    if !report.errors.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            format!("✗ {} records failed:", report.errors.len()).red()
+        )?;
+        for error in report.errors.iter().take(SHOWN_IMPORT_ERRORS) {
+            writeln!(out, "  {}", error)?;
+        }
+        if report.errors.len() > SHOWN_IMPORT_ERRORS {
+            writeln!(
+                out,
+                "  ... and {} more",
+                report.errors.len() - SHOWN_IMPORT_ERRORS
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (MemoryManager::new(database, validator), temp_dir)
+    }
+
+    fn run(manager: &MemoryManager, args: ImportArgs) -> Result<String> {
+        let mut out = Vec::new();
+        handle(args, manager, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_import_chroma_file() {
+        let (manager, temp_dir) = setup_test_manager();
+        let path = temp_dir.path().join("chroma.json");
+        std::fs::write(
+            &path,
+            r#"{"ids": ["a", "b"], "documents": ["Likes tea", ""], "embeddings": [[0.1], [0.2]]}"#,
+        )
+        .unwrap();
+        let args = |user: Option<&str>| ImportArgs {
+            from: ImportFormat::Chroma,
+            path: path.to_string_lossy().to_string(),
+            user: user.map(str::to_string),
+            session: DEFAULT_IMPORT_SESSION.to_string(),
+            embedding_model: None,
+        };
+
+        let err = run(&manager, args(None)).unwrap_err();
+        assert!(err.to_string().contains("no user_id"), "{}", err);
+
+        let output = run(&manager, args(Some("alice"))).unwrap();
+        assert!(
+            output.contains("Imported 1 of 2 chroma records"),
+            "{}",
+            output
+        );
+        assert!(output.contains("1 embeddings not stored"), "{}", output);
+        assert!(output.contains("1 records failed"), "{}", output);
+
+        let memories = manager.export_user_memories("alice").unwrap();
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].session_id, DEFAULT_IMPORT_SESSION);
+    }
+}
@@ -0,0 +1,190 @@
+//! `memex maintenance ...` command handlers
+
+use anyhow::Result;
+use chrono::Utc;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::core::maintenance::MaintenanceScheduler;
+use crate::database::maintenance_runs::{MaintenanceJob, MaintenanceRun, MaintenanceStatus};
+
+#[derive(Subcommand, Debug)]
+pub enum MaintenanceCommands {
+    /// Show each job's schedule and when it's next due
+    Schedule,
+    /// Run one job now: decay, checkpoint, analyze, stats_snapshot,
+    /// embedding_backfill, backup or wal_archive
+    Run { job: MaintenanceJob },
+    /// Run every job whose schedule is due
+    RunDue,
+    /// Show recent runs, newest first
+    History {
+        /// Only runs of this job
+        #[arg(long)]
+        job: Option<MaintenanceJob>,
+        /// Number of runs to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+pub fn handle(
+    action: MaintenanceCommands,
+    scheduler: &MaintenanceScheduler,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        MaintenanceCommands::Schedule => {
+            writeln!(out, "{}", "Maintenance Schedule:".green().bold())?;
+            for job in MaintenanceJob::ALL {
+                let Some(schedule) = scheduler.schedules().get(&job) else {
+                    writeln!(out, "  {:<20} {}", job, "on demand only".dimmed())?;
+                    continue;
+                };
+                let next = match scheduler.next_due(job)? {
+                    Some(next) => next.format("%Y-%m-%d %H:%M UTC").to_string(),
+                    None => "never".to_string(),
+                };
+                writeln!(
+                    out,
+                    "  {:<20} {:<20} next: {}",
+                    job,
+                    schedule.to_string().bright_blue(),
+                    next
+                )?;
+            }
+        }
+
+        MaintenanceCommands::Run { job } => {
+            writeln!(out, "{}", format!("🔧 Running {}...", job).green().bold())?;
+            let run = scheduler.run_job(job)?;
+            print_run(out, &run)?;
+            if let Some(detail) = &run.detail {
+                writeln!(out, "  Detail: {}", detail)?;
+            }
+        }
+
+        MaintenanceCommands::RunDue => {
+            let runs = scheduler.run_due(Utc::now())?;
+            if runs.is_empty() {
+                writeln!(out, "{}", "No maintenance jobs are due".yellow())?;
+                return Ok(());
+            }
+            for run in &runs {
+                print_run(out, run)?;
+            }
+        }
+
+        MaintenanceCommands::History { job, limit } => {
+            let runs = scheduler.history(job, limit)?;
+            if runs.is_empty() {
+                writeln!(out, "{}", "No maintenance runs recorded".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "Maintenance History:".green().bold())?;
+            for run in &runs {
+                print_run(out, run)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_run(out: &mut dyn Write, run: &MaintenanceRun) -> Result<()> {
+    let status = match run.status {
+        MaintenanceStatus::Completed => "✓ completed".green(),
+        MaintenanceStatus::Failed => "✗ failed".red(),
+        MaintenanceStatus::Skipped => "⏭ skipped".yellow(),
+    };
+    writeln!(
+        out,
+        "  {}  {:<20} {}  {}ms",
+        run.started_at.format("%Y-%m-%d %H:%M:%S"),
+        run.job,
+        status,
+        (run.completed_at - run.started_at).num_milliseconds()
+    )?;
+    if let Some(message) = &run.message {
+        writeln!(out, "      {}", message.dimmed())?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::CliContext;
+    use crate::core::MemexConfig;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn run(scheduler: &MaintenanceScheduler, action: MaintenanceCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(action, scheduler, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_run_and_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test.db")
+            .to_string_lossy()
+            .to_string();
+        let database = Database::new(DatabaseConfig {
+            path: path.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let context = CliContext::new(
+            MemexConfig {
+                database_path: path,
+                ..Default::default()
+            },
+            database,
+        );
+        let scheduler = context.maintenance_scheduler();
+
+        let output = run(&scheduler, MaintenanceCommands::Schedule).unwrap();
+        assert!(output.contains("decay"));
+        assert!(output.contains("@every 24h"));
+        assert!(output.contains("@hourly"));
+        assert!(output.contains("on demand only"));
+
+        let output = run(&scheduler, MaintenanceCommands::RunDue).unwrap();
+        assert!(output.contains("No maintenance jobs are due"));
+
+        let output = run(
+            &scheduler,
+            MaintenanceCommands::Run {
+                job: MaintenanceJob::Checkpoint,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("completed"));
+        assert!(output.contains("wal_size_before"));
+
+        let output = run(
+            &scheduler,
+            MaintenanceCommands::History {
+                job: Some(MaintenanceJob::Decay),
+                limit: 20,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("No maintenance runs recorded"));
+        let output = run(
+            &scheduler,
+            MaintenanceCommands::History {
+                job: None,
+                limit: 20,
+            },
+        )
+        .unwrap();
+        assert!(output.contains("checkpoint"));
+    }
+}
@@ -0,0 +1,2587 @@
+//! `memex memory ...` command handlers
+
+use anyhow::{Context, Result};
+use clap::{Args, Subcommand};
+use colored::*;
+use std::collections::HashMap;
+use std::io::Write;
+
+use crate::cli::{
+    format_bytes, format_duration, parse_duration, parse_key_value, print_memory_item,
+    InteractiveCli,
+};
+use crate::core::conflicts::ConflictReason;
+use crate::core::decay::DecayEngine;
+use crate::core::interop::DocumentFormat;
+use crate::core::memory::{DeleteOptions, MemoryManager, MemoryUpdate};
+use crate::core::timeline::{TimeBucket, Timeline};
+use crate::core::trends::KeywordTrends;
+use crate::database::models::*;
+use crate::display::truncate_graphemes;
+use crate::wire::WireFormat;
+
+/// Matching IDs listed by `memory delete --dry-run`; the rest are only counted
+const SHOWN_DELETED_IDS: usize = 20;
+
+/// Conflicting IDs listed by `memory migrate-metadata`; the rest are only
+/// counted
+const SHOWN_CONFLICT_IDS: usize = 20;
+
+#[derive(Subcommand, Debug)]
+pub enum MemoryCommands {
+    /// Save a new memory
+    Save {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Session ID
+        #[arg(short, long)]
+        session: String,
+        /// Memory content
+        content: String,
+        /// Importance (0.0-1.0)
+        #[arg(short, long)]
+        importance: Option<f32>,
+        /// How sure you are the memory is true (0.0-1.0)
+        #[arg(long)]
+        confidence: Option<f32>,
+        /// TTL in hours
+        #[arg(short, long)]
+        ttl: Option<u32>,
+        /// Metadata as JSON
+        #[arg(short, long)]
+        metadata: Option<String>,
+        /// Content format: text, json or markdown
+        #[arg(long, default_value = "text")]
+        content_type: ContentType,
+        /// Save as a draft, recalled only once confirmed with `memory state`
+        #[arg(long)]
+        draft: bool,
+        /// Where the memory came from: user, agent, tool or import
+        #[arg(long)]
+        source_type: Option<SourceType>,
+        /// URL, message ID or file path within the source
+        #[arg(long)]
+        source_ref: Option<String>,
+        /// Agent or tool saving the memory
+        #[arg(long)]
+        ingested_by: Option<String>,
+    },
+    /// Recall memories
+    Recall {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Search keywords
+        #[arg(short, long)]
+        keywords: Option<String>,
+        /// Rank by meaning against this text; needs vector search and an
+        /// embedding command, and otherwise searches its words as keywords
+        #[arg(long)]
+        semantic: Option<String>,
+        /// Session ID filter
+        #[arg(short, long)]
+        session: Option<String>,
+        /// Minimum importance
+        #[arg(long)]
+        min_importance: Option<f32>,
+        /// Minimum confidence
+        #[arg(long)]
+        min_confidence: Option<f32>,
+        /// Maximum confidence, e.g. to review unverified memories
+        #[arg(long)]
+        max_confidence: Option<f32>,
+        /// Limit results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        /// Offset for pagination
+        #[arg(long, default_value = "0")]
+        offset: usize,
+        /// Only memories with this metadata value (repeatable)
+        #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        meta: Vec<(String, String)>,
+        /// Only JSON memories whose field equals the value, given as JSON
+        /// or a bare string (repeatable)
+        #[arg(long = "json-field", value_name = "PATH=VALUE", value_parser = parse_key_value)]
+        json_fields: Vec<(String, String)>,
+        /// Only memories of this content type
+        #[arg(long)]
+        content_type: Option<ContentType>,
+        /// Only memories with at least this many bytes of content
+        #[arg(long)]
+        min_length: Option<usize>,
+        /// Only memories with at most this many bytes of content
+        #[arg(long)]
+        max_length: Option<usize>,
+        /// Fall back to similarly spelled words if nothing matches exactly
+        #[arg(long)]
+        fuzzy: bool,
+        /// Only memories that expire within this long, e.g. 12h or 2d
+        #[arg(long, value_parser = parse_duration)]
+        expiring_within: Option<chrono::Duration>,
+        /// Recall memories in this state: draft, active or archived
+        /// (repeatable; active only by default)
+        #[arg(long = "state")]
+        states: Vec<MemoryState>,
+        /// Only memories from this kind of source (repeatable)
+        #[arg(long = "source-type")]
+        source_types: Vec<SourceType>,
+        /// Only memories with this source reference
+        #[arg(long)]
+        source_ref: Option<String>,
+        /// Only memories saved by this agent, tool or API key
+        #[arg(long)]
+        ingested_by: Option<String>,
+        #[command(flatten)]
+        exclusions: Box<ExclusionArgs>,
+        /// Show why each memory ranks where it does
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Print the SQL, redacted parameters and SQLite query plan a recall
+    /// filter would use, without running it
+    ExplainFilter {
+        /// Recall filter as JSON, or @file to read it from a file
+        #[arg(long)]
+        filter: String,
+    },
+    /// Search memories with full-text search
+    Search {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Search query
+        query: String,
+        /// Limit results (per session with --by-session)
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        /// Group results by session, with each session's match count
+        #[arg(long)]
+        by_session: bool,
+        /// Show why each memory ranks where it does
+        #[arg(long, conflicts_with = "by_session")]
+        explain: bool,
+    },
+    /// Get memory by ID
+    Get {
+        /// Memory ID
+        id: String,
+    },
+    /// Update memory
+    Update {
+        /// Memory ID
+        id: String,
+        /// New content
+        #[arg(short, long)]
+        content: Option<String>,
+        /// Text appended to the content
+        #[arg(long)]
+        append: Option<String>,
+        /// New importance
+        #[arg(short, long)]
+        importance: Option<f32>,
+        /// New confidence
+        #[arg(long)]
+        confidence: Option<f32>,
+        /// New TTL in hours, counted from now
+        #[arg(short, long, conflicts_with = "clear_ttl")]
+        ttl: Option<u32>,
+        /// Remove the TTL so the memory never expires
+        #[arg(long)]
+        clear_ttl: bool,
+        /// Hours to add to the TTL
+        #[arg(long, value_name = "HOURS", conflicts_with = "clear_ttl")]
+        extend_ttl: Option<u32>,
+        /// Set a metadata key (repeatable)
+        #[arg(long = "set-meta", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        set_meta: Vec<(String, String)>,
+        /// Remove a metadata key (repeatable)
+        #[arg(long = "unset-meta", value_name = "KEY")]
+        unset_meta: Vec<String>,
+        /// Only update if the memory is still at this version
+        #[arg(long)]
+        expected_version: Option<i64>,
+    },
+    /// Delete a memory, or every memory matching --user/--session filters
+    Delete {
+        /// Memory ID
+        #[arg(
+            required_unless_present_any = ["user", "session"],
+            conflicts_with_all = ["user", "session", "since", "until", "meta", "dry_run", "max"]
+        )]
+        id: Option<String>,
+        #[command(flatten)]
+        filter: DeleteFilterArgs,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Restore the memories a compressed memory replaced
+    Decompress {
+        /// Compressed memory ID
+        id: String,
+    },
+    /// List expired memories that cleanup hasn't deleted yet
+    Expired {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Bring back an expired memory before cleanup deletes it
+    Restore {
+        /// Memory ID
+        id: String,
+        /// New TTL in hours; defaults to the memory's old TTL
+        #[arg(short, long)]
+        ttl: Option<u32>,
+    },
+    /// Confirm a draft, archive a memory or bring an archived one back
+    State {
+        /// Memory ID
+        id: String,
+        /// New state: active or archived
+        state: MemoryState,
+    },
+    /// List memories that may contradict a memory
+    Conflicts {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Memory ID
+        id: String,
+    },
+    /// Confirm a conflict (contradicts) or dismiss it (compatible)
+    Link {
+        /// Memory ID
+        id: String,
+        /// ID of the other memory
+        other: String,
+        /// Link type: contradicts or compatible
+        link_type: LinkType,
+        /// Remove the link instead
+        #[arg(long)]
+        remove: bool,
+    },
+    /// Export memories for a user
+    Export {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Output file; printed to stdout if omitted
+        #[arg(short, long)]
+        output: Option<String>,
+        /// Write LangChain or LlamaIndex documents instead of memex memories
+        #[arg(long)]
+        format: Option<DocumentFormat>,
+        /// File encoding: json, or cbor for a compact binary file
+        #[arg(long, default_value = "json")]
+        encoding: WireFormat,
+    },
+    /// Show memory statistics for a user
+    Stats {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Show rising and falling keywords instead
+        #[arg(long)]
+        trends: bool,
+        /// Length of each trend window, e.g. 1d or 2w
+        #[arg(long, default_value = "7d", value_parser = parse_duration, requires = "trends")]
+        window: chrono::Duration,
+    },
+    /// Group a user's memories into topics
+    Clusters {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Number of clusters (picked automatically if omitted)
+        #[arg(short, long)]
+        k: Option<usize>,
+    },
+    /// Show when memories were saved, by day or week, with highlights
+    Timeline {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// How far back to go, e.g. 30d or 12w
+        #[arg(long, default_value = "30d", value_parser = parse_duration)]
+        since: chrono::Duration,
+        /// Bucket size: day or week
+        #[arg(short, long, default_value = "day")]
+        bucket: TimeBucket,
+        /// Session ID filter
+        #[arg(short, long)]
+        session: Option<String>,
+        /// Only memories matching these keywords
+        #[arg(short, long)]
+        keywords: Option<String>,
+    },
+    /// Manage JSON Schemas for JSON memories, per namespace
+    Schema {
+        #[command(subcommand)]
+        action: SchemaCommands,
+    },
+    /// Manage templates for memories saved with the same layout
+    Template {
+        #[command(subcommand)]
+        action: TemplateCommands,
+    },
+    /// Move a metadata key to another and/or rename its values across
+    /// every memory that has it
+    MigrateMetadata {
+        /// Key to migrate
+        #[arg(long)]
+        from_key: String,
+        /// Key to move the values to (default: keep them under --from-key)
+        #[arg(long)]
+        to_key: Option<String>,
+        /// Replace this value on the way (repeatable); tags are mapped one
+        /// by one
+        #[arg(long = "map", value_name = "OLD=NEW", value_parser = parse_key_value)]
+        map: Vec<(String, String)>,
+        /// Only this user's memories
+        #[arg(short, long)]
+        user: Option<String>,
+        /// Memories migrated per transaction
+        #[arg(short, long, default_value = "500")]
+        batch_size: usize,
+        /// Count the memories that would change without changing them
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
+
+/// Memories for `memory recall` to leave out
+#[derive(Args, Debug, Default)]
+pub struct ExclusionArgs {
+    /// Leave out memories containing this word (repeatable)
+    #[arg(long = "exclude", value_name = "WORD")]
+    words: Vec<String>,
+    /// Leave out memories from this session (repeatable)
+    #[arg(long = "exclude-session", value_name = "SESSION")]
+    sessions: Vec<String>,
+    /// Leave out memories with this tag (repeatable)
+    #[arg(long = "exclude-tag", value_name = "TAG")]
+    tags: Vec<String>,
+}
+
+/// Filter and safeguards for deleting many memories with `memory delete`
+#[derive(Args, Debug, Default)]
+pub struct DeleteFilterArgs {
+    /// Delete this user's memories
+    #[arg(short, long)]
+    user: Option<String>,
+    /// Delete memories from this session
+    #[arg(short, long)]
+    session: Option<String>,
+    /// Only memories created within this long ago (e.g. 30m, 12h, 7d, 2w)
+    #[arg(long, value_parser = parse_duration)]
+    since: Option<chrono::Duration>,
+    /// Only memories created at least this long ago
+    #[arg(long, value_parser = parse_duration)]
+    until: Option<chrono::Duration>,
+    /// Only memories with this metadata value (repeatable)
+    #[arg(long = "meta", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+    meta: Vec<(String, String)>,
+    /// List the memories a filter matches without deleting them
+    #[arg(long)]
+    dry_run: bool,
+    /// Delete nothing if more memories than this match
+    #[arg(long)]
+    max: Option<usize>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SchemaCommands {
+    /// List registered schemas
+    List,
+    /// Register a namespace's schema, replacing any existing one
+    Set {
+        /// Namespace, matched against the memories' `namespace` metadata
+        namespace: String,
+        /// JSON Schema, or @path to read it from a file
+        schema: String,
+    },
+    /// Remove a namespace's schema
+    Remove {
+        /// Namespace
+        namespace: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum TemplateCommands {
+    /// List templates
+    List,
+    /// Add a template, replacing any existing one with the same ID
+    Set {
+        /// Template ID
+        id: String,
+        /// Content, with {{name}} placeholders
+        content: String,
+        /// Metadata key, whose value may also use placeholders (repeatable)
+        #[arg(long = "set-meta", value_name = "KEY=VALUE", value_parser = parse_key_value)]
+        set_meta: Vec<(String, String)>,
+        /// Importance of memories saved from the template (0.0-1.0)
+        #[arg(short, long, default_value = "0.5")]
+        importance: f32,
+        /// TTL in hours of memories saved from the template
+        #[arg(short, long)]
+        ttl: Option<u32>,
+    },
+    /// Remove a template
+    Remove {
+        /// Template ID
+        id: String,
+    },
+    /// Fill in a template and save the result as a memory
+    Use {
+        /// Template ID
+        id: String,
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Session ID
+        #[arg(short, long)]
+        session: String,
+        /// Placeholder value (repeatable)
+        #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_key_value)]
+        vars: Vec<(String, String)>,
+    },
+}
+
+pub fn handle(
+    action: MemoryCommands,
+    manager: &MemoryManager,
+    decay: &DecayEngine,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        MemoryCommands::Save {
+            user,
+            session,
+            content,
+            importance,
+            confidence,
+            ttl,
+            metadata,
+            content_type,
+            draft,
+            source_type,
+            source_ref,
+            ingested_by,
+        } => {
+            let metadata_map = if let Some(meta) = metadata {
+                serde_json::from_str(&meta).context("Invalid metadata JSON")?
+            } else {
+                HashMap::new()
+            };
+
+            let memory = MemoryItem {
+                user_id: user.clone(),
+                session_id: session.clone(),
+                content: content.clone(),
+                importance: importance.unwrap_or(0.5).clamp(0.0, 1.0),
+                confidence: confidence.unwrap_or(1.0).clamp(0.0, 1.0),
+                ttl_hours: ttl,
+                metadata: metadata_map,
+                content_type,
+                state: if draft {
+                    MemoryState::Draft
+                } else {
+                    MemoryState::Active
+                },
+                source_type,
+                source_ref,
+                ingested_by,
+                ..Default::default()
+            };
+
+            let receipt = manager.save_memory_with_receipt(memory)?;
+
+            if draft {
+                writeln!(
+                    out,
+                    "{}",
+                    "✓ Draft saved; confirm it to make it recallable".green()
+                )?;
+            } else {
+                writeln!(out, "{}", "✓ Memory saved successfully".green())?;
+            }
+            writeln!(out, "  ID: {}", receipt.id.bright_blue())?;
+            writeln!(out, "  User: {}", user)?;
+            if receipt.session_created {
+                writeln!(out, "  Session: {} {}", session, "(new)".dimmed())?;
+            } else {
+                writeln!(out, "  Session: {}", session)?;
+            }
+            writeln!(out, "  Content: {}", truncate_graphemes(&content, 50))?;
+        }
+
+        MemoryCommands::Recall {
+            user,
+            keywords,
+            semantic,
+            session,
+            min_importance,
+            min_confidence,
+            max_confidence,
+            limit,
+            offset,
+            meta,
+            json_fields,
+            content_type,
+            min_length,
+            max_length,
+            fuzzy,
+            expiring_within,
+            states,
+            source_types,
+            source_ref,
+            ingested_by,
+            exclusions,
+            explain,
+        } => {
+            let keywords_vec =
+                keywords.map(|k| k.split_whitespace().map(|s| s.to_string()).collect());
+            // Values that aren't valid JSON are taken as strings
+            let json_fields = json_fields
+                .into_iter()
+                .map(|(path, value)| {
+                    let value =
+                        serde_json::from_str(&value).unwrap_or(serde_json::Value::String(value));
+                    (path, value)
+                })
+                .collect();
+
+            let filter = QueryFilter {
+                user_id: Some(user),
+                session_id: session,
+                keywords: keywords_vec,
+                min_importance,
+                min_confidence,
+                max_confidence,
+                limit: Some(limit),
+                offset: Some(offset),
+                content_type,
+                metadata: meta.into_iter().collect(),
+                json_fields,
+                fuzzy,
+                expiring_within,
+                states,
+                source_types,
+                source_ref,
+                ingested_by,
+                explain,
+                semantic_query: semantic,
+                exclude_keywords: exclusions.words,
+                exclude_session_ids: exclusions.sessions,
+                exclude_tags: exclusions.tags,
+                min_content_length: min_length,
+                max_content_length: max_length,
+                ..Default::default()
+            };
+
+            let response = manager.recall_memories(filter)?;
+
+            if response.data.is_empty() {
+                writeln!(out, "{}", "No memories found".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "Found {} memories (page {}/{})",
+                    response.data.len(),
+                    response.page + 1,
+                    response.total_pages
+                )
+                .green()
+            )?;
+            writeln!(
+                out,
+                "{}",
+                format!("Total: {} memories", response.total_count).dimmed()
+            )?;
+            writeln!(out)?;
+
+            for (i, memory) in response.data.iter().enumerate() {
+                print_memory_item(out, memory, i + 1 + offset)?;
+                if i < response.data.len() - 1 {
+                    writeln!(out, "{}", "─".repeat(80).dimmed())?;
+                }
+            }
+
+            if response.has_next {
+                writeln!(
+                    out,
+                    "\n{}",
+                    format!("Use --offset {} to see more results", offset + limit).dimmed()
+                )?;
+            }
+        }
+
+        MemoryCommands::Search {
+            user,
+            query,
+            limit,
+            by_session: true,
+            ..
+        } => {
+            let groups = manager.search_grouped_by_session(&user, &query, limit)?;
+            if groups.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories found for query: '{}'", query).yellow()
+                )?;
+                return Ok(());
+            }
+
+            for group in &groups {
+                writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "{} match{} in {} ({})",
+                        group.match_count,
+                        if group.match_count == 1 { "" } else { "es" },
+                        group.session.name.as_deref().unwrap_or("(unnamed)"),
+                        group.session.id
+                    )
+                    .green()
+                    .bold()
+                )?;
+                for (i, memory) in group.memories.iter().enumerate() {
+                    print_memory_item(out, memory, i + 1)?;
+                }
+                writeln!(out)?;
+            }
+        }
+
+        MemoryCommands::Search {
+            user,
+            query,
+            limit,
+            explain,
+            ..
+        } => {
+            let response = if explain {
+                manager.search_memories_explained(&user, &query, Some(limit), Some(0))?
+            } else {
+                manager.search_memories(&user, &query, Some(limit), Some(0))?
+            };
+
+            if response.data.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories found for query: '{}'", query).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("Search results for '{}' ({})", query, response.data.len()).green()
+            )?;
+            writeln!(out)?;
+
+            for (i, memory) in response.data.iter().enumerate() {
+                print_memory_item(out, memory, i + 1)?;
+                if i < response.data.len() - 1 {
+                    writeln!(out, "{}", "─".repeat(80).dimmed())?;
+                }
+            }
+        }
+
+        MemoryCommands::ExplainFilter { filter } => {
+            let filter = match filter.strip_prefix('@') {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path))?,
+                None => filter,
+            };
+            let filter = crate::ffi::filter_arg(&filter, "--filter", false)?;
+            let plan = manager.explain_recall(filter)?;
+
+            writeln!(out, "{}", "SQL".green().bold())?;
+            writeln!(out, "  {}", plan.sql)?;
+            writeln!(out, "{}", "Parameters".green().bold())?;
+            if plan.params.is_empty() {
+                writeln!(out, "  {}", "(none)".dimmed())?;
+            }
+            for param in &plan.params {
+                writeln!(out, "  {}", param)?;
+            }
+            writeln!(out, "{}", "Query plan".green().bold())?;
+            for step in plan.query_plan.lines() {
+                writeln!(out, "  {}", step)?;
+            }
+
+            writeln!(out)?;
+            if plan.full_scans.is_empty() {
+                writeln!(out, "{}", "✓ No full table scans".green())?;
+            } else {
+                for scan in &plan.full_scans {
+                    writeln!(out, "{}", format!("⚠ Full scan: {}", scan).yellow())?;
+                }
+            }
+        }
+
+        MemoryCommands::Get { id } => match manager.get_memory(&id)? {
+            Some(memory) => {
+                writeln!(out, "{}", "Memory Details".green().bold())?;
+                print_memory_item(out, &memory, 1)?;
+                match (memory.source_type, &memory.source_ref) {
+                    (Some(source_type), Some(source_ref)) => {
+                        writeln!(out, "  Source: {} ({})", source_type, source_ref)?
+                    }
+                    (Some(source_type), None) => writeln!(out, "  Source: {}", source_type)?,
+                    (None, Some(source_ref)) => writeln!(out, "  Source: {}", source_ref)?,
+                    (None, None) => writeln!(out, "  Source: {}", "unknown".dimmed())?,
+                }
+                if let Some(ingested_by) = &memory.ingested_by {
+                    writeln!(out, "  Ingested by: {}", ingested_by)?;
+                }
+            }
+            None => {
+                writeln!(out, "{}", format!("Memory not found: {}", id).yellow())?;
+            }
+        },
+
+        MemoryCommands::Update {
+            id,
+            content,
+            append,
+            importance,
+            confidence,
+            ttl,
+            clear_ttl,
+            extend_ttl,
+            set_meta,
+            unset_meta,
+            expected_version,
+        } => {
+            let metadata_patch = set_meta
+                .into_iter()
+                .map(|(key, value)| (key, Some(value)))
+                .chain(unset_meta.into_iter().map(|key| (key, None)))
+                .collect();
+            let update = MemoryUpdate {
+                content,
+                content_append: append,
+                importance,
+                confidence,
+                ttl_hours: if clear_ttl { Some(None) } else { ttl.map(Some) },
+                extend_ttl_hours: extend_ttl,
+                metadata_patch,
+                expected_version,
+                ..Default::default()
+            };
+
+            if manager.update_memory(&id, update)? {
+                writeln!(out, "{}", "✓ Memory updated successfully".green())?;
+            } else {
+                writeln!(out, "{}", format!("Memory not found: {}", id).yellow())?;
+            }
+        }
+
+        MemoryCommands::Delete {
+            id: Some(id), yes, ..
+        } => {
+            if !yes && !InteractiveCli::confirm(&format!("Delete memory {}?", id), false)? {
+                writeln!(out, "Cancelled")?;
+                return Ok(());
+            }
+
+            if manager.delete_memory(&id)? {
+                writeln!(out, "{}", "✓ Memory deleted successfully".green())?;
+            } else {
+                writeln!(out, "{}", format!("Memory not found: {}", id).yellow())?;
+            }
+        }
+
+        MemoryCommands::Delete {
+            id: None,
+            filter:
+                DeleteFilterArgs {
+                    user,
+                    session,
+                    since,
+                    until,
+                    meta,
+                    dry_run,
+                    max,
+                },
+            yes,
+        } => {
+            let now = chrono::Utc::now();
+            let filter = QueryFilter {
+                user_id: user,
+                session_id: session,
+                date_from: since.map(|since| now - since),
+                date_to: until.map(|until| now - until),
+                metadata: meta.into_iter().collect(),
+                ..Default::default()
+            };
+
+            // Count the matches first so the confirmation names a number,
+            // then refuse to delete more than that
+            let preview = manager.delete_by_filter(
+                filter.clone(),
+                DeleteOptions {
+                    dry_run: true,
+                    max_affected: max,
+                },
+            )?;
+            if preview.ids.is_empty() {
+                writeln!(out, "{}", "No memories match".yellow())?;
+                return Ok(());
+            }
+
+            if dry_run {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("Would delete {} memories:", preview.count()).yellow()
+                )?;
+                for id in preview.ids.iter().take(SHOWN_DELETED_IDS) {
+                    writeln!(out, "  {}", id)?;
+                }
+                if preview.count() > SHOWN_DELETED_IDS {
+                    writeln!(
+                        out,
+                        "  ... and {} more",
+                        preview.count() - SHOWN_DELETED_IDS
+                    )?;
+                }
+                return Ok(());
+            }
+
+            if !yes
+                && !InteractiveCli::confirm(
+                    &format!("Delete {} memories?", preview.count()),
+                    false,
+                )?
+            {
+                writeln!(out, "Cancelled")?;
+                return Ok(());
+            }
+
+            let report = manager.delete_by_filter(
+                filter,
+                DeleteOptions {
+                    dry_run: false,
+                    max_affected: Some(preview.count()),
+                },
+            )?;
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Deleted {} memories", report.count()).green()
+            )?;
+        }
+
+        MemoryCommands::Decompress { id } => {
+            let restored = decay.decompress(&id)?;
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Restored {} memories from {}", restored.len(), id).green()
+            )?;
+            for restored_id in &restored {
+                writeln!(out, "  {}", restored_id)?;
+            }
+        }
+
+        MemoryCommands::Expired { user } => {
+            let memories = manager.get_expired_memories(&user)?;
+            if memories.is_empty() {
+                writeln!(out, "{}", "No expired memories".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("{} expired memories awaiting cleanup", memories.len()).green()
+            )?;
+            writeln!(out)?;
+            for (i, memory) in memories.iter().enumerate() {
+                print_memory_item(out, memory, i + 1)?;
+                if i < memories.len() - 1 {
+                    writeln!(out, "{}", "─".repeat(80).dimmed())?;
+                }
+            }
+        }
+
+        MemoryCommands::Restore { id, ttl } => {
+            if manager.restore_expired_memory(&id, ttl)? {
+                writeln!(out, "{}", format!("✓ Restored memory {}", id).green())?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("Expired memory not found: {}", id).yellow()
+                )?;
+            }
+        }
+
+        MemoryCommands::State { id, state } => match manager.set_memory_state(&id, state)? {
+            Some(previous) if previous == state => {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("Memory {} is already {}", id, state).yellow()
+                )?;
+            }
+            Some(previous) => {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Memory {} moved from {} to {}", id, previous, state).green()
+                )?;
+            }
+            None => {
+                writeln!(out, "{}", format!("Memory not found: {}", id).yellow())?;
+            }
+        },
+
+        MemoryCommands::Conflicts { user, id } => {
+            let Some(conflicts) = manager.find_conflicts(&user, &id)? else {
+                writeln!(out, "{}", format!("Memory not found: {}", id).yellow())?;
+                return Ok(());
+            };
+            if conflicts.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No conflicts found for memory {}", id).green()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("{} possible conflicts with memory {}", conflicts.len(), id)
+                    .yellow()
+                    .bold()
+            )?;
+            for (index, conflict) in conflicts.iter().enumerate() {
+                writeln!(out)?;
+                print_memory_item(out, &conflict.memory, index + 1)?;
+                let mut reasons: Vec<String> = conflict
+                    .reasons
+                    .iter()
+                    .map(|reason| match reason {
+                        ConflictReason::Negation => "negation".to_string(),
+                        ConflictReason::Metadata {
+                            key,
+                            value,
+                            other_value,
+                        } => format!("{}: {} vs {}", key, value, other_value),
+                    })
+                    .collect();
+                if conflict.confirmed {
+                    reasons.insert(0, "confirmed".to_string());
+                }
+                writeln!(
+                    out,
+                    "   {} {} (similarity {:.2})",
+                    "Conflict:".red(),
+                    reasons.join(", "),
+                    conflict.similarity
+                )?;
+            }
+        }
+
+        MemoryCommands::Link {
+            id,
+            other,
+            link_type,
+            remove,
+        } => {
+            let changed = if remove {
+                manager.unlink_memories(&id, &other, link_type)?
+            } else {
+                manager.link_memories(&id, &other, link_type)?
+            };
+            let message = match (remove, changed) {
+                (false, true) => format!("✓ Linked {} and {} as {}", id, other, link_type).green(),
+                (true, true) => format!(
+                    "✓ Removed the {} link between {} and {}",
+                    link_type, id, other
+                )
+                .green(),
+                (false, false) => format!(
+                    "Nothing linked: {} and {} are already {} or don't both exist",
+                    id, other, link_type
+                )
+                .yellow(),
+                (true, false) => {
+                    format!("No {} link between {} and {}", link_type, id, other).yellow()
+                }
+            };
+            writeln!(out, "{}", message)?;
+        }
+
+        MemoryCommands::Export {
+            user,
+            output,
+            format,
+            encoding,
+        } => {
+            let (count, data) = match format {
+                Some(format) => {
+                    let documents = manager.export_user_documents(&user, format)?;
+                    (documents.len(), encode_export(encoding, &documents))
+                }
+                None => {
+                    let memories = manager.export_user_memories(&user)?;
+                    (memories.len(), encode_export(encoding, &memories))
+                }
+            };
+            let data = data.context("Failed to serialize memories")?;
+
+            match output {
+                Some(file_path) => {
+                    std::fs::write(&file_path, data)
+                        .with_context(|| format!("Failed to write to file: {}", file_path))?;
+                    writeln!(
+                        out,
+                        "{}",
+                        format!("✓ Exported {} memories to {}", count, file_path).green()
+                    )?;
+                }
+                None => {
+                    out.write_all(&data)?;
+                    if encoding == WireFormat::Json {
+                        writeln!(out)?;
+                    }
+                }
+            }
+        }
+
+        MemoryCommands::Stats {
+            user,
+            trends: true,
+            window,
+        } => {
+            let trends = manager.keyword_trends(&user, window)?;
+            print_keyword_trends(out, &trends, window)?;
+        }
+
+        MemoryCommands::Stats { user, .. } => {
+            let stats = manager.get_user_memory_stats(&user)?;
+
+            writeln!(
+                out,
+                "{}",
+                format!("Memory Statistics for {}", user).green().bold()
+            )?;
+            writeln!(
+                out,
+                "Total memories: {}",
+                stats.total_memories.to_string().bright_blue()
+            )?;
+            writeln!(out, "Average importance: {:.2}", stats.avg_importance)?;
+            writeln!(out, "Content size: {}", format_bytes(stats.content_bytes))?;
+
+            if let Some(oldest) = stats.oldest_memory {
+                writeln!(out, "Oldest memory: {}", oldest.format("%Y-%m-%d %H:%M"))?;
+            }
+
+            if let Some(newest) = stats.newest_memory {
+                writeln!(out, "Newest memory: {}", newest.format("%Y-%m-%d %H:%M"))?;
+            }
+
+            writeln!(out, "\n{}", "Importance Distribution:".bold())?;
+            for (category, count) in &stats.importance_distribution {
+                writeln!(out, "  {}: {}", category, count)?;
+            }
+
+            writeln!(out, "\n{}", "Age Distribution:".bold())?;
+            for (category, count) in &stats.age_distribution {
+                writeln!(out, "  {}: {}", category, count)?;
+            }
+
+            if stats.total_memories > 0 {
+                print_value_distribution(
+                    out,
+                    "Importance",
+                    &stats.importance_percentiles,
+                    |value| format!("{:.1}", value),
+                )?;
+                print_value_distribution(
+                    out,
+                    "Content Size",
+                    &stats.content_bytes_percentiles,
+                    |value| format_bytes(value as u64),
+                )?;
+            }
+
+            if !stats.session_storage.is_empty() {
+                writeln!(
+                    out,
+                    "\n{} {}",
+                    "Storage by Session:".bold(),
+                    format!(
+                        "({} tokens in all, by {})",
+                        stats.total_tokens, stats.tokenizer
+                    )
+                    .dimmed()
+                )?;
+                for usage in &stats.session_storage {
+                    let session_id = usage.session_id.as_deref().unwrap_or_default();
+                    writeln!(
+                        out,
+                        "  {}: {} in {} memories, {} tokens",
+                        session_id,
+                        format_bytes(usage.content_bytes),
+                        usage.memory_count,
+                        stats.session_tokens.get(session_id).copied().unwrap_or(0)
+                    )?;
+                }
+            }
+        }
+
+        MemoryCommands::Clusters { user, k } => {
+            let report = manager.cluster_memories(&user, k)?;
+
+            if report.clusters.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories found for user: {}", user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "{} topics in {} memories of {} (by {})",
+                    report.clusters.len(),
+                    report.memory_count,
+                    user,
+                    report.method
+                )
+                .green()
+                .bold()
+            )?;
+
+            for cluster in &report.clusters {
+                writeln!(
+                    out,
+                    "\n{} ({} memories)",
+                    cluster.label.bright_blue().bold(),
+                    cluster.size
+                )?;
+                if !cluster.key_terms.is_empty() {
+                    writeln!(
+                        out,
+                        "  Key terms: {}",
+                        cluster.key_terms.join(", ").bright_yellow()
+                    )?;
+                }
+                for memory in &cluster.representatives {
+                    writeln!(out, "  • {}", truncate_graphemes(&memory.content, 80))?;
+                }
+            }
+        }
+
+        MemoryCommands::Timeline {
+            user,
+            since,
+            bucket,
+            session,
+            keywords,
+        } => {
+            let now = chrono::Utc::now();
+            let filter = QueryFilter {
+                session_id: session,
+                keywords: keywords.map(|k| k.split_whitespace().map(|s| s.to_string()).collect()),
+                ..QueryFilter::date_range(&user, now - since, now)
+            };
+            let timeline = manager.recall_timeline(filter, bucket)?;
+            if timeline.buckets.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories found for user: {}", user).yellow()
+                )?;
+                return Ok(());
+            }
+            print_timeline(out, &timeline, &user, now - since, now)?;
+        }
+
+        MemoryCommands::Schema { action } => handle_schemas(action, manager, out)?,
+        MemoryCommands::Template { action } => handle_templates(action, manager, out)?,
+
+        MemoryCommands::MigrateMetadata {
+            from_key,
+            to_key,
+            map,
+            user,
+            batch_size,
+            dry_run,
+        } => {
+            let migration = MetadataMigration {
+                user_id: user,
+                from_key,
+                to_key,
+                value_map: map.into_iter().collect(),
+                dry_run,
+            };
+
+            let mut progress_error = None;
+            let progress = manager.migrate_metadata(&migration, batch_size, |progress| {
+                if let Err(e) = writeln!(
+                    out,
+                    "  Migrated {} of {} scanned",
+                    progress.migrated, progress.scanned
+                ) {
+                    progress_error.get_or_insert(e);
+                }
+            })?;
+            if let Some(e) = progress_error {
+                return Err(e.into());
+            }
+
+            if progress.scanned == 0 {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories have metadata key `{}`", migration.from_key).yellow()
+                )?;
+            } else if dry_run {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("Would migrate {} memories", progress.migrated).yellow()
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Migrated {} memories", progress.migrated).green()
+                )?;
+            }
+            if !progress.conflicts.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "{} memories already have a different `{}` and were left alone:",
+                        progress.conflicts.len(),
+                        migration.to_key.as_deref().unwrap_or(&migration.from_key)
+                    )
+                    .yellow()
+                )?;
+                for id in progress.conflicts.iter().take(SHOWN_CONFLICT_IDS) {
+                    writeln!(out, "  {}", id)?;
+                }
+                if progress.conflicts.len() > SHOWN_CONFLICT_IDS {
+                    writeln!(
+                        out,
+                        "  ... and {} more",
+                        progress.conflicts.len() - SHOWN_CONFLICT_IDS
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_schemas(
+    action: SchemaCommands,
+    manager: &MemoryManager,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        SchemaCommands::List => {
+            let schemas = manager.list_json_schemas()?;
+            if schemas.is_empty() {
+                writeln!(out, "No JSON schemas; JSON memories only need to parse")?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "JSON Schemas:".green().bold())?;
+            for schema in &schemas {
+                writeln!(
+                    out,
+                    "  {} (updated {})",
+                    schema.namespace.bright_blue(),
+                    schema.updated_at.format("%Y-%m-%d %H:%M")
+                )?;
+                writeln!(
+                    out,
+                    "    {}",
+                    truncate_graphemes(&schema.schema.to_string(), 70).dimmed()
+                )?;
+            }
+        }
+
+        SchemaCommands::Set { namespace, schema } => {
+            let schema = match schema.strip_prefix('@') {
+                Some(path) => std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read {}", path))?,
+                None => schema,
+            };
+            let schema = serde_json::from_str(&schema).context("Invalid schema JSON")?;
+            manager.register_json_schema(&namespace, schema)?;
+            writeln!(
+                out,
+                "{}",
+                format!("✓ JSON memories in {} must now match the schema", namespace).green()
+            )?;
+        }
+
+        SchemaCommands::Remove { namespace } => {
+            if !manager.remove_json_schema(&namespace)? {
+                return Err(anyhow::anyhow!(
+                    "No JSON schema for namespace: {}",
+                    namespace
+                ));
+            }
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Removed JSON schema for {}", namespace).green()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle_templates(
+    action: TemplateCommands,
+    manager: &MemoryManager,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        TemplateCommands::List => {
+            let templates = manager.list_templates()?;
+            if templates.is_empty() {
+                writeln!(out, "No templates")?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "Templates:".green().bold())?;
+            for template in &templates {
+                writeln!(
+                    out,
+                    "  {} [⭐{:.1}] {}",
+                    template.id.bright_blue(),
+                    template.importance,
+                    truncate_graphemes(&template.content, 60)
+                )?;
+                let placeholders = template.placeholders();
+                if !placeholders.is_empty() {
+                    writeln!(out, "    Variables: {}", placeholders.join(", ").dimmed())?;
+                }
+            }
+        }
+
+        TemplateCommands::Set {
+            id,
+            content,
+            set_meta,
+            importance,
+            ttl,
+        } => {
+            let template = MemoryTemplate {
+                id,
+                content,
+                metadata: set_meta.into_iter().collect(),
+                importance,
+                ttl_hours: ttl,
+            };
+            let placeholders = template.placeholders();
+            let id = template.id.clone();
+            manager.set_template(template)?;
+
+            writeln!(out, "{}", format!("✓ Template {} saved", id).green())?;
+            if !placeholders.is_empty() {
+                writeln!(out, "  Variables: {}", placeholders.join(", "))?;
+            }
+        }
+
+        TemplateCommands::Remove { id } => {
+            if !manager.delete_template(&id)? {
+                return Err(anyhow::anyhow!("Template not found: {}", id));
+            }
+            writeln!(out, "{}", format!("✓ Removed template {}", id).green())?;
+        }
+
+        TemplateCommands::Use {
+            id,
+            user,
+            session,
+            vars,
+        } => {
+            let vars: HashMap<String, String> = vars.into_iter().collect();
+            let memory_id = manager.save_from_template(&id, &user, &session, &vars)?;
+            let memory = manager
+                .get_memory(&memory_id)?
+                .context("Saved memory disappeared")?;
+
+            writeln!(out, "{}", "✓ Memory saved successfully".green())?;
+            writeln!(out, "  ID: {}", memory_id.bright_blue())?;
+            writeln!(
+                out,
+                "  Content: {}",
+                truncate_graphemes(&memory.content, 50)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Calendar grid (by day) or bar chart (by week) of a timeline, followed by
+/// each bucket's top highlight
+fn print_timeline(
+    out: &mut dyn Write,
+    timeline: &Timeline,
+    user: &str,
+    since: chrono::DateTime<chrono::Utc>,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "📅 {} memories of {} since {} (by {})",
+            timeline.memory_count,
+            user,
+            since.format("%Y-%m-%d"),
+            timeline.bucket
+        )
+        .green()
+        .bold()
+    )?;
+    if timeline.truncated {
+        writeln!(
+            out,
+            "{}",
+            "Only the newest memories are shown; narrow the range to see older ones".yellow()
+        )?;
+    }
+
+    let count_at = |start| {
+        timeline
+            .buckets
+            .iter()
+            .find(|bucket| bucket.start == start)
+            .map_or(0, |bucket| bucket.count)
+    };
+    let first_week = TimeBucket::Week.start_of(since);
+    let last_week = TimeBucket::Week.start_of(now);
+
+    match timeline.bucket {
+        TimeBucket::Day => {
+            writeln!(
+                out,
+                "\n{:<12}{}",
+                "",
+                " Mon Tue Wed Thu Fri Sat Sun".dimmed()
+            )?;
+            let (first_day, last_day) = (
+                TimeBucket::Day.start_of(since),
+                TimeBucket::Day.start_of(now),
+            );
+            let mut week = first_week;
+            while week <= last_week {
+                let mut row = String::new();
+                for offset in 0..7 {
+                    let day = week + TimeBucket::Day.duration() * offset;
+                    let cell = if day < first_day || day > last_day {
+                        "    ".to_string()
+                    } else {
+                        match count_at(day) {
+                            0 => format!("{:>4}", "·").dimmed().to_string(),
+                            count => format!("{:>4}", count).bright_blue().to_string(),
+                        }
+                    };
+                    row.push_str(&cell);
+                }
+                writeln!(out, "{:<12}{}", week.format("%Y-%m-%d"), row)?;
+                week += TimeBucket::Week.duration();
+            }
+        }
+        TimeBucket::Week => {
+            writeln!(out)?;
+            let max = timeline
+                .buckets
+                .iter()
+                .map(|bucket| bucket.count)
+                .max()
+                .unwrap_or(0)
+                .max(1);
+            let mut week = first_week;
+            while week <= last_week {
+                let count = count_at(week);
+                let bar = "█".repeat((count * 30).div_ceil(max));
+                writeln!(
+                    out,
+                    "Week of {}  {:>4} {}",
+                    week.format("%Y-%m-%d"),
+                    count,
+                    bar.bright_blue()
+                )?;
+                week += TimeBucket::Week.duration();
+            }
+        }
+    }
+
+    writeln!(out, "\n{}", "Highlights:".bold())?;
+    for bucket in timeline.buckets.iter().rev() {
+        let Some(top) = bucket.highlights.first() else {
+            continue;
+        };
+        writeln!(
+            out,
+            "  {}  {} {}",
+            bucket.start.format("%Y-%m-%d"),
+            format!("[⭐{:.1}]", top.importance).bright_yellow(),
+            truncate_graphemes(&top.content.replace('\n', " "), 70)
+        )?;
+    }
+    Ok(())
+}
+
+/// Percentiles, then one bar per histogram bucket scaled to the fullest
+fn print_value_distribution(
+    out: &mut dyn Write,
+    title: &str,
+    distribution: &ValueDistribution,
+    format_value: impl Fn(f64) -> String,
+) -> Result<()> {
+    writeln!(
+        out,
+        "\n{} {}",
+        format!("{}:", title).bold(),
+        format!(
+            "p50 {}  p90 {}  p99 {}  (min {}, max {})",
+            format_value(distribution.p50),
+            format_value(distribution.p90),
+            format_value(distribution.p99),
+            format_value(distribution.min),
+            format_value(distribution.max)
+        )
+        .dimmed()
+    )?;
+
+    let max = distribution
+        .histogram
+        .iter()
+        .map(|bucket| bucket.count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for bucket in &distribution.histogram {
+        let range = match bucket.to {
+            Some(to) => format!("{}-{}", format_value(bucket.from), format_value(to)),
+            None => format!("{}+", format_value(bucket.from)),
+        };
+        write!(out, "  {:<18} {:>5}", range, bucket.count)?;
+        if bucket.count > 0 {
+            let bar = "█".repeat((bucket.count * 30).div_ceil(max) as usize);
+            write!(out, " {}", bar.bright_blue())?;
+        }
+        writeln!(out)?;
+    }
+    Ok(())
+}
+
+/// Block heights for trend sparklines, lowest first
+const SPARK_LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+fn print_keyword_trends(
+    out: &mut dyn Write,
+    trends: &KeywordTrends,
+    window: chrono::Duration,
+) -> Result<()> {
+    let Some(since) = trends.window_starts.first() else {
+        return Ok(());
+    };
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "Keyword trends for {} since {} ({} windows of {})",
+            trends.user_id,
+            since.format("%Y-%m-%d %H:%M"),
+            trends.window_starts.len(),
+            format_duration(window.num_seconds())
+        )
+        .green()
+        .bold()
+    )?;
+    writeln!(
+        out,
+        "Memories per window: {}",
+        trends
+            .memory_counts
+            .iter()
+            .map(|count| count.to_string())
+            .collect::<Vec<_>>()
+            .join(" ")
+    )?;
+
+    if trends.rising.is_empty() && trends.falling.is_empty() {
+        writeln!(
+            out,
+            "{}",
+            "No keyword came up often enough to trend".yellow()
+        )?;
+        return Ok(());
+    }
+
+    for (title, keywords) in [("Rising:", &trends.rising), ("Falling:", &trends.falling)] {
+        if keywords.is_empty() {
+            continue;
+        }
+        writeln!(out, "\n{}", title.bold())?;
+        for trend in keywords.iter() {
+            let max = trend.counts.iter().copied().max().unwrap_or(0).max(1);
+            let sparkline: String = trend
+                .counts
+                .iter()
+                .map(|&count| SPARK_LEVELS[count * (SPARK_LEVELS.len() - 1) / max])
+                .collect();
+            let change = format!("{:+.0}%/window", trend.slope * 100.0);
+            writeln!(
+                out,
+                "  {:<20} {}  {:>3} total  {}",
+                truncate_graphemes(&trend.keyword, 20),
+                sparkline.bright_blue(),
+                trend.total,
+                if trend.slope > 0.0 {
+                    change.green()
+                } else {
+                    change.red()
+                }
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Export data in `encoding`; JSON is pretty-printed for people to read
+fn encode_export<T: serde::Serialize>(encoding: WireFormat, value: &T) -> Result<Vec<u8>> {
+    match encoding {
+        WireFormat::Json => Ok(serde_json::to_vec_pretty(value)?),
+        WireFormat::Cbor => encoding.encode(value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, DecayEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        let decay = DecayEngine::new(database.clone(), validator.clone(), DecayPolicy::default());
+        (MemoryManager::new(database, validator), decay, temp_dir)
+    }
+
+    fn run(manager: &MemoryManager, decay: &DecayEngine, action: MemoryCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, manager, decay, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn save(manager: &MemoryManager, content: &str) -> String {
+        manager
+            .save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_save_and_get() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Save {
+                user: "user1".to_string(),
+                session: "session1".to_string(),
+                content: "Bought AAPL at 150".to_string(),
+                importance: Some(0.9),
+                confidence: None,
+                ttl: None,
+                metadata: Some(r#"{"ticker":"AAPL"}"#.to_string()),
+                content_type: ContentType::Text,
+                draft: false,
+                source_type: Some(SourceType::Tool),
+                source_ref: Some("broker:fill-881".to_string()),
+                ingested_by: Some("trade-sync".to_string()),
+            },
+        );
+        assert!(output.contains("Memory saved successfully"));
+        assert!(output.contains("Session: session1 (new)"));
+
+        let memories = manager.export_user_memories("user1").unwrap();
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].metadata.get("ticker").unwrap(), "AAPL");
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Get {
+                id: memories[0].id.clone(),
+            },
+        );
+        assert!(output.contains("Bought AAPL at 150"));
+        assert!(output.contains("Source: tool (broker:fill-881)"));
+        assert!(output.contains("Ingested by: trade-sync"));
+    }
+
+    #[test]
+    fn test_explain_filter() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        save(&manager, "Secret plans");
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::ExplainFilter {
+                filter: r#"{"user_id": "user1", "keywords": ["secret"], "limit": 5}"#.to_string(),
+            },
+        );
+        assert!(output.contains("SELECT"), "{}", output);
+        assert!(output.contains("?1=text("), "{}", output);
+        assert!(output.contains("int(5)"), "{}", output);
+        assert!(!output.contains("user1") && !output.contains("secret"));
+        assert!(output.contains("idx_memories_"), "{}", output);
+        assert!(output.contains("No full table scans"), "{}", output);
+
+        let mut out = Vec::new();
+        let typo = MemoryCommands::ExplainFilter {
+            filter: r#"{"userId": "user1"}"#.to_string(),
+        };
+        assert!(handle(typo, &manager, &decay, &mut out).is_err());
+    }
+
+    #[test]
+    fn test_save_rejects_invalid_metadata() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+
+        let mut out = Vec::new();
+        let result = handle(
+            MemoryCommands::Save {
+                user: "user1".to_string(),
+                session: "session1".to_string(),
+                content: "content".to_string(),
+                importance: None,
+                confidence: None,
+                ttl: None,
+                metadata: Some("not json".to_string()),
+                content_type: ContentType::Text,
+                draft: false,
+                source_type: None,
+                source_ref: None,
+                ingested_by: None,
+            },
+            &manager,
+            &decay,
+            &mut out,
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_json_memories_and_schemas() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let schema = |action| {
+            let mut out = Vec::new();
+            handle(
+                MemoryCommands::Schema { action },
+                &manager,
+                &decay,
+                &mut out,
+            )
+            .map(|_| String::from_utf8(out).unwrap())
+        };
+        let save_json = |content: &str| {
+            let mut out = Vec::new();
+            handle(
+                MemoryCommands::Save {
+                    user: "user1".to_string(),
+                    session: "session1".to_string(),
+                    content: content.to_string(),
+                    importance: None,
+                    confidence: None,
+                    ttl: None,
+                    metadata: Some(r#"{"namespace":"trading"}"#.to_string()),
+                    content_type: ContentType::Json,
+                    draft: false,
+                    source_type: None,
+                    source_ref: None,
+                    ingested_by: None,
+                },
+                &manager,
+                &decay,
+                &mut out,
+            )
+        };
+
+        assert!(schema(SchemaCommands::List)
+            .unwrap()
+            .contains("No JSON schemas"));
+        schema(SchemaCommands::Set {
+            namespace: "trading".to_string(),
+            schema: r#"{"type": "object", "required": ["ticker"]}"#.to_string(),
+        })
+        .unwrap();
+        assert!(schema(SchemaCommands::List).unwrap().contains("trading"));
+
+        save_json(r#"{"ticker": "AAPL", "side": "buy"}"#).unwrap();
+        save_json(r#"{"ticker": "MSFT", "side": "sell"}"#).unwrap();
+        assert!(save_json(r#"{"side": "buy"}"#).is_err());
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Recall {
+                user: "user1".to_string(),
+                keywords: None,
+                semantic: None,
+                exclusions: Box::default(),
+                session: None,
+                min_importance: None,
+                min_confidence: None,
+                max_confidence: None,
+                limit: 10,
+                offset: 0,
+                meta: vec![("namespace".to_string(), "trading".to_string())],
+                json_fields: vec![("side".to_string(), "buy".to_string())],
+                content_type: Some(ContentType::Json),
+                min_length: None,
+                max_length: None,
+                fuzzy: false,
+                expiring_within: None,
+                states: Vec::new(),
+                source_types: Vec::new(),
+                source_ref: None,
+                ingested_by: None,
+                explain: false,
+            },
+        );
+        assert!(output.contains("Total: 1 memories"), "{}", output);
+        // Pretty-printed, one field per line
+        assert!(output.contains(r#""ticker": "AAPL""#), "{}", output);
+
+        schema(SchemaCommands::Remove {
+            namespace: "trading".to_string(),
+        })
+        .unwrap();
+        save_json(r#"{"side": "buy"}"#).unwrap();
+    }
+
+    #[test]
+    fn test_timeline() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let timeline = |bucket: TimeBucket| {
+            run(
+                &manager,
+                &decay,
+                MemoryCommands::Timeline {
+                    user: "user1".to_string(),
+                    since: chrono::Duration::days(14),
+                    bucket,
+                    session: None,
+                    keywords: None,
+                },
+            )
+        };
+        assert!(timeline(TimeBucket::Day).contains("No memories found"));
+
+        save(&manager, "Routine note");
+        manager
+            .save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Shipped the release".to_string(),
+                importance: 0.9,
+                ..Default::default()
+            })
+            .unwrap();
+
+        let output = timeline(TimeBucket::Day);
+        assert!(output.contains("2 memories of user1"), "{}", output);
+        assert!(output.contains("Mon Tue Wed"), "{}", output);
+        // Today's highlight is the more important memory
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        assert!(
+            output.contains(&format!("{}  [⭐0.9] Shipped the release", today)),
+            "{}",
+            output
+        );
+        assert!(!output.contains("Routine note"), "{}", output);
+
+        let output = timeline(TimeBucket::Week);
+        assert_eq!(output.matches("Week of").count(), 3, "{}", output);
+        assert!(output.contains("   2 ██"), "{}", output);
+    }
+
+    #[test]
+    fn test_recall_and_search() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        save(&manager, "Bitcoin analysis shows bullish trend");
+        save(&manager, "Weekly grocery list");
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Recall {
+                user: "user1".to_string(),
+                keywords: None,
+                semantic: None,
+                exclusions: Box::default(),
+                session: None,
+                min_importance: None,
+                min_confidence: None,
+                max_confidence: None,
+                limit: 10,
+                offset: 0,
+                meta: Vec::new(),
+                json_fields: Vec::new(),
+                content_type: None,
+                min_length: None,
+                max_length: None,
+                fuzzy: false,
+                expiring_within: None,
+                states: Vec::new(),
+                source_types: Vec::new(),
+                source_ref: None,
+                ingested_by: None,
+                explain: false,
+            },
+        );
+        assert!(output.contains("Total: 2 memories"));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Search {
+                user: "user1".to_string(),
+                query: "bitcoin".to_string(),
+                limit: 10,
+                by_session: false,
+                explain: true,
+            },
+        );
+        assert!(output.contains("Bitcoin analysis"));
+        assert!(!output.contains("grocery"));
+        assert!(
+            output.contains("↳ #1 by newest first, then importance"),
+            "{}",
+            output
+        );
+        assert!(output.contains("text relevance"));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Search {
+                user: "user1".to_string(),
+                query: "bitcoin".to_string(),
+                limit: 10,
+                by_session: true,
+                explain: false,
+            },
+        );
+        assert!(output.contains("1 match in"), "{}", output);
+        assert!(output.contains("Bitcoin analysis"));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Recall {
+                user: "user1".to_string(),
+                keywords: Some("bitconi".to_string()),
+                semantic: None,
+                exclusions: Box::default(),
+                session: None,
+                min_importance: None,
+                min_confidence: None,
+                max_confidence: None,
+                limit: 10,
+                offset: 0,
+                meta: Vec::new(),
+                json_fields: Vec::new(),
+                content_type: None,
+                min_length: None,
+                max_length: None,
+                fuzzy: true,
+                expiring_within: None,
+                states: Vec::new(),
+                source_types: Vec::new(),
+                source_ref: None,
+                ingested_by: None,
+                explain: false,
+            },
+        );
+        assert!(output.contains("Bitcoin analysis"), "{}", output);
+        assert!(output.contains("Fuzzy match"), "{}", output);
+    }
+
+    #[test]
+    fn test_templates() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let template = |action| {
+            let mut out = Vec::new();
+            handle(
+                MemoryCommands::Template { action },
+                &manager,
+                &decay,
+                &mut out,
+            )
+            .map(|_| String::from_utf8(out).unwrap())
+        };
+
+        assert!(template(TemplateCommands::List)
+            .unwrap()
+            .contains("No templates"));
+        let output = template(TemplateCommands::Set {
+            id: "task_done".to_string(),
+            content: "Finished {{task}} for {{client}}".to_string(),
+            set_meta: vec![("client".to_string(), "{{client}}".to_string())],
+            importance: 0.7,
+            ttl: None,
+        })
+        .unwrap();
+        assert!(output.contains("Variables: client, task"), "{}", output);
+        assert!(template(TemplateCommands::List)
+            .unwrap()
+            .contains("task_done"));
+
+        let output = template(TemplateCommands::Use {
+            id: "task_done".to_string(),
+            user: "user1".to_string(),
+            session: "session1".to_string(),
+            vars: vec![
+                ("task".to_string(), "the audit".to_string()),
+                ("client".to_string(), "Acme".to_string()),
+            ],
+        })
+        .unwrap();
+        assert!(output.contains("Finished the audit for Acme"), "{}", output);
+
+        assert!(template(TemplateCommands::Use {
+            id: "task_done".to_string(),
+            user: "user1".to_string(),
+            session: "session1".to_string(),
+            vars: Vec::new(),
+        })
+        .is_err());
+        template(TemplateCommands::Remove {
+            id: "task_done".to_string(),
+        })
+        .unwrap();
+        assert!(template(TemplateCommands::Remove {
+            id: "task_done".to_string(),
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let id = save(&manager, "Original content");
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Update {
+                id: id.clone(),
+                content: Some("Updated content".to_string()),
+                append: Some(" and more".to_string()),
+                importance: None,
+                confidence: None,
+                ttl: Some(4),
+                clear_ttl: false,
+                extend_ttl: Some(2),
+                set_meta: vec![("ticker".to_string(), "AAPL".to_string())],
+                unset_meta: vec!["missing".to_string()],
+                expected_version: Some(1),
+            },
+        );
+        assert!(output.contains("Memory updated successfully"));
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!(memory.content, "Updated content and more");
+        assert_eq!(memory.metadata["ticker"], "AAPL");
+        assert_eq!(memory.ttl_hours, Some(6));
+        assert!(run(&manager, &decay, MemoryCommands::Get { id: id.clone() }).contains("v2"));
+
+        run(
+            &manager,
+            &decay,
+            MemoryCommands::Update {
+                id: id.clone(),
+                content: None,
+                append: None,
+                importance: None,
+                confidence: None,
+                ttl: None,
+                clear_ttl: true,
+                extend_ttl: None,
+                set_meta: Vec::new(),
+                unset_meta: Vec::new(),
+                expected_version: None,
+            },
+        );
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!((memory.ttl_hours, memory.expires_at), (None, None));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Delete {
+                id: Some(id.clone()),
+                filter: DeleteFilterArgs::default(),
+                yes: true,
+            },
+        );
+        assert!(output.contains("Memory deleted successfully"));
+        assert!(manager.get_memory(&id).unwrap().is_none());
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Delete {
+                id: Some(id),
+                filter: DeleteFilterArgs::default(),
+                yes: true,
+            },
+        );
+        assert!(output.contains("Memory not found"));
+    }
+
+    #[test]
+    fn test_delete_by_filter() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let kept = save(&manager, "Kept");
+        manager
+            .save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "scratch".to_string(),
+                content: "Scratch note".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let delete = |dry_run: bool, max: Option<usize>| {
+            let mut out = Vec::new();
+            handle(
+                MemoryCommands::Delete {
+                    id: None,
+                    filter: DeleteFilterArgs {
+                        session: Some("scratch".to_string()),
+                        dry_run,
+                        max,
+                        ..Default::default()
+                    },
+                    yes: true,
+                },
+                &manager,
+                &decay,
+                &mut out,
+            )
+            .map(|_| String::from_utf8(out).unwrap())
+        };
+
+        let output = delete(true, None).unwrap();
+        assert!(output.contains("Would delete 1 memories"), "{}", output);
+        assert!(delete(false, Some(0)).is_err());
+
+        let output = delete(false, None).unwrap();
+        assert!(output.contains("Deleted 1 memories"), "{}", output);
+        assert!(delete(false, None).unwrap().contains("No memories match"));
+        assert!(manager.get_memory(&kept).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_migrate_metadata() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let id = manager
+            .save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Bought AAPL".to_string(),
+                metadata: HashMap::from([("category".to_string(), "stocks".to_string())]),
+                ..Default::default()
+            })
+            .unwrap();
+        let migrate = |dry_run| {
+            run(
+                &manager,
+                &decay,
+                MemoryCommands::MigrateMetadata {
+                    from_key: "category".to_string(),
+                    to_key: Some("topic".to_string()),
+                    map: vec![("stocks".to_string(), "equities".to_string())],
+                    user: None,
+                    batch_size: 500,
+                    dry_run,
+                },
+            )
+        };
+
+        let output = migrate(true);
+        assert!(output.contains("Would migrate 1 memories"), "{}", output);
+        let output = migrate(false);
+        assert!(output.contains("Migrated 1 memories"), "{}", output);
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!(
+            memory.metadata.get("topic").map(String::as_str),
+            Some("equities")
+        );
+        assert!(migrate(false).contains("No memories have metadata key `category`"));
+    }
+
+    #[test]
+    fn test_decompress_requires_compressed_memory() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let id = save(&manager, "Never compressed");
+
+        let mut out = Vec::new();
+        let err = handle(
+            MemoryCommands::Decompress { id: id.clone() },
+            &manager,
+            &decay,
+            &mut out,
+        )
+        .unwrap_err();
+        assert!(err.to_string().contains("not compressed"), "{}", err);
+        assert!(manager.get_memory(&id).unwrap().is_some());
+    }
+
+    #[test]
+    fn test_expiring_expired_and_restore() {
+        let (manager, decay, temp_dir) = setup_test_manager();
+        save(&manager, "Permanent");
+        let id = manager
+            .save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Short-lived".to_string(),
+                ttl_hours: Some(1),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let recall = |expiring_within| {
+            run(
+                &manager,
+                &decay,
+                MemoryCommands::Recall {
+                    user: "user1".to_string(),
+                    keywords: None,
+                    semantic: None,
+                    exclusions: Box::default(),
+                    session: None,
+                    min_importance: None,
+                    min_confidence: None,
+                    max_confidence: None,
+                    limit: 10,
+                    offset: 0,
+                    meta: Vec::new(),
+                    json_fields: Vec::new(),
+                    content_type: None,
+                    min_length: None,
+                    max_length: None,
+                    fuzzy: false,
+                    expiring_within,
+                    states: Vec::new(),
+                    source_types: Vec::new(),
+                    source_ref: None,
+                    ingested_by: None,
+                    explain: false,
+                },
+            )
+        };
+        let output = recall(Some(chrono::Duration::hours(2)));
+        assert!(output.contains("Total: 1 memories"), "{}", output);
+        assert!(output.contains("Short-lived"), "{}", output);
+
+        // Expire it behind the manager's back
+        Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+        .get_connection_pool()
+        .with_write_transaction(|tx| {
+            tx.execute(
+                "UPDATE memories SET expires_at = datetime('now', '-1 hour') WHERE id = ?1",
+                [&id],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        assert!(recall(None).contains("Total: 1 memories"));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Expired {
+                user: "user1".to_string(),
+            },
+        );
+        assert!(output.contains("1 expired memories"), "{}", output);
+        assert!(output.contains("Short-lived"), "{}", output);
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Restore {
+                id: id.clone(),
+                ttl: Some(24),
+            },
+        );
+        assert!(output.contains("Restored memory"), "{}", output);
+        assert_eq!(
+            manager.get_memory(&id).unwrap().unwrap().ttl_hours,
+            Some(24)
+        );
+        assert!(recall(None).contains("Total: 2 memories"));
+
+        let output = run(&manager, &decay, MemoryCommands::Restore { id, ttl: None });
+        assert!(output.contains("Expired memory not found"), "{}", output);
+    }
+
+    #[test]
+    fn test_export_to_file() {
+        let (manager, decay, temp_dir) = setup_test_manager();
+        save(&manager, "Exported memory");
+
+        let path = temp_dir.path().join("export.json");
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Export {
+                user: "user1".to_string(),
+                output: Some(path.to_string_lossy().to_string()),
+                format: None,
+                encoding: WireFormat::Json,
+            },
+        );
+        assert!(output.contains("Exported 1 memories"));
+
+        let exported: Vec<MemoryItem> =
+            serde_json::from_str(&std::fs::read_to_string(path).unwrap()).unwrap();
+        assert_eq!(exported[0].content, "Exported memory");
+
+        let cbor_path = temp_dir.path().join("export.cbor");
+        run(
+            &manager,
+            &decay,
+            MemoryCommands::Export {
+                user: "user1".to_string(),
+                output: Some(cbor_path.to_string_lossy().to_string()),
+                format: None,
+                encoding: WireFormat::Cbor,
+            },
+        );
+        let decoded: Vec<MemoryItem> = WireFormat::Cbor
+            .decode(&std::fs::read(cbor_path).unwrap())
+            .unwrap();
+        assert_eq!(decoded[0].id, exported[0].id);
+        assert_eq!(decoded[0].created_at, exported[0].created_at);
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Export {
+                user: "user1".to_string(),
+                output: None,
+                format: Some(DocumentFormat::LlamaIndex),
+                encoding: WireFormat::Json,
+            },
+        );
+        let documents: Vec<serde_json::Value> = serde_json::from_str(&output).unwrap();
+        assert_eq!(documents[0]["text"], "Exported memory");
+        assert_eq!(documents[0]["id_"], exported[0].id.as_str());
+    }
+
+    #[test]
+    fn test_clusters() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Clusters {
+                user: "user1".to_string(),
+                k: None,
+            },
+        );
+        assert!(output.contains("No memories found for user: user1"));
+
+        for content in [
+            "Portfolio rebalancing moved shares into bonds",
+            "Bonds portfolio yield looks better than shares",
+            "Sourdough bread needs a long proof",
+            "Proof the sourdough overnight before baking bread",
+        ] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Clusters {
+                user: "user1".to_string(),
+                k: Some(2),
+            },
+        );
+        assert!(output.contains("2 topics in 4 memories of user1 (by TF-IDF)"));
+        assert!(output.contains("(2 memories)"));
+        assert!(output.contains("sourdough"));
+        assert!(output.contains("portfolio"));
+    }
+
+    #[test]
+    fn test_conflicts_and_links() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let ids: Vec<String> = [
+            "Dana prefers aisle seats on trains",
+            "Dana never prefers aisle seats on trains",
+        ]
+        .iter()
+        .map(|content| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        })
+        .collect();
+        let conflicts = || {
+            run(
+                &manager,
+                &decay,
+                MemoryCommands::Conflicts {
+                    user: "user1".to_string(),
+                    id: ids[0].clone(),
+                },
+            )
+        };
+
+        let output = conflicts();
+        assert!(output.contains("1 possible conflicts with memory"));
+        assert!(output.contains("Conflict: negation"));
+
+        let output = run(
+            &manager,
+            &decay,
+            MemoryCommands::Link {
+                id: ids[1].clone(),
+                other: ids[0].clone(),
+                link_type: LinkType::Compatible,
+                remove: false,
+            },
+        );
+        assert!(output.contains("as compatible"));
+        assert!(conflicts().contains("No conflicts found"));
+    }
+
+    #[test]
+    fn test_stats_trends() {
+        let (manager, decay, _temp_dir) = setup_test_manager();
+        let stats = |trends| {
+            run(
+                &manager,
+                &decay,
+                MemoryCommands::Stats {
+                    user: "user1".to_string(),
+                    trends,
+                    window: chrono::Duration::days(1),
+                },
+            )
+        };
+
+        let output = stats(true);
+        assert!(output.contains("Keyword trends for user1"));
+        assert!(output.contains("(8 windows of 1d 0h)"));
+        assert!(output.contains("No keyword came up often enough to trend"));
+
+        for content in ["Kubernetes rollout", "Kubernetes upgrade"] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        // Everything landed in the latest window
+        let output = stats(true);
+        assert!(output.contains("Memories per window: 0 0 0 0 0 0 0 2"));
+        assert!(output.contains("Rising:"));
+        assert!(output.contains("kubernetes"));
+        assert!(output.contains("▁▁▁▁▁▁▁█"));
+        assert!(!output.contains("Falling:"));
+
+        let output = stats(false);
+        assert!(output.contains("Memory Statistics for user1"));
+        // "Kubernetes rollout" and "Kubernetes upgrade"
+        assert!(output.contains("Content size: 36 B"), "{}", output);
+        assert!(
+            output.contains("session1: 36 B in 2 memories"),
+            "{}",
+            output
+        );
+        assert!(output.contains("p50 0.5  p90 0.5"), "{}", output);
+        assert!(output.contains("p50 18 B"), "{}", output);
+        assert!(output.contains("0.5-0.6"), "{}", output);
+        assert!(output.contains("64.0 KB+"), "{}", output);
+    }
+}
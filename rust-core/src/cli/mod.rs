@@ -1,12 +1,39 @@
-//! CLI module for additional command utilities
+//! CLI module: the `memex` command tree plus shared terminal utilities
+//!
+//! Each command group lives in its own module with a `handle` function that
+//! receives the manager it needs and writes to any `Write`, so handlers can be
+//! exercised in tests without a terminal.
 
+pub mod app;
 pub mod commands;
+pub mod database;
+pub mod decay;
+pub mod export;
+pub mod import;
+pub mod maintenance;
+pub mod memory;
+pub mod plugin;
+pub mod search;
+pub mod session;
+pub mod shard;
+pub mod summaries;
+pub mod system;
+pub mod user;
+#[cfg(feature = "vector-search")]
+pub mod vector;
+
+pub use crate::display::wrap_text;
+pub use app::{dispatch, run, Cli, CliContext, Commands, GlobalArgs};
+pub use plugin::{CliBuilder, PluginHandler};
 
 use anyhow::Result;
 use colored::*;
 use std::io::{self, Write};
 
-use crate::database::models::{MemoryItem, Session};
+use crate::database::models::{
+    ContentType, MemoryItem, MemoryState, RankedBy, RecallExplanation, Session,
+};
+use crate::display::{display_width, pad_to_width, take_graphemes, truncate_graphemes};
 
 /// Interactive CLI utilities
 pub struct InteractiveCli;
@@ -63,12 +90,11 @@ impl InteractiveCli {
         let default_text = default.map(|d| d.to_string());
         let input = Self::prompt_text(message, default_text.as_deref())?;
 
-        if input.is_empty() && default.is_some() {
-            Ok(default.unwrap())
-        } else {
-            input
+        match default {
+            Some(default) if input.is_empty() => Ok(default),
+            _ => input
                 .parse()
-                .map_err(|_| anyhow::anyhow!("Invalid number format"))
+                .map_err(|_| anyhow::anyhow!("Invalid number format")),
         }
     }
 
@@ -120,13 +146,13 @@ impl InteractiveCli {
         println!("\n{}", title.green().bold());
 
         // Calculate column widths
-        let mut col_widths = headers.iter().map(|h| h.len()).collect::<Vec<_>>();
+        let mut col_widths = headers.iter().map(|h| display_width(h)).collect::<Vec<_>>();
 
         for row in rows {
             let row_data = row.to_row();
             for (i, cell) in row_data.iter().enumerate() {
                 if i < col_widths.len() {
-                    col_widths[i] = col_widths[i].max(cell.len());
+                    col_widths[i] = col_widths[i].max(display_width(cell));
                 }
             }
         }
@@ -143,7 +169,7 @@ impl InteractiveCli {
 
         print!("│");
         for (i, (header, width)) in headers.iter().zip(&col_widths).enumerate() {
-            print!(" {:<width$} ", header.bold(), width = width);
+            print!(" {} ", pad_to_width(header, *width).bold());
             if i < headers.len() - 1 {
                 print!("│");
             }
@@ -165,7 +191,7 @@ impl InteractiveCli {
             let row_data = row.to_row();
             print!("│");
             for (i, (cell, width)) in row_data.iter().zip(&col_widths).enumerate() {
-                print!(" {:<width$} ", cell, width = width);
+                print!(" {} ", pad_to_width(cell, *width));
                 if i < row_data.len() - 1 {
                     print!("│");
                 }
@@ -194,13 +220,9 @@ pub trait TableRow {
 impl TableRow for MemoryItem {
     fn to_row(&self) -> Vec<String> {
         vec![
-            self.id[..8].to_string(), // Truncated ID
+            take_graphemes(&self.id, 8).to_string(), // Truncated ID
             self.user_id.clone(),
-            if self.content.len() > 30 {
-                format!("{}...", &self.content[..30])
-            } else {
-                self.content.clone()
-            },
+            truncate_graphemes(&self.content, 30).into_owned(),
             format!("{:.1}", self.importance),
             self.created_at.format("%Y-%m-%d").to_string(),
         ]
@@ -210,7 +232,7 @@ impl TableRow for MemoryItem {
 impl TableRow for Session {
     fn to_row(&self) -> Vec<String> {
         vec![
-            self.id[..8].to_string(), // Truncated ID
+            take_graphemes(&self.id, 8).to_string(), // Truncated ID
             self.name
                 .as_ref()
                 .unwrap_or(&"(unnamed)".to_string())
@@ -221,6 +243,191 @@ impl TableRow for Session {
     }
 }
 
+/// Lines of JSON content shown before it is cut off
+const JSON_PREVIEW_LINES: usize = 12;
+
+/// Content of `memory` laid out for its content type: JSON pretty-printed,
+/// markdown with bold headings and bullet points, text wrapped
+fn content_lines(memory: &MemoryItem) -> Vec<String> {
+    match memory.content_type {
+        ContentType::Json => match serde_json::from_str::<serde_json::Value>(&memory.content) {
+            Ok(document) => {
+                let pretty = serde_json::to_string_pretty(&document).unwrap_or_default();
+                let mut lines: Vec<String> = pretty.lines().map(str::to_string).collect();
+                if lines.len() > JSON_PREVIEW_LINES {
+                    let hidden = lines.len() - JSON_PREVIEW_LINES + 1;
+                    lines.truncate(JSON_PREVIEW_LINES - 1);
+                    lines.push(format!("… {} more lines", hidden).dimmed().to_string());
+                }
+                lines
+            }
+            Err(_) => wrap_text(&truncate_graphemes(&memory.content, 200), 76),
+        },
+        ContentType::Markdown => {
+            let content = truncate_graphemes(&memory.content, 400);
+            content
+                .lines()
+                .filter(|line| !line.trim().is_empty())
+                .flat_map(|line| {
+                    let trimmed = line.trim_start();
+                    if trimmed.starts_with('#') {
+                        let heading = trimmed.trim_start_matches('#').trim();
+                        vec![heading.bold().to_string()]
+                    } else if let Some(item) = trimmed
+                        .strip_prefix("- ")
+                        .or_else(|| trimmed.strip_prefix("* "))
+                    {
+                        wrap_text(item, 74)
+                            .into_iter()
+                            .enumerate()
+                            .map(|(i, part)| format!("{} {}", if i == 0 { "•" } else { " " }, part))
+                            .collect()
+                    } else {
+                        wrap_text(trimmed, 76)
+                    }
+                })
+                .collect()
+        }
+        ContentType::Text => wrap_text(&truncate_graphemes(&memory.content, 200), 76),
+    }
+}
+
+/// Print a memory in the multi-line format used by list and detail commands
+/// One-line summary of why recall ranked a memory where it did
+fn explain_line(explanation: &RecallExplanation) -> String {
+    let mut parts = Vec::new();
+    if let Some(similarity) = explanation.fuzzy_similarity {
+        parts.push(format!("fuzzy similarity {:.2}", similarity));
+    }
+    if let Some(similarity) = explanation.semantic_similarity {
+        parts.push(format!("semantic similarity {:.2}", similarity));
+    }
+    parts.push(format!(
+        "{} old",
+        format_duration((explanation.age_hours * 3600.0) as i64)
+    ));
+    parts.push(format!("importance {:.2}", explanation.importance));
+    if let Some(relevance) = explanation.text_relevance {
+        if explanation.ranked_by == RankedBy::Semantic {
+            parts.push(format!("text relevance {:.2}", relevance));
+        } else {
+            parts.push(format!("text relevance {:.2} (not ranked on)", relevance));
+        }
+    }
+    let ranked_by = match explanation.ranked_by {
+        RankedBy::Recency => "newest first, then importance",
+        RankedBy::FuzzySimilarity => "fuzzy similarity, then newest",
+        RankedBy::Semantic => "semantic similarity blended with text relevance",
+    };
+    format!(
+        "↳ #{} by {}: {}",
+        explanation.position,
+        ranked_by,
+        parts.join(", ")
+    )
+}
+
+pub fn print_memory_item(out: &mut dyn Write, memory: &MemoryItem, index: usize) -> Result<()> {
+    writeln!(
+        out,
+        "{} {} {}{}{}",
+        format!("{}.", index).dimmed(),
+        memory.id.bright_blue(),
+        format!("[⭐{:.1}]", memory.importance).bright_yellow(),
+        if memory.confidence < 1.0 {
+            format!(
+                " {}",
+                format!("[{:.0}% sure]", memory.confidence * 100.0).cyan()
+            )
+        } else {
+            String::new()
+        },
+        if memory.state == MemoryState::Active {
+            String::new()
+        } else {
+            format!(" {}", format!("({})", memory.state).magenta())
+        }
+    )?;
+
+    writeln!(
+        out,
+        "  👤 {} | 🗂️ {} | 📅 {}{}",
+        memory.user_id,
+        memory.session_id,
+        memory.created_at.format("%Y-%m-%d %H:%M"),
+        if memory.version > 1 {
+            format!(" | ✏️ v{}", memory.version)
+        } else {
+            String::new()
+        }
+    )?;
+
+    if let Some(score) = memory.match_score.filter(|score| *score < 1.0) {
+        writeln!(
+            out,
+            "  {}",
+            format!("≈ Fuzzy match ({:.0}%)", score * 100.0).dimmed()
+        )?;
+    }
+
+    if let Some(explanation) = &memory.explanation {
+        writeln!(out, "  {}", explain_line(explanation).dimmed())?;
+    }
+
+    for line in content_lines(memory) {
+        writeln!(out, "  {}", line)?;
+    }
+
+    #[cfg(feature = "vector-search")]
+    if let (Some(embedding), model) = (&memory.embedding, &memory.embedding_model) {
+        let model = model.as_deref().unwrap_or("unknown model");
+        writeln!(
+            out,
+            "  {}",
+            format!("🧠 Vector: {}D ({})", embedding.len(), model).dimmed()
+        )?;
+    }
+
+    if !memory.metadata.is_empty() {
+        let metadata_items: Vec<String> = memory
+            .metadata
+            .iter()
+            .map(|(k, v)| format!("{}:{}", k, v))
+            .collect();
+        writeln!(out, "  📋 {}", metadata_items.join(" | ").dimmed())?;
+    }
+
+    if let Some(ttl) = memory.ttl_hours {
+        if let Some(expires_at) = memory.expires_at {
+            let now = chrono::Utc::now();
+            if expires_at > now {
+                let remaining = expires_at - now;
+                writeln!(
+                    out,
+                    "  ⏰ Expires in {} hours ({})",
+                    remaining.num_hours(),
+                    expires_at.format("%Y-%m-%d %H:%M").to_string().dimmed()
+                )?;
+            } else {
+                writeln!(out, "  ⏰ {}", "EXPIRED".red().bold())?;
+            }
+        } else {
+            writeln!(out, "  ⏰ TTL: {} hours", ttl.to_string().dimmed())?;
+        }
+    }
+
+    if memory.is_compressed {
+        writeln!(
+            out,
+            "  📦 {} (from {} memories)",
+            "COMPRESSED".bright_magenta(),
+            memory.compressed_from.len()
+        )?;
+    }
+
+    Ok(())
+}
+
 /// Format file sizes in human-readable format
 pub fn format_bytes(bytes: u64) -> String {
     const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
@@ -239,6 +446,15 @@ pub fn format_bytes(bytes: u64) -> String {
     }
 }
 
+/// Format a size that may be negative, such as a change in size
+pub fn format_signed_bytes(bytes: i64) -> String {
+    if bytes < 0 {
+        format!("-{}", format_bytes(bytes.unsigned_abs()))
+    } else {
+        format_bytes(bytes as u64)
+    }
+}
+
 /// Format duration in human-readable format
 pub fn format_duration(seconds: i64) -> String {
     if seconds < 60 {
@@ -256,6 +472,37 @@ pub fn format_duration(seconds: i64) -> String {
     }
 }
 
+/// Parse a duration like `30m`, `12h`, `7d` or `2w`, for `--since` arguments
+pub fn parse_duration(value: &str) -> std::result::Result<chrono::Duration, String> {
+    let value = value.trim();
+    let split = value.len() - value.chars().last().map_or(0, char::len_utf8);
+    let (amount, unit) = value.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|_| format!("invalid duration `{}`; expected e.g. 12h, 7d or 2w", value))?;
+
+    match unit {
+        "m" => Ok(chrono::Duration::minutes(amount)),
+        "h" => Ok(chrono::Duration::hours(amount)),
+        "d" => Ok(chrono::Duration::days(amount)),
+        "w" => Ok(chrono::Duration::weeks(amount)),
+        _ => Err(format!(
+            "invalid duration unit in `{}`; use m, h, d or w",
+            value
+        )),
+    }
+}
+
+/// Parse a `KEY=VALUE` pair, for `--set-meta` arguments
+pub fn parse_key_value(value: &str) -> std::result::Result<(String, String), String> {
+    match value.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("invalid `{}`; expected KEY=VALUE", value)),
+    }
+}
+
 /// Colorize text based on value ranges
 pub fn colorize_importance(importance: f32) -> colored::ColoredString {
     match importance {
@@ -290,6 +537,71 @@ mod tests {
         assert_eq!(format_bytes(1024 * 1024 * 1024), "1.0 GB");
     }
 
+    #[test]
+    fn test_print_memory_item() {
+        let memory = MemoryItem {
+            id: "mem-1".to_string(),
+            user_id: "user1".to_string(),
+            session_id: "session1".to_string(),
+            content: "Remember the milk".to_string(),
+            ..Default::default()
+        };
+
+        let mut out = Vec::new();
+        print_memory_item(&mut out, &memory, 3).unwrap();
+        let output = String::from_utf8(out).unwrap();
+
+        assert!(output.contains("mem-1"));
+        assert!(output.contains("Remember the milk"));
+
+        let json = MemoryItem {
+            content: r#"{"ticker":"AAPL","qty":10}"#.to_string(),
+            content_type: ContentType::Json,
+            ..memory.clone()
+        };
+        let lines = content_lines(&json);
+        assert_eq!(lines.first().map(String::as_str), Some("{"));
+        assert!(lines
+            .iter()
+            .any(|line| line.trim().starts_with(r#""ticker": "AAPL""#)));
+
+        let markdown = MemoryItem {
+            content: "# Plan\n\n- buy\n* sell".to_string(),
+            content_type: ContentType::Markdown,
+            ..memory
+        };
+        let lines = content_lines(&markdown);
+        assert!(lines[0].contains("Plan"));
+        assert_eq!(lines[1..], ["• buy", "• sell"]);
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7d").unwrap(), chrono::Duration::days(7));
+        assert_eq!(parse_duration("12h").unwrap(), chrono::Duration::hours(12));
+        assert_eq!(parse_duration("2w").unwrap(), chrono::Duration::weeks(2));
+        assert_eq!(
+            parse_duration("30m").unwrap(),
+            chrono::Duration::minutes(30)
+        );
+
+        for invalid in ["", "7", "d", "7y", "-", "7 d", "1.5h", "日"] {
+            assert!(parse_duration(invalid).is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn test_parse_key_value() {
+        assert_eq!(
+            parse_key_value("ticker=AAPL").unwrap(),
+            ("ticker".to_string(), "AAPL".to_string())
+        );
+        assert_eq!(parse_key_value("note=a=b").unwrap().1, "a=b");
+        assert_eq!(parse_key_value("empty=").unwrap().1, "");
+        assert!(parse_key_value("ticker").is_err());
+        assert!(parse_key_value("=AAPL").is_err());
+    }
+
     #[test]
     fn test_format_duration() {
         assert_eq!(format_duration(30), "30s");
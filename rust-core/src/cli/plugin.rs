@@ -0,0 +1,180 @@
+//! Custom subcommands for crates embedding memex as a library
+//!
+//! A [`CliBuilder`] starts from the built-in command tree and adds
+//! top-level subcommands with their handlers. Handlers receive the same
+//! [`CliContext`] as the built-in commands, so plugins get the global
+//! options, configuration loading and database setup for free.
+
+use anyhow::Result;
+use clap::{ArgMatches, Command, CommandFactory, FromArgMatches};
+use colored::*;
+use std::ffi::OsString;
+use std::io::Write;
+
+use crate::cli::app::{self, Cli, CliContext, GlobalArgs};
+use crate::logging::{self, LogFilter, LogFormat};
+
+/// Runs a plugin subcommand; gets the subcommand's own matches
+pub type PluginHandler =
+    Box<dyn Fn(&ArgMatches, &mut CliContext, &mut dyn Write) -> Result<()> + Send + Sync>;
+
+/// The `memex` command tree plus registered plugin subcommands
+#[derive(Default)]
+pub struct CliBuilder {
+    plugins: Vec<(Command, PluginHandler)>,
+}
+
+impl CliBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add `command` as a top-level subcommand run by `handler`
+    ///
+    /// # Panics
+    ///
+    /// If a built-in or already registered subcommand has the same name.
+    pub fn subcommand(
+        mut self,
+        command: Command,
+        handler: impl Fn(&ArgMatches, &mut CliContext, &mut dyn Write) -> Result<()>
+            + Send
+            + Sync
+            + 'static,
+    ) -> Self {
+        let name = command.get_name().to_string();
+        assert!(
+            self.command().find_subcommand(&name).is_none(),
+            "CLI subcommand '{}' is already defined",
+            name
+        );
+        self.plugins.push((command, Box::new(handler)));
+        self
+    }
+
+    /// The full command tree, for parsing or generating help
+    pub fn command(&self) -> Command {
+        self.plugins
+            .iter()
+            .fold(Cli::command(), |command, (plugin, _)| {
+                command.subcommand(plugin.clone())
+            })
+    }
+
+    /// Parse `args`, starting with the program name, and run the command
+    pub fn run_from<I, T>(&self, args: I, out: &mut dyn Write) -> Result<()>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        let matches = self.command().try_get_matches_from(args)?;
+        self.run_matches(&matches, out)
+    }
+
+    /// Entry point for a `memex` binary: parse the process arguments, set
+    /// up logging, run the command and exit with status 1 if it fails
+    pub fn main(&self) {
+        let matches = self.command().get_matches();
+
+        let verbose = GlobalArgs::from_arg_matches(&matches).is_ok_and(|global| global.verbose);
+        init_logging(verbose);
+
+        if let Err(e) = self.run_matches(&matches, &mut std::io::stdout()) {
+            eprintln!("{} {:#}", "Error:".red().bold(), e);
+            std::process::exit(1);
+        }
+    }
+
+    fn run_matches(&self, matches: &ArgMatches, out: &mut dyn Write) -> Result<()> {
+        if let Some((name, sub_matches)) = matches.subcommand() {
+            if let Some((_, handler)) = self
+                .plugins
+                .iter()
+                .find(|(plugin, _)| plugin.get_name() == name)
+            {
+                let global = GlobalArgs::from_arg_matches(matches)?;
+                let mut context = CliContext::open(&global, out)?;
+                return handler(sub_matches, &mut context, out);
+            }
+        }
+
+        app::run(Cli::from_arg_matches(matches)?, out)
+    }
+}
+
+fn init_logging(verbose: bool) {
+    // --verbose wins over RUST_LOG; an invalid RUST_LOG falls back to info
+    let filter = match std::env::var("RUST_LOG") {
+        _ if verbose => "debug".to_string(),
+        Ok(filter) if filter.parse::<LogFilter>().is_ok() => filter,
+        _ => "info".to_string(),
+    };
+    let format = match std::env::var("MEMEX_LOG_FORMAT").as_deref() {
+        Ok("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+    let _ = logging::init(&filter, format);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+    use tempfile::TempDir;
+
+    fn builder() -> CliBuilder {
+        CliBuilder::new().subcommand(
+            Command::new("greet")
+                .about("Greet a user with their memory count")
+                .arg(Arg::new("user").long("user").required(true)),
+            |matches, context, out| {
+                let user = matches.get_one::<String>("user").unwrap();
+                let count = context.memory.export_user_memories(user)?.len();
+                writeln!(out, "Hello {}, you have {} memories", user, count)?;
+                Ok(())
+            },
+        )
+    }
+
+    #[test]
+    fn test_plugin_subcommand() {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("cli.db").to_string_lossy().to_string();
+        let builder = builder();
+        builder.command().debug_assert();
+
+        let mut out = Vec::new();
+        builder
+            .run_from(
+                ["memex", "--database", &db_path, "greet", "--user", "alice"],
+                &mut out,
+            )
+            .unwrap();
+        let output = String::from_utf8(out).unwrap();
+        assert!(
+            output.contains("Hello alice, you have 0 memories"),
+            "{}",
+            output
+        );
+
+        // Built-in commands still work alongside plugins
+        let mut out = Vec::new();
+        builder
+            .run_from(
+                ["memex", "--database", &db_path, "system", "info"],
+                &mut out,
+            )
+            .unwrap();
+        assert!(String::from_utf8(out).unwrap().contains(&db_path));
+
+        assert!(builder
+            .run_from(["memex", "--database", &db_path, "greet"], &mut Vec::new())
+            .is_err());
+    }
+
+    #[test]
+    #[should_panic(expected = "already defined")]
+    fn test_plugin_name_clash() {
+        builder().subcommand(Command::new("memory"), |_, _, _| Ok(()));
+    }
+}
@@ -0,0 +1,199 @@
+//! `memex search ...` command handlers
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::core::memory::MemoryManager;
+use crate::database::models::DEFAULT_NAMESPACE;
+
+#[derive(Subcommand, Debug)]
+pub enum SearchCommands {
+    /// Manage words that keyword search treats as interchangeable
+    Synonyms {
+        #[command(subcommand)]
+        action: SynonymCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum SynonymCommands {
+    /// List synonym pairs
+    List {
+        /// Only this namespace's pairs
+        #[arg(short, long)]
+        namespace: Option<String>,
+    },
+    /// Pair two words; searching for either also finds the other
+    Add {
+        term: String,
+        synonym: String,
+        /// Namespace whose searches use the pair
+        #[arg(short, long, default_value = DEFAULT_NAMESPACE)]
+        namespace: String,
+    },
+    /// Remove a synonym pair
+    Remove {
+        term: String,
+        synonym: String,
+        /// Namespace the pair belongs to
+        #[arg(short, long, default_value = DEFAULT_NAMESPACE)]
+        namespace: String,
+    },
+}
+
+pub fn handle(action: SearchCommands, manager: &MemoryManager, out: &mut dyn Write) -> Result<()> {
+    match action {
+        SearchCommands::Synonyms { action } => handle_synonyms(action, manager, out),
+    }
+}
+
+fn handle_synonyms(
+    action: SynonymCommands,
+    manager: &MemoryManager,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        SynonymCommands::List { namespace } => {
+            let synonyms = manager.list_synonyms(namespace.as_deref())?;
+            if synonyms.is_empty() {
+                writeln!(out, "No synonyms; searches only find the words typed")?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "Synonyms:".green().bold())?;
+            let mut current = None;
+            for synonym in &synonyms {
+                if current != Some(&synonym.namespace) {
+                    writeln!(out, "  {}", synonym.namespace.bright_blue())?;
+                    current = Some(&synonym.namespace);
+                }
+                writeln!(out, "    {} ⇄ {}", synonym.term, synonym.synonym)?;
+            }
+        }
+
+        SynonymCommands::Add {
+            term,
+            synonym,
+            namespace,
+        } => {
+            if manager.add_synonym(&namespace, &term, &synonym)? {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ {} ⇄ {} in {}", term, synonym, namespace).green()
+                )?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("{} ⇄ {} already exists in {}", term, synonym, namespace).yellow()
+                )?;
+            }
+        }
+
+        SynonymCommands::Remove {
+            term,
+            synonym,
+            namespace,
+        } => {
+            if !manager.remove_synonym(&namespace, &term, &synonym)? {
+                return Err(anyhow::anyhow!(
+                    "No synonym pair {} ⇄ {} in {}",
+                    term,
+                    synonym,
+                    namespace
+                ));
+            }
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Removed {} ⇄ {} from {}", term, synonym, namespace).green()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (MemoryManager::new(database, validator), temp_dir)
+    }
+
+    fn run(manager: &MemoryManager, action: SynonymCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(SearchCommands::Synonyms { action }, manager, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_synonym_commands() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let list = |namespace: Option<&str>| {
+            run(
+                &manager,
+                SynonymCommands::List {
+                    namespace: namespace.map(str::to_string),
+                },
+            )
+            .unwrap()
+        };
+        assert!(list(None).contains("No synonyms"));
+
+        let add = |term: &str, synonym: &str, namespace: &str| {
+            run(
+                &manager,
+                SynonymCommands::Add {
+                    term: term.to_string(),
+                    synonym: synonym.to_string(),
+                    namespace: namespace.to_string(),
+                },
+            )
+        };
+        assert!(add("AAPL", "Apple", "trading").unwrap().contains("✓"));
+        assert!(add("Apple", "AAPL", "trading")
+            .unwrap()
+            .contains("already exists"));
+        add("car", "automobile", DEFAULT_NAMESPACE).unwrap();
+
+        let output = list(None);
+        assert!(output.contains("trading"), "{}", output);
+        assert!(output.contains("AAPL ⇄ Apple"), "{}", output);
+        assert!(output.contains("car ⇄ automobile"), "{}", output);
+        assert!(!list(Some("trading")).contains("car"));
+
+        let remove = |namespace: &str| {
+            run(
+                &manager,
+                SynonymCommands::Remove {
+                    term: "Apple".to_string(),
+                    synonym: "AAPL".to_string(),
+                    namespace: namespace.to_string(),
+                },
+            )
+        };
+        assert!(remove(DEFAULT_NAMESPACE).is_err());
+        assert!(remove("trading").unwrap().contains("Removed"));
+        assert!(!list(None).contains("AAPL"));
+    }
+}
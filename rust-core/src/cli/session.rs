@@ -0,0 +1,843 @@
+//! `memex session ...` command handlers
+
+#[cfg(feature = "vector-search")]
+use anyhow::Context;
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::cli::{format_duration, InteractiveCli};
+use crate::core::session::SessionManager;
+use crate::database::models::{Session, SessionStatus, UserActivity};
+use crate::display::wrap_text;
+
+#[derive(Subcommand, Debug)]
+pub enum SessionCommands {
+    /// Create a new session
+    Create {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Session name
+        #[arg(short, long)]
+        name: Option<String>,
+    },
+    /// List sessions for a user
+    List {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Limit results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        /// Only sessions with this status: active, closed or archived
+        #[arg(long, conflicts_with = "all")]
+        status: Option<SessionStatus>,
+        /// Include archived sessions
+        #[arg(long)]
+        all: bool,
+    },
+    /// Mark a session active, closed or archived
+    Status {
+        /// Session ID
+        id: String,
+        /// New status: active, closed or archived
+        status: SessionStatus,
+    },
+    /// Generate session summary
+    Summary {
+        /// Session ID
+        id: String,
+    },
+    /// Search sessions
+    Search {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Search keywords
+        keywords: Vec<String>,
+        /// Search by meaning instead: query embedding as JSON array
+        #[cfg(feature = "vector-search")]
+        #[arg(long, conflicts_with = "keywords", requires = "model")]
+        embedding: Option<String>,
+        /// Model whose embeddings to compare against
+        #[cfg(feature = "vector-search")]
+        #[arg(short, long)]
+        model: Option<String>,
+    },
+    /// Delete session
+    Delete {
+        /// Session ID
+        id: String,
+        /// Also delete memories
+        #[arg(long)]
+        delete_memories: bool,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+    },
+    /// Print a session's memories in conversation order
+    Transcript {
+        /// Session ID
+        id: String,
+        /// First position to show
+        #[arg(long)]
+        from: Option<i64>,
+        /// Last position to show
+        #[arg(long)]
+        to: Option<i64>,
+    },
+    /// Move a memory to another position in its session's transcript
+    Move {
+        /// Memory ID
+        memory_id: String,
+        /// New position; memories in between shift to make room
+        #[arg(long)]
+        to: i64,
+    },
+    /// Show session analytics
+    Analytics {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+    },
+}
+
+pub fn handle(
+    action: SessionCommands,
+    manager: &SessionManager,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        SessionCommands::Create { user, name } => {
+            let session_id = manager.create_session(&user, name.clone())?;
+
+            writeln!(out, "{}", "✓ Session created successfully".green())?;
+            writeln!(out, "  ID: {}", session_id.bright_blue())?;
+            writeln!(out, "  User: {}", user)?;
+            if let Some(n) = name {
+                writeln!(out, "  Name: {}", n)?;
+            }
+        }
+
+        SessionCommands::List {
+            user,
+            limit,
+            status,
+            all,
+        } => {
+            let statuses = match status {
+                Some(status) => vec![status],
+                None if all => SessionStatus::ALL.to_vec(),
+                None => SessionStatus::LISTED.to_vec(),
+            };
+            let response =
+                manager.get_user_sessions_with_status(&user, &statuses, Some(limit), Some(0))?;
+
+            if response.data.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No sessions found for user: {}", user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "Sessions for {} ({}/{})",
+                    user,
+                    response.data.len(),
+                    response.total_count
+                )
+                .green()
+            )?;
+            writeln!(out)?;
+
+            for session in &response.data {
+                print_session(out, session)?;
+            }
+        }
+
+        SessionCommands::Summary { id } => match manager.generate_session_summary(&id) {
+            Ok(summary) => {
+                writeln!(out, "{}", "Session Summary".green().bold())?;
+                writeln!(out, "Session ID: {}", summary.session_id.bright_blue())?;
+                writeln!(out, "Memory count: {}", summary.memory_count)?;
+                writeln!(out, "Importance score: {:.2}", summary.importance_score)?;
+                writeln!(
+                    out,
+                    "Version: {} ({}, generated {})",
+                    summary.version,
+                    summary.generator,
+                    summary.generated_at.format("%Y-%m-%d %H:%M")
+                )?;
+                writeln!(
+                    out,
+                    "Date range: {} to {}",
+                    summary.date_range.0.format("%Y-%m-%d"),
+                    summary.date_range.1.format("%Y-%m-%d")
+                )?;
+
+                if !summary.key_topics.is_empty() {
+                    writeln!(
+                        out,
+                        "Key topics: {}",
+                        summary.key_topics.join(", ").bright_yellow()
+                    )?;
+                }
+
+                writeln!(out, "\n{}", "Summary:".bold())?;
+                writeln!(out, "{}", summary.summary_text)?;
+            }
+            Err(e) => {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("Failed to generate summary: {}", e).red()
+                )?;
+            }
+        },
+
+        #[cfg(feature = "vector-search")]
+        SessionCommands::Search {
+            user,
+            embedding: Some(embedding),
+            model: Some(model),
+            ..
+        } => {
+            let embedding_vec: Vec<f32> =
+                serde_json::from_str(&embedding).context("Invalid embedding JSON")?;
+            let results = manager.search_sessions_semantic(&user, &embedding_vec, &model, None)?;
+
+            if results.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No sessions with {} embeddings for user: {}", model, user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "Found {} sessions by {} similarity",
+                    results.len(),
+                    results[0].metric
+                )
+                .green()
+            )?;
+            writeln!(out)?;
+
+            for result in &results {
+                writeln!(
+                    out,
+                    "🗂️  {} {}",
+                    result.session_id.bright_blue(),
+                    result.name.as_deref().unwrap_or("(unnamed)")
+                )?;
+                writeln!(
+                    out,
+                    "    {} embedded memories | Similarity: {:.3}",
+                    result.memory_count.to_string().bright_green(),
+                    result.similarity
+                )?;
+                writeln!(out)?;
+            }
+        }
+
+        SessionCommands::Search { user, keywords, .. } => {
+            let sessions = manager.search_sessions(&user, keywords.clone())?;
+
+            if sessions.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No sessions found for keywords: {}", keywords.join(" ")).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "Found {} sessions matching: {}",
+                    sessions.len(),
+                    keywords.join(" ")
+                )
+                .green()
+            )?;
+            writeln!(out)?;
+
+            for session in &sessions {
+                print_session(out, session)?;
+            }
+        }
+
+        SessionCommands::Delete {
+            id,
+            delete_memories,
+            yes,
+        } => {
+            let prompt = format!(
+                "Delete session {}{}?",
+                id,
+                if delete_memories {
+                    " and all its memories"
+                } else {
+                    ""
+                }
+            );
+            if !yes && !InteractiveCli::confirm(&prompt, false)? {
+                writeln!(out, "Cancelled")?;
+                return Ok(());
+            }
+
+            if manager.delete_session(&id, delete_memories)? {
+                writeln!(out, "{}", "✓ Session deleted successfully".green())?;
+            } else {
+                writeln!(out, "{}", format!("Session not found: {}", id).yellow())?;
+            }
+        }
+
+        SessionCommands::Status { id, status } => {
+            if manager.set_session_status(&id, status)? {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Session {} is now {}", id, status).green()
+                )?;
+            } else {
+                writeln!(out, "{}", format!("Session not found: {}", id).yellow())?;
+            }
+        }
+
+        SessionCommands::Transcript { id, from, to } => {
+            let memories = manager.get_transcript(&id, from, to)?;
+            if memories.is_empty() {
+                writeln!(out, "No memories in session {}", id)?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", format!("📜 Transcript of {}", id).green().bold())?;
+            for memory in &memories {
+                writeln!(
+                    out,
+                    "{} {} {}",
+                    format!("#{}", memory.seq).bright_yellow(),
+                    memory
+                        .created_at
+                        .format("%Y-%m-%d %H:%M")
+                        .to_string()
+                        .dimmed(),
+                    memory.id.bright_blue()
+                )?;
+                for line in wrap_text(&memory.content, 76) {
+                    writeln!(out, "  {}", line)?;
+                }
+            }
+        }
+
+        SessionCommands::Move { memory_id, to } => match manager.move_memory(&memory_id, to)? {
+            Some(seq) => writeln!(
+                out,
+                "{}",
+                format!("✓ Moved {} to position {}", memory_id, seq).green()
+            )?,
+            None => writeln!(
+                out,
+                "{}",
+                format!("Memory not found: {}", memory_id).yellow()
+            )?,
+        },
+
+        SessionCommands::Analytics { user } => {
+            let analytics = manager.get_session_analytics(&user)?;
+
+            writeln!(
+                out,
+                "{}",
+                format!("Session Analytics for {}", user).green().bold()
+            )?;
+            writeln!(
+                out,
+                "Total sessions: {}",
+                analytics.total_sessions.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Total memories: {}",
+                analytics.total_memories.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Avg memories per session: {:.1}",
+                analytics.avg_memories_per_session
+            )?;
+
+            if let Some(most_active) = &analytics.most_active_session {
+                writeln!(out, "\n{}", "Most Active Session:".bold())?;
+                writeln!(
+                    out,
+                    "  {} {} ({} memories)",
+                    most_active.id.bright_blue(),
+                    most_active.name.as_deref().unwrap_or("(unnamed)"),
+                    most_active.memory_count
+                )?;
+            }
+
+            if let Some(most_recent) = &analytics.most_recent_session {
+                writeln!(out, "\n{}", "Most Recent Session:".bold())?;
+                writeln!(
+                    out,
+                    "  {} {} ({})",
+                    most_recent.id.bright_blue(),
+                    most_recent.name.as_deref().unwrap_or("(unnamed)"),
+                    most_recent.last_active.format("%Y-%m-%d %H:%M")
+                )?;
+            }
+
+            if !analytics.activity_by_day.is_empty() {
+                writeln!(out, "\n{}", "Recent Activity:".bold())?;
+                let mut sorted_activity: Vec<_> = analytics.activity_by_day.iter().collect();
+                sorted_activity.sort_by_key(|(date, _)| *date);
+
+                for (date, count) in sorted_activity.iter().rev().take(7) {
+                    writeln!(out, "  {}: {} memories", date, count)?;
+                }
+            }
+
+            print_activity(out, &analytics.activity)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Heat shades for activity cells, empty first
+const HEAT_LEVELS: [char; 5] = ['·', '░', '▒', '▓', '█'];
+
+/// Weekday-by-hour heat grid followed by streaks, the longest gap and
+/// per-session importance
+fn print_activity(out: &mut dyn Write, activity: &UserActivity) -> Result<()> {
+    if activity.by_hour.iter().all(|&count| count == 0) {
+        return Ok(());
+    }
+    let max_cell = activity
+        .heatmap
+        .iter()
+        .flatten()
+        .copied()
+        .max()
+        .unwrap_or(0)
+        .max(1);
+
+    writeln!(out, "\n{}", "Activity by weekday and hour (UTC):".bold())?;
+    writeln!(out, "     {}", "0     6     12    18    ".dimmed())?;
+    let weekdays = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"];
+    for ((weekday, hours), total) in weekdays
+        .iter()
+        .zip(&activity.heatmap)
+        .zip(activity.by_weekday)
+    {
+        let cells: String = hours
+            .iter()
+            .map(|&count| HEAT_LEVELS[(count * (HEAT_LEVELS.len() - 1)).div_ceil(max_cell)])
+            .collect();
+        writeln!(out, "{}  {}  {}", weekday, cells.bright_green(), total)?;
+    }
+
+    writeln!(
+        out,
+        "\nStreak: {} days (longest {})",
+        activity.current_streak_days, activity.longest_streak_days
+    )?;
+    if let Some(gap) = activity
+        .longest_gap
+        .as_ref()
+        .filter(|gap| gap.duration() > chrono::Duration::zero())
+    {
+        writeln!(
+            out,
+            "Longest gap: {} ({} to {})",
+            format_duration(gap.duration().num_seconds()),
+            gap.from.format("%Y-%m-%d %H:%M"),
+            gap.to.format("%Y-%m-%d %H:%M")
+        )?;
+    }
+
+    let mut sessions: Vec<_> = activity.avg_importance_by_session.iter().collect();
+    sessions.sort_by(|a, b| b.1.total_cmp(a.1).then(a.0.cmp(b.0)));
+    if !sessions.is_empty() {
+        writeln!(out, "\n{}", "Average importance by session:".bold())?;
+        for (session_id, importance) in sessions.iter().take(5) {
+            writeln!(out, "  {} {:.2}", session_id.bright_blue(), importance)?;
+        }
+    }
+    Ok(())
+}
+
+fn print_session(out: &mut dyn Write, session: &Session) -> Result<()> {
+    write!(
+        out,
+        "🗂️  {} {}",
+        session.id.bright_blue(),
+        session.name.as_deref().unwrap_or("(unnamed)")
+    )?;
+    if session.status != SessionStatus::Active {
+        write!(out, " {}", format!("[{}]", session.status).dimmed())?;
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    {} memories | Last active: {}",
+        session.memory_count.to_string().bright_green(),
+        session.last_active.format("%Y-%m-%d %H:%M")
+    )?;
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (SessionManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (SessionManager::new(database, validator), temp_dir)
+    }
+
+    fn run(manager: &SessionManager, action: SessionCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, manager, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_create_and_list() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let output = run(
+            &manager,
+            SessionCommands::Create {
+                user: "user1".to_string(),
+                name: Some("Trading".to_string()),
+            },
+        );
+        assert!(output.contains("Session created successfully"));
+        assert!(output.contains("Name: Trading"));
+
+        let output = run(
+            &manager,
+            SessionCommands::List {
+                user: "user1".to_string(),
+                limit: 10,
+                status: None,
+                all: false,
+            },
+        );
+        assert!(output.contains("Trading"));
+    }
+
+    #[test]
+    fn test_list_empty() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let output = run(
+            &manager,
+            SessionCommands::List {
+                user: "nobody".to_string(),
+                limit: 10,
+                status: None,
+                all: false,
+            },
+        );
+        assert!(output.contains("No sessions found for user: nobody"));
+    }
+
+    #[test]
+    fn test_status_and_archived_listing() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let id = manager
+            .create_session("user1", Some("Trading".to_string()))
+            .unwrap();
+        let list = |status: Option<SessionStatus>, all: bool| {
+            run(
+                &manager,
+                SessionCommands::List {
+                    user: "user1".to_string(),
+                    limit: 10,
+                    status,
+                    all,
+                },
+            )
+        };
+
+        let output = run(
+            &manager,
+            SessionCommands::Status {
+                id: id.clone(),
+                status: SessionStatus::Archived,
+            },
+        );
+        assert!(output.contains(&format!("✓ Session {} is now archived", id)));
+        assert!(list(None, false).contains("No sessions found"));
+        assert!(list(None, true).contains("Trading [archived]"));
+        assert!(list(Some(SessionStatus::Archived), false).contains("Trading"));
+        assert!(list(Some(SessionStatus::Closed), false).contains("No sessions found"));
+
+        let output = run(
+            &manager,
+            SessionCommands::Status {
+                id: "missing".to_string(),
+                status: SessionStatus::Closed,
+            },
+        );
+        assert!(output.contains("Session not found: missing"));
+    }
+
+    #[test]
+    fn test_analytics() {
+        let (manager, _temp_dir) = setup_test_manager();
+        manager
+            .create_session("user1", Some("Research".to_string()))
+            .unwrap();
+
+        let output = run(
+            &manager,
+            SessionCommands::Analytics {
+                user: "user1".to_string(),
+            },
+        );
+        assert!(output.contains("Session Analytics for user1"));
+        assert!(output.contains("Research"));
+        assert!(!output.contains("Activity by weekday"));
+    }
+
+    #[test]
+    fn test_analytics_heatmap() {
+        use crate::database::models::MemoryItem;
+
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let manager = SessionManager::new(
+            database.clone(),
+            RequestValidator::new(&MemexConfig::default()),
+        );
+
+        let created_at = chrono::Utc::now();
+        for importance in [0.2, 0.6] {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "research".to_string(),
+                    content: "Read the paper".to_string(),
+                    importance,
+                    created_at,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = run(
+            &manager,
+            SessionCommands::Analytics {
+                user: "user1".to_string(),
+            },
+        );
+        assert!(
+            output.contains("Activity by weekday and hour"),
+            "{}",
+            output
+        );
+        assert_eq!(output.matches('█').count(), 1, "{}", output);
+        assert!(output.contains("Streak: 1 days (longest 1)"), "{}", output);
+        assert!(output.contains("research 0.40"), "{}", output);
+    }
+
+    #[test]
+    fn test_transcript_and_move() {
+        use crate::database::models::MemoryItem;
+
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let manager = SessionManager::new(
+            database.clone(),
+            RequestValidator::new(&MemexConfig::default()),
+        );
+
+        // Same timestamp for all; only seq orders them
+        let created_at = chrono::Utc::now();
+        let ids: Vec<String> = ["first", "second", "third"]
+            .iter()
+            .map(|content| {
+                database
+                    .save_memory(&MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "chat".to_string(),
+                        content: content.to_string(),
+                        created_at,
+                        ..Default::default()
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        let output = run(
+            &manager,
+            SessionCommands::Transcript {
+                id: "chat".to_string(),
+                from: None,
+                to: None,
+            },
+        );
+        let first = output.find("first").unwrap();
+        let second = output.find("second").unwrap();
+        let third = output.find("third").unwrap();
+        assert!(first < second && second < third, "{}", output);
+        assert!(output.contains("#3"), "{}", output);
+
+        let output = run(
+            &manager,
+            SessionCommands::Move {
+                memory_id: ids[2].clone(),
+                to: 1,
+            },
+        );
+        assert!(output.contains("to position 1"), "{}", output);
+
+        let output = run(
+            &manager,
+            SessionCommands::Transcript {
+                id: "chat".to_string(),
+                from: Some(1),
+                to: Some(2),
+            },
+        );
+        assert!(output.find("third").unwrap() < output.find("first").unwrap());
+        assert!(!output.contains("second"), "{}", output);
+
+        // Re-saving keeps the moved position
+        let mut moved = database.get_memory(&ids[2]).unwrap().unwrap();
+        moved.content = "third, edited".to_string();
+        database.save_memory(&moved).unwrap();
+        assert_eq!(database.get_memory(&ids[2]).unwrap().unwrap().seq, 1);
+
+        assert!(run(
+            &manager,
+            SessionCommands::Move {
+                memory_id: "missing".to_string(),
+                to: 1,
+            },
+        )
+        .contains("Memory not found"));
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_semantic_search() {
+        use crate::database::models::MemoryItem;
+        use crate::database::vector::{VectorConfig, VectorSearchEngine};
+
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let engine = VectorSearchEngine::new(
+            database.get_connection_pool(),
+            VectorConfig {
+                dimension: 2,
+                ..Default::default()
+            },
+        );
+        engine.initialize_schema().unwrap();
+
+        let mut manager = SessionManager::new(
+            database.clone(),
+            RequestValidator::new(&MemexConfig::default()),
+        );
+        manager.enable_vector_search(engine.clone());
+
+        for (name, embedding) in [("Trading", [1.0, 0.0]), ("Cooking", [0.0, 1.0])] {
+            let session_id = manager
+                .create_session("user1", Some(name.to_string()))
+                .unwrap();
+            let memory_id = database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id,
+                    content: format!("{} notes", name),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &embedding, "model")
+                .unwrap();
+        }
+
+        let output = run(
+            &manager,
+            SessionCommands::Search {
+                user: "user1".to_string(),
+                keywords: Vec::new(),
+                embedding: Some("[0.9, 0.1]".to_string()),
+                model: Some("model".to_string()),
+            },
+        );
+        assert!(output.contains("Found 2 sessions by cosine similarity"));
+        assert!(output.find("Trading").unwrap() < output.find("Cooking").unwrap());
+
+        let output = run(
+            &manager,
+            SessionCommands::Search {
+                user: "user2".to_string(),
+                keywords: Vec::new(),
+                embedding: Some("[0.9, 0.1]".to_string()),
+                model: Some("model".to_string()),
+            },
+        );
+        assert!(output.contains("No sessions with model embeddings for user: user2"));
+    }
+}
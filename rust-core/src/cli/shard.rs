@@ -0,0 +1,157 @@
+//! `memex shard ...` command handlers
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::cli::format_bytes;
+use crate::database::sharded::ShardedDatabase;
+
+#[derive(Subcommand, Debug)]
+pub enum ShardCommands {
+    /// List shard files with their size and memory count
+    List,
+    /// Show which shard holds a user's data
+    Locate {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+    },
+    /// Remove expired memories from every shard
+    Cleanup,
+}
+
+pub fn handle(action: ShardCommands, sharded: &ShardedDatabase, out: &mut dyn Write) -> Result<()> {
+    match action {
+        ShardCommands::List => {
+            writeln!(
+                out,
+                "{}",
+                format!("🗂 Shards in {}", sharded.config().directory)
+                    .green()
+                    .bold()
+            )?;
+
+            let shards = sharded.list_shards()?;
+            if shards.is_empty() {
+                writeln!(out, "No shards yet; they are created on first save")?;
+                return Ok(());
+            }
+
+            let mut total_memories = 0;
+            let mut total_bytes = 0;
+            for shard in &shards {
+                let memories = sharded.open_shard(&shard.name)?.get_stats()?["total_memories"]
+                    .as_i64()
+                    .unwrap_or(0);
+                total_memories += memories;
+                total_bytes += shard.size_bytes;
+                writeln!(
+                    out,
+                    "  {:<32}  {:>9} memories  {:>10}",
+                    shard.name,
+                    memories,
+                    format_bytes(shard.size_bytes)
+                )?;
+            }
+            writeln!(
+                out,
+                "\n{} shards, {} memories, {}",
+                shards.len(),
+                total_memories,
+                format_bytes(total_bytes)
+            )?;
+        }
+
+        ShardCommands::Locate { user } => {
+            let name = sharded.shard_name(&user);
+            let exists = sharded
+                .list_shards()?
+                .iter()
+                .any(|shard| shard.name == name);
+            writeln!(out, "User: {}", user.bright_blue())?;
+            writeln!(out, "Shard: {}", name)?;
+            writeln!(
+                out,
+                "File: {}/{}.db{}",
+                sharded.config().directory,
+                name,
+                if exists {
+                    String::new()
+                } else {
+                    " (not created yet)".dimmed().to_string()
+                }
+            )?;
+        }
+
+        ShardCommands::Cleanup => {
+            let removed = sharded.cleanup_expired()?;
+            writeln!(
+                out,
+                "{}",
+                format!("✓ Removed {} expired memories across shards", removed).green()
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::sharded::{ShardStrategy, ShardedDatabaseConfig};
+    use tempfile::TempDir;
+
+    fn run(sharded: &ShardedDatabase, action: ShardCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, sharded, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_list_and_locate() {
+        let temp_dir = TempDir::new().unwrap();
+        let sharded = ShardedDatabase::new(ShardedDatabaseConfig {
+            directory: temp_dir.path().to_string_lossy().to_string(),
+            strategy: ShardStrategy::PerUser,
+            ..Default::default()
+        })
+        .unwrap();
+
+        assert!(run(&sharded, ShardCommands::List).contains("No shards yet"));
+        let output = run(
+            &sharded,
+            ShardCommands::Locate {
+                user: "alice".to_string(),
+            },
+        );
+        assert!(output.contains("Shard: alice"));
+        assert!(output.contains("not created yet"));
+
+        for content in ["One", "Two"] {
+            sharded
+                .save_memory(&MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "s".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = run(&sharded, ShardCommands::List);
+        assert!(output.contains("alice"), "{}", output);
+        assert!(output.contains("2 memories"), "{}", output);
+        assert!(output.contains("1 shards, 2 memories"), "{}", output);
+        assert!(!run(
+            &sharded,
+            ShardCommands::Locate {
+                user: "alice".to_string()
+            }
+        )
+        .contains("not created yet"));
+    }
+}
@@ -0,0 +1,298 @@
+//! `memex summaries ...` command handlers
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::cli::parse_duration;
+use crate::core::session::SessionManager;
+use crate::database::models::{SessionStatus, Summary, SummaryGranularity};
+use crate::display::wrap_text;
+
+#[derive(Subcommand, Debug)]
+pub enum SummaryCommands {
+    /// List stored summaries, or those fitting a token budget
+    List {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Only this granularity: session, day or topic
+        #[arg(short, long, conflicts_with = "budget")]
+        granularity: Option<SummaryGranularity>,
+        /// Pick the finest granularity whose summaries fit in this many tokens
+        #[arg(long)]
+        budget: Option<usize>,
+    },
+    /// Generate summaries missing or stale at a granularity
+    Generate {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+        /// Granularity: session, day or topic
+        #[arg(short, long)]
+        granularity: SummaryGranularity,
+        /// Only days this far back, e.g. 7d or 4w
+        #[arg(long, value_parser = parse_duration)]
+        since: Option<chrono::Duration>,
+        /// Number of topics (picked automatically if omitted)
+        #[arg(short, long)]
+        k: Option<usize>,
+    },
+}
+
+pub fn handle(
+    action: SummaryCommands,
+    manager: &SessionManager,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        SummaryCommands::List {
+            user,
+            budget: Some(budget),
+            ..
+        } => {
+            let selection = manager.summaries_for_budget(&user, budget)?;
+            if selection.summaries.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No summaries fit in {} tokens for user: {}", budget, user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "{} summaries for {} (~{} of {} tokens{})",
+                    selection.granularity,
+                    user,
+                    selection.tokens,
+                    budget,
+                    if selection.truncated {
+                        ", truncated"
+                    } else {
+                        ""
+                    }
+                )
+                .green()
+            )?;
+            writeln!(out)?;
+            for summary in &selection.summaries {
+                print_summary(out, summary)?;
+            }
+        }
+
+        SummaryCommands::List {
+            user, granularity, ..
+        } => {
+            let summaries = manager.list_summaries(&user, granularity)?;
+            if summaries.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No summaries stored for user: {}", user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("Summaries for {} ({})", user, summaries.len()).green()
+            )?;
+            writeln!(out)?;
+            for summary in &summaries {
+                print_summary(out, summary)?;
+            }
+        }
+
+        SummaryCommands::Generate {
+            user,
+            granularity,
+            since,
+            k,
+        } => {
+            let summaries = match granularity {
+                SummaryGranularity::Session => {
+                    let sessions = manager.get_user_sessions_with_status(
+                        &user,
+                        &SessionStatus::ALL,
+                        None,
+                        None,
+                    )?;
+                    let mut summaries = Vec::new();
+                    for session in sessions.data.iter().filter(|s| s.memory_count > 0) {
+                        summaries.push(Summary::from(
+                            manager.generate_session_summary(&session.id)?,
+                        ));
+                    }
+                    summaries
+                }
+                SummaryGranularity::Day => manager
+                    .generate_day_summaries(&user, since.map(|since| chrono::Utc::now() - since))?,
+                SummaryGranularity::Topic => manager.generate_topic_summaries(&user, k)?,
+            };
+
+            if summaries.is_empty() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("No memories to summarize for user: {}", user).yellow()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!(
+                    "✓ {} {} summaries for {}",
+                    summaries.len(),
+                    granularity,
+                    user
+                )
+                .green()
+            )?;
+            writeln!(out)?;
+            for summary in &summaries {
+                print_summary(out, summary)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_summary(out: &mut dyn Write, summary: &Summary) -> Result<()> {
+    write!(
+        out,
+        "📝 {} {}",
+        format!("[{}]", summary.granularity).dimmed(),
+        summary.key.bright_blue()
+    )?;
+    if summary.stale {
+        write!(out, " {}", "(stale)".yellow())?;
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "    {} memories | {} to {} | v{} ({}) | ~{} tokens",
+        summary.memory_count.to_string().bright_green(),
+        summary.date_range.0.format("%Y-%m-%d"),
+        summary.date_range.1.format("%Y-%m-%d"),
+        summary.version,
+        summary.generator,
+        summary.count_tokens()
+    )?;
+    for line in wrap_text(&summary.summary_text, 76) {
+        writeln!(out, "    {}", line)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (SessionManager, Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (
+            SessionManager::new(database.clone(), validator),
+            database,
+            temp_dir,
+        )
+    }
+
+    fn run(manager: &SessionManager, action: SummaryCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, manager, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_generate_and_list_by_granularity() {
+        let (manager, database, _temp_dir) = setup_test_manager();
+        for (session, content) in [
+            ("s1", "Bought shares of the index fund"),
+            ("s1", "Index fund fees are low"),
+            ("s2", "Planned the hiking trip to the lakes"),
+            ("s2", "Hiking boots need replacing before the trip"),
+        ] {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: session.to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let generate = |granularity| {
+            run(
+                &manager,
+                SummaryCommands::Generate {
+                    user: "user1".to_string(),
+                    granularity,
+                    since: None,
+                    k: Some(2),
+                },
+            )
+        };
+
+        assert!(generate(SummaryGranularity::Session).contains("✓ 2 session summaries"));
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let output = generate(SummaryGranularity::Day);
+        assert!(output.contains("✓ 1 day summaries"));
+        assert!(output.contains(&today));
+        assert!(generate(SummaryGranularity::Topic).contains("✓ 2 topic summaries"));
+
+        let output = run(
+            &manager,
+            SummaryCommands::List {
+                user: "user1".to_string(),
+                granularity: None,
+                budget: None,
+            },
+        );
+        assert!(output.contains("Summaries for user1 (5)"));
+
+        // Too small for even one summary
+        let output = run(
+            &manager,
+            SummaryCommands::List {
+                user: "user1".to_string(),
+                granularity: None,
+                budget: Some(1),
+            },
+        );
+        assert!(output.contains("No summaries fit in 1 tokens"));
+        let output = run(
+            &manager,
+            SummaryCommands::List {
+                user: "user1".to_string(),
+                granularity: None,
+                budget: Some(100_000),
+            },
+        );
+        assert!(output.contains("session summaries for user1"));
+    }
+}
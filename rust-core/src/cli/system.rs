@@ -0,0 +1,1004 @@
+//! `memex system ...` command handlers
+
+use anyhow::Result;
+use chrono::Utc;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+use validator::Validate;
+
+use crate::cli::{format_bytes, parse_duration, wrap_text, CliContext};
+use crate::core::anomaly::{AnomalyMonitor, IngestionRate};
+use crate::database::audit::AuditFilter;
+use crate::database::schema;
+use crate::database::stats_history::StatsSnapshot;
+use crate::display::truncate_graphemes;
+
+#[derive(Subcommand, Debug)]
+pub enum SystemCommands {
+    /// Show system health
+    Health,
+    /// Show performance metrics
+    Performance,
+    /// Show system information
+    Info,
+    /// Run system diagnostics
+    Diagnostics,
+    /// Show queries recorded by the slow-query log
+    SlowQueries {
+        /// Number of entries to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+        /// Delete all recorded entries instead of showing them
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Recommend composite indexes for the recall filters seen so far
+    IndexAdvisor {
+        /// Ignore filter shapes recalled fewer times than this
+        #[arg(long, default_value = "10")]
+        min_recalls: u64,
+        /// Create the recommended indexes
+        #[arg(long, conflicts_with = "reset")]
+        apply: bool,
+        /// Forget the recorded filter shapes instead
+        #[arg(long)]
+        reset: bool,
+    },
+    /// Show database statistics and their recorded history
+    Stats {
+        /// Chart the recorded snapshots instead of the current numbers
+        #[arg(long)]
+        history: bool,
+        /// How far back the history goes, e.g. 12h, 7d or 4w
+        #[arg(long, default_value = "7d", value_parser = parse_duration)]
+        since: chrono::Duration,
+        /// Record a snapshot of the current numbers first
+        #[arg(long)]
+        snapshot: bool,
+    },
+    /// Compare each user's saves in the last hour against their baseline
+    Anomalies {
+        /// Standard deviations above the baseline that count as a spike
+        /// (defaults to the configured sensitivity)
+        #[arg(short, long)]
+        sensitivity: Option<f32>,
+        /// List every active user, not just the spiking ones
+        #[arg(long)]
+        all: bool,
+    },
+    /// Show writes recorded in the audit log, newest first
+    Audit {
+        /// Only writes by this actor
+        #[arg(long)]
+        actor: Option<String>,
+        /// Only writes made under this trace ID
+        #[arg(long)]
+        trace_id: Option<String>,
+        /// Only writes to this memory or session
+        #[arg(long)]
+        target: Option<String>,
+        /// Number of entries to show
+        #[arg(short, long, default_value = "20")]
+        limit: usize,
+    },
+}
+
+pub fn handle(action: SystemCommands, context: &CliContext, out: &mut dyn Write) -> Result<()> {
+    match action {
+        SystemCommands::Health => {
+            writeln!(out, "{}", "🏥 System Health Check".green().bold())?;
+
+            match context.database.get_stats() {
+                Ok(stats) => {
+                    writeln!(out, "✓ Database: {}", "Healthy".green())?;
+
+                    let wal_size = stats["wal_size_bytes"].as_u64().unwrap_or(0);
+                    let threshold = stats["wal_truncate_threshold_bytes"].as_u64().unwrap_or(0);
+                    if threshold > 0 && wal_size >= threshold {
+                        writeln!(
+                            out,
+                            "✗ WAL: {} - {} (truncates at {}); run `memex database checkpoint`",
+                            "Oversized".yellow(),
+                            format_bytes(wal_size),
+                            format_bytes(threshold)
+                        )?;
+                    } else {
+                        writeln!(out, "✓ WAL: {}", format_bytes(wal_size))?;
+                    }
+                }
+                Err(e) => writeln!(out, "✗ Database: {} - {}", "Error".red(), e)?,
+            }
+
+            match context.config.validate() {
+                Ok(_) => writeln!(out, "✓ Configuration: {}", "Valid".green())?,
+                Err(e) => writeln!(out, "✗ Configuration: {} - {}", "Invalid".red(), e)?,
+            }
+
+            if context.database.get_pool_status().is_healthy() {
+                writeln!(out, "✓ Connection pool: {}", "Healthy".green())?;
+            } else {
+                writeln!(out, "✗ Connection pool: {}", "Degraded".yellow())?;
+            }
+
+            let monitor =
+                AnomalyMonitor::new(context.database.clone(), context.config.anomaly_policy());
+            match monitor.check() {
+                Ok(anomalies) if anomalies.is_empty() => {
+                    writeln!(out, "✓ Ingestion rate: {}", "Normal".green())?
+                }
+                Ok(anomalies) => writeln!(
+                    out,
+                    "✗ Ingestion rate: {} - {} user(s) spiking; see `memex system anomalies`",
+                    "Anomalous".yellow(),
+                    anomalies.len()
+                )?,
+                Err(e) => writeln!(out, "✗ Ingestion rate: {} - {}", "Error".red(), e)?,
+            }
+
+            match context.maintenance_scheduler().backup_status(Utc::now()) {
+                Ok(None) => {}
+                Ok(Some(status)) => match &status.latest {
+                    Some(latest) if !status.overdue => writeln!(
+                        out,
+                        "✓ Backups: last {} ({} kept)",
+                        latest.created_at.format("%Y-%m-%d %H:%M UTC"),
+                        status.count
+                    )?,
+                    Some(latest) => writeln!(
+                        out,
+                        "✗ Backups: {} - last {}; run `memex maintenance run backup`",
+                        "Overdue".yellow(),
+                        latest.created_at.format("%Y-%m-%d %H:%M UTC")
+                    )?,
+                    None => writeln!(
+                        out,
+                        "✗ Backups: {} - none in {}; run `memex maintenance run backup`",
+                        "Overdue".yellow(),
+                        status.directory
+                    )?,
+                },
+                Err(e) => writeln!(out, "✗ Backups: {} - {}", "Error".red(), e)?,
+            }
+        }
+
+        SystemCommands::Performance => {
+            writeln!(out, "{}", "⚡ Performance Metrics".green().bold())?;
+
+            let metrics = context.memory.get_performance_metrics();
+            writeln!(out, "Avg query time: {:.2}ms", metrics.avg_query_time_ms)?;
+            writeln!(out, "Avg save time: {:.2}ms", metrics.avg_save_time_ms)?;
+            if let Some(tier) = metrics.hot_tier.filter(|tier| tier.budget_bytes > 0) {
+                writeln!(
+                    out,
+                    "Hot tier: {} memories, {} of {} ({:.0}% hit rate, {} hits, {} misses, {} evicted)",
+                    tier.entries,
+                    format_bytes(tier.bytes),
+                    format_bytes(tier.budget_bytes),
+                    tier.hit_rate() * 100.0,
+                    tier.hits,
+                    tier.misses,
+                    tier.evictions
+                )?;
+            } else {
+                writeln!(out, "Hot tier: off")?;
+            }
+
+            let pool_status = context.database.get_pool_status();
+            writeln!(
+                out,
+                "Write pool: {}/{} connections ({:.0}% utilized)",
+                pool_status.write_pool.connections,
+                pool_status.write_pool.max_connections,
+                pool_status.write_pool.utilization() * 100.0
+            )?;
+            writeln!(
+                out,
+                "Read pools: {} ({:.0}% overall utilization)",
+                pool_status.read_pools.len(),
+                pool_status.overall_utilization() * 100.0
+            )?;
+            writeln!(
+                out,
+                "Busy write retries: {}",
+                pool_status.write_pool.busy_retries
+            )?;
+            writeln!(
+                out,
+                "Recycled connections: {} ({} failed health checks, {} connection errors)",
+                pool_status.write_pool.recycled_connections,
+                pool_status.write_pool.health_check_failures,
+                pool_status.write_pool.connection_errors
+            )?;
+        }
+
+        SystemCommands::Info => {
+            writeln!(out, "{}", "ℹ️ System Information".green().bold())?;
+
+            writeln!(out, "Version: {}", env!("CARGO_PKG_VERSION"))?;
+            writeln!(out, "Database path: {}", context.config.database_path)?;
+            writeln!(out, "Auto decay: {}", context.config.auto_decay_enabled)?;
+            writeln!(out, "Compression: {}", context.config.enable_compression)?;
+            writeln!(
+                out,
+                "Max memories per user: {}",
+                context.config.max_memories_per_user
+            )?;
+            writeln!(
+                out,
+                "Importance threshold: {}",
+                context.config.importance_threshold
+            )?;
+
+            match context.database.maintenance_lock_holder()? {
+                Some(holder) => writeln!(
+                    out,
+                    "Maintenance lock: pid {} on {}{} (since {}, heartbeat {})",
+                    holder.pid,
+                    holder.hostname,
+                    if holder.instance_id == context.database.instance_id() {
+                        " (this process)"
+                    } else {
+                        ""
+                    },
+                    holder.acquired_at.format("%Y-%m-%d %H:%M:%S"),
+                    holder.heartbeat_at.format("%Y-%m-%d %H:%M:%S")
+                )?,
+                None => writeln!(out, "Maintenance lock: {}", "free".green())?,
+            }
+        }
+
+        SystemCommands::Diagnostics => {
+            writeln!(out, "{}", "🔍 Running System Diagnostics".green().bold())?;
+
+            let mut failures = 0;
+
+            let config_check = context.config.validate().map_err(anyhow::Error::from);
+            failures += report(out, "Configuration validation", config_check)?;
+
+            let stats_check = context.database.get_stats().map(|_| ());
+            failures += report(out, "Database connectivity", stats_check)?;
+
+            let schema_check = context
+                .database
+                .get_connection_pool()
+                .get_connection()
+                .and_then(|conn| Ok(schema::get_schema_version(&conn)?))
+                .map(|_| ());
+            failures += report(out, "Schema version", schema_check)?;
+
+            let fts_check = context.database.check_fts_consistency().and_then(|c| {
+                if c.is_consistent() {
+                    Ok(())
+                } else {
+                    Err(anyhow::anyhow!(
+                        "{} of {} memories unindexed, {} orphaned entries{}; run `memex database reindex-fts`",
+                        c.missing,
+                        c.memory_rows,
+                        c.orphaned,
+                        if c.integrity_ok { "" } else { ", integrity check failed" }
+                    ))
+                }
+            });
+            failures += report(out, "Full-text index consistency", fts_check)?;
+
+            if failures == 0 {
+                writeln!(out, "{}", "✓ All diagnostics passed".green())?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✗ {} diagnostic(s) failed", failures).red()
+                )?;
+            }
+        }
+
+        SystemCommands::SlowQueries { limit, clear } => {
+            if clear {
+                let removed = context.database.clear_slow_queries()?;
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Cleared {} slow queries", removed).green()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "🐢 Slow Queries".green().bold())?;
+
+            let queries = context.database.get_slow_queries(limit)?;
+            if queries.is_empty() {
+                writeln!(out, "No slow queries recorded")?;
+            }
+
+            for query in queries {
+                writeln!(
+                    out,
+                    "\n{} {} {}",
+                    format!("{:.1} ms", query.duration_ms).yellow().bold(),
+                    query.recorded_at.format("%Y-%m-%d %H:%M:%S"),
+                    if query.failed {
+                        "(failed)".red().to_string()
+                    } else {
+                        String::new()
+                    }
+                )?;
+                for line in wrap_text(&query.sql, 76) {
+                    writeln!(out, "  {}", line)?;
+                }
+                if !query.params.is_empty() {
+                    writeln!(out, "  {} {}", "Params:".dimmed(), query.params)?;
+                }
+                if let Some(plan) = &query.query_plan {
+                    writeln!(out, "  {}", "Plan:".dimmed())?;
+                    for line in plan.lines() {
+                        writeln!(out, "    {}", line)?;
+                    }
+                }
+            }
+        }
+
+        SystemCommands::IndexAdvisor {
+            min_recalls,
+            apply,
+            reset,
+        } => {
+            if reset {
+                let removed = context.database.clear_query_shapes()?;
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Forgot {} recorded filter shapes", removed).green()
+                )?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", "🧭 Index Advisor".green().bold())?;
+
+            let advice = context.database.get_index_advice(min_recalls)?;
+            if advice.is_empty() {
+                writeln!(
+                    out,
+                    "✓ No new indexes recommended for filters recalled at least {} times",
+                    min_recalls
+                )?;
+                return Ok(());
+            }
+
+            for index in &advice {
+                writeln!(out, "\n{}", index.sql.bold())?;
+                writeln!(
+                    out,
+                    "  {} recalls, {} slow, {:.1} ms average",
+                    index.recalls,
+                    if index.slow_recalls > 0 {
+                        index.slow_recalls.to_string().yellow()
+                    } else {
+                        index.slow_recalls.to_string().normal()
+                    },
+                    index.avg_ms
+                )?;
+                for shape in &index.shapes {
+                    writeln!(out, "  {} {}", "Serves:".dimmed(), shape)?;
+                }
+            }
+
+            if apply {
+                context.database.apply_index_advice(&advice)?;
+                writeln!(
+                    out,
+                    "\n{}",
+                    format!("✓ Created {} indexes", advice.len()).green()
+                )?;
+            } else {
+                writeln!(out, "\nRun with --apply to create them")?;
+            }
+        }
+
+        SystemCommands::Stats {
+            history,
+            since,
+            snapshot,
+        } => {
+            if snapshot {
+                context.database.snapshot_stats()?;
+                writeln!(out, "{}", "✓ Recorded statistics snapshot".green())?;
+            }
+
+            if history {
+                let since = chrono::Utc::now() - since;
+                let snapshots = context.database.get_stats_history(since)?;
+                print_stats_history(out, &snapshots, since)?;
+            } else {
+                let stats = context.database.get_stats()?;
+                writeln!(out, "{}", "📊 Database Statistics".green().bold())?;
+                writeln!(
+                    out,
+                    "Memories: {}",
+                    stats["total_memories"].to_string().bright_blue()
+                )?;
+                writeln!(
+                    out,
+                    "Users: {}",
+                    stats["user_counts"]
+                        .as_object()
+                        .map_or(0, |users| users.len())
+                )?;
+                writeln!(
+                    out,
+                    "Database size: {}",
+                    format_bytes(stats["database_size_bytes"].as_u64().unwrap_or(0))
+                )?;
+            }
+        }
+
+        SystemCommands::Anomalies { sensitivity, all } => {
+            let mut policy = context.config.anomaly_policy();
+            if let Some(sensitivity) = sensitivity {
+                policy.sensitivity = sensitivity;
+            }
+            let monitor = AnomalyMonitor::new(context.database.clone(), policy);
+            let rates = if all {
+                monitor.ingestion_rates()?
+            } else {
+                monitor.check()?
+            };
+            print_ingestion_rates(out, &rates, &monitor)?;
+        }
+
+        SystemCommands::Audit {
+            actor,
+            trace_id,
+            target,
+            limit,
+        } => {
+            let filter = AuditFilter {
+                actor,
+                trace_id,
+                target_id: target,
+            };
+            let entries = context.database.get_audit_log(&filter, limit)?;
+
+            writeln!(out, "{}", "📜 Audit Log".green().bold())?;
+            if entries.is_empty() {
+                writeln!(
+                    out,
+                    "No audited writes; writes are audited when made with --actor or --trace-id"
+                )?;
+            }
+            for entry in entries {
+                writeln!(
+                    out,
+                    "{}  {:<15} {}  {}{}{}",
+                    entry.recorded_at.format("%Y-%m-%d %H:%M:%S"),
+                    entry.action.as_str(),
+                    entry.target_id,
+                    entry.actor.as_deref().unwrap_or("-").bright_blue(),
+                    entry
+                        .trace_id
+                        .map(|trace_id| format!(" trace={}", trace_id))
+                        .unwrap_or_default()
+                        .dimmed(),
+                    entry
+                        .source
+                        .map(|source| format!(" via {}", source))
+                        .unwrap_or_default()
+                        .dimmed(),
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn print_ingestion_rates(
+    out: &mut dyn Write,
+    rates: &[IngestionRate],
+    monitor: &AnomalyMonitor,
+) -> Result<()> {
+    let policy = monitor.policy();
+    writeln!(out, "{}", "🚨 Ingestion Anomalies".green().bold())?;
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "Last hour vs. the {} hours before it; spike at {:.1}σ and {}+ saves",
+            policy.baseline_hours, policy.sensitivity, policy.min_saves_per_hour
+        )
+        .dimmed()
+    )?;
+
+    if rates.is_empty() {
+        writeln!(out, "\n{}", "✓ No ingestion spikes".green())?;
+        return Ok(());
+    }
+
+    writeln!(
+        out,
+        "\n  {:<24}  {:>9}  {:>9}  {:>7}",
+        "User", "Last hour", "Baseline", "σ"
+    )?;
+    for rate in rates {
+        let line = format!(
+            "  {:<24}  {:>9}  {:>9.1}  {:>7.1}",
+            truncate_graphemes(&rate.user_id, 24),
+            rate.saves_last_hour,
+            rate.baseline_mean,
+            rate.z_score
+        );
+        if rate.anomalous {
+            writeln!(out, "{}  {}", line.red(), "← spike".red().bold())?;
+        } else {
+            writeln!(out, "{}", line)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Width of the growth bars in `system stats --history`
+const CHART_WIDTH: usize = 40;
+
+/// How many users the per-user growth table lists
+const TOP_USERS: usize = 10;
+
+fn print_stats_history(
+    out: &mut dyn Write,
+    snapshots: &[StatsSnapshot],
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<()> {
+    let (first, last) = match (snapshots.first(), snapshots.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => {
+            writeln!(
+                out,
+                "No statistics recorded since {}; take one with `memex system stats --snapshot`",
+                since.format("%Y-%m-%d %H:%M")
+            )?;
+            return Ok(());
+        }
+    };
+
+    writeln!(
+        out,
+        "{}",
+        format!(
+            "📈 Growth since {} ({} snapshots)",
+            since.format("%Y-%m-%d %H:%M"),
+            snapshots.len()
+        )
+        .green()
+        .bold()
+    )?;
+    writeln!(
+        out,
+        "\n  {:<16}  {:>9}  {:>8}  {:>10}",
+        "Recorded", "Memories", "Sessions", "Size"
+    )?;
+
+    let max_memories = snapshots
+        .iter()
+        .map(|s| s.total_memories)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    for snapshot in snapshots {
+        let bar_length =
+            (snapshot.total_memories.max(0) as usize * CHART_WIDTH) / max_memories as usize;
+        writeln!(
+            out,
+            "  {:<16}  {:>9}  {:>8}  {:>10}  {}",
+            snapshot.recorded_at.format("%Y-%m-%d %H:%M"),
+            snapshot.total_memories,
+            snapshot.total_sessions,
+            format_bytes(snapshot.database_size_bytes),
+            "█".repeat(bar_length).bright_blue()
+        )?;
+    }
+
+    writeln!(
+        out,
+        "\nMemories: {} → {} ({})",
+        first.total_memories,
+        last.total_memories,
+        signed(last.total_memories - first.total_memories)
+    )?;
+    writeln!(
+        out,
+        "Database size: {} → {}",
+        format_bytes(first.database_size_bytes),
+        format_bytes(last.database_size_bytes)
+    )?;
+
+    let mut users: Vec<(&String, i64, i64)> = last
+        .user_counts
+        .iter()
+        .map(|(user, &count)| {
+            (
+                user,
+                first.user_counts.get(user).copied().unwrap_or(0),
+                count,
+            )
+        })
+        .chain(
+            first
+                .user_counts
+                .iter()
+                .filter(|(user, _)| !last.user_counts.contains_key(*user))
+                .map(|(user, &count)| (user, count, 0)),
+        )
+        .collect();
+    if users.is_empty() {
+        return Ok(());
+    }
+
+    users.sort_by_key(|&(user, _, count)| (std::cmp::Reverse(count), user));
+    writeln!(out, "\n{}", "Memories per user:".bold())?;
+    for (user, before, after) in users.iter().take(TOP_USERS) {
+        writeln!(
+            out,
+            "  {}: {} → {} ({})",
+            user,
+            before,
+            after,
+            signed(after - before)
+        )?;
+    }
+    if users.len() > TOP_USERS {
+        writeln!(
+            out,
+            "  {}",
+            format!("... and {} more users", users.len() - TOP_USERS).dimmed()
+        )?;
+    }
+
+    Ok(())
+}
+
+fn signed(change: i64) -> String {
+    if change > 0 {
+        format!("+{}", change)
+    } else {
+        change.to_string()
+    }
+}
+
+/// Print a single diagnostic line, returning 1 if the check failed
+fn report(out: &mut dyn Write, name: &str, check: Result<()>) -> Result<usize> {
+    match check {
+        Ok(()) => {
+            writeln!(out, "✓ {}", name)?;
+            Ok(0)
+        }
+        Err(e) => {
+            writeln!(out, "✗ {}: {}", name, e.to_string().red())?;
+            Ok(1)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::context::RequestContext;
+    use crate::core::maintenance::ScheduledBackupConfig;
+    use crate::core::MemexConfig;
+    use crate::database::maintenance_runs::MaintenanceJob;
+    use crate::database::models::QueryFilter;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_context() -> (CliContext, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("test.db")
+            .to_string_lossy()
+            .to_string();
+        let database = Database::new(DatabaseConfig {
+            path: path.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let config = MemexConfig {
+            database_path: path,
+            ..Default::default()
+        };
+
+        (CliContext::new(config, database), temp_dir)
+    }
+
+    fn run(context: &CliContext, action: SystemCommands) -> String {
+        let mut out = Vec::new();
+        handle(action, context, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_health() {
+        let (context, _temp_dir) = setup_test_context();
+
+        let output = run(&context, SystemCommands::Health);
+        assert!(output.contains("✓ Database"));
+        assert!(output.contains("✓ WAL"), "{}", output);
+        assert!(output.contains("✓ Configuration"));
+        assert!(output.contains("✓ Ingestion rate"));
+    }
+
+    #[test]
+    fn test_health_reports_overdue_backups() {
+        let (mut context, temp_dir) = setup_test_context();
+        assert!(!run(&context, SystemCommands::Health).contains("Backups"));
+
+        context.config.maintenance.backup = Some(ScheduledBackupConfig::new(
+            temp_dir.path().join("backups").to_string_lossy(),
+        ));
+        let output = run(&context, SystemCommands::Health);
+        assert!(output.contains("✗ Backups"), "{}", output);
+
+        context
+            .maintenance_scheduler()
+            .run_job(MaintenanceJob::Backup)
+            .unwrap();
+        let output = run(&context, SystemCommands::Health);
+        assert!(output.contains("✓ Backups: last"), "{}", output);
+        assert!(output.contains("(1 kept)"), "{}", output);
+    }
+
+    #[test]
+    fn test_anomalies_report_spikes() {
+        let (context, _temp_dir) = setup_test_context();
+        let anomalies =
+            |sensitivity, all| run(&context, SystemCommands::Anomalies { sensitivity, all });
+
+        assert!(anomalies(None, false).contains("No ingestion spikes"));
+
+        for i in 0..25 {
+            context
+                .database
+                .save_memory(&crate::database::models::MemoryItem {
+                    user_id: "looper".to_string(),
+                    session_id: "loop".to_string(),
+                    content: format!("Retrying step {}", i),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = anomalies(None, false);
+        assert!(output.contains("looper"), "{}", output);
+        assert!(output.contains("← spike"), "{}", output);
+        assert!(run(&context, SystemCommands::Health).contains("1 user(s) spiking"));
+
+        // Demanding far more deviation hides the spike unless --all is given
+        assert!(anomalies(Some(100.0), false).contains("No ingestion spikes"));
+        let output = anomalies(Some(100.0), true);
+        assert!(output.contains("looper"), "{}", output);
+        assert!(!output.contains("← spike"), "{}", output);
+    }
+
+    #[test]
+    fn test_diagnostics_pass_on_fresh_database() {
+        let (context, _temp_dir) = setup_test_context();
+
+        let output = run(&context, SystemCommands::Diagnostics);
+        assert!(output.contains("All diagnostics passed"));
+    }
+
+    #[test]
+    fn test_diagnostics_report_fts_drift() {
+        let (context, _temp_dir) = setup_test_context();
+        let pool = context.database.get_connection_pool();
+        pool.get_connection()
+            .unwrap()
+            .execute_batch("INSERT INTO memories_fts(rowid, content) VALUES (42, 'orphaned entry')")
+            .unwrap();
+
+        let output = run(&context, SystemCommands::Diagnostics);
+        assert!(output.contains("✗ Full-text index consistency"));
+        assert!(output.contains("1 orphaned entries"));
+        assert!(output.contains("reindex-fts"));
+    }
+
+    #[test]
+    fn test_info_reports_config() {
+        let (context, _temp_dir) = setup_test_context();
+
+        let output = run(&context, SystemCommands::Info);
+        assert!(output.contains(env!("CARGO_PKG_VERSION")));
+        assert!(output.contains(&context.config.database_path));
+        assert!(output.contains("Maintenance lock: free"));
+
+        context.database.acquire_maintenance_lock().unwrap();
+        let output = run(&context, SystemCommands::Info);
+        assert!(
+            output.contains(&format!("pid {}", std::process::id())),
+            "{}",
+            output
+        );
+        assert!(output.contains("(this process)"));
+    }
+
+    #[test]
+    fn test_slow_queries_show_and_clear() {
+        let (context, _temp_dir) = setup_test_context();
+
+        let output = run(
+            &context,
+            SystemCommands::SlowQueries {
+                limit: 20,
+                clear: false,
+            },
+        );
+        assert!(output.contains("No slow queries recorded"));
+
+        let pool = context.database.get_connection_pool();
+        pool.get_connection()
+            .unwrap()
+            .execute(
+                "INSERT INTO slow_queries (sql, params, duration_ms, query_plan, failed)
+                 VALUES ('SELECT * FROM memories WHERE user_id = ?1', '?1=text(\"u\")', 812.5, 'SCAN memories', 1)",
+                [],
+            )
+            .unwrap();
+
+        let output = run(
+            &context,
+            SystemCommands::SlowQueries {
+                limit: 20,
+                clear: false,
+            },
+        );
+        assert!(output.contains("812.5 ms"));
+        assert!(output.contains("(failed)"));
+        assert!(output.contains("SELECT * FROM memories WHERE user_id = ?1"));
+        assert!(output.contains("SCAN memories"));
+
+        let output = run(
+            &context,
+            SystemCommands::SlowQueries {
+                limit: 20,
+                clear: true,
+            },
+        );
+        assert!(output.contains("Cleared 1 slow queries"));
+        assert!(context.database.get_slow_queries(20).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_index_advisor_recommends_and_applies() {
+        let (context, _temp_dir) = setup_test_context();
+        let advisor = |min_recalls, apply, reset| {
+            run(
+                &context,
+                SystemCommands::IndexAdvisor {
+                    min_recalls,
+                    apply,
+                    reset,
+                },
+            )
+        };
+
+        for _ in 0..3 {
+            context
+                .database
+                .recall_memories(&QueryFilter {
+                    session_id: Some("session1".to_string()),
+                    min_confidence: Some(0.8),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = advisor(10, false, false);
+        assert!(output.contains("No new indexes recommended"));
+
+        let output = advisor(3, false, false);
+        assert!(output.contains(
+            "CREATE INDEX IF NOT EXISTS idx_memories_advised_session_id_state_confidence"
+        ));
+        assert!(output.contains("3 recalls"));
+        assert!(output.contains("Serves: session_id= state= confidence<>"));
+        assert!(output.contains("--apply"));
+
+        let output = advisor(3, true, false);
+        assert!(output.contains("Created 1 indexes"));
+        let output = advisor(3, false, false);
+        assert!(output.contains("No new indexes recommended"));
+
+        let output = advisor(3, false, true);
+        assert!(output.contains("Forgot 1 recorded filter shapes"));
+    }
+
+    #[test]
+    fn test_stats_snapshot_and_history() {
+        let (context, _temp_dir) = setup_test_context();
+        let stats = |history, snapshot| {
+            run(
+                &context,
+                SystemCommands::Stats {
+                    history,
+                    since: chrono::Duration::days(7),
+                    snapshot,
+                },
+            )
+        };
+
+        let output = stats(true, false);
+        assert!(output.contains("No statistics recorded"));
+        stats(false, true);
+
+        let session_id = context.database.create_session("alice", None).unwrap();
+        context
+            .database
+            .save_memory(&crate::database::models::MemoryItem {
+                user_id: "alice".to_string(),
+                session_id,
+                content: "Growth".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let output = stats(false, true);
+        assert!(output.contains("Recorded statistics snapshot"));
+        assert!(output.contains("Memories: 1"));
+
+        let output = stats(true, false);
+        assert!(output.contains("(2 snapshots)"), "{}", output);
+        assert!(output.contains("Memories: 0 → 1 (+1)"), "{}", output);
+        assert!(output.contains("alice: 0 → 1 (+1)"), "{}", output);
+
+        // The default interval is a day, so the fresh snapshot isn't due yet
+        assert!(context.database.snapshot_stats_if_due().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_audit_attributes_writes_to_their_context() {
+        let (context, _temp_dir) = setup_test_context();
+        let audit = |actor: Option<&str>| {
+            run(
+                &context,
+                SystemCommands::Audit {
+                    actor: actor.map(str::to_string),
+                    trace_id: None,
+                    target: None,
+                    limit: 20,
+                },
+            )
+        };
+        let memory = |content: &str| crate::database::models::MemoryItem {
+            user_id: "alice".to_string(),
+            session_id: "shared".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        };
+
+        // Writes outside a context aren't audited
+        context.memory.save_memory(memory("Unattributed")).unwrap();
+        assert!(audit(None).contains("No audited writes"));
+
+        let planner = RequestContext::new()
+            .with_actor("planner")
+            .with_trace_id("trace-7")
+            .with_source("test");
+        let id = context
+            .memory
+            .in_context(&planner, |manager| manager.save_memory(memory("Plan")))
+            .unwrap();
+        let researcher = RequestContext::new().with_actor("researcher");
+        context
+            .memory
+            .in_context(&researcher, |manager| manager.delete_memory(&id))
+            .unwrap();
+
+        let output = audit(None);
+        assert!(output.contains("memory_save"), "{}", output);
+        assert!(output.contains("trace=trace-7 via test"), "{}", output);
+        assert!(output.contains("memory_delete"), "{}", output);
+
+        let output = audit(Some("researcher"));
+        assert!(output.contains("memory_delete"), "{}", output);
+        assert!(!output.contains("memory_save"), "{}", output);
+    }
+}
@@ -0,0 +1,421 @@
+//! `memex user ...` command handlers
+
+use anyhow::Result;
+use clap::Subcommand;
+use colored::*;
+use std::io::Write;
+
+use crate::cli::{format_bytes, InteractiveCli};
+use crate::core::analytics::GROWTH_WINDOW_DAYS;
+use crate::core::memory::MemoryManager;
+use crate::database::models::UserOverview;
+use crate::display::pad_to_width;
+
+#[derive(Subcommand, Debug)]
+pub enum UserCommands {
+    /// List every user with memory and session counts and last activity
+    List {
+        /// Limit results
+        #[arg(short, long, default_value = "50")]
+        limit: usize,
+        /// Offset for pagination
+        #[arg(long, default_value = "0")]
+        offset: usize,
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show a user's counts, storage, importance and token figures
+    Show {
+        /// User ID
+        user_id: String,
+        /// Print JSON instead of a summary
+        #[arg(long)]
+        json: bool,
+    },
+    /// Erase everything stored about a user: memories, sessions and
+    /// summaries
+    Purge {
+        /// User ID
+        user_id: String,
+        /// Skip the confirmation prompt
+        #[arg(short, long)]
+        yes: bool,
+        /// Print what was erased as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show storage, growth, decay and topic analytics for a user
+    Analytics {
+        /// User ID
+        #[arg(short, long)]
+        user: String,
+    },
+}
+
+pub fn handle(action: UserCommands, manager: &MemoryManager, out: &mut dyn Write) -> Result<()> {
+    match action {
+        UserCommands::List {
+            limit,
+            offset,
+            json,
+        } => {
+            let response = manager.list_users(Some(limit), Some(offset))?;
+            let users = &response.data;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(users)?)?;
+                return Ok(());
+            }
+            if users.is_empty() {
+                writeln!(out, "{}", "No users found".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("👤 Users ({})", response.total_count)
+                    .green()
+                    .bold()
+            )?;
+            writeln!(
+                out,
+                "  {} {:>9} {:>9} {:>10}  Last active",
+                pad_to_width("User", 24),
+                "Memories",
+                "Sessions",
+                "Storage"
+            )?;
+            for user in users {
+                writeln!(
+                    out,
+                    "  {} {:>9} {:>9} {:>10}  {}",
+                    pad_to_width(&user.user_id, 24).bright_blue(),
+                    user.memory_count,
+                    user.session_count,
+                    format_bytes(user.content_bytes),
+                    format_last_active(user)
+                )?;
+            }
+            if response.has_next {
+                writeln!(
+                    out,
+                    "\n{}",
+                    format!("Use --offset {} to see more users", offset + limit).dimmed()
+                )?;
+            }
+        }
+
+        UserCommands::Show { user_id, json } => {
+            let Some(overview) = manager.get_user_overview(&user_id)? else {
+                if json {
+                    writeln!(out, "null")?;
+                } else {
+                    writeln!(out, "{}", format!("User not found: {}", user_id).yellow())?;
+                }
+                return Ok(());
+            };
+            let stats = manager.get_user_memory_stats(&user_id)?;
+            if json {
+                let details = serde_json::json!({
+                    "overview": overview,
+                    "stats": stats,
+                });
+                writeln!(out, "{}", serde_json::to_string_pretty(&details)?)?;
+                return Ok(());
+            }
+
+            writeln!(out, "{}", format!("👤 User {}", user_id).green().bold())?;
+            writeln!(
+                out,
+                "Memories: {} in {} sessions",
+                overview.memory_count.to_string().bright_blue(),
+                overview.session_count.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Storage: {}",
+                format_bytes(overview.content_bytes).bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Tokens: {} ({})",
+                stats.total_tokens.to_string().bright_blue(),
+                stats.tokenizer
+            )?;
+            writeln!(out, "Average importance: {:.2}", stats.avg_importance)?;
+            if let (Some(oldest), Some(newest)) = (stats.oldest_memory, stats.newest_memory) {
+                writeln!(
+                    out,
+                    "Memories from {} to {}",
+                    oldest.format("%Y-%m-%d"),
+                    newest.format("%Y-%m-%d")
+                )?;
+            }
+            writeln!(out, "Last active: {}", format_last_active(&overview))?;
+        }
+
+        UserCommands::Purge { user_id, yes, json } => {
+            if !yes
+                && !InteractiveCli::confirm(
+                    &format!(
+                        "Erase all memories, sessions and summaries of {}? This can't be undone",
+                        user_id
+                    ),
+                    false,
+                )?
+            {
+                writeln!(out, "Cancelled")?;
+                return Ok(());
+            }
+
+            let purge = manager.purge_user(&user_id)?;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&purge)?)?;
+            } else if purge.found() {
+                writeln!(
+                    out,
+                    "{}",
+                    format!(
+                        "✓ Purged {}: {} memories, {} sessions, {} summaries",
+                        user_id,
+                        purge.memory_ids.len(),
+                        purge.sessions,
+                        purge.summaries
+                    )
+                    .green()
+                )?;
+            } else {
+                writeln!(out, "{}", format!("User not found: {}", user_id).yellow())?;
+            }
+        }
+
+        UserCommands::Analytics { user } => {
+            let analytics = manager.get_user_analytics(&user)?;
+
+            writeln!(
+                out,
+                "{}",
+                format!("User Analytics for {}", user).green().bold()
+            )?;
+            writeln!(
+                out,
+                "Memories: {} in {} sessions",
+                analytics.memory_count.to_string().bright_blue(),
+                analytics.session_count.to_string().bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Storage: {}",
+                format_bytes(analytics.storage_bytes).bright_blue()
+            )?;
+            writeln!(
+                out,
+                "Growth: {}/day, {:.1} memories/day (last {} days)",
+                format_bytes(analytics.growth_bytes_per_day.round() as u64),
+                analytics.memories_per_day,
+                GROWTH_WINDOW_DAYS
+            )?;
+
+            match analytics.compression_ratio {
+                Some(ratio) => writeln!(
+                    out,
+                    "Compression: {} compressed memories, {:.1} originals each",
+                    analytics.compressed_memories, ratio
+                )?,
+                None => writeln!(out, "Compression: none yet")?,
+            }
+            match analytics.decay_removal_rate {
+                Some(rate) => writeln!(
+                    out,
+                    "Decay removal rate: {:.1}% per run (all users)",
+                    rate * 100.0
+                )?,
+                None => writeln!(out, "Decay removal rate: no decay runs yet")?,
+            }
+
+            if !analytics.top_sessions.is_empty() {
+                writeln!(out, "\n{}", "Top Sessions:".bold())?;
+                for session in &analytics.top_sessions {
+                    writeln!(
+                        out,
+                        "  {} {} ({} memories, {})",
+                        session.session_id.bright_blue(),
+                        session.name.as_deref().unwrap_or("(unnamed)"),
+                        session.memory_count,
+                        format_bytes(session.storage_bytes)
+                    )?;
+                }
+            }
+
+            if !analytics.topic_distribution.is_empty() {
+                writeln!(out, "\n{}", "Topics:".bold())?;
+                for topic in &analytics.topic_distribution {
+                    writeln!(
+                        out,
+                        "  {:<20} {:>5} {:>5.1}%",
+                        topic.topic,
+                        topic.memory_count,
+                        topic.share * 100.0
+                    )?;
+                }
+                if analytics.uncategorized_memories > 0 {
+                    writeln!(
+                        out,
+                        "  {:<20} {:>5}",
+                        "(uncategorized)".dimmed(),
+                        analytics.uncategorized_memories
+                    )?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn format_last_active(user: &UserOverview) -> String {
+    user.last_active
+        .map(|at| at.format("%Y-%m-%d %H:%M").to_string())
+        .unwrap_or_else(|| "never".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (MemoryManager::new(database, validator), temp_dir)
+    }
+
+    fn run(manager: &MemoryManager, action: UserCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(action, manager, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_analytics() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let analytics = || {
+            run(
+                &manager,
+                UserCommands::Analytics {
+                    user: "alice".to_string(),
+                },
+            )
+            .unwrap()
+        };
+
+        let output = analytics();
+        assert!(output.contains("Compression: none yet"), "{}", output);
+        assert!(!output.contains("Top Sessions"), "{}", output);
+
+        for category in ["trading", "trading", "research"] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "journal".to_string(),
+                    content: format!("A {} note", category),
+                    metadata: HashMap::from([("category".to_string(), category.to_string())]),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let output = analytics();
+        assert!(output.contains("Memories: "), "{}", output);
+        assert!(output.contains("Top Sessions:"), "{}", output);
+        assert!(output.contains("journal"), "{}", output);
+        assert!(output.contains("trading"), "{}", output);
+        assert!(output.contains("66.7%"), "{}", output);
+    }
+
+    #[test]
+    fn test_list_show_and_purge() {
+        let (manager, _temp_dir) = setup_test_manager();
+        for (user, session) in [("alice", "s1"), ("alice", "s2"), ("bob", "s3")] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: user.to_string(),
+                    session_id: session.to_string(),
+                    content: format!("A note from {}", user),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let list = |limit, offset, json| {
+            run(
+                &manager,
+                UserCommands::List {
+                    limit,
+                    offset,
+                    json,
+                },
+            )
+            .unwrap()
+        };
+        let output = list(1, 0, false);
+        assert!(output.contains("Users (2)"), "{}", output);
+        assert!(output.contains("alice") && !output.contains("bob"));
+        assert!(output.contains("Use --offset 1"));
+        assert!(list(1, 1, false).contains("bob"));
+        let users: Vec<UserOverview> = serde_json::from_str(&list(50, 0, true)).unwrap();
+        assert_eq!(users.len(), 2);
+        assert_eq!(users[0].user_id, "alice");
+        assert_eq!((users[0].memory_count, users[0].session_count), (2, 2));
+        assert!(users[0].last_active.is_some());
+
+        let show = |user_id: &str| {
+            run(
+                &manager,
+                UserCommands::Show {
+                    user_id: user_id.to_string(),
+                    json: false,
+                },
+            )
+            .unwrap()
+        };
+        assert!(show("alice").contains("Memories: 2 in 2 sessions"));
+        assert!(show("carol").contains("User not found: carol"));
+
+        let purge = |json| {
+            run(
+                &manager,
+                UserCommands::Purge {
+                    user_id: "alice".to_string(),
+                    yes: true,
+                    json,
+                },
+            )
+            .unwrap()
+        };
+        let purged: serde_json::Value = serde_json::from_str(&purge(true)).unwrap();
+        assert_eq!(purged["memory_ids"].as_array().unwrap().len(), 2);
+        assert_eq!(purged["sessions"], 2);
+        assert!(purge(false).contains("User not found: alice"));
+
+        let users = manager.list_users(None, None).unwrap().data;
+        assert_eq!(users.len(), 1);
+        assert_eq!(users[0].user_id, "bob");
+        assert!(manager.export_user_memories("alice").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,726 @@
+//! `memex vector ...` command handlers (requires the `vector-search` feature)
+
+use anyhow::{Context, Result};
+use chrono::Utc;
+use clap::{Args, Subcommand};
+use colored::*;
+use std::io::Write;
+
+use super::{format_bytes, parse_duration};
+use crate::database::vector::{CommandEmbedder, VectorFilter, VectorSearchEngine};
+use crate::display::truncate_graphemes;
+
+#[derive(Subcommand, Debug)]
+pub enum VectorCommands {
+    /// Store embedding for a memory
+    Store {
+        /// Memory ID
+        #[arg(short = 'i', long)]
+        memory_id: String,
+        /// Embedding vector as JSON array
+        #[arg(short, long)]
+        embedding: String,
+        /// Model name
+        #[arg(short, long)]
+        model: String,
+    },
+    /// Search for similar memories
+    Search {
+        /// Query embedding as JSON array
+        #[arg(short, long)]
+        embedding: String,
+        /// Model name
+        #[arg(short, long)]
+        model: String,
+        /// Maximum results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    /// Hybrid search (text + vector)
+    Hybrid {
+        /// Text query
+        #[arg(short, long)]
+        text: String,
+        /// Vector query as JSON array
+        #[arg(short, long)]
+        vector: String,
+        /// Model name
+        #[arg(short, long)]
+        model: String,
+        /// Text weight (0.0-1.0)
+        #[arg(long, default_value = "0.5")]
+        text_weight: f32,
+        /// Vector weight (0.0-1.0)
+        #[arg(long, default_value = "0.5")]
+        vector_weight: f32,
+        /// Maximum results
+        #[arg(short, long, default_value = "10")]
+        limit: usize,
+        #[command(flatten)]
+        filter: FilterArgs,
+        /// Show how much the text match and the vector similarity each
+        /// added to the score
+        #[arg(long)]
+        explain: bool,
+    },
+    /// Show vector search statistics
+    Stats,
+    /// Check embeddings for orphans, dimension mismatches and other anomalies
+    Diagnostics,
+    /// Delete a memory's embedding
+    Delete {
+        /// Memory ID
+        #[arg(short = 'i', long)]
+        memory_id: String,
+    },
+    /// Replace a memory's embedding using another model
+    Reembed {
+        /// Memory ID
+        #[arg(short = 'i', long)]
+        memory_id: String,
+        /// Model name to store with the new embedding
+        #[arg(short, long)]
+        model: String,
+        /// Embedding command, as for `backfill`
+        #[arg(short, long)]
+        command: String,
+    },
+    /// Generate embeddings for memories that don't have one yet
+    Backfill {
+        /// Model name to store with the embeddings
+        #[arg(short, long)]
+        model: String,
+        /// Shell command that reads a JSON array of texts on stdin and
+        /// writes a JSON array of embeddings to stdout
+        #[arg(short, long)]
+        command: String,
+        /// Memories embedded per command invocation
+        #[arg(short, long, default_value = "32")]
+        batch_size: usize,
+    },
+    /// Embeddings cached by content hash, reused for identical content
+    Cache {
+        #[command(subcommand)]
+        action: CacheCommands,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+pub enum CacheCommands {
+    /// Show how many embeddings are cached and the cache's hit rate
+    Stats,
+    /// Drop cached embeddings
+    Purge {
+        /// Only this model's embeddings
+        #[arg(short, long)]
+        model: Option<String>,
+        /// Only embeddings unused for this long (e.g. 30d, 4w)
+        #[arg(long, value_parser = parse_duration)]
+        unused_for: Option<chrono::Duration>,
+    },
+}
+
+/// Restrictions shared by `search` and `hybrid`
+#[derive(Args, Debug, Default)]
+pub struct FilterArgs {
+    /// Only this user's memories
+    #[arg(short, long)]
+    user: Option<String>,
+    /// Only memories from this session
+    #[arg(short, long)]
+    session: Option<String>,
+    /// Minimum importance
+    #[arg(long)]
+    min_importance: Option<f32>,
+    /// Minimum confidence
+    #[arg(long)]
+    min_confidence: Option<f32>,
+    /// Only memories created within this long ago (e.g. 30m, 12h, 7d, 2w)
+    #[arg(long, value_parser = parse_duration)]
+    since: Option<chrono::Duration>,
+    /// Only memories created at least this long ago
+    #[arg(long, value_parser = parse_duration)]
+    until: Option<chrono::Duration>,
+    /// Required tag from the memory's `tags` metadata (repeatable)
+    #[arg(long = "tag")]
+    tags: Vec<String>,
+}
+
+impl FilterArgs {
+    fn into_filter(self) -> VectorFilter {
+        let now = Utc::now();
+        VectorFilter {
+            user_id: self.user,
+            session_id: self.session,
+            min_importance: self.min_importance,
+            min_confidence: self.min_confidence,
+            date_from: self.since.map(|since| now - since),
+            date_to: self.until.map(|until| now - until),
+            tags: self.tags,
+        }
+    }
+}
+
+pub fn handle(
+    action: VectorCommands,
+    engine: &VectorSearchEngine,
+    out: &mut dyn Write,
+) -> Result<()> {
+    match action {
+        VectorCommands::Store {
+            memory_id,
+            embedding,
+            model,
+        } => {
+            let embedding_vec: Vec<f32> =
+                serde_json::from_str(&embedding).context("Invalid embedding JSON")?;
+
+            engine.store_embedding(&memory_id, &embedding_vec, &model)?;
+
+            writeln!(out, "{}", "✓ Embedding stored successfully".green())?;
+            writeln!(out, "  Memory ID: {}", memory_id.bright_blue())?;
+            writeln!(out, "  Model: {}", model)?;
+            writeln!(out, "  Dimension: {}", embedding_vec.len())?;
+        }
+
+        VectorCommands::Search {
+            embedding,
+            model,
+            limit,
+            filter,
+        } => {
+            let embedding_vec: Vec<f32> =
+                serde_json::from_str(&embedding).context("Invalid embedding JSON")?;
+
+            let results = engine.search_similar(
+                &embedding_vec,
+                &model,
+                Some(limit),
+                &filter.into_filter(),
+            )?;
+
+            if results.is_empty() {
+                writeln!(out, "{}", "No similar memories found".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("Found {} similar memories", results.len()).green()
+            )?;
+            writeln!(out)?;
+
+            for (i, result) in results.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{}. {} ({} similarity: {:.3})",
+                    i + 1,
+                    result.memory_id.bright_blue(),
+                    result.metric,
+                    result.similarity
+                )?;
+                writeln!(
+                    out,
+                    "   Content: {}",
+                    truncate_graphemes(&result.content, 80)
+                )?;
+                writeln!(out, "   Importance: {:.1}", result.importance)?;
+                writeln!(out)?;
+            }
+        }
+
+        VectorCommands::Hybrid {
+            text,
+            vector,
+            model,
+            text_weight,
+            vector_weight,
+            limit,
+            filter,
+            explain,
+        } => {
+            let vector_query: Vec<f32> =
+                serde_json::from_str(&vector).context("Invalid vector JSON")?;
+
+            let results = engine.hybrid_search(
+                &text,
+                &vector_query,
+                &model,
+                text_weight,
+                vector_weight,
+                Some(limit),
+                &filter.into_filter(),
+            )?;
+
+            if results.is_empty() {
+                writeln!(out, "{}", "No matching memories found".yellow())?;
+                return Ok(());
+            }
+
+            writeln!(
+                out,
+                "{}",
+                format!("Found {} matching memories", results.len()).green()
+            )?;
+            writeln!(out)?;
+
+            for (i, result) in results.iter().enumerate() {
+                writeln!(
+                    out,
+                    "{}. {} (score: {:.3})",
+                    i + 1,
+                    result.memory_id.bright_blue(),
+                    result.combined_score
+                )?;
+                writeln!(
+                    out,
+                    "   Content: {}",
+                    truncate_graphemes(&result.content, 80)
+                )?;
+                writeln!(
+                    out,
+                    "   Vector similarity ({}): {:.3} | Text match: {:.1}",
+                    result.metric, result.vector_similarity, result.text_match
+                )?;
+                if explain {
+                    writeln!(
+                        out,
+                        "   {}",
+                        format!(
+                            "↳ text {:.2} × {:.1} + vector {:.2} × {:.3} = {:.3}",
+                            text_weight,
+                            result.text_match,
+                            vector_weight,
+                            result.vector_similarity,
+                            result.combined_score
+                        )
+                        .dimmed()
+                    )?;
+                }
+                writeln!(out)?;
+            }
+        }
+
+        VectorCommands::Stats => {
+            let stats = engine.get_vector_stats()?;
+
+            writeln!(out, "{}", "Vector Search Statistics".green().bold())?;
+            writeln!(
+                out,
+                "Total embeddings: {}",
+                stats.total_embeddings.to_string().bright_blue()
+            )?;
+            writeln!(out, "Vector dimension: {}", stats.dimension)?;
+            writeln!(out, "Distance metric: {}", stats.metric)?;
+
+            writeln!(
+                out,
+                "Embedding cache: {} entries, {:.1}% hit rate",
+                stats.embedding_cache.entries,
+                stats.embedding_cache.hit_rate * 100.0
+            )?;
+
+            if !stats.models.is_empty() {
+                writeln!(out, "\n{}", "Models:".bold())?;
+                for (model, count) in &stats.models {
+                    writeln!(out, "  {}: {} embeddings", model, count)?;
+                }
+            }
+        }
+
+        VectorCommands::Diagnostics => {
+            let stats = engine.get_vector_stats()?;
+
+            writeln!(out, "{}", "Vector Index Diagnostics".green().bold())?;
+            writeln!(
+                out,
+                "Embeddings: {} ({} memories without one)",
+                stats.total_embeddings.to_string().bright_blue(),
+                stats.missing_embeddings
+            )?;
+            writeln!(out, "Orphaned embeddings: {}", stats.orphaned_embeddings)?;
+            writeln!(
+                out,
+                "Dimension mismatches: {} (expected {})",
+                stats.dimension_mismatches, stats.dimension
+            )?;
+            match stats.average_norm {
+                Some(norm) => writeln!(
+                    out,
+                    "Average norm: {:.3} over {} sampled",
+                    norm, stats.sampled_embeddings
+                )?,
+                None => writeln!(out, "Average norm: n/a")?,
+            }
+            writeln!(
+                out,
+                "Estimated index build time: {:.1}ms",
+                stats.index_build_ms
+            )?;
+            writeln!(
+                out,
+                "Estimated index memory: {}",
+                format_bytes(stats.index_memory_bytes as u64)
+            )?;
+
+            let anomalies = stats.anomalies();
+            writeln!(out)?;
+            if anomalies.is_empty() {
+                writeln!(out, "{}", "✓ No anomalies found".green())?;
+            } else {
+                writeln!(out, "{}", "Anomalies:".yellow().bold())?;
+                for anomaly in &anomalies {
+                    writeln!(out, "  {} {}", "⚠".yellow(), anomaly)?;
+                }
+            }
+        }
+
+        VectorCommands::Delete { memory_id } => {
+            if !engine.delete_embedding(&memory_id)? {
+                return Err(anyhow::anyhow!("No embedding for memory: {}", memory_id));
+            }
+            writeln!(out, "{}", "✓ Embedding deleted".green())?;
+            writeln!(out, "  Memory ID: {}", memory_id.bright_blue())?;
+        }
+
+        VectorCommands::Reembed {
+            memory_id,
+            model,
+            command,
+        } => {
+            let embedder = CommandEmbedder { model, command };
+            if !engine.reembed(&memory_id, &embedder)? {
+                return Err(anyhow::anyhow!("Memory not found: {}", memory_id));
+            }
+            writeln!(out, "{}", "✓ Memory re-embedded".green())?;
+            writeln!(out, "  Memory ID: {}", memory_id.bright_blue())?;
+            writeln!(out, "  Model: {}", embedder.model)?;
+        }
+
+        VectorCommands::Backfill {
+            model,
+            command,
+            batch_size,
+        } => {
+            let embedder = CommandEmbedder { model, command };
+
+            let mut progress_error = None;
+            let progress = engine.backfill(&embedder, batch_size, |progress| {
+                if let Err(e) = writeln!(out, "  Embedded {}/{}", progress.embedded, progress.total)
+                {
+                    progress_error.get_or_insert(e);
+                }
+            })?;
+            if let Some(e) = progress_error {
+                return Err(e.into());
+            }
+
+            if progress.total == 0 {
+                writeln!(out, "{}", "All memories already have embeddings".yellow())?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Backfilled {} embeddings", progress.embedded).green()
+                )?;
+                writeln!(out, "  Model: {}", embedder.model.bright_blue())?;
+            }
+        }
+
+        VectorCommands::Cache {
+            action: CacheCommands::Stats,
+        } => {
+            let stats = engine.embedding_cache_stats()?;
+            writeln!(out, "{}", "Embedding Cache".green().bold())?;
+            writeln!(
+                out,
+                "Entries: {} ({})",
+                stats.entries.to_string().bright_blue(),
+                format_bytes(stats.bytes.max(0) as u64)
+            )?;
+            writeln!(out, "Hits: {}", stats.hits)?;
+            writeln!(out, "Hit rate: {:.1}%", stats.hit_rate * 100.0)?;
+        }
+
+        VectorCommands::Cache {
+            action: CacheCommands::Purge { model, unused_for },
+        } => {
+            let purged = engine
+                .purge_embedding_cache(model.as_deref(), unused_for.map(|d| Utc::now() - d))?;
+            if purged == 0 {
+                writeln!(out, "{}", "No cached embeddings to purge".yellow())?;
+            } else {
+                writeln!(
+                    out,
+                    "{}",
+                    format!("✓ Purged {} cached embeddings", purged).green()
+                )?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::vector::VectorConfig;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_engine() -> (Database, VectorSearchEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            // `vector_similarity` only exists on the connection that set up
+            // the vector schema
+            max_connections: 1,
+            min_connections: 1,
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let config = VectorConfig {
+            dimension: 3,
+            ..Default::default()
+        };
+        let engine = VectorSearchEngine::new(database.get_connection_pool(), config);
+        engine.initialize_schema().unwrap();
+        (database, engine, temp_dir)
+    }
+
+    fn run(engine: &VectorSearchEngine, action: VectorCommands) -> Result<String> {
+        let mut out = Vec::new();
+        handle(action, engine, &mut out)?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[test]
+    fn test_store_and_stats() {
+        let (database, engine, _temp_dir) = setup_test_engine();
+        let memory_id = database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Embedded memory".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let output = run(
+            &engine,
+            VectorCommands::Store {
+                memory_id,
+                embedding: "[0.1, 0.2, 0.3]".to_string(),
+                model: "test-model".to_string(),
+            },
+        )
+        .unwrap();
+        assert!(output.contains("Embedding stored successfully"));
+
+        let output = run(&engine, VectorCommands::Stats).unwrap();
+        assert!(output.contains("test-model: 1 embeddings"));
+        assert!(output.contains("Distance metric: cosine"));
+    }
+
+    #[test]
+    fn test_store_rejects_bad_json() {
+        let (_database, engine, _temp_dir) = setup_test_engine();
+
+        let result = run(
+            &engine,
+            VectorCommands::Store {
+                memory_id: "missing".to_string(),
+                embedding: "not json".to_string(),
+                model: "test-model".to_string(),
+            },
+        );
+        assert!(result.is_err());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_backfill_with_command() {
+        let (database, engine, _temp_dir) = setup_test_engine();
+        for content in ["first", "second", "third"] {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let backfill = |command: &str| VectorCommands::Backfill {
+            model: "test-model".to_string(),
+            command: command.to_string(),
+            batch_size: 1,
+        };
+
+        // Wrong number of embeddings for the batch
+        assert!(run(&engine, backfill("cat > /dev/null; echo '[]'")).is_err());
+
+        let output = run(&engine, backfill("cat > /dev/null; echo '[[1, 0, 0]]'")).unwrap();
+        assert!(output.contains("Embedded 1/3"));
+        assert!(output.contains("Embedded 3/3"));
+        assert!(output.contains("Backfilled 3 embeddings"));
+
+        let output = run(&engine, backfill("exit 1")).unwrap();
+        assert!(output.contains("All memories already have embeddings"));
+
+        let cache = |action| run(&engine, VectorCommands::Cache { action }).unwrap();
+        assert!(cache(CacheCommands::Stats).contains("Entries: 3"));
+        let purge = |model: &str| {
+            cache(CacheCommands::Purge {
+                model: Some(model.to_string()),
+                unused_for: None,
+            })
+        };
+        assert!(purge("other-model").contains("No cached embeddings to purge"));
+        assert!(purge("test-model").contains("Purged 3 cached embeddings"));
+        assert!(cache(CacheCommands::Stats).contains("Entries: 0"));
+    }
+
+    #[test]
+    fn test_search_with_filters() {
+        let (database, engine, _temp_dir) = setup_test_engine();
+        for (user_id, tags) in [("alice", "work"), ("bob", "work,travel")] {
+            let memory_id = database
+                .save_memory(&MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: format!("{}-session", user_id),
+                    content: format!("Memory of {}", user_id),
+                    metadata: [("tags".to_string(), tags.to_string())].into(),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &[0.1, 0.2, 0.3], "test-model")
+                .unwrap();
+        }
+
+        let search = |filter: FilterArgs| {
+            run(
+                &engine,
+                VectorCommands::Search {
+                    embedding: "[0.1, 0.2, 0.3]".to_string(),
+                    model: "test-model".to_string(),
+                    limit: 10,
+                    filter,
+                },
+            )
+            .unwrap()
+        };
+
+        let output = search(FilterArgs::default());
+        assert!(output.contains("Found 2 similar memories"));
+
+        let output = search(FilterArgs {
+            user: Some("alice".to_string()),
+            ..Default::default()
+        });
+        assert!(output.contains("Found 1 similar memories"));
+        assert!(output.contains("Memory of alice"));
+
+        let output = search(FilterArgs {
+            tags: vec!["travel".to_string()],
+            ..Default::default()
+        });
+        assert!(output.contains("Memory of bob"));
+        assert!(!output.contains("Memory of alice"));
+
+        let output = search(FilterArgs {
+            until: Some(chrono::Duration::days(1)),
+            ..Default::default()
+        });
+        assert!(output.contains("No similar memories found"));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_reembed_and_delete() {
+        let (database, engine, _temp_dir) = setup_test_engine();
+        let memory_id = database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Embedded memory".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        engine
+            .store_embedding(&memory_id, &[0.1, 0.2, 0.3], "old-model")
+            .unwrap();
+
+        let output = run(
+            &engine,
+            VectorCommands::Reembed {
+                memory_id: memory_id.clone(),
+                model: "new-model".to_string(),
+                command: "cat > /dev/null; echo '[[0, 0, 1]]'".to_string(),
+            },
+        )
+        .unwrap();
+        assert!(output.contains("Memory re-embedded"));
+        assert_eq!(engine.get_embedding(&memory_id, "old-model").unwrap(), None);
+        assert_eq!(
+            engine.get_embedding(&memory_id, "new-model").unwrap(),
+            Some(vec![0.0, 0.0, 1.0])
+        );
+
+        let delete = || {
+            run(
+                &engine,
+                VectorCommands::Delete {
+                    memory_id: memory_id.clone(),
+                },
+            )
+        };
+        assert!(delete().unwrap().contains("Embedding deleted"));
+        assert!(delete().is_err());
+    }
+
+    #[test]
+    fn test_diagnostics() {
+        let (database, engine, _temp_dir) = setup_test_engine();
+        let memory_id = database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Embedded memory".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        engine
+            .store_embedding(&memory_id, &[0.0, 3.0, 4.0], "test-model")
+            .unwrap();
+
+        let output = run(&engine, VectorCommands::Diagnostics).unwrap();
+        // Stored normalized under the default cosine metric
+        assert!(output.contains("Average norm: 1.000 over 1 sampled"));
+        assert!(output.contains("No anomalies found"));
+
+        database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Not embedded yet".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let output = run(&engine, VectorCommands::Diagnostics).unwrap();
+        assert!(output.contains("Anomalies:"));
+        assert!(output.contains("1 memories have no embedding"));
+    }
+}
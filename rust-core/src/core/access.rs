@@ -0,0 +1,244 @@
+//! API keys and roles
+//!
+//! An [`AccessConfig`] lists the API keys allowed to use a store. Each key
+//! has a [`Role`] that applies everywhere, and may raise or lower it for
+//! particular namespaces, so an agent can be a writer in its own namespace
+//! and read-only elsewhere. Keys are configured by the SHA-256 of their
+//! secret, never the secret itself:
+//!
+//! ```json
+//! {
+//!   "access": {
+//!     "api_keys": [
+//!       { "name": "dashboard", "key_sha256": "9f86d0…", "role": "read_only" },
+//!       {
+//!         "name": "planner",
+//!         "key_sha256": "60303a…",
+//!         "role": "read_only",
+//!         "namespaces": { "planner": "writer" }
+//!       },
+//!       { "name": "ops", "key_sha256": "fd61a0…", "role": "admin" }
+//!     ]
+//!   }
+//! }
+//! ```
+//!
+//! A key with `analytics_privacy` may only read analytics, which it gets
+//! aggregated as described in [`crate::core::privacy`].
+//!
+//! The library itself doesn't check roles; the FFI layer does when an
+//! instance is opened with a `token`, and so does the Node server for every
+//! request once keys are configured.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+use validator::Validate;
+
+use crate::core::privacy::AnalyticsPrivacy;
+
+/// What a key may do, each role allowing everything the ones before it do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    /// Read memories, sessions, stats and analytics
+    ReadOnly,
+    /// Also save and update memories and create sessions
+    Writer,
+    /// Also delete, run decay and maintenance, change policies and read the
+    /// audit log
+    Admin,
+}
+
+impl Role {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Role::ReadOnly => "read_only",
+            Role::Writer => "writer",
+            Role::Admin => "admin",
+        }
+    }
+}
+
+impl fmt::Display for Role {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Role {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s {
+            "read_only" => Ok(Role::ReadOnly),
+            "writer" => Ok(Role::Writer),
+            "admin" => Ok(Role::Admin),
+            _ => Err(anyhow::anyhow!("Invalid role: {}", s)),
+        }
+    }
+}
+
+/// Keys allowed to use the store; none configured means no checks
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct AccessConfig {
+    #[validate]
+    pub api_keys: Vec<ApiKey>,
+}
+
+/// One configured API key
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ApiKey {
+    /// Who holds the key; recorded as the actor of the writes it makes
+    #[validate(length(min = 1, max = 256))]
+    pub name: String,
+    /// Lowercase hex SHA-256 of the key's secret
+    #[validate(custom = "validate_sha256")]
+    pub key_sha256: String,
+    /// Role outside the namespaces listed in `namespaces`
+    pub role: Role,
+    /// Role for memories of particular namespaces, overriding `role`
+    #[serde(default)]
+    pub namespaces: BTreeMap<String, Role>,
+    /// Limit the key to aggregated analytics
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[validate]
+    pub analytics_privacy: Option<AnalyticsPrivacy>,
+}
+
+fn validate_sha256(hash: &str) -> Result<(), validator::ValidationError> {
+    if hash.len() == 64 && hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+        Ok(())
+    } else {
+        let mut error = validator::ValidationError::new("key_sha256");
+        error.message = Some("key_sha256 must be 64 lowercase hex digits".into());
+        Err(error)
+    }
+}
+
+/// Lowercase hex SHA-256 of `secret`, as `key_sha256` expects it
+pub fn hash_key(secret: &str) -> String {
+    Sha256::digest(secret.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+impl AccessConfig {
+    /// Whether any key is configured
+    pub fn is_enabled(&self) -> bool {
+        !self.api_keys.is_empty()
+    }
+
+    /// The key whose secret is `token`, if any
+    pub fn authenticate(&self, token: &str) -> Option<Principal> {
+        let hash = hash_key(token);
+        self.api_keys
+            .iter()
+            .find(|key| key.key_sha256 == hash)
+            .map(|key| Principal {
+                name: key.name.clone(),
+                role: key.role,
+                namespaces: key.namespaces.clone(),
+                analytics_privacy: key.analytics_privacy.clone(),
+            })
+    }
+}
+
+/// An authenticated API key
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Principal {
+    pub name: String,
+    pub role: Role,
+    pub namespaces: BTreeMap<String, Role>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub analytics_privacy: Option<AnalyticsPrivacy>,
+}
+
+impl Principal {
+    /// Role for memories of `namespace`, or outside any namespace if `None`
+    pub fn role_in(&self, namespace: Option<&str>) -> Role {
+        namespace
+            .and_then(|namespace| self.namespaces.get(namespace))
+            .copied()
+            .unwrap_or(self.role)
+    }
+
+    /// Highest role the key has anywhere
+    pub fn max_role(&self) -> Role {
+        self.namespaces.values().copied().fold(self.role, Role::max)
+    }
+
+    /// Fail unless the key has at least `required` in `namespace`
+    pub fn check(&self, required: Role, namespace: Option<&str>) -> Result<(), AccessDenied> {
+        let role = self.role_in(namespace);
+        if role >= required {
+            Ok(())
+        } else {
+            Err(AccessDenied {
+                principal: self.name.clone(),
+                role,
+                required,
+                namespace: namespace.map(str::to_string),
+            })
+        }
+    }
+}
+
+/// A key tried something its role doesn't allow
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AccessDenied {
+    pub principal: String,
+    pub role: Role,
+    pub required: Role,
+    pub namespace: Option<String>,
+}
+
+impl fmt::Display for AccessDenied {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has role {}", self.principal, self.role)?;
+        if let Some(namespace) = &self.namespace {
+            write!(f, " in namespace {}", namespace)?;
+        }
+        write!(f, ", but {} is required", self.required)
+    }
+}
+
+impl std::error::Error for AccessDenied {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_namespace_roles_override_the_default() {
+        let config = AccessConfig {
+            api_keys: vec![ApiKey {
+                name: "planner".to_string(),
+                key_sha256: hash_key("secret"),
+                role: Role::ReadOnly,
+                namespaces: BTreeMap::from([("planner".to_string(), Role::Writer)]),
+                analytics_privacy: None,
+            }],
+        };
+        assert!(config.validate().is_ok());
+        assert!(config.authenticate("wrong").is_none());
+
+        let principal = config.authenticate("secret").unwrap();
+        assert_eq!(principal.name, "planner");
+        assert_eq!(principal.max_role(), Role::Writer);
+        assert!(principal.check(Role::Writer, Some("planner")).is_ok());
+        assert!(principal.check(Role::ReadOnly, None).is_ok());
+
+        let denied = principal.check(Role::Writer, None).unwrap_err();
+        assert_eq!(denied.role, Role::ReadOnly);
+        assert_eq!(
+            denied.to_string(),
+            "planner has role read_only, but writer is required"
+        );
+        assert!(principal.check(Role::Admin, Some("planner")).is_err());
+    }
+}
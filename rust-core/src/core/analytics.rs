@@ -0,0 +1,58 @@
+//! Storage, growth, decay and topic figures for one user
+
+use serde::{Deserialize, Serialize};
+
+/// Sessions listed in [`UserAnalytics::top_sessions`]
+pub const ANALYTICS_TOP_SESSIONS: usize = 5;
+
+/// Topics listed in [`UserAnalytics::topic_distribution`]
+pub const ANALYTICS_TOP_TOPICS: usize = 10;
+
+/// Days of recent saves the growth rate is averaged over
+pub const GROWTH_WINDOW_DAYS: i64 = 30;
+
+/// One of a user's busiest sessions
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct SessionUsage {
+    pub session_id: String,
+    pub name: Option<String>,
+    pub memory_count: usize,
+    pub storage_bytes: u64,
+}
+
+/// Memories filed under one metadata `category`
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct TopicShare {
+    pub topic: String,
+    pub memory_count: usize,
+    /// Fraction of all the user's memories, between 0 and 1
+    pub share: f32,
+}
+
+/// Result of [`crate::core::memory::MemoryManager::get_user_analytics`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct UserAnalytics {
+    pub user_id: String,
+    pub memory_count: usize,
+    pub session_count: usize,
+    /// Content and metadata bytes of the user's memories
+    pub storage_bytes: u64,
+    /// Bytes saved per day over the last [`GROWTH_WINDOW_DAYS`] days
+    pub growth_bytes_per_day: f64,
+    /// Memories saved per day over the last [`GROWTH_WINDOW_DAYS`] days
+    pub memories_per_day: f64,
+    pub compressed_memories: usize,
+    /// Original memories folded into each compressed memory, on average;
+    /// `None` until decay has compressed some of the user's memories
+    pub compression_ratio: Option<f32>,
+    /// Share of memories a completed decay run removed or compressed away,
+    /// over all recorded runs. Decay runs cover the whole database, so this
+    /// is the same for every user; `None` before the first run.
+    pub decay_removal_rate: Option<f32>,
+    /// Up to [`ANALYTICS_TOP_SESSIONS`] sessions, most memories first
+    pub top_sessions: Vec<SessionUsage>,
+    /// Up to [`ANALYTICS_TOP_TOPICS`] categories, largest first
+    pub topic_distribution: Vec<TopicShare>,
+    /// Memories without a `category` in their metadata
+    pub uncategorized_memories: usize,
+}
@@ -0,0 +1,239 @@
+//! Ingestion anomaly detection
+//!
+//! Each user's saves over the last `baseline_hours` hours form a baseline;
+//! a last hour far above it usually means an agent stuck in a loop. Checks
+//! log a warning and call the registered hooks for every spiking user.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+
+use crate::database::Database;
+
+/// Thresholds for flagging an ingestion spike
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct AnomalyPolicy {
+    /// Standard deviations above the baseline mean that count as a spike
+    pub sensitivity: f32,
+    /// Hours before the last one the baseline is learned from
+    pub baseline_hours: u32,
+    /// Fewer saves than this in the last hour are never a spike
+    pub min_saves_per_hour: u32,
+}
+
+impl Default for AnomalyPolicy {
+    fn default() -> Self {
+        Self {
+            sensitivity: 3.0,
+            baseline_hours: 24 * 7,
+            min_saves_per_hour: 20,
+        }
+    }
+}
+
+/// A user's save rate in the last hour against their baseline
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IngestionRate {
+    pub user_id: String,
+    pub saves_last_hour: i64,
+    pub baseline_mean: f32,
+    pub baseline_stddev: f32,
+    /// Standard deviations the last hour is above the baseline mean
+    pub z_score: f32,
+    pub anomalous: bool,
+}
+
+/// Called with every user found spiking
+pub type AnomalyHook = Box<dyn Fn(&IngestionRate) + Send + Sync>;
+
+/// Watches per-user save rates for spikes
+pub struct AnomalyMonitor {
+    database: Database,
+    policy: AnomalyPolicy,
+    hooks: Mutex<Vec<AnomalyHook>>,
+}
+
+impl AnomalyMonitor {
+    pub fn new(database: Database, policy: AnomalyPolicy) -> Self {
+        Self {
+            database,
+            policy,
+            hooks: Mutex::new(Vec::new()),
+        }
+    }
+
+    pub fn policy(&self) -> AnomalyPolicy {
+        self.policy
+    }
+
+    /// Call `hook` for each spike found by later checks
+    pub fn on_anomaly(&self, hook: impl Fn(&IngestionRate) + Send + Sync + 'static) {
+        self.hooks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Box::new(hook));
+    }
+
+    /// Save rates of every user who saved anything in the baseline period
+    /// or the last hour, most anomalous first
+    pub fn ingestion_rates(&self) -> Result<Vec<IngestionRate>> {
+        let hourly = self
+            .database
+            .get_hourly_saves(self.policy.baseline_hours + 1)?;
+
+        let mut rates: Vec<IngestionRate> = hourly
+            .into_iter()
+            .map(|(user_id, saves)| self.rate(user_id, &saves))
+            .collect();
+        rates.sort_by(|a, b| {
+            b.z_score
+                .total_cmp(&a.z_score)
+                .then_with(|| a.user_id.cmp(&b.user_id))
+        });
+        Ok(rates)
+    }
+
+    /// Find spiking users, warning about each and passing it to the hooks
+    pub fn check(&self) -> Result<Vec<IngestionRate>> {
+        let anomalies: Vec<IngestionRate> = self
+            .ingestion_rates()?
+            .into_iter()
+            .filter(|rate| rate.anomalous)
+            .collect();
+
+        let hooks = self
+            .hooks
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        for anomaly in &anomalies {
+            tracing::warn!(
+                user_id = anomaly.user_id.as_str(),
+                saves = anomaly.saves_last_hour,
+                baseline = anomaly.baseline_mean,
+                z_score = anomaly.z_score,
+                "Memory ingestion spike"
+            );
+            for hook in hooks.iter() {
+                hook(anomaly);
+            }
+        }
+        Ok(anomalies)
+    }
+
+    /// Score the last hour (`saves[0]`) against the hours before it
+    fn rate(&self, user_id: String, saves: &[i64]) -> IngestionRate {
+        let saves_last_hour = saves.first().copied().unwrap_or(0);
+        let baseline = saves.get(1..).unwrap_or(&[]);
+
+        let count = baseline.len().max(1) as f32;
+        let mean = baseline.iter().sum::<i64>() as f32 / count;
+        let variance = baseline
+            .iter()
+            .map(|&saves| (saves as f32 - mean).powi(2))
+            .sum::<f32>()
+            / count;
+        let stddev = variance.sqrt();
+
+        // Save counts are roughly Poisson, so a perfectly steady baseline
+        // still allows sqrt(mean) of noise, and at least one save
+        let spread = stddev.max(mean.sqrt()).max(1.0);
+        let z_score = (saves_last_hour as f32 - mean) / spread;
+
+        IngestionRate {
+            user_id,
+            saves_last_hour,
+            baseline_mean: mean,
+            baseline_stddev: stddev,
+            z_score,
+            anomalous: saves_last_hour >= self.policy.min_saves_per_hour as i64
+                && z_score >= self.policy.sensitivity,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::DatabaseConfig;
+    use chrono::{Duration, Utc};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_detects_spikes() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        let save = |user_id: &str, hours_ago: i64| {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: format!("{}-session", user_id),
+                    content: "content".to_string(),
+                    created_at: Utc::now() - Duration::hours(hours_ago) - Duration::minutes(5),
+                    ..Default::default()
+                })
+                .unwrap();
+        };
+
+        // Steady: two saves an hour for the last day, same as the baseline
+        for hours_ago in 0..24 {
+            save("steady", hours_ago);
+            save("steady", hours_ago);
+        }
+        // Looping: one save a day, then thirty in the last hour
+        for hours_ago in [24, 48] {
+            save("looping", hours_ago);
+        }
+        for _ in 0..30 {
+            save("looping", 0);
+        }
+
+        let monitor = AnomalyMonitor::new(
+            database,
+            AnomalyPolicy {
+                baseline_hours: 72,
+                ..Default::default()
+            },
+        );
+        let fired = Arc::new(AtomicUsize::new(0));
+        let counter = fired.clone();
+        monitor.on_anomaly(move |rate| {
+            assert_eq!(rate.user_id, "looping");
+            counter.fetch_add(1, Ordering::SeqCst);
+        });
+
+        let rates = monitor.ingestion_rates().unwrap();
+        assert_eq!(rates.len(), 2);
+        assert_eq!(rates[0].user_id, "looping");
+        assert_eq!(rates[0].saves_last_hour, 30);
+        assert!(rates[0].anomalous);
+        assert_eq!(rates[1].saves_last_hour, 2);
+        assert!(!rates[1].anomalous);
+
+        let anomalies = monitor.check().unwrap();
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(fired.load(Ordering::SeqCst), 1);
+
+        // Raising the floor above the spike silences it
+        let monitor = AnomalyMonitor::new(
+            monitor.database.clone(),
+            AnomalyPolicy {
+                baseline_hours: 72,
+                min_saves_per_hour: 50,
+                ..Default::default()
+            },
+        );
+        assert!(monitor.check().unwrap().is_empty());
+    }
+}
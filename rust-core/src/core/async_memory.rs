@@ -8,6 +8,7 @@ use tokio::task;
 use crate::core::{PerformanceMonitor, RequestValidator};
 use crate::database::async_db::AsyncDatabase;
 use crate::database::models::*;
+use crate::display::truncate_graphemes;
 
 /// Async session manager for high-performance operations
 pub struct AsyncSessionManager {
@@ -41,7 +42,7 @@ impl AsyncSessionManager {
             .await
             .context("Failed to create session")?;
 
-        log::debug!("Created session {} for user {}", session_id, user_id);
+        tracing::debug!("Created session {} for user {}", session_id, user_id);
         Ok(session_id)
     }
 
@@ -69,7 +70,7 @@ impl AsyncSessionManager {
             monitor.record_query_time(duration);
         }
 
-        log::debug!(
+        tracing::debug!(
             "Retrieved {} sessions for user {} in {}ms",
             response.data.len(),
             user_id,
@@ -112,7 +113,7 @@ impl AsyncSessionManager {
             monitor.record_query_time(duration);
         }
 
-        log::debug!(
+        tracing::debug!(
             "Generated summary for session {} with {} memories in {}ms",
             session_id,
             memories.len(),
@@ -173,6 +174,11 @@ impl AsyncSessionManager {
             importance_score,
             created_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            version: 0,
+            generator: Default::default(),
+            generated_at: chrono::Utc::now(),
+            stale: false,
+            memory_id: None,
         })
     }
 
@@ -255,13 +261,7 @@ impl AsyncSessionManager {
         let highlights = important_memories
             .iter()
             .take(3)
-            .map(|m| {
-                if m.content.len() > 100 {
-                    format!("\"{}...\"", &m.content[..100])
-                } else {
-                    format!("\"{}\"", m.content)
-                }
-            })
+            .map(|m| format!("\"{}\"", truncate_graphemes(&m.content, 100)))
             .collect::<Vec<_>>();
 
         // Construct summary
@@ -486,7 +486,7 @@ impl AsyncSessionManager {
                 }
             }
 
-            log::info!(
+            tracing::info!(
                 "Deleted {} memories from session {}",
                 deleted_count,
                 session_id
@@ -494,7 +494,7 @@ impl AsyncSessionManager {
         }
 
         // TODO: Implement delete_session in database layer
-        log::debug!("Deleted session {}", session_id);
+        tracing::debug!("Deleted session {}", session_id);
 
         Ok(true)
     }
@@ -40,7 +40,7 @@ impl AsyncSessionManager {
             .await
             .context("Failed to create session")?;
 
-        log::debug!("Created session {} for user {}", session_id, user_id);
+        tracing::debug!("Created session {} for user {}", session_id, user_id);
         Ok(session_id)
     }
 
@@ -190,7 +190,7 @@ impl AsyncSessionManager {
             .await
             .context("Failed to delete session")?;
 
-        log::info!(
+        tracing::info!(
             "Deleted session {} for user {} ({} memories)",
             session_id,
             user_id,
@@ -0,0 +1,442 @@
+//! Topic clustering of a user's memories
+//!
+//! Memories are clustered with spherical k-means (cosine similarity) over
+//! their embeddings when every memory has one, or over TF-IDF vectors of
+//! their content otherwise. Clusters are labelled with their most
+//! distinctive terms either way.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::models::MemoryItem;
+
+/// Most recent memories considered; silhouette scoring is quadratic
+pub const MAX_CLUSTER_MEMORIES: usize = 500;
+
+/// Largest cluster count tried when choosing k automatically
+const MAX_AUTO_CLUSTERS: usize = 8;
+
+const MAX_ITERATIONS: usize = 50;
+
+/// Most frequent terms kept as TF-IDF dimensions
+const TFIDF_VOCABULARY: usize = 300;
+
+const KEY_TERMS: usize = 5;
+const REPRESENTATIVES: usize = 3;
+
+/// What the memories were compared by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ClusteringMethod {
+    Embeddings,
+    TfIdf,
+}
+
+impl std::fmt::Display for ClusteringMethod {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Embeddings => "embeddings",
+            Self::TfIdf => "TF-IDF",
+        })
+    }
+}
+
+/// One topic among a user's memories
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryCluster {
+    /// The top key terms, or "Cluster N" when the memories share none
+    pub label: String,
+    pub size: usize,
+    /// Terms frequent in this cluster but rare elsewhere, best first
+    pub key_terms: Vec<String>,
+    /// Memories closest to the cluster's centre, closest first
+    pub representatives: Vec<MemoryItem>,
+}
+
+/// Result of [`crate::core::memory::MemoryManager::cluster_memories`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClusterReport {
+    pub user_id: String,
+    pub method: ClusteringMethod,
+    /// Memories that were clustered, at most `MAX_CLUSTER_MEMORIES`
+    pub memory_count: usize,
+    /// Largest first
+    pub clusters: Vec<MemoryCluster>,
+}
+
+/// Cluster `memories` into `k` topics, or as many as fit best if `k` is None
+///
+/// `vectors` holds one vector per memory; they're normalized here. `k` is
+/// capped at the number of memories.
+pub fn cluster(
+    memories: Vec<MemoryItem>,
+    mut vectors: Vec<Vec<f32>>,
+    k: Option<usize>,
+) -> Vec<MemoryCluster> {
+    if memories.is_empty() {
+        return Vec::new();
+    }
+    let assignments = assign(&mut vectors, k);
+
+    let documents: Vec<HashSet<String>> = memories
+        .iter()
+        .map(|memory| super::text::tokenize(&memory.content).into_iter().collect())
+        .collect();
+    let mut doc_freq: HashMap<&str, usize> = HashMap::new();
+    for document in &documents {
+        for term in document {
+            *doc_freq.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let cluster_count = assignments.iter().max().map_or(0, |max| max + 1);
+    let mut members: Vec<Vec<usize>> = vec![Vec::new(); cluster_count];
+    for (index, &cluster) in assignments.iter().enumerate() {
+        members[cluster].push(index);
+    }
+
+    let mut clusters: Vec<MemoryCluster> = members
+        .into_iter()
+        .filter(|indices| !indices.is_empty())
+        .map(|indices| {
+            let center = centroid(&vectors, &indices);
+            let mut by_closeness = indices.clone();
+            by_closeness
+                .sort_by(|&a, &b| dot(&vectors[b], &center).total_cmp(&dot(&vectors[a], &center)));
+
+            let key_terms = key_terms(&documents, &indices, &doc_freq);
+            MemoryCluster {
+                label: key_terms
+                    .iter()
+                    .take(3)
+                    .cloned()
+                    .collect::<Vec<_>>()
+                    .join(", "),
+                size: indices.len(),
+                key_terms,
+                representatives: by_closeness
+                    .into_iter()
+                    .take(REPRESENTATIVES)
+                    .map(|index| memories[index].clone())
+                    .collect(),
+            }
+        })
+        .collect();
+
+    clusters.sort_by_key(|cluster| std::cmp::Reverse(cluster.size));
+    for (number, cluster) in clusters.iter_mut().enumerate() {
+        if cluster.label.is_empty() {
+            cluster.label = format!("Cluster {}", number + 1);
+        }
+    }
+    clusters
+}
+
+/// Each vector's cluster among `k`, or as many as fit best if `k` is None,
+/// normalizing the vectors in place
+///
+/// `k` is capped at the number of vectors.
+pub fn assign(vectors: &mut [Vec<f32>], k: Option<usize>) -> Vec<usize> {
+    if vectors.is_empty() {
+        return Vec::new();
+    }
+    vectors.iter_mut().for_each(|vector| normalize(vector));
+    match k {
+        Some(k) => kmeans(vectors, k.clamp(1, vectors.len())),
+        None => choose_k(vectors),
+    }
+}
+
+/// TF-IDF vectors of each memory's content over the most common terms
+pub fn tfidf_vectors(memories: &[MemoryItem]) -> Vec<Vec<f32>> {
+    let documents: Vec<Vec<String>> = memories
+        .iter()
+        .map(|memory| super::text::tokenize(&memory.content))
+        .collect();
+
+    let mut doc_freq: HashMap<&str, usize> = HashMap::new();
+    for document in &documents {
+        let unique: HashSet<&str> = document.iter().map(String::as_str).collect();
+        for term in unique {
+            *doc_freq.entry(term).or_insert(0) += 1;
+        }
+    }
+
+    let mut vocabulary: Vec<(&str, usize)> = doc_freq.into_iter().collect();
+    vocabulary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    vocabulary.truncate(TFIDF_VOCABULARY);
+    let positions: HashMap<&str, usize> = vocabulary
+        .iter()
+        .enumerate()
+        .map(|(position, (term, _))| (*term, position))
+        .collect();
+
+    let total_docs = documents.len() as f32;
+    documents
+        .iter()
+        .map(|document| {
+            let mut vector = vec![0.0; vocabulary.len()];
+            for term in document {
+                if let Some(&position) = positions.get(term.as_str()) {
+                    vector[position] += 1.0;
+                }
+            }
+            for (value, (_, df)) in vector.iter_mut().zip(&vocabulary) {
+                *value *= (1.0 + total_docs / *df as f32).ln();
+            }
+            vector
+        })
+        .collect()
+}
+
+/// Spherical k-means over normalized vectors, returning each vector's
+/// cluster. Centres are seeded farthest-first, so results are deterministic.
+pub fn kmeans(vectors: &[Vec<f32>], k: usize) -> Vec<usize> {
+    let all: Vec<usize> = (0..vectors.len()).collect();
+    let mean = centroid(vectors, &all);
+
+    // Start from the most typical vector, then keep adding the one least
+    // similar to every centre so far
+    let first = (0..vectors.len())
+        .max_by(|&a, &b| dot(&vectors[a], &mean).total_cmp(&dot(&vectors[b], &mean)))
+        .unwrap_or(0);
+    let mut centers = vec![vectors[first].clone()];
+    while centers.len() < k {
+        let farthest = (0..vectors.len())
+            .min_by(|&a, &b| {
+                closest(&centers, &vectors[a])
+                    .1
+                    .total_cmp(&closest(&centers, &vectors[b]).1)
+            })
+            .unwrap_or(0);
+        centers.push(vectors[farthest].clone());
+    }
+
+    let mut assignments = vec![usize::MAX; vectors.len()];
+    for _ in 0..MAX_ITERATIONS {
+        let mut changed = false;
+        for (assignment, vector) in assignments.iter_mut().zip(vectors) {
+            let (cluster, _) = closest(&centers, vector);
+            if *assignment != cluster {
+                *assignment = cluster;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+
+        for (cluster, center) in centers.iter_mut().enumerate() {
+            let indices: Vec<usize> = (0..vectors.len())
+                .filter(|&index| assignments[index] == cluster)
+                .collect();
+            // An empty cluster keeps its old centre
+            if !indices.is_empty() {
+                *center = centroid(vectors, &indices);
+            }
+        }
+    }
+    assignments
+}
+
+/// Mean silhouette of a clustering under cosine distance, in [-1, 1];
+/// higher means tighter, better separated clusters
+pub fn silhouette(similarities: &[Vec<f32>], assignments: &[usize]) -> f32 {
+    let cluster_count = assignments.iter().max().map_or(0, |max| max + 1);
+    if cluster_count < 2 {
+        return 0.0;
+    }
+
+    let mut total = 0.0;
+    for (i, &own) in assignments.iter().enumerate() {
+        let mut distance_sums = vec![0.0f32; cluster_count];
+        let mut sizes = vec![0usize; cluster_count];
+        for (j, &cluster) in assignments.iter().enumerate() {
+            if i != j {
+                distance_sums[cluster] += 1.0 - similarities[i][j];
+                sizes[cluster] += 1;
+            }
+        }
+
+        // Singletons score 0 by convention
+        if sizes[own] == 0 {
+            continue;
+        }
+        let cohesion = distance_sums[own] / sizes[own] as f32;
+        let separation = (0..cluster_count)
+            .filter(|&cluster| cluster != own && sizes[cluster] > 0)
+            .map(|cluster| distance_sums[cluster] / sizes[cluster] as f32)
+            .fold(f32::INFINITY, f32::min);
+        if separation.is_finite() {
+            let spread = cohesion.max(separation);
+            if spread > 0.0 {
+                total += (separation - cohesion) / spread;
+            }
+        }
+    }
+    total / assignments.len() as f32
+}
+
+/// Cluster with the k in 2..=`MAX_AUTO_CLUSTERS` that has the best
+/// silhouette; fewer than four memories stay in one cluster
+fn choose_k(vectors: &[Vec<f32>]) -> Vec<usize> {
+    let max_k = MAX_AUTO_CLUSTERS.min(vectors.len() / 2);
+    if max_k < 2 {
+        return vec![0; vectors.len()];
+    }
+
+    let similarities: Vec<Vec<f32>> = vectors
+        .iter()
+        .map(|a| vectors.iter().map(|b| dot(a, b)).collect())
+        .collect();
+
+    let mut best: Option<(f32, Vec<usize>)> = None;
+    for k in 2..=max_k {
+        let assignments = kmeans(vectors, k);
+        let score = silhouette(&similarities, &assignments);
+        if best
+            .as_ref()
+            .is_none_or(|(best_score, _)| score > *best_score)
+        {
+            best = Some((score, assignments));
+        }
+    }
+    best.map(|(_, assignments)| assignments)
+        .unwrap_or_else(|| vec![0; vectors.len()])
+}
+
+/// Terms in the most of a cluster's memories, weighted by how rare they are
+/// across all memories
+fn key_terms(
+    documents: &[HashSet<String>],
+    indices: &[usize],
+    doc_freq: &HashMap<&str, usize>,
+) -> Vec<String> {
+    let mut cluster_freq: HashMap<&str, usize> = HashMap::new();
+    for &index in indices {
+        for term in &documents[index] {
+            *cluster_freq.entry(term.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let total_docs = documents.len() as f32;
+    let mut scored: Vec<(&str, f32)> = cluster_freq
+        .into_iter()
+        // A term in a single memory says little about the topic
+        .filter(|(_, count)| *count > 1 || indices.len() == 1)
+        .map(|(term, count)| {
+            let idf = (1.0 + total_docs / doc_freq[term] as f32).ln();
+            (term, count as f32 * idf)
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+
+    scored
+        .into_iter()
+        .take(KEY_TERMS)
+        .map(|(term, _)| term.to_string())
+        .collect()
+}
+
+/// Index and similarity of the centre closest to `vector`
+fn closest(centers: &[Vec<f32>], vector: &[f32]) -> (usize, f32) {
+    centers
+        .iter()
+        .enumerate()
+        .map(|(index, center)| (index, dot(center, vector)))
+        .fold((0, f32::NEG_INFINITY), |best, candidate| {
+            if candidate.1 > best.1 {
+                candidate
+            } else {
+                best
+            }
+        })
+}
+
+/// Normalized mean of the vectors at `indices`
+fn centroid(vectors: &[Vec<f32>], indices: &[usize]) -> Vec<f32> {
+    let dimension = vectors.first().map_or(0, Vec::len);
+    let mut center = vec![0.0; dimension];
+    for &index in indices {
+        for (sum, value) in center.iter_mut().zip(&vectors[index]) {
+            *sum += value;
+        }
+    }
+    normalize(&mut center);
+    center
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|x| *x /= norm);
+    }
+}
+
+fn dot(a: &[f32], b: &[f32]) -> f32 {
+    a.iter().zip(b).map(|(x, y)| x * y).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(content: &str) -> MemoryItem {
+        MemoryItem {
+            user_id: "user1".to_string(),
+            session_id: "session1".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_kmeans_separates_groups() {
+        let mut vectors = vec![
+            vec![1.0, 0.1, 0.0],
+            vec![0.9, 0.0, 0.1],
+            vec![0.0, 1.0, 0.1],
+            vec![0.1, 0.9, 0.0],
+            vec![0.0, 0.1, 1.0],
+        ];
+        vectors.iter_mut().for_each(|vector| normalize(vector));
+
+        let assignments = kmeans(&vectors, 3);
+        assert_eq!(assignments[0], assignments[1]);
+        assert_eq!(assignments[2], assignments[3]);
+        let distinct: HashSet<_> = assignments.iter().collect();
+        assert_eq!(distinct.len(), 3);
+
+        // k larger than the data still gives every vector a cluster
+        assert_eq!(kmeans(&vectors, 5).len(), 5);
+    }
+
+    #[test]
+    fn test_auto_clusters_by_topic() {
+        let memories = vec![
+            memory("Bought shares of the tech index fund"),
+            memory("Stock market rally lifted the index fund"),
+            memory("Sold shares before the market closed"),
+            memory("Tried a new pasta recipe with basil"),
+            memory("Pasta dinner needs fresh basil and garlic"),
+            memory("Garlic bread recipe for the dinner party"),
+        ];
+        let vectors = tfidf_vectors(&memories);
+
+        let clusters = cluster(memories, vectors, None);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters.iter().map(|c| c.size).sum::<usize>(), 6);
+
+        let cooking = clusters
+            .iter()
+            .find(|c| c.key_terms.contains(&"pasta".to_string()))
+            .unwrap();
+        assert_eq!(cooking.size, 3);
+        assert!(!cooking.key_terms.contains(&"market".to_string()));
+        assert!(cooking
+            .representatives
+            .iter()
+            .all(|m| !m.content.contains("shares")));
+        assert!(!cooking.label.is_empty());
+    }
+}
@@ -0,0 +1,164 @@
+//! Contradictions between memories
+//!
+//! Two memories are flagged as a possible conflict when they are about the
+//! same thing (cosine similarity of their embeddings or TF-IDF vectors of at
+//! least [`CONFLICT_SIMILARITY`]) and either one is negated where the other
+//! isn't ("prefers dark mode" / "doesn't prefer dark mode"), or they share a
+//! metadata key holding different numbers or booleans. Flags are only
+//! suggestions: an agent confirms them with a `contradicts` link or
+//! dismisses them with a `compatible` one, which stops them being flagged
+//! again.
+
+use serde::{Deserialize, Serialize};
+
+use crate::database::models::MemoryItem;
+
+/// Least similarity at which two memories are compared for conflicts
+pub const CONFLICT_SIMILARITY: f32 = 0.5;
+
+/// Words that negate a statement
+const NEGATIONS: &[&str] = &[
+    "not", "no", "never", "none", "nobody", "nothing", "neither", "nor", "without", "cannot",
+];
+
+/// Why two memories look contradictory
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ConflictReason {
+    /// One memory is negated and the other isn't
+    Negation,
+    /// Both memories have metadata `key`, with different numbers or booleans
+    Metadata {
+        key: String,
+        value: String,
+        other_value: String,
+    },
+}
+
+/// A memory that may contradict another one
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryConflict {
+    pub memory: MemoryItem,
+    pub similarity: f32,
+    /// Empty for a confirmed conflict the heuristics no longer flag
+    pub reasons: Vec<ConflictReason>,
+    /// Linked as `contradicts`
+    pub confirmed: bool,
+}
+
+/// Whether `content` is negated: it holds an odd number of negations, so
+/// "not that she doesn't" reads as unnegated
+pub fn is_negated(content: &str) -> bool {
+    let negations = content
+        .split(|c: char| !c.is_alphanumeric() && c != '\'' && c != '’')
+        .filter(|word| {
+            let word = word.to_lowercase();
+            NEGATIONS.contains(&word.as_str()) || word.ends_with("n't") || word.ends_with("n’t")
+        })
+        .count();
+    negations % 2 == 1
+}
+
+/// Reasons `memory` and `other` contradict each other, assuming they are
+/// similar enough to be about the same thing
+pub fn conflict_reasons(memory: &MemoryItem, other: &MemoryItem) -> Vec<ConflictReason> {
+    let mut reasons = Vec::new();
+    if is_negated(&memory.content) != is_negated(&other.content) {
+        reasons.push(ConflictReason::Negation);
+    }
+
+    let mut keys: Vec<&String> = memory.metadata.keys().collect();
+    keys.sort();
+    for key in keys {
+        let (Some(value), Some(other_value)) = (memory.metadata.get(key), other.metadata.get(key))
+        else {
+            continue;
+        };
+        let differs = match (parse_bool(value), parse_bool(other_value)) {
+            (Some(a), Some(b)) => a != b,
+            _ => match (
+                value.trim().parse::<f64>(),
+                other_value.trim().parse::<f64>(),
+            ) {
+                (Ok(a), Ok(b)) => a.is_finite() && b.is_finite() && a != b,
+                _ => false,
+            },
+        };
+        if differs {
+            reasons.push(ConflictReason::Metadata {
+                key: key.clone(),
+                value: value.clone(),
+                other_value: other_value.clone(),
+            });
+        }
+    }
+    reasons
+}
+
+fn parse_bool(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" => Some(true),
+        "false" | "no" => Some(false),
+        _ => None,
+    }
+}
+
+/// Cosine similarity of two vectors, 0 if either is all zeros
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        0.0
+    } else {
+        dot / (norm_a * norm_b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(content: &str, metadata: &[(&str, &str)]) -> MemoryItem {
+        MemoryItem {
+            content: content.to_string(),
+            metadata: metadata
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_negation_and_metadata_reasons() {
+        assert!(is_negated("Alice doesn't like coffee"));
+        assert!(is_negated("Alice never drinks coffee"));
+        assert!(!is_negated("Alice likes coffee"));
+        assert!(!is_negated("It's not that she doesn't like coffee"));
+
+        let likes = memory("Alice likes coffee", &[("cups", "3"), ("decaf", "false")]);
+        let dislikes = memory("Alice doesn't like coffee", &[("cups", "3.0")]);
+        assert_eq!(
+            conflict_reasons(&likes, &dislikes),
+            vec![ConflictReason::Negation]
+        );
+
+        let decaf = memory("Alice likes coffee", &[("cups", "2"), ("decaf", "yes")]);
+        assert_eq!(
+            conflict_reasons(&likes, &decaf),
+            vec![
+                ConflictReason::Metadata {
+                    key: "cups".to_string(),
+                    value: "3".to_string(),
+                    other_value: "2".to_string(),
+                },
+                ConflictReason::Metadata {
+                    key: "decaf".to_string(),
+                    value: "false".to_string(),
+                    other_value: "yes".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,161 @@
+//! Request context
+//!
+//! A [`RequestContext`] says who made a request, which trace it belongs to
+//! and where it came from. Entering one, directly with
+//! [`RequestContext::enter`] or through a manager's `in_context`, adds those
+//! fields to the logging context and makes every memory and session write on
+//! the thread leave an entry in the audit log, so systems with several agents
+//! sharing one store can tell which of them wrote what. Writes made outside a
+//! context are not audited.
+
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use validator::Validate;
+
+use crate::logging;
+
+thread_local! {
+    /// Contexts entered on this thread, outermost first
+    static CURRENT: RefCell<Vec<RequestContext>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Who made a request and why, recorded with the writes it makes
+///
+/// Every field is optional and the default context is empty:
+///
+/// ```
+/// use memex_core::core::context::RequestContext;
+///
+/// let context = RequestContext::new()
+///     .with_actor("planner-agent")
+///     .with_trace_id("4bf92f3577b34da6a3ce929d0e0e4736")
+///     .with_source("node-api");
+/// let _scope = context.enter();
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct RequestContext {
+    /// The agent, user or service making the request
+    #[validate(length(min = 1, max = 256))]
+    pub actor: Option<String>,
+    /// Correlates the request's writes and log lines with the caller's trace
+    #[validate(length(min = 1, max = 256))]
+    pub trace_id: Option<String>,
+    /// What sent the request, e.g. "cli" or "node-api"
+    #[validate(length(min = 1, max = 256))]
+    pub source: Option<String>,
+}
+
+impl RequestContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_actor(mut self, actor: impl Into<String>) -> Self {
+        self.actor = Some(actor.into());
+        self
+    }
+
+    pub fn with_trace_id(mut self, trace_id: impl Into<String>) -> Self {
+        self.trace_id = Some(trace_id.into());
+        self
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Whether no field is set; an empty context audits nothing
+    pub fn is_empty(&self) -> bool {
+        self.actor.is_none() && self.trace_id.is_none() && self.source.is_none()
+    }
+
+    /// Make this the current context on this thread until the returned scope
+    /// is dropped
+    ///
+    /// Scopes nest; the innermost is current. Entering an empty context
+    /// hides any outer one, so nothing inside it is audited.
+    pub fn enter(&self) -> ContextScope {
+        let span = logging::scope();
+        for (key, value) in [
+            ("actor", &self.actor),
+            ("trace_id", &self.trace_id),
+            ("source", &self.source),
+        ] {
+            if let Some(value) = value {
+                span.record(key, value);
+            }
+        }
+        CURRENT.with(|current| current.borrow_mut().push(self.clone()));
+
+        ContextScope {
+            _span: span,
+            _not_send: PhantomData,
+        }
+    }
+
+    /// The innermost context entered on this thread, if it isn't empty
+    pub fn current() -> Option<RequestContext> {
+        CURRENT.with(|current| {
+            current
+                .borrow()
+                .last()
+                .filter(|context| !context.is_empty())
+                .cloned()
+        })
+    }
+}
+
+/// Guard returned by [`RequestContext::enter`]
+#[must_use = "the context is left when the scope is dropped"]
+pub struct ContextScope {
+    _span: logging::Span,
+    // The context is thread-local, so the guard must stay on its thread
+    _not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextScope {
+    fn drop(&mut self) {
+        CURRENT.with(|current| {
+            current.borrow_mut().pop();
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scopes_nest() {
+        assert_eq!(RequestContext::current(), None);
+        let (dispatch, lines) = logging::capture("info", logging::LogFormat::Text);
+        let _dispatch = tracing::dispatcher::set_default(&dispatch);
+        let last_line = || lines.lock().unwrap().pop().unwrap();
+
+        let outer = RequestContext::new().with_actor("outer");
+        let _outer = outer.enter();
+        assert_eq!(RequestContext::current(), Some(outer.clone()));
+        tracing::info!("Outer");
+        assert!(last_line().contains(" actor=outer"));
+
+        {
+            let inner = RequestContext::new()
+                .with_actor("inner")
+                .with_trace_id("trace-1");
+            let _inner = inner.enter();
+            assert_eq!(RequestContext::current(), Some(inner));
+            tracing::info!("Inner");
+            assert!(last_line().ends_with(" actor=inner trace_id=trace-1"));
+
+            let _hidden = RequestContext::new().enter();
+            assert_eq!(RequestContext::current(), None);
+        }
+
+        assert_eq!(RequestContext::current(), Some(outer));
+        tracing::info!("Outer again");
+        assert!(!last_line().contains("trace_id"));
+    }
+}
@@ -0,0 +1,147 @@
+//! Lock-free operation counters
+//!
+//! Managers bump these on every save, recall and search so FFI consumers can
+//! report usage without running the stats queries. Counts start at zero when
+//! the managers are created and are never persisted.
+
+use chrono::{DateTime, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicI64, AtomicU64, Ordering};
+
+/// Operation counted by [`EventCounters`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CounterEvent {
+    Save,
+    Recall,
+    Search,
+}
+
+/// Counts and last-seen times of manager operations
+///
+/// Shared between the managers of one instance through an `Arc`; every
+/// update is a single atomic operation, so recording never blocks.
+#[derive(Debug)]
+pub struct EventCounters {
+    started_at: DateTime<Utc>,
+    saves: AtomicU64,
+    recalls: AtomicU64,
+    searches: AtomicU64,
+    errors: AtomicU64,
+    // Unix milliseconds; 0 until the first event
+    last_save_at: AtomicI64,
+    last_recall_at: AtomicI64,
+    last_search_at: AtomicI64,
+    last_error_at: AtomicI64,
+}
+
+/// Point-in-time copy of [`EventCounters`], as returned by
+/// `memex_get_counters`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CounterSnapshot {
+    /// When counting started
+    pub since: DateTime<Utc>,
+    pub saves: u64,
+    pub recalls: u64,
+    pub searches: u64,
+    pub errors: u64,
+    pub last_save_at: Option<DateTime<Utc>>,
+    pub last_recall_at: Option<DateTime<Utc>>,
+    pub last_search_at: Option<DateTime<Utc>>,
+    pub last_error_at: Option<DateTime<Utc>>,
+}
+
+impl Default for EventCounters {
+    fn default() -> Self {
+        Self {
+            started_at: Utc::now(),
+            saves: AtomicU64::new(0),
+            recalls: AtomicU64::new(0),
+            searches: AtomicU64::new(0),
+            errors: AtomicU64::new(0),
+            last_save_at: AtomicI64::new(0),
+            last_recall_at: AtomicI64::new(0),
+            last_search_at: AtomicI64::new(0),
+            last_error_at: AtomicI64::new(0),
+        }
+    }
+}
+
+impl EventCounters {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count `event` if `result` succeeded, otherwise count an error
+    pub fn record<T, E>(&self, event: CounterEvent, result: &Result<T, E>) {
+        if result.is_err() {
+            self.record_error();
+            return;
+        }
+
+        let (count, last) = match event {
+            CounterEvent::Save => (&self.saves, &self.last_save_at),
+            CounterEvent::Recall => (&self.recalls, &self.last_recall_at),
+            CounterEvent::Search => (&self.searches, &self.last_search_at),
+        };
+        count.fetch_add(1, Ordering::Relaxed);
+        last.fetch_max(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn record_error(&self) {
+        self.errors.fetch_add(1, Ordering::Relaxed);
+        self.last_error_at
+            .fetch_max(Utc::now().timestamp_millis(), Ordering::Relaxed);
+    }
+
+    pub fn snapshot(&self) -> CounterSnapshot {
+        let at = |millis: &AtomicI64| match millis.load(Ordering::Relaxed) {
+            0 => None,
+            millis => Utc.timestamp_millis_opt(millis).single(),
+        };
+
+        CounterSnapshot {
+            since: self.started_at,
+            saves: self.saves.load(Ordering::Relaxed),
+            recalls: self.recalls.load(Ordering::Relaxed),
+            searches: self.searches.load(Ordering::Relaxed),
+            errors: self.errors.load(Ordering::Relaxed),
+            last_save_at: at(&self.last_save_at),
+            last_recall_at: at(&self.last_recall_at),
+            last_search_at: at(&self.last_search_at),
+            last_error_at: at(&self.last_error_at),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_concurrent_recording() {
+        let counters = Arc::new(EventCounters::new());
+        let threads: Vec<_> = (0..8)
+            .map(|_| {
+                let counters = counters.clone();
+                std::thread::spawn(move || {
+                    for _ in 0..1000 {
+                        counters.record(CounterEvent::Save, &Ok::<_, ()>(()));
+                        counters.record(CounterEvent::Search, &Err::<(), _>(()));
+                    }
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let snapshot = counters.snapshot();
+        assert_eq!(snapshot.saves, 8000);
+        assert_eq!(snapshot.searches, 0);
+        assert_eq!(snapshot.errors, 8000);
+        assert!(snapshot.last_save_at.unwrap() >= snapshot.since);
+        assert!(snapshot.last_error_at.is_some());
+        assert!(snapshot.last_recall_at.is_none());
+    }
+}
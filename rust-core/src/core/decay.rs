@@ -2,12 +2,44 @@
 
 use anyhow::{Context, Result};
 use chrono::Utc;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::mpsc::{self, SyncSender};
+use std::sync::{Arc, Mutex};
+use std::thread;
 use uuid::Uuid;
 use validator::Validate;
 
+use crate::core::session::SessionManager;
 use crate::core::{PerformanceMonitor, RequestValidator};
 use crate::database::{models::*, Database};
+use crate::display::truncate_graphemes;
+use crate::logging;
+
+/// Memories read per batch during decay, and most deleted per transaction
+/// or folded into one compressed memory
+pub const DECAY_BATCH_SIZE: usize = 1000;
+
+/// Fewest memories a session needs before decay summarizes it
+pub const MIN_SUMMARIZED_MEMORIES: usize = 5;
+
+/// Step of a decay run reported to the progress callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecayPhase {
+    Expiring,
+    Compressing,
+}
+
+/// How far a decay run has got through its current phase
+#[derive(Debug, Clone)]
+pub struct DecayProgress {
+    pub phase: DecayPhase,
+    /// Memories read so far
+    pub scanned: usize,
+    /// Memories expired or compressed so far
+    pub processed: usize,
+}
+
+type ProgressCallback = Arc<dyn Fn(&DecayProgress) + Send + Sync>;
 
 /// Memory decay engine for automated cleanup and compression
 pub struct DecayEngine {
@@ -15,40 +47,115 @@ pub struct DecayEngine {
     validator: RequestValidator,
     monitor: PerformanceMonitor,
     policy: DecayPolicy,
+    /// Stores summaries of inactive sessions
+    sessions: SessionManager,
+    on_progress: Option<ProgressCallback>,
 }
 
 impl DecayEngine {
     pub fn new(database: Database, validator: RequestValidator, policy: DecayPolicy) -> Self {
         Self {
+            sessions: SessionManager::new(database.clone(), validator.clone()),
             database,
             validator,
             monitor: PerformanceMonitor::new(100), // Smaller sample size for decay operations
             policy,
+            on_progress: None,
         }
     }
 
+    /// Call `on_progress` after each batch a decay run expires or compresses
+    ///
+    /// Expiry and compression run on several threads, so it may be called
+    /// from any of them, and counts may arrive slightly out of order.
+    pub fn on_progress(&mut self, on_progress: impl Fn(&DecayProgress) + Send + Sync + 'static) {
+        self.on_progress = Some(Arc::new(on_progress));
+    }
+
+    /// Current decay policy
+    pub fn policy(&self) -> &DecayPolicy {
+        &self.policy
+    }
+
     /// Update decay policy
     pub fn update_policy(&mut self, policy: DecayPolicy) -> Result<()> {
         // Validate policy
         policy.validate().context("Invalid decay policy")?;
 
         self.policy = policy;
-        log::info!(
-            "Updated decay policy: max_age={}h, threshold={}, compression={}",
-            self.policy.max_age_hours,
-            self.policy.importance_threshold,
-            self.policy.compression_enabled
+        tracing::info!(
+            max_age_hours = self.policy.max_age_hours,
+            importance_threshold = self.policy.importance_threshold,
+            compression = self.policy.compression_enabled,
+            "Updated decay policy"
         );
 
         Ok(())
     }
 
+    /// Per-category retention rules, by category
+    pub fn retention_rules(&self) -> Result<Vec<RetentionRule>> {
+        self.database.get_retention_rules()
+    }
+
+    /// Add or replace the retention rule for a category
+    pub fn set_retention_rule(&self, rule: RetentionRule) -> Result<()> {
+        self.database.set_retention_rule(&rule)?;
+        tracing::info!(
+            category = rule.category.as_str(),
+            max_age_hours = ?rule.max_age_hours,
+            "Set retention rule"
+        );
+        Ok(())
+    }
+
+    /// Remove a category's retention rule; returns false if it had none
+    pub fn remove_retention_rule(&self, category: &str) -> Result<bool> {
+        let removed = self.database.delete_retention_rule(category)?;
+        if removed {
+            tracing::info!(category = category, "Removed retention rule");
+        }
+        Ok(removed)
+    }
+
+    /// Restore the memories a compressed memory was made from
+    ///
+    /// The compressed memory is marked superseded rather than deleted.
+    /// Restored memories are as old and unimportant as before, so a later
+    /// decay run compresses them again unless their importance is raised or
+    /// a retention rule covers them. Returns the restored IDs, oldest first.
+    pub fn decompress(&self, memory_id: &str) -> Result<Vec<String>> {
+        let memory = self
+            .database
+            .get_memory(memory_id)?
+            .ok_or_else(|| anyhow::anyhow!("Memory not found: {}", memory_id))?;
+        if !memory.is_compressed {
+            return Err(anyhow::anyhow!("Memory {} is not compressed", memory_id));
+        }
+
+        let restored = self.database.restore_archived_memories(memory_id)?;
+        if restored.is_empty() {
+            return Err(anyhow::anyhow!(
+                "No archived originals for memory {}; it was compressed before archiving was added",
+                memory_id
+            ));
+        }
+
+        tracing::info!(
+            memory_id = memory_id,
+            count = restored.len(),
+            "Decompressed memory"
+        );
+        Ok(restored)
+    }
+
     /// Run full decay process
     pub fn run_decay(&self) -> Result<DecayStats> {
         let start_time = Utc::now();
         let run_id = Uuid::new_v4().to_string();
+        let _span = logging::span("run_decay").with("run_id", &run_id);
 
-        log::info!("Starting decay process (run_id: {})", run_id);
+        tracing::info!("Starting decay process");
 
         // Rate limiting (decay is expensive)
         self.validator.validate_request(10)?;
@@ -61,32 +168,87 @@ impl DecayEngine {
             memories_expired: 0,
             memories_compressed: 0,
             sessions_summarized: 0,
+            sessions_archived: 0,
             total_memories_before: 0,
             total_memories_after: 0,
             storage_saved_bytes: 0,
+            physical_bytes_saved: 0,
             status: DecayStatus::Running,
             error_message: None,
         };
 
+        // Only one process sharing the file runs decay
+        match self.database.acquire_maintenance_lock() {
+            Ok(true) => {}
+            Ok(false) => {
+                let holder = self.database.maintenance_lock_holder().ok().flatten();
+                let message = match holder {
+                    Some(holder) => format!(
+                        "Maintenance lock held by pid {} on {}",
+                        holder.pid, holder.hostname
+                    ),
+                    None => "Maintenance lock held by another instance".to_string(),
+                };
+                tracing::info!(reason = message.as_str(), "Skipping decay");
+                stats.status = DecayStatus::Skipped;
+                stats.error_message = Some(message);
+                stats.completed_at = Some(Utc::now());
+                return Ok(stats);
+            }
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to acquire maintenance lock");
+                stats.status = DecayStatus::Failed;
+                stats.error_message = Some(format!("Acquiring maintenance lock failed: {}", e));
+                return Ok(stats);
+            }
+        }
+
         // Get initial memory count
         match self.get_total_memory_count() {
             Ok(count) => stats.total_memories_before = count,
             Err(e) => {
-                log::error!("Failed to get initial memory count: {}", e);
+                tracing::error!(error = %e, "Failed to get initial memory count");
                 stats.status = DecayStatus::Failed;
                 stats.error_message = Some(e.to_string());
                 return Ok(stats);
             }
         }
 
+        let rules = match self.retention_rules() {
+            Ok(rules) => rules,
+            Err(e) => {
+                tracing::error!(error = %e, "Failed to load retention rules");
+                stats.status = DecayStatus::Failed;
+                stats.error_message = Some(format!("Loading retention rules failed: {}", e));
+                return Ok(stats);
+            }
+        };
+        let ruled_categories: HashSet<&str> =
+            rules.iter().map(|rule| rule.category.as_str()).collect();
+
+        // Baselines for what the run frees
+        let used_before = match self.database.used_bytes() {
+            Ok(bytes) => Some(bytes),
+            Err(e) => {
+                tracing::warn!(error = %e, "Failed to measure database size");
+                None
+            }
+        };
+        let index_ratio = self.database.text_index_ratio().unwrap_or_else(|e| {
+            tracing::warn!(error = %e, "Failed to measure full-text index size");
+            0.0
+        });
+        let mut freed = Footprint::default();
+
         // Step 1: Remove expired memories
-        match self.expire_old_memories() {
+        match self.expire_old_memories(&rules) {
             Ok(expired) => {
-                stats.memories_expired = expired;
-                log::info!("Expired {} memories", expired);
+                stats.memories_expired = expired.memories;
+                freed += expired;
+                tracing::info!(count = expired.memories, "Expired memories");
             }
             Err(e) => {
-                log::error!("Failed to expire memories: {}", e);
+                tracing::error!(error = %e, "Failed to expire memories");
                 stats.status = DecayStatus::Failed;
                 stats.error_message = Some(format!("Expiry failed: {}", e));
                 return Ok(stats);
@@ -95,13 +257,14 @@ impl DecayEngine {
 
         // Step 2: Compress old memories if enabled
         if self.policy.compression_enabled {
-            match self.compress_old_memories() {
+            match self.compress_old_memories(&ruled_categories) {
                 Ok(compressed) => {
-                    stats.memories_compressed = compressed;
-                    log::info!("Compressed {} memories", compressed);
+                    stats.memories_compressed = compressed.memories;
+                    freed += compressed;
+                    tracing::info!(count = compressed.memories, "Compressed memories");
                 }
                 Err(e) => {
-                    log::error!("Failed to compress memories: {}", e);
+                    tracing::error!(error = %e, "Failed to compress memories");
                     // Don't fail the entire process for compression errors
                     stats.error_message = Some(format!("Compression failed: {}", e));
                 }
@@ -113,23 +276,39 @@ impl DecayEngine {
             match self.summarize_old_sessions() {
                 Ok(summarized) => {
                     stats.sessions_summarized = summarized;
-                    log::info!("Summarized {} sessions", summarized);
+                    tracing::info!(count = summarized, "Summarized sessions");
                 }
                 Err(e) => {
-                    log::error!("Failed to summarize sessions: {}", e);
+                    tracing::error!(error = %e, "Failed to summarize sessions");
                     // Don't fail the entire process for summarization errors
                 }
             }
         }
 
-        // Step 4: Enforce per-user memory limits
+        // Step 4: Archive sessions nobody has touched in a while
+        if let Some(days) = self.policy.archive_sessions_after_days {
+            let cutoff = Utc::now() - chrono::Duration::days(days as i64);
+            match self.database.archive_inactive_sessions(cutoff) {
+                Ok(archived) => {
+                    stats.sessions_archived = archived;
+                    tracing::info!(count = archived, "Archived inactive sessions");
+                }
+                Err(e) => {
+                    tracing::error!(error = %e, "Failed to archive sessions");
+                    stats.error_message = Some(format!("Session archiving failed: {}", e));
+                }
+            }
+        }
+
+        // Step 5: Enforce per-user memory limits
         match self.enforce_memory_limits() {
             Ok(limited) => {
-                stats.memories_expired += limited;
-                log::info!("Enforced limits, removed {} additional memories", limited);
+                stats.memories_expired += limited.memories;
+                freed += limited;
+                tracing::info!(count = limited.memories, "Enforced memory limits");
             }
             Err(e) => {
-                log::error!("Failed to enforce memory limits: {}", e);
+                tracing::error!(error = %e, "Failed to enforce memory limits");
                 stats.error_message = Some(format!("Limit enforcement failed: {}", e));
             }
         }
@@ -137,12 +316,18 @@ impl DecayEngine {
         // Get final memory count
         match self.get_total_memory_count() {
             Ok(count) => stats.total_memories_after = count,
-            Err(e) => log::error!("Failed to get final memory count: {}", e),
+            Err(e) => tracing::error!(error = %e, "Failed to get final memory count"),
         }
 
-        // Calculate storage saved (rough estimate)
-        let memories_removed = stats.memories_expired + stats.memories_compressed;
-        stats.storage_saved_bytes = memories_removed * 1024; // Rough estimate: 1KB per memory
+        // Piggyback the periodic statistics snapshot on the decay schedule
+        if let Err(e) = self.database.snapshot_stats_if_due() {
+            tracing::warn!(error = %e, "Failed to snapshot database statistics");
+        }
+
+        // What the removed memories stored, and what their full-text index
+        // entries took at the index's current size per content byte
+        let index_bytes = (freed.content_bytes as f64 * index_ratio) as u64;
+        stats.storage_saved_bytes = (freed.total_bytes() + index_bytes) as usize;
 
         // Complete decay run
         stats.completed_at = Some(Utc::now());
@@ -150,135 +335,320 @@ impl DecayEngine {
             stats.status = DecayStatus::Completed;
         }
 
-        let duration = Utc::now() - start_time;
-        log::info!(
-            "Decay process completed in {}ms (run_id: {})",
-            duration.num_milliseconds(),
-            run_id
+        // Pages in use count the WAL's frames, so this holds once they are
+        // checkpointed too
+        if let Some(used_before) = used_before {
+            match self.database.used_bytes() {
+                Ok(used_after) => {
+                    stats.physical_bytes_saved = used_before as i64 - used_after as i64;
+                }
+                Err(e) => tracing::warn!(error = %e, "Failed to measure database size"),
+            }
+        }
+        tracing::info!(
+            content = freed.content_bytes,
+            metadata = freed.metadata_bytes,
+            embeddings = freed.embedding_bytes,
+            index_estimate = index_bytes,
+            physical = stats.physical_bytes_saved,
+            "Storage saved"
         );
-        log::info!(
-            "Results: expired={}, compressed={}, sessions={}, before={}, after={}",
-            stats.memories_expired,
-            stats.memories_compressed,
-            stats.sessions_summarized,
-            stats.total_memories_before,
-            stats.total_memories_after
+
+        if let Err(e) = self.database.record_decay_run(&stats) {
+            tracing::warn!(error = %e, "Failed to record decay run");
+        }
+
+        // Last, so the run's own writes are folded in: truncate the WAL
+        // once it has grown past the configured size
+        if let Err(e) = self.database.checkpoint_if_due() {
+            tracing::warn!(error = %e, "Failed to checkpoint the WAL");
+        }
+
+        let duration = Utc::now() - start_time;
+        tracing::info!(
+            duration_ms = duration.num_milliseconds(),
+            expired = stats.memories_expired,
+            compressed = stats.memories_compressed,
+            sessions = stats.sessions_summarized,
+            archived = stats.sessions_archived,
+            before = stats.total_memories_before,
+            after = stats.total_memories_after,
+            "Decay process completed"
         );
 
         Ok(stats)
     }
 
-    /// Remove memories that have exceeded their TTL or are too old
-    fn expire_old_memories(&self) -> Result<usize> {
+    /// Remove memories that have exceeded their TTL, their category's
+    /// retention or, in categories without a rule, the policy's max age
+    fn expire_old_memories(&self, rules: &[RetentionRule]) -> Result<Footprint> {
         let now = Utc::now();
         let cutoff_time = now - chrono::Duration::hours(self.policy.max_age_hours as i64);
 
         // First, cleanup explicitly expired memories (TTL-based)
-        let expired_count = self
+        let mut expired_count = self
             .database
-            .cleanup_expired()
+            .cleanup_expired_footprint()
             .context("Failed to cleanup expired memories")?;
 
-        // Then, find old low-importance memories to expire
-        let filter = QueryFilter {
-            date_to: Some(cutoff_time),
-            limit: Some(1000), // Process in batches
-            ..Default::default()
-        };
-
-        let old_memories_response = self.database.recall_memories(&filter)?;
-        let mut additional_expired = 0;
-
-        for memory in old_memories_response.data {
-            // Only expire if importance is below threshold
-            if memory.importance < self.policy.importance_threshold {
-                match self.database.delete_memory(&memory.id) {
-                    Ok(true) => {
-                        additional_expired += 1;
-                        log::debug!(
-                            "Expired old memory {} (age: {}h, importance: {})",
-                            memory.id,
-                            (now - memory.created_at).num_hours(),
-                            memory.importance
-                        );
-                    }
-                    Ok(false) => {
-                        log::warn!("Memory {} not found for expiry", memory.id);
-                    }
-                    Err(e) => {
-                        log::error!("Failed to delete memory {}: {}", memory.id, e);
-                    }
-                }
+        // Categories with a rule follow it regardless of importance
+        for rule in rules {
+            if let Some(max_age_hours) = rule.max_age_hours {
+                let cutoff = now - chrono::Duration::hours(max_age_hours as i64);
+                let expired = self
+                    .database
+                    .expire_category(&rule.category, cutoff)
+                    .with_context(|| format!("Failed to apply retention for {}", rule.category))?;
+                tracing::debug!(
+                    category = rule.category.as_str(),
+                    count = expired.memories,
+                    "Applied retention rule"
+                );
+                expired_count += expired;
             }
         }
+        let ruled_categories: HashSet<&str> =
+            rules.iter().map(|rule| rule.category.as_str()).collect();
+
+        // Then, stream old low-importance memories to workers deleting them
+        // a batch per transaction
+        let expired = Mutex::new(Footprint::default());
+        let mut scanned = 0;
+        self.in_parallel(
+            |jobs| {
+                let mut scan = |filter: QueryFilter| {
+                    self.database
+                        .scan_memories(&filter, DECAY_BATCH_SIZE, |batch| {
+                            scanned += batch.len();
+                            let ids: Vec<String> = batch
+                                .into_iter()
+                                .filter(|memory| {
+                                    memory.importance < self.policy.importance_threshold
+                                        && now - memory.created_at >= self.max_age(memory)
+                                        && !has_retention_rule(memory, &ruled_categories)
+                                        && !memory.is_session_summary()
+                                })
+                                .map(|memory| memory.id)
+                                .collect();
+                            if !ids.is_empty() {
+                                jobs.send(ids)
+                                    .map_err(|_| anyhow::anyhow!("Decay workers stopped"))?;
+                            }
+                            self.report(
+                                DecayPhase::Expiring,
+                                scanned,
+                                expired.lock().unwrap().memories,
+                            );
+                            Ok(())
+                        })
+                };
+                scan(QueryFilter {
+                    date_to: Some(cutoff_time),
+                    ..Default::default()
+                })?;
+
+                // Less confident memories count as old sooner, at any age;
+                // those already expired above are gone by now or are
+                // skipped by the delete
+                if self.policy.confidence_threshold > 0.0 {
+                    scan(QueryFilter {
+                        max_confidence: Some(self.policy.confidence_threshold),
+                        ..Default::default()
+                    })?;
+                }
+                Ok(())
+            },
+            |ids: Vec<String>| match self.database.delete_memories_footprint(&ids) {
+                Ok(deleted) => {
+                    *expired.lock().unwrap() += deleted;
+                    tracing::debug!(count = deleted.memories, "Expired old memories");
+                }
+                Err(e) => {
+                    tracing::error!(count = ids.len(), error = %e, "Failed to delete memories");
+                }
+            },
+        )?;
+
+        let expired = expired.into_inner().unwrap();
+        self.report(DecayPhase::Expiring, scanned, expired.memories);
+        expired_count += expired;
+        Ok(expired_count)
+    }
 
-        Ok(expired_count + additional_expired)
+    /// Age past which `memory` counts as old: the policy's max age, scaled
+    /// down by confidence below `confidence_threshold`
+    fn max_age(&self, memory: &MemoryItem) -> chrono::Duration {
+        let hours = self.policy.max_age_hours as f32;
+        let threshold = self.policy.confidence_threshold;
+        let hours = if memory.confidence < threshold {
+            hours * memory.confidence / threshold
+        } else {
+            hours
+        };
+        chrono::Duration::minutes((hours * 60.0) as i64)
     }
 
-    /// Compress groups of old, low-importance memories
-    fn compress_old_memories(&self) -> Result<usize> {
+    /// Compress groups of old, low-importance memories, a session at a time
+    fn compress_old_memories(&self, ruled_categories: &HashSet<&str>) -> Result<Footprint> {
         let cutoff_date =
             Utc::now() - chrono::Duration::hours(self.policy.max_age_hours as i64 / 2);
-        let mut compressed_count = 0;
+        let is_candidate = |memory: &MemoryItem| {
+            memory.importance < self.policy.importance_threshold
+                && !has_retention_rule(memory, ruled_categories)
+                && !memory.is_session_summary()
+        };
 
-        // Get old memories with low importance, grouped by user and session
+        // Find the sessions worth compressing without holding their memories
         let filter = QueryFilter {
             date_to: Some(cutoff_date),
-            limit: Some(1000),
             ..Default::default()
         };
+        let mut sessions: HashMap<(String, String), usize> = HashMap::new();
+        let mut scanned = 0;
+        self.database
+            .scan_memories(&filter, DECAY_BATCH_SIZE, |batch| {
+                scanned += batch.len();
+                for memory in batch.into_iter().filter(|memory| is_candidate(memory)) {
+                    *sessions
+                        .entry((memory.user_id, memory.session_id))
+                        .or_default() += 1;
+                }
+                self.report(DecayPhase::Compressing, scanned, 0);
+                Ok(())
+            })?;
+
+        // Compress each session with 3+ candidates on a worker, up to a
+        // batch of memories per compressed memory
+        let compressed = Mutex::new(Footprint::default());
+        self.in_parallel(
+            |jobs| {
+                for (session, count) in sessions {
+                    if count >= 3 {
+                        jobs.send(session)
+                            .map_err(|_| anyhow::anyhow!("Decay workers stopped"))?;
+                    }
+                }
+                Ok(())
+            },
+            |(user_id, session_id): (String, String)| {
+                let filter = QueryFilter {
+                    user_id: Some(user_id),
+                    session_id: Some(session_id.clone()),
+                    date_to: Some(cutoff_date),
+                    ..Default::default()
+                };
+                let mut candidates = Vec::new();
+                let read = self.database.scan_memories(&filter, DECAY_BATCH_SIZE, |batch| {
+                    candidates.extend(batch.into_iter().filter(|memory| is_candidate(memory)));
+                    Ok(())
+                });
+                if let Err(e) = read {
+                    tracing::error!(session_id = session_id.as_str(), error = %e, "Failed to read session memories");
+                    return;
+                }
 
-        let old_memories_response = self.database.recall_memories(&filter)?;
-        let old_memories: Vec<_> = old_memories_response
-            .data
-            .into_iter()
-            .filter(|m| m.importance < self.policy.importance_threshold)
-            .collect();
-
-        // Group by (user_id, session_id)
-        let mut memory_groups: HashMap<(String, String), Vec<MemoryItem>> = HashMap::new();
-        for memory in old_memories {
-            let key = (memory.user_id.clone(), memory.session_id.clone());
-            memory_groups
-                .entry(key)
-                .or_insert_with(Vec::new)
-                .push(memory);
-        }
-
-        // Compress groups with 3+ memories
-        for ((_user_id, session_id), memories) in memory_groups {
-            if memories.len() >= 3 {
-                match self.create_compressed_memory(memories) {
-                    Ok(compressed_memory) => {
-                        // Save compressed memory
-                        let compressed_id = self.database.save_memory(&compressed_memory)?;
-
-                        // Delete original memories
-                        for memory in &compressed_memory.compressed_from {
-                            self.database.delete_memory(memory).ok(); // Continue even if some deletions fail
+                for group in candidates.chunks(DECAY_BATCH_SIZE) {
+                    if group.len() < 3 {
+                        continue;
+                    }
+                    match self.compress_group(group) {
+                        Ok((compressed_memory, freed)) => {
+                            let count = compressed_memory.compressed_from.len();
+                            let total = {
+                                let mut compressed = compressed.lock().unwrap();
+                                *compressed += freed;
+                                compressed.memories
+                            };
+                            self.report(DecayPhase::Compressing, scanned, total);
+                            tracing::debug!(
+                                session_id = session_id.as_str(),
+                                memory_id = compressed_memory.id.as_str(),
+                                count = count,
+                                "Compressed session memories"
+                            );
                         }
+                        Err(e) => {
+                            tracing::error!(
+                                session_id = session_id.as_str(),
+                                error = %e,
+                                "Failed to compress session memories"
+                            );
+                        }
+                    }
+                }
+            },
+        )?;
 
-                        compressed_count += compressed_memory.compressed_from.len();
+        let compressed = compressed.into_inner().unwrap();
+        self.report(DecayPhase::Compressing, scanned, compressed.memories);
+        Ok(compressed)
+    }
 
-                        log::debug!(
-                            "Compressed {} memories from session {} into {}",
-                            compressed_memory.compressed_from.len(),
-                            session_id,
-                            compressed_id
-                        );
-                    }
-                    Err(e) => {
-                        log::error!(
-                            "Failed to compress memories for session {}: {}",
-                            session_id,
-                            e
-                        );
+    /// Save one compressed memory in place of `group`, archiving the
+    /// originals it replaces; returns it with what replacing them freed
+    fn compress_group(&self, group: &[MemoryItem]) -> Result<(MemoryItem, Footprint)> {
+        let compressed_memory = self.create_compressed_memory(group.to_vec())?;
+        let ids: Vec<String> = group.iter().map(|memory| memory.id.clone()).collect();
+        let originals = self.database.memory_footprint(&ids)?;
+        self.database
+            .save_compressed_memory(&compressed_memory, group)?;
+
+        let added = Footprint {
+            memories: 1,
+            content_bytes: compressed_memory.content.len() as u64,
+            metadata_bytes: serde_json::to_string(&compressed_memory.metadata)?.len() as u64,
+            embedding_bytes: 0,
+        };
+        Ok((compressed_memory, originals.less(&added)))
+    }
+
+    /// Run `work` on every job `produce` sends, on as many worker threads
+    /// as the write pool can keep busy
+    ///
+    /// The queue holds a couple of jobs per worker, so `produce` waits for
+    /// the workers rather than reading ahead of them. Jobs already queued
+    /// are still worked off if `produce` fails.
+    fn in_parallel<J: Send>(
+        &self,
+        produce: impl FnOnce(&SyncSender<J>) -> Result<()>,
+        work: impl Fn(J) + Sync,
+    ) -> Result<()> {
+        let workers = self.worker_count();
+        let (jobs, queue) = mpsc::sync_channel::<J>(workers * 2);
+        let queue = Mutex::new(queue);
+        thread::scope(|scope| {
+            for _ in 0..workers {
+                scope.spawn(|| loop {
+                    let job = queue.lock().unwrap().recv();
+                    match job {
+                        Ok(job) => work(job),
+                        Err(_) => break,
                     }
-                }
+                });
             }
-        }
+            let produced = produce(&jobs);
+            drop(jobs);
+            produced
+        })
+    }
+
+    /// Worker threads for decay: one per core, but no more than there are
+    /// write connections to hand them
+    fn worker_count(&self) -> usize {
+        let cores = thread::available_parallelism().map_or(1, |n| n.get());
+        let connections = self.database.get_pool_status().write_pool.max_connections as usize;
+        cores.min(connections).max(1)
+    }
 
-        Ok(compressed_count)
+    /// Tell the progress callback, if any, how far `phase` has got
+    fn report(&self, phase: DecayPhase, scanned: usize, processed: usize) {
+        if let Some(on_progress) = &self.on_progress {
+            on_progress(&DecayProgress {
+                phase,
+                scanned,
+                processed,
+            });
+        }
     }
 
     /// Create a compressed memory from multiple memories
@@ -307,6 +677,18 @@ impl DecayEngine {
         let total_importance: f32 = sorted_memories.iter().map(|m| m.importance).sum();
         let combined_importance = total_importance / sorted_memories.len() as f32;
 
+        // The summary is as sure as its originals on average; weighting by
+        // importance keeps a trivial shaky memory from dragging it down
+        let combined_confidence = if total_importance > 0.0 {
+            sorted_memories
+                .iter()
+                .map(|m| m.confidence * m.importance)
+                .sum::<f32>()
+                / total_importance
+        } else {
+            sorted_memories.iter().map(|m| m.confidence).sum::<f32>() / sorted_memories.len() as f32
+        };
+
         // Create compressed memory
         let compressed_memory = MemoryItem {
             id: Uuid::new_v4().to_string(),
@@ -315,7 +697,7 @@ impl DecayEngine {
             content: summary,
             metadata: {
                 let mut metadata = HashMap::new();
-                metadata.insert("type".to_string(), "compressed".to_string());
+                metadata.insert(TYPE_METADATA_KEY.to_string(), "compressed".to_string());
                 metadata.insert(
                     "original_count".to_string(),
                     sorted_memories.len().to_string(),
@@ -338,6 +720,7 @@ impl DecayEngine {
             updated_at: Utc::now(),
             expires_at: None, // Compressed memories don't expire automatically
             importance: combined_importance,
+            confidence: combined_confidence.clamp(0.0, 1.0),
             ttl_hours: None,
             is_compressed: true,
             compressed_from: original_ids,
@@ -377,13 +760,7 @@ impl DecayEngine {
         let sample_content: String = sample_memories
             .iter()
             .take(3)
-            .map(|m| {
-                if m.content.len() > 50 {
-                    format!("{}...", &m.content[..50])
-                } else {
-                    m.content.clone()
-                }
-            })
+            .map(|m| truncate_graphemes(&m.content, 50).into_owned())
             .collect::<Vec<_>>()
             .join(" | ");
 
@@ -421,7 +798,7 @@ impl DecayEngine {
 
         // Get top 5 most frequent words
         let mut sorted_words: Vec<(String, usize)> = word_counts.into_iter().collect();
-        sorted_words.sort_by(|a, b| b.1.cmp(&a.1));
+        sorted_words.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
         sorted_words
             .into_iter()
             .take(5)
@@ -485,60 +862,76 @@ impl DecayEngine {
         )
     }
 
-    /// Auto-summarize sessions that haven't been active recently
+    /// Store a summary memory in each session inactive for a week whose
+    /// memories changed since its last summary, or that has none
     fn summarize_old_sessions(&self) -> Result<usize> {
-        let cutoff_date = Utc::now() - chrono::Duration::days(7); // Sessions inactive for 7+ days
-        let mut summarized_count = 0;
-
-        // Get all users (we'll need to implement a way to get all users)
-        // For now, we'll use a different approach - find sessions from old memories
-        let filter = QueryFilter {
-            date_to: Some(cutoff_date),
-            limit: Some(1000),
-            ..Default::default()
-        };
-
-        let old_memories_response = self.database.recall_memories(&filter)?;
-
-        // Group by session to find sessions with enough memories
-        let mut session_memory_counts: HashMap<String, usize> = HashMap::new();
-        for memory in old_memories_response.data {
-            *session_memory_counts.entry(memory.session_id).or_insert(0) += 1;
-        }
+        let cutoff_date = Utc::now() - chrono::Duration::days(7);
+        let sessions = self
+            .database
+            .sessions_to_summarize(cutoff_date, MIN_SUMMARIZED_MEMORIES)?;
 
-        // Summarize sessions with 5+ memories
-        for (session_id, memory_count) in session_memory_counts {
-            if memory_count >= 5 {
-                // TODO: Implement session summarization
-                // This would require a SessionManager instance or similar functionality
-                log::debug!(
-                    "Would summarize session {} with {} memories",
-                    session_id,
-                    memory_count
-                );
-                summarized_count += 1;
+        let mut summarized_count = 0;
+        for session_id in sessions {
+            match self.sessions.store_session_summary(&session_id) {
+                Ok(summary) => {
+                    summarized_count += 1;
+                    tracing::debug!(
+                        session_id = session_id.as_str(),
+                        memory_count = summary.memory_count,
+                        "Summarized session"
+                    );
+                }
+                Err(e) => {
+                    tracing::error!(
+                        session_id = session_id.as_str(),
+                        error = %e,
+                        "Failed to summarize session"
+                    );
+                }
             }
         }
 
         Ok(summarized_count)
     }
 
-    /// Enforce per-user memory limits
-    fn enforce_memory_limits(&self) -> Result<usize> {
-        let removed_count = 0;
-
-        // Get memory counts per user (we'll need to implement this efficiently)
-        // For now, we'll use a simple approach with pagination
-
-        // This is a simplified implementation - in production, you'd want to:
-        // 1. Get all users efficiently
-        // 2. Check their memory counts
-        // 3. Remove least important memories for users over limit
-
-        // TODO: Implement efficient user enumeration and quota enforcement
-        log::debug!("Memory limit enforcement not fully implemented");
+    /// Drop the least important memories of users holding more than the
+    /// policy's `max_memories_per_user`, down to the limit
+    fn enforce_memory_limits(&self) -> Result<Footprint> {
+        let mut removed = Footprint::default();
+        for user in self.users_over_limit()? {
+            let excess = user.memory_count as usize - self.policy.max_memories_per_user;
+            let ids = self.database.excess_memories(&user.user_id, excess)?;
+            for batch in ids.chunks(DECAY_BATCH_SIZE) {
+                removed += self.database.delete_memories_footprint(batch)?;
+            }
+            tracing::debug!(
+                user_id = user.user_id.as_str(),
+                count = ids.len(),
+                "Trimmed memories over the per-user limit"
+            );
+        }
+        Ok(removed)
+    }
 
-        Ok(removed_count)
+    /// Users holding more memories than the policy allows, a page of users
+    /// at a time from the maintained per-user counters
+    fn users_over_limit(&self) -> Result<Vec<UserOverview>> {
+        let mut over = Vec::new();
+        let mut offset = 0;
+        loop {
+            let page = self
+                .database
+                .list_users(Some(DECAY_BATCH_SIZE), Some(offset))?;
+            over.extend(
+                page.data
+                    .into_iter()
+                    .filter(|user| user.memory_count as usize > self.policy.max_memories_per_user),
+            );
+            if !page.has_next {
+                return Ok(over);
+            }
+            offset += DECAY_BATCH_SIZE;
+        }
     }
 
     /// Get total memory count (active memories only)
@@ -619,9 +1012,12 @@ impl DecayEngine {
                 .push("Consider running decay process - over 50% of memories are old".to_string());
         }
 
-        if total_memories > self.policy.max_memories_per_user {
-            recommendations
-                .push("Memory count exceeds configured limits - cleanup recommended".to_string());
+        let users_over_limit = self.users_over_limit()?.len();
+        if users_over_limit > 0 {
+            recommendations.push(format!(
+                "{} users exceed the per-user memory limit - cleanup recommended",
+                users_over_limit
+            ));
         }
 
         if *age_distribution.get("0-24h").unwrap_or(&0) > (total_memories / 2) {
@@ -661,6 +1057,14 @@ pub struct DecayRecommendations {
     pub estimated_cleanup_count: usize,
 }
 
+/// Whether a retention rule, rather than the global policy, governs `memory`
+fn has_retention_rule(memory: &MemoryItem, ruled_categories: &HashSet<&str>) -> bool {
+    memory
+        .metadata
+        .get(CATEGORY_METADATA_KEY)
+        .is_some_and(|category| ruled_categories.contains(category.as_str()))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -698,6 +1102,8 @@ mod tests {
             max_memories_per_user: 5000,
             compression_enabled: false,
             auto_summarize_sessions: false,
+            archive_sessions_after_days: None,
+            confidence_threshold: 0.25,
         };
 
         engine.update_policy(new_policy.clone()).unwrap();
@@ -786,6 +1192,7 @@ mod tests {
                 session_id: "session1".to_string(),
                 content: "First memory".to_string(),
                 importance: 0.2,
+                confidence: 0.5,
                 created_at: Utc::now() - chrono::Duration::hours(2),
                 ..Default::default()
             },
@@ -807,9 +1214,96 @@ mod tests {
         assert!(compressed.compressed_from.contains(&"mem1".to_string()));
         assert!(compressed.compressed_from.contains(&"mem2".to_string()));
         assert_eq!(compressed.importance, 0.25); // Average of 0.2 and 0.3
+        assert!((compressed.confidence - 0.8).abs() < 1e-6); // 0.5 and 1.0 by importance
         assert!(compressed.content.contains("[COMPRESSED]"));
     }
 
+    #[test]
+    fn test_low_confidence_memories_expire_sooner() {
+        let (engine, _temp_dir) = setup_test_engine();
+        let session_id = engine.database.create_session("user1", None).unwrap();
+        // The default policy expires unimportant memories after 30 days, and
+        // those below 0.5 confidence proportionally sooner
+        let save = |age_days: i64, confidence: f32| {
+            engine
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: session_id.clone(),
+                    content: format!("{} days old, {} confident", age_days, confidence),
+                    importance: 0.1,
+                    confidence,
+                    created_at: Utc::now() - chrono::Duration::days(age_days),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let sure = save(20, 1.0);
+        let unsure = save(20, 0.25);
+        let recent_unsure = save(10, 0.25);
+        let guess = save(1, 0.0);
+
+        assert_eq!(engine.expire_old_memories(&[]).unwrap().memories, 2);
+        assert!(engine.database.get_memory(&sure).unwrap().is_some());
+        assert!(engine.database.get_memory(&unsure).unwrap().is_none());
+        assert!(engine
+            .database
+            .get_memory(&recent_unsure)
+            .unwrap()
+            .is_some());
+        assert!(engine.database.get_memory(&guess).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_memory_limits_trim_least_important() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+        engine.policy.max_memories_per_user = 2;
+        let save = |user_id: &str, importance: f32, summary: bool| {
+            let mut metadata = HashMap::new();
+            if summary {
+                metadata.insert(
+                    TYPE_METADATA_KEY.to_string(),
+                    SESSION_SUMMARY_TYPE.to_string(),
+                );
+            }
+            engine
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: format!("{}-session", user_id),
+                    content: format!("{} memory at {}", user_id, importance),
+                    metadata,
+                    importance,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let summary = save("alice", 0.0, true);
+        let low = save("alice", 0.1, false);
+        let mid = save("alice", 0.5, false);
+        let high = save("alice", 0.9, false);
+        save("bob", 0.1, false);
+        save("bob", 0.2, false);
+
+        let recommendations = engine.get_decay_recommendations().unwrap();
+        assert!(recommendations
+            .recommendations
+            .iter()
+            .any(|r| r.starts_with("1 users exceed")));
+
+        assert_eq!(engine.enforce_memory_limits().unwrap().memories, 2);
+        let exists = |id: &str| engine.database.get_memory(id).unwrap().is_some();
+        assert!(exists(&summary) && exists(&high));
+        assert!(!exists(&low) && !exists(&mid));
+        assert_eq!(
+            engine.database.list_users(None, None).unwrap().data[1].memory_count,
+            2
+        );
+        assert!(engine.users_over_limit().unwrap().is_empty());
+    }
+
     #[test]
     fn test_age_distribution_analysis() {
         let (engine, _temp_dir) = setup_test_engine();
@@ -834,6 +1328,145 @@ mod tests {
         assert!(recommendations.old_memory_percentage <= 100.0);
     }
 
+    #[test]
+    fn test_retention_rules_override_policy() {
+        let (engine, _temp_dir) = setup_test_engine();
+        let session_id = engine.database.create_session("user1", None).unwrap();
+        let save = |category: Option<&str>, age_days: i64, importance: f32| {
+            let mut metadata = HashMap::new();
+            if let Some(category) = category {
+                metadata.insert(CATEGORY_METADATA_KEY.to_string(), category.to_string());
+            }
+            engine
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: session_id.clone(),
+                    content: format!("{:?} memory, {} days old", category, age_days),
+                    metadata,
+                    importance,
+                    created_at: Utc::now() - chrono::Duration::days(age_days),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let old_trade = save(Some("trading"), 10, 0.9);
+        let recent_trade = save(Some("trading"), 1, 0.9);
+        // Old and unimportant enough for the policy to expire or compress
+        let personal: Vec<_> = (0..3).map(|_| save(Some("personal"), 60, 0.1)).collect();
+        let untagged = save(None, 60, 0.1);
+
+        engine
+            .set_retention_rule(RetentionRule {
+                category: "trading".to_string(),
+                max_age_hours: Some(24 * 7),
+            })
+            .unwrap();
+        engine
+            .set_retention_rule(RetentionRule {
+                category: "personal".to_string(),
+                max_age_hours: None,
+            })
+            .unwrap();
+        assert!(engine
+            .set_retention_rule(RetentionRule {
+                category: String::new(),
+                max_age_hours: Some(1),
+            })
+            .is_err());
+
+        let rules = engine.retention_rules().unwrap();
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[0].category, "personal");
+        assert!(rules[0].never_expire());
+
+        let stats = engine.run_decay().unwrap();
+        assert!(matches!(stats.status, DecayStatus::Completed));
+        assert_eq!(stats.memories_expired, 2);
+        assert_eq!(stats.memories_compressed, 0);
+        let analytics = engine.database.get_user_analytics("user1").unwrap();
+        assert_eq!(analytics.decay_removal_rate, Some(2.0 / 6.0));
+
+        let exists = |id: &str| engine.database.get_memory(id).unwrap().is_some();
+        assert!(!exists(&old_trade));
+        assert!(exists(&recent_trade));
+        assert!(personal.iter().all(|id| exists(id)));
+        assert!(!exists(&untagged));
+
+        assert!(engine.remove_retention_rule("personal").unwrap());
+        assert!(!engine.remove_retention_rule("personal").unwrap());
+        assert_eq!(engine.retention_rules().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_decompress_restores_originals() {
+        let (engine, _temp_dir) = setup_test_engine();
+        let originals: Vec<_> = (0..3)
+            .map(|i| {
+                engine
+                    .database
+                    .save_memory(&MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "session1".to_string(),
+                        content: format!("Old note {}", i),
+                        importance: 0.1,
+                        created_at: Utc::now() - chrono::Duration::days(20 - i),
+                        ..Default::default()
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        let stats = engine.run_decay().unwrap();
+        assert_eq!(stats.memories_compressed, 3);
+        // Recall leaves compressed memories out, so look it up directly
+        let compressed_id: String = engine
+            .database
+            .get_connection_pool()
+            .with_read_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT id FROM memories WHERE is_compressed = 1",
+                    [],
+                    |row| row.get(0),
+                )?)
+            })
+            .unwrap();
+        let compressed = engine.database.get_memory(&compressed_id).unwrap().unwrap();
+        assert_eq!(compressed.compressed_from, originals);
+        assert!(engine.database.get_memory(&originals[0]).unwrap().is_none());
+        let archived = engine
+            .database
+            .get_archived_memories(&compressed.id)
+            .unwrap();
+        assert_eq!(
+            archived.iter().map(|m| m.id.clone()).collect::<Vec<_>>(),
+            originals
+        );
+
+        let restored = engine.decompress(&compressed.id).unwrap();
+        assert_eq!(restored, originals);
+        let first = engine.database.get_memory(&originals[0]).unwrap().unwrap();
+        assert_eq!(first.content, "Old note 0");
+        assert_eq!(first.created_at, archived[0].created_at);
+        assert!(engine
+            .database
+            .get_memory(&compressed.id)
+            .unwrap()
+            .is_none());
+        assert!(engine
+            .database
+            .get_archived_memories(&compressed.id)
+            .unwrap()
+            .is_empty());
+
+        // Superseded, and the originals aren't compressed
+        let err = engine.decompress(&compressed.id).unwrap_err();
+        assert!(err.to_string().contains("not found"), "{}", err);
+        let err = engine.decompress(&originals[0]).unwrap_err();
+        assert!(err.to_string().contains("not compressed"), "{}", err);
+    }
+
     #[test]
     fn test_run_decay_empty_database() {
         let (engine, _temp_dir) = setup_test_engine();
@@ -846,4 +1479,269 @@ mod tests {
         assert_eq!(stats.memories_expired, 0);
         assert!(matches!(stats.status, DecayStatus::Completed));
     }
+
+    #[test]
+    fn test_run_decay_processes_more_than_a_batch() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let reported = progress.clone();
+        engine.on_progress(move |progress| reported.lock().unwrap().push(progress.clone()));
+
+        // Past max age, and past half of it but not all of it
+        let expiring = engine.database.create_session("user1", None).unwrap();
+        let compressing = engine.database.create_session("user1", None).unwrap();
+        for (session_id, age_days, count) in [(&expiring, 40, 1500), (&compressing, 20, 1200)] {
+            for i in 0..count {
+                engine
+                    .database
+                    .save_memory(&MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: session_id.clone(),
+                        content: format!("Memory {}", i),
+                        importance: 0.1,
+                        created_at: Utc::now() - chrono::Duration::days(age_days),
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+        }
+
+        assert!(engine.database.text_index_ratio().unwrap() > 0.0);
+
+        let stats = engine.run_decay().unwrap();
+        assert!(matches!(stats.status, DecayStatus::Completed));
+        assert_eq!(stats.memories_expired, 1500);
+        assert_eq!(stats.memories_compressed, 1200);
+        assert_eq!(stats.total_memories_before, 2700);
+
+        // At least the expired memories' content and metadata
+        let expired_bytes: usize = (0..1500)
+            .map(|i| format!("Memory {}", i).len() + "{}".len())
+            .sum();
+        assert!(stats.storage_saved_bytes > expired_bytes);
+        let recorded = engine.database.last_decay_run().unwrap().unwrap();
+        assert_eq!(recorded.storage_saved_bytes, stats.storage_saved_bytes);
+        assert_eq!(recorded.physical_bytes_saved, stats.physical_bytes_saved);
+
+        let progress = progress.lock().unwrap();
+        let last = |phase| {
+            progress
+                .iter()
+                .filter(|p| p.phase == phase)
+                .max_by_key(|p| p.processed)
+                .unwrap()
+                .clone()
+        };
+        assert_eq!(last(DecayPhase::Expiring).processed, 1500);
+        assert_eq!(last(DecayPhase::Compressing).processed, 1200);
+        assert!(
+            progress
+                .iter()
+                .filter(|p| p.phase == DecayPhase::Expiring)
+                .count()
+                > 2
+        );
+    }
+
+    #[test]
+    fn test_run_decay_archives_inactive_sessions() {
+        let (mut engine, _temp_dir) = setup_test_engine();
+        let stale = engine.database.create_session("user1", None).unwrap();
+        let fresh = engine.database.create_session("user1", None).unwrap();
+        engine
+            .database
+            .get_connection_pool()
+            .with_write_transaction(|tx| {
+                tx.execute(
+                    "UPDATE sessions SET last_active = datetime('now', '-40 days') WHERE id = ?1",
+                    [&stale],
+                )?;
+                Ok(())
+            })
+            .unwrap();
+
+        // Without a policy nothing is archived
+        assert_eq!(engine.run_decay().unwrap().sessions_archived, 0);
+
+        engine
+            .update_policy(DecayPolicy {
+                archive_sessions_after_days: Some(30),
+                ..DecayPolicy::default()
+            })
+            .unwrap();
+        let stats = engine.run_decay().unwrap();
+        assert!(matches!(stats.status, DecayStatus::Completed));
+        assert_eq!(stats.sessions_archived, 1);
+
+        let listed = engine
+            .database
+            .get_user_sessions("user1", None, None)
+            .unwrap();
+        assert_eq!(listed.data.len(), 1);
+        assert_eq!(listed.data[0].id, fresh);
+        assert_eq!(engine.run_decay().unwrap().sessions_archived, 0);
+    }
+
+    #[test]
+    fn test_run_decay_stores_session_summaries() {
+        let (engine, _temp_dir) = setup_test_engine();
+        let session = |memories: usize, inactive: bool| {
+            let session_id = engine.database.create_session("user1", None).unwrap();
+            let ids: Vec<String> = (0..memories)
+                .map(|i| {
+                    engine
+                        .database
+                        .save_memory(&MemoryItem {
+                            user_id: "user1".to_string(),
+                            session_id: session_id.clone(),
+                            content: format!("Discussed the quarterly budget, item {}", i),
+                            importance: 0.9,
+                            ..Default::default()
+                        })
+                        .unwrap()
+                })
+                .collect();
+            if inactive {
+                engine
+                    .database
+                    .get_connection_pool()
+                    .with_write_transaction(|tx| {
+                        tx.execute(
+                            "UPDATE sessions SET last_active = datetime('now', '-10 days') WHERE id = ?1",
+                            [&session_id],
+                        )?;
+                        Ok(())
+                    })
+                    .unwrap();
+            }
+            (session_id, ids)
+        };
+        let last_active = |session_id: &str| -> String {
+            engine
+                .database
+                .get_connection_pool()
+                .with_write_transaction(|tx| {
+                    Ok(tx.query_row(
+                        "SELECT last_active FROM sessions WHERE id = ?1",
+                        [session_id],
+                        |row| row.get(0),
+                    )?)
+                })
+                .unwrap()
+        };
+
+        let (stale, ids) = session(6, true);
+        session(6, false);
+        session(MIN_SUMMARIZED_MEMORIES - 1, true);
+        let stale_since = last_active(&stale);
+
+        assert_eq!(engine.run_decay().unwrap().sessions_summarized, 1);
+        let summary = engine
+            .database
+            .get_session_summary(&stale)
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.memory_count, 6);
+        assert_eq!(summary.version, 1);
+        let memory = engine
+            .database
+            .get_memory(summary.memory_id.as_ref().unwrap())
+            .unwrap()
+            .unwrap();
+        assert_eq!(memory.session_id, stale);
+        assert_eq!(memory.content, summary.summary_text);
+        assert!(memory.is_session_summary());
+        // Storing the summary doesn't count as activity
+        assert_eq!(last_active(&stale), stale_since);
+
+        // Unchanged sessions aren't summarized again
+        assert_eq!(engine.run_decay().unwrap().sessions_summarized, 0);
+
+        // A changed one is, replacing its summary
+        engine.database.delete_memory(&ids[0]).unwrap();
+        assert_eq!(engine.run_decay().unwrap().sessions_summarized, 1);
+        let summary = engine
+            .database
+            .get_session_summary(&stale)
+            .unwrap()
+            .unwrap();
+        assert_eq!(summary.memory_count, 5);
+        assert_eq!(summary.version, 2);
+        assert_ne!(summary.memory_id.as_deref(), Some(memory.id.as_str()));
+        assert!(engine.database.get_memory(&memory.id).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_run_decay_truncates_large_wal() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            wal_truncate_threshold_bytes: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        database
+            .save_memory(&MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "session1".to_string(),
+                content: "Grows the WAL".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(database.wal_size_bytes() > 0);
+
+        let engine = DecayEngine::new(
+            database.clone(),
+            RequestValidator::new(&MemexConfig::default()),
+            DecayPolicy::default(),
+        );
+        engine.run_decay().unwrap();
+        assert_eq!(database.wal_size_bytes(), 0);
+
+        // Below the threshold nothing runs
+        assert!(database.checkpoint_if_due().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_second_instance_skips_decay() {
+        let (engine, temp_dir) = setup_test_engine();
+        let open_second = || {
+            Database::new(DatabaseConfig {
+                path: temp_dir
+                    .path()
+                    .join("test.db")
+                    .to_string_lossy()
+                    .to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        let second = DecayEngine::new(
+            open_second(),
+            RequestValidator::new(&MemexConfig::default()),
+            DecayPolicy::default(),
+        );
+
+        assert!(matches!(
+            engine.run_decay().unwrap().status,
+            DecayStatus::Completed
+        ));
+        let stats = second.run_decay().unwrap();
+        assert!(matches!(stats.status, DecayStatus::Skipped));
+        assert!(stats
+            .error_message
+            .unwrap()
+            .contains(&std::process::id().to_string()));
+
+        // Dropping the holder releases the lock
+        drop(engine);
+        assert!(matches!(
+            second.run_decay().unwrap().status,
+            DecayStatus::Completed
+        ));
+    }
 }
@@ -0,0 +1,478 @@
+//! A user's sessions and memories as a graph for Graphviz or Gephi
+//!
+//! Sessions link to the memories saved in them, and compressed memories
+//! link to the memories they were built from. Originals that decay has
+//! already removed stay in the graph as `removed` nodes so the lineage
+//! still shows.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fmt::Write as _;
+use std::str::FromStr;
+
+use crate::database::models::{MemoryItem, Session};
+use crate::display::truncate_graphemes;
+
+/// Longest node label, in characters
+pub const GRAPH_LABEL_LENGTH: usize = 40;
+
+/// File formats a graph can be written as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphFormat {
+    /// Graphviz DOT
+    #[default]
+    Dot,
+    /// GraphML, which Gephi, yEd and networkx read
+    GraphMl,
+}
+
+impl GraphFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+        }
+    }
+}
+
+impl fmt::Display for GraphFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for GraphFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<Self> {
+        match s.to_lowercase().as_str() {
+            "dot" | "gv" => Ok(GraphFormat::Dot),
+            "graphml" => Ok(GraphFormat::GraphMl),
+            _ => Err(anyhow::anyhow!(
+                "Unknown graph format '{}' (expected dot or graphml)",
+                s
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    Session,
+    Memory,
+    /// A memory made by compressing others
+    Compressed,
+    /// A compression original that no longer exists
+    Removed,
+}
+
+impl NodeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            NodeKind::Session => "session",
+            NodeKind::Memory => "memory",
+            NodeKind::Compressed => "compressed",
+            NodeKind::Removed => "removed",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EdgeKind {
+    /// Session to a memory saved in it
+    Contains,
+    /// Compressed memory to one of its originals
+    CompressedFrom,
+}
+
+impl EdgeKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EdgeKind::Contains => "contains",
+            EdgeKind::CompressedFrom => "compressed_from",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GraphNode {
+    /// `session:<id>` or `memory:<id>`
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+    pub importance: Option<f32>,
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GraphEdge {
+    pub source: String,
+    pub target: String,
+    pub kind: EdgeKind,
+}
+
+/// Nodes and edges of one user's memory graph
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryGraph {
+    pub user_id: String,
+    /// Sessions first, then memories, each in the order given
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+fn session_node_id(id: &str) -> String {
+    format!("session:{}", id)
+}
+
+fn memory_node_id(id: &str) -> String {
+    format!("memory:{}", id)
+}
+
+impl MemoryGraph {
+    /// Graph of `memories`; `sessions` supply session names, and sessions
+    /// without memories are left out
+    pub fn build(user_id: &str, sessions: &[Session], memories: &[MemoryItem]) -> Self {
+        let names: BTreeMap<&str, &str> = sessions
+            .iter()
+            .filter_map(|session| Some((session.id.as_str(), session.name.as_deref()?)))
+            .collect();
+
+        let mut session_ids: Vec<&str> = Vec::new();
+        for memory in memories {
+            if !session_ids.contains(&memory.session_id.as_str()) {
+                session_ids.push(&memory.session_id);
+            }
+        }
+        let mut nodes: Vec<GraphNode> = session_ids
+            .iter()
+            .map(|id| GraphNode {
+                id: session_node_id(id),
+                kind: NodeKind::Session,
+                label: names.get(id).copied().unwrap_or(id).to_string(),
+                importance: None,
+                created_at: None,
+            })
+            .collect();
+
+        let present: HashSet<&str> = memories.iter().map(|m| m.id.as_str()).collect();
+        let mut removed: Vec<&str> = Vec::new();
+        let mut edges = Vec::new();
+        for memory in memories {
+            let id = memory_node_id(&memory.id);
+            nodes.push(GraphNode {
+                id: id.clone(),
+                kind: if memory.is_compressed {
+                    NodeKind::Compressed
+                } else {
+                    NodeKind::Memory
+                },
+                label: truncate_graphemes(&memory.content, GRAPH_LABEL_LENGTH).into_owned(),
+                importance: Some(memory.importance),
+                created_at: Some(memory.created_at),
+            });
+            edges.push(GraphEdge {
+                source: session_node_id(&memory.session_id),
+                target: id.clone(),
+                kind: EdgeKind::Contains,
+            });
+
+            for original in &memory.compressed_from {
+                if !present.contains(original.as_str()) && !removed.contains(&original.as_str()) {
+                    removed.push(original);
+                }
+                edges.push(GraphEdge {
+                    source: id.clone(),
+                    target: memory_node_id(original),
+                    kind: EdgeKind::CompressedFrom,
+                });
+            }
+        }
+        nodes.extend(removed.into_iter().map(|id| GraphNode {
+            id: memory_node_id(id),
+            kind: NodeKind::Removed,
+            label: id.to_string(),
+            importance: None,
+            created_at: None,
+        }));
+
+        Self {
+            user_id: user_id.to_string(),
+            nodes,
+            edges,
+        }
+    }
+
+    pub fn render(&self, format: GraphFormat) -> String {
+        match format {
+            GraphFormat::Dot => self.to_dot(),
+            GraphFormat::GraphMl => self.to_graphml(),
+        }
+    }
+
+    fn to_dot(&self) -> String {
+        let mut dot = String::new();
+        let _ = writeln!(
+            dot,
+            "digraph {} {{",
+            dot_quote(&format!("memex:{}", self.user_id))
+        );
+        dot.push_str("  rankdir=LR;\n");
+        dot.push_str("  node [shape=box, style=rounded];\n");
+
+        for node in &self.nodes {
+            let style = match node.kind {
+                NodeKind::Session => ", shape=folder",
+                NodeKind::Memory => "",
+                NodeKind::Compressed => ", style=\"rounded,bold\"",
+                NodeKind::Removed => ", style=\"rounded,dashed\", fontcolor=gray",
+            };
+            let _ = write!(
+                dot,
+                "  {} [label={}, kind={}{}",
+                dot_quote(&node.id),
+                dot_quote(&node.label),
+                dot_quote(node.kind.as_str()),
+                style
+            );
+            if let Some(importance) = node.importance {
+                let _ = write!(dot, ", importance=\"{:.2}\"", importance);
+            }
+            if let Some(created_at) = node.created_at {
+                let _ = write!(dot, ", created_at=\"{}\"", created_at.to_rfc3339());
+            }
+            dot.push_str("];\n");
+        }
+
+        for edge in &self.edges {
+            let style = match edge.kind {
+                EdgeKind::Contains => "",
+                EdgeKind::CompressedFrom => ", style=dashed",
+            };
+            let _ = writeln!(
+                dot,
+                "  {} -> {} [kind={}{}];",
+                dot_quote(&edge.source),
+                dot_quote(&edge.target),
+                dot_quote(edge.kind.as_str()),
+                style
+            );
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    fn to_graphml(&self) -> String {
+        let mut xml = String::new();
+        xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        xml.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\">\n");
+        xml.push_str("  <key id=\"kind\" for=\"node\" attr.name=\"kind\" attr.type=\"string\"/>\n");
+        xml.push_str(
+            "  <key id=\"label\" for=\"node\" attr.name=\"label\" attr.type=\"string\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"importance\" for=\"node\" attr.name=\"importance\" attr.type=\"double\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"created_at\" for=\"node\" attr.name=\"created_at\" attr.type=\"string\"/>\n",
+        );
+        xml.push_str(
+            "  <key id=\"edge_kind\" for=\"edge\" attr.name=\"kind\" attr.type=\"string\"/>\n",
+        );
+        let _ = writeln!(
+            xml,
+            "  <graph id=\"{}\" edgedefault=\"directed\">",
+            xml_escape(&format!("memex:{}", self.user_id))
+        );
+
+        for node in &self.nodes {
+            let _ = writeln!(xml, "    <node id=\"{}\">", xml_escape(&node.id));
+            let _ = writeln!(
+                xml,
+                "      <data key=\"kind\">{}</data>",
+                node.kind.as_str()
+            );
+            let _ = writeln!(
+                xml,
+                "      <data key=\"label\">{}</data>",
+                xml_escape(&node.label)
+            );
+            if let Some(importance) = node.importance {
+                let _ = writeln!(xml, "      <data key=\"importance\">{}</data>", importance);
+            }
+            if let Some(created_at) = node.created_at {
+                let _ = writeln!(
+                    xml,
+                    "      <data key=\"created_at\">{}</data>",
+                    created_at.to_rfc3339()
+                );
+            }
+            xml.push_str("    </node>\n");
+        }
+
+        for edge in &self.edges {
+            let _ = writeln!(
+                xml,
+                "    <edge source=\"{}\" target=\"{}\">",
+                xml_escape(&edge.source),
+                xml_escape(&edge.target)
+            );
+            let _ = writeln!(
+                xml,
+                "      <data key=\"edge_kind\">{}</data>",
+                edge.kind.as_str()
+            );
+            xml.push_str("    </edge>\n");
+        }
+
+        xml.push_str("  </graph>\n</graphml>\n");
+        xml
+    }
+}
+
+/// A DOT double-quoted string
+fn dot_quote(s: &str) -> String {
+    let mut quoted = String::with_capacity(s.len() + 2);
+    quoted.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => {}
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Escape text for XML content and attribute values, dropping characters
+/// XML 1.0 can't hold
+fn xml_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if (c as u32) < 0x20 => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(id: &str, session: &str, content: &str) -> MemoryItem {
+        MemoryItem {
+            id: id.to_string(),
+            user_id: "alice".to_string(),
+            session_id: session.to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn sample_graph() -> MemoryGraph {
+        let sessions = vec![Session {
+            id: "s1".to_string(),
+            user_id: "alice".to_string(),
+            name: Some("Trading \"desk\"".to_string()),
+            created_at: Utc::now(),
+            last_active: Utc::now(),
+            memory_count: 2,
+            tags: Vec::new(),
+            status: Default::default(),
+            metadata: Default::default(),
+        }];
+        let mut summary = memory("c", "s2", "Summary of <old> notes & more");
+        summary.is_compressed = true;
+        summary.compressed_from = vec!["a".to_string(), "gone".to_string()];
+        let memories = vec![
+            memory("a", "s1", "Bought AAPL"),
+            memory("b", "s1", "Sold TSLA"),
+            summary,
+        ];
+        MemoryGraph::build("alice", &sessions, &memories)
+    }
+
+    #[test]
+    fn test_build_graph() {
+        let graph = sample_graph();
+        let kinds: Vec<(&str, NodeKind)> = graph
+            .nodes
+            .iter()
+            .map(|node| (node.id.as_str(), node.kind))
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ("session:s1", NodeKind::Session),
+                ("session:s2", NodeKind::Session),
+                ("memory:a", NodeKind::Memory),
+                ("memory:b", NodeKind::Memory),
+                ("memory:c", NodeKind::Compressed),
+                ("memory:gone", NodeKind::Removed),
+            ]
+        );
+        assert_eq!(graph.nodes[0].label, "Trading \"desk\"");
+        assert_eq!(graph.nodes[1].label, "s2");
+
+        let lineage: Vec<&str> = graph
+            .edges
+            .iter()
+            .filter(|edge| edge.kind == EdgeKind::CompressedFrom)
+            .map(|edge| edge.target.as_str())
+            .collect();
+        assert_eq!(lineage, vec!["memory:a", "memory:gone"]);
+        assert_eq!(
+            graph
+                .edges
+                .iter()
+                .filter(|edge| edge.kind == EdgeKind::Contains)
+                .count(),
+            3
+        );
+    }
+
+    #[test]
+    fn test_render_formats() {
+        let graph = sample_graph();
+
+        let dot = graph.render(GraphFormat::Dot);
+        assert!(dot.starts_with("digraph \"memex:alice\" {"), "{}", dot);
+        assert!(dot.contains("label=\"Trading \\\"desk\\\"\""), "{}", dot);
+        assert!(dot.contains("\"session:s1\" -> \"memory:a\" [kind=\"contains\"];"));
+        assert!(dot
+            .contains("\"memory:c\" -> \"memory:gone\" [kind=\"compressed_from\", style=dashed];"));
+
+        let xml = graph.render(GraphFormat::GraphMl);
+        assert!(xml.contains("<graph id=\"memex:alice\" edgedefault=\"directed\">"));
+        assert!(
+            xml.contains("Summary of &lt;old&gt; notes &amp; more"),
+            "{}",
+            xml
+        );
+        assert!(xml.contains("<edge source=\"memory:c\" target=\"memory:gone\">"));
+        assert_eq!(xml.matches("<node ").count(), 6);
+        assert_eq!(xml.matches("<edge ").count(), 5);
+
+        assert_eq!(
+            "GraphML".parse::<GraphFormat>().unwrap(),
+            GraphFormat::GraphMl
+        );
+        assert!("svg".parse::<GraphFormat>().is_err());
+    }
+}
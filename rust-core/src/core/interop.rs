@@ -0,0 +1,621 @@
+//! Importers and exporters for other memory stores and RAG frameworks
+//!
+//! Each importer maps one export format onto [`MemoryItem`]s, keeping the
+//! record's embedding when the export has one. Imported memories have source
+//! type `import` and a `source_ref` of `<format>:<record id>`, and keep the
+//! same in their `import_source` and `import_id` metadata.
+//! `MemoryManager::import_memories` saves the result.
+//!
+//! [`to_documents`] goes the other way, turning memories into LangChain or
+//! LlamaIndex document JSON.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::database::models::{MemoryItem, SourceType};
+
+/// Session imported memories go to when neither the record nor the caller
+/// names one
+pub const DEFAULT_IMPORT_SESSION: &str = "imported";
+
+/// Export formats memex can import
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ImportFormat {
+    /// JSON of a Chroma `collection.get()` result: parallel `ids`,
+    /// `documents`, `metadatas` and `embeddings` arrays
+    Chroma,
+    /// JSON array of LangChain `Document`s, plain (`page_content` and
+    /// `metadata`) or serialized with `dumpd`
+    LangChain,
+    /// JSON of Mem0's `get_all()`, either the `results` object or the bare
+    /// list of memories
+    Mem0,
+}
+
+impl ImportFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ImportFormat::Chroma => "chroma",
+            ImportFormat::LangChain => "langchain",
+            ImportFormat::Mem0 => "mem0",
+        }
+    }
+}
+
+impl fmt::Display for ImportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for ImportFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "chroma" => Ok(ImportFormat::Chroma),
+            "langchain" => Ok(ImportFormat::LangChain),
+            "mem0" => Ok(ImportFormat::Mem0),
+            _ => Err(anyhow::anyhow!(
+                "Unknown import format '{}' (expected chroma, langchain or mem0)",
+                s
+            )),
+        }
+    }
+}
+
+/// Owner and session for records that don't carry their own
+#[derive(Debug, Clone)]
+pub struct ImportDefaults {
+    pub user_id: Option<String>,
+    pub session_id: String,
+}
+
+impl Default for ImportDefaults {
+    fn default() -> Self {
+        Self {
+            user_id: None,
+            session_id: DEFAULT_IMPORT_SESSION.to_string(),
+        }
+    }
+}
+
+/// A memory mapped from an export, ready to save
+#[derive(Debug, Clone)]
+pub struct ImportedMemory {
+    pub memory: MemoryItem,
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Result of `MemoryManager::import_memories`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub records: usize,
+    pub imported: usize,
+    pub embeddings_stored: usize,
+    /// Embeddings dropped because vector search is off or storing failed
+    pub embeddings_skipped: usize,
+    /// One message per record that could not be saved
+    pub errors: Vec<String>,
+}
+
+/// Map the export `json` in `format` onto memories
+///
+/// Fails without importing anything if the export doesn't parse or a
+/// record has no user and `defaults` has none either.
+pub fn parse(
+    format: ImportFormat,
+    json: &str,
+    defaults: &ImportDefaults,
+) -> Result<Vec<ImportedMemory>> {
+    let records = match format {
+        ImportFormat::Chroma => chroma_records(json)?,
+        ImportFormat::LangChain => langchain_records(json)?,
+        ImportFormat::Mem0 => mem0_records(json)?,
+    };
+
+    records
+        .into_iter()
+        .enumerate()
+        .map(|(index, record)| record.into_memory(format, defaults, index))
+        .collect()
+}
+
+/// Fields shared by every format before they become a `MemoryItem`
+#[derive(Default)]
+struct Record {
+    id: Option<String>,
+    content: String,
+    metadata: Map<String, Value>,
+    user_id: Option<String>,
+    session_id: Option<String>,
+    created_at: Option<DateTime<Utc>>,
+    embedding: Option<Vec<f32>>,
+}
+
+impl Record {
+    fn into_memory(
+        mut self,
+        format: ImportFormat,
+        defaults: &ImportDefaults,
+        index: usize,
+    ) -> Result<ImportedMemory> {
+        // Stores without users or sessions often keep them in metadata
+        let user_id = self
+            .user_id
+            .or_else(|| take_string(&mut self.metadata, "user_id"))
+            .or_else(|| defaults.user_id.clone())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Record {} has no user_id; pass a default user to import it",
+                    index + 1
+                )
+            })?;
+        let session_id = self
+            .session_id
+            .or_else(|| take_string(&mut self.metadata, "session_id"))
+            .unwrap_or_else(|| defaults.session_id.clone());
+        // As written by `to_documents`
+        let created_at = self.created_at.or_else(|| {
+            let created_at = self.metadata.get("created_at")?.as_str()?;
+            let created_at = DateTime::parse_from_rfc3339(created_at).ok()?;
+            self.metadata.remove("created_at");
+            Some(created_at.with_timezone(&Utc))
+        });
+        let importance = match self.metadata.get("importance").and_then(Value::as_f64) {
+            Some(importance) if (0.0..=1.0).contains(&importance) => {
+                self.metadata.remove("importance");
+                Some(importance as f32)
+            }
+            _ => None,
+        };
+
+        let mut metadata: HashMap<String, String> = self
+            .metadata
+            .into_iter()
+            .filter_map(|(key, value)| metadata_value(value).map(|value| (key, value)))
+            .collect();
+        metadata.insert("import_source".to_string(), format.to_string());
+        let source_ref = self.id.as_ref().map(|id| format!("{}:{}", format, id));
+        if let Some(id) = self.id {
+            metadata.insert("import_id".to_string(), id);
+        }
+
+        let mut memory = MemoryItem {
+            user_id,
+            session_id,
+            content: self.content,
+            metadata,
+            source_type: Some(SourceType::Import),
+            source_ref,
+            ..Default::default()
+        };
+        if let Some(created_at) = created_at {
+            memory.created_at = created_at;
+        }
+        if let Some(importance) = importance {
+            memory.importance = importance;
+        }
+
+        Ok(ImportedMemory {
+            memory,
+            embedding: self.embedding,
+        })
+    }
+}
+
+fn take_string(metadata: &mut Map<String, Value>, key: &str) -> Option<String> {
+    match metadata.get(key) {
+        Some(Value::String(value)) if !value.is_empty() => {
+            let value = value.clone();
+            metadata.remove(key);
+            Some(value)
+        }
+        _ => None,
+    }
+}
+
+/// Metadata values are strings; other JSON values keep their JSON text
+fn metadata_value(value: Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(value) => Some(value),
+        other => Some(other.to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ChromaExport {
+    ids: Vec<String>,
+    #[serde(default)]
+    documents: Option<Vec<Option<String>>>,
+    #[serde(default)]
+    metadatas: Option<Vec<Option<Map<String, Value>>>>,
+    #[serde(default)]
+    embeddings: Option<Vec<Option<Vec<f32>>>>,
+}
+
+fn chroma_records(json: &str) -> Result<Vec<Record>> {
+    let export: ChromaExport = serde_json::from_str(json).context("Invalid Chroma export")?;
+    let count = export.ids.len();
+    let documents = export
+        .documents
+        .context("Chroma export has no documents; include them in collection.get()")?;
+    let mut metadatas = export.metadatas.unwrap_or_else(|| vec![None; count]);
+    let mut embeddings = export.embeddings.unwrap_or_else(|| vec![None; count]);
+    if documents.len() != count || metadatas.len() != count || embeddings.len() != count {
+        return Err(anyhow::anyhow!(
+            "Chroma export arrays differ in length from its {} ids",
+            count
+        ));
+    }
+
+    let mut records = Vec::with_capacity(count);
+    for (index, (id, document)) in export.ids.into_iter().zip(documents).enumerate() {
+        // Collections can hold embeddings without text; there is nothing to recall
+        let Some(content) = document else {
+            tracing::warn!(
+                id = id.as_str(),
+                "Skipping Chroma record without a document"
+            );
+            continue;
+        };
+        records.push(Record {
+            id: Some(id),
+            content,
+            metadata: metadatas[index].take().unwrap_or_default(),
+            embedding: embeddings[index].take(),
+            ..Default::default()
+        });
+    }
+    Ok(records)
+}
+
+#[derive(Deserialize)]
+struct LangChainDocument {
+    page_content: String,
+    #[serde(default)]
+    metadata: Map<String, Value>,
+    #[serde(default)]
+    id: Option<String>,
+    #[serde(default)]
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum LangChainRecord {
+    /// `langchain_core.load.dumpd` output
+    Serialized {
+        kwargs: LangChainDocument,
+    },
+    Plain(LangChainDocument),
+}
+
+fn langchain_records(json: &str) -> Result<Vec<Record>> {
+    let documents: Vec<LangChainRecord> =
+        serde_json::from_str(json).context("Invalid LangChain export")?;
+
+    Ok(documents
+        .into_iter()
+        .map(|document| {
+            let document = match document {
+                LangChainRecord::Serialized { kwargs } => kwargs,
+                LangChainRecord::Plain(document) => document,
+            };
+            Record {
+                id: document.id,
+                content: document.page_content,
+                metadata: document.metadata,
+                embedding: document.embedding,
+                ..Default::default()
+            }
+        })
+        .collect())
+}
+
+#[derive(Deserialize)]
+struct Mem0Memory {
+    #[serde(default)]
+    id: Option<String>,
+    memory: String,
+    #[serde(default)]
+    metadata: Option<Map<String, Value>>,
+    #[serde(default)]
+    user_id: Option<String>,
+    #[serde(default)]
+    agent_id: Option<String>,
+    #[serde(default)]
+    run_id: Option<String>,
+    #[serde(default)]
+    categories: Option<Vec<String>>,
+    #[serde(default)]
+    created_at: Option<String>,
+    #[serde(default)]
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Mem0Export {
+    Results { results: Vec<Mem0Memory> },
+    List(Vec<Mem0Memory>),
+}
+
+fn mem0_records(json: &str) -> Result<Vec<Record>> {
+    let export: Mem0Export = serde_json::from_str(json).context("Invalid Mem0 export")?;
+    let memories = match export {
+        Mem0Export::Results { results } => results,
+        Mem0Export::List(memories) => memories,
+    };
+
+    Ok(memories
+        .into_iter()
+        .map(|memory| {
+            let mut metadata = memory.metadata.unwrap_or_default();
+            if let Some(agent_id) = memory.agent_id {
+                metadata.insert("agent_id".to_string(), Value::String(agent_id));
+            }
+            if let Some(categories) = memory.categories.filter(|c| !c.is_empty()) {
+                // Analytics group memories by their `category`
+                if !metadata.contains_key("category") {
+                    metadata.insert("category".to_string(), Value::String(categories[0].clone()));
+                }
+                metadata.insert("categories".to_string(), Value::from(categories));
+            }
+            let created_at = memory.created_at.and_then(|created_at| {
+                DateTime::parse_from_rfc3339(&created_at)
+                    .map(|time| time.with_timezone(&Utc))
+                    .ok()
+            });
+
+            Record {
+                id: memory.id,
+                content: memory.memory,
+                metadata,
+                user_id: memory.user_id,
+                session_id: memory.run_id,
+                created_at,
+                embedding: memory.embedding,
+            }
+        })
+        .collect())
+}
+
+/// Document JSON layouts memories can be exported as
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DocumentFormat {
+    /// LangChain `Document`: `id`, `page_content` and `metadata`, plus
+    /// `embedding` when the memory has one
+    LangChain,
+    /// LlamaIndex `Document`: `id_`, `text`, `metadata` and `embedding`
+    LlamaIndex,
+}
+
+impl DocumentFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DocumentFormat::LangChain => "langchain",
+            DocumentFormat::LlamaIndex => "llamaindex",
+        }
+    }
+}
+
+impl fmt::Display for DocumentFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for DocumentFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "langchain" => Ok(DocumentFormat::LangChain),
+            "llamaindex" | "llama_index" => Ok(DocumentFormat::LlamaIndex),
+            _ => Err(anyhow::anyhow!(
+                "Unknown document format '{}' (expected langchain or llamaindex)",
+                s
+            )),
+        }
+    }
+}
+
+/// One document per memory in `format`
+///
+/// Document metadata holds the memory's own metadata plus its `user_id`,
+/// `session_id`, `created_at` and `importance`, so importing the documents
+/// back keeps their owner and session.
+pub fn to_documents(format: DocumentFormat, memories: &[MemoryItem]) -> Vec<Value> {
+    memories
+        .iter()
+        .map(|memory| {
+            let mut metadata: Map<String, Value> = memory
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                .collect();
+            metadata.insert("user_id".to_string(), json!(memory.user_id));
+            metadata.insert("session_id".to_string(), json!(memory.session_id));
+            metadata.insert(
+                "created_at".to_string(),
+                json!(memory.created_at.to_rfc3339()),
+            );
+            metadata.insert("importance".to_string(), json!(memory.importance));
+
+            match format {
+                DocumentFormat::LangChain => {
+                    let mut document = json!({
+                        "id": memory.id,
+                        "type": "Document",
+                        "page_content": memory.content,
+                        "metadata": metadata,
+                    });
+                    if let Some(embedding) = embedding_of(memory) {
+                        document["embedding"] = json!(embedding);
+                    }
+                    document
+                }
+                DocumentFormat::LlamaIndex => json!({
+                    "id_": memory.id,
+                    "class_name": "Document",
+                    "text": memory.content,
+                    "metadata": metadata,
+                    "embedding": embedding_of(memory),
+                }),
+            }
+        })
+        .collect()
+}
+
+#[cfg(feature = "vector-search")]
+fn embedding_of(memory: &MemoryItem) -> Option<&Vec<f32>> {
+    memory.embedding.as_ref()
+}
+
+#[cfg(not(feature = "vector-search"))]
+fn embedding_of(_memory: &MemoryItem) -> Option<&Vec<f32>> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn defaults(user: Option<&str>) -> ImportDefaults {
+        ImportDefaults {
+            user_id: user.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_chroma() {
+        let json = r#"{
+            "ids": ["a", "b", "c"],
+            "documents": ["Likes tea", null, "Works remotely"],
+            "metadatas": [{"user_id": "alice", "topic": "food", "score": 3}, null, null],
+            "embeddings": [[0.1, 0.2], [0.3, 0.4], null],
+            "included": ["documents", "metadatas", "embeddings"]
+        }"#;
+
+        let memories = parse(ImportFormat::Chroma, json, &defaults(Some("bob"))).unwrap();
+        assert_eq!(memories.len(), 2, "records without a document are skipped");
+
+        let first = &memories[0].memory;
+        assert_eq!(first.user_id, "alice");
+        assert_eq!(first.session_id, DEFAULT_IMPORT_SESSION);
+        assert_eq!(first.content, "Likes tea");
+        assert_eq!(first.metadata["topic"], "food");
+        assert_eq!(first.metadata["score"], "3");
+        assert_eq!(first.metadata["import_source"], "chroma");
+        assert_eq!(first.metadata["import_id"], "a");
+        assert_eq!(first.source_type, Some(SourceType::Import));
+        assert_eq!(first.source_ref.as_deref(), Some("chroma:a"));
+        assert!(!first.metadata.contains_key("user_id"));
+        assert_eq!(memories[0].embedding, Some(vec![0.1, 0.2]));
+
+        assert_eq!(memories[1].memory.user_id, "bob");
+        assert_eq!(memories[1].embedding, None);
+
+        let uneven = r#"{"ids": ["a"], "documents": ["x", "y"]}"#;
+        assert!(parse(ImportFormat::Chroma, uneven, &defaults(Some("bob"))).is_err());
+        let no_documents = r#"{"ids": ["a"]}"#;
+        assert!(parse(ImportFormat::Chroma, no_documents, &defaults(Some("bob"))).is_err());
+    }
+
+    #[test]
+    fn test_parse_langchain() {
+        let json = r#"[
+            {"page_content": "Plain document", "metadata": {"source": "notes.md", "session_id": "s1"}},
+            {"lc": 1, "type": "constructor", "id": ["langchain", "schema", "document", "Document"],
+             "kwargs": {"page_content": "Serialized document", "metadata": {}, "id": "doc-2"}}
+        ]"#;
+
+        assert!(parse(ImportFormat::LangChain, json, &defaults(None)).is_err());
+
+        let memories = parse(ImportFormat::LangChain, json, &defaults(Some("alice"))).unwrap();
+        assert_eq!(memories.len(), 2);
+        assert_eq!(memories[0].memory.content, "Plain document");
+        assert_eq!(memories[0].memory.session_id, "s1");
+        assert_eq!(memories[0].memory.metadata["source"], "notes.md");
+        assert_eq!(memories[1].memory.content, "Serialized document");
+        assert_eq!(memories[1].memory.metadata["import_id"], "doc-2");
+    }
+
+    #[test]
+    fn test_parse_mem0() {
+        let json = r#"{"results": [{
+            "id": "m1",
+            "memory": "Prefers window seats",
+            "hash": "abc",
+            "metadata": null,
+            "categories": ["travel", "preferences"],
+            "created_at": "2024-07-20T01:02:03.123456-07:00",
+            "user_id": "alice",
+            "agent_id": "planner",
+            "run_id": "trip"
+        }]}"#;
+
+        let memories = parse(ImportFormat::Mem0, json, &ImportDefaults::default()).unwrap();
+        let memory = &memories[0].memory;
+        assert_eq!(memory.user_id, "alice");
+        assert_eq!(memory.session_id, "trip");
+        assert_eq!(memory.content, "Prefers window seats");
+        assert_eq!(memory.metadata["category"], "travel");
+        assert_eq!(memory.metadata["categories"], r#"["travel","preferences"]"#);
+        assert_eq!(memory.metadata["agent_id"], "planner");
+        assert_eq!(
+            memory.created_at.to_rfc3339(),
+            "2024-07-20T08:02:03.123456+00:00"
+        );
+
+        let list = r#"[{"memory": "Bare list entry"}]"#;
+        let memories = parse(ImportFormat::Mem0, list, &defaults(Some("bob"))).unwrap();
+        assert_eq!(memories[0].memory.user_id, "bob");
+        assert!(!memories[0].memory.metadata.contains_key("import_id"));
+    }
+
+    #[test]
+    fn test_to_documents() {
+        let memory = MemoryItem {
+            id: "mem_1".to_string(),
+            user_id: "alice".to_string(),
+            session_id: "trip".to_string(),
+            content: "Prefers window seats".to_string(),
+            metadata: HashMap::from([("category".to_string(), "travel".to_string())]),
+            ..Default::default()
+        };
+        let memories = [memory];
+
+        let langchain = to_documents(DocumentFormat::LangChain, &memories);
+        assert_eq!(langchain[0]["id"], "mem_1");
+        assert_eq!(langchain[0]["page_content"], "Prefers window seats");
+        assert_eq!(langchain[0]["metadata"]["category"], "travel");
+        assert_eq!(langchain[0]["metadata"]["session_id"], "trip");
+        assert!(langchain[0].get("embedding").is_none());
+
+        let llamaindex = to_documents(DocumentFormat::LlamaIndex, &memories);
+        assert_eq!(llamaindex[0]["id_"], "mem_1");
+        assert_eq!(llamaindex[0]["text"], "Prefers window seats");
+        assert_eq!(llamaindex[0]["metadata"]["user_id"], "alice");
+        assert!(llamaindex[0]["embedding"].is_null());
+
+        // LangChain documents import back into the same user and session
+        let json = serde_json::to_string(&langchain).unwrap();
+        let imported = parse(ImportFormat::LangChain, &json, &ImportDefaults::default()).unwrap();
+        assert_eq!(imported[0].memory.user_id, "alice");
+        assert_eq!(imported[0].memory.session_id, "trip");
+        assert_eq!(imported[0].memory.created_at, memories[0].created_at);
+        assert_eq!(imported[0].memory.metadata["import_id"], "mem_1");
+        assert!(!imported[0].memory.metadata.contains_key("importance"));
+    }
+}
@@ -0,0 +1,1068 @@
+//! Periodic maintenance: decay, WAL checkpoints, ANALYZE, statistics
+//! snapshots, embeddings backfill and backups on cron-like schedules
+//!
+//! [`MaintenanceScheduler::run_due`] runs every job whose schedule has come
+//! round since its last recorded run, holding the maintenance lock so only
+//! one process sharing the file does the work. FFI instances with
+//! `maintenance.enabled` call it from a background thread; the CLI runs jobs
+//! on demand. Each run is recorded in `maintenance_runs`.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread::JoinHandle;
+use validator::Validate;
+
+use crate::core::decay::DecayEngine;
+use crate::database::backup::{self, BackupFile, BackupOptions};
+use crate::database::maintenance_runs::{MaintenanceJob, MaintenanceRun, MaintenanceStatus};
+use crate::database::models::DecayStatus;
+use crate::database::pool::CheckpointMode;
+#[cfg(feature = "vector-search")]
+use crate::database::vector::{CommandEmbedder, VectorSearchEngine};
+use crate::database::wal_archive::WalArchiveConfig;
+use crate::database::Database;
+
+/// How far ahead a cron schedule is searched for its next match
+const MAX_CRON_SEARCH_DAYS: i64 = 366 * 5;
+
+/// When a maintenance job runs
+///
+/// Written as `@every <n><s|m|h|d>`, one of `@hourly`, `@daily`, `@weekly`
+/// and `@monthly`, or a five-field cron expression
+/// (`minute hour day-of-month month day-of-week`, in UTC) whose fields take
+/// `*`, numbers, ranges `a-b`, steps `*/n` or `a-b/n` and comma lists.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Schedule {
+    source: String,
+    kind: ScheduleKind,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ScheduleKind {
+    Every(Duration),
+    Cron(CronSchedule),
+}
+
+impl Schedule {
+    /// Run once per `interval`
+    pub fn every(interval: Duration) -> Self {
+        let source = if interval.num_seconds() % 3600 == 0 {
+            format!("@every {}h", interval.num_hours())
+        } else {
+            format!("@every {}s", interval.num_seconds())
+        };
+        Self {
+            source,
+            kind: ScheduleKind::Every(interval),
+        }
+    }
+
+    /// The first time after `after` the job is due; `None` if a cron
+    /// expression never matches
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match &self.kind {
+            ScheduleKind::Every(interval) => Some(after + *interval),
+            ScheduleKind::Cron(cron) => cron.next_after(after),
+        }
+    }
+}
+
+impl fmt::Display for Schedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl FromStr for Schedule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let source = s.trim();
+        let kind = match source {
+            "@hourly" => ScheduleKind::Cron(CronSchedule::parse("0 * * * *")?),
+            "@daily" | "@midnight" => ScheduleKind::Cron(CronSchedule::parse("0 0 * * *")?),
+            "@weekly" => ScheduleKind::Cron(CronSchedule::parse("0 0 * * 0")?),
+            "@monthly" => ScheduleKind::Cron(CronSchedule::parse("0 0 1 * *")?),
+            _ => match source.strip_prefix("@every") {
+                Some(interval) => ScheduleKind::Every(parse_interval(interval.trim())?),
+                None => ScheduleKind::Cron(CronSchedule::parse(source)?),
+            },
+        };
+        Ok(Self {
+            source: source.to_string(),
+            kind,
+        })
+    }
+}
+
+impl TryFrom<String> for Schedule {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self> {
+        s.parse()
+    }
+}
+
+impl From<Schedule> for String {
+    fn from(schedule: Schedule) -> Self {
+        schedule.source
+    }
+}
+
+/// `30m`, `6h` and the like, at least a second long
+fn parse_interval(s: &str) -> Result<Duration> {
+    let split = s
+        .find(|c: char| !c.is_ascii_digit())
+        .ok_or_else(|| anyhow::anyhow!("Interval '{}' needs a unit (s, m, h or d)", s))?;
+    let (amount, unit) = s.split_at(split);
+    let amount: i64 = amount
+        .parse()
+        .with_context(|| format!("Invalid interval '{}'", s))?;
+    let interval = match unit {
+        "s" => Duration::seconds(amount),
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        _ => {
+            return Err(anyhow::anyhow!(
+                "Unknown interval unit '{}' (expected s, m, h or d)",
+                unit
+            ))
+        }
+    };
+    if interval < Duration::seconds(1) {
+        return Err(anyhow::anyhow!("Interval '{}' is shorter than a second", s));
+    }
+    Ok(interval)
+}
+
+/// A parsed five-field cron expression; each field is a bitmask of the
+/// values it matches
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days_of_month: u64,
+    months: u64,
+    days_of_week: u64,
+    /// Cron matches either day field when both are restricted
+    day_of_month_any: bool,
+    day_of_week_any: bool,
+}
+
+impl CronSchedule {
+    fn parse(expression: &str) -> Result<Self> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        let [minute, hour, day_of_month, month, day_of_week] = fields[..] else {
+            return Err(anyhow::anyhow!(
+                "Cron expression '{}' needs 5 fields, got {}",
+                expression,
+                fields.len()
+            ));
+        };
+
+        // Sunday may be written as 0 or 7
+        let mut days_of_week = parse_cron_field(day_of_week, 0, 7, "day of week")?;
+        if days_of_week & (1 << 7) != 0 {
+            days_of_week = (days_of_week | 1) & !(1 << 7);
+        }
+
+        Ok(Self {
+            minutes: parse_cron_field(minute, 0, 59, "minute")?,
+            hours: parse_cron_field(hour, 0, 23, "hour")?,
+            days_of_month: parse_cron_field(day_of_month, 1, 31, "day of month")?,
+            months: parse_cron_field(month, 1, 12, "month")?,
+            days_of_week,
+            day_of_month_any: day_of_month == "*",
+            day_of_week_any: day_of_week == "*",
+        })
+    }
+
+    fn day_matches(&self, time: DateTime<Utc>) -> bool {
+        let day_of_month = self.days_of_month & (1 << time.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.day_of_month_any, self.day_of_week_any) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        }
+    }
+
+    fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.timestamp() - after.timestamp().rem_euclid(60) + 60;
+        let mut time = Utc.timestamp_opt(start, 0).single()?;
+        let end = after + Duration::days(MAX_CRON_SEARCH_DAYS);
+
+        while time < end {
+            if self.months & (1 << time.month()) == 0 {
+                let (year, month) = match time.month() {
+                    12 => (time.year() + 1, 1),
+                    month => (time.year(), month + 1),
+                };
+                time = midnight(NaiveDate::from_ymd_opt(year, month, 1)?);
+            } else if !self.day_matches(time) {
+                time = midnight(time.date_naive().succ_opt()?);
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+fn midnight(date: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&date.and_time(chrono::NaiveTime::MIN))
+}
+
+/// Bitmask of the values in `min..=max` that cron field `field` matches
+fn parse_cron_field(field: &str, min: u32, max: u32, name: &str) -> Result<u64> {
+    let invalid = || anyhow::anyhow!("Invalid {} field '{}' in cron expression", name, field);
+    let value = |s: &str| -> Result<u32> {
+        let value: u32 = s.parse().map_err(|_| invalid())?;
+        if value < min || value > max {
+            return Err(anyhow::anyhow!(
+                "{} {} is out of range ({}-{})",
+                name,
+                value,
+                min,
+                max
+            ));
+        }
+        Ok(value)
+    };
+
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().map_err(|_| invalid())?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            return Err(invalid());
+        }
+        let (first, last) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((first, last)) => (value(first)?, value(last)?),
+                // `5/15` runs from 5 to the end of the range
+                None if step > 1 => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if first > last {
+            return Err(invalid());
+        }
+        for value in (first..=last).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+/// External command the `embedding_backfill` job embeds memories with
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct EmbeddingBackfillConfig {
+    /// Model name stored with the embeddings
+    pub model: String,
+    /// Shell command reading a JSON array of texts on stdin and writing a
+    /// JSON array of embeddings on stdout
+    pub command: String,
+    #[validate(range(min = 1, max = 10000))]
+    #[serde(default = "default_backfill_batch_size")]
+    pub batch_size: usize,
+}
+
+fn default_backfill_batch_size() -> usize {
+    64
+}
+
+#[cfg(feature = "vector-search")]
+impl EmbeddingBackfillConfig {
+    /// The embedder running the command
+    pub fn embedder(&self) -> CommandEmbedder {
+        CommandEmbedder {
+            model: self.model.clone(),
+            command: self.command.clone(),
+        }
+    }
+}
+
+/// Where the `backup` job writes backups and how many it keeps
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+pub struct ScheduledBackupConfig {
+    /// Directory backups are written to, created if missing
+    pub directory: String,
+    /// zstd-compress each backup
+    #[serde(default = "default_true")]
+    pub compress: bool,
+    /// Encrypt each backup with the passphrase in `MEMEX_BACKUP_PASSPHRASE`
+    #[serde(default)]
+    pub encrypt: bool,
+    /// Keep the newest backup of each of this many days
+    #[validate(range(min = 1, max = 3650))]
+    #[serde(default = "default_keep_daily")]
+    pub keep_daily: usize,
+    /// Also keep the newest backup of each of this many ISO weeks
+    #[validate(range(max = 520))]
+    #[serde(default = "default_keep_weekly")]
+    pub keep_weekly: usize,
+    /// `system health` reports backups overdue once the newest is older
+    #[validate(range(min = 1, max = 8760))]
+    #[serde(default = "default_backup_overdue_hours")]
+    pub overdue_after_hours: u32,
+    /// Also upload each backup under this `s3://bucket/prefix`; needs the
+    /// `s3-backup` feature and AWS_* credentials
+    #[serde(default)]
+    pub upload_to: Option<String>,
+}
+
+impl ScheduledBackupConfig {
+    pub fn new(directory: impl Into<String>) -> Self {
+        Self {
+            directory: directory.into(),
+            compress: true,
+            encrypt: false,
+            keep_daily: default_keep_daily(),
+            keep_weekly: default_keep_weekly(),
+            overdue_after_hours: default_backup_overdue_hours(),
+            upload_to: None,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_keep_daily() -> usize {
+    7
+}
+
+fn default_keep_weekly() -> usize {
+    4
+}
+
+fn default_backup_overdue_hours() -> u32 {
+    48
+}
+
+/// Age of the newest scheduled backup, for health checks
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupStatus {
+    pub directory: String,
+    /// Newest backup; `None` if there are none yet
+    pub latest: Option<BackupFile>,
+    /// Backups currently kept
+    pub count: usize,
+    /// No backup, or the newest is older than `overdue_after_hours`
+    pub overdue: bool,
+}
+
+/// Which maintenance jobs run when
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct MaintenanceConfig {
+    /// Run due jobs on a background thread of each FFI instance
+    pub enabled: bool,
+
+    /// Seconds between checks for due jobs
+    #[validate(range(min = 1, max = 86400))]
+    pub poll_interval_secs: u64,
+
+    /// Schedule of each job; jobs without one only run on demand. Decay
+    /// follows `decay_interval_hours` unless scheduled here, and never runs
+    /// on its own when `auto_decay_enabled` is off.
+    pub schedules: BTreeMap<MaintenanceJob, Schedule>,
+
+    /// Embedding command for the `embedding_backfill` job, which is skipped
+    /// without one
+    #[validate]
+    pub embedding_backfill: Option<EmbeddingBackfillConfig>,
+
+    /// Destination and retention of the `backup` job, which runs daily
+    /// unless scheduled here and is skipped without this
+    #[validate]
+    pub backup: Option<ScheduledBackupConfig>,
+
+    /// WAL archiving for point-in-time recovery; the `wal_archive` job runs
+    /// every 5 minutes unless scheduled here
+    #[validate]
+    pub wal_archive: Option<WalArchiveConfig>,
+}
+
+impl Default for MaintenanceConfig {
+    fn default() -> Self {
+        let schedule = |s: &str| s.parse::<Schedule>().expect("valid default schedule");
+        Self {
+            enabled: false,
+            poll_interval_secs: 60,
+            schedules: BTreeMap::from([
+                (MaintenanceJob::Checkpoint, schedule("@hourly")),
+                (MaintenanceJob::Analyze, schedule("@daily")),
+                (MaintenanceJob::StatsSnapshot, schedule("@daily")),
+            ]),
+            embedding_backfill: None,
+            backup: None,
+            wal_archive: None,
+        }
+    }
+}
+
+/// Result of one job, before it's recorded
+struct Outcome {
+    status: MaintenanceStatus,
+    detail: Option<serde_json::Value>,
+    message: Option<String>,
+}
+
+impl Outcome {
+    fn completed(detail: impl Serialize) -> Result<Self> {
+        Ok(Self {
+            status: MaintenanceStatus::Completed,
+            detail: Some(serde_json::to_value(detail)?),
+            message: None,
+        })
+    }
+
+    fn skipped(message: impl Into<String>) -> Self {
+        Self {
+            status: MaintenanceStatus::Skipped,
+            detail: None,
+            message: Some(message.into()),
+        }
+    }
+}
+
+/// Runs maintenance jobs on their schedules and records every run
+pub struct MaintenanceScheduler {
+    database: Database,
+    decay: Arc<Mutex<DecayEngine>>,
+    schedules: BTreeMap<MaintenanceJob, Schedule>,
+    embedding_backfill: Option<EmbeddingBackfillConfig>,
+    backup: Option<ScheduledBackupConfig>,
+    #[cfg(feature = "vector-search")]
+    vector: Option<VectorSearchEngine>,
+    /// Jobs that have never run are first due one period after this
+    created_at: DateTime<Utc>,
+}
+
+impl MaintenanceScheduler {
+    pub fn new(
+        database: Database,
+        decay: Arc<Mutex<DecayEngine>>,
+        schedules: BTreeMap<MaintenanceJob, Schedule>,
+        embedding_backfill: Option<EmbeddingBackfillConfig>,
+    ) -> Self {
+        Self {
+            database,
+            decay,
+            schedules,
+            embedding_backfill,
+            backup: None,
+            #[cfg(feature = "vector-search")]
+            vector: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// Let the `embedding_backfill` job store embeddings through `engine`
+    #[cfg(feature = "vector-search")]
+    pub fn enable_vector_search(&mut self, engine: VectorSearchEngine) {
+        self.vector = Some(engine);
+    }
+
+    /// Let the `backup` job write backups as `config` says
+    pub fn enable_backups(&mut self, config: ScheduledBackupConfig) {
+        self.backup = Some(config);
+    }
+
+    /// Each scheduled job's schedule, by job
+    pub fn schedules(&self) -> &BTreeMap<MaintenanceJob, Schedule> {
+        &self.schedules
+    }
+
+    /// When `job` is next due on its schedule; `None` if it has none
+    pub fn next_due(&self, job: MaintenanceJob) -> Result<Option<DateTime<Utc>>> {
+        let Some(schedule) = self.schedules.get(&job) else {
+            return Ok(None);
+        };
+        let last = self
+            .database
+            .last_maintenance_run_at(job)?
+            .unwrap_or(self.created_at);
+        Ok(schedule.next_after(last))
+    }
+
+    /// Scheduled jobs due at `now`
+    pub fn due_jobs(&self, now: DateTime<Utc>) -> Result<Vec<MaintenanceJob>> {
+        let mut due = Vec::new();
+        for &job in self.schedules.keys() {
+            if self.next_due(job)?.is_some_and(|next| next <= now) {
+                due.push(job);
+            }
+        }
+        Ok(due)
+    }
+
+    /// The `limit` most recent runs, of `job` or of every job, newest first
+    pub fn history(
+        &self,
+        job: Option<MaintenanceJob>,
+        limit: usize,
+    ) -> Result<Vec<MaintenanceRun>> {
+        self.database.get_maintenance_runs(job, limit)
+    }
+
+    /// How recent the newest scheduled backup is at `now`; `None` when
+    /// backups aren't set up
+    pub fn backup_status(&self, now: DateTime<Utc>) -> Result<Option<BackupStatus>> {
+        let Some(config) = &self.backup else {
+            return Ok(None);
+        };
+        let backups = backup::list_scheduled(Path::new(&config.directory), self.database.path())?;
+        let overdue = backups.first().is_none_or(|latest| {
+            now - latest.created_at > Duration::hours(config.overdue_after_hours as i64)
+        });
+        Ok(Some(BackupStatus {
+            directory: config.directory.clone(),
+            count: backups.len(),
+            latest: backups.into_iter().next(),
+            overdue,
+        }))
+    }
+
+    /// Run and record every job due at `now`
+    ///
+    /// Does nothing while another instance holds the maintenance lock; that
+    /// instance runs the jobs and its records make them no longer due.
+    pub fn run_due(&self, now: DateTime<Utc>) -> Result<Vec<MaintenanceRun>> {
+        if self.database.get_connection_pool().is_read_only() {
+            return Ok(Vec::new());
+        }
+        let due = self.due_jobs(now)?;
+        if due.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !self.database.acquire_maintenance_lock()? {
+            tracing::debug!(
+                jobs = due.len(),
+                "Maintenance lock held elsewhere; not running due jobs"
+            );
+            return Ok(Vec::new());
+        }
+
+        due.into_iter().map(|job| self.execute(job)).collect()
+    }
+
+    /// Run and record `job` now, whatever its schedule
+    pub fn run_job(&self, job: MaintenanceJob) -> Result<MaintenanceRun> {
+        let started_at = Utc::now();
+        let outcome = if self.database.get_connection_pool().is_read_only() {
+            Outcome::skipped("Database is read-only")
+        } else if !self.database.acquire_maintenance_lock()? {
+            let message = match self.database.maintenance_lock_holder()? {
+                Some(holder) => format!(
+                    "Maintenance lock held by pid {} on {}",
+                    holder.pid, holder.hostname
+                ),
+                None => "Maintenance lock held by another instance".to_string(),
+            };
+            Outcome::skipped(message)
+        } else {
+            return self.execute(job);
+        };
+        self.record(job, started_at, outcome)
+    }
+
+    /// Run `job`, which the caller holds the maintenance lock for, and
+    /// record how it went
+    fn execute(&self, job: MaintenanceJob) -> Result<MaintenanceRun> {
+        let started_at = Utc::now();
+        tracing::info!(job = %job, "Running maintenance job");
+
+        let outcome = self.run(job).unwrap_or_else(|e| {
+            tracing::error!(job = %job, error = %e, "Maintenance job failed");
+            Outcome {
+                status: MaintenanceStatus::Failed,
+                detail: None,
+                message: Some(format!("{:#}", e)),
+            }
+        });
+        self.record(job, started_at, outcome)
+    }
+
+    fn run(&self, job: MaintenanceJob) -> Result<Outcome> {
+        match job {
+            MaintenanceJob::Decay => {
+                let stats = self.decay_engine().run_decay()?;
+                let status = match stats.status {
+                    DecayStatus::Failed => MaintenanceStatus::Failed,
+                    DecayStatus::Skipped => MaintenanceStatus::Skipped,
+                    DecayStatus::Running | DecayStatus::Completed => MaintenanceStatus::Completed,
+                };
+                Ok(Outcome {
+                    status,
+                    message: stats.error_message.clone(),
+                    detail: Some(serde_json::to_value(&stats)?),
+                })
+            }
+            MaintenanceJob::Checkpoint => {
+                Outcome::completed(self.database.checkpoint(CheckpointMode::Truncate)?)
+            }
+            MaintenanceJob::Analyze => {
+                self.database.analyze()?;
+                Ok(Outcome {
+                    status: MaintenanceStatus::Completed,
+                    detail: None,
+                    message: None,
+                })
+            }
+            MaintenanceJob::StatsSnapshot => {
+                let snapshot = self.database.snapshot_stats()?;
+                Outcome::completed(serde_json::json!({
+                    "total_memories": snapshot.total_memories,
+                    "total_sessions": snapshot.total_sessions,
+                    "database_size_bytes": snapshot.database_size_bytes,
+                }))
+            }
+            MaintenanceJob::EmbeddingBackfill => self.backfill_embeddings(),
+            MaintenanceJob::Backup => self.back_up(),
+            MaintenanceJob::WalArchive => match self.database.archive_wal()? {
+                Some(run) => Outcome::completed(run),
+                None => Ok(Outcome::skipped("WAL archiving is not enabled")),
+            },
+        }
+    }
+
+    fn back_up(&self) -> Result<Outcome> {
+        let Some(config) = &self.backup else {
+            return Ok(Outcome::skipped("No backup directory configured"));
+        };
+        let source = self.database.path();
+        let directory = Path::new(&config.directory);
+        std::fs::create_dir_all(directory).with_context(|| {
+            format!("Failed to create backup directory {}", directory.display())
+        })?;
+
+        let archive = config.compress || config.encrypt;
+        let created_at = Utc::now();
+        let file = directory.join(backup::scheduled_file_name(source, created_at, archive));
+        let path = file.to_string_lossy();
+        let size_bytes = if archive {
+            let passphrase = if config.encrypt {
+                match std::env::var(backup::PASSPHRASE_ENV) {
+                    Ok(passphrase) if !passphrase.is_empty() => Some(passphrase),
+                    _ => {
+                        return Err(anyhow::anyhow!(
+                            "Encrypted backups need {} to be set",
+                            backup::PASSPHRASE_ENV
+                        ))
+                    }
+                }
+            } else {
+                None
+            };
+            let options = BackupOptions {
+                compress: config.compress,
+                passphrase,
+            };
+            self.database.backup_archive(&path, &options)?.payload_size
+        } else {
+            self.database.backup_to(&path)?;
+            std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0)
+        };
+
+        let pruned = backup::prune(directory, source, config.keep_daily, config.keep_weekly)?;
+        // Uploaded after pruning so local retention keeps working while the
+        // remote store is unreachable
+        let uploaded = match &config.upload_to {
+            Some(to) => Some(backup::upload(&file, source, created_at, archive, to)?.url),
+            None => None,
+        };
+        Outcome::completed(serde_json::json!({
+            "file": path,
+            "size_bytes": size_bytes,
+            "pruned": pruned.iter().map(|b| b.path.to_string_lossy()).collect::<Vec<_>>(),
+            "uploaded": uploaded,
+        }))
+    }
+
+    #[cfg(feature = "vector-search")]
+    fn backfill_embeddings(&self) -> Result<Outcome> {
+        let Some(engine) = &self.vector else {
+            return Ok(Outcome::skipped("Vector search is not enabled"));
+        };
+        let Some(config) = &self.embedding_backfill else {
+            return Ok(Outcome::skipped("No embedding command configured"));
+        };
+
+        Outcome::completed(engine.backfill(&config.embedder(), config.batch_size, |_| {})?)
+    }
+
+    #[cfg(not(feature = "vector-search"))]
+    fn backfill_embeddings(&self) -> Result<Outcome> {
+        Ok(Outcome::skipped(match self.embedding_backfill {
+            Some(_) => "Built without the vector-search feature",
+            None => "No embedding command configured",
+        }))
+    }
+
+    fn record(
+        &self,
+        job: MaintenanceJob,
+        started_at: DateTime<Utc>,
+        outcome: Outcome,
+    ) -> Result<MaintenanceRun> {
+        let mut run = MaintenanceRun {
+            id: 0,
+            job,
+            started_at,
+            completed_at: Utc::now(),
+            status: outcome.status,
+            detail: outcome.detail,
+            message: outcome.message,
+        };
+        run.id = self.database.record_maintenance_run(&run)?;
+        tracing::info!(
+            job = %job,
+            status = %run.status,
+            duration_ms = (run.completed_at - run.started_at).num_milliseconds(),
+            "Maintenance job finished"
+        );
+        Ok(run)
+    }
+
+    fn decay_engine(&self) -> MutexGuard<'_, DecayEngine> {
+        self.decay
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Run due jobs every `poll_interval` on a background thread, until the
+    /// returned handle is dropped
+    pub fn spawn(self: Arc<Self>, poll_interval: std::time::Duration) -> Result<SchedulerThread> {
+        let stop = Arc::new((Mutex::new(false), Condvar::new()));
+        let thread = std::thread::Builder::new()
+            .name("memex-maintenance".to_string())
+            .spawn({
+                let stop = stop.clone();
+                move || {
+                    let (stopped, wake) = &*stop;
+                    let mut stopped = stopped.lock().unwrap_or_else(|p| p.into_inner());
+                    loop {
+                        stopped = wake
+                            .wait_timeout(stopped, poll_interval)
+                            .unwrap_or_else(|p| p.into_inner())
+                            .0;
+                        if *stopped {
+                            break;
+                        }
+                        if let Err(e) = self.run_due(Utc::now()) {
+                            tracing::warn!(error = %e, "Failed to run due maintenance jobs");
+                        }
+                    }
+                }
+            })
+            .context("Failed to start maintenance thread")?;
+
+        Ok(SchedulerThread {
+            stop,
+            thread: Some(thread),
+        })
+    }
+}
+
+/// Background thread started by [`MaintenanceScheduler::spawn`]; dropping it
+/// stops the thread once any job in progress finishes
+pub struct SchedulerThread {
+    stop: Arc<(Mutex<bool>, Condvar)>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl Drop for SchedulerThread {
+    fn drop(&mut self) {
+        let (stopped, wake) = &*self.stop;
+        *stopped.lock().unwrap_or_else(|p| p.into_inner()) = true;
+        wake.notify_all();
+        if let Some(thread) = self.thread.take() {
+            if thread.join().is_err() {
+                tracing::warn!("Maintenance thread panicked");
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::RequestValidator;
+    use crate::database::models::{DecayPolicy, MemoryItem};
+    use crate::database::DatabaseConfig;
+    use tempfile::TempDir;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    fn next(schedule: &str, after: &str) -> Option<DateTime<Utc>> {
+        schedule.parse::<Schedule>().unwrap().next_after(at(after))
+    }
+
+    #[test]
+    fn test_schedule_parsing() {
+        assert_eq!(
+            next("@every 90m", "2026-03-01T10:15:30Z"),
+            Some(at("2026-03-01T11:45:30Z"))
+        );
+        assert_eq!(
+            next("@hourly", "2026-03-01T10:15:30Z"),
+            Some(at("2026-03-01T11:00:00Z"))
+        );
+        assert_eq!(
+            next("@daily", "2026-03-31T23:59:00Z"),
+            Some(at("2026-04-01T00:00:00Z"))
+        );
+        assert_eq!(
+            next("*/15 9-17 * * 1-5", "2026-03-06T17:50:00Z"),
+            Some(at("2026-03-09T09:00:00Z"))
+        );
+        assert_eq!(
+            next("30 3 * * 7", "2026-03-01T04:00:00Z"),
+            Some(at("2026-03-08T03:30:00Z"))
+        );
+        // Both day fields restricted: either one matching is enough
+        assert_eq!(
+            next("0 0 13 * 5", "2026-03-01T00:00:00Z"),
+            Some(at("2026-03-06T00:00:00Z"))
+        );
+        assert_eq!(
+            next("0 12 29 2 *", "2026-03-01T00:00:00Z"),
+            Some(at("2028-02-29T12:00:00Z"))
+        );
+        assert_eq!(next("0 0 31 2 *", "2026-03-01T00:00:00Z"), None);
+
+        for invalid in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "*/0 * * * *",
+            "5-1 * * * *",
+            "@every 5",
+            "@every 0s",
+            "@yearly",
+        ] {
+            assert!(invalid.parse::<Schedule>().is_err(), "{}", invalid);
+        }
+
+        let config: MaintenanceConfig =
+            serde_json::from_str(r#"{"schedules": {"decay": "0 4 * * *"}}"#).unwrap();
+        assert_eq!(
+            config.schedules[&MaintenanceJob::Decay].to_string(),
+            "0 4 * * *"
+        );
+        assert!(
+            serde_json::from_str::<MaintenanceConfig>(r#"{"schedules": {"decay": "daily"}}"#)
+                .is_err()
+        );
+        assert!(serde_json::from_str::<MaintenanceConfig>(
+            r#"{"schedules": {"webhooks": "@daily"}}"#
+        )
+        .is_err());
+    }
+
+    fn setup_scheduler(
+        schedules: BTreeMap<MaintenanceJob, Schedule>,
+    ) -> (MaintenanceScheduler, Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("maintenance.db");
+        let database = Database::new(DatabaseConfig {
+            path: db_path.to_string_lossy().to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let decay = DecayEngine::new(
+            database.clone(),
+            RequestValidator::new(&Default::default()),
+            DecayPolicy::default(),
+        );
+        let scheduler = MaintenanceScheduler::new(
+            database.clone(),
+            Arc::new(Mutex::new(decay)),
+            schedules,
+            None,
+        );
+        (scheduler, database, temp_dir)
+    }
+
+    #[test]
+    fn test_run_due_records_history() {
+        let (scheduler, database, _temp_dir) = setup_scheduler(BTreeMap::from([
+            (MaintenanceJob::Decay, Schedule::every(Duration::hours(24))),
+            (MaintenanceJob::Analyze, "@every 1h".parse().unwrap()),
+            (MaintenanceJob::StatsSnapshot, "@every 2h".parse().unwrap()),
+        ]));
+        database
+            .save_memory(&MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "s1".to_string(),
+                content: "Kept through maintenance".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Nothing is due until a period has passed since the scheduler started
+        let now = Utc::now();
+        assert!(scheduler.run_due(now).unwrap().is_empty());
+
+        let runs = scheduler.run_due(now + Duration::minutes(61)).unwrap();
+        assert_eq!(runs.len(), 1);
+        assert_eq!(runs[0].job, MaintenanceJob::Analyze);
+        assert_eq!(runs[0].status, MaintenanceStatus::Completed);
+
+        // The recorded run pushes the next analyze out by an hour from when
+        // it ran
+        assert_eq!(
+            scheduler.next_due(MaintenanceJob::Analyze).unwrap(),
+            Some(runs[0].started_at + Duration::hours(1))
+        );
+        let later = now + Duration::hours(3);
+        let jobs: Vec<_> = scheduler
+            .run_due(later)
+            .unwrap()
+            .into_iter()
+            .map(|run| run.job)
+            .collect();
+        assert_eq!(
+            jobs,
+            vec![MaintenanceJob::Analyze, MaintenanceJob::StatsSnapshot]
+        );
+
+        // On-demand runs are recorded too
+        let run = scheduler.run_job(MaintenanceJob::Decay).unwrap();
+        assert_eq!(run.status, MaintenanceStatus::Completed);
+        assert_eq!(run.detail.unwrap()["status"], "completed");
+        let run = scheduler
+            .run_job(MaintenanceJob::EmbeddingBackfill)
+            .unwrap();
+        assert_eq!(run.status, MaintenanceStatus::Skipped);
+
+        let history = database.get_maintenance_runs(None, 10).unwrap();
+        assert_eq!(history.len(), 5);
+        assert_eq!(history[0].job, MaintenanceJob::EmbeddingBackfill);
+        assert_eq!(history[4].job, MaintenanceJob::Analyze);
+        let decay_runs = database
+            .get_maintenance_runs(Some(MaintenanceJob::Decay), 10)
+            .unwrap();
+        assert_eq!(decay_runs.len(), 1);
+        assert!(scheduler.next_due(MaintenanceJob::Decay).unwrap().unwrap() > later);
+        assert_eq!(
+            scheduler.next_due(MaintenanceJob::Checkpoint).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_lock_held_elsewhere_skips() {
+        let (scheduler, database, temp_dir) = setup_scheduler(BTreeMap::from([(
+            MaintenanceJob::Analyze,
+            "@every 1h".parse().unwrap(),
+        )]));
+        let other = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("maintenance.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        assert!(other.acquire_maintenance_lock().unwrap());
+
+        assert!(scheduler
+            .run_due(Utc::now() + Duration::hours(2))
+            .unwrap()
+            .is_empty());
+        let run = scheduler.run_job(MaintenanceJob::Analyze).unwrap();
+        assert_eq!(run.status, MaintenanceStatus::Skipped);
+        assert!(run.message.unwrap().contains("Maintenance lock held"));
+        assert_eq!(database.get_maintenance_runs(None, 10).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_background_thread_runs_due_jobs() {
+        let (scheduler, database, _temp_dir) = setup_scheduler(BTreeMap::from([(
+            MaintenanceJob::StatsSnapshot,
+            "@every 1s".parse().unwrap(),
+        )]));
+        let thread = Arc::new(scheduler)
+            .spawn(std::time::Duration::from_millis(100))
+            .unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(2000));
+        drop(thread);
+
+        let runs = database
+            .get_maintenance_runs(Some(MaintenanceJob::StatsSnapshot), 10)
+            .unwrap();
+        assert!(!runs.is_empty());
+        assert!(runs
+            .iter()
+            .all(|run| run.status == MaintenanceStatus::Completed));
+    }
+
+    #[test]
+    fn test_backup_job_writes_and_prunes() {
+        let (mut scheduler, database, temp_dir) = setup_scheduler(BTreeMap::new());
+        let run = scheduler.run_job(MaintenanceJob::Backup).unwrap();
+        assert_eq!(run.status, MaintenanceStatus::Skipped);
+        assert_eq!(scheduler.backup_status(Utc::now()).unwrap(), None);
+
+        let directory = temp_dir.path().join("backups");
+        scheduler.enable_backups(ScheduledBackupConfig {
+            compress: false,
+            keep_daily: 1,
+            keep_weekly: 0,
+            ..ScheduledBackupConfig::new(directory.to_string_lossy())
+        });
+        let status = scheduler.backup_status(Utc::now()).unwrap().unwrap();
+        assert!(status.overdue && status.latest.is_none());
+
+        // An older backup of the same day is pruned in favour of the new one
+        std::fs::create_dir_all(&directory).unwrap();
+        let stale = directory.join(backup::scheduled_file_name(
+            database.path(),
+            Utc::now() - Duration::seconds(5),
+            false,
+        ));
+        database.backup_to(&stale.to_string_lossy()).unwrap();
+
+        let run = scheduler.run_job(MaintenanceJob::Backup).unwrap();
+        assert_eq!(run.status, MaintenanceStatus::Completed, "{:?}", run);
+        let detail = run.detail.unwrap();
+        assert!(detail["size_bytes"].as_u64().unwrap() > 0);
+        assert_eq!(detail["pruned"][0], stale.to_string_lossy().as_ref());
+        assert!(!stale.exists());
+
+        let status = scheduler.backup_status(Utc::now()).unwrap().unwrap();
+        assert!(!status.overdue);
+        assert_eq!(status.count, 1);
+        assert_eq!(
+            status.latest.unwrap().path.to_string_lossy(),
+            detail["file"].as_str().unwrap()
+        );
+        let status = scheduler
+            .backup_status(Utc::now() + Duration::hours(49))
+            .unwrap()
+            .unwrap();
+        assert!(status.overdue);
+    }
+}
@@ -3,17 +3,57 @@
 use anyhow::{Context, Result};
 use chrono::{DateTime, Utc};
 use std::collections::HashMap;
+use std::sync::Arc;
 use std::time::Instant;
-use uuid::Uuid;
 
-use crate::core::{BatchRequest, BatchResponse, PerformanceMonitor, RequestValidator};
-use crate::database::{models::*, Database};
+use crate::core::analytics::UserAnalytics;
+use crate::core::clustering::{self, ClusterReport, ClusteringMethod, MAX_CLUSTER_MEMORIES};
+use crate::core::conflicts::{self, MemoryConflict, CONFLICT_SIMILARITY};
+use crate::core::context::RequestContext;
+use crate::core::counters::{CounterEvent, EventCounters};
+use crate::core::graph::MemoryGraph;
+use crate::core::interop::{self, DocumentFormat, ImportReport, ImportedMemory};
+use crate::core::middleware::MemoryMiddleware;
+use crate::core::timeline::{self, TimeBucket, Timeline, MAX_TIMELINE_MEMORIES};
+use crate::core::tokens;
+use crate::core::trends::{self, KeywordTrends, TREND_WINDOWS};
+use crate::core::{
+    BatchRequest, BatchResponse, PerformanceMonitor, QuotaAction, QuotaPolicy, RequestValidator,
+    ValidationError,
+};
+use crate::database::ids::IdKind;
+#[cfg(feature = "vector-search")]
+use crate::database::vector::{Embedder, VectorSearchEngine};
+use crate::database::{models::*, Database, RecallPlan};
+use crate::logging;
+
+/// Most IDs one `MemoryManager::get_memories` call accepts
+pub const MAX_GET_MEMORIES: usize = 1000;
+
+/// Most hits per session `MemoryManager::search_grouped_by_session` returns
+pub const MAX_SESSION_HITS: usize = 100;
+
+/// Longest term or synonym `MemoryManager::add_synonym` accepts, in
+/// characters
+pub const MAX_SYNONYM_LENGTH: usize = 255;
+
+/// Lower bounds of the importance histogram in `UserMemoryStats`
+const IMPORTANCE_EDGES: [f64; 10] = [0.0, 0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9];
+
+/// Lower bounds of the content size histogram in `UserMemoryStats`, in bytes
+const CONTENT_BYTES_EDGES: [f64; 7] = [0.0, 64.0, 256.0, 1024.0, 4096.0, 16384.0, 65536.0];
 
 /// Memory management service
 pub struct MemoryManager {
     database: Database,
     validator: RequestValidator,
     monitor: PerformanceMonitor,
+    counters: Arc<EventCounters>,
+    middleware: Vec<Arc<dyn MemoryMiddleware>>,
+    #[cfg(feature = "vector-search")]
+    vector: Option<VectorSearchEngine>,
+    #[cfg(feature = "vector-search")]
+    query_embedder: Option<Arc<dyn Embedder + Send + Sync>>,
 }
 
 impl MemoryManager {
@@ -22,23 +62,108 @@ impl MemoryManager {
             database,
             validator,
             monitor: PerformanceMonitor::new(1000), // Keep last 1000 samples
+            counters: Arc::new(EventCounters::new()),
+            middleware: Vec::new(),
+            #[cfg(feature = "vector-search")]
+            vector: None,
+            #[cfg(feature = "vector-search")]
+            query_embedder: None,
         }
     }
 
+    /// Run `middleware` around later saves, recalls, updates and deletes,
+    /// after any middleware added before it
+    pub fn add_middleware(&mut self, middleware: Arc<dyn MemoryMiddleware>) {
+        tracing::debug!(middleware = middleware.name(), "Added memory middleware");
+        self.middleware.push(middleware);
+    }
+
+    /// Let clustering use memory embeddings from `engine`
+    #[cfg(feature = "vector-search")]
+    pub fn enable_vector_search(&mut self, engine: VectorSearchEngine) {
+        self.vector = Some(engine);
+    }
+
+    /// Embed `QueryFilter::semantic_query` with `embedder`, through the
+    /// embedding cache, to rank recalls against memories embedded by the
+    /// same model; needs [`enable_vector_search`](Self::enable_vector_search)
+    #[cfg(feature = "vector-search")]
+    pub fn set_query_embedder(&mut self, embedder: Arc<dyn Embedder + Send + Sync>) {
+        tracing::debug!(model = embedder.model_name(), "Set query embedder");
+        self.query_embedder = Some(embedder);
+    }
+
+    /// Operation counters; share them with the `SessionManager` through
+    /// `SessionManager::use_counters`
+    pub fn counters(&self) -> Arc<EventCounters> {
+        self.counters.clone()
+    }
+
+    /// Run `op` on this manager inside `context`, so the writes it makes are
+    /// attributed to the context's actor in the audit log
+    ///
+    /// ```no_run
+    /// # use memex_core::core::{context::RequestContext, memory::MemoryManager};
+    /// # use memex_core::MemoryItem;
+    /// # fn save(manager: &MemoryManager, memory: MemoryItem) -> anyhow::Result<String> {
+    /// let context = RequestContext::new().with_actor("planner-agent");
+    /// manager.in_context(&context, |manager| manager.save_memory(memory))
+    /// # }
+    /// ```
+    pub fn in_context<T>(&self, context: &RequestContext, op: impl FnOnce(&Self) -> T) -> T {
+        let _scope = context.enter();
+        op(self)
+    }
+
     /// Save a single memory item
-    pub fn save_memory(&self, mut memory: MemoryItem) -> Result<String> {
-        let start = Instant::now();
+    pub fn save_memory(&self, memory: MemoryItem) -> Result<String> {
+        self.save_memory_with_receipt(memory)
+            .map(|receipt| receipt.id)
+    }
+
+    /// Save a single memory item and report what the write did
+    pub fn save_memory_with_receipt(&self, memory: MemoryItem) -> Result<SaveReceipt> {
+        self.save_memory_checked(memory, true)
+    }
 
+    /// Save `memory`, holding its user to `max_bytes_per_user` only if
+    /// `enforce_quota` is set
+    fn save_memory_checked(&self, memory: MemoryItem, enforce_quota: bool) -> Result<SaveReceipt> {
         // Rate limiting
-        self.validator.validate_request(1)?;
+        let result = self
+            .validator
+            .validate_request_for(&memory.user_id, 1)
+            .map_err(Into::into)
+            .and_then(|()| self.save_memory_inner(memory, enforce_quota));
+        self.counters.record(CounterEvent::Save, &result);
+        result
+    }
 
-        // Validation
-        self.validator.validate_memory_item(&memory)?;
+    fn save_memory_inner(
+        &self,
+        mut memory: MemoryItem,
+        enforce_quota: bool,
+    ) -> Result<SaveReceipt> {
+        let start = Instant::now();
+        let span = logging::span("save_memory")
+            .with("user_id", &memory.user_id)
+            .with("session_id", &memory.session_id);
+
+        // A retry of a save already made is answered before validation, so
+        // it can't fail where the first attempt succeeded
+        if let Some(key) = &memory.idempotency_key {
+            if let Some(receipt) = self.database.find_idempotent_save(&memory.user_id, key)? {
+                span.record("memory_id", &receipt.id);
+                tracing::debug!("Save already made with this idempotency key");
+                return Ok(receipt);
+            }
+        }
 
         // Set default values
         if memory.id.is_empty() {
-            memory.id = Uuid::new_v4().to_string();
+            memory.id = self.database.generate_id(IdKind::Memory);
         }
+        span.record("memory_id", &memory.id);
 
         if memory.created_at == DateTime::<Utc>::MIN_UTC {
             memory.created_at = Utc::now();
@@ -46,10 +171,18 @@ impl MemoryManager {
 
         memory.updated_at = Utc::now();
 
-        // Calculate expiration
-        if let Some(ttl_hours) = memory.ttl_hours {
-            memory.expires_at = Some(memory.created_at + chrono::Duration::hours(ttl_hours as i64));
+        for middleware in &self.middleware {
+            middleware.before_save(&mut memory)?;
+        }
+
+        // Validation
+        self.validator.validate_memory_item(&memory)?;
+        self.validate_content(&memory)?;
+        if enforce_quota {
+            self.validator
+                .validate_byte_quota(&self.database, &memory)?;
         }
+        self.validator.validate_session(&self.database, &memory)?;
 
         // Clamp importance
         memory.importance = memory.importance.clamp(0.0, 1.0);
@@ -57,22 +190,70 @@ impl MemoryManager {
         // Save to database
         let result = self
             .database
-            .save_memory(&memory)
+            .save_memory_with_receipt(&memory)
             .context("Failed to save memory to database");
 
         // Record performance
         let duration = start.elapsed().as_millis() as f32;
         self.monitor.record_save_time(duration);
 
-        log::debug!("Saved memory {} in {}ms", memory.id, duration);
+        if result.as_ref().is_ok_and(|receipt| !receipt.replayed) {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_save(&memory);
+            }
+        }
+
+        tracing::debug!(duration_ms = duration, "Saved memory");
         result
     }
 
+    /// Check that JSON content parses and matches its namespace's schema
+    fn validate_content(&self, memory: &MemoryItem) -> Result<()> {
+        if memory.content_type != ContentType::Json {
+            return Ok(());
+        }
+
+        let document: serde_json::Value =
+            serde_json::from_str(&memory.content).map_err(|e| ValidationError::InvalidInput {
+                message: format!("Content is not valid JSON: {}", e),
+                field: Some("content".to_string()),
+                limit: None,
+                actual: None,
+            })?;
+        let Some(registered) = self.database.get_json_schema(memory.namespace())? else {
+            return Ok(());
+        };
+
+        let schema = compile_json_schema(&registered.schema)?;
+        if let Err(errors) = schema.validate(&document) {
+            let problems: Vec<String> = errors
+                .map(|error| match error.instance_path.to_string() {
+                    path if path.is_empty() => error.to_string(),
+                    path => format!("{}: {}", path, error),
+                })
+                .collect();
+            return Err(ValidationError::InvalidInput {
+                message: format!(
+                    "Content does not match the {} schema: {}",
+                    registered.namespace,
+                    problems.join("; ")
+                ),
+                field: Some("content".to_string()),
+                limit: None,
+                actual: None,
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     /// Save multiple memories in a batch
     pub fn save_memories_batch(
         &self,
         request: BatchRequest<MemoryItem>,
     ) -> Result<BatchResponse<String>> {
+        let _span = logging::span("save_memories_batch");
+
         // Validate batch size
         self.validator.validate_batch_size(request.items.len())?;
 
@@ -81,46 +262,394 @@ impl MemoryManager {
         self.validator.validate_request(batch_tokens)?;
 
         let mut response = BatchResponse::new();
+        let mut saved = Vec::new();
+        let mut items = request.items.into_iter();
 
-        for memory in request.items {
-            match self.save_memory(memory) {
-                Ok(id) => response.add_success(id),
-                Err(e) => {
-                    let error_msg = e.to_string();
-                    response.add_error(error_msg);
+        while let Some(memory) = items.next() {
+            let (result, quota_action) = match self.save_memory(memory.clone()) {
+                Err(e) => match over_byte_quota(&e) {
+                    Some(excess) => {
+                        let (result, action) =
+                            self.save_over_quota(request.quota_policy, memory, e, excess, &saved);
+                        (result, Some(action))
+                    }
+                    None => (Err(e), None),
+                },
+                result => (result, None),
+            };
+            let stop = match &result {
+                Ok(_) => false,
+                Err(_) => {
+                    request.fail_on_error
+                        || (request.quota_policy == QuotaPolicy::StopAtQuota
+                            && quota_action == Some(QuotaAction::Rejected))
+                }
+            };
+            if let Ok(id) = &result {
+                saved.push(id.clone());
+            }
+            response.add(result.map_err(|e| e.to_string()), quota_action);
 
-                    // If fail_on_error is true, stop processing
-                    if request.fail_on_error {
-                        break;
+            // If fail_on_error is true, stop processing
+            if stop {
+                if !request.fail_on_error {
+                    for _ in items.by_ref() {
+                        response.add(
+                            Err("Skipped: an earlier item hit the storage quota".to_string()),
+                            Some(QuotaAction::Skipped),
+                        );
                     }
                 }
+                break;
             }
         }
 
-        log::info!(
-            "Batch save completed: {}/{} successful",
-            response.success_count,
-            response.results.len()
+        tracing::info!(
+            succeeded = response.success_count,
+            total = response.results.len(),
+            "Batch save completed"
         );
 
         Ok(response)
     }
 
+    /// Deal with `memory`, which `error` turned away for taking its user
+    /// `excess` bytes past the quota, as `policy` says; memories in `saved`
+    /// are never evicted
+    fn save_over_quota(
+        &self,
+        policy: QuotaPolicy,
+        mut memory: MemoryItem,
+        error: anyhow::Error,
+        excess: u64,
+        saved: &[String],
+    ) -> (Result<String>, QuotaAction) {
+        match policy {
+            QuotaPolicy::Reject | QuotaPolicy::StopAtQuota => (Err(error), QuotaAction::Rejected),
+            QuotaPolicy::EvictLowestImportance => {
+                let evicted = self
+                    .database
+                    .eviction_candidates(
+                        &memory.user_id,
+                        memory.importance.clamp(0.0, 1.0),
+                        excess,
+                        saved,
+                    )
+                    .and_then(|candidates| self.evict(&candidates));
+                match evicted {
+                    Ok(ids) if !ids.is_empty() => {
+                        tracing::info!(
+                            user_id = memory.user_id.as_str(),
+                            count = ids.len(),
+                            "Evicted memories to make room"
+                        );
+                        (self.save_memory(memory), QuotaAction::Evicted { ids })
+                    }
+                    Ok(_) => (Err(error), QuotaAction::Rejected),
+                    Err(e) => (Err(e), QuotaAction::Rejected),
+                }
+            }
+            QuotaPolicy::OverflowToArchive => {
+                memory.state = MemoryState::Archived;
+                let result = self
+                    .save_memory_checked(memory, false)
+                    .map(|receipt| receipt.id);
+                (result, QuotaAction::Archived)
+            }
+        }
+    }
+
+    /// Delete memories `ids` through the middleware, returning those deleted
+    fn evict(&self, ids: &[String]) -> Result<Vec<String>> {
+        for id in ids {
+            for middleware in &self.middleware {
+                middleware.before_delete(id)?;
+            }
+        }
+
+        let deleted = self
+            .database
+            .delete_memories(ids)
+            .context("Failed to evict memories")?;
+
+        for id in &deleted {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_delete(id);
+            }
+        }
+        Ok(deleted)
+    }
+
+    /// Save memories mapped from another store's export by
+    /// [`crate::core::interop::parse`], storing their embeddings under
+    /// `embedding_model` when vector search is enabled
+    ///
+    /// The import counts as one request against the rate limit. Records
+    /// that fail validation are reported and the rest are still saved.
+    pub fn import_memories(
+        &self,
+        records: Vec<ImportedMemory>,
+        embedding_model: &str,
+    ) -> Result<ImportReport> {
+        let _span = logging::span("import_memories");
+        self.validator.validate_request(1)?;
+
+        let mut report = ImportReport {
+            records: records.len(),
+            ..Default::default()
+        };
+        for (index, record) in records.into_iter().enumerate() {
+            let result = self.save_memory_inner(record.memory, true);
+            self.counters.record(CounterEvent::Save, &result);
+            let id = match result {
+                Ok(receipt) => receipt.id,
+                Err(e) => {
+                    report.errors.push(format!("Record {}: {:#}", index + 1, e));
+                    continue;
+                }
+            };
+            report.imported += 1;
+
+            if let Some(embedding) = record.embedding {
+                match self.store_imported_embedding(&id, &embedding, embedding_model) {
+                    Ok(true) => report.embeddings_stored += 1,
+                    Ok(false) => report.embeddings_skipped += 1,
+                    Err(e) => {
+                        tracing::warn!(memory_id = id.as_str(), error = %e, "Failed to store imported embedding");
+                        report.embeddings_skipped += 1;
+                    }
+                }
+            }
+        }
+
+        tracing::info!(
+            imported = report.imported,
+            failed = report.errors.len(),
+            embeddings = report.embeddings_stored,
+            "Import completed"
+        );
+        Ok(report)
+    }
+
+    /// Store an imported embedding; false if vector search is off
+    #[cfg(feature = "vector-search")]
+    fn store_imported_embedding(&self, id: &str, embedding: &[f32], model: &str) -> Result<bool> {
+        let Some(engine) = &self.vector else {
+            return Ok(false);
+        };
+        engine.store_embedding(id, embedding, model)?;
+        Ok(true)
+    }
+
+    #[cfg(not(feature = "vector-search"))]
+    fn store_imported_embedding(
+        &self,
+        _id: &str,
+        _embedding: &[f32],
+        _model: &str,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Render template `template_id` with `vars` and save the result for
+    /// `user_id` in `session_id`
+    ///
+    /// Every placeholder in the template's content and metadata must have a
+    /// value in `vars`; extra variables are ignored. The memory takes the
+    /// template's importance and TTL.
+    pub fn save_from_template(
+        &self,
+        template_id: &str,
+        user_id: &str,
+        session_id: &str,
+        vars: &HashMap<String, String>,
+    ) -> Result<String> {
+        let memory = self.render_template(template_id, user_id, session_id, vars)?;
+        self.save_memory(memory)
+    }
+
+    /// The memory [`save_from_template`](Self::save_from_template) would
+    /// save, without saving it
+    pub fn render_template(
+        &self,
+        template_id: &str,
+        user_id: &str,
+        session_id: &str,
+        vars: &HashMap<String, String>,
+    ) -> Result<MemoryItem> {
+        let template = self
+            .get_template(template_id)?
+            .ok_or_else(|| anyhow::anyhow!("Template not found: {}", template_id))?;
+
+        let metadata = template
+            .metadata
+            .iter()
+            .map(|(key, value)| Ok((key.clone(), render_placeholders(value, vars)?)))
+            .collect::<Result<HashMap<_, _>>>()?;
+        let memory = MemoryItem {
+            user_id: user_id.to_string(),
+            session_id: session_id.to_string(),
+            content: render_placeholders(&template.content, vars)?,
+            metadata,
+            importance: template.importance,
+            ttl_hours: template.ttl_hours,
+            ..Default::default()
+        };
+
+        tracing::debug!(template_id = template_id, "Rendered memory template");
+        Ok(memory)
+    }
+
+    /// Register the JSON Schema that JSON memories in `namespace` must
+    /// match, replacing any existing one
+    ///
+    /// Memories saved before are not re-checked.
+    pub fn register_json_schema(&self, namespace: &str, schema: serde_json::Value) -> Result<()> {
+        if namespace.trim().is_empty() {
+            return Err(ValidationError::invalid_input("Namespace must not be empty").into());
+        }
+        compile_json_schema(&schema)?;
+        self.database.set_json_schema(namespace, &schema)?;
+        tracing::info!(namespace = namespace, "Registered JSON schema");
+        Ok(())
+    }
+
+    /// Registered JSON Schemas, by namespace
+    pub fn list_json_schemas(&self) -> Result<Vec<NamespaceSchema>> {
+        self.database.list_json_schemas()
+    }
+
+    /// Remove the JSON Schema of `namespace`; returns false if it had none
+    pub fn remove_json_schema(&self, namespace: &str) -> Result<bool> {
+        self.database.delete_json_schema(namespace)
+    }
+
+    /// Make keyword searches scoped to `namespace` also find `synonym`
+    /// when asked for `term`, and the other way round; returns false if the
+    /// pair already existed
+    ///
+    /// Searches without a namespace filter use [`DEFAULT_NAMESPACE`].
+    pub fn add_synonym(&self, namespace: &str, term: &str, synonym: &str) -> Result<bool> {
+        let (term, synonym) = (term.trim(), synonym.trim());
+        if namespace.trim().is_empty() {
+            return Err(ValidationError::invalid_input("Namespace must not be empty").into());
+        }
+        for (field, value) in [("term", term), ("synonym", synonym)] {
+            let length = value.chars().count();
+            if length == 0 || length > MAX_SYNONYM_LENGTH {
+                return Err(ValidationError::InvalidInput {
+                    message: format!(
+                        "{} must be between 1 and {} characters",
+                        field, MAX_SYNONYM_LENGTH
+                    ),
+                    field: Some(field.to_string()),
+                    limit: Some(MAX_SYNONYM_LENGTH),
+                    actual: Some(length),
+                }
+                .into());
+            }
+        }
+        if term.to_lowercase() == synonym.to_lowercase() {
+            return Err(ValidationError::invalid_input("A term can't be its own synonym").into());
+        }
+
+        let added = self.database.add_synonym(namespace, term, synonym)?;
+        if added {
+            tracing::info!(
+                namespace = namespace,
+                term = term,
+                synonym = synonym,
+                "Added synonym"
+            );
+        }
+        Ok(added)
+    }
+
+    /// Synonym pairs of `namespace`, or of every namespace
+    pub fn list_synonyms(&self, namespace: Option<&str>) -> Result<Vec<Synonym>> {
+        self.database.list_synonyms(namespace)
+    }
+
+    /// Remove a synonym pair, given in either order; returns false if there
+    /// was none
+    pub fn remove_synonym(&self, namespace: &str, term: &str, synonym: &str) -> Result<bool> {
+        self.database
+            .remove_synonym(namespace, term.trim(), synonym.trim())
+    }
+
+    /// `keywords` followed by their synonyms in `namespace`, each once
+    fn expand_keywords(&self, namespace: &str, keywords: Vec<String>) -> Result<Vec<String>> {
+        let synonyms = self.database.synonyms_of(namespace, &keywords)?;
+        let mut seen: std::collections::HashSet<String> =
+            keywords.iter().map(|k| k.to_lowercase()).collect();
+        let mut expanded = keywords;
+        for synonym in synonyms {
+            if seen.insert(synonym.to_lowercase()) {
+                expanded.push(synonym);
+            }
+        }
+        Ok(expanded)
+    }
+
+    /// Get a memory template by ID
+    pub fn get_template(&self, template_id: &str) -> Result<Option<MemoryTemplate>> {
+        self.database.get_template(template_id)
+    }
+
+    /// All memory templates, by ID
+    pub fn list_templates(&self) -> Result<Vec<MemoryTemplate>> {
+        self.database.list_templates()
+    }
+
+    /// Add or replace a memory template
+    pub fn set_template(&self, template: MemoryTemplate) -> Result<()> {
+        self.database.set_template(&template)?;
+        tracing::info!(
+            template_id = template.id.as_str(),
+            placeholders = ?template.placeholders(),
+            "Set memory template"
+        );
+        Ok(())
+    }
+
+    /// Remove a memory template; returns false if there was none
+    pub fn delete_template(&self, template_id: &str) -> Result<bool> {
+        self.database.delete_template(template_id)
+    }
+
     /// Recall memories with filtering and pagination
     pub fn recall_memories(&self, filter: QueryFilter) -> Result<PaginatedResponse<MemoryItem>> {
+        let result = self.query_memories(filter);
+        self.counters.record(CounterEvent::Recall, &result);
+        result
+    }
+
+    /// `recall_memories` without counting the call, for internal paging
+    fn query_memories(&self, mut filter: QueryFilter) -> Result<PaginatedResponse<MemoryItem>> {
         let start = Instant::now();
+        let span = logging::span("recall_memories");
+        if let Some(user_id) = &filter.user_id {
+            span.record("user_id", user_id);
+        }
+        if let Some(session_id) = &filter.session_id {
+            span.record("session_id", session_id);
+        }
 
         // Rate limiting
-        self.validator.validate_request(1)?;
+        self.validator
+            .validate_request_for(filter.user_id.as_deref().unwrap_or_default(), 1)?;
 
-        // Validation
-        self.validator.validate_query_filter(&filter)?;
+        self.prepare_recall_filter(&mut filter)?;
 
         // Execute query
-        let result = self
-            .database
-            .recall_memories(&filter)
+        let mut result = self
+            .recall_prepared(&filter)
             .context("Failed to recall memories from database");
+        if let Ok(response) = &mut result {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_recall(&filter, response);
+            }
+        }
 
         // Record performance
         let duration = start.elapsed().as_millis() as f32;
@@ -128,25 +657,95 @@ impl MemoryManager {
 
         match &result {
             Ok(response) => {
-                log::debug!(
-                    "Recalled {} memories in {}ms (page {}/{})",
-                    response.data.len(),
-                    duration,
-                    response.page + 1,
-                    response.total_pages
+                tracing::debug!(
+                    count = response.data.len(),
+                    duration_ms = duration,
+                    page = response.page + 1,
+                    total_pages = response.total_pages,
+                    "Recalled memories"
                 );
             }
             Err(e) => {
-                log::error!("Failed to recall memories: {}", e);
+                tracing::error!(error = %e, "Failed to recall memories");
             }
         }
 
         result
     }
 
+    /// Recall with a prepared filter, ranking by its semantic query when
+    /// there is an engine and embedder to compare it with, and otherwise
+    /// searching the query's words as keywords if there are none
+    fn recall_prepared(&self, filter: &QueryFilter) -> Result<PaginatedResponse<MemoryItem>> {
+        let Some(semantic_query) = &filter.semantic_query else {
+            return self.database.recall_memories(filter);
+        };
+
+        #[cfg(feature = "vector-search")]
+        if let (Some(engine), Some(embedder)) = (&self.vector, &self.query_embedder) {
+            let embedding = engine
+                .embed_cached(embedder.as_ref(), &[semantic_query.as_str()])?
+                .remove(0);
+            return self.database.recall_semantic(
+                filter,
+                &embedding,
+                embedder.model_name(),
+                engine.config().similarity_threshold,
+            );
+        }
+
+        if filter.keywords.is_some() {
+            return self.database.recall_memories(filter);
+        }
+        let namespace = filter
+            .metadata
+            .get(NAMESPACE_METADATA_KEY)
+            .map_or(DEFAULT_NAMESPACE, String::as_str);
+        let keywords = semantic_query
+            .split_whitespace()
+            .map(str::to_string)
+            .collect();
+        self.database.recall_memories(&QueryFilter {
+            keywords: Some(self.expand_keywords(namespace, keywords)?),
+            ..filter.clone()
+        })
+    }
+
+    /// Run `filter` through the recall middleware and validation, and
+    /// expand its keywords with their synonyms
+    fn prepare_recall_filter(&self, filter: &mut QueryFilter) -> Result<()> {
+        for middleware in &self.middleware {
+            middleware.before_recall(filter)?;
+        }
+
+        // Validation
+        self.validator.validate_query_filter(filter)?;
+
+        // Search for the synonyms of the filter's namespace too
+        if let Some(keywords) = filter.keywords.take() {
+            let namespace = filter
+                .metadata
+                .get(NAMESPACE_METADATA_KEY)
+                .map_or(DEFAULT_NAMESPACE, String::as_str);
+            let expanded = self.expand_keywords(namespace, keywords)?;
+            filter.keywords = Some(expanded);
+        }
+        Ok(())
+    }
+
+    /// The SQL a recall with `filter` would run, its redacted parameters and
+    /// SQLite's query plan, so operators can check a new access pattern is
+    /// served by an index. The filter is prepared exactly as for a recall,
+    /// but no memories are read.
+    pub fn explain_recall(&self, mut filter: QueryFilter) -> Result<RecallPlan> {
+        self.prepare_recall_filter(&mut filter)?;
+        self.database.explain_recall(&filter)
+    }
+
     /// Get a single memory by ID
     pub fn get_memory(&self, id: &str) -> Result<Option<MemoryItem>> {
         let start = Instant::now();
+        let _span = logging::span("get_memory").with("memory_id", id);
 
         // Rate limiting
         self.validator.validate_request(1)?;
@@ -162,97 +761,365 @@ impl MemoryManager {
         result
     }
 
+    /// Get several memories by ID in one round trip, in the order given
+    ///
+    /// Unknown or expired IDs are skipped, so the result may be shorter
+    /// than `ids`.
+    pub fn get_memories(&self, ids: &[String]) -> Result<Vec<MemoryItem>> {
+        let start = Instant::now();
+        let _span = logging::span("get_memories");
+
+        if ids.len() > MAX_GET_MEMORIES {
+            return Err(ValidationError::BatchSizeExceeded {
+                size: ids.len(),
+                max: MAX_GET_MEMORIES,
+            }
+            .into());
+        }
+
+        // Rate limiting (1 token per 100 IDs)
+        self.validator
+            .validate_request((ids.len() / 100).max(1) as u32)?;
+
+        let result = self
+            .database
+            .get_memories(ids)
+            .context("Failed to get memories from database");
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+        self.counters.record(CounterEvent::Recall, &result);
+
+        if let Ok(memories) = &result {
+            tracing::debug!(
+                requested = ids.len(),
+                found = memories.len(),
+                duration_ms = duration,
+                "Fetched memories by ID"
+            );
+        }
+        result
+    }
+
     /// Update a memory item
-    pub fn update_memory(&self, id: &str, updates: MemoryUpdate) -> Result<bool> {
+    ///
+    /// The stored memory is read, patched and written back in one
+    /// transaction, so concurrent appends and metadata patches don't lose
+    /// each other's changes. Set `expected_version` to also reject the
+    /// update if someone else changed the memory since it was read.
+    pub fn update_memory(&self, id: &str, mut updates: MemoryUpdate) -> Result<bool> {
         let start = Instant::now();
+        let _span = logging::span("update_memory").with("memory_id", id);
 
         // Rate limiting
         self.validator.validate_request(1)?;
 
-        // Get existing memory
-        let mut memory = match self.database.get_memory(id)? {
-            Some(m) => m,
-            None => return Ok(false), // Memory not found
-        };
-
-        // Apply updates
-        if let Some(content) = updates.content {
-            memory.content = content;
+        for middleware in &self.middleware {
+            middleware.before_update(id, &mut updates)?;
         }
 
-        if let Some(importance) = updates.importance {
-            memory.importance = importance.clamp(0.0, 1.0);
-        }
+        let updated = self
+            .database
+            .update_memory_with(id, updates.expected_version, |memory| {
+                updates.apply(memory);
+                self.validator.validate_memory_item(memory)?;
+                self.validate_content(memory)?;
+                self.validator.validate_byte_quota(&self.database, memory)
+            })?;
 
-        if let Some(metadata) = updates.metadata {
-            memory.metadata = metadata;
-        }
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_save_time(duration);
 
-        if let Some(ttl_hours) = updates.ttl_hours {
-            memory.ttl_hours = ttl_hours;
-            memory.expires_at =
-                ttl_hours.map(|ttl| memory.created_at + chrono::Duration::hours(ttl as i64));
+        if let Some(memory) = &updated {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_update(memory);
+            }
+            tracing::debug!(duration_ms = duration, "Updated memory");
         }
+        Ok(updated.is_some())
+    }
 
-        memory.updated_at = Utc::now();
+    /// Push a memory's expiry back by `hours`; false if it doesn't exist
+    ///
+    /// Memories without a TTL never expire and are left as they are.
+    pub fn extend_ttl(&self, id: &str, hours: u32) -> Result<bool> {
+        self.update_memory(
+            id,
+            MemoryUpdate {
+                extend_ttl_hours: Some(hours),
+                ..Default::default()
+            },
+        )
+    }
 
-        // Validate updated memory
-        self.validator.validate_memory_item(&memory)?;
+    /// Memories of a user that have expired but are still within the
+    /// database's expiry grace period, most recently expired first
+    pub fn get_expired_memories(&self, user_id: &str) -> Result<Vec<MemoryItem>> {
+        let _span = logging::span("get_expired_memories").with("user_id", user_id);
 
-        // Save updated memory
-        self.database.save_memory(&memory)?;
+        // Rate limiting
+        self.validator.validate_request_for(user_id, 1)?;
 
-        let duration = start.elapsed().as_millis() as f32;
-        self.monitor.record_save_time(duration);
+        self.database
+            .get_expired_memories(user_id)
+            .context("Failed to get expired memories from database")
+    }
 
-        log::debug!("Updated memory {} in {}ms", id, duration);
-        Ok(true)
+    /// Bring back an expired memory before cleanup deletes it, with a new
+    /// TTL or, if `ttl_hours` is `None`, its old one; false if it isn't an
+    /// expired memory
+    pub fn restore_expired_memory(&self, id: &str, ttl_hours: Option<u32>) -> Result<bool> {
+        let _span = logging::span("restore_expired_memory").with("memory_id", id);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database
+            .restore_expired_memory(id, ttl_hours)
+            .context("Failed to restore expired memory")
+    }
+
+    /// Move a memory to another lifecycle state, returning the one it left,
+    /// or `None` if there is no live memory `id`; fails for transitions
+    /// [`MemoryState::can_become`] rules out
+    pub fn set_memory_state(&self, id: &str, state: MemoryState) -> Result<Option<MemoryState>> {
+        let _span = logging::span("set_memory_state").with("memory_id", id);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database.set_memory_state(id, state)
+    }
+
+    /// Make a draft memory recallable; false if there is no live memory `id`
+    pub fn confirm_memory(&self, id: &str) -> Result<bool> {
+        Ok(self.set_memory_state(id, MemoryState::Active)?.is_some())
+    }
+
+    /// Keep a memory but leave it out of recall; false if there is no live
+    /// memory `id`
+    pub fn archive_memory(&self, id: &str) -> Result<bool> {
+        Ok(self.set_memory_state(id, MemoryState::Archived)?.is_some())
     }
 
     /// Delete a memory by ID
     pub fn delete_memory(&self, id: &str) -> Result<bool> {
+        let _span = logging::span("delete_memory").with("memory_id", id);
+
         // Rate limiting
         self.validator.validate_request(1)?;
 
+        for middleware in &self.middleware {
+            middleware.before_delete(id)?;
+        }
+
         let result = self
             .database
             .delete_memory(id)
             .context("Failed to delete memory from database");
 
         if let Ok(true) = result {
-            log::debug!("Deleted memory {}", id);
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_delete(id);
+            }
+            tracing::debug!("Deleted memory");
         }
 
         result
     }
 
-    /// Search memories using full-text search
-    pub fn search_memories(
+    /// Delete every memory matching `filter`
+    ///
+    /// The filter must name a user or session. All matches are deleted, so
+    /// its `limit` and `offset` are ignored. With `max_affected` set, more
+    /// matches than that fail with `BatchSizeExceeded` and nothing is
+    /// deleted; with `dry_run` the matches are only reported.
+    pub fn delete_by_filter(
         &self,
-        user_id: &str,
-        query: &str,
-        limit: Option<usize>,
-        offset: Option<usize>,
-    ) -> Result<PaginatedResponse<MemoryItem>> {
-        let keywords = query
-            .split_whitespace()
-            .map(|s| s.to_string())
-            .collect::<Vec<_>>();
+        filter: QueryFilter,
+        options: DeleteOptions,
+    ) -> Result<DeleteReport> {
+        let _span = logging::span("delete_by_filter");
 
-        if keywords.is_empty() {
-            return Ok(PaginatedResponse::empty());
+        if filter.user_id.is_none() && filter.session_id.is_none() {
+            return Err(ValidationError::invalid_input(
+                "Deleting by filter requires a user_id or session_id",
+            )
+            .into());
         }
 
-        let filter = QueryFilter {
-            user_id: Some(user_id.to_string()),
-            keywords: Some(keywords),
-            limit,
-            offset,
-            ..Default::default()
-        };
-
-        self.recall_memories(filter)
-    }
+        let mut ids = Vec::new();
+        let limit = 1000;
+        let mut offset = 0;
+        loop {
+            let page = self.database.recall_memories(&QueryFilter {
+                limit: Some(limit),
+                offset: Some(offset),
+                ..filter.clone()
+            })?;
+            ids.extend(page.data.into_iter().map(|memory| memory.id));
+
+            if !page.has_next {
+                break;
+            }
+            offset += limit;
+        }
+
+        if let Some(max) = options.max_affected {
+            if ids.len() > max {
+                return Err(ValidationError::BatchSizeExceeded {
+                    size: ids.len(),
+                    max,
+                }
+                .into());
+            }
+        }
+
+        if options.dry_run || ids.is_empty() {
+            return Ok(DeleteReport {
+                ids,
+                dry_run: options.dry_run,
+            });
+        }
+
+        // Rate limiting (1 token per 100 memories)
+        self.validator
+            .validate_request((ids.len() / 100).max(1) as u32)?;
+
+        for id in &ids {
+            for middleware in &self.middleware {
+                middleware.before_delete(id)?;
+            }
+        }
+
+        let deleted = self
+            .database
+            .delete_memories(&ids)
+            .context("Failed to delete memories from database")?;
+
+        for id in &deleted {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_delete(id);
+            }
+        }
+        tracing::info!(count = deleted.len(), "Deleted memories by filter");
+
+        Ok(DeleteReport {
+            ids: deleted,
+            dry_run: false,
+        })
+    }
+
+    /// Search memories using full-text search
+    pub fn search_memories(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<MemoryItem>> {
+        self.search(user_id, query, limit, offset, false)
+    }
+
+    /// [`search_memories`](Self::search_memories) with each result's
+    /// `explanation` of its ranking filled in
+    pub fn search_memories_explained(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<MemoryItem>> {
+        self.search(user_id, query, limit, offset, true)
+    }
+
+    fn search(
+        &self,
+        user_id: &str,
+        query: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+        explain: bool,
+    ) -> Result<PaginatedResponse<MemoryItem>> {
+        let keywords = query
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+
+        if keywords.is_empty() {
+            return Ok(PaginatedResponse::empty());
+        }
+
+        let filter = QueryFilter {
+            user_id: Some(user_id.to_string()),
+            keywords: Some(keywords),
+            limit,
+            offset,
+            explain,
+            ..Default::default()
+        };
+
+        let result = self.query_memories(filter);
+        self.counters.record(CounterEvent::Search, &result);
+        result
+    }
+
+    /// Full-text search bucketed by session
+    ///
+    /// Returns each session with matches, most matches first, along with
+    /// its match count and newest `per_session_limit` hits, so callers can
+    /// show "3 matches in Trading Journal" without a query per session.
+    pub fn search_grouped_by_session(
+        &self,
+        user_id: &str,
+        query: &str,
+        per_session_limit: usize,
+    ) -> Result<Vec<SessionSearchGroup>> {
+        let start = Instant::now();
+        let _span = logging::span("search_grouped_by_session").with("user_id", user_id);
+
+        let keywords = query
+            .split_whitespace()
+            .map(|s| s.to_string())
+            .collect::<Vec<_>>();
+        if keywords.is_empty() {
+            return Ok(Vec::new());
+        }
+        if !(1..=MAX_SESSION_HITS).contains(&per_session_limit) {
+            return Err(ValidationError::InvalidInput {
+                message: format!(
+                    "per_session_limit must be between 1 and {}",
+                    MAX_SESSION_HITS
+                ),
+                field: Some("per_session_limit".to_string()),
+                limit: Some(MAX_SESSION_HITS),
+                actual: Some(per_session_limit),
+            }
+            .into());
+        }
+
+        // Rate limiting
+        self.validator.validate_request_for(user_id, 1)?;
+
+        let keywords = self.expand_keywords(DEFAULT_NAMESPACE, keywords)?;
+        let result = self
+            .database
+            .search_grouped_by_session(user_id, &keywords, per_session_limit)
+            .context("Failed to search memories by session");
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+        self.counters.record(CounterEvent::Search, &result);
+
+        if let Ok(groups) = &result {
+            tracing::debug!(
+                sessions = groups.len(),
+                duration_ms = duration,
+                "Searched memories grouped by session"
+            );
+        }
+        result
+    }
 
     /// Get memories for a specific session with pagination
     pub fn get_session_memories(
@@ -301,6 +1168,50 @@ impl MemoryManager {
 
     /// Export all memories for a user
     pub fn export_user_memories(&self, user_id: &str) -> Result<Vec<MemoryItem>> {
+        let _span = logging::span("export_user_memories").with("user_id", user_id);
+        self.collect_user_memories(user_id, false)
+    }
+
+    /// Export all memories for a user as documents RAG frameworks load
+    /// directly, with their stored embeddings
+    pub fn export_user_documents(
+        &self,
+        user_id: &str,
+        format: DocumentFormat,
+    ) -> Result<Vec<serde_json::Value>> {
+        let _span = logging::span("export_user_documents").with("user_id", user_id);
+        let memories = self.collect_user_memories(user_id, true)?;
+        Ok(interop::to_documents(format, &memories))
+    }
+
+    /// Graph of a user's sessions, memories and compression lineage
+    pub fn export_user_graph(&self, user_id: &str) -> Result<MemoryGraph> {
+        let _span = logging::span("export_user_graph").with("user_id", user_id);
+        let memories = self.collect_user_memories(user_id, false)?;
+
+        let mut sessions = Vec::new();
+        let limit = 1000;
+        loop {
+            let page = self.database.get_user_sessions_with_status(
+                user_id,
+                &SessionStatus::ALL,
+                Some(limit),
+                Some(sessions.len()),
+            )?;
+            sessions.extend(page.data);
+            if !page.has_next {
+                break;
+            }
+        }
+
+        Ok(MemoryGraph::build(user_id, &sessions, &memories))
+    }
+
+    fn collect_user_memories(
+        &self,
+        user_id: &str,
+        include_embeddings: bool,
+    ) -> Result<Vec<MemoryItem>> {
         let mut all_memories = Vec::new();
         let mut offset = 0;
         let limit = 1000;
@@ -310,10 +1221,11 @@ impl MemoryManager {
                 user_id: Some(user_id.to_string()),
                 limit: Some(limit),
                 offset: Some(offset),
+                include_embeddings,
                 ..Default::default()
             };
 
-            let response = self.recall_memories(filter)?;
+            let response = self.query_memories(filter)?;
             all_memories.extend(response.data);
 
             if !response.has_next {
@@ -323,11 +1235,7 @@ impl MemoryManager {
             offset += limit;
         }
 
-        log::info!(
-            "Exported {} memories for user {}",
-            all_memories.len(),
-            user_id
-        );
+        tracing::info!(count = all_memories.len(), "Exported memories");
         Ok(all_memories)
     }
 
@@ -339,7 +1247,7 @@ impl MemoryManager {
             ..Default::default()
         };
 
-        let response = self.recall_memories(filter)?;
+        let response = self.query_memories(filter)?;
 
         // Get importance distribution
         let all_memories = self.export_user_memories(user_id)?;
@@ -382,6 +1290,25 @@ impl MemoryManager {
             *age_buckets.entry(bucket.to_string()).or_insert(0) += 1;
         }
 
+        let session_storage = self.database.session_storage(user_id)?;
+
+        let tokenizer = tokens::tokenizer();
+        let mut session_tokens: HashMap<String, u64> = HashMap::new();
+        for memory in &all_memories {
+            *session_tokens.entry(memory.session_id.clone()).or_insert(0) +=
+                tokenizer.count_tokens(&memory.content) as u64;
+        }
+
+        // Importance is stored as f32; rounding keeps 0.7 out of the 0.6 bucket
+        let importance_percentiles = self.database.get_value_distribution(
+            user_id,
+            "ROUND(importance, 6)",
+            &IMPORTANCE_EDGES,
+        )?;
+        let content_bytes_percentiles =
+            self.database
+                .get_value_distribution(user_id, "content_bytes", &CONTENT_BYTES_EDGES)?;
+
         Ok(UserMemoryStats {
             user_id: user_id.to_string(),
             total_memories: response.total_count,
@@ -396,189 +1323,2517 @@ impl MemoryManager {
                 .iter()
                 .max_by_key(|m| m.created_at)
                 .map(|m| m.created_at),
+            importance_percentiles,
+            content_bytes_percentiles,
+            content_bytes: session_storage.iter().map(|s| s.content_bytes).sum(),
+            session_storage,
+            tokenizer: tokenizer.name().to_string(),
+            total_tokens: session_tokens.values().sum(),
+            session_tokens,
         })
     }
 
-    /// Get performance metrics
-    pub fn get_performance_metrics(&self) -> crate::core::PerformanceMetrics {
-        self.monitor.get_metrics()
-    }
+    /// Storage, growth, compression, decay and topic figures for a user
+    pub fn get_user_analytics(&self, user_id: &str) -> Result<UserAnalytics> {
+        let start = Instant::now();
+        let _span = logging::span("get_user_analytics").with("user_id", user_id);
 
-    /// Reset performance monitoring
-    pub fn reset_performance_monitoring(&self) {
-        self.monitor.reset();
+        let analytics = self.database.get_user_analytics(user_id)?;
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            memories = analytics.memory_count,
+            storage_bytes = analytics.storage_bytes,
+            duration_ms = duration,
+            "Computed user analytics"
+        );
+        Ok(analytics)
     }
-}
 
-/// Memory update request
-#[derive(Debug, Clone)]
-pub struct MemoryUpdate {
-    pub content: Option<String>,
-    pub importance: Option<f32>,
-    pub metadata: Option<HashMap<String, String>>,
-    pub ttl_hours: Option<Option<u32>>, // None = no change, Some(None) = remove TTL, Some(Some(x)) = set TTL
-}
+    /// A page of the users with something stored, by user ID, with their
+    /// memory and session counts, storage and last activity
+    pub fn list_users(
+        &self,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<UserOverview>> {
+        let _span = logging::span("list_users");
 
-/// User memory statistics
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
-pub struct UserMemoryStats {
-    pub user_id: String,
-    pub total_memories: i64,
-    pub avg_importance: f32,
-    pub importance_distribution: HashMap<String, i32>,
-    pub age_distribution: HashMap<String, i32>,
-    pub oldest_memory: Option<DateTime<Utc>>,
-    pub newest_memory: Option<DateTime<Utc>>,
-}
+        // Rate limiting
+        self.validator.validate_request(1)?;
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::core::{MemexConfig, RequestValidator};
-    use crate::database::{Database, DatabaseConfig};
-    use tempfile::TempDir;
+        self.database.list_users(limit, offset)
+    }
 
-    fn setup_test_manager() -> (MemoryManager, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let db_config = DatabaseConfig {
-            path: temp_dir
-                .path()
-                .join("test.db")
-                .to_string_lossy()
-                .to_string(),
-            ..Default::default()
-        };
+    /// [`list_users`](Self::list_users) for one user; `None` if nothing is
+    /// stored about them
+    pub fn get_user_overview(&self, user_id: &str) -> Result<Option<UserOverview>> {
+        let _span = logging::span("get_user_overview").with("user_id", user_id);
 
-        let database = Database::new(db_config).unwrap();
-        let config = MemexConfig::default();
-        let validator = RequestValidator::new(&config);
-        let manager = MemoryManager::new(database, validator);
+        // Rate limiting
+        self.validator.validate_request_for(user_id, 1)?;
 
-        (manager, temp_dir)
+        self.database.get_user_overview(user_id)
     }
 
-    #[test]
-    fn test_save_and_recall_memory() {
-        let (manager, _temp_dir) = setup_test_manager();
+    /// Erase everything stored about a user, as a right-to-erasure request
+    /// needs: memories, sessions, summaries and the user record
+    ///
+    /// Middleware can't veto a purge, but hears of every memory deleted.
+    pub fn purge_user(&self, user_id: &str) -> Result<UserPurge> {
+        let _span = logging::span("purge_user").with("user_id", user_id);
 
-        let memory = MemoryItem {
-            user_id: "test_user".to_string(),
-            session_id: "test_session".to_string(),
-            content: "Test memory content".to_string(),
-            importance: 0.8,
-            ..Default::default()
-        };
+        // Rate limiting (deletion is expensive)
+        self.validator.validate_request_for(user_id, 5)?;
 
-        // Save memory
-        let memory_id = manager.save_memory(memory.clone()).unwrap();
-        assert!(!memory_id.is_empty());
+        let purge = self
+            .database
+            .purge_user(user_id)
+            .context("Failed to purge user")?;
 
-        // Recall memory
-        let filter = QueryFilter::for_user_with_keywords("test_user", vec!["Test".to_string()]);
-        let response = manager.recall_memories(filter).unwrap();
+        for id in &purge.memory_ids {
+            for middleware in self.middleware.iter().rev() {
+                middleware.after_delete(id);
+            }
+        }
+        tracing::info!(
+            memories = purge.memory_ids.len(),
+            sessions = purge.sessions,
+            summaries = purge.summaries,
+            "Purged user"
+        );
+        Ok(purge)
+    }
 
-        assert_eq!(response.data.len(), 1);
-        assert_eq!(response.data[0].content, "Test memory content");
-        assert_eq!(response.data[0].importance, 0.8);
+    /// Rename a metadata key and/or map its values across many memories,
+    /// in batches with progress; see [`Database::migrate_metadata`]
+    pub fn migrate_metadata(
+        &self,
+        migration: &MetadataMigration,
+        batch_size: usize,
+        on_progress: impl FnMut(&MetadataMigrationProgress),
+    ) -> Result<MetadataMigrationProgress> {
+        let _span = logging::span("migrate_metadata").with("from_key", &migration.from_key);
+
+        // Rate limiting (touches every memory with the key)
+        self.validator
+            .validate_request_for(migration.user_id.as_deref().unwrap_or(""), 5)?;
+
+        let progress = self
+            .database
+            .migrate_metadata(migration, batch_size, on_progress)
+            .context("Failed to migrate metadata")?;
+        tracing::info!(
+            from_key = migration.from_key.as_str(),
+            to_key = migration.to_key.as_deref(),
+            migrated = progress.migrated,
+            conflicts = progress.conflicts.len(),
+            dry_run = migration.dry_run,
+            "Migrated metadata"
+        );
+        Ok(progress)
     }
 
-    #[test]
-    fn test_batch_save_memories() {
-        let (manager, _temp_dir) = setup_test_manager();
+    /// Group a user's most recent memories into topics
+    ///
+    /// `k` fixes the number of clusters; None picks the count that separates
+    /// the memories best. Embeddings are used when vector search is enabled
+    /// and every memory has one from the same model, TF-IDF vectors of the
+    /// content otherwise.
+    pub fn cluster_memories(&self, user_id: &str, k: Option<usize>) -> Result<ClusterReport> {
+        let start = Instant::now();
+        let _span = logging::span("cluster_memories").with("user_id", user_id);
 
-        let memories = vec![
-            MemoryItem {
-                user_id: "test_user".to_string(),
-                session_id: "test_session".to_string(),
-                content: "Memory 1".to_string(),
-                ..Default::default()
-            },
-            MemoryItem {
-                user_id: "test_user".to_string(),
-                session_id: "test_session".to_string(),
-                content: "Memory 2".to_string(),
+        if k == Some(0) {
+            return Err(anyhow::anyhow!("Cluster count must be at least 1"));
+        }
+
+        let memories = self
+            .query_memories(QueryFilter {
+                user_id: Some(user_id.to_string()),
+                limit: Some(MAX_CLUSTER_MEMORIES),
                 ..Default::default()
-            },
-        ];
+            })?
+            .data;
 
-        let request = BatchRequest {
-            items: memories,
-            fail_on_error: false,
+        let (method, vectors) = match self.memory_embeddings(&memories)? {
+            Some(vectors) => (ClusteringMethod::Embeddings, vectors),
+            None => (
+                ClusteringMethod::TfIdf,
+                clustering::tfidf_vectors(&memories),
+            ),
         };
 
-        let response = manager.save_memories_batch(request).unwrap();
-        assert_eq!(response.success_count, 2);
-        assert_eq!(response.error_count, 0);
+        let memory_count = memories.len();
+        let clusters = clustering::cluster(memories, vectors, k);
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            method = %method,
+            memories = memory_count,
+            clusters = clusters.len(),
+            duration_ms = duration,
+            "Clustered memories"
+        );
+
+        Ok(ClusterReport {
+            user_id: user_id.to_string(),
+            method,
+            memory_count,
+            clusters,
+        })
     }
 
-    #[test]
-    fn test_search_memories() {
-        let (manager, _temp_dir) = setup_test_manager();
+    /// Memories of `user_id` that may contradict memory `memory_id`, confirmed
+    /// conflicts first, then by similarity; `None` if the user has no live
+    /// memory `memory_id`
+    ///
+    /// The user's [`MAX_CLUSTER_MEMORIES`] newest memories are compared by
+    /// embeddings when vector search is enabled and every one has an
+    /// embedding, by TF-IDF otherwise; see [`conflicts`] for what counts as
+    /// a conflict. Memories linked `contradicts` are always listed and ones
+    /// linked `compatible` never are.
+    pub fn find_conflicts(
+        &self,
+        user_id: &str,
+        memory_id: &str,
+    ) -> Result<Option<Vec<MemoryConflict>>> {
+        let start = Instant::now();
+        let _span = logging::span("find_conflicts")
+            .with("user_id", user_id)
+            .with("memory_id", memory_id);
 
-        // Save some test memories
-        let memories = vec![
-            MemoryItem {
-                user_id: "test_user".to_string(),
-                session_id: "session1".to_string(),
-                content: "Trading AAPL stocks today".to_string(),
+        let Some(memory) = self
+            .get_memory(memory_id)?
+            .filter(|memory| memory.user_id == user_id)
+        else {
+            return Ok(None);
+        };
+
+        let links: HashMap<String, LinkType> = self
+            .database
+            .get_memory_links(memory_id)?
+            .into_iter()
+            .map(|link| {
+                let other = if link.source_id == memory_id {
+                    link.target_id
+                } else {
+                    link.source_id
+                };
+                (other, link.link_type)
+            })
+            .collect();
+
+        let mut memories = self
+            .query_memories(QueryFilter {
+                user_id: Some(user_id.to_string()),
+                limit: Some(MAX_CLUSTER_MEMORIES),
                 ..Default::default()
-            },
+            })?
+            .data;
+        memories.retain(|other| other.id != memory_id);
+        // Confirmed conflicts older than the newest memories, or archived
+        let missing: Vec<String> = links
+            .iter()
+            .filter(|(id, link_type)| {
+                **link_type == LinkType::Contradicts
+                    && !memories.iter().any(|other| &other.id == *id)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        memories.extend(self.database.get_memories(&missing)?);
+        memories.insert(0, memory);
+
+        let vectors = match self.memory_embeddings(&memories)? {
+            Some(vectors) => vectors,
+            None => clustering::tfidf_vectors(&memories),
+        };
+
+        let mut found: Vec<MemoryConflict> = memories
+            .iter()
+            .zip(&vectors)
+            .skip(1)
+            .filter_map(|(other, vector)| {
+                let link_type = links.get(&other.id);
+                if link_type == Some(&LinkType::Compatible) {
+                    return None;
+                }
+                let similarity = conflicts::cosine_similarity(&vectors[0], vector);
+                let reasons = if similarity >= CONFLICT_SIMILARITY {
+                    conflicts::conflict_reasons(&memories[0], other)
+                } else {
+                    Vec::new()
+                };
+                let confirmed = link_type == Some(&LinkType::Contradicts);
+                (confirmed || !reasons.is_empty()).then(|| MemoryConflict {
+                    memory: other.clone(),
+                    similarity,
+                    reasons,
+                    confirmed,
+                })
+            })
+            .collect();
+        found.sort_by(|a, b| {
+            b.confirmed
+                .cmp(&a.confirmed)
+                .then(b.similarity.total_cmp(&a.similarity))
+        });
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            compared = memories.len() - 1,
+            conflicts = found.len(),
+            duration_ms = duration,
+            "Found conflicting memories"
+        );
+        Ok(Some(found))
+    }
+
+    /// Link memories `a` and `b` of the same user, replacing any other link
+    /// between them; false if either is missing or they were already linked
+    /// that way
+    ///
+    /// Use [`LinkType::Contradicts`] to confirm a conflict
+    /// [`find_conflicts`](Self::find_conflicts) flagged and
+    /// [`LinkType::Compatible`] to dismiss it.
+    pub fn link_memories(&self, a: &str, b: &str, link_type: LinkType) -> Result<bool> {
+        let _span = logging::span("link_memories")
+            .with("memory_id", a)
+            .with("other_id", b);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database.link_memories(a, b, link_type)
+    }
+
+    /// Remove the `link_type` link between memories `a` and `b`
+    pub fn unlink_memories(&self, a: &str, b: &str, link_type: LinkType) -> Result<bool> {
+        let _span = logging::span("unlink_memories")
+            .with("memory_id", a)
+            .with("other_id", b);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database.unlink_memories(a, b, link_type)
+    }
+
+    /// Links of memory `id` to other memories
+    pub fn get_memory_links(&self, id: &str) -> Result<Vec<MemoryLink>> {
+        self.database.get_memory_links(id)
+    }
+
+    /// Memories matching `filter` grouped by the day or week they were
+    /// created in, each bucket with its count and most important memories
+    ///
+    /// `filter`'s limit and offset are ignored; buckets cover the newest
+    /// [`MAX_TIMELINE_MEMORIES`] matches.
+    pub fn recall_timeline(&self, filter: QueryFilter, bucket: TimeBucket) -> Result<Timeline> {
+        let start = Instant::now();
+        let result = self.collect_timeline(filter, bucket);
+        self.counters.record(CounterEvent::Recall, &result);
+
+        if let Ok(timeline) = &result {
+            tracing::debug!(
+                bucket = %bucket,
+                buckets = timeline.buckets.len(),
+                memories = timeline.memory_count,
+                duration_ms = start.elapsed().as_millis(),
+                "Recalled timeline"
+            );
+        }
+        result
+    }
+
+    fn collect_timeline(&self, filter: QueryFilter, bucket: TimeBucket) -> Result<Timeline> {
+        const PAGE: usize = 1000;
+
+        let mut memories = Vec::new();
+        let mut truncated = false;
+        loop {
+            let page = QueryFilter {
+                limit: Some(PAGE),
+                offset: Some(memories.len()),
+                before_id: None,
+                ..filter.clone()
+            };
+            let response = self.query_memories(page)?;
+            memories.extend(response.data);
+            if !response.has_next {
+                break;
+            }
+            if memories.len() >= MAX_TIMELINE_MEMORIES {
+                truncated = true;
+                break;
+            }
+        }
+        memories.truncate(MAX_TIMELINE_MEMORIES);
+
+        Ok(timeline::group(memories, bucket, truncated))
+    }
+
+    /// Rising and falling keywords across the last `TREND_WINDOWS` windows
+    /// of length `window`
+    ///
+    /// Keywords are the tokenized words of each memory's content; a memory
+    /// counts once per keyword however often it repeats it.
+    pub fn keyword_trends(&self, user_id: &str, window: chrono::Duration) -> Result<KeywordTrends> {
+        let start = Instant::now();
+        let _span = logging::span("keyword_trends").with("user_id", user_id);
+
+        if window <= chrono::Duration::zero() {
+            return Err(anyhow::anyhow!("Trend window must be positive"));
+        }
+
+        let end = Utc::now();
+        let from = end - window * TREND_WINDOWS as i32;
+        let mut memories = Vec::new();
+        let mut offset = 0;
+        loop {
+            let filter = QueryFilter {
+                limit: Some(1000),
+                offset: Some(offset),
+                ..QueryFilter::date_range(user_id, from, end)
+            };
+            let response = self.query_memories(filter)?;
+            memories.extend(response.data);
+            if !response.has_next {
+                break;
+            }
+            offset += 1000;
+        }
+
+        let trends = trends::keyword_trends(user_id, &memories, end, window);
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            memories = memories.len(),
+            rising = trends.rising.len(),
+            falling = trends.falling.len(),
+            duration_ms = duration,
+            "Computed keyword trends"
+        );
+        Ok(trends)
+    }
+
+    /// One embedding per memory, in order, if all of them have one
+    #[cfg(feature = "vector-search")]
+    fn memory_embeddings(&self, memories: &[MemoryItem]) -> Result<Option<Vec<Vec<f32>>>> {
+        let (Some(engine), Some(first)) = (&self.vector, memories.first()) else {
+            return Ok(None);
+        };
+        let Some(mut user_embeddings) = engine.user_embeddings(&first.user_id)? else {
+            return Ok(None);
+        };
+
+        let vectors: Option<Vec<Vec<f32>>> = memories
+            .iter()
+            .map(|memory| user_embeddings.embeddings.remove(&memory.id))
+            .collect();
+        if vectors.is_none() {
+            tracing::debug!(
+                model = user_embeddings.model_name.as_str(),
+                "Not every memory has an embedding, clustering by TF-IDF"
+            );
+        }
+        Ok(vectors)
+    }
+
+    #[cfg(not(feature = "vector-search"))]
+    fn memory_embeddings(&self, _memories: &[MemoryItem]) -> Result<Option<Vec<Vec<f32>>>> {
+        Ok(None)
+    }
+
+    /// Get performance metrics
+    pub fn get_performance_metrics(&self) -> crate::core::PerformanceMetrics {
+        let hot_tier = self.database.hot_tier_stats();
+        crate::core::PerformanceMetrics {
+            cache_hit_rate: hot_tier.hit_rate(),
+            hot_tier: Some(hot_tier),
+            ..self.monitor.get_metrics()
+        }
+    }
+
+    /// Reset performance monitoring
+    pub fn reset_performance_monitoring(&self) {
+        self.monitor.reset();
+    }
+}
+
+/// Safeguards for `MemoryManager::delete_by_filter`
+#[derive(Debug, Clone, Default)]
+pub struct DeleteOptions {
+    /// Report the matching memories without deleting them
+    pub dry_run: bool,
+    /// Delete nothing if more memories than this match
+    pub max_affected: Option<usize>,
+}
+
+/// Result of `MemoryManager::delete_by_filter`
+#[derive(Debug, Clone, Default, serde::Serialize)]
+pub struct DeleteReport {
+    /// Deleted memories, or the ones that would be on a dry run
+    pub ids: Vec<String>,
+    pub dry_run: bool,
+}
+
+impl DeleteReport {
+    pub fn count(&self) -> usize {
+        self.ids.len()
+    }
+}
+
+/// Memory update request
+#[derive(Debug, Clone, Default, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryUpdate {
+    pub content: Option<String>,
+    /// Appended verbatim to the content (after `content`, if both are set)
+    pub content_append: Option<String>,
+    pub importance: Option<f32>,
+    pub confidence: Option<f32>,
+    pub metadata: Option<HashMap<String, String>>,
+    /// Per-key changes applied after `metadata`: `Some` sets the key, `None`
+    /// (`null` in JSON) removes it
+    pub metadata_patch: HashMap<String, Option<String>>,
+    #[serde(deserialize_with = "deserialize_ttl_update")]
+    pub ttl_hours: Option<Option<u32>>, // None = no change, Some(None) = remove TTL, Some(Some(x)) = set TTL
+    /// Hours added to the TTL and expiry (after `ttl_hours`); memories
+    /// without a TTL never expire and keep none
+    pub extend_ttl_hours: Option<u32>,
+    /// Fail with `VersionConflict` unless the memory is still at this
+    /// version
+    pub expected_version: Option<i64>,
+}
+
+impl MemoryUpdate {
+    /// Namespace the update moves the memory into, if it changes it
+    pub fn namespace(&self) -> Option<&str> {
+        match self.metadata_patch.get(NAMESPACE_METADATA_KEY) {
+            Some(Some(namespace)) => Some(namespace),
+            Some(None) => Some(DEFAULT_NAMESPACE),
+            None => self.metadata.as_ref().map(|metadata| {
+                metadata
+                    .get(NAMESPACE_METADATA_KEY)
+                    .map_or(DEFAULT_NAMESPACE, String::as_str)
+            }),
+        }
+    }
+
+    /// Apply the requested changes to `memory`
+    pub fn apply(&self, memory: &mut MemoryItem) {
+        if let Some(content) = &self.content {
+            memory.content = content.clone();
+        }
+        if let Some(suffix) = &self.content_append {
+            memory.content.push_str(suffix);
+        }
+
+        if let Some(importance) = self.importance {
+            memory.importance = importance.clamp(0.0, 1.0);
+        }
+        if let Some(confidence) = self.confidence {
+            memory.confidence = confidence.clamp(0.0, 1.0);
+        }
+
+        if let Some(metadata) = &self.metadata {
+            memory.metadata = metadata.clone();
+        }
+        for (key, value) in &self.metadata_patch {
+            match value {
+                Some(value) => {
+                    memory.metadata.insert(key.clone(), value.clone());
+                }
+                None => {
+                    memory.metadata.remove(key);
+                }
+            }
+        }
+
+        if let Some(ttl_hours) = self.ttl_hours {
+            memory.ttl_hours = ttl_hours;
+            // Counted from now, as when the memory was saved
+            memory.expires_at =
+                ttl_hours.map(|ttl| Utc::now() + chrono::Duration::hours(ttl as i64));
+        }
+        if let (Some(hours), Some(ttl)) = (self.extend_ttl_hours, memory.ttl_hours) {
+            let expires_at = memory
+                .expires_at
+                .unwrap_or_else(|| Utc::now() + chrono::Duration::hours(ttl as i64));
+            memory.ttl_hours = Some(ttl.saturating_add(hours));
+            memory.expires_at = Some(expires_at + chrono::Duration::hours(hours as i64));
+        }
+    }
+}
+
+/// Keep an explicit `null` TTL apart from a missing one
+fn deserialize_ttl_update<'de, D>(deserializer: D) -> Result<Option<Option<u32>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    <Option<u32> as serde::Deserialize>::deserialize(deserializer).map(Some)
+}
+
+/// Bytes past the storage quota `error` turned a save away for, if that
+/// was why
+fn over_byte_quota(error: &anyhow::Error) -> Option<u64> {
+    error.chain().find_map(|cause| match cause.downcast_ref() {
+        Some(ValidationError::ByteQuotaExceeded { requested, max }) => {
+            Some(requested.saturating_sub(*max))
+        }
+        _ => None,
+    })
+}
+
+fn compile_json_schema(schema: &serde_json::Value) -> Result<jsonschema::JSONSchema> {
+    jsonschema::JSONSchema::compile(schema).map_err(|e| {
+        ValidationError::InvalidInput {
+            message: format!("Invalid JSON Schema: {}", e),
+            field: Some("schema".to_string()),
+            limit: None,
+            actual: None,
+        }
+        .into()
+    })
+}
+
+/// Fill in the `{{name}}` placeholders of `text` from `vars`
+fn render_placeholders(text: &str, vars: &HashMap<String, String>) -> Result<String> {
+    let mut rendered = String::with_capacity(text.len());
+    for (i, part) in split_placeholders(text).into_iter().enumerate() {
+        if i % 2 == 0 {
+            rendered.push_str(part);
+            continue;
+        }
+        let value = vars
+            .get(part)
+            .ok_or_else(|| ValidationError::InvalidInput {
+                message: format!("Missing template variable: {}", part),
+                field: Some(part.to_string()),
+                limit: None,
+                actual: None,
+            })?;
+        rendered.push_str(value);
+    }
+    Ok(rendered)
+}
+
+/// User memory statistics
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct UserMemoryStats {
+    pub user_id: String,
+    pub total_memories: i64,
+    pub avg_importance: f32,
+    pub importance_distribution: HashMap<String, i32>,
+    pub age_distribution: HashMap<String, i32>,
+    /// Importance percentiles, with a histogram in steps of 0.1
+    #[serde(default)]
+    pub importance_percentiles: ValueDistribution,
+    /// Content size percentiles and histogram, in UTF-8 bytes
+    #[serde(default)]
+    pub content_bytes_percentiles: ValueDistribution,
+    pub oldest_memory: Option<DateTime<Utc>>,
+    pub newest_memory: Option<DateTime<Utc>>,
+    /// Content bytes stored, expired memories included until decay
+    /// removes them
+    pub content_bytes: u64,
+    /// Memories and content bytes per session, largest first
+    pub session_storage: Vec<StorageUsage>,
+    /// Name of the tokenizer that counted the tokens below
+    #[serde(default)]
+    pub tokenizer: String,
+    /// Content tokens of the user's live memories
+    #[serde(default)]
+    pub total_tokens: u64,
+    /// Content tokens per session
+    #[serde(default)]
+    pub session_tokens: HashMap<String, u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::conflicts::ConflictReason;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let config = MemexConfig::default();
+        let validator = RequestValidator::new(&config);
+        let manager = MemoryManager::new(database, validator);
+
+        (manager, temp_dir)
+    }
+
+    #[test]
+    fn test_prefixed_ulids_and_keyset_pagination() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            id_strategy: crate::database::ids::IdStrategy::PrefixedUlid,
+            ..Default::default()
+        })
+        .unwrap();
+        let manager = MemoryManager::new(database, RequestValidator::new(&MemexConfig::default()));
+
+        // Same timestamp and importance, so only the ID orders them
+        let created_at = Utc::now();
+        let mut ids: Vec<String> = (0..7)
+            .map(|i| {
+                manager
+                    .save_memory(MemoryItem {
+                        user_id: "pager".to_string(),
+                        session_id: "pages".to_string(),
+                        content: format!("Page item {}", i),
+                        created_at,
+                        ..Default::default()
+                    })
+                    .unwrap()
+            })
+            .collect();
+        assert!(ids.iter().all(|id| id.starts_with("mem_")));
+        ids.reverse();
+
+        let mut paged = Vec::new();
+        let mut before_id = None;
+        loop {
+            let page = manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("pager".to_string()),
+                    limit: Some(3),
+                    offset: None,
+                    before_id: before_id.take(),
+                    ..Default::default()
+                })
+                .unwrap();
+            if page.data.is_empty() {
+                break;
+            }
+            before_id = page.data.last().map(|memory| memory.id.clone());
+            paged.extend(page.data.into_iter().map(|memory| memory.id));
+        }
+        assert_eq!(paged, ids);
+    }
+
+    #[test]
+    fn test_save_and_recall_memory() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let memory = MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "test_session".to_string(),
+            content: "Test memory content".to_string(),
+            importance: 0.8,
+            ..Default::default()
+        };
+
+        // Save memory
+        let memory_id = manager.save_memory(memory.clone()).unwrap();
+        assert!(!memory_id.is_empty());
+
+        // Recall memory
+        let filter = QueryFilter::for_user_with_keywords("test_user", vec!["Test".to_string()]);
+        let response = manager.recall_memories(filter).unwrap();
+
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.data[0].content, "Test memory content");
+        assert_eq!(response.data[0].importance, 0.8);
+    }
+
+    #[test]
+    fn test_save_receipt() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let memory = MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "test_session".to_string(),
+            content: "Café notes".to_string(),
+            ..Default::default()
+        };
+
+        let first = manager.save_memory_with_receipt(memory.clone()).unwrap();
+        assert!(first.session_created);
+        assert!(!first.deduplicated);
+        assert_eq!(first.bytes_written, "Café notes".len());
+
+        let stored = manager.get_memory(&first.id).unwrap().unwrap();
+        assert_eq!(stored.created_at, first.created_at);
+        assert_eq!(stored.updated_at, first.written_at);
+
+        // Same session, new memory
+        let second = manager.save_memory_with_receipt(memory.clone()).unwrap();
+        assert!(!second.session_created);
+        assert!(!second.deduplicated);
+        assert_ne!(second.id, first.id);
+
+        // Re-saving an id replaces that memory
+        let resaved = manager
+            .save_memory_with_receipt(MemoryItem {
+                id: first.id.clone(),
+                content: "Updated".to_string(),
+                ..memory
+            })
+            .unwrap();
+        assert_eq!(resaved.id, first.id);
+        assert!(resaved.deduplicated);
+        assert_eq!(resaved.bytes_written, 7);
+        assert_eq!(manager.export_user_memories("test_user").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_batch_save_memories() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let memories = vec![
             MemoryItem {
                 user_id: "test_user".to_string(),
-                session_id: "session1".to_string(),
-                content: "Bitcoin price analysis".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Memory 1".to_string(),
+                ..Default::default()
+            },
+            MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Memory 2".to_string(),
                 ..Default::default()
             },
         ];
 
-        for memory in memories {
-            manager.save_memory(memory).unwrap();
+        let request = BatchRequest {
+            items: memories,
+            fail_on_error: false,
+            quota_policy: Default::default(),
+        };
+
+        let response = manager.save_memories_batch(request).unwrap();
+        assert_eq!(response.success_count, 2);
+        assert_eq!(response.error_count, 0);
+    }
+
+    #[test]
+    fn test_import_memories() {
+        use crate::core::interop::{self, ImportDefaults, ImportFormat};
+
+        let (manager, _temp_dir) = setup_test_manager();
+        let json = r#"[
+            {"id": "m1", "memory": "Prefers window seats", "user_id": "alice", "run_id": "trip"},
+            {"id": "m2", "memory": "", "user_id": "alice"},
+            {"id": "m3", "memory": "Allergic to peanuts", "embedding": [0.1, 0.2]}
+        ]"#;
+        let defaults = ImportDefaults {
+            user_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let records = interop::parse(ImportFormat::Mem0, json, &defaults).unwrap();
+
+        let report = manager.import_memories(records, "mem0").unwrap();
+        assert_eq!(report.records, 3);
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert!(
+            report.errors[0].starts_with("Record 2:"),
+            "{:?}",
+            report.errors
+        );
+        // No vector engine, so the embedding can't be kept
+        assert_eq!(report.embeddings_stored, 0);
+        assert_eq!(report.embeddings_skipped, 1);
+
+        let memories = manager.export_user_memories("alice").unwrap();
+        assert_eq!(memories.len(), 2);
+        let trip = memories.iter().find(|m| m.session_id == "trip").unwrap();
+        assert_eq!(trip.metadata["import_id"], "m1");
+        assert_eq!(trip.metadata["import_source"], "mem0");
+    }
+
+    #[test]
+    fn test_search_memories() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        // Save some test memories
+        let memories = vec![
+            MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "session1".to_string(),
+                content: "Trading AAPL stocks today".to_string(),
+                ..Default::default()
+            },
+            MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "session1".to_string(),
+                content: "Bitcoin price analysis".to_string(),
+                ..Default::default()
+            },
+        ];
+
+        for memory in memories {
+            manager.save_memory(memory).unwrap();
+        }
+
+        // Search for "AAPL"
+        let response = manager
+            .search_memories("test_user", "AAPL", Some(10), Some(0))
+            .unwrap();
+        assert_eq!(response.data.len(), 1);
+        assert!(response.data[0].content.contains("AAPL"));
+
+        // Search for "price"
+        let response = manager
+            .search_memories("test_user", "price", Some(10), Some(0))
+            .unwrap();
+        assert_eq!(response.data.len(), 1);
+        assert!(response.data[0].content.contains("Bitcoin"));
+    }
+
+    #[test]
+    fn test_fuzzy_recall() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            trigram_index: false,
+            ..Default::default()
+        };
+        let open = |config: DatabaseConfig| {
+            MemoryManager::new(
+                Database::new(config).unwrap(),
+                RequestValidator::new(&MemexConfig::default()),
+            )
+        };
+        let recall = |manager: &MemoryManager, keywords: &[&str], fuzzy: bool| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    keywords: Some(keywords.iter().map(|k| k.to_string()).collect()),
+                    fuzzy,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        // Without the trigram index, candidates are found with LIKE
+        let manager = open(config.clone());
+        for content in ["Keep the receipt for taxes", "Tuned the database indexes"] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert_eq!(recall(&manager, &["reciept"], false).total_count, 0);
+        let found = recall(&manager, &["reciept"], true);
+        assert_eq!(found.total_count, 1);
+        assert!(found.data[0].content.contains("receipt"));
+        drop(manager);
+
+        // Reopening with the index builds it for the existing memories
+        let manager = open(DatabaseConfig {
+            trigram_index: true,
+            ..config
+        });
+        let found = recall(&manager, &["databse"], true);
+        assert_eq!(found.total_count, 1);
+        let score = found.data[0].match_score.unwrap();
+        assert!(score > 0.0 && score < 1.0, "{}", score);
+
+        // Exact hits win, and unrelated words match nothing
+        let exact = recall(&manager, &["database"], true);
+        assert_eq!(exact.data[0].match_score, Some(1.0));
+        assert_eq!(recall(&manager, &["walking"], true).total_count, 0);
+        assert_eq!(recall(&manager, &["db"], true).total_count, 0);
+    }
+
+    #[test]
+    fn test_fuzzy_recall_after_resave() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let mut memory = MemoryItem {
+            id: "resaved".to_string(),
+            user_id: "test_user".to_string(),
+            session_id: "session1".to_string(),
+            content: "Tuned the database indexes".to_string(),
+            ..Default::default()
+        };
+        manager.save_memory(memory.clone()).unwrap();
+        memory.content = "Kept the receipt for taxes".to_string();
+        manager.save_memory(memory).unwrap();
+
+        let found = manager
+            .recall_memories(QueryFilter {
+                user_id: Some("test_user".to_string()),
+                keywords: Some(vec!["reciept".to_string()]),
+                fuzzy: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(found.total_count, 1);
+
+        // Fuzzy recall re-scores its candidates, so look at the index itself
+        // for rows the old content left behind
+        manager
+            .database
+            .get_connection_pool()
+            .with_write_transaction(|tx| {
+                let stale: i64 = tx.query_row(
+                    "SELECT COUNT(*) FROM memories_trigram WHERE memories_trigram MATCH 'database'",
+                    [],
+                    |row| row.get(0),
+                )?;
+                assert_eq!(stale, 0);
+                let indexed: i64 =
+                    tx.query_row("SELECT COUNT(*) FROM memories_trigram_docsize", [], |row| {
+                        row.get(0)
+                    })?;
+                assert_eq!(indexed, 1);
+                tx.execute_batch(
+                    "INSERT INTO memories_trigram(memories_trigram, rank)
+                     VALUES ('integrity-check', 1)",
+                )?;
+                Ok(())
+            })
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recall_explains_ranking() {
+        let (manager, _temp_dir) = setup_test_manager();
+        for (content, importance) in [
+            ("Coffee with oat milk", 0.4),
+            ("Coffee, coffee and more coffee", 0.9),
+            ("Tea in the afternoon", 0.7),
+        ] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "test_session".to_string(),
+                    content: content.to_string(),
+                    importance,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let recall = |keywords: Option<&str>, fuzzy, offset| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    keywords: keywords.map(|keyword| vec![keyword.to_string()]),
+                    fuzzy,
+                    offset: Some(offset),
+                    limit: Some(2),
+                    explain: true,
+                    ..Default::default()
+                })
+                .unwrap()
+                .data
+        };
+
+        let second_page = recall(None, false, 2);
+        let explanation = second_page[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.position, 3);
+        assert_eq!(explanation.ranked_by, RankedBy::Recency);
+        assert_eq!(explanation.text_relevance, None);
+        assert!(explanation.age_hours >= 0.0);
+
+        let coffee = recall(Some("coffee"), false, 0);
+        assert_eq!(coffee.len(), 2);
+        let relevance = |memory: &MemoryItem| {
+            let explanation = memory.explanation.as_ref().unwrap();
+            explanation.text_relevance.unwrap()
+        };
+        let (plain, repeated) = if coffee[0].importance > 0.5 {
+            (&coffee[1], &coffee[0])
+        } else {
+            (&coffee[0], &coffee[1])
+        };
+        assert!(relevance(repeated) > relevance(plain));
+
+        let fuzzy = recall(Some("coffe"), true, 0);
+        let explanation = fuzzy[0].explanation.as_ref().unwrap();
+        assert_eq!(explanation.ranked_by, RankedBy::FuzzySimilarity);
+        assert!(explanation.fuzzy_similarity.unwrap() > fuzzy[0].match_score.unwrap());
+
+        let unexplained = manager
+            .search_memories("test_user", "tea", None, None)
+            .unwrap();
+        assert!(unexplained.data[0].explanation.is_none());
+        let explained = manager
+            .search_memories_explained("test_user", "tea", None, None)
+            .unwrap();
+        assert!(explained.data[0].explanation.is_some());
+    }
+
+    #[test]
+    fn test_stemming_tokenizer() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        let open = |config: DatabaseConfig| {
+            MemoryManager::new(
+                Database::new(config).unwrap(),
+                RequestValidator::new(&MemexConfig::default()),
+            )
+        };
+        let search = |manager: &MemoryManager| {
+            manager
+                .search_memories("test_user", "trading", Some(10), Some(0))
+                .unwrap()
+                .total_count
+        };
+
+        let manager = open(config.clone());
+        for content in ["Traded the open", "Two trades today", "Trade plan"] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert_eq!(search(&manager), 0);
+        drop(manager);
+
+        // Reopening with another tokenizer re-indexes existing memories
+        let manager = open(DatabaseConfig {
+            fts_tokenizer: crate::database::schema::FtsTokenizer::Porter,
+            ..config
+        });
+        assert_eq!(search(&manager), 3);
+    }
+
+    #[test]
+    fn test_synonym_expansion() {
+        let (manager, _temp_dir) = setup_test_manager();
+        for (content, namespace) in [
+            ("Bought more Apple shares", "trading"),
+            ("Apple pie recipe", "cooking"),
+        ] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    metadata: HashMap::from([(
+                        NAMESPACE_METADATA_KEY.to_string(),
+                        namespace.to_string(),
+                    )]),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let recall = |namespace: &str, keyword: &str| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    keywords: Some(vec![keyword.to_string()]),
+                    metadata: HashMap::from([(
+                        NAMESPACE_METADATA_KEY.to_string(),
+                        namespace.to_string(),
+                    )]),
+                    ..Default::default()
+                })
+                .unwrap()
+                .total_count
+        };
+
+        assert_eq!(recall("trading", "AAPL"), 0);
+        assert!(manager.add_synonym("trading", "AAPL", "Apple").unwrap());
+        // Pairs work both ways, so the reverse is a duplicate
+        assert!(!manager.add_synonym("trading", "apple", "aapl").unwrap());
+        assert!(manager.add_synonym("trading", "AAPL", "aapl").is_err());
+        assert!(manager.add_synonym("trading", "", "Apple").is_err());
+
+        assert_eq!(recall("trading", "aapl"), 1);
+        assert_eq!(
+            manager
+                .search_memories("test_user", "AAPL", Some(10), Some(0))
+                .unwrap()
+                .total_count,
+            0,
+            "unscoped searches use the default namespace"
+        );
+
+        manager.add_synonym("cooking", "dessert", "pie").unwrap();
+        assert_eq!(recall("cooking", "dessert"), 1);
+        assert_eq!(recall("trading", "dessert"), 0);
+        assert_eq!(manager.list_synonyms(None).unwrap().len(), 2);
+        assert_eq!(
+            manager.list_synonyms(Some("trading")).unwrap()[0].term,
+            "AAPL"
+        );
+
+        assert!(manager.remove_synonym("trading", "Apple", "AAPL").unwrap());
+        assert!(!manager.remove_synonym("trading", "Apple", "AAPL").unwrap());
+        assert_eq!(recall("trading", "AAPL"), 0);
+    }
+
+    #[test]
+    fn test_read_only_database() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+        {
+            let manager = MemoryManager::new(
+                Database::new(config.clone()).unwrap(),
+                RequestValidator::new(&MemexConfig::default()),
+            );
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "session1".to_string(),
+                    content: "Quarterly revenue report".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let database = Database::new(DatabaseConfig {
+            read_only: true,
+            ..config
+        })
+        .unwrap();
+        let manager = MemoryManager::new(database, RequestValidator::new(&MemexConfig::default()));
+
+        let recalled = manager
+            .recall_memories(QueryFilter {
+                user_id: Some("test_user".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(recalled.data.len(), 1);
+        let found = manager
+            .search_memories("test_user", "revenue", Some(10), Some(0))
+            .unwrap();
+        assert_eq!(found.data.len(), 1);
+
+        let error = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "session1".to_string(),
+                content: "Should not be written".to_string(),
+                ..Default::default()
+            })
+            .unwrap_err();
+        assert!(error
+            .chain()
+            .any(|cause| cause.is::<crate::database::pool::ReadOnlyDatabase>()));
+        assert!(manager.database.vacuum().is_err());
+    }
+
+    #[test]
+    fn test_get_memories_preserves_order() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        // Saved straight to the database to stay clear of the rate limit
+        let ids: Vec<String> = (0..600)
+            .map(|i| {
+                manager
+                    .database
+                    .save_memory(&MemoryItem {
+                        user_id: "test_user".to_string(),
+                        session_id: "session1".to_string(),
+                        content: format!("Memory {}", i),
+                        ..Default::default()
+                    })
+                    .unwrap()
+            })
+            .collect();
+
+        // Spans several chunks, reversed, with an unknown ID mixed in
+        let mut requested: Vec<String> = ids.iter().rev().cloned().collect();
+        requested.insert(3, "missing".to_string());
+        let memories = manager.get_memories(&requested).unwrap();
+
+        assert_eq!(memories.len(), 600);
+        assert_eq!(memories[0].content, "Memory 599");
+        assert_eq!(memories[3].content, "Memory 596");
+        assert_eq!(memories[599].content, "Memory 0");
+        assert!(manager.get_memories(&[]).unwrap().is_empty());
+
+        let too_many = vec![ids[0].clone(); MAX_GET_MEMORIES + 1];
+        assert!(manager.get_memories(&too_many).is_err());
+    }
+
+    #[test]
+    fn test_search_grouped_by_session() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let sessions = [("trading", 3), ("research", 2), ("cooking", 0)];
+        for (session, matches) in sessions {
+            for i in 0..4 {
+                let content = if i < matches {
+                    format!("Bitcoin note {} from {}", i, session)
+                } else {
+                    format!("Unrelated note {}", i)
+                };
+                manager
+                    .save_memory(MemoryItem {
+                        user_id: "test_user".to_string(),
+                        session_id: session.to_string(),
+                        content,
+                        ..Default::default()
+                    })
+                    .unwrap();
+            }
+        }
+        // Another user's matches stay out
+        manager
+            .save_memory(MemoryItem {
+                user_id: "other_user".to_string(),
+                session_id: "other".to_string(),
+                content: "Bitcoin elsewhere".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let groups = manager
+            .search_grouped_by_session("test_user", "bitcoin", 2)
+            .unwrap();
+        let summary: Vec<_> = groups
+            .iter()
+            .map(|group| {
+                (
+                    group.session.id.as_str(),
+                    group.match_count,
+                    group.memories.len(),
+                )
+            })
+            .collect();
+        assert_eq!(summary, vec![("trading", 3, 2), ("research", 2, 2)]);
+        assert_eq!(groups[0].session.memory_count, 4);
+        assert!(groups[0]
+            .memories
+            .iter()
+            .all(|memory| memory.session_id == "trading"));
+
+        assert!(manager
+            .search_grouped_by_session("test_user", "  ", 2)
+            .unwrap()
+            .is_empty());
+        assert!(manager
+            .search_grouped_by_session("test_user", "bitcoin", 0)
+            .is_err());
+    }
+
+    #[test]
+    fn test_save_from_template() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        manager
+            .set_template(MemoryTemplate {
+                id: "trade_executed".to_string(),
+                content: "Bought {{ qty }} {{ticker}} at {{price}} ({{not a var}})".to_string(),
+                metadata: HashMap::from([
+                    ("category".to_string(), "trading".to_string()),
+                    ("ticker".to_string(), "{{ticker}}".to_string()),
+                ]),
+                importance: 0.8,
+                ttl_hours: Some(48),
+            })
+            .unwrap();
+        assert_eq!(
+            manager.list_templates().unwrap()[0].placeholders(),
+            ["price", "qty", "ticker"]
+        );
+
+        let vars = HashMap::from([
+            ("qty".to_string(), "10".to_string()),
+            ("ticker".to_string(), "AAPL".to_string()),
+            ("price".to_string(), "$190".to_string()),
+            ("unused".to_string(), "ignored".to_string()),
+        ]);
+        let id = manager
+            .save_from_template("trade_executed", "trader", "journal", &vars)
+            .unwrap();
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!(memory.content, "Bought 10 AAPL at $190 ({{not a var}})");
+        assert_eq!(memory.metadata["ticker"], "AAPL");
+        assert_eq!(memory.metadata["category"], "trading");
+        assert_eq!(memory.importance, 0.8);
+        assert!(memory.expires_at.is_some());
+
+        let mut missing = vars.clone();
+        missing.remove("price");
+        let err = manager
+            .save_from_template("trade_executed", "trader", "journal", &missing)
+            .unwrap_err();
+        assert!(err.to_string().contains("price"), "{}", err);
+        assert!(manager
+            .save_from_template("unknown", "trader", "journal", &vars)
+            .is_err());
+
+        assert!(manager.delete_template("trade_executed").unwrap());
+        assert!(!manager.delete_template("trade_executed").unwrap());
+    }
+
+    #[test]
+    fn test_json_memories_with_schema() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let json_memory = |content: &str, namespace: Option<&str>| MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "trades".to_string(),
+            content: content.to_string(),
+            content_type: ContentType::Json,
+            metadata: namespace
+                .map(|namespace| {
+                    HashMap::from([(NAMESPACE_METADATA_KEY.to_string(), namespace.to_string())])
+                })
+                .unwrap_or_default(),
+            ..Default::default()
+        };
+
+        assert!(manager
+            .register_json_schema("trading", serde_json::json!({"type": "nope"}))
+            .is_err());
+        manager
+            .register_json_schema(
+                "trading",
+                serde_json::json!({
+                    "type": "object",
+                    "required": ["ticker", "qty"],
+                    "properties": {
+                        "ticker": {"type": "string"},
+                        "qty": {"type": "integer", "minimum": 1}
+                    }
+                }),
+            )
+            .unwrap();
+
+        manager
+            .save_memory(json_memory(
+                r#"{"ticker": "AAPL", "qty": 10, "filled": true}"#,
+                Some("trading"),
+            ))
+            .unwrap();
+        manager
+            .save_memory(json_memory(
+                r#"{"ticker": "MSFT", "qty": 5}"#,
+                Some("trading"),
+            ))
+            .unwrap();
+        let err = manager
+            .save_memory(json_memory(
+                r#"{"ticker": "AAPL", "qty": 0}"#,
+                Some("trading"),
+            ))
+            .unwrap_err();
+        assert!(err.to_string().contains("/qty"), "{}", err);
+        assert!(manager.save_memory(json_memory("not json", None)).is_err());
+        // Other namespaces only need well-formed JSON
+        manager
+            .save_memory(json_memory(r#"{"ticker": "AAPL"}"#, None))
+            .unwrap();
+        manager
+            .save_memory(json_memory(
+                r#"{"ticker": "TSLA", "tags": ["swing", "tech"], "venue": {"lot": 100, "name": "NYSE"}}"#,
+                None,
+            ))
+            .unwrap();
+        // Plain text is never parsed
+        manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "trades".to_string(),
+                content: "{ticker: AAPL".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let recall = |filter: QueryFilter| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    ..filter
+                })
+                .unwrap()
+                .data
+        };
+        let found = recall(QueryFilter {
+            json_fields: HashMap::from([("ticker".to_string(), serde_json::json!("AAPL"))]),
+            ..Default::default()
+        });
+        assert_eq!(found.len(), 2);
+        // Arrays and objects compare as JSON, whatever their spacing
+        let json_field = |path: &str, value: serde_json::Value| {
+            recall(QueryFilter {
+                json_fields: HashMap::from([(path.to_string(), value)]),
+                ..Default::default()
+            })
+        };
+        let found = json_field("tags", serde_json::json!(["swing", "tech"]));
+        assert_eq!(found.len(), 1);
+        assert!(found[0].content.contains("TSLA"));
+        assert_eq!(
+            json_field("venue", serde_json::json!({"lot": 100, "name": "NYSE"})).len(),
+            1
+        );
+        assert!(json_field("tags", serde_json::json!(["tech"])).is_empty());
+        let found = recall(QueryFilter {
+            json_fields: HashMap::from([
+                ("$.qty".to_string(), serde_json::json!(10)),
+                ("filled".to_string(), serde_json::json!(true)),
+            ]),
+            metadata: HashMap::from([("namespace".to_string(), "trading".to_string())]),
+            ..Default::default()
+        });
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].content_type, ContentType::Json);
+        assert_eq!(
+            recall(QueryFilter {
+                content_type: Some(ContentType::Text),
+                ..Default::default()
+            })
+            .len(),
+            1
+        );
+
+        // Updates are checked too
+        let id = found[0].id.clone();
+        assert!(manager
+            .update_memory(
+                &id,
+                MemoryUpdate {
+                    content: Some(r#"{"ticker": 7}"#.to_string()),
+                    ..Default::default()
+                },
+            )
+            .is_err());
+        assert!(manager.remove_json_schema("trading").unwrap());
+        assert!(manager.list_json_schemas().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_memory_update() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let memory = MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "test_session".to_string(),
+            content: "Original content".to_string(),
+            importance: 0.5,
+            ..Default::default()
+        };
+
+        let memory_id = manager.save_memory(memory).unwrap();
+
+        // Update memory
+        let update = MemoryUpdate {
+            content: Some("Updated content".to_string()),
+            importance: Some(0.9),
+            ..Default::default()
+        };
+
+        let updated = manager.update_memory(&memory_id, update).unwrap();
+        assert!(updated);
+
+        // Verify update
+        let retrieved = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(retrieved.content, "Updated content");
+        assert_eq!(retrieved.importance, 0.9);
+    }
+
+    #[test]
+    fn test_drafts_and_archived_memories_stay_out_of_recall() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let draft_id = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Candidate fact".to_string(),
+                state: MemoryState::Draft,
+                ..Default::default()
+            })
+            .unwrap();
+        let recall = |states: Vec<MemoryState>| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    keywords: Some(vec!["candidate".to_string()]),
+                    states,
+                    ..Default::default()
+                })
+                .unwrap()
+                .data
+        };
+
+        assert!(recall(vec![]).is_empty());
+        assert_eq!(recall(vec![MemoryState::Draft]).len(), 1);
+        assert_eq!(
+            manager.get_memory(&draft_id).unwrap().unwrap().state,
+            MemoryState::Draft
+        );
+
+        assert!(manager.confirm_memory(&draft_id).unwrap());
+        assert_eq!(recall(vec![]).len(), 1);
+
+        assert!(manager.archive_memory(&draft_id).unwrap());
+        assert!(recall(vec![]).is_empty());
+        assert_eq!(recall(MemoryState::ALL.to_vec()).len(), 1);
+
+        let error = manager
+            .set_memory_state(&draft_id, MemoryState::Draft)
+            .unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("archived and can't become draft"));
+        assert!(!manager.confirm_memory("missing").unwrap());
+    }
+
+    #[test]
+    fn test_confidence_is_validated_filtered_and_updated() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |content: &str, confidence: f32| {
+            manager.save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: content.to_string(),
+                confidence,
+                ..Default::default()
+            })
+        };
+
+        let rumor = save("Heard the office might move", 0.3).unwrap();
+        save("The office is on Main Street", 0.95).unwrap();
+        assert!(save("Impossible certainty", 1.5).is_err());
+
+        let recall = |min_confidence, max_confidence| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    min_confidence,
+                    max_confidence,
+                    ..Default::default()
+                })
+                .unwrap()
+                .data
+        };
+        assert_eq!(recall(Some(0.9), None).len(), 1);
+        let shaky = recall(None, Some(0.5));
+        assert_eq!(shaky.len(), 1);
+        assert_eq!(shaky[0].id, rumor);
+
+        assert!(manager
+            .update_memory(
+                &rumor,
+                MemoryUpdate {
+                    confidence: Some(0.9),
+                    ..Default::default()
+                },
+            )
+            .unwrap());
+        assert_eq!(manager.get_memory(&rumor).unwrap().unwrap().confidence, 0.9);
+        assert_eq!(recall(Some(0.9), None).len(), 2);
+    }
+
+    #[test]
+    fn test_hot_tier_serves_lookups_and_writes_through() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            hot_tier_bytes: 1 << 20,
+            ..Default::default()
+        })
+        .unwrap();
+        let manager = MemoryManager::new(database, RequestValidator::new(&MemexConfig::default()));
+        let id = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Prefers window seats".to_string(),
+                importance: 0.9,
+                ..Default::default()
+            })
+            .unwrap();
+
+        // Recall warms the tier, so the lookup after it is a hit
+        manager
+            .recall_memories(QueryFilter {
+                user_id: Some("test_user".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert!(manager.get_memory(&id).unwrap().is_some());
+        let metrics = manager.get_performance_metrics();
+        let tier = metrics.hot_tier.unwrap();
+        assert_eq!((tier.hits, tier.misses, tier.entries), (1, 0, 1));
+        assert_eq!(metrics.cache_hit_rate, 1.0);
+
+        manager
+            .update_memory(
+                &id,
+                MemoryUpdate {
+                    content: Some("Prefers aisle seats".to_string()),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!(memory.content, "Prefers aisle seats");
+        assert_eq!(memory.version, 2);
+        assert_eq!(manager.get_performance_metrics().hot_tier.unwrap().hits, 2);
+
+        manager.delete_memory(&id).unwrap();
+        assert!(manager.get_memory(&id).unwrap().is_none());
+        assert!(manager.get_memories(&[id]).unwrap().is_empty());
+        let tier = manager.get_performance_metrics().hot_tier.unwrap();
+        assert_eq!((tier.misses, tier.entries), (2, 0));
+    }
+
+    #[test]
+    fn test_provenance_is_stored_and_filtered() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |content: &str, source_type, source_ref: Option<&str>, ingested_by| {
+            manager.save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: content.to_string(),
+                source_type,
+                source_ref: source_ref.map(str::to_string),
+                ingested_by,
+                ..Default::default()
+            })
+        };
+
+        let stated = save(
+            "Prefers aisle seats",
+            Some(SourceType::User),
+            Some("msg_42"),
+            Some("chat-agent".to_string()),
+        )
+        .unwrap();
+        save(
+            "Flight AB123 departs at 9:40",
+            Some(SourceType::Tool),
+            Some("https://flights.example/AB123"),
+            Some("flight-lookup".to_string()),
+        )
+        .unwrap();
+        save("Saved before provenance", None, None, None).unwrap();
+        assert!(save("Blank reference", None, Some(""), None).is_err());
+
+        let memory = manager.get_memory(&stated).unwrap().unwrap();
+        assert_eq!(memory.source_type, Some(SourceType::User));
+        assert_eq!(memory.source_ref.as_deref(), Some("msg_42"));
+        assert_eq!(memory.ingested_by.as_deref(), Some("chat-agent"));
+
+        let recall = |filter: QueryFilter| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    ..filter
+                })
+                .unwrap()
+                .data
+        };
+        let from_users = recall(QueryFilter {
+            source_types: vec![SourceType::User],
+            ..Default::default()
+        });
+        assert_eq!(from_users.len(), 1);
+        assert_eq!(from_users[0].id, stated);
+        assert_eq!(
+            recall(QueryFilter {
+                source_types: vec![SourceType::User, SourceType::Tool],
+                ..Default::default()
+            })
+            .len(),
+            2
+        );
+        assert_eq!(
+            recall(QueryFilter {
+                ingested_by: Some("flight-lookup".to_string()),
+                ..Default::default()
+            })[0]
+                .content,
+            "Flight AB123 departs at 9:40"
+        );
+        assert_eq!(
+            recall(QueryFilter {
+                source_ref: Some("msg_42".to_string()),
+                ..Default::default()
+            })
+            .len(),
+            1
+        );
+        assert_eq!(recall(QueryFilter::default()).len(), 3);
+    }
+
+    #[test]
+    fn test_exclusions_leave_out_matching_memories() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |session: &str, content: &str, tags: Option<&str>| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session.to_string(),
+                    content: content.to_string(),
+                    metadata: tags
+                        .map(|tags| HashMap::from([("tags".to_string(), tags.to_string())]))
+                        .unwrap_or_default(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let likes_tea = save("chat", "Likes green tea", Some("preference"));
+        save("chat", "Debug: cache warmed in 3ms", Some("system, debug"));
+        save("chat", "Retry budget exhausted", None);
+        save("health-check", "Ping from the monitor", None);
+
+        let recall = |filter: QueryFilter| {
+            let mut contents: Vec<String> = manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    ..filter
+                })
+                .unwrap()
+                .data
+                .into_iter()
+                .map(|memory| memory.content)
+                .collect();
+            contents.sort();
+            contents
+        };
+        assert_eq!(
+            recall(QueryFilter {
+                exclude_keywords: vec!["debug".to_string(), "retry".to_string()],
+                ..Default::default()
+            }),
+            ["Likes green tea", "Ping from the monitor"]
+        );
+        assert_eq!(
+            recall(QueryFilter {
+                exclude_session_ids: vec!["health-check".to_string()],
+                ..Default::default()
+            })
+            .len(),
+            3
+        );
+        // Untagged memories are kept, and tags match whole
+        assert_eq!(
+            recall(QueryFilter {
+                exclude_tags: vec!["debug".to_string(), "pref".to_string()],
+                ..Default::default()
+            }),
+            [
+                "Likes green tea",
+                "Ping from the monitor",
+                "Retry budget exhausted"
+            ]
+        );
+
+        let kept = manager
+            .recall_memories(QueryFilter {
+                keywords: Some(vec!["tea".to_string(), "cache".to_string()]),
+                exclude_tags: vec!["system".to_string()],
+                ..Default::default()
+            })
+            .unwrap()
+            .data;
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].id, likes_tea);
+    }
+
+    #[test]
+    fn test_content_length_and_type_filters() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |content: &str, content_type| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "test_session".to_string(),
+                    content: content.to_string(),
+                    content_type,
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        save("ok", ContentType::Text);
+        let note = save("Prefers window seats on long flights", ContentType::Text);
+        save(&"log line\n".repeat(200), ContentType::Text);
+        let order = save(r#"{"ticker": "AAPL", "side": "buy"}"#, ContentType::Json);
+
+        let recall = |filter: QueryFilter| {
+            let mut ids: Vec<String> = manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    ..filter
+                })
+                .unwrap()
+                .data
+                .into_iter()
+                .map(|memory| memory.id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        let mut expected = vec![note.clone(), order.clone()];
+        expected.sort();
+        assert_eq!(
+            recall(QueryFilter {
+                min_content_length: Some(10),
+                max_content_length: Some(1000),
+                ..Default::default()
+            }),
+            expected
+        );
+        assert_eq!(
+            recall(QueryFilter {
+                min_content_length: Some(10),
+                max_content_length: Some(1000),
+                content_type: Some(ContentType::Text),
+                ..Default::default()
+            }),
+            [note]
+        );
+        // Bounds are inclusive
+        assert_eq!(
+            recall(QueryFilter {
+                max_content_length: Some(2),
+                ..Default::default()
+            })
+            .len(),
+            1
+        );
+        assert_eq!(
+            recall(QueryFilter {
+                max_content_length: Some(usize::MAX),
+                ..Default::default()
+            })
+            .len(),
+            4
+        );
+        assert!(manager
+            .recall_memories(QueryFilter {
+                max_content_length: Some(0),
+                ..Default::default()
+            })
+            .is_err());
+        assert!(manager
+            .recall_memories(QueryFilter {
+                min_content_length: Some(100),
+                max_content_length: Some(10),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[test]
+    fn test_migrate_metadata_in_batches() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |user: &str, metadata: &[(&str, &str)]| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: user.to_string(),
+                    session_id: format!("{}_session", user),
+                    content: "Portfolio note".to_string(),
+                    metadata: metadata
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let stocks = save("alice", &[("category", "stocks")]);
+        let crypto = save("alice", &[("category", "crypto")]);
+        let clash = save("alice", &[("category", "stocks"), ("topic", "bonds")]);
+        let other_user = save("bob", &[("category", "stocks")]);
+        save("alice", &[]);
+
+        let migration = MetadataMigration {
+            user_id: Some("alice".to_string()),
+            from_key: "category".to_string(),
+            to_key: Some("topic".to_string()),
+            value_map: HashMap::from([("stocks".to_string(), "equities".to_string())]),
+            dry_run: true,
+        };
+        let dry_run = manager.migrate_metadata(&migration, 2, |_| {}).unwrap();
+        assert_eq!((dry_run.scanned, dry_run.migrated), (3, 2));
+        assert_eq!(dry_run.conflicts, std::slice::from_ref(&clash));
+        assert!(manager
+            .get_memory(&stocks)
+            .unwrap()
+            .unwrap()
+            .metadata
+            .contains_key("category"));
+
+        let mut batches = Vec::new();
+        let progress = manager
+            .migrate_metadata(
+                &MetadataMigration {
+                    dry_run: false,
+                    ..migration.clone()
+                },
+                2,
+                |progress| batches.push(progress.scanned),
+            )
+            .unwrap();
+        assert_eq!(batches, [2, 3]);
+        assert_eq!(progress.migrated, 2);
+
+        let metadata = |id: &str| manager.get_memory(id).unwrap().unwrap().metadata;
+        let migrated = manager.get_memory(&stocks).unwrap().unwrap();
+        assert_eq!(
+            migrated.metadata.get("topic").map(String::as_str),
+            Some("equities")
+        );
+        assert!(!migrated.metadata.contains_key("category"));
+        assert_eq!(migrated.version, 2);
+        assert_eq!(
+            metadata(&crypto).get("topic").map(String::as_str),
+            Some("crypto")
+        );
+        assert_eq!(
+            metadata(&clash).get("category").map(String::as_str),
+            Some("stocks")
+        );
+        assert!(metadata(&other_user).contains_key("category"));
+
+        // Tags are mapped one by one, in place
+        let tagged = save("alice", &[("tags", "fx, stocks,macro")]);
+        let retag = MetadataMigration {
+            from_key: "tags".to_string(),
+            value_map: HashMap::from([("stocks".to_string(), "equities".to_string())]),
+            ..Default::default()
+        };
+        assert_eq!(
+            manager
+                .migrate_metadata(&retag, 10, |_| {})
+                .unwrap()
+                .migrated,
+            1
+        );
+        assert_eq!(
+            metadata(&tagged).get("tags").map(String::as_str),
+            Some("fx, equities, macro")
+        );
+        assert_eq!(
+            manager
+                .migrate_metadata(&retag, 10, |_| {})
+                .unwrap()
+                .migrated,
+            0
+        );
+
+        let noop = MetadataMigration {
+            from_key: "tags".to_string(),
+            ..Default::default()
+        };
+        assert!(manager.migrate_metadata(&noop, 10, |_| {}).is_err());
+    }
+
+    #[test]
+    fn test_idempotency_key_returns_original_save() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let memory = |user: &str, content: &str| MemoryItem {
+            user_id: user.to_string(),
+            session_id: "session1".to_string(),
+            content: content.to_string(),
+            idempotency_key: Some("req-42".to_string()),
+            ..Default::default()
+        };
+
+        let first = manager
+            .save_memory_with_receipt(memory("alice", "Bought 10 AAPL"))
+            .unwrap();
+        assert!(!first.replayed);
+        // A retry, even with a changed body, gets the first save back
+        let retry = manager
+            .save_memory_with_receipt(memory("alice", "Bought 10 AAPL (retry)"))
+            .unwrap();
+        assert!(retry.replayed);
+        assert_eq!(retry.id, first.id);
+        assert_eq!(retry.created_at, first.created_at);
+
+        // Keys are per user
+        let other_user = manager
+            .save_memory(memory("bob", "Bought 10 AAPL"))
+            .unwrap();
+        assert_ne!(other_user, first.id);
+
+        let batch = manager
+            .save_memories_batch(BatchRequest {
+                items: vec![
+                    memory("alice", "Bought 10 AAPL"),
+                    MemoryItem {
+                        idempotency_key: Some("req-43".to_string()),
+                        ..memory("alice", "Sold 5 MSFT")
+                    },
+                ],
+                fail_on_error: true,
+                quota_policy: QuotaPolicy::default(),
+            })
+            .unwrap();
+        assert_eq!(batch.results[0].result.as_ref(), Some(&first.id));
+        assert_eq!(
+            manager
+                .get_user_memory_stats("alice")
+                .unwrap()
+                .total_memories,
+            2
+        );
+    }
+
+    #[test]
+    fn test_conflicts_are_flagged_confirmed_and_dismissed() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |content: &str, metadata: &[(&str, &str)]| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "test_session".to_string(),
+                    content: content.to_string(),
+                    metadata: metadata
+                        .iter()
+                        .map(|(key, value)| (key.to_string(), value.to_string()))
+                        .collect(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+
+        let likes = save("Alice prefers dark mode in the editor", &[]);
+        let dislikes = save("Alice doesn't prefer dark mode in the editor", &[]);
+        // Agreeing with `likes` isn't a conflict
+        save(
+            "Alice prefers dark mode in the editor",
+            &[("theme_id", "7")],
+        );
+        save("Sourdough needs a long proof overnight", &[]);
+        save("Bob never drinks coffee", &[]);
+
+        let conflicts = manager
+            .find_conflicts("test_user", &likes)
+            .unwrap()
+            .unwrap();
+        assert_eq!(conflicts.len(), 1);
+        assert_eq!(conflicts[0].memory.id, dislikes);
+        assert_eq!(conflicts[0].reasons, vec![ConflictReason::Negation]);
+        assert!(!conflicts[0].confirmed);
+
+        assert!(manager
+            .link_memories(&dislikes, &likes, LinkType::Contradicts)
+            .unwrap());
+        assert!(!manager
+            .link_memories(&likes, &dislikes, LinkType::Contradicts)
+            .unwrap());
+        assert!(
+            manager
+                .find_conflicts("test_user", &likes)
+                .unwrap()
+                .unwrap()[0]
+                .confirmed
+        );
+
+        // Dismissing replaces the confirmation
+        assert!(manager
+            .link_memories(&likes, &dislikes, LinkType::Compatible)
+            .unwrap());
+        assert!(manager
+            .find_conflicts("test_user", &likes)
+            .unwrap()
+            .unwrap()
+            .is_empty());
+        assert_eq!(manager.get_memory_links(&dislikes).unwrap().len(), 1);
+
+        assert!(!manager
+            .link_memories(&likes, "missing", LinkType::Contradicts)
+            .unwrap());
+        assert!(manager
+            .find_conflicts("other_user", &likes)
+            .unwrap()
+            .is_none());
+
+        assert!(manager.delete_memory(&dislikes).unwrap());
+        assert!(manager.get_memory_links(&likes).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_ttl_updates_keep_expiry_consistent() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let memory_id = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Expiring".to_string(),
+                ttl_hours: Some(2),
+                ..Default::default()
+            })
+            .unwrap();
+        let hours_left = |memory: &MemoryItem| {
+            let left = memory.expires_at.unwrap() - Utc::now();
+            (left.num_minutes() as f64 / 60.0).round() as i64
+        };
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(hours_left(&memory), 2);
+
+        // Extending moves the expiry and the TTL together
+        assert!(manager.extend_ttl(&memory_id, 3).unwrap());
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(memory.ttl_hours, Some(5));
+        assert_eq!(hours_left(&memory), 5);
+
+        // Setting a TTL counts from now
+        let update: MemoryUpdate = serde_json::from_str(r#"{"ttl_hours": 1}"#).unwrap();
+        assert!(manager.update_memory(&memory_id, update).unwrap());
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(memory.ttl_hours, Some(1));
+        assert_eq!(hours_left(&memory), 1);
+
+        // An explicit null clears both
+        let update: MemoryUpdate = serde_json::from_str(r#"{"ttl_hours": null}"#).unwrap();
+        assert!(manager.update_memory(&memory_id, update).unwrap());
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!((memory.ttl_hours, memory.expires_at), (None, None));
+
+        // Without a TTL there is nothing to extend
+        assert!(manager.extend_ttl(&memory_id, 3).unwrap());
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!((memory.ttl_hours, memory.expires_at), (None, None));
+        assert!(!manager.extend_ttl("missing", 3).unwrap());
+    }
+
+    #[test]
+    fn test_expiring_soon_and_grace_period() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            expiry_grace_hours: 24,
+            ..Default::default()
+        })
+        .unwrap();
+        let manager = MemoryManager::new(database, RequestValidator::new(&MemexConfig::default()));
+
+        let mut ids = Vec::new();
+        for ttl_hours in [Some(1), Some(48), None] {
+            ids.push(
+                manager
+                    .save_memory(MemoryItem {
+                        user_id: "test_user".to_string(),
+                        session_id: "test_session".to_string(),
+                        content: format!("TTL {:?}", ttl_hours),
+                        ttl_hours,
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
         }
 
-        // Search for "AAPL"
-        let response = manager
-            .search_memories("test_user", "AAPL", Some(10), Some(0))
+        let expiring = |within: chrono::Duration| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("test_user".to_string()),
+                    expiring_within: Some(within),
+                    ..Default::default()
+                })
+                .unwrap()
+                .data
+                .into_iter()
+                .map(|m| m.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(expiring(chrono::Duration::hours(2)), vec![ids[0].clone()]);
+        assert_eq!(expiring(chrono::Duration::days(3)).len(), 2);
+        assert!(manager
+            .recall_memories(QueryFilter {
+                user_id: Some("test_user".to_string()),
+                expiring_within: Some(chrono::Duration::zero()),
+                ..Default::default()
+            })
+            .is_err());
+
+        let expire = |id: &str, hours_ago: i64| {
+            manager
+                .database
+                .get_connection_pool()
+                .with_write_transaction(|tx| {
+                    tx.execute(
+                        "UPDATE memories SET expires_at = datetime('now', ?2) WHERE id = ?1",
+                        rusqlite::params![id, format!("-{} hours", hours_ago)],
+                    )?;
+                    Ok(())
+                })
+                .unwrap();
+        };
+
+        // Within the grace period an expired memory is hidden but kept
+        expire(&ids[0], 1);
+        assert!(manager.get_memory(&ids[0]).unwrap().is_none());
+        assert!(expiring(chrono::Duration::hours(2)).is_empty());
+        assert_eq!(manager.database.cleanup_expired().unwrap(), 0);
+        let expired = manager.get_expired_memories("test_user").unwrap();
+        assert_eq!(expired.len(), 1);
+        assert_eq!(expired[0].id, ids[0]);
+
+        // Restoring restarts its old TTL
+        assert!(manager.restore_expired_memory(&ids[0], None).unwrap());
+        let memory = manager.get_memory(&ids[0]).unwrap().unwrap();
+        assert_eq!(memory.ttl_hours, Some(1));
+        assert!(memory.expires_at.unwrap() > Utc::now());
+        assert!(!manager.restore_expired_memory(&ids[0], None).unwrap());
+
+        // Past the grace period cleanup deletes it for good
+        expire(&ids[0], 25);
+        assert_eq!(manager.database.cleanup_expired().unwrap(), 1);
+        assert!(!manager.restore_expired_memory(&ids[0], Some(5)).unwrap());
+        assert!(manager
+            .get_expired_memories("test_user")
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_content_append_and_metadata_patch() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let manager = std::sync::Arc::new(manager);
+
+        let memory_id = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Watching".to_string(),
+                metadata: HashMap::from([
+                    ("ticker".to_string(), "AAPL".to_string()),
+                    ("draft".to_string(), "yes".to_string()),
+                ]),
+                ..Default::default()
+            })
             .unwrap();
-        assert_eq!(response.data.len(), 1);
-        assert!(response.data[0].content.contains("AAPL"));
 
-        // Search for "price"
-        let response = manager
-            .search_memories("test_user", "price", Some(10), Some(0))
+        let update = MemoryUpdate {
+            content_append: Some(" NVDA earnings".to_string()),
+            metadata_patch: HashMap::from([
+                ("draft".to_string(), None),
+                ("sector".to_string(), Some("tech".to_string())),
+            ]),
+            ..Default::default()
+        };
+        assert!(manager.update_memory(&memory_id, update).unwrap());
+
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(memory.content, "Watching NVDA earnings");
+        assert_eq!(memory.metadata.len(), 2);
+        assert_eq!(memory.metadata["ticker"], "AAPL");
+        assert_eq!(memory.metadata["sector"], "tech");
+
+        // The appended text is searchable
+        let found = manager
+            .search_memories("test_user", "earnings", None, None)
             .unwrap();
-        assert_eq!(response.data.len(), 1);
-        assert!(response.data[0].content.contains("Bitcoin"));
+        assert_eq!(found.data.len(), 1);
+
+        // Concurrent appends don't overwrite each other
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let manager = manager.clone();
+                let memory_id = memory_id.clone();
+                std::thread::spawn(move || {
+                    let update = MemoryUpdate {
+                        content_append: Some(format!(" #{}", i)),
+                        metadata_patch: HashMap::from([(format!("k{}", i), Some(i.to_string()))]),
+                        ..Default::default()
+                    };
+                    manager.update_memory(&memory_id, update).unwrap();
+                })
+            })
+            .collect();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        for i in 0..4 {
+            assert!(memory.content.contains(&format!(" #{}", i)));
+            assert_eq!(memory.metadata[&format!("k{}", i)], i.to_string());
+        }
+
+        let update = MemoryUpdate {
+            content_append: Some("x".to_string()),
+            ..Default::default()
+        };
+        assert!(!manager.update_memory("missing", update).unwrap());
     }
 
     #[test]
-    fn test_memory_update() {
+    fn test_expected_version_conflicts() {
         let (manager, _temp_dir) = setup_test_manager();
 
-        let memory = MemoryItem {
-            user_id: "test_user".to_string(),
-            session_id: "test_session".to_string(),
-            content: "Original content".to_string(),
-            importance: 0.5,
+        let memory_id = manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "test_session".to_string(),
+                content: "Draft".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let read = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(read.version, 1);
+
+        // Two agents edit the same version; the second one loses
+        let edit = |content: &str| MemoryUpdate {
+            content: Some(content.to_string()),
+            expected_version: Some(read.version),
             ..Default::default()
         };
+        assert!(manager.update_memory(&memory_id, edit("First")).unwrap());
+        let error = manager
+            .update_memory(&memory_id, edit("Second"))
+            .unwrap_err();
+        let conflict = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<crate::database::VersionConflict>())
+            .unwrap();
+        assert_eq!((conflict.expected, conflict.actual), (1, 2));
 
-        let memory_id = manager.save_memory(memory).unwrap();
+        let memory = manager.get_memory(&memory_id).unwrap().unwrap();
+        assert_eq!(memory.content, "First");
+        assert_eq!(memory.version, 2);
+        assert_eq!(serde_json::to_value(&memory).unwrap()["version"], 2);
 
-        // Update memory
+        // Unconditional updates still go through and bump the version
         let update = MemoryUpdate {
-            content: Some("Updated content".to_string()),
             importance: Some(0.9),
-            metadata: None,
-            ttl_hours: None,
+            ..Default::default()
         };
+        assert!(manager.update_memory(&memory_id, update).unwrap());
+        assert_eq!(manager.get_memory(&memory_id).unwrap().unwrap().version, 3);
+    }
 
-        let updated = manager.update_memory(&memory_id, update).unwrap();
-        assert!(updated);
+    #[test]
+    fn test_delete_by_filter() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |session: &str, age_days: i64| {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session.to_string(),
+                    content: format!("{} days old", age_days),
+                    created_at: Utc::now() - chrono::Duration::days(age_days),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let old: Vec<_> = (0..3).map(|i| save("journal", 40 + i)).collect();
+        let recent = save("journal", 1);
+        let other_session = save("scratch", 40);
 
-        // Verify update
-        let retrieved = manager.get_memory(&memory_id).unwrap().unwrap();
-        assert_eq!(retrieved.content, "Updated content");
-        assert_eq!(retrieved.importance, 0.9);
+        let filter = QueryFilter {
+            session_id: Some("journal".to_string()),
+            date_to: Some(Utc::now() - chrono::Duration::days(30)),
+            ..Default::default()
+        };
+
+        // Unscoped filters are refused
+        let error = manager
+            .delete_by_filter(
+                QueryFilter {
+                    session_id: None,
+                    ..filter.clone()
+                },
+                DeleteOptions::default(),
+            )
+            .unwrap_err();
+        assert!(error.to_string().contains("user_id or session_id"));
+
+        let preview = manager
+            .delete_by_filter(
+                filter.clone(),
+                DeleteOptions {
+                    dry_run: true,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert!(preview.dry_run);
+        let mut ids = preview.ids.clone();
+        ids.sort();
+        let mut expected = old.clone();
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert!(manager.get_memory(&old[0]).unwrap().is_some());
+
+        let error = manager
+            .delete_by_filter(
+                filter.clone(),
+                DeleteOptions {
+                    max_affected: Some(2),
+                    ..Default::default()
+                },
+            )
+            .unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ValidationError>(),
+            Some(ValidationError::BatchSizeExceeded { size: 3, max: 2 })
+        ));
+        assert!(manager.get_memory(&old[0]).unwrap().is_some());
+
+        let report = manager
+            .delete_by_filter(
+                filter,
+                DeleteOptions {
+                    max_affected: Some(3),
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        assert_eq!(report.count(), 3);
+        assert!(!report.dry_run);
+        assert!(old
+            .iter()
+            .all(|id| manager.get_memory(id).unwrap().is_none()));
+        assert!(manager.get_memory(&recent).unwrap().is_some());
+        assert!(manager.get_memory(&other_session).unwrap().is_some());
     }
 
     #[test]
@@ -613,6 +3868,374 @@ mod tests {
         assert!(stats.importance_distribution.contains_key("very_low"));
     }
 
+    #[test]
+    fn test_user_memory_stats_percentiles() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let stats = manager.get_user_memory_stats("test_user").unwrap();
+        assert_eq!(stats.importance_percentiles.p50, 0.0);
+        assert_eq!(stats.importance_percentiles.histogram.len(), 10);
+        assert!(stats
+            .importance_percentiles
+            .histogram
+            .iter()
+            .all(|bucket| bucket.count == 0));
+
+        for i in 1..=10 {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "session1".to_string(),
+                    content: "x".repeat(100 * i),
+                    importance: i as f32 / 10.0,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let stats = manager.get_user_memory_stats("test_user").unwrap();
+        let importance = &stats.importance_percentiles;
+        assert_eq!(
+            (
+                importance.min,
+                importance.p50,
+                importance.p90,
+                importance.p99,
+                importance.max
+            ),
+            (0.1, 0.5, 0.9, 1.0, 1.0)
+        );
+        let counts: Vec<_> = importance.histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![0, 1, 1, 1, 1, 1, 1, 1, 1, 2]);
+        assert_eq!(importance.histogram[9].to, None);
+
+        let content = &stats.content_bytes_percentiles;
+        assert_eq!((content.p50, content.max), (500.0, 1000.0));
+        let counts: Vec<_> = content.histogram.iter().map(|b| b.count).collect();
+        assert_eq!(counts, vec![0, 2, 8, 0, 0, 0, 0]);
+        assert_eq!(
+            (content.histogram[1].from, content.histogram[1].to),
+            (64.0, Some(256.0))
+        );
+    }
+
+    #[test]
+    fn test_unknown_sessions() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let memory = |session_id: &str, content: &str| MemoryItem {
+            user_id: "alice".to_string(),
+            session_id: session_id.to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        };
+
+        // By default saving creates the session, named after the memory
+        manager
+            .save_memory(memory("new_session", "Kickoff notes\nsecond line"))
+            .unwrap();
+        let sessions = manager
+            .database
+            .get_user_sessions("alice", None, None)
+            .unwrap();
+        assert_eq!(sessions.data.len(), 1);
+        assert_eq!(sessions.data[0].name.as_deref(), Some("Kickoff notes"));
+
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let config = MemexConfig {
+            auto_create_sessions: false,
+            ..Default::default()
+        };
+        let manager = MemoryManager::new(database, RequestValidator::new(&config));
+
+        let error = manager.save_memory(memory("missing", "Lost")).unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ValidationError>(),
+            Some(ValidationError::UnknownSession { session_id, .. }) if session_id == "missing"
+        ));
+
+        let session_id = manager.database.create_session("alice", None).unwrap();
+        manager.save_memory(memory(&session_id, "Kept")).unwrap();
+
+        // Someone else's session counts as unknown
+        let bob_session = manager.database.create_session("bob", None).unwrap();
+        assert!(manager
+            .save_memory(memory(&bob_session, "Misfiled"))
+            .is_err());
+        assert_eq!(
+            manager
+                .database
+                .get_user_sessions("alice", None, None)
+                .unwrap()
+                .data
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_byte_quota() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let config = MemexConfig {
+            max_bytes_per_user: Some(20),
+            ..Default::default()
+        };
+        let manager = MemoryManager::new(database, RequestValidator::new(&config));
+        let save = |user: &str, content: &str| {
+            manager.save_memory(MemoryItem {
+                user_id: user.to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+        };
+
+        let memory_id = save("alice", "0123456789").unwrap();
+        save("alice", "abcdefghij").unwrap();
+        let error = save("alice", "!").unwrap_err();
+        assert!(matches!(
+            error.downcast_ref::<ValidationError>(),
+            Some(ValidationError::ByteQuotaExceeded {
+                requested: 21,
+                max: 20
+            })
+        ));
+        // Quotas are per user
+        save("bob", "0123456789").unwrap();
+
+        // An update only counts the difference
+        let update = |content: &str| MemoryUpdate {
+            content: Some(content.to_string()),
+            ..Default::default()
+        };
+        assert!(manager
+            .update_memory(&memory_id, update("9876543210"))
+            .unwrap());
+        assert!(manager
+            .update_memory(&memory_id, update("9876543210!"))
+            .is_err());
+        assert!(manager.update_memory(&memory_id, update("short")).unwrap());
+
+        let stats = manager.get_user_memory_stats("alice").unwrap();
+        assert_eq!(stats.content_bytes, 15);
+        assert_eq!(stats.session_storage.len(), 1);
+        assert_eq!(stats.session_storage[0].memory_count, 2);
+        // "short" and "abcdefghij" at four characters a token
+        assert_eq!(stats.total_tokens, 5);
+        assert_eq!(stats.session_tokens["session1"], 5);
+
+        let storage = manager.database.user_storage().unwrap();
+        let users: Vec<_> = storage
+            .iter()
+            .map(|usage| (usage.user_id.as_str(), usage.content_bytes))
+            .collect();
+        assert_eq!(users, vec![("alice", 15), ("bob", 10)]);
+        let stats = manager.database.get_stats().unwrap();
+        assert_eq!(stats["content_bytes"], 25);
+        assert_eq!(stats["user_bytes"]["bob"], 10);
+    }
+
+    #[test]
+    fn test_batch_quota_policies() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let config = MemexConfig {
+            max_bytes_per_user: Some(20),
+            ..Default::default()
+        };
+        let manager = MemoryManager::new(database, RequestValidator::new(&config));
+        let memory = |user: &str, content: &str, importance: f32| MemoryItem {
+            user_id: user.to_string(),
+            session_id: "session1".to_string(),
+            content: content.to_string(),
+            importance,
+            ..Default::default()
+        };
+        let save_batch = |user: &str, contents: &[(&str, f32)], quota_policy| {
+            manager
+                .save_memories_batch(BatchRequest {
+                    items: contents
+                        .iter()
+                        .map(|(content, importance)| memory(user, content, *importance))
+                        .collect(),
+                    fail_on_error: false,
+                    quota_policy,
+                })
+                .unwrap()
+        };
+        let actions = |response: &BatchResponse<String>| {
+            response
+                .results
+                .iter()
+                .map(|result| result.quota_action.clone())
+                .collect::<Vec<_>>()
+        };
+        let over_quota = [("0123456789", 0.5), ("abcdefghijk", 0.5), ("xyz", 0.5)];
+
+        // Each item over quota fails on its own
+        let response = save_batch("reject", &over_quota, QuotaPolicy::Reject);
+        assert_eq!(response.success_count, 2);
+        assert_eq!(
+            actions(&response),
+            vec![None, Some(QuotaAction::Rejected), None]
+        );
+
+        let response = save_batch("stop", &over_quota, QuotaPolicy::StopAtQuota);
+        assert_eq!(response.success_count, 1);
+        assert_eq!(
+            actions(&response),
+            vec![
+                None,
+                Some(QuotaAction::Rejected),
+                Some(QuotaAction::Skipped)
+            ]
+        );
+
+        // Archived overflow still counts, so everything after it overflows
+        let response = save_batch("archive", &over_quota, QuotaPolicy::OverflowToArchive);
+        assert_eq!(response.success_count, 3);
+        assert_eq!(
+            actions(&response),
+            vec![
+                None,
+                Some(QuotaAction::Archived),
+                Some(QuotaAction::Archived)
+            ]
+        );
+        let archived = response.results[1].result.as_ref().unwrap();
+        assert_eq!(
+            manager.get_memory(archived).unwrap().unwrap().state,
+            MemoryState::Archived
+        );
+
+        // Only memories less important than the item make room for it
+        let low = manager
+            .save_memory(memory("evict", "0123456789", 0.1))
+            .unwrap();
+        manager
+            .save_memory(memory("evict", "abcdefghij", 0.9))
+            .unwrap();
+        let response = save_batch(
+            "evict",
+            &[("fresh", 0.5), ("tiny!", 0.05), ("another", 0.05)],
+            QuotaPolicy::EvictLowestImportance,
+        );
+        assert_eq!(response.success_count, 2);
+        assert_eq!(
+            actions(&response),
+            vec![
+                Some(QuotaAction::Evicted {
+                    ids: vec![low.clone()]
+                }),
+                None,
+                Some(QuotaAction::Rejected)
+            ]
+        );
+        assert!(manager.get_memory(&low).unwrap().is_none());
+        let json = serde_json::to_value(&response).unwrap();
+        assert_eq!(json["results"][0]["quota_action"]["action"], "evicted");
+        assert!(json["results"][1].get("quota_action").is_none());
+    }
+
+    #[test]
+    fn test_user_analytics() {
+        let (manager, _temp_dir) = setup_test_manager();
+
+        let empty = manager.get_user_analytics("test_user").unwrap();
+        assert_eq!(empty.memory_count, 0);
+        assert_eq!(empty.storage_bytes, 0);
+        assert!(empty.compression_ratio.is_none());
+        assert!(empty.top_sessions.is_empty());
+
+        let save = |session: &str, content: &str, category: Option<&str>| {
+            let mut metadata = HashMap::new();
+            if let Some(category) = category {
+                metadata.insert("category".to_string(), category.to_string());
+            }
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session.to_string(),
+                    content: content.to_string(),
+                    metadata,
+                    ..Default::default()
+                })
+                .unwrap();
+        };
+        save("journal", "Bought AAPL", Some("trading"));
+        save("journal", "Sold AAPL", Some("trading"));
+        save("journal", "Read a paper", Some("research"));
+        save("scratch", "Groceries", None);
+        manager
+            .save_memory(MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: "scratch".to_string(),
+                content: "Summary of three notes".to_string(),
+                is_compressed: true,
+                compressed_from: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+                ..Default::default()
+            })
+            .unwrap();
+
+        let analytics = manager.get_user_analytics("test_user").unwrap();
+        assert_eq!(analytics.memory_count, 5);
+        assert_eq!(analytics.session_count, 2);
+        assert!(analytics.storage_bytes > 0);
+        assert!(analytics.growth_bytes_per_day > 0.0);
+        assert_eq!(analytics.compressed_memories, 1);
+        assert_eq!(analytics.compression_ratio, Some(3.0));
+        assert!(analytics.decay_removal_rate.is_none());
+
+        let sessions: Vec<_> = analytics
+            .top_sessions
+            .iter()
+            .map(|s| (&s.session_id[..], s.memory_count))
+            .collect();
+        assert_eq!(sessions, vec![("journal", 3), ("scratch", 2)]);
+
+        let topics: Vec<_> = analytics
+            .topic_distribution
+            .iter()
+            .map(|t| (&t.topic[..], t.memory_count))
+            .collect();
+        assert_eq!(topics, vec![("trading", 2), ("research", 1)]);
+        assert_eq!(analytics.topic_distribution[0].share, 0.4);
+        assert_eq!(analytics.uncategorized_memories, 2);
+
+        assert_eq!(
+            manager
+                .get_user_analytics("other_user")
+                .unwrap()
+                .memory_count,
+            0
+        );
+    }
+
     #[test]
     fn test_performance_monitoring() {
         let (manager, _temp_dir) = setup_test_manager();
@@ -637,4 +4260,248 @@ mod tests {
         assert!(metrics.avg_save_time_ms > 0.0);
         assert!(metrics.avg_query_time_ms > 0.0);
     }
+
+    #[test]
+    fn test_semantic_query_without_embedder_searches_its_words() {
+        let (manager, _temp_dir) = setup_test_manager();
+        for content in ["Drinks coffee every morning", "The car needs new tires"] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "s1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let recall = |keywords: Option<Vec<String>>| {
+            manager
+                .recall_memories(QueryFilter {
+                    user_id: Some("alice".to_string()),
+                    keywords,
+                    semantic_query: Some("coffee mornings".to_string()),
+                    ..Default::default()
+                })
+                .unwrap()
+                .data
+        };
+        let memories = recall(None);
+        assert_eq!(memories.len(), 1);
+        assert_eq!(memories[0].content, "Drinks coffee every morning");
+        // Explicit keywords win
+        assert_eq!(
+            recall(Some(vec!["tires".to_string()]))[0].content,
+            "The car needs new tires"
+        );
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_semantic_query_blends_similarity_and_text() {
+        use crate::database::vector::{Embedder, VectorConfig, VectorSearchEngine};
+
+        /// Embeds whether a text mentions drinks and whether it mentions cars
+        struct TopicEmbedder;
+
+        impl Embedder for TopicEmbedder {
+            fn model_name(&self) -> &str {
+                "topics"
+            }
+
+            fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+                let mentions = |text: &str, words: &[&str]| {
+                    let text = text.to_lowercase();
+                    words.iter().any(|word| text.contains(word)) as u8 as f32
+                };
+                Ok(texts
+                    .iter()
+                    .map(|text| {
+                        vec![
+                            mentions(text, &["coffee", "espresso", "tea"]),
+                            mentions(text, &["car", "tires"]),
+                            0.1,
+                        ]
+                    })
+                    .collect())
+            }
+        }
+
+        let (mut manager, _temp_dir) = setup_test_manager();
+        let engine = VectorSearchEngine::new(
+            manager.database.get_connection_pool(),
+            VectorConfig {
+                dimension: 3,
+                ..Default::default()
+            },
+        );
+        engine.initialize_schema().unwrap();
+        for content in [
+            "Espresso after lunch",
+            "Tea before bed",
+            "The car needs new tires",
+            "Coffee shop by the car wash",
+        ] {
+            manager
+                .save_memory(MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "s1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        engine.backfill(&TopicEmbedder, 10, |_| {}).unwrap();
+        manager.enable_vector_search(engine);
+        manager.set_query_embedder(Arc::new(TopicEmbedder));
+
+        let response = manager
+            .recall_memories(QueryFilter {
+                user_id: Some("alice".to_string()),
+                semantic_query: Some("coffee".to_string()),
+                explain: true,
+                ..Default::default()
+            })
+            .unwrap();
+        // The car memory isn't similar enough; the mixed one is barely
+        // similar but the only text match
+        let contents: Vec<&str> = response.data.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents[0], "Coffee shop by the car wash");
+        assert_eq!(response.total_count, 3);
+        assert!(!contents.contains(&"The car needs new tires"));
+        let explanation = response.data[1].explanation.as_ref().unwrap();
+        assert_eq!(explanation.ranked_by, RankedBy::Semantic);
+        assert!(explanation.semantic_similarity.unwrap() > 0.99);
+        assert!(response.data[0].match_score > response.data[1].match_score);
+
+        assert!(manager
+            .recall_memories(QueryFilter {
+                semantic_query: Some("coffee".to_string()),
+                before_id: Some(response.data[0].id.clone()),
+                ..Default::default()
+            })
+            .is_err());
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_import_memories_keeps_embeddings() {
+        use crate::core::interop::{self, ImportDefaults, ImportFormat};
+        use crate::database::vector::{VectorConfig, VectorSearchEngine};
+
+        let (mut manager, _temp_dir) = setup_test_manager();
+        let engine = VectorSearchEngine::new(
+            manager.database.get_connection_pool(),
+            VectorConfig {
+                dimension: 2,
+                ..Default::default()
+            },
+        );
+        engine.initialize_schema().unwrap();
+        manager.enable_vector_search(engine);
+
+        let json = r#"{
+            "ids": ["a", "b"],
+            "documents": ["Likes tea", "Works remotely"],
+            "embeddings": [[0.0, 1.0], [1.0, 0.0, 0.0]]
+        }"#;
+        let defaults = ImportDefaults {
+            user_id: Some("alice".to_string()),
+            ..Default::default()
+        };
+        let records = interop::parse(ImportFormat::Chroma, json, &defaults).unwrap();
+        let report = manager.import_memories(records, "minilm").unwrap();
+        assert_eq!(report.imported, 2);
+        assert_eq!(report.embeddings_stored, 1);
+        assert_eq!(report.embeddings_skipped, 1, "wrong dimension is skipped");
+
+        let response = manager
+            .recall_memories(QueryFilter {
+                user_id: Some("alice".to_string()),
+                keywords: Some(vec!["tea".to_string()]),
+                include_embeddings: true,
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(response.data[0].embedding, Some(vec![0.0, 1.0]));
+        assert_eq!(response.data[0].embedding_model.as_deref(), Some("minilm"));
+
+        let documents = manager
+            .export_user_documents("alice", DocumentFormat::LangChain)
+            .unwrap();
+        let tea = documents
+            .iter()
+            .find(|d| d["page_content"] == "Likes tea")
+            .unwrap();
+        assert_eq!(tea["embedding"], serde_json::json!([0.0, 1.0]));
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_cluster_memories_by_embedding() {
+        use crate::database::vector::{VectorConfig, VectorSearchEngine};
+
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let engine = VectorSearchEngine::new(
+            database.get_connection_pool(),
+            VectorConfig {
+                dimension: 2,
+                ..Default::default()
+            },
+        );
+        engine.initialize_schema().unwrap();
+
+        let mut manager =
+            MemoryManager::new(database, RequestValidator::new(&MemexConfig::default()));
+        manager.enable_vector_search(engine.clone());
+
+        // The wording overlaps, so only the embeddings tell the topics apart
+        let memories = [
+            ("Notes from monday", [1.0, 0.1]),
+            ("Notes from tuesday", [0.9, 0.0]),
+            ("Notes from wednesday", [0.0, 1.0]),
+        ];
+        let mut ids = Vec::new();
+        for (content, embedding) in memories {
+            let id = manager
+                .save_memory(MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: "test_session".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+            ids.push((id, embedding));
+        }
+
+        // Until every memory is embedded, content is all there is
+        engine
+            .store_embedding(&ids[0].0, &ids[0].1, "model")
+            .unwrap();
+        let report = manager.cluster_memories("test_user", Some(2)).unwrap();
+        assert_eq!(report.method, ClusteringMethod::TfIdf);
+
+        for (id, embedding) in &ids[1..] {
+            engine.store_embedding(id, embedding, "model").unwrap();
+        }
+        let report = manager.cluster_memories("test_user", Some(2)).unwrap();
+        assert_eq!(report.method, ClusteringMethod::Embeddings);
+        assert_eq!(report.memory_count, 3);
+        assert_eq!(report.clusters[0].size, 2);
+        assert!(report.clusters[0]
+            .representatives
+            .iter()
+            .all(|memory| memory.content != "Notes from wednesday"));
+
+        assert!(manager.cluster_memories("test_user", Some(0)).is_err());
+    }
 }
@@ -0,0 +1,312 @@
+//! Hooks around `MemoryManager` operations
+//!
+//! Host applications register [`MemoryMiddleware`] on a manager with
+//! `MemoryManager::add_middleware` to enrich, check, measure or mirror
+//! memories without forking the manager. `before_*` hooks run in
+//! registration order and may change the request or reject it by returning
+//! an error; `after_*` hooks run in reverse order once the operation has
+//! succeeded.
+
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::core::memory::MemoryUpdate;
+use crate::database::models::{MemoryItem, PaginatedResponse, QueryFilter};
+
+/// Hooks called around memory operations; every hook defaults to doing
+/// nothing
+pub trait MemoryMiddleware: Send + Sync {
+    /// Name used in logs
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Before a memory is validated and saved; its ID and creation time are
+    /// already set
+    fn before_save(&self, _memory: &mut MemoryItem) -> Result<()> {
+        Ok(())
+    }
+
+    /// After a memory was saved
+    fn after_save(&self, _memory: &MemoryItem) {}
+
+    /// Before a recall or search is validated and run. Internal operations
+    /// that page through memories, such as exports and timelines, call this
+    /// for every page; searches grouped by session don't call it.
+    fn before_recall(&self, _filter: &mut QueryFilter) -> Result<()> {
+        Ok(())
+    }
+
+    /// After a recall or search, with the page about to be returned
+    fn after_recall(&self, _filter: &QueryFilter, _response: &mut PaginatedResponse<MemoryItem>) {}
+
+    /// Before an update is applied to memory `id`
+    fn before_update(&self, _id: &str, _update: &mut MemoryUpdate) -> Result<()> {
+        Ok(())
+    }
+
+    /// After a memory was updated, with its new state
+    fn after_update(&self, _memory: &MemoryItem) {}
+
+    /// Before memory `id` is deleted
+    fn before_delete(&self, _id: &str) -> Result<()> {
+        Ok(())
+    }
+
+    /// After memory `id` was deleted
+    fn after_delete(&self, _id: &str) {}
+}
+
+/// Adds fixed metadata to every saved memory, keeping keys the memory
+/// already has
+#[derive(Debug, Clone, Default)]
+pub struct MetadataEnrichment {
+    defaults: HashMap<String, String>,
+}
+
+impl MetadataEnrichment {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set `key` to `value` on memories saved without it
+    pub fn with(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.defaults.insert(key.into(), value.into());
+        self
+    }
+}
+
+impl MemoryMiddleware for MetadataEnrichment {
+    fn name(&self) -> &str {
+        "metadata_enrichment"
+    }
+
+    fn before_save(&self, memory: &mut MemoryItem) -> Result<()> {
+        for (key, value) in &self.defaults {
+            memory
+                .metadata
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+        Ok(())
+    }
+}
+
+/// Logs every completed operation at info level
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LoggingMiddleware;
+
+impl MemoryMiddleware for LoggingMiddleware {
+    fn name(&self) -> &str {
+        "logging"
+    }
+
+    fn after_save(&self, memory: &MemoryItem) {
+        tracing::info!(
+            memory_id = memory.id.as_str(),
+            user_id = memory.user_id.as_str(),
+            session_id = memory.session_id.as_str(),
+            "Memory saved"
+        );
+    }
+
+    fn after_recall(&self, filter: &QueryFilter, response: &mut PaginatedResponse<MemoryItem>) {
+        tracing::info!(
+            user_id = filter.user_id.as_deref().unwrap_or(""),
+            returned = response.data.len(),
+            total = response.total_count,
+            "Memories recalled"
+        );
+    }
+
+    fn after_update(&self, memory: &MemoryItem) {
+        tracing::info!(
+            memory_id = memory.id.as_str(),
+            version = memory.version,
+            "Memory updated"
+        );
+    }
+
+    fn after_delete(&self, id: &str) {
+        tracing::info!(memory_id = id, "Memory deleted");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::memory::MemoryManager;
+    use crate::core::{MemexConfig, RequestValidator};
+    use crate::database::{Database, DatabaseConfig};
+    use std::sync::{Arc, Mutex};
+    use tempfile::TempDir;
+
+    fn setup_test_manager() -> (MemoryManager, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let db_config = DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        };
+
+        let database = Database::new(db_config).unwrap();
+        let validator = RequestValidator::new(&MemexConfig::default());
+        (MemoryManager::new(database, validator), temp_dir)
+    }
+
+    /// Records hook calls and rejects saves mentioning secrets
+    struct Recorder {
+        label: &'static str,
+        calls: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl Recorder {
+        fn record(&self, call: &str) {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("{}:{}", self.label, call));
+        }
+    }
+
+    impl MemoryMiddleware for Recorder {
+        fn before_save(&self, memory: &mut MemoryItem) -> Result<()> {
+            self.record("before_save");
+            if memory.content.contains("secret") {
+                return Err(anyhow::anyhow!("secrets are not stored"));
+            }
+            Ok(())
+        }
+
+        fn after_save(&self, _memory: &MemoryItem) {
+            self.record("after_save");
+        }
+
+        fn before_recall(&self, filter: &mut QueryFilter) -> Result<()> {
+            self.record("before_recall");
+            filter.limit = Some(1);
+            Ok(())
+        }
+
+        fn after_recall(
+            &self,
+            _filter: &QueryFilter,
+            response: &mut PaginatedResponse<MemoryItem>,
+        ) {
+            self.record("after_recall");
+            for memory in &mut response.data {
+                memory.content = memory.content.to_uppercase();
+            }
+        }
+
+        fn before_update(&self, _id: &str, update: &mut MemoryUpdate) -> Result<()> {
+            self.record("before_update");
+            update.importance = Some(0.9);
+            Ok(())
+        }
+
+        fn after_update(&self, _memory: &MemoryItem) {
+            self.record("after_update");
+        }
+
+        fn before_delete(&self, _id: &str) -> Result<()> {
+            self.record("before_delete");
+            Ok(())
+        }
+
+        fn after_delete(&self, _id: &str) {
+            self.record("after_delete");
+        }
+    }
+
+    #[test]
+    fn test_middleware_chain() {
+        let (mut manager, _temp_dir) = setup_test_manager();
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        for label in ["outer", "inner"] {
+            manager.add_middleware(Arc::new(Recorder {
+                label,
+                calls: calls.clone(),
+            }));
+        }
+        manager.add_middleware(Arc::new(MetadataEnrichment::new().with("source", "mobile")));
+        manager.add_middleware(Arc::new(LoggingMiddleware));
+        let take_calls = || std::mem::take(&mut *calls.lock().unwrap());
+
+        let save = |content: &str, metadata: HashMap<String, String>| {
+            manager.save_memory(MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                metadata,
+                ..Default::default()
+            })
+        };
+        let id = save("first note", HashMap::new()).unwrap();
+        assert_eq!(
+            take_calls(),
+            vec![
+                "outer:before_save",
+                "inner:before_save",
+                "inner:after_save",
+                "outer:after_save"
+            ]
+        );
+        let memory = manager.get_memory(&id).unwrap().unwrap();
+        assert_eq!(memory.metadata["source"], "mobile");
+        let kept = HashMap::from([("source".to_string(), "desktop".to_string())]);
+        let second = save("second note", kept).unwrap();
+        let memory = manager.get_memory(&second).unwrap().unwrap();
+        assert_eq!(memory.metadata["source"], "desktop");
+
+        let err = save("my secret", HashMap::new()).unwrap_err();
+        assert!(err.to_string().contains("secrets are not stored"));
+        assert_eq!(take_calls().len(), 5, "rejected save stops the chain");
+
+        let response = manager
+            .recall_memories(QueryFilter {
+                user_id: Some("user1".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(response.data.len(), 1);
+        assert_eq!(response.total_count, 2);
+        assert!(response.data[0].content.ends_with("NOTE"));
+        assert_eq!(
+            take_calls(),
+            vec![
+                "outer:before_recall",
+                "inner:before_recall",
+                "inner:after_recall",
+                "outer:after_recall"
+            ]
+        );
+
+        let update = MemoryUpdate {
+            content: Some("edited".to_string()),
+            ..Default::default()
+        };
+        assert!(manager.update_memory(&id, update).unwrap());
+        assert_eq!(manager.get_memory(&id).unwrap().unwrap().importance, 0.9);
+        assert!(manager.delete_memory(&id).unwrap());
+        assert!(!manager.delete_memory(&id).unwrap());
+        assert_eq!(
+            take_calls(),
+            vec![
+                "outer:before_update",
+                "inner:before_update",
+                "inner:after_update",
+                "outer:after_update",
+                "outer:before_delete",
+                "inner:before_delete",
+                "inner:after_delete",
+                "outer:after_delete",
+                "outer:before_delete",
+                "inner:before_delete"
+            ]
+        );
+    }
+}
@@ -6,9 +6,25 @@
 //! - Decay policies and cleanup processes
 //! - Async variants for better Node.js integration
 
+pub mod access;
+pub mod analytics;
+pub mod anomaly;
+pub mod clustering;
+pub mod conflicts;
+pub mod context;
+pub mod counters;
 pub mod decay;
+pub mod graph;
+pub mod interop;
+pub mod maintenance;
 pub mod memory;
+pub mod middleware;
+pub mod privacy;
 pub mod session;
+pub mod text;
+pub mod timeline;
+pub mod tokens;
+pub mod trends;
 
 #[cfg(feature = "async")]
 pub mod async_memory;
@@ -19,12 +35,23 @@ pub mod async_session;
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
 use validator::Validate;
 
-use crate::database::{models::*, Database};
+use crate::core::access::AccessConfig;
+use crate::core::anomaly::{AnomalyMonitor, AnomalyPolicy, IngestionRate};
+use crate::core::counters::{CounterSnapshot, EventCounters};
+use crate::core::maintenance::{MaintenanceConfig, Schedule};
+use crate::database::maintenance_runs::{MaintenanceJob, MaintenanceRun, MaintenanceStatus};
+use crate::database::{models::*, Database, DatabasePoolStatus};
+use crate::logging::{LogFilter, LogFormat};
+use crate::wire::WireFormat;
 
 /// Main Memex configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(default)]
 pub struct MemexConfig {
     pub database_path: String,
 
@@ -38,19 +65,97 @@ pub struct MemexConfig {
 
     pub enable_compression: bool,
 
+    /// Store a tamper-evident hash with every memory write, signed with
+    /// `MEMEX_INTEGRITY_KEY` when it is set; see `database::integrity`
+    pub seal_memories: bool,
+
     #[validate(range(min = 1, max = 1000000))]
     pub max_memories_per_user: usize,
 
+    /// Content bytes a user may hold across all their memories; `None`
+    /// leaves it unlimited
+    #[validate(range(min = 1))]
+    pub max_bytes_per_user: Option<u64>,
+
     #[validate(range(min = 0.0, max = 1.0))]
     pub importance_threshold: f32,
 
+    /// Memories less confident than this expire sooner; see
+    /// `DecayPolicy::confidence_threshold`
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub confidence_threshold: f32,
+
+    /// Saving into a session that doesn't exist yet creates it, named after
+    /// the memory; when false such saves fail with
+    /// [`ValidationError::UnknownSession`]
+    pub auto_create_sessions: bool,
+
+    /// Decay archives sessions inactive for this many days; `None` leaves
+    /// them be
+    #[validate(range(min = 1, max = 3650))]
+    pub archive_sessions_after_days: Option<u32>,
+
     pub enable_request_limits: bool,
 
     #[validate(range(min = 1, max = 10000))]
     pub max_requests_per_minute: u32,
 
+    /// Milliseconds a rate-limited request waits for its turn before
+    /// failing; 0 fails it straight away
+    #[validate(range(max = 60000))]
+    pub rate_limit_wait_ms: u64,
+
     #[validate(range(min = 1, max = 1000))]
     pub max_batch_size: usize,
+
+    /// Maximum memory content size in bytes
+    #[validate(range(min = 1, max = 1000000))]
+    pub max_content_bytes: usize,
+
+    /// Maximum combined size of a memory's metadata keys and values in bytes
+    #[validate(range(min = 1, max = 1000000))]
+    pub max_metadata_bytes: usize,
+
+    #[validate(range(min = 1, max = 10000))]
+    pub max_metadata_keys: usize,
+
+    /// Log filter directives for FFI instances, e.g. `"info"` or
+    /// `"warn,memex_core::database=debug"`
+    #[validate(custom = "validate_log_level")]
+    pub log_level: String,
+
+    pub log_format: LogFormat,
+
+    /// Standard deviations above a user's hourly save baseline that count
+    /// as an ingestion spike
+    #[validate(range(min = 1.0, max = 100.0))]
+    pub anomaly_sensitivity: f32,
+
+    /// Hours of history the save-rate baseline is learned from
+    #[validate(range(min = 24, max = 2160))]
+    pub anomaly_baseline_hours: u32,
+
+    /// Saves in the last hour below which no spike is reported
+    #[validate(range(min = 1, max = 1000000))]
+    pub anomaly_min_saves_per_hour: u32,
+
+    /// FFI recall ignores unknown filter fields and treats a filter that
+    /// doesn't parse as no filter, instead of failing the call
+    pub lenient_filters: bool,
+
+    /// Encoding of the `memex_*_encoded` FFI results and of CBOR-capable
+    /// server responses
+    pub response_format: WireFormat,
+
+    /// Schedules of the periodic maintenance jobs and whether FFI instances
+    /// run them in the background
+    #[validate]
+    pub maintenance: MaintenanceConfig,
+
+    /// API keys and their roles; FFI instances opened with a `token` and
+    /// the Node server check requests against them
+    #[validate]
+    pub access: AccessConfig,
 }
 
 impl Default for MemexConfig {
@@ -61,87 +166,376 @@ impl Default for MemexConfig {
             auto_decay_enabled: true,
             decay_interval_hours: 24,
             enable_compression: true,
+            seal_memories: false,
             max_memories_per_user: 10000,
+            max_bytes_per_user: None,
             importance_threshold: 0.3,
+            confidence_threshold: DecayPolicy::default().confidence_threshold,
+            auto_create_sessions: true,
+            archive_sessions_after_days: None,
             enable_request_limits: true,
             max_requests_per_minute: 1000,
+            rate_limit_wait_ms: 0,
             max_batch_size: 100,
+            max_content_bytes: ContentLimits::default().max_content_bytes,
+            max_metadata_bytes: ContentLimits::default().max_metadata_bytes,
+            max_metadata_keys: ContentLimits::default().max_metadata_keys,
+            log_level: "warn".to_string(),
+            log_format: LogFormat::Text,
+            anomaly_sensitivity: 3.0,
+            anomaly_baseline_hours: 24 * 7,
+            anomaly_min_saves_per_hour: 20,
+            lenient_filters: false,
+            response_format: WireFormat::Json,
+            maintenance: MaintenanceConfig::default(),
+            access: AccessConfig::default(),
         }
     }
 }
 
-/// Request rate limiter (simple token bucket implementation)
-#[derive(Debug)]
+fn validate_log_level(log_level: &str) -> Result<(), validator::ValidationError> {
+    log_level.parse::<LogFilter>().map(|_| ()).map_err(|e| {
+        let mut error = validator::ValidationError::new("log_level");
+        error.message = Some(format!("log_level is invalid: {}", e).into());
+        error
+    })
+}
+
+impl MemexConfig {
+    /// Decay policy derived from this configuration
+    pub fn decay_policy(&self) -> DecayPolicy {
+        DecayPolicy {
+            max_age_hours: self.default_memory_ttl_hours.unwrap_or(24 * 30),
+            importance_threshold: self.importance_threshold,
+            max_memories_per_user: self.max_memories_per_user,
+            compression_enabled: self.enable_compression,
+            auto_summarize_sessions: true,
+            archive_sessions_after_days: self.archive_sessions_after_days,
+            confidence_threshold: self.confidence_threshold,
+        }
+    }
+
+    /// Schedule of each maintenance job, with decay every
+    /// `decay_interval_hours` unless `maintenance.schedules` sets its own,
+    /// and none at all when `auto_decay_enabled` is off, and backups daily
+    /// once `maintenance.backup` is set unless scheduled otherwise
+    pub fn maintenance_schedules(&self) -> std::collections::BTreeMap<MaintenanceJob, Schedule> {
+        let mut schedules = self.maintenance.schedules.clone();
+        if self.auto_decay_enabled {
+            schedules.entry(MaintenanceJob::Decay).or_insert_with(|| {
+                Schedule::every(chrono::Duration::hours(self.decay_interval_hours as i64))
+            });
+        } else {
+            schedules.remove(&MaintenanceJob::Decay);
+        }
+        if self.maintenance.backup.is_some() {
+            schedules
+                .entry(MaintenanceJob::Backup)
+                .or_insert_with(|| "@daily".parse().expect("valid schedule"));
+        }
+        if self.maintenance.wal_archive.is_some() {
+            schedules
+                .entry(MaintenanceJob::WalArchive)
+                .or_insert_with(|| "@every 5m".parse().expect("valid schedule"));
+        }
+        schedules
+    }
+
+    /// Ingestion anomaly thresholds derived from this configuration
+    pub fn anomaly_policy(&self) -> AnomalyPolicy {
+        AnomalyPolicy {
+            sensitivity: self.anomaly_sensitivity,
+            baseline_hours: self.anomaly_baseline_hours,
+            min_saves_per_hour: self.anomaly_min_saves_per_hour,
+        }
+    }
+
+    /// Content and metadata limits enforced on saved memories
+    pub fn content_limits(&self) -> ContentLimits {
+        ContentLimits {
+            max_content_bytes: self.max_content_bytes,
+            max_metadata_bytes: self.max_metadata_bytes,
+            max_metadata_keys: self.max_metadata_keys,
+        }
+    }
+}
+
+/// Request rate limiter: a token bucket shared by all clones of it
+///
+/// Tokens refill continuously at `refill_rate` a minute, up to
+/// `max_tokens`. Callers that wait with
+/// [`acquire_with_timeout`](Self::acquire_with_timeout) name a key, usually
+/// the user ID, and waiting keys are served round-robin so one busy user
+/// can't starve the rest. While anyone is waiting a background tick refills
+/// the bucket and wakes them.
+#[derive(Debug, Clone)]
 pub struct RateLimiter {
-    tokens: std::sync::atomic::AtomicU32,
-    last_refill: std::sync::Mutex<DateTime<Utc>>,
+    shared: Arc<RateLimiterShared>,
+}
+
+#[derive(Debug)]
+struct RateLimiterShared {
+    state: Mutex<BucketState>,
+    refilled: Condvar,
     max_tokens: u32,
     refill_rate: u32, // tokens per minute
 }
 
-impl Clone for RateLimiter {
-    fn clone(&self) -> Self {
-        Self::new(self.max_tokens, self.refill_rate)
+#[derive(Debug)]
+struct BucketState {
+    tokens: f64,
+    last_refill: Instant,
+    /// Keys with callers waiting, in the order they are served
+    waiting: VecDeque<String>,
+    /// Callers waiting per key
+    waiters: HashMap<String, usize>,
+    /// Tokens the waiting callers want between them
+    demand: f64,
+    ticking: bool,
+}
+
+impl BucketState {
+    fn enqueue(&mut self, key: &str, cost: f64) {
+        let waiters = self.waiters.entry(key.to_string()).or_insert(0);
+        if *waiters == 0 {
+            self.waiting.push_back(key.to_string());
+        }
+        *waiters += 1;
+        self.demand += cost;
+    }
+
+    /// Stop waiting for `key`; a served key goes to the back of the line if
+    /// it still has callers waiting
+    fn dequeue(&mut self, key: &str, cost: f64, served: bool) {
+        self.demand = (self.demand - cost).max(0.0);
+        let remaining = match self.waiters.get_mut(key) {
+            Some(waiters) => {
+                *waiters -= 1;
+                *waiters
+            }
+            None => return,
+        };
+        if remaining == 0 {
+            self.waiters.remove(key);
+            self.waiting.retain(|waiting| waiting != key);
+        } else if served {
+            self.waiting.retain(|waiting| waiting != key);
+            self.waiting.push_back(key.to_string());
+        }
+    }
+}
+
+impl RateLimiterShared {
+    fn refill(&self, state: &mut BucketState) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+        state.tokens =
+            (state.tokens + elapsed * self.refill_rate as f64 / 60.0).min(self.max_tokens as f64);
+        state.last_refill = now;
+    }
+
+    /// Tokens a request for `tokens` takes; more than the bucket holds
+    /// waits for a full bucket instead of forever
+    fn cost(&self, tokens: u32) -> f64 {
+        tokens.min(self.max_tokens) as f64
+    }
+
+    /// How long until the bucket holds `wanted` tokens
+    fn time_until(&self, state: &BucketState, wanted: f64) -> Duration {
+        let missing = (wanted - state.tokens).max(0.0);
+        Duration::try_from_secs_f64(missing * 60.0 / self.refill_rate as f64)
+            .unwrap_or(Duration::MAX)
     }
 }
 
 impl RateLimiter {
+    /// How often the bucket is refilled while callers are waiting
+    const TICK: Duration = Duration::from_millis(100);
+
     pub fn new(max_tokens: u32, refill_rate: u32) -> Self {
         Self {
-            tokens: std::sync::atomic::AtomicU32::new(max_tokens),
-            last_refill: std::sync::Mutex::new(Utc::now()),
-            max_tokens,
-            refill_rate,
+            shared: Arc::new(RateLimiterShared {
+                state: Mutex::new(BucketState {
+                    tokens: max_tokens as f64,
+                    last_refill: Instant::now(),
+                    waiting: VecDeque::new(),
+                    waiters: HashMap::new(),
+                    demand: 0.0,
+                    ticking: false,
+                }),
+                refilled: Condvar::new(),
+                max_tokens,
+                refill_rate,
+            }),
         }
     }
 
     pub fn try_acquire(&self, tokens: u32) -> bool {
-        self.refill_tokens();
-
-        let current_tokens = self.tokens.load(std::sync::atomic::Ordering::Acquire);
-        if current_tokens >= tokens {
-            let new_tokens = current_tokens - tokens;
-            self.tokens
-                .store(new_tokens, std::sync::atomic::Ordering::Release);
-            true
+        self.try_acquire_with_retry_after(tokens).is_ok()
+    }
+
+    /// Take `tokens` without waiting, or say how long until they could be
+    /// had; callers already waiting go first. Async callers can sleep for
+    /// the returned duration and try again instead of blocking a thread
+    pub fn try_acquire_with_retry_after(&self, tokens: u32) -> Result<(), Duration> {
+        let shared = &self.shared;
+        let cost = shared.cost(tokens);
+        let mut state = shared.state.lock().unwrap();
+        shared.refill(&mut state);
+
+        if state.waiting.is_empty() && state.tokens >= cost {
+            state.tokens -= cost;
+            Ok(())
         } else {
-            false
+            Err(shared.time_until(&state, state.demand + cost))
         }
     }
 
-    fn refill_tokens(&self) {
-        let now = Utc::now();
-        let mut last_refill = self.last_refill.lock().unwrap();
+    /// Take `tokens` for `key`, waiting up to `timeout` for them
+    ///
+    /// Once it is `key`'s turn and the bucket holds enough, one of its
+    /// callers takes its tokens and the key goes to the back of the line.
+    /// On timeout the error says how long until the tokens could be had.
+    pub fn acquire_with_timeout(
+        &self,
+        key: &str,
+        tokens: u32,
+        timeout: Duration,
+    ) -> Result<(), Duration> {
+        if timeout.is_zero() {
+            return self.try_acquire_with_retry_after(tokens);
+        }
+
+        let shared = &self.shared;
+        let cost = shared.cost(tokens);
+        let deadline = Instant::now() + timeout;
+        let mut state = shared.state.lock().unwrap();
+        shared.refill(&mut state);
+        if state.waiting.is_empty() && state.tokens >= cost {
+            state.tokens -= cost;
+            return Ok(());
+        }
 
-        let duration = now - *last_refill;
-        let minutes_elapsed = duration.num_minutes() as u32;
+        state.enqueue(key, cost);
+        self.start_tick(&mut state);
+        loop {
+            shared.refill(&mut state);
+            if state.waiting.front().map(String::as_str) == Some(key) && state.tokens >= cost {
+                state.tokens -= cost;
+                state.dequeue(key, cost, true);
+                shared.refilled.notify_all();
+                return Ok(());
+            }
 
-        if minutes_elapsed > 0 {
-            let tokens_to_add = minutes_elapsed * (self.refill_rate / 60).max(1);
-            let current_tokens = self.tokens.load(std::sync::atomic::Ordering::Acquire);
-            let new_tokens = (current_tokens + tokens_to_add).min(self.max_tokens);
+            let now = Instant::now();
+            if now >= deadline {
+                state.dequeue(key, cost, false);
+                let retry_after = shared.time_until(&state, state.demand + cost);
+                shared.refilled.notify_all();
+                return Err(retry_after);
+            }
+            state = shared
+                .refilled
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+    }
 
-            self.tokens
-                .store(new_tokens, std::sync::atomic::Ordering::Release);
-            *last_refill = now;
+    /// Refill the bucket and wake waiting callers every [`Self::TICK`]
+    /// until nobody is waiting or the limiter is dropped
+    fn start_tick(&self, state: &mut BucketState) {
+        if state.ticking {
+            return;
         }
+
+        let shared = Arc::downgrade(&self.shared);
+        let spawned = std::thread::Builder::new()
+            .name("memex-rate-limit".to_string())
+            .spawn(move || loop {
+                std::thread::sleep(Self::TICK);
+                let Some(shared) = shared.upgrade() else {
+                    return;
+                };
+                let mut state = shared.state.lock().unwrap();
+                shared.refill(&mut state);
+                shared.refilled.notify_all();
+                if state.waiting.is_empty() {
+                    state.ticking = false;
+                    return;
+                }
+            });
+        // Without a tick, waiters still wake at their deadline
+        state.ticking = spawned.is_ok();
     }
 }
 
 /// Request validation errors
 #[derive(Debug, thiserror::Error)]
 pub enum ValidationError {
-    #[error("Rate limit exceeded. Try again later.")]
-    RateLimitExceeded,
+    #[error("Rate limit exceeded. Try again in {:.1}s", .retry_after.as_secs_f64())]
+    RateLimitExceeded {
+        /// How long until the request could be served
+        retry_after: Duration,
+    },
 
     #[error("Batch size too large: {size}. Maximum allowed: {max}")]
     BatchSizeExceeded { size: usize, max: usize },
 
     #[error("Invalid input: {message}")]
-    InvalidInput { message: String },
+    InvalidInput {
+        message: String,
+        /// Field the problem is tied to, if known
+        field: Option<String>,
+        /// Configured maximum, for size limit violations
+        limit: Option<usize>,
+        /// Size that exceeded `limit`
+        actual: Option<usize>,
+    },
 
     #[error("User quota exceeded: {current}/{max}")]
     UserQuotaExceeded { current: usize, max: usize },
+
+    #[error("User storage quota exceeded: {requested}/{max} bytes")]
+    ByteQuotaExceeded { requested: u64, max: u64 },
+
+    #[error("Unknown session {session_id} for user {user_id}")]
+    UnknownSession { session_id: String, user_id: String },
+}
+
+impl ValidationError {
+    /// Invalid input without field or limit details
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::InvalidInput {
+            message: message.into(),
+            field: None,
+            limit: None,
+            actual: None,
+        }
+    }
+
+    /// Convert a field-level `validator` error, keeping any limit details
+    fn from_field_error(context: &str, error: &validator::ValidationError) -> Self {
+        let param = |name: &str| error.params.get(name);
+        let field = param("field").and_then(|v| v.as_str()).map(str::to_string);
+        let limit = param("limit").and_then(|v| v.as_u64()).map(|v| v as usize);
+        let actual = param("actual").and_then(|v| v.as_u64()).map(|v| v as usize);
+
+        let message = match (&field, limit, actual) {
+            (Some(field), Some(limit), Some(actual)) => format!(
+                "{}: {} exceeds limit ({} > {})",
+                context, field, actual, limit
+            ),
+            _ => format!("{}: {}", context, error.code),
+        };
+
+        Self::InvalidInput {
+            message,
+            field,
+            limit,
+            actual,
+        }
+    }
 }
 
 /// Request validator
@@ -169,10 +563,17 @@ impl RequestValidator {
     }
 
     pub fn validate_request(&self, tokens: u32) -> Result<(), ValidationError> {
+        self.validate_request_for("", tokens)
+    }
+
+    /// Charge a request costing `tokens` to `key`, usually the user it is
+    /// for, waiting up to `rate_limit_wait_ms` in turn with other keys
+    pub fn validate_request_for(&self, key: &str, tokens: u32) -> Result<(), ValidationError> {
         if let Some(ref limiter) = self.rate_limiter {
-            if !limiter.try_acquire(tokens) {
-                return Err(ValidationError::RateLimitExceeded);
-            }
+            let wait = Duration::from_millis(self.config.rate_limit_wait_ms);
+            limiter
+                .acquire_with_timeout(key, tokens, wait)
+                .map_err(|retry_after| ValidationError::RateLimitExceeded { retry_after })?;
         }
         Ok(())
     }
@@ -188,29 +589,53 @@ impl RequestValidator {
     }
 
     pub fn validate_memory_item(&self, memory: &MemoryItem) -> Result<(), ValidationError> {
-        // Use validator crate validation
+        // Configured limits first so oversized input gets a structured error
         memory
-            .validate()
-            .map_err(|e| ValidationError::InvalidInput {
-                message: format!("Memory validation failed: {:?}", e),
-            })?;
+            .validate_custom(&self.config.content_limits())
+            .map_err(|e| ValidationError::from_field_error("Custom validation failed", &e))?;
 
-        // Custom validation
-        memory
-            .validate_custom()
-            .map_err(|e| ValidationError::InvalidInput {
-                message: format!("Custom validation failed: {:?}", e),
-            })?;
+        // Use validator crate validation
+        memory.validate().map_err(|e| {
+            ValidationError::invalid_input(format!("Memory validation failed: {:?}", e))
+        })?;
 
         Ok(())
     }
 
+    /// Check that storing `memory` keeps its user within
+    /// `max_bytes_per_user`; a stored memory with the same ID is replaced,
+    /// so its bytes don't count
+    pub fn validate_byte_quota(&self, db: &Database, memory: &MemoryItem) -> anyhow::Result<()> {
+        let Some(max) = self.config.max_bytes_per_user else {
+            return Ok(());
+        };
+        let current = db.user_content_bytes(&memory.user_id, Some(&memory.id))?;
+        let requested = current.saturating_add(memory.content.len() as u64);
+        if requested > max {
+            return Err(ValidationError::ByteQuotaExceeded { requested, max }.into());
+        }
+        Ok(())
+    }
+
+    /// Unless `auto_create_sessions` is set, check that `memory` goes into
+    /// an existing session of its user
+    pub fn validate_session(&self, db: &Database, memory: &MemoryItem) -> anyhow::Result<()> {
+        if self.config.auto_create_sessions
+            || db.session_exists(&memory.session_id, &memory.user_id)?
+        {
+            return Ok(());
+        }
+        Err(ValidationError::UnknownSession {
+            session_id: memory.session_id.clone(),
+            user_id: memory.user_id.clone(),
+        }
+        .into())
+    }
+
     pub fn validate_query_filter(&self, filter: &QueryFilter) -> Result<(), ValidationError> {
-        filter
-            .validate()
-            .map_err(|e| ValidationError::InvalidInput {
-                message: format!("Filter validation failed: {:?}", e),
-            })?;
+        filter.validate().map_err(|e| {
+            ValidationError::invalid_input(format!("Filter validation failed: {:?}", e))
+        })?;
 
         Ok(())
     }
@@ -229,9 +654,7 @@ impl RequestValidator {
 
         let response = db
             .recall_memories(&filter)
-            .map_err(|e| ValidationError::InvalidInput {
-                message: format!("Database error: {}", e),
-            })?;
+            .map_err(|e| ValidationError::invalid_input(format!("Database error: {}", e)))?;
 
         if response.total_count as usize >= self.config.max_memories_per_user {
             return Err(ValidationError::UserQuotaExceeded {
@@ -249,6 +672,59 @@ impl RequestValidator {
 pub struct BatchRequest<T> {
     pub items: Vec<T>,
     pub fail_on_error: bool, // If true, entire batch fails on first error
+    /// What to do with items that would take their user past
+    /// `max_bytes_per_user`
+    #[serde(default)]
+    pub quota_policy: QuotaPolicy,
+}
+
+/// How a batch save handles items over their user's storage quota
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QuotaPolicy {
+    /// Each such item fails on its own; later items that fit are saved
+    #[default]
+    Reject,
+    /// The first such item fails and the rest of the batch is skipped
+    StopAtQuota,
+    /// Delete the user's least important memories, all less important
+    /// than the item, until it fits; it fails if they can't free enough
+    EvictLowestImportance,
+    /// Save such items archived, past the quota: kept, but out of recall
+    /// until revived. They still count toward the quota afterwards
+    OverflowToArchive,
+}
+
+impl std::str::FromStr for QuotaPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "reject" => Ok(Self::Reject),
+            "stop_at_quota" => Ok(Self::StopAtQuota),
+            "evict_lowest_importance" => Ok(Self::EvictLowestImportance),
+            "overflow_to_archive" => Ok(Self::OverflowToArchive),
+            _ => Err(format!(
+                "unknown quota policy `{}`; expected reject, stop_at_quota, \
+                 evict_lowest_importance or overflow_to_archive",
+                value
+            )),
+        }
+    }
+}
+
+/// What a [`QuotaPolicy`] did with an item over quota
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum QuotaAction {
+    /// Turned away for being over quota
+    Rejected,
+    /// Not attempted, because an earlier item hit the quota
+    Skipped,
+    /// Saved after deleting these memories to make room
+    Evicted { ids: Vec<String> },
+    /// Saved archived
+    Archived,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -263,6 +739,15 @@ pub struct BatchResult<T> {
     pub success: bool,
     pub result: Option<T>,
     pub error: Option<String>,
+    /// Set when the item was over quota
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quota_action: Option<QuotaAction>,
+}
+
+impl<T> Default for BatchResponse<T> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl<T> BatchResponse<T> {
@@ -275,21 +760,39 @@ impl<T> BatchResponse<T> {
     }
 
     pub fn add_success(&mut self, result: T) {
-        self.results.push(BatchResult {
-            success: true,
-            result: Some(result),
-            error: None,
-        });
-        self.success_count += 1;
+        self.add(Ok(result), None);
     }
 
     pub fn add_error(&mut self, error: String) {
-        self.results.push(BatchResult {
-            success: false,
-            result: None,
-            error: Some(error),
-        });
-        self.error_count += 1;
+        self.add(Err(error), None);
+    }
+
+    /// Record an item's outcome and what the quota policy did with it
+    pub fn add(
+        &mut self,
+        result: std::result::Result<T, String>,
+        quota_action: Option<QuotaAction>,
+    ) {
+        match result {
+            Ok(result) => {
+                self.results.push(BatchResult {
+                    success: true,
+                    result: Some(result),
+                    error: None,
+                    quota_action,
+                });
+                self.success_count += 1;
+            }
+            Err(error) => {
+                self.results.push(BatchResult {
+                    success: false,
+                    result: None,
+                    error: Some(error),
+                    quota_action,
+                });
+                self.error_count += 1;
+            }
+        }
     }
 
     pub fn is_empty(&self) -> bool {
@@ -309,18 +812,140 @@ impl<T> BatchResponse<T> {
     }
 }
 
-/// System health information
+/// System health, as returned by `memex_health`
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HealthStatus {
     pub status: String, // "healthy", "degraded", "unhealthy"
     pub timestamp: DateTime<Utc>,
+    /// "connected", or why the database could not be queried
     pub database_status: String,
+    pub pool_status: DatabasePoolStatus,
+    pub wal_size_bytes: u64,
+    /// 0 when checkpoints never truncate the WAL
+    pub wal_truncate_threshold_bytes: u64,
+    /// Most recent decay run on any instance sharing the file
+    pub last_decay_run: Option<DecayStats>,
+    /// Operations and errors counted since the instance was created
+    pub counters: CounterSnapshot,
     pub memory_usage: MemoryUsage,
     pub performance_metrics: PerformanceMetrics,
+    /// Maintenance jobs whose latest runs failed
     pub recent_errors: Vec<ErrorInfo>,
+    /// Users whose save rate is spiking; any entry means "degraded"
+    #[serde(default)]
+    pub ingestion_anomalies: Vec<IngestionRate>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+impl HealthStatus {
+    /// Check the database behind an instance
+    ///
+    /// Never fails: a database that cannot be queried makes the status
+    /// "unhealthy", while an unhealthy pool, an oversized WAL, a failed decay
+    /// or maintenance run, or an ingestion spike make it "degraded".
+    pub fn check(
+        database: &Database,
+        counters: &EventCounters,
+        performance_metrics: PerformanceMetrics,
+        anomaly_policy: AnomalyPolicy,
+    ) -> Self {
+        let mut problems = Vec::new();
+        let mut note = |what: &str, e: anyhow::Error| problems.push(format!("{}: {}", what, e));
+
+        let memory_usage = database
+            .get_memory_usage()
+            .map_err(|e| note("memory usage", e))
+            .unwrap_or_default();
+        let last_decay_run = database
+            .last_decay_run()
+            .map_err(|e| note("decay runs", e))
+            .ok()
+            .flatten();
+        let recent_errors = database
+            .get_maintenance_runs(None, HEALTH_MAINTENANCE_RUNS)
+            .map(|runs| failing_maintenance_jobs(&runs))
+            .map_err(|e| note("maintenance runs", e))
+            .unwrap_or_default();
+        let ingestion_anomalies = AnomalyMonitor::new(database.clone(), anomaly_policy)
+            .check()
+            .map_err(|e| note("ingestion rates", e))
+            .unwrap_or_default();
+
+        let pool_status = database.get_pool_status();
+        let wal_size_bytes = database.wal_size_bytes();
+        let wal_truncate_threshold_bytes = database.wal_truncate_threshold_bytes();
+        let wal_oversized =
+            wal_truncate_threshold_bytes > 0 && wal_size_bytes >= wal_truncate_threshold_bytes;
+        let decay_failed = last_decay_run
+            .as_ref()
+            .is_some_and(|run| run.status == DecayStatus::Failed);
+
+        let (status, database_status) = if !problems.is_empty() {
+            ("unhealthy", problems.join("; "))
+        } else if !pool_status.is_healthy()
+            || wal_oversized
+            || decay_failed
+            || !recent_errors.is_empty()
+            || !ingestion_anomalies.is_empty()
+        {
+            ("degraded", "connected".to_string())
+        } else {
+            ("healthy", "connected".to_string())
+        };
+
+        Self {
+            status: status.to_string(),
+            timestamp: Utc::now(),
+            database_status,
+            pool_status,
+            wal_size_bytes,
+            wal_truncate_threshold_bytes,
+            last_decay_run,
+            counters: counters.snapshot(),
+            memory_usage,
+            performance_metrics,
+            recent_errors,
+            ingestion_anomalies,
+        }
+    }
+}
+
+/// Maintenance runs [`HealthStatus::check`] looks back over for failures
+const HEALTH_MAINTENANCE_RUNS: usize = 50;
+
+/// One entry per job whose latest runs failed, counting the failures since
+/// its last completed run; skipped runs are passed over
+fn failing_maintenance_jobs(runs: &[MaintenanceRun]) -> Vec<ErrorInfo> {
+    let mut errors: Vec<ErrorInfo> = Vec::new();
+    let mut settled = std::collections::HashSet::new();
+
+    // Newest first, so a job is settled by its most recent completed run
+    for run in runs {
+        if settled.contains(&run.job) {
+            continue;
+        }
+        match run.status {
+            MaintenanceStatus::Completed => {
+                settled.insert(run.job);
+            }
+            MaintenanceStatus::Skipped => {}
+            MaintenanceStatus::Failed => {
+                let error_type = format!("maintenance.{}", run.job.as_str());
+                match errors.iter_mut().find(|e| e.error_type == error_type) {
+                    Some(error) => error.count += 1,
+                    None => errors.push(ErrorInfo {
+                        timestamp: run.started_at,
+                        error_type,
+                        message: run.message.clone().unwrap_or_default(),
+                        count: 1,
+                    }),
+                }
+            }
+        }
+    }
+    errors
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct MemoryUsage {
     pub total_memories: i64,
     pub active_memories: i64,
@@ -335,7 +960,11 @@ pub struct PerformanceMetrics {
     pub avg_save_time_ms: f32,
     pub queries_per_second: f32,
     pub saves_per_second: f32,
+    /// Share of lookups by ID the hot tier served without SQLite
     pub cache_hit_rate: f32,
+    /// Hot tier counters, reported by managers backed by a `Database`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hot_tier: Option<crate::database::hot_tier::HotTierStats>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -410,7 +1039,8 @@ impl PerformanceMonitor {
             } else {
                 0.0
             },
-            cache_hit_rate: 0.0, // TODO: Implement caching
+            cache_hit_rate: 0.0,
+            hot_tier: None,
         }
     }
 
@@ -438,6 +1068,64 @@ mod tests {
         assert!(!limiter.try_acquire(1));
     }
 
+    #[test]
+    fn test_rate_limiter_retry_after_and_timeout() {
+        let limiter = RateLimiter::new(2, 60); // a token a second
+        assert!(limiter.try_acquire(2));
+
+        let retry_after = limiter.try_acquire_with_retry_after(1).unwrap_err();
+        assert!(retry_after > Duration::from_millis(900) && retry_after <= Duration::from_secs(1));
+        // More than the bucket holds waits for a full bucket
+        assert!(limiter.try_acquire_with_retry_after(50).unwrap_err() <= Duration::from_secs(2));
+
+        let started = std::time::Instant::now();
+        let retry_after = limiter
+            .acquire_with_timeout("user1", 1, Duration::from_millis(50))
+            .unwrap_err();
+        assert!(started.elapsed() >= Duration::from_millis(50));
+        assert!(retry_after < Duration::from_secs(1));
+
+        // Clones share the bucket
+        let clone = limiter.clone();
+        assert!(clone
+            .acquire_with_timeout("user1", 1, Duration::from_secs(5))
+            .is_ok());
+        assert!(!limiter.try_acquire(1));
+    }
+
+    #[test]
+    fn test_rate_limiter_serves_keys_round_robin() {
+        let limiter = RateLimiter::new(1, 300); // a token every 200ms
+        assert!(limiter.try_acquire(1));
+        let served = Arc::new(Mutex::new(Vec::new()));
+
+        let spawn = |key: &'static str| {
+            let limiter = limiter.clone();
+            let served = served.clone();
+            let handle = std::thread::spawn(move || {
+                limiter
+                    .acquire_with_timeout(key, 1, Duration::from_secs(10))
+                    .unwrap();
+                served.lock().unwrap().push(key);
+            });
+            std::thread::sleep(Duration::from_millis(20));
+            handle
+        };
+        // Three callers for user1 queue up before one for user2, which is
+        // still served second
+        let handles: Vec<_> = ["user1", "user1", "user1", "user2"]
+            .into_iter()
+            .map(spawn)
+            .collect();
+        for handle in handles {
+            handle.join().unwrap();
+        }
+        assert_eq!(
+            *served.lock().unwrap(),
+            vec!["user1", "user2", "user1", "user1"]
+        );
+    }
+
     #[test]
     fn test_request_validator() {
         let config = MemexConfig {
@@ -468,6 +1156,37 @@ mod tests {
         assert!(validator.validate_memory_item(&valid_memory).is_ok());
     }
 
+    #[test]
+    fn test_content_limit_errors_are_structured() {
+        let config = MemexConfig {
+            max_content_bytes: 10,
+            ..Default::default()
+        };
+        let validator = RequestValidator::new(&config);
+
+        let memory = MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "test_session".to_string(),
+            content: "more than ten bytes".to_string(),
+            ..Default::default()
+        };
+
+        match validator.validate_memory_item(&memory) {
+            Err(ValidationError::InvalidInput {
+                field,
+                limit,
+                actual,
+                message,
+            }) => {
+                assert_eq!(field.as_deref(), Some("content"));
+                assert_eq!(limit, Some(10));
+                assert_eq!(actual, Some(19));
+                assert!(message.contains("content exceeds limit (19 > 10)"));
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+
     #[test]
     fn test_batch_response() {
         let mut response = BatchResponse::<String>::new();
@@ -521,4 +1240,33 @@ mod tests {
         config.importance_threshold = 1.5;
         assert!(config.validate().is_err());
     }
+
+    #[test]
+    fn test_failing_maintenance_jobs() {
+        let run = |job, minutes_ago, status| MaintenanceRun {
+            id: 0,
+            job,
+            started_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            completed_at: Utc::now() - chrono::Duration::minutes(minutes_ago),
+            status,
+            detail: None,
+            message: Some(format!("{} minutes ago", minutes_ago)),
+        };
+        // Newest first, as `Database::get_maintenance_runs` returns them
+        let runs = vec![
+            run(MaintenanceJob::Backup, 1, MaintenanceStatus::Failed),
+            run(MaintenanceJob::Decay, 2, MaintenanceStatus::Completed),
+            run(MaintenanceJob::Backup, 3, MaintenanceStatus::Skipped),
+            run(MaintenanceJob::Backup, 4, MaintenanceStatus::Failed),
+            run(MaintenanceJob::Decay, 5, MaintenanceStatus::Failed),
+            run(MaintenanceJob::Backup, 6, MaintenanceStatus::Completed),
+            run(MaintenanceJob::Backup, 7, MaintenanceStatus::Failed),
+        ];
+
+        let errors = failing_maintenance_jobs(&runs);
+        assert_eq!(errors.len(), 1, "decay recovered");
+        assert_eq!(errors[0].error_type, "maintenance.backup");
+        assert_eq!(errors[0].count, 2);
+        assert_eq!(errors[0].message, "1 minutes ago");
+    }
 }
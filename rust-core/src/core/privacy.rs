@@ -0,0 +1,280 @@
+//! Aggregate-only analytics
+//!
+//! An [`AnalyticsPrivacy`] rewrites analytics reports so they can be handed
+//! to people who mustn't see what users stored. Session names, tags and
+//! metadata are dropped, any group (a session, topic, day, hour or histogram
+//! bucket) with fewer than `min_group_size` memories is suppressed, and with
+//! `epsilon` set every count gets Laplace noise of scale `1 / epsilon`, the
+//! usual mechanism for counting queries under differential privacy.
+//!
+//! It's set per API key with `analytics_privacy`; such a key may read
+//! analytics and nothing else:
+//!
+//! ```json
+//! {
+//!   "name": "product-dashboard",
+//!   "key_sha256": "9f86d0…",
+//!   "role": "read_only",
+//!   "analytics_privacy": { "min_group_size": 10, "epsilon": 1.0 }
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::Validate;
+
+use crate::core::analytics::UserAnalytics;
+use crate::core::memory::UserMemoryStats;
+use crate::core::session::SessionAnalytics;
+use crate::database::models::{Session, ValueDistribution};
+
+/// How analytics are aggregated for privacy mode keys
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct AnalyticsPrivacy {
+    /// Groups with fewer memories than this are left out
+    #[validate(range(min = 1, max = 10000))]
+    pub min_group_size: usize,
+    /// Privacy budget of each count; smaller adds more noise, `None` adds none
+    #[validate(range(min = 0.01, max = 100.0))]
+    pub epsilon: Option<f64>,
+}
+
+impl Default for AnalyticsPrivacy {
+    fn default() -> Self {
+        Self {
+            min_group_size: 5,
+            epsilon: None,
+        }
+    }
+}
+
+impl AnalyticsPrivacy {
+    /// `count` with noise added, never below zero
+    pub fn noisy(&self, count: usize) -> usize {
+        let Some(epsilon) = self.epsilon else {
+            return count;
+        };
+        // Inverse CDF of the Laplace distribution
+        let u = fastrand::f64() - 0.5;
+        let noise = -u.signum() * (1.0 - 2.0 * u.abs()).ln() / epsilon;
+        (count as f64 + noise).round().max(0.0) as usize
+    }
+
+    /// `count` of one group, or `None` if the group is too small to show
+    pub fn group(&self, count: usize) -> Option<usize> {
+        (count >= self.min_group_size).then(|| self.noisy(count))
+    }
+
+    /// [`group`](Self::group), with suppressed groups shown as empty
+    fn cell(&self, count: usize) -> usize {
+        self.group(count).unwrap_or(0)
+    }
+
+    /// Keep only what identifies a session, not what it's about
+    fn session(&self, session: Session) -> Session {
+        Session {
+            name: None,
+            tags: Vec::new(),
+            metadata: HashMap::new(),
+            memory_count: self.noisy(session.memory_count),
+            ..session
+        }
+    }
+
+    fn distribution(&self, total: usize, distribution: ValueDistribution) -> ValueDistribution {
+        if total < self.min_group_size {
+            return ValueDistribution::default();
+        }
+        let mut distribution = distribution;
+        for bucket in &mut distribution.histogram {
+            bucket.count = self.cell(bucket.count as usize) as u64;
+        }
+        distribution
+    }
+
+    fn counts(&self, counts: HashMap<String, i32>) -> HashMap<String, i32> {
+        counts
+            .into_iter()
+            .filter_map(|(key, count)| Some((key, self.group(count.max(0) as usize)? as i32)))
+            .collect()
+    }
+
+    pub fn user_analytics(&self, analytics: UserAnalytics) -> UserAnalytics {
+        let memory_count = self.noisy(analytics.memory_count);
+        let top_sessions = analytics
+            .top_sessions
+            .into_iter()
+            .filter_map(|mut usage| {
+                usage.memory_count = self.group(usage.memory_count)?;
+                usage.name = None;
+                Some(usage)
+            })
+            .collect();
+        let topic_distribution = analytics
+            .topic_distribution
+            .into_iter()
+            .filter_map(|mut topic| {
+                topic.memory_count = self.group(topic.memory_count)?;
+                topic.share = topic.memory_count as f32 / memory_count.max(1) as f32;
+                Some(topic)
+            })
+            .collect();
+
+        UserAnalytics {
+            memory_count,
+            session_count: self.noisy(analytics.session_count),
+            compressed_memories: self.noisy(analytics.compressed_memories),
+            uncategorized_memories: self.noisy(analytics.uncategorized_memories),
+            top_sessions,
+            topic_distribution,
+            ..analytics
+        }
+    }
+
+    pub fn session_analytics(&self, analytics: SessionAnalytics) -> SessionAnalytics {
+        let mut activity = analytics.activity;
+        for count in activity.heatmap.iter_mut().flatten() {
+            *count = self.cell(*count);
+        }
+        for count in activity.by_hour.iter_mut().chain(&mut activity.by_weekday) {
+            *count = self.cell(*count);
+        }
+        // Keyed by session with no memory count to suppress on
+        activity.avg_importance_by_session.clear();
+
+        SessionAnalytics {
+            total_sessions: self.noisy(analytics.total_sessions),
+            total_memories: self.noisy(analytics.total_memories),
+            most_active_session: analytics.most_active_session.map(|s| self.session(s)),
+            most_recent_session: analytics.most_recent_session.map(|s| self.session(s)),
+            activity_by_day: analytics
+                .activity_by_day
+                .into_iter()
+                .filter_map(|(day, count)| Some((day, self.group(count)?)))
+                .collect(),
+            activity,
+            ..analytics
+        }
+    }
+
+    pub fn user_memory_stats(&self, stats: UserMemoryStats) -> UserMemoryStats {
+        let total = stats.total_memories.max(0) as usize;
+        UserMemoryStats {
+            total_memories: self.noisy(total) as i64,
+            importance_distribution: self.counts(stats.importance_distribution),
+            age_distribution: self.counts(stats.age_distribution),
+            importance_percentiles: self.distribution(total, stats.importance_percentiles),
+            content_bytes_percentiles: self.distribution(total, stats.content_bytes_percentiles),
+            session_storage: stats
+                .session_storage
+                .into_iter()
+                .filter_map(|mut usage| {
+                    usage.memory_count = self.group(usage.memory_count.max(0) as usize)? as i64;
+                    Some(usage)
+                })
+                .collect(),
+            ..stats
+        }
+    }
+
+    /// The store-wide figures of `Database::get_stats`, without the
+    /// database path and with per-user counts of small users left out
+    pub fn stats(&self, stats: serde_json::Value) -> serde_json::Value {
+        let serde_json::Value::Object(mut stats) = stats else {
+            return stats;
+        };
+        stats.remove("database_path");
+        if let Some(total) = stats.get("total_memories").and_then(|v| v.as_u64()) {
+            stats.insert("total_memories".into(), self.noisy(total as usize).into());
+        }
+
+        let user_counts = match stats.remove("user_counts") {
+            Some(serde_json::Value::Object(counts)) => counts
+                .into_iter()
+                .filter_map(|(user, count)| {
+                    let count = self.group(count.as_u64()? as usize)?;
+                    Some((user, count.into()))
+                })
+                .collect(),
+            _ => serde_json::Map::new(),
+        };
+        if let Some(serde_json::Value::Object(bytes)) = stats.get_mut("user_bytes") {
+            bytes.retain(|user, _| user_counts.contains_key(user));
+        }
+        stats.insert("user_counts".into(), user_counts.into());
+        stats.into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::analytics::{SessionUsage, TopicShare};
+
+    #[test]
+    fn test_small_groups_and_session_names_are_dropped() {
+        let privacy = AnalyticsPrivacy {
+            min_group_size: 3,
+            epsilon: None,
+        };
+        let analytics = privacy.user_analytics(UserAnalytics {
+            memory_count: 10,
+            top_sessions: vec![
+                SessionUsage {
+                    session_id: "s1".to_string(),
+                    name: Some("Divorce paperwork".to_string()),
+                    memory_count: 8,
+                    storage_bytes: 800,
+                },
+                SessionUsage {
+                    session_id: "s2".to_string(),
+                    name: None,
+                    memory_count: 2,
+                    storage_bytes: 200,
+                },
+            ],
+            topic_distribution: vec![
+                TopicShare {
+                    topic: "legal".to_string(),
+                    memory_count: 8,
+                    share: 0.8,
+                },
+                TopicShare {
+                    topic: "health".to_string(),
+                    memory_count: 2,
+                    share: 0.2,
+                },
+            ],
+            ..Default::default()
+        });
+
+        assert_eq!(analytics.memory_count, 10);
+        assert_eq!(analytics.top_sessions.len(), 1);
+        assert_eq!(analytics.top_sessions[0].name, None);
+        assert_eq!(analytics.topic_distribution.len(), 1);
+        assert_eq!(analytics.topic_distribution[0].topic, "legal");
+
+        let stats = privacy.stats(serde_json::json!({
+            "total_memories": 10,
+            "database_path": "/srv/memex.db",
+            "user_counts": { "alice": 8, "bob": 2 },
+            "user_bytes": { "alice": 800, "bob": 200 },
+        }));
+        assert!(stats.get("database_path").is_none());
+        assert_eq!(stats["user_counts"], serde_json::json!({ "alice": 8 }));
+        assert_eq!(stats["user_bytes"], serde_json::json!({ "alice": 800 }));
+    }
+
+    #[test]
+    fn test_noise_is_centered_on_the_count() {
+        let privacy = AnalyticsPrivacy {
+            min_group_size: 1,
+            epsilon: Some(1.0),
+        };
+        let samples = 2000;
+        let sum: usize = (0..samples).map(|_| privacy.noisy(100)).sum();
+        let mean = sum as f64 / samples as f64;
+        assert!((mean - 100.0).abs() < 1.0, "mean {}", mean);
+    }
+}
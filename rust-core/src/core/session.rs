@@ -1,17 +1,29 @@
 //! Session management and operations
 
 use anyhow::{Context, Result};
-use chrono::Utc;
-use std::collections::HashMap;
-
-use crate::core::{PerformanceMonitor, RequestValidator};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use crate::core::clustering::{self, MAX_CLUSTER_MEMORIES};
+use crate::core::context::RequestContext;
+use crate::core::counters::{CounterEvent, EventCounters};
+use crate::core::timeline::TimeBucket;
+use crate::core::{text, PerformanceMonitor, RequestValidator};
+#[cfg(feature = "vector-search")]
+use crate::database::vector::{SessionSearchResult, VectorSearchEngine};
 use crate::database::{models::*, Database};
+use crate::display::truncate_graphemes;
+use crate::logging;
 
 /// Session management service
 pub struct SessionManager {
     database: Database,
     validator: RequestValidator,
     monitor: PerformanceMonitor,
+    counters: Arc<EventCounters>,
+    #[cfg(feature = "vector-search")]
+    vector: Option<VectorSearchEngine>,
 }
 
 impl SessionManager {
@@ -20,13 +32,36 @@ impl SessionManager {
             database,
             validator,
             monitor: PerformanceMonitor::new(1000),
+            counters: Arc::new(EventCounters::new()),
+            #[cfg(feature = "vector-search")]
+            vector: None,
         }
     }
 
+    /// Enable semantic session search through `engine`
+    #[cfg(feature = "vector-search")]
+    pub fn enable_vector_search(&mut self, engine: VectorSearchEngine) {
+        self.vector = Some(engine);
+    }
+
+    /// Count searches in `counters`, typically the `MemoryManager`'s
+    pub fn use_counters(&mut self, counters: Arc<EventCounters>) {
+        self.counters = counters;
+    }
+
+    /// Run `op` on this manager inside `context`; see
+    /// `MemoryManager::in_context`
+    pub fn in_context<T>(&self, context: &RequestContext, op: impl FnOnce(&Self) -> T) -> T {
+        let _scope = context.enter();
+        op(self)
+    }
+
     /// Create a new session
     pub fn create_session(&self, user_id: &str, name: Option<String>) -> Result<String> {
+        let span = logging::span("create_session").with("user_id", user_id);
+
         // Rate limiting
-        self.validator.validate_request(1)?;
+        self.validator.validate_request_for(user_id, 1)?;
 
         // Validate user_id
         if user_id.trim().is_empty() || user_id.len() > 255 {
@@ -37,98 +72,465 @@ impl SessionManager {
             .database
             .create_session(user_id, name)
             .context("Failed to create session")?;
+        span.record("session_id", &session_id);
 
-        log::debug!("Created session {} for user {}", session_id, user_id);
+        tracing::debug!("Created session");
         Ok(session_id)
     }
 
-    /// Get sessions for a user with pagination
+    /// Get a user's active and closed sessions with pagination
     pub fn get_user_sessions(
         &self,
         user_id: &str,
         limit: Option<usize>,
         offset: Option<usize>,
+    ) -> Result<PaginatedResponse<Session>> {
+        self.get_user_sessions_with_status(user_id, &SessionStatus::LISTED, limit, offset)
+    }
+
+    /// Get a user's sessions in any of `statuses` with pagination
+    pub fn get_user_sessions_with_status(
+        &self,
+        user_id: &str,
+        statuses: &[SessionStatus],
+        limit: Option<usize>,
+        offset: Option<usize>,
     ) -> Result<PaginatedResponse<Session>> {
         let start = std::time::Instant::now();
+        let _span = logging::span("get_user_sessions").with("user_id", user_id);
 
         // Rate limiting
         self.validator.validate_request(1)?;
 
         let response = self
             .database
-            .get_user_sessions(user_id, limit, offset)
+            .get_user_sessions_with_status(user_id, statuses, limit, offset)
             .context("Failed to get user sessions")?;
 
         let duration = start.elapsed().as_millis() as f32;
         self.monitor.record_query_time(duration);
 
-        log::debug!(
-            "Retrieved {} sessions for user {} in {}ms",
-            response.data.len(),
-            user_id,
-            duration
+        tracing::debug!(
+            count = response.data.len(),
+            duration_ms = duration,
+            "Retrieved sessions"
         );
 
         Ok(response)
     }
 
-    /// Generate a summary for a session
+    /// Summary of a session, generated and stored unless the stored one is
+    /// still current
+    ///
+    /// A stored summary stays current until a memory is added to, edited in
+    /// or removed from its session.
     pub fn generate_session_summary(&self, session_id: &str) -> Result<SessionSummary> {
         let start = std::time::Instant::now();
+        let span = logging::span("generate_session_summary").with("session_id", session_id);
+
+        if let Some(stored) = self.database.get_session_summary(session_id)? {
+            if !stored.stale {
+                span.record("user_id", &stored.user_id);
+                tracing::debug!(version = stored.version, "Served stored session summary");
+                return Ok(stored);
+            }
+        }
 
         // Rate limiting (summary generation is expensive)
         self.validator.validate_request(5)?;
 
-        // Get all memories for the session
+        let summary = self.summarize(session_id)?;
+        span.record("user_id", &summary.user_id);
+        let summary = self.persist_summary(summary, false)?;
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            memory_count = summary.memory_count,
+            version = summary.version,
+            duration_ms = duration,
+            "Generated session summary"
+        );
+
+        Ok(summary)
+    }
+
+    /// Store `summary_text`, written by `generator` such as a language
+    /// model, as the summary of a session
+    ///
+    /// Key topics, the date range and importance are still worked out from
+    /// the session's memories.
+    pub fn save_session_summary_text(
+        &self,
+        session_id: &str,
+        summary_text: &str,
+        generator: SummaryGenerator,
+    ) -> Result<SessionSummary> {
+        if summary_text.trim().is_empty() {
+            return Err(anyhow::anyhow!("Summary text cannot be empty"));
+        }
+        self.validator.validate_request(1)?;
+
+        let summary = SessionSummary {
+            summary_text: summary_text.to_string(),
+            generator,
+            ..self.summarize(session_id)?
+        };
+        self.persist_summary(summary, false)
+    }
+
+    /// The stored summary of a session, which may be stale
+    pub fn get_session_summary(&self, session_id: &str) -> Result<Option<SessionSummary>> {
+        self.validator.validate_request(1)?;
+        self.database
+            .get_session_summary(session_id)
+            .context("Failed to get session summary")
+    }
+
+    /// A user's stored session summaries, most recently generated first
+    pub fn get_user_session_summaries(&self, user_id: &str) -> Result<Vec<SessionSummary>> {
+        self.validator.validate_request(1)?;
+        self.database
+            .get_user_session_summaries(user_id)
+            .context("Failed to get session summaries")
+    }
+
+    /// Summarize a session and store the summary as a memory in it,
+    /// replacing any summary stored before
+    ///
+    /// Not rate limited, unlike `generate_session_summary`, as decay stores
+    /// summaries for many sessions in one go.
+    pub fn store_session_summary(&self, session_id: &str) -> Result<SessionSummary> {
+        let summary = self.summarize(session_id)?;
+        self.persist_summary(summary, true)
+    }
+
+    /// Store `summary`, along with a memory holding it when `as_memory` is
+    /// set or its session already has one, so that memory stays current
+    fn persist_summary(&self, summary: SessionSummary, as_memory: bool) -> Result<SessionSummary> {
+        let as_memory = as_memory
+            || self
+                .database
+                .get_session_summary(&summary.session_id)?
+                .is_some_and(|stored| stored.memory_id.is_some());
+        if !as_memory {
+            return self.database.save_session_summary(&summary, None);
+        }
+
+        let mut metadata = HashMap::new();
+        metadata.insert(
+            TYPE_METADATA_KEY.to_string(),
+            SESSION_SUMMARY_TYPE.to_string(),
+        );
+        metadata.insert(
+            "key_topics".to_string(),
+            serde_json::to_string(&summary.key_topics)?,
+        );
+        metadata.insert("memory_count".to_string(), summary.memory_count.to_string());
+        metadata.insert(
+            "date_range_start".to_string(),
+            summary.date_range.0.to_rfc3339(),
+        );
+        metadata.insert(
+            "date_range_end".to_string(),
+            summary.date_range.1.to_rfc3339(),
+        );
+        metadata.insert("generator".to_string(), summary.generator.to_string());
+        let memory = MemoryItem {
+            user_id: summary.user_id.clone(),
+            session_id: summary.session_id.clone(),
+            content: summary.summary_text.clone(),
+            metadata,
+            importance: summary.importance_score,
+            source_type: Some(SourceType::Agent),
+            ..Default::default()
+        };
+        self.database.save_session_summary(&summary, Some(&memory))
+    }
+
+    /// Summaries of each day a user saved memories on since `since`, or
+    /// ever, generating those missing or stale
+    ///
+    /// Days are UTC calendar days, and `since` is rounded down to the start
+    /// of its day so the first one is summarized whole.
+    pub fn generate_day_summaries(
+        &self,
+        user_id: &str,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Summary>> {
+        let start = std::time::Instant::now();
+        let _span = logging::span("generate_day_summaries").with("user_id", user_id);
+        self.validator.validate_request(5)?;
+
         let filter = QueryFilter {
-            session_id: Some(session_id.to_string()),
-            limit: Some(1000), // Reasonable limit for summary generation
+            user_id: Some(user_id.to_string()),
+            date_from: since.map(|since| TimeBucket::Day.start_of(since)),
             ..Default::default()
         };
+        let mut days: BTreeMap<String, Vec<MemoryItem>> = BTreeMap::new();
+        for memory in self.summarizable_memories(&filter)? {
+            days.entry(memory.created_at.format("%Y-%m-%d").to_string())
+                .or_default()
+                .push(memory);
+        }
 
-        let memories_response = self.database.recall_memories(&filter)?;
-        let memories = memories_response.data;
+        let mut stored: HashMap<String, Summary> = self
+            .database
+            .get_summaries(user_id, SummaryGranularity::Day)?
+            .into_iter()
+            .map(|summary| (summary.key.clone(), summary))
+            .collect();
+        let mut current = Vec::new();
+        let mut generated = Vec::new();
+        for (day, memories) in days {
+            match stored.remove(&day) {
+                Some(summary) if !summary.stale => current.push(summary),
+                _ => {
+                    let summary = self.generate_intelligent_summary(&memories, "Day")?;
+                    generated.push(Summary {
+                        granularity: SummaryGranularity::Day,
+                        key: day,
+                        ..summary.into()
+                    });
+                }
+            }
+        }
+        let generated_count = generated.len();
+        current.extend(self.database.save_summaries(
+            user_id,
+            SummaryGranularity::Day,
+            &generated,
+            false,
+        )?);
+        current.sort_by(|a, b| a.key.cmp(&b.key));
 
-        if memories.is_empty() {
-            return Err(anyhow::anyhow!("No memories found for session"));
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+        tracing::debug!(
+            days = current.len(),
+            generated = generated_count,
+            duration_ms = duration,
+            "Generated day summaries"
+        );
+
+        Ok(current)
+    }
+
+    /// Summaries of the topics among a user's most recent memories, into
+    /// `k` topics or as many as fit best if `k` is None
+    ///
+    /// Topics are regenerated as a set, replacing the stored ones, unless
+    /// `k` is None and none of those is stale.
+    pub fn generate_topic_summaries(
+        &self,
+        user_id: &str,
+        k: Option<usize>,
+    ) -> Result<Vec<Summary>> {
+        let start = std::time::Instant::now();
+        let _span = logging::span("generate_topic_summaries").with("user_id", user_id);
+        if k == Some(0) {
+            return Err(anyhow::anyhow!("Topic count must be at least 1"));
         }
 
-        // Extract session info from first memory
-        let user_id = memories[0].user_id.clone();
+        let stored = self
+            .database
+            .get_summaries(user_id, SummaryGranularity::Topic)?;
+        if k.is_none() && !stored.is_empty() && stored.iter().all(|summary| !summary.stale) {
+            return Ok(stored);
+        }
+        self.validator.validate_request(5)?;
 
-        // Generate summary using advanced text processing
-        let summary = self.generate_intelligent_summary(&memories)?;
-
-        // Save summary to database
-        let summary_record = SessionSummary {
-            session_id: session_id.to_string(),
-            user_id: user_id.clone(),
-            summary_text: summary.summary_text.clone(),
-            key_topics: summary.key_topics.clone(),
-            memory_count: memories.len(),
-            date_range: summary.date_range,
-            importance_score: summary.importance_score,
-            created_at: Utc::now(),
-            updated_at: Utc::now(),
+        let filter = QueryFilter {
+            user_id: Some(user_id.to_string()),
+            limit: Some(MAX_CLUSTER_MEMORIES),
+            ..Default::default()
         };
+        let memories: Vec<MemoryItem> = self
+            .database
+            .recall_memories(&filter)?
+            .data
+            .into_iter()
+            .filter(|memory| !memory.is_session_summary())
+            .collect();
+        if memories.is_empty() {
+            return Err(anyhow::anyhow!("No memories found for user"));
+        }
 
-        // TODO: Save to database (implement save_session_summary in database)
+        let mut vectors = clustering::tfidf_vectors(&memories);
+        let assignments = clustering::assign(&mut vectors, k);
+        let mut topics: Vec<Vec<MemoryItem>> = Vec::new();
+        for (memory, topic) in memories.into_iter().zip(assignments) {
+            if topics.len() <= topic {
+                topics.resize_with(topic + 1, Vec::new);
+            }
+            topics[topic].push(memory);
+        }
+        topics.retain(|memories| !memories.is_empty());
+        topics.sort_by_key(|memories| std::cmp::Reverse(memories.len()));
+
+        let mut summaries: Vec<Summary> = Vec::new();
+        for (number, memories) in topics.iter().enumerate() {
+            let summary = self.generate_intelligent_summary(memories, "Topic")?;
+            let mut key = summary
+                .key_topics
+                .iter()
+                .take(3)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", ");
+            if key.is_empty() || summaries.iter().any(|summary| summary.key == key) {
+                key = format!("Topic {}", number + 1);
+            }
+            summaries.push(Summary {
+                granularity: SummaryGranularity::Topic,
+                key,
+                ..summary.into()
+            });
+        }
+        let stored =
+            self.database
+                .save_summaries(user_id, SummaryGranularity::Topic, &summaries, true)?;
 
         let duration = start.elapsed().as_millis() as f32;
         self.monitor.record_query_time(duration);
-
-        log::debug!(
-            "Generated summary for session {} with {} memories in {}ms",
-            session_id,
-            memories.len(),
-            duration
+        tracing::debug!(
+            topics = stored.len(),
+            duration_ms = duration,
+            "Generated topic summaries"
         );
 
-        Ok(summary_record)
+        Ok(stored)
     }
 
-    /// Generate an intelligent summary from memories
-    fn generate_intelligent_summary(&self, memories: &[MemoryItem]) -> Result<SessionSummary> {
+    /// A user's stored summaries at `granularity`, or at every granularity
+    /// finest first; stale ones included
+    pub fn list_summaries(
+        &self,
+        user_id: &str,
+        granularity: Option<SummaryGranularity>,
+    ) -> Result<Vec<Summary>> {
+        self.validator.validate_request(1)?;
+        let levels = match granularity {
+            Some(granularity) => vec![granularity],
+            None => SummaryGranularity::LEVELS.to_vec(),
+        };
+        let mut summaries = Vec::new();
+        for granularity in levels {
+            summaries.extend(self.stored_summaries(user_id, granularity)?);
+        }
+        Ok(summaries)
+    }
+
+    /// A user's stored summaries at the finest granularity that fits in
+    /// `budget_tokens`, most important first
+    ///
+    /// When even the coarsest stored level doesn't fit, its most important
+    /// summaries are taken until the budget runs out. Nothing is generated
+    /// here; levels without stored summaries are skipped.
+    pub fn summaries_for_budget(
+        &self,
+        user_id: &str,
+        budget_tokens: usize,
+    ) -> Result<SummarySelection> {
+        self.validator.validate_request(1)?;
+
+        let mut coarsest = None;
+        for granularity in SummaryGranularity::LEVELS {
+            let mut summaries = self.stored_summaries(user_id, granularity)?;
+            if summaries.is_empty() {
+                continue;
+            }
+            summaries.sort_by(|a, b| b.importance_score.total_cmp(&a.importance_score));
+            let tokens = summaries.iter().map(Summary::count_tokens).sum();
+            if tokens <= budget_tokens {
+                return Ok(SummarySelection {
+                    granularity,
+                    summaries,
+                    tokens,
+                    truncated: false,
+                });
+            }
+            coarsest = Some((granularity, summaries));
+        }
+
+        let Some((granularity, summaries)) = coarsest else {
+            return Ok(SummarySelection {
+                granularity: SummaryGranularity::Session,
+                summaries: Vec::new(),
+                tokens: 0,
+                truncated: false,
+            });
+        };
+        let mut selected = Vec::new();
+        let mut tokens = 0;
+        for summary in summaries {
+            let summary_tokens = summary.count_tokens();
+            if tokens + summary_tokens <= budget_tokens {
+                tokens += summary_tokens;
+                selected.push(summary);
+            }
+        }
+        Ok(SummarySelection {
+            granularity,
+            summaries: selected,
+            tokens,
+            truncated: true,
+        })
+    }
+
+    fn stored_summaries(
+        &self,
+        user_id: &str,
+        granularity: SummaryGranularity,
+    ) -> Result<Vec<Summary>> {
+        match granularity {
+            SummaryGranularity::Session => Ok(self
+                .database
+                .get_user_session_summaries(user_id)?
+                .into_iter()
+                .map(Summary::from)
+                .collect()),
+            _ => self.database.get_summaries(user_id, granularity),
+        }
+    }
+
+    /// Summary of every memory in a session except earlier summaries
+    fn summarize(&self, session_id: &str) -> Result<SessionSummary> {
+        let filter = QueryFilter {
+            session_id: Some(session_id.to_string()),
+            ..Default::default()
+        };
+        let memories = self.summarizable_memories(&filter)?;
+        if memories.is_empty() {
+            return Err(anyhow::anyhow!("No memories found for session"));
+        }
+
+        // Generate summary using advanced text processing
+        self.generate_intelligent_summary(&memories, "Session")
+    }
+
+    /// Every memory matching `filter` except stored session summaries
+    fn summarizable_memories(&self, filter: &QueryFilter) -> Result<Vec<MemoryItem>> {
+        let mut memories = Vec::new();
+        self.database.scan_memories(filter, 1000, |batch| {
+            memories.extend(
+                batch
+                    .into_iter()
+                    .filter(|memory| !memory.is_session_summary()),
+            );
+            Ok(())
+        })?;
+        Ok(memories)
+    }
+
+    /// Generate an intelligent summary from memories, describing them as
+    /// `subject`'s
+    fn generate_intelligent_summary(
+        &self,
+        memories: &[MemoryItem],
+        subject: &str,
+    ) -> Result<SessionSummary> {
         if memories.is_empty() {
             return Err(anyhow::anyhow!("Cannot summarize empty memory list"));
         }
@@ -144,7 +546,7 @@ impl SessionManager {
         let key_topics = self.extract_key_topics(&sorted_memories)?;
 
         // Generate summary text
-        let summary_text = self.generate_summary_text(&sorted_memories, &key_topics)?;
+        let summary_text = self.generate_summary_text(&sorted_memories, &key_topics, subject)?;
 
         // Calculate date range
         let date_range = (
@@ -166,6 +568,11 @@ impl SessionManager {
             importance_score,
             created_at: Utc::now(),
             updated_at: Utc::now(),
+            version: 0,
+            generator: SummaryGenerator::Heuristic,
+            generated_at: Utc::now(),
+            stale: false,
+            memory_id: None,
         })
     }
 
@@ -225,6 +632,7 @@ impl SessionManager {
         &self,
         memories: &[MemoryItem],
         key_topics: &[String],
+        subject: &str,
     ) -> Result<String> {
         let memory_count = memories.len();
         let time_span = if memories.len() > 1 {
@@ -250,18 +658,13 @@ impl SessionManager {
         let highlights = important_memories
             .iter()
             .take(3)
-            .map(|m| {
-                if m.content.len() > 100 {
-                    format!("\"{}...\"", &m.content[..100])
-                } else {
-                    format!("\"{}\"", m.content)
-                }
-            })
+            .map(|m| format!("\"{}\"", truncate_graphemes(&m.content, 100)))
             .collect::<Vec<_>>();
 
         // Construct summary
         let mut summary = format!(
-            "Session contains {} memories{}.{}",
+            "{} contains {} memories{}.{}",
+            subject,
             memory_count,
             time_span,
             if key_topics.is_empty() {
@@ -297,119 +700,21 @@ impl SessionManager {
 
     /// Tokenize text and filter out stop words and short words
     fn tokenize_and_filter(&self, text: &str) -> Vec<String> {
-        text.split_whitespace()
-            .filter_map(|word| {
-                let cleaned = word
-                    .chars()
-                    .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
-                    .collect::<String>();
-
-                if cleaned.len() >= 3 && !self.is_stop_word(&cleaned) {
-                    Some(cleaned)
-                } else {
-                    None
-                }
-            })
-            .collect()
-    }
-
-    /// Check if a word is a stop word
-    fn is_stop_word(&self, word: &str) -> bool {
-        matches!(
-            word.to_lowercase().as_str(),
-            "the"
-                | "and"
-                | "or"
-                | "but"
-                | "in"
-                | "on"
-                | "at"
-                | "to"
-                | "for"
-                | "of"
-                | "with"
-                | "by"
-                | "from"
-                | "up"
-                | "about"
-                | "into"
-                | "through"
-                | "during"
-                | "before"
-                | "after"
-                | "above"
-                | "below"
-                | "between"
-                | "among"
-                | "this"
-                | "that"
-                | "these"
-                | "those"
-                | "was"
-                | "were"
-                | "are"
-                | "is"
-                | "been"
-                | "being"
-                | "have"
-                | "has"
-                | "had"
-                | "will"
-                | "would"
-                | "could"
-                | "should"
-                | "may"
-                | "might"
-                | "can"
-                | "must"
-                | "shall"
-                | "am"
-                | "do"
-                | "does"
-                | "did"
-                | "done"
-                | "get"
-                | "got"
-                | "getting"
-                | "very"
-                | "much"
-                | "more"
-                | "most"
-                | "many"
-                | "some"
-                | "any"
-                | "all"
-                | "each"
-                | "every"
-                | "few"
-                | "several"
-                | "other"
-                | "another"
-                | "such"
-                | "only"
-                | "own"
-                | "same"
-                | "so"
-                | "than"
-                | "too"
-                | "just"
-                | "now"
-                | "here"
-                | "there"
-                | "when"
-                | "where"
-                | "why"
-                | "how"
-                | "what"
-                | "which"
-                | "who"
-        )
+        text::tokenize(text)
     }
 
     /// Search sessions by content keywords
     pub fn search_sessions(&self, user_id: &str, keywords: Vec<String>) -> Result<Vec<Session>> {
+        let result = self.find_sessions(user_id, keywords);
+        self.counters.record(CounterEvent::Search, &result);
+        result
+    }
+
+    fn find_sessions(&self, user_id: &str, keywords: Vec<String>) -> Result<Vec<Session>> {
+        let _span = logging::span("search_sessions").with("user_id", user_id);
+
         // Rate limiting
-        self.validator.validate_request(2)?;
+        self.validator.validate_request_for(user_id, 2)?;
 
         if keywords.is_empty() {
             return Ok(Vec::new());
@@ -442,11 +747,112 @@ impl SessionManager {
         }
 
         // Sort by last_active descending
-        matching_sessions.sort_by(|a, b| b.last_active.cmp(&a.last_active));
+        matching_sessions.sort_by_key(|s| std::cmp::Reverse(s.last_active));
 
         Ok(matching_sessions)
     }
 
+    /// Search a user's sessions by meaning rather than keywords
+    ///
+    /// Each session is represented by the importance-weighted mean of its
+    /// memory embeddings from `model_name`; sessions without embedded
+    /// memories never match.
+    #[cfg(feature = "vector-search")]
+    pub fn search_sessions_semantic(
+        &self,
+        user_id: &str,
+        query_embedding: &[f32],
+        model_name: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<SessionSearchResult>> {
+        let start = std::time::Instant::now();
+        let _span = logging::span("search_sessions_semantic").with("user_id", user_id);
+
+        // Rate limiting
+        self.validator.validate_request(2)?;
+
+        let engine = self
+            .vector
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Vector search is not enabled"))?;
+        let results = engine
+            .search_sessions(user_id, query_embedding, model_name, limit)
+            .context("Failed to search session embeddings");
+        self.counters.record(CounterEvent::Search, &results);
+        let results = results?;
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            count = results.len(),
+            duration_ms = duration,
+            "Searched sessions semantically"
+        );
+        Ok(results)
+    }
+
+    /// Memories of a session in conversation order, optionally limited to
+    /// the `seq` range `from..=to`
+    pub fn get_transcript(
+        &self,
+        session_id: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<Vec<MemoryItem>> {
+        let start = std::time::Instant::now();
+        let _span = logging::span("get_transcript").with("session_id", session_id);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        let result = self
+            .database
+            .get_session_memories_by_seq(session_id, from, to)
+            .context("Failed to get session transcript");
+        self.counters.record(CounterEvent::Recall, &result);
+        let memories = result?;
+
+        let duration = start.elapsed().as_millis() as f32;
+        self.monitor.record_query_time(duration);
+
+        tracing::debug!(
+            count = memories.len(),
+            duration_ms = duration,
+            "Retrieved transcript"
+        );
+        Ok(memories)
+    }
+
+    /// Move a memory to position `to_seq` within its session, shifting the
+    /// memories in between; returns its new position, or `None` if the
+    /// memory doesn't exist
+    pub fn move_memory(&self, memory_id: &str, to_seq: i64) -> Result<Option<i64>> {
+        let _span = logging::span("move_memory").with("memory_id", memory_id);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database
+            .move_memory(memory_id, to_seq)
+            .context("Failed to move memory")
+    }
+
+    /// Move a session to `status`; false if it doesn't exist
+    ///
+    /// Archived sessions drop out of listings and searches until saving a
+    /// memory into them makes them active again.
+    pub fn set_session_status(&self, session_id: &str, status: SessionStatus) -> Result<bool> {
+        let _span = logging::span("set_session_status").with("session_id", session_id);
+
+        // Rate limiting
+        self.validator.validate_request(1)?;
+
+        self.database
+            .set_session_status(session_id, status)
+            .context("Failed to set session status")
+    }
+
     /// Update session metadata
     pub fn update_session(
         &self,
@@ -454,16 +860,13 @@ impl SessionManager {
         name: Option<String>,
         tags: Option<Vec<String>>,
     ) -> Result<bool> {
+        let _span = logging::span("update_session").with("session_id", session_id);
+
         // Rate limiting
         self.validator.validate_request(1)?;
 
         // TODO: Implement update_session in database layer
-        log::debug!(
-            "Updated session {} with name: {:?}, tags: {:?}",
-            session_id,
-            name,
-            tags
-        );
+        tracing::debug!(name = ?name, tags = ?tags, "Updated session");
 
         // For now, return true (would implement actual update in database)
         Ok(true)
@@ -471,33 +874,40 @@ impl SessionManager {
 
     /// Delete a session and optionally its memories
     pub fn delete_session(&self, session_id: &str, delete_memories: bool) -> Result<bool> {
+        let _span = logging::span("delete_session").with("session_id", session_id);
+
         // Rate limiting (deletion is expensive)
         self.validator.validate_request(5)?;
 
         if delete_memories {
-            // First, get all memories in the session
+            // Delete page by page; the filter caps page size at 1000
             let filter = QueryFilter {
                 session_id: Some(session_id.to_string()),
-                limit: Some(10000), // Large limit to get all memories
+                limit: Some(1000),
                 ..Default::default()
             };
 
-            let memories_response = self.database.recall_memories(&filter)?;
+            let mut deleted = 0;
+            loop {
+                let page = self.database.recall_memories(&filter)?;
+                let mut deleted_in_page = 0;
+                for memory in &page.data {
+                    if self.database.delete_memory(&memory.id)? {
+                        deleted_in_page += 1;
+                    }
+                }
 
-            // Delete each memory
-            for memory in memories_response.data {
-                self.database.delete_memory(&memory.id)?;
+                deleted += deleted_in_page;
+                if deleted_in_page == 0 {
+                    break;
+                }
             }
 
-            log::info!(
-                "Deleted {} memories from session {}",
-                memories_response.total_count,
-                session_id
-            );
+            tracing::info!(count = deleted, "Deleted session memories");
         }
 
         // TODO: Implement delete_session in database layer
-        log::debug!("Deleted session {}", session_id);
+        tracing::debug!("Deleted session");
 
         Ok(true)
     }
@@ -531,6 +941,11 @@ impl SessionManager {
             0.0
         };
 
+        let activity = self
+            .database
+            .get_user_activity(user_id)
+            .context("Failed to compute session activity")?;
+
         Ok(SessionAnalytics {
             user_id: user_id.to_string(),
             total_sessions,
@@ -539,6 +954,7 @@ impl SessionManager {
             most_active_session,
             most_recent_session,
             activity_by_day,
+            activity,
         })
     }
 
@@ -558,6 +974,9 @@ pub struct SessionAnalytics {
     pub most_active_session: Option<Session>,
     pub most_recent_session: Option<Session>,
     pub activity_by_day: HashMap<String, usize>,
+    /// Hour/weekday heatmap, per-session importance, gaps and streaks
+    #[serde(default)]
+    pub activity: UserActivity,
 }
 
 impl Default for SessionAnalytics {
@@ -570,6 +989,7 @@ impl Default for SessionAnalytics {
             most_active_session: None,
             most_recent_session: None,
             activity_by_day: HashMap::new(),
+            activity: UserActivity::default(),
         }
     }
 }
@@ -631,6 +1051,195 @@ mod tests {
         assert!(names.contains(&&"Session 2".to_string()));
     }
 
+    #[test]
+    fn test_session_status_transitions() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let open = manager
+            .create_session("test_user", Some("Open".to_string()))
+            .unwrap();
+        let old = manager
+            .create_session("test_user", Some("Old".to_string()))
+            .unwrap();
+        for session_id in [&open, &old] {
+            manager
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session_id.clone(),
+                    content: "Portfolio rebalancing notes".to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        assert!(manager
+            .set_session_status(&old, SessionStatus::Archived)
+            .unwrap());
+        assert!(!manager
+            .set_session_status("missing", SessionStatus::Closed)
+            .unwrap());
+
+        // Archived sessions drop out of listings and searches by default
+        let listed = manager.get_user_sessions("test_user", None, None).unwrap();
+        assert_eq!(listed.total_count, 1);
+        assert_eq!(listed.data[0].id, open);
+        let found = manager
+            .search_sessions("test_user", vec!["portfolio".to_string()])
+            .unwrap();
+        assert_eq!(found.len(), 1);
+
+        let all = manager
+            .get_user_sessions_with_status("test_user", &SessionStatus::ALL, None, None)
+            .unwrap();
+        assert_eq!(all.total_count, 2);
+        let archived = manager
+            .get_user_sessions_with_status("test_user", &[SessionStatus::Archived], None, None)
+            .unwrap();
+        assert_eq!(archived.data[0].id, old);
+        assert_eq!(archived.data[0].status, SessionStatus::Archived);
+
+        // Closing keeps a session listed; new memories revive archived ones
+        manager
+            .set_session_status(&open, SessionStatus::Closed)
+            .unwrap();
+        manager
+            .database
+            .save_memory(&MemoryItem {
+                user_id: "test_user".to_string(),
+                session_id: old.clone(),
+                content: "Back to this one".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+        let listed = manager.get_user_sessions("test_user", None, None).unwrap();
+        let status_of = |id: &str| listed.data.iter().find(|s| s.id == id).unwrap().status;
+        assert_eq!(status_of(&open), SessionStatus::Closed);
+        assert_eq!(status_of(&old), SessionStatus::Active);
+    }
+
+    #[test]
+    fn test_stored_summaries_refresh_when_the_session_changes() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let session_id = manager.create_session("test_user", None).unwrap();
+        let save = |content: &str| {
+            manager
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session_id.clone(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        save("Portfolio rebalancing notes");
+        save("Bond ladder for the next five years");
+
+        let first = manager.generate_session_summary(&session_id).unwrap();
+        assert_eq!(first.version, 1);
+        assert_eq!(first.generator, SummaryGenerator::Heuristic);
+        assert!(!first.stale);
+
+        // Served as stored until a memory arrives in the session
+        let cached = manager.generate_session_summary(&session_id).unwrap();
+        assert_eq!(cached.version, 1);
+        assert_eq!(cached.generated_at, first.generated_at);
+
+        save("Rebalance quarterly");
+        let stored = manager.get_session_summary(&session_id).unwrap().unwrap();
+        assert!(stored.stale);
+        let second = manager.generate_session_summary(&session_id).unwrap();
+        assert_eq!(second.version, 2);
+        assert_eq!(second.memory_count, 3);
+        assert!(!second.stale);
+
+        let written = manager
+            .save_session_summary_text(&session_id, "Rebalancing plans", SummaryGenerator::Llm)
+            .unwrap();
+        assert_eq!(written.version, 3);
+        assert_eq!(written.generator, SummaryGenerator::Llm);
+        assert_eq!(written.summary_text, "Rebalancing plans");
+        assert_eq!(written.memory_count, 3);
+        assert!(written.memory_id.is_none());
+
+        let listed = manager.get_user_session_summaries("test_user").unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].summary_text, "Rebalancing plans");
+        assert!(manager.get_session_summary("missing").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_day_and_topic_summaries_and_budget_selection() {
+        let (manager, _temp_dir) = setup_test_manager();
+        let save = |session_id: &str, content: &str| {
+            manager
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session_id.to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        save("s1", "Portfolio rebalancing notes");
+        save("s1", "Bond ladder for the next five years");
+        save("s2", "Hiking trip to the lakes");
+        save("s2", "Hiking boots need replacing");
+        for session_id in ["s1", "s2"] {
+            manager.generate_session_summary(session_id).unwrap();
+        }
+
+        let days = manager.generate_day_summaries("test_user", None).unwrap();
+        assert_eq!(days.len(), 1);
+        assert_eq!(days[0].key, Utc::now().format("%Y-%m-%d").to_string());
+        assert_eq!(days[0].memory_count, 4);
+        let topics = manager
+            .generate_topic_summaries("test_user", Some(2))
+            .unwrap();
+        assert_eq!(topics.len(), 2);
+        assert_eq!(
+            topics.iter().map(|topic| topic.memory_count).sum::<usize>(),
+            4
+        );
+
+        // Stored summaries are reused until the user's memories change
+        let again = manager.generate_day_summaries("test_user", None).unwrap();
+        assert_eq!(again[0].version, 1);
+        assert_eq!(
+            manager.generate_topic_summaries("test_user", None).unwrap()[0].version,
+            1
+        );
+        save("s2", "Booked the campsite");
+        let stored = manager
+            .list_summaries("test_user", Some(SummaryGranularity::Topic))
+            .unwrap();
+        assert!(stored.iter().all(|summary| summary.stale));
+        let again = manager.generate_day_summaries("test_user", None).unwrap();
+        assert_eq!(again[0].version, 2);
+        assert_eq!(again[0].memory_count, 5);
+
+        // The finest level that fits wins; sessions are finer than the day
+        let sessions: usize = manager
+            .list_summaries("test_user", Some(SummaryGranularity::Session))
+            .unwrap()
+            .iter()
+            .map(Summary::count_tokens)
+            .sum();
+        let selection = manager.summaries_for_budget("test_user", sessions).unwrap();
+        assert_eq!(selection.granularity, SummaryGranularity::Session);
+        assert_eq!(selection.summaries.len(), 2);
+        let day_tokens = again[0].count_tokens();
+        let selection = manager
+            .summaries_for_budget("test_user", day_tokens.max(sessions - 1))
+            .unwrap();
+        assert_ne!(selection.granularity, SummaryGranularity::Session);
+        assert!(selection.tokens <= day_tokens.max(sessions - 1));
+        let selection = manager.summaries_for_budget("test_user", 0).unwrap();
+        assert!(selection.truncated);
+        assert!(selection.summaries.is_empty());
+    }
+
     #[test]
     fn test_key_topic_extraction() {
         let (manager, _temp_dir) = setup_test_manager();
@@ -685,6 +1294,63 @@ mod tests {
         assert!(analytics.most_recent_session.is_some());
     }
 
+    #[test]
+    fn test_user_activity() {
+        use chrono::{Datelike, Duration, NaiveTime};
+
+        let (manager, _temp_dir) = setup_test_manager();
+        let midnight = Utc::now().date_naive().and_time(NaiveTime::MIN).and_utc();
+        let entries = [
+            (0, 9, "work", 0.8),
+            (1, 9, "work", 0.4),
+            (2, 21, "home", 0.2),
+            (10, 9, "home", 0.6),
+            (11, 9, "home", 0.4),
+        ];
+        for (days_ago, hour, session, importance) in entries {
+            let created_at = midnight - Duration::days(days_ago) + Duration::hours(hour);
+            manager
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: "test_user".to_string(),
+                    session_id: session.to_string(),
+                    content: format!("Note from {} days ago", days_ago),
+                    importance,
+                    created_at,
+                    updated_at: created_at,
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+
+        let activity = manager.database.get_user_activity("test_user").unwrap();
+        assert_eq!(activity.by_hour[9], 4);
+        assert_eq!(activity.by_hour[21], 1);
+        assert_eq!(activity.by_weekday.iter().sum::<usize>(), 5);
+        let weekday = |days_ago| {
+            (midnight - Duration::days(days_ago))
+                .weekday()
+                .num_days_from_monday() as usize
+        };
+        assert_eq!(activity.heatmap[weekday(2)][21], 1);
+        assert!(activity.heatmap[weekday(0)][9] >= 1);
+
+        assert!((activity.avg_importance_by_session["work"] - 0.6).abs() < 1e-6);
+        assert!((activity.avg_importance_by_session["home"] - 0.4).abs() < 1e-6);
+
+        let gap = activity.longest_gap.unwrap();
+        assert_eq!(gap.from, midnight - Duration::days(10) + Duration::hours(9));
+        assert_eq!(gap.duration(), Duration::days(8) + Duration::hours(12));
+
+        assert_eq!(activity.current_streak_days, 3);
+        assert_eq!(activity.longest_streak_days, 3);
+
+        assert_eq!(
+            manager.database.get_user_activity("nobody").unwrap(),
+            UserActivity::default()
+        );
+    }
+
     #[test]
     fn test_tokenization_and_filtering() {
         let (manager, _temp_dir) = setup_test_manager();
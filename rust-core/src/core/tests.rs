@@ -249,6 +249,7 @@ mod tests {
         let batch_request = crate::core::BatchRequest {
             items: memories,
             fail_on_error: false,
+            quota_policy: Default::default(),
         };
         
         let batch_response = memory_manager.save_memories_batch(batch_request).unwrap();
@@ -0,0 +1,114 @@
+//! Tokenization shared by the text analytics
+
+/// Split text into lowercase words, dropping punctuation, stop words and
+/// words shorter than three characters
+pub fn tokenize(text: &str) -> Vec<String> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let cleaned = word
+                .chars()
+                .filter(|c| c.is_alphanumeric() || *c == '-' || *c == '_')
+                .collect::<String>()
+                .to_lowercase();
+
+            if cleaned.len() >= 3 && !is_stop_word(&cleaned) {
+                Some(cleaned)
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+/// Check if a word is a stop word
+pub fn is_stop_word(word: &str) -> bool {
+    matches!(
+        word.to_lowercase().as_str(),
+        "the"
+            | "and"
+            | "or"
+            | "but"
+            | "in"
+            | "on"
+            | "at"
+            | "to"
+            | "for"
+            | "of"
+            | "with"
+            | "by"
+            | "from"
+            | "up"
+            | "about"
+            | "into"
+            | "through"
+            | "during"
+            | "before"
+            | "after"
+            | "above"
+            | "below"
+            | "between"
+            | "among"
+            | "this"
+            | "that"
+            | "these"
+            | "those"
+            | "was"
+            | "were"
+            | "are"
+            | "is"
+            | "been"
+            | "being"
+            | "have"
+            | "has"
+            | "had"
+            | "will"
+            | "would"
+            | "could"
+            | "should"
+            | "may"
+            | "might"
+            | "can"
+            | "must"
+            | "shall"
+            | "am"
+            | "do"
+            | "does"
+            | "did"
+            | "done"
+            | "get"
+            | "got"
+            | "getting"
+            | "very"
+            | "much"
+            | "more"
+            | "most"
+            | "many"
+            | "some"
+            | "any"
+            | "all"
+            | "each"
+            | "every"
+            | "few"
+            | "several"
+            | "other"
+            | "another"
+            | "such"
+            | "only"
+            | "own"
+            | "same"
+            | "so"
+            | "than"
+            | "too"
+            | "just"
+            | "now"
+            | "here"
+            | "there"
+            | "when"
+            | "where"
+            | "why"
+            | "how"
+            | "what"
+            | "which"
+            | "who"
+    )
+}
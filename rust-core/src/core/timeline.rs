@@ -0,0 +1,173 @@
+//! Memories grouped into calendar days or weeks for timeline views
+
+use chrono::{DateTime, Datelike, Duration, NaiveTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::database::models::MemoryItem;
+
+/// Most important memories listed per bucket
+pub const TIMELINE_HIGHLIGHTS: usize = 3;
+
+/// Most memories one timeline covers; older ones are left out
+pub const MAX_TIMELINE_MEMORIES: usize = 10_000;
+
+/// Length of a timeline bucket, in UTC calendar terms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeBucket {
+    #[default]
+    Day,
+    /// Monday to Sunday
+    Week,
+}
+
+impl TimeBucket {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimeBucket::Day => "day",
+            TimeBucket::Week => "week",
+        }
+    }
+
+    /// Start of the bucket `time` falls in
+    pub fn start_of(self, time: DateTime<Utc>) -> DateTime<Utc> {
+        let day = time.date_naive();
+        let day = match self {
+            TimeBucket::Day => day,
+            TimeBucket::Week => day - Duration::days(day.weekday().num_days_from_monday() as i64),
+        };
+        day.and_time(NaiveTime::MIN).and_utc()
+    }
+
+    pub fn duration(self) -> Duration {
+        match self {
+            TimeBucket::Day => Duration::days(1),
+            TimeBucket::Week => Duration::weeks(1),
+        }
+    }
+}
+
+impl fmt::Display for TimeBucket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for TimeBucket {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "day" => Ok(TimeBucket::Day),
+            "week" => Ok(TimeBucket::Week),
+            other => Err(format!("unknown time bucket '{}' (use day or week)", other)),
+        }
+    }
+}
+
+/// Memories created in one day or week
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimelineBucket {
+    pub start: DateTime<Utc>,
+    pub count: usize,
+    /// Up to [`TIMELINE_HIGHLIGHTS`] memories, most important first
+    pub highlights: Vec<MemoryItem>,
+}
+
+/// Result of [`crate::core::memory::MemoryManager::recall_timeline`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Timeline {
+    pub bucket: TimeBucket,
+    /// Buckets with at least one memory, oldest first
+    pub buckets: Vec<TimelineBucket>,
+    pub memory_count: usize,
+    /// More memories matched than [`MAX_TIMELINE_MEMORIES`]; the oldest
+    /// are missing
+    pub truncated: bool,
+}
+
+/// Group `memories` by the bucket their creation time falls in
+pub fn group(memories: Vec<MemoryItem>, bucket: TimeBucket, truncated: bool) -> Timeline {
+    let memory_count = memories.len();
+    let mut by_start: BTreeMap<DateTime<Utc>, Vec<MemoryItem>> = BTreeMap::new();
+    for memory in memories {
+        by_start
+            .entry(bucket.start_of(memory.created_at))
+            .or_default()
+            .push(memory);
+    }
+
+    let buckets = by_start
+        .into_iter()
+        .map(|(start, mut memories)| {
+            let count = memories.len();
+            // Ties go to the newer memory
+            memories.sort_by(|a, b| {
+                b.importance
+                    .total_cmp(&a.importance)
+                    .then(b.created_at.cmp(&a.created_at))
+            });
+            memories.truncate(TIMELINE_HIGHLIGHTS);
+            TimelineBucket {
+                start,
+                count,
+                highlights: memories,
+            }
+        })
+        .collect();
+
+    Timeline {
+        bucket,
+        buckets,
+        memory_count,
+        truncated,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn memory(content: &str, created_at: DateTime<Utc>, importance: f32) -> MemoryItem {
+        MemoryItem {
+            content: content.to_string(),
+            created_at,
+            importance,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_day_and_week() {
+        // 2026-10-12 is a Monday
+        let at = |day: u32, hour: u32| Utc.with_ymd_and_hms(2026, 10, day, hour, 0, 0).unwrap();
+        let memories = vec![
+            memory("Late sunday", at(11, 23), 0.2),
+            memory("Monday low", at(12, 8), 0.1),
+            memory("Monday high", at(12, 9), 0.9),
+            memory("Monday mid", at(12, 10), 0.5),
+            memory("Monday tie", at(12, 11), 0.5),
+            memory("Friday", at(16, 12), 0.4),
+        ];
+
+        let days = group(memories.clone(), TimeBucket::Day, false);
+        assert_eq!(days.memory_count, 6);
+        let starts: Vec<_> = days.buckets.iter().map(|b| b.start).collect();
+        assert_eq!(starts, vec![at(11, 0), at(12, 0), at(16, 0)]);
+        let monday = &days.buckets[1];
+        assert_eq!(monday.count, 4);
+        let highlights: Vec<_> = monday.highlights.iter().map(|m| &m.content[..]).collect();
+        assert_eq!(highlights, vec!["Monday high", "Monday tie", "Monday mid"]);
+
+        let weeks = group(memories, TimeBucket::Week, false);
+        let counts: Vec<_> = weeks.buckets.iter().map(|b| (b.start, b.count)).collect();
+        assert_eq!(counts, vec![(at(5, 0), 1), (at(12, 0), 5)]);
+
+        assert_eq!("Week".parse::<TimeBucket>(), Ok(TimeBucket::Week));
+        assert!("month".parse::<TimeBucket>().is_err());
+    }
+}
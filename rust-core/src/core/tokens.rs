@@ -0,0 +1,153 @@
+//! Token counting for fitting memories and summaries into model context
+//!
+//! Counts go through a process-wide [`Tokenizer`], by default the
+//! [`HeuristicTokenizer`] estimate of a token per four characters. Built
+//! with the `tiktoken` feature, [`TiktokenTokenizer`] counts exactly with
+//! OpenAI's BPE encodings; install one with [`set_tokenizer`].
+
+use std::sync::{Arc, RwLock};
+
+/// Counts the tokens a piece of text takes up for some model
+pub trait Tokenizer: Send + Sync {
+    /// Shown in stats next to the counts it produced
+    fn name(&self) -> &str;
+
+    fn count_tokens(&self, text: &str) -> usize;
+}
+
+/// A token per four characters, rounded up; close for English prose with
+/// most BPE vocabularies, and free to compute
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HeuristicTokenizer;
+
+impl Tokenizer for HeuristicTokenizer {
+    fn name(&self) -> &str {
+        "heuristic"
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        text.chars().count().div_ceil(4)
+    }
+}
+
+/// Exact counts with one of OpenAI's BPE encodings
+#[cfg(feature = "tiktoken")]
+pub struct TiktokenTokenizer {
+    name: String,
+    bpe: tiktoken_rs::CoreBPE,
+}
+
+#[cfg(feature = "tiktoken")]
+impl TiktokenTokenizer {
+    /// The encoding named `name`, e.g. `cl100k_base` or `o200k_base`, or
+    /// the one a model such as `gpt-4o` uses
+    pub fn new(name: &str) -> anyhow::Result<Self> {
+        let bpe = match name {
+            "cl100k_base" => tiktoken_rs::cl100k_base(),
+            "o200k_base" => tiktoken_rs::o200k_base(),
+            "p50k_base" => tiktoken_rs::p50k_base(),
+            "p50k_edit" => tiktoken_rs::p50k_edit(),
+            "r50k_base" => tiktoken_rs::r50k_base(),
+            model => tiktoken_rs::get_bpe_from_model(model),
+        }
+        .map_err(|e| anyhow::anyhow!("Unknown tiktoken encoding or model `{}`: {}", name, e))?;
+        Ok(Self {
+            name: name.to_string(),
+            bpe,
+        })
+    }
+}
+
+#[cfg(feature = "tiktoken")]
+impl Tokenizer for TiktokenTokenizer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn count_tokens(&self, text: &str) -> usize {
+        self.bpe.encode_ordinary(text).len()
+    }
+}
+
+static TOKENIZER: once_cell::sync::Lazy<RwLock<Arc<dyn Tokenizer>>> =
+    once_cell::sync::Lazy::new(|| RwLock::new(Arc::new(HeuristicTokenizer)));
+
+/// The tokenizer counts go through
+pub fn tokenizer() -> Arc<dyn Tokenizer> {
+    TOKENIZER.read().unwrap().clone()
+}
+
+/// Count tokens with `tokenizer` from now on, process-wide
+pub fn set_tokenizer(tokenizer: Arc<dyn Tokenizer>) {
+    tracing::info!(tokenizer = tokenizer.name(), "Tokenizer set");
+    *TOKENIZER.write().unwrap() = tokenizer;
+}
+
+/// Tokens `text` takes up, by the current tokenizer
+pub fn count_tokens(text: &str) -> usize {
+    tokenizer().count_tokens(text)
+}
+
+/// Split `text` at whitespace into chunks of at most `max_tokens` each
+///
+/// Words are kept whole, so a single word longer than `max_tokens` makes a
+/// chunk of its own. Chunks are trimmed; blank ones are dropped.
+pub fn chunk(text: &str, max_tokens: usize, tokenizer: &dyn Tokenizer) -> Vec<String> {
+    let mut chunks = Vec::new();
+    let mut current = String::new();
+    let mut current_tokens = 0;
+    let mut flush = |current: &mut String| {
+        let trimmed = current.trim();
+        if !trimmed.is_empty() {
+            chunks.push(trimmed.to_string());
+        }
+        current.clear();
+    };
+
+    for piece in text.split_inclusive(char::is_whitespace) {
+        let tokens = tokenizer.count_tokens(piece);
+        if current_tokens + tokens > max_tokens && !current.trim().is_empty() {
+            flush(&mut current);
+            current_tokens = 0;
+        }
+        current.push_str(piece);
+        current_tokens += tokens;
+    }
+    flush(&mut current);
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_heuristic_counts_and_chunks() {
+        let tokenizer = HeuristicTokenizer;
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        assert_eq!(tokenizer.count_tokens("abcd"), 1);
+        assert_eq!(tokenizer.count_tokens("abcde"), 2);
+        // Characters, not bytes
+        assert_eq!(tokenizer.count_tokens("日本語です"), 2);
+
+        let text = "alpha beta gamma delta epsilon zeta eta theta";
+        let chunks = chunk(text, 4, &tokenizer);
+        assert!(chunks.len() > 1);
+        assert_eq!(chunks.join(" "), text);
+        assert!(chunks
+            .iter()
+            .all(|chunk| tokenizer.count_tokens(chunk) <= 4));
+
+        assert_eq!(chunk("supercalifragilistic", 2, &tokenizer).len(), 1);
+        assert!(chunk("   ", 10, &tokenizer).is_empty());
+    }
+
+    #[cfg(feature = "tiktoken")]
+    #[test]
+    fn test_tiktoken_counts() {
+        let tokenizer = TiktokenTokenizer::new("cl100k_base").unwrap();
+        assert_eq!(tokenizer.count_tokens("hello world"), 2);
+        assert!(TiktokenTokenizer::new("gpt-4").is_ok());
+        assert!(TiktokenTokenizer::new("no-such-model").is_err());
+    }
+}
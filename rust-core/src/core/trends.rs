@@ -0,0 +1,202 @@
+//! Keyword frequency trends across consecutive time windows
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+use crate::database::models::MemoryItem;
+
+/// Windows in a trend series, the last one ending now
+pub const TREND_WINDOWS: usize = 8;
+
+/// Keywords listed as rising and as falling
+const TRENDING_KEYWORDS: usize = 10;
+
+/// Keywords in fewer memories than this are noise, not a trend
+const MIN_KEYWORD_MEMORIES: usize = 2;
+
+/// How often one keyword came up in each window
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordTrend {
+    pub keyword: String,
+    /// Memories mentioning the keyword, per window, oldest first
+    pub counts: Vec<usize>,
+    pub total: usize,
+    /// Change per window in the share of memories mentioning the keyword,
+    /// fitted by least squares; 0.1 means 10 percentage points per window
+    pub slope: f32,
+}
+
+/// Result of [`crate::core::memory::MemoryManager::keyword_trends`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeywordTrends {
+    pub user_id: String,
+    /// Start of each window, oldest first
+    pub window_starts: Vec<DateTime<Utc>>,
+    /// Memories created in each window
+    pub memory_counts: Vec<usize>,
+    /// Steepest rise first
+    pub rising: Vec<KeywordTrend>,
+    /// Steepest fall first
+    pub falling: Vec<KeywordTrend>,
+}
+
+/// Count keywords per window and rank them by how their share of memories
+/// changed. Memories outside the windows are ignored.
+pub fn keyword_trends(
+    user_id: &str,
+    memories: &[MemoryItem],
+    end: DateTime<Utc>,
+    window: Duration,
+) -> KeywordTrends {
+    let start = end - window * TREND_WINDOWS as i32;
+    let window_starts: Vec<DateTime<Utc>> = (0..TREND_WINDOWS)
+        .map(|index| start + window * index as i32)
+        .collect();
+
+    let mut memory_counts = vec![0usize; TREND_WINDOWS];
+    let mut keyword_counts: HashMap<String, Vec<usize>> = HashMap::new();
+    for memory in memories {
+        if memory.created_at < start || memory.created_at > end {
+            continue;
+        }
+        let elapsed = (memory.created_at - start).num_milliseconds();
+        let index = ((elapsed / window.num_milliseconds().max(1)) as usize).min(TREND_WINDOWS - 1);
+
+        memory_counts[index] += 1;
+        let keywords: HashSet<String> =
+            super::text::tokenize(&memory.content).into_iter().collect();
+        for keyword in keywords {
+            keyword_counts
+                .entry(keyword)
+                .or_insert_with(|| vec![0; TREND_WINDOWS])[index] += 1;
+        }
+    }
+
+    let mut trends: Vec<KeywordTrend> = keyword_counts
+        .into_iter()
+        .filter_map(|(keyword, counts)| {
+            let total = counts.iter().sum::<usize>();
+            if total < MIN_KEYWORD_MEMORIES {
+                return None;
+            }
+            let shares: Vec<f32> = counts
+                .iter()
+                .zip(&memory_counts)
+                .map(|(&count, &memories)| {
+                    if memories == 0 {
+                        0.0
+                    } else {
+                        count as f32 / memories as f32
+                    }
+                })
+                .collect();
+            Some(KeywordTrend {
+                keyword,
+                slope: slope(&shares),
+                counts,
+                total,
+            })
+        })
+        .collect();
+
+    // Ties go to the more frequent keyword, then alphabetically
+    trends.sort_by(|a, b| {
+        b.slope
+            .total_cmp(&a.slope)
+            .then_with(|| b.total.cmp(&a.total))
+            .then_with(|| a.keyword.cmp(&b.keyword))
+    });
+    let rising: Vec<KeywordTrend> = trends
+        .iter()
+        .filter(|trend| trend.slope > 0.0)
+        .take(TRENDING_KEYWORDS)
+        .cloned()
+        .collect();
+
+    trends.sort_by(|a, b| {
+        a.slope
+            .total_cmp(&b.slope)
+            .then_with(|| b.total.cmp(&a.total))
+            .then_with(|| a.keyword.cmp(&b.keyword))
+    });
+    let falling: Vec<KeywordTrend> = trends
+        .into_iter()
+        .filter(|trend| trend.slope < 0.0)
+        .take(TRENDING_KEYWORDS)
+        .collect();
+
+    KeywordTrends {
+        user_id: user_id.to_string(),
+        window_starts,
+        memory_counts,
+        rising,
+        falling,
+    }
+}
+
+/// Least-squares slope of evenly spaced values
+fn slope(values: &[f32]) -> f32 {
+    let n = values.len() as f32;
+    if n < 2.0 {
+        return 0.0;
+    }
+    let mean_x = (n - 1.0) / 2.0;
+    let mean_y = values.iter().sum::<f32>() / n;
+
+    let (covariance, variance) =
+        values
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(covariance, variance), (x, y)| {
+                let dx = x as f32 - mean_x;
+                (covariance + dx * (y - mean_y), variance + dx * dx)
+            });
+    covariance / variance
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_trends() {
+        let end = Utc::now();
+        let window = Duration::days(1);
+        let memory = |days_ago: i64, content: &str| MemoryItem {
+            user_id: "user1".to_string(),
+            content: content.to_string(),
+            created_at: end - Duration::days(days_ago) - Duration::hours(1),
+            ..Default::default()
+        };
+
+        let memories = vec![
+            memory(7, "Reading about bonds"),
+            memory(6, "Bonds and more bonds"),
+            memory(5, "Bonds yield curve"),
+            memory(2, "Started learning rust"),
+            memory(1, "Rust borrow checker"),
+            memory(0, "Rust async runtime"),
+            memory(0, "Weekend hiking trip"),
+            // Outside the eight windows
+            memory(30, "Bonds again"),
+        ];
+
+        let trends = keyword_trends("user1", &memories, end, window);
+        assert_eq!(trends.window_starts.len(), TREND_WINDOWS);
+        assert_eq!(trends.memory_counts.iter().sum::<usize>(), 7);
+
+        assert_eq!(trends.rising[0].keyword, "rust");
+        assert_eq!(trends.rising[0].counts, vec![0, 0, 0, 0, 0, 1, 1, 1]);
+        assert_eq!(trends.falling[0].keyword, "bonds");
+        assert_eq!(trends.falling[0].total, 3);
+        assert!(trends.falling[0].slope < 0.0);
+
+        // A single mention isn't a trend
+        assert!(trends
+            .rising
+            .iter()
+            .chain(&trends.falling)
+            .all(|trend| trend.keyword != "hiking"));
+    }
+}
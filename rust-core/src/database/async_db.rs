@@ -4,7 +4,9 @@ use anyhow::{Context, Result};
 use std::sync::Arc;
 use tokio::task;
 
-use super::vector::{HybridSearchResult, VectorConfig, VectorSearchEngine, VectorSearchResult};
+use super::vector::{
+    HybridSearchResult, VectorConfig, VectorFilter, VectorSearchEngine, VectorSearchResult,
+};
 use super::{models::*, Database, DatabaseConfig};
 
 /// Async wrapper for database operations
@@ -130,12 +132,13 @@ impl AsyncDatabase {
         query_embedding: Vec<f32>,
         model_name: String,
         limit: Option<usize>,
+        filter: VectorFilter,
     ) -> Result<Vec<VectorSearchResult>> {
         match &self.vector_engine {
             Some(engine) => {
                 let engine = engine.clone();
                 task::spawn_blocking(move || {
-                    engine.search_similar(&query_embedding, &model_name, limit)
+                    engine.search_similar(&query_embedding, &model_name, limit, &filter)
                 })
                 .await
                 .context("Failed to spawn vector search task")?
@@ -145,6 +148,7 @@ impl AsyncDatabase {
     }
 
     /// Async hybrid search (text + vector)
+    #[allow(clippy::too_many_arguments)]
     pub async fn hybrid_search(
         &self,
         text_query: String,
@@ -153,6 +157,7 @@ impl AsyncDatabase {
         text_weight: f32,
         vector_weight: f32,
         limit: Option<usize>,
+        filter: VectorFilter,
     ) -> Result<Vec<HybridSearchResult>> {
         match &self.vector_engine {
             Some(engine) => {
@@ -165,6 +170,7 @@ impl AsyncDatabase {
                         text_weight,
                         vector_weight,
                         limit,
+                        &filter,
                     )
                 })
                 .await
@@ -0,0 +1,176 @@
+//! Audit log
+//!
+//! Memory and session writes made inside a [`RequestContext`] leave a row in
+//! `audit_log`, written in the same transaction as the change, with the
+//! context's actor, trace ID and source. Writes made outside a context, such
+//! as decay, are not audited.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::core::context::RequestContext;
+
+/// Older entries are pruned so the log can't grow without bound
+const MAX_RECORDED: i64 = 100_000;
+
+/// What an audited write did
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    MemorySave,
+    MemoryUpdate,
+    MemoryDelete,
+    /// An expired memory was brought back
+    MemoryRestore,
+    /// A memory moved within its session
+    MemoryMove,
+    /// A memory was confirmed, archived or revived
+    MemoryState,
+    /// Two memories were linked or unlinked
+    MemoryLink,
+    SessionCreate,
+    SessionStatus,
+    /// Everything stored about a user was erased
+    UserPurge,
+}
+
+impl AuditAction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AuditAction::MemorySave => "memory_save",
+            AuditAction::MemoryUpdate => "memory_update",
+            AuditAction::MemoryDelete => "memory_delete",
+            AuditAction::MemoryRestore => "memory_restore",
+            AuditAction::MemoryMove => "memory_move",
+            AuditAction::MemoryState => "memory_state",
+            AuditAction::MemoryLink => "memory_link",
+            AuditAction::SessionCreate => "session_create",
+            AuditAction::SessionStatus => "session_status",
+            AuditAction::UserPurge => "user_purge",
+        }
+    }
+}
+
+impl fmt::Display for AuditAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for AuditAction {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "memory_save" => Ok(AuditAction::MemorySave),
+            "memory_update" => Ok(AuditAction::MemoryUpdate),
+            "memory_delete" => Ok(AuditAction::MemoryDelete),
+            "memory_restore" => Ok(AuditAction::MemoryRestore),
+            "memory_move" => Ok(AuditAction::MemoryMove),
+            "memory_state" => Ok(AuditAction::MemoryState),
+            "memory_link" => Ok(AuditAction::MemoryLink),
+            "session_create" => Ok(AuditAction::SessionCreate),
+            "session_status" => Ok(AuditAction::SessionStatus),
+            "user_purge" => Ok(AuditAction::UserPurge),
+            _ => Err(anyhow::anyhow!("Invalid audit action: {}", s)),
+        }
+    }
+}
+
+/// One audited write
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub id: i64,
+    pub recorded_at: DateTime<Utc>,
+    pub action: AuditAction,
+    /// ID of the memory, session or user written
+    pub target_id: String,
+    pub actor: Option<String>,
+    pub trace_id: Option<String>,
+    pub source: Option<String>,
+}
+
+/// Which entries [`recent`] returns; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AuditFilter {
+    pub actor: Option<String>,
+    pub trace_id: Option<String>,
+    pub target_id: Option<String>,
+}
+
+/// Record `action` on `target_id` against the current request context, if
+/// there is one
+pub fn record(conn: &rusqlite::Connection, action: AuditAction, target_id: &str) -> Result<()> {
+    let Some(context) = RequestContext::current() else {
+        return Ok(());
+    };
+
+    conn.execute(
+        "INSERT INTO audit_log (recorded_at, action, target_id, actor, trace_id, source)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            Utc::now(),
+            action.as_str(),
+            target_id,
+            context.actor,
+            context.trace_id,
+            context.source,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM audit_log WHERE id <= last_insert_rowid() - ?1",
+        [MAX_RECORDED],
+    )?;
+    Ok(())
+}
+
+/// The `limit` most recent entries matching `filter`, newest first
+pub fn recent(
+    conn: &rusqlite::Connection,
+    filter: &AuditFilter,
+    limit: usize,
+) -> Result<Vec<AuditEntry>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, recorded_at, action, target_id, actor, trace_id, source
+         FROM audit_log
+         WHERE (?1 IS NULL OR actor = ?1)
+           AND (?2 IS NULL OR trace_id = ?2)
+           AND (?3 IS NULL OR target_id = ?3)
+         ORDER BY id DESC LIMIT ?4",
+    )?;
+
+    let rows = stmt.query_map(
+        rusqlite::params![
+            filter.actor,
+            filter.trace_id,
+            filter.target_id,
+            limit as i64
+        ],
+        |row| {
+            Ok((
+                AuditEntry {
+                    id: row.get("id")?,
+                    recorded_at: row.get("recorded_at")?,
+                    action: AuditAction::MemorySave,
+                    target_id: row.get("target_id")?,
+                    actor: row.get("actor")?,
+                    trace_id: row.get("trace_id")?,
+                    source: row.get("source")?,
+                },
+                row.get::<_, String>("action")?,
+            ))
+        },
+    )?;
+
+    let mut entries = Vec::new();
+    for row in rows {
+        let (mut entry, action) = row?;
+        entry.action = action.parse()?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
@@ -0,0 +1,972 @@
+//! Compressed and encrypted backup archives
+//!
+//! A plain backup is the file `VACUUM INTO` writes. An archive wraps that
+//! file with a manifest so it can be checked before it is restored:
+//!
+//! ```text
+//! MEMEXBAK | manifest length (u32 LE) | manifest JSON | payload
+//! ```
+//!
+//! The payload is the database file, zstd-compressed when asked and then
+//! encrypted with AES-256-GCM under a key derived from a passphrase with
+//! Argon2id. The manifest is never encrypted: it records the schema
+//! version, row counts and SHA-256 checksums of both the payload and the
+//! database file, plus the salt, nonce and KDF parameters needed to decrypt.
+//!
+//! [`restore`] accepts an archive or a plain backup. It decodes the backup
+//! into a temporary file next to the target, checks it with
+//! `PRAGMA integrity_check` and against the manifest, and only then moves the
+//! live database aside and renames the restored file into place.
+//!
+//! The maintenance scheduler's `backup` job writes timestamped backups into a
+//! directory and [`prune`]s them to the newest of each of the last few days
+//! and weeks. With the `s3-backup` feature, backups can also be [`upload`]ed
+//! to S3-compatible object storage and restored from a URL.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+use super::schema;
+
+/// First bytes of every archive
+pub const MAGIC: &[u8; 8] = b"MEMEXBAK";
+
+/// Archive layout written by this version
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Environment variable the CLI reads backup passphrases from
+pub const PASSPHRASE_ENV: &str = "MEMEX_BACKUP_PASSPHRASE";
+
+/// Manifests larger than this are rejected as corrupt
+const MAX_MANIFEST_BYTES: u32 = 1024 * 1024;
+
+/// How an archive is written
+#[derive(Debug, Clone, Default)]
+pub struct BackupOptions {
+    /// Compress the database with zstd
+    pub compress: bool,
+    /// Encrypt the (compressed) database with a key derived from this
+    pub passphrase: Option<String>,
+}
+
+/// Cipher and key derivation settings of an encrypted archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupEncryption {
+    /// Always `aes-256-gcm`
+    pub cipher: String,
+    /// Always `argon2id`
+    pub kdf: String,
+    /// Hex-encoded KDF salt
+    pub salt: String,
+    /// Hex-encoded GCM nonce
+    pub nonce: String,
+    /// Argon2 memory cost in KiB
+    pub memory_kib: u32,
+    /// Argon2 iterations
+    pub iterations: u32,
+    /// Argon2 lanes
+    pub parallelism: u32,
+}
+
+/// Describes an archive; stored in its header
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupManifest {
+    pub format_version: u32,
+    pub created_at: DateTime<Utc>,
+    /// Database the backup was taken of
+    pub source: String,
+    pub schema_version: u32,
+    /// Memories, expired ones included
+    pub memory_count: i64,
+    pub session_count: i64,
+    pub user_count: i64,
+    /// Size of the database file once decoded
+    pub database_size: u64,
+    /// Hex SHA-256 of the database file once decoded
+    pub database_sha256: String,
+    /// Size of the payload as stored in the archive
+    pub payload_size: u64,
+    /// Hex SHA-256 of the payload as stored in the archive
+    pub payload_sha256: String,
+    /// Whether the database was zstd-compressed before any encryption
+    pub compressed: bool,
+    pub encryption: Option<BackupEncryption>,
+}
+
+impl BackupManifest {
+    pub fn is_encrypted(&self) -> bool {
+        self.encryption.is_some()
+    }
+}
+
+/// Result of [`restore`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RestoreOutcome {
+    /// Manifest of the archive; `None` for a plain backup
+    pub manifest: Option<BackupManifest>,
+    pub schema_version: u32,
+    pub memory_count: i64,
+    /// Where the database that was replaced now lives, if there was one
+    pub previous: Option<PathBuf>,
+}
+
+/// Copy the database open on `conn` into an archive at `path`
+pub fn create(
+    conn: &rusqlite::Connection,
+    source: &str,
+    path: &Path,
+    options: &BackupOptions,
+) -> Result<BackupManifest> {
+    if path.exists() {
+        return Err(anyhow::anyhow!(
+            "Backup target already exists: {}",
+            path.display()
+        ));
+    }
+
+    let copy = sibling(path, "tmp");
+    let _ = std::fs::remove_file(&copy);
+    let result = write_archive(conn, source, path, &copy, options);
+    let _ = std::fs::remove_file(&copy);
+    result
+}
+
+fn write_archive(
+    conn: &rusqlite::Connection,
+    source: &str,
+    path: &Path,
+    copy: &Path,
+    options: &BackupOptions,
+) -> Result<BackupManifest> {
+    let created_at = Utc::now();
+    conn.execute(
+        "VACUUM INTO ?1",
+        rusqlite::params![copy.to_string_lossy().as_ref()],
+    )
+    .with_context(|| format!("Failed to back up database to {}", copy.display()))?;
+
+    // Described from the copy itself, so the manifest matches it exactly
+    let (schema_version, counts) = {
+        let copy_conn = rusqlite::Connection::open(copy)?;
+        (
+            schema::get_schema_version(&copy_conn)?,
+            count_rows(&copy_conn)?,
+        )
+    };
+    let database = std::fs::read(copy)
+        .with_context(|| format!("Failed to read backup copy {}", copy.display()))?;
+
+    let mut payload = if options.compress {
+        compress(&database)?
+    } else {
+        database.clone()
+    };
+    let encryption = match &options.passphrase {
+        Some(passphrase) => {
+            let (ciphertext, encryption) = encrypt(&payload, passphrase)?;
+            payload = ciphertext;
+            Some(encryption)
+        }
+        None => None,
+    };
+
+    let manifest = BackupManifest {
+        format_version: FORMAT_VERSION,
+        created_at,
+        source: source.to_string(),
+        schema_version,
+        memory_count: counts.memories,
+        session_count: counts.sessions,
+        user_count: counts.users,
+        database_size: database.len() as u64,
+        database_sha256: sha256_hex(&database),
+        payload_size: payload.len() as u64,
+        payload_sha256: sha256_hex(&payload),
+        compressed: options.compress,
+        encryption,
+    };
+
+    let manifest_json = serde_json::to_vec(&manifest)?;
+    let mut archive = Vec::with_capacity(MAGIC.len() + 4 + manifest_json.len() + payload.len());
+    archive.extend_from_slice(MAGIC);
+    archive.extend_from_slice(&(manifest_json.len() as u32).to_le_bytes());
+    archive.extend_from_slice(&manifest_json);
+    archive.extend_from_slice(&payload);
+
+    // Written beside the target first so a failed write leaves no archive
+    let partial = sibling(path, "partial");
+    std::fs::write(&partial, &archive)
+        .with_context(|| format!("Failed to write backup to {}", partial.display()))?;
+    std::fs::rename(&partial, path)
+        .with_context(|| format!("Failed to move backup into place at {}", path.display()))?;
+    Ok(manifest)
+}
+
+/// The manifest of the archive at `path`; `None` for a plain backup
+pub fn read_manifest(path: &Path) -> Result<Option<BackupManifest>> {
+    Ok(read_header(path)?.map(|(manifest, _)| manifest))
+}
+
+/// The manifest and where the payload starts
+fn read_header(path: &Path) -> Result<Option<(BackupManifest, usize)>> {
+    let mut file = std::fs::File::open(path)
+        .with_context(|| format!("Failed to open backup {}", path.display()))?;
+    let mut header = [0u8; 12];
+    let read = read_up_to(&mut file, &mut header)?;
+    if read < MAGIC.len() || &header[..MAGIC.len()] != MAGIC {
+        return Ok(None);
+    }
+    if read < header.len() {
+        return Err(anyhow::anyhow!("Backup archive is truncated"));
+    }
+
+    let length = u32::from_le_bytes(header[MAGIC.len()..].try_into().unwrap());
+    if length > MAX_MANIFEST_BYTES {
+        return Err(anyhow::anyhow!("Backup archive manifest is corrupt"));
+    }
+    let mut manifest = vec![0u8; length as usize];
+    file.read_exact(&mut manifest)
+        .context("Backup archive is truncated")?;
+    let manifest: BackupManifest =
+        serde_json::from_slice(&manifest).context("Backup archive manifest is corrupt")?;
+    if manifest.format_version > FORMAT_VERSION {
+        return Err(anyhow::anyhow!(
+            "Backup archive format {} is newer than this version of Memex supports ({})",
+            manifest.format_version,
+            FORMAT_VERSION
+        ));
+    }
+    Ok(Some((manifest, header.len() + length as usize)))
+}
+
+/// Decode the archive at `path` back into database bytes, checking both
+/// checksums on the way
+pub fn decode(path: &Path, passphrase: Option<&str>) -> Result<(BackupManifest, Vec<u8>)> {
+    let (manifest, payload_start) = read_header(path)?
+        .ok_or_else(|| anyhow::anyhow!("{} is not a backup archive", path.display()))?;
+    let archive =
+        std::fs::read(path).with_context(|| format!("Failed to read backup {}", path.display()))?;
+    let payload = &archive[payload_start..];
+
+    if payload.len() as u64 != manifest.payload_size
+        || sha256_hex(payload) != manifest.payload_sha256
+    {
+        return Err(anyhow::anyhow!(
+            "Backup archive is corrupt: payload checksum mismatch"
+        ));
+    }
+
+    let mut database = match &manifest.encryption {
+        Some(encryption) => {
+            let passphrase = passphrase.ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Backup archive is encrypted; set {} to its passphrase",
+                    PASSPHRASE_ENV
+                )
+            })?;
+            decrypt(payload, passphrase, encryption)?
+        }
+        None => payload.to_vec(),
+    };
+    if manifest.compressed {
+        database = decompress(&database)?;
+    }
+
+    if database.len() as u64 != manifest.database_size
+        || sha256_hex(&database) != manifest.database_sha256
+    {
+        return Err(anyhow::anyhow!(
+            "Backup archive is corrupt: database checksum mismatch"
+        ));
+    }
+    Ok((manifest, database))
+}
+
+/// Replace the database at `target` with the backup at `path`, an archive or
+/// a plain database file, after checking the backup is intact
+///
+/// Nothing may have `target` open: the live file is renamed away, not
+/// written through SQLite. It is kept, with its WAL, as
+/// `<target>.pre-restore-<timestamp>`.
+pub fn restore(path: &Path, target: &str, passphrase: Option<&str>) -> Result<RestoreOutcome> {
+    let target = Path::new(target);
+    let restoring = sibling(target, "restoring");
+    let _ = std::fs::remove_file(&restoring);
+
+    let manifest = match read_manifest(path)? {
+        Some(_) => {
+            let (manifest, database) = decode(path, passphrase)?;
+            std::fs::write(&restoring, database)
+                .with_context(|| format!("Failed to write {}", restoring.display()))?;
+            Some(manifest)
+        }
+        None => {
+            std::fs::copy(path, &restoring)
+                .with_context(|| format!("Failed to read backup {}", path.display()))?;
+            None
+        }
+    };
+
+    let (schema_version, counts) = match validate(&restoring, manifest.as_ref()) {
+        Ok(checked) => checked,
+        Err(e) => {
+            let _ = std::fs::remove_file(&restoring);
+            return Err(e);
+        }
+    };
+
+    let previous = if target.exists() {
+        let previous = sibling(
+            target,
+            &format!("pre-restore-{}", Utc::now().format("%Y%m%d%H%M%S")),
+        );
+        std::fs::rename(target, &previous).with_context(|| {
+            format!(
+                "Failed to move {} aside to {}",
+                target.display(),
+                previous.display()
+            )
+        })?;
+        for suffix in ["-wal", "-shm"] {
+            let live = PathBuf::from(format!("{}{}", target.display(), suffix));
+            if live.exists() {
+                std::fs::rename(&live, format!("{}{}", previous.display(), suffix))?;
+            }
+        }
+        Some(previous)
+    } else {
+        None
+    };
+
+    std::fs::rename(&restoring, target)
+        .with_context(|| format!("Failed to move restored database to {}", target.display()))?;
+
+    Ok(RestoreOutcome {
+        manifest,
+        schema_version,
+        memory_count: counts.memories,
+        previous,
+    })
+}
+
+/// A backup in a directory of scheduled backups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub path: PathBuf,
+    /// When the backup was taken, from its file name
+    pub created_at: DateTime<Utc>,
+    pub size_bytes: u64,
+}
+
+/// File name of a scheduled backup of the database at `source` taken at
+/// `at`: `<database file stem>-<UTC timestamp>.memex` for an archive, `.db`
+/// for a plain backup
+pub fn scheduled_file_name(source: &str, at: DateTime<Utc>, archive: bool) -> String {
+    format!(
+        "{}-{}.{}",
+        file_stem(source),
+        at.format("%Y%m%dT%H%M%SZ"),
+        if archive { "memex" } else { "db" }
+    )
+}
+
+/// Scheduled backups of the database at `source` in `dir`, newest first;
+/// empty if `dir` doesn't exist
+pub fn list_scheduled(dir: &Path, source: &str) -> Result<Vec<BackupFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(e).with_context(|| format!("Failed to list backups in {}", dir.display()))
+        }
+    };
+
+    let prefix = format!("{}-", file_stem(source));
+    let mut backups = Vec::new();
+    for entry in entries {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().to_string();
+        let Some(timestamp) = name
+            .strip_prefix(&prefix)
+            .and_then(|rest| rest.strip_suffix(".memex").or(rest.strip_suffix(".db")))
+        else {
+            continue;
+        };
+        let Ok(created_at) = chrono::NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%SZ")
+        else {
+            continue;
+        };
+        backups.push(BackupFile {
+            path: entry.path(),
+            created_at: created_at.and_utc(),
+            size_bytes: entry.metadata().map(|m| m.len()).unwrap_or(0),
+        });
+    }
+    backups.sort_by_key(|backup| std::cmp::Reverse(backup.created_at));
+    Ok(backups)
+}
+
+/// Which of `backups`, newest first, to keep: the newest of each of the
+/// `keep_daily` most recent days with a backup and of each of the
+/// `keep_weekly` most recent ISO weeks with one
+pub fn retained(backups: &[BackupFile], keep_daily: usize, keep_weekly: usize) -> Vec<bool> {
+    let mut days = std::collections::HashSet::new();
+    let mut weeks = std::collections::HashSet::new();
+    backups
+        .iter()
+        .map(|backup| {
+            let date = backup.created_at.date_naive();
+            let week = date.iso_week();
+            let daily = days.len() < keep_daily && days.insert(date);
+            let weekly = weeks.len() < keep_weekly && weeks.insert((week.year(), week.week()));
+            daily || weekly
+        })
+        .collect()
+}
+
+/// Delete the scheduled backups of `source` in `dir` that [`retained`]
+/// doesn't keep, returning the deleted ones
+pub fn prune(
+    dir: &Path,
+    source: &str,
+    keep_daily: usize,
+    keep_weekly: usize,
+) -> Result<Vec<BackupFile>> {
+    let backups = list_scheduled(dir, source)?;
+    let keep = retained(&backups, keep_daily, keep_weekly);
+    let mut pruned = Vec::new();
+    for (backup, keep) in backups.into_iter().zip(keep) {
+        if !keep {
+            std::fs::remove_file(&backup.path)
+                .with_context(|| format!("Failed to delete backup {}", backup.path.display()))?;
+            pruned.push(backup);
+        }
+    }
+    Ok(pruned)
+}
+
+/// What an upload to object storage stored
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UploadReceipt {
+    /// `s3://bucket/key` of the object
+    pub url: String,
+    pub size_bytes: u64,
+    /// 1 for a single-part upload
+    pub parts: usize,
+    pub etag: Option<String>,
+}
+
+/// Whether `location` names remote storage rather than a local file
+pub fn is_remote(location: &str) -> bool {
+    ["s3://", "https://", "http://"]
+        .iter()
+        .any(|scheme| location.starts_with(scheme))
+}
+
+/// Upload the backup at `file` of the database at `source` under the
+/// `s3://bucket/prefix` URL `to`, keyed by database and date
+#[cfg(feature = "s3-backup")]
+pub fn upload(
+    file: &Path,
+    source: &str,
+    created_at: DateTime<Utc>,
+    archive: bool,
+    to: &str,
+) -> Result<UploadReceipt> {
+    use super::s3::{backup_key, S3Client, S3Url};
+
+    let prefix: S3Url = to.parse()?;
+    let url = S3Url {
+        key: backup_key(&prefix.key, source, created_at, archive),
+        bucket: prefix.bucket,
+    };
+    S3Client::from_env()?.upload_file(file, &url)
+}
+
+/// Download the backup at an `s3://` or `https://` URL into `path`,
+/// returning its size
+#[cfg(feature = "s3-backup")]
+pub fn download(url: &str, path: &Path) -> Result<u64> {
+    use super::s3::{self, S3Client, S3Url};
+
+    if S3Url::is_s3_url(url) {
+        S3Client::from_env()?.download_file(&url.parse()?, path)
+    } else {
+        s3::download_url(url, path)
+    }
+}
+
+#[cfg(not(feature = "s3-backup"))]
+pub fn upload(
+    _file: &Path,
+    _source: &str,
+    _created_at: DateTime<Utc>,
+    _archive: bool,
+    to: &str,
+) -> Result<UploadReceipt> {
+    Err(anyhow::anyhow!(
+        "Uploading backups to {} requires the 's3-backup' feature",
+        to
+    ))
+}
+
+#[cfg(not(feature = "s3-backup"))]
+pub fn download(url: &str, _path: &Path) -> Result<u64> {
+    Err(anyhow::anyhow!(
+        "Restoring backups from {} requires the 's3-backup' feature",
+        url
+    ))
+}
+
+pub(crate) fn file_stem(path: &str) -> String {
+    Path::new(path)
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "memex".to_string())
+}
+
+struct RowCounts {
+    memories: i64,
+    sessions: i64,
+    users: i64,
+}
+
+fn count_rows(conn: &rusqlite::Connection) -> Result<RowCounts> {
+    Ok(conn.query_row(
+        "SELECT (SELECT COUNT(*) FROM memories),
+                (SELECT COUNT(*) FROM sessions),
+                (SELECT COUNT(DISTINCT user_id) FROM memories)",
+        [],
+        |row| {
+            Ok(RowCounts {
+                memories: row.get(0)?,
+                sessions: row.get(1)?,
+                users: row.get(2)?,
+            })
+        },
+    )?)
+}
+
+/// Check the database file at `path` is intact, not from a newer Memex and,
+/// for an archive, holds what its manifest says
+fn validate(path: &Path, manifest: Option<&BackupManifest>) -> Result<(u32, RowCounts)> {
+    let conn =
+        rusqlite::Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+            .with_context(|| format!("Backup {} is not a SQLite database", path.display()))?;
+
+    let integrity: String = conn
+        .query_row("PRAGMA integrity_check", [], |row| row.get(0))
+        .context("Backup is not a valid SQLite database")?;
+    if integrity != "ok" {
+        return Err(anyhow::anyhow!(
+            "Backup failed its integrity check: {}",
+            integrity
+        ));
+    }
+
+    let schema_version: u32 = conn
+        .query_row(
+            "SELECT value FROM system_config WHERE key = ?1",
+            [schema::SCHEMA_VERSION_KEY],
+            |row| row.get::<_, String>(0),
+        )
+        .optional()
+        .context("Backup is not a Memex database")?
+        .map_or(0, |version| version.parse().unwrap_or(0));
+    let latest = schema::get_migrations()
+        .iter()
+        .map(|m| m.version)
+        .max()
+        .unwrap_or(0);
+    if schema_version > latest {
+        return Err(anyhow::anyhow!(
+            "Backup has schema version {}, newer than this version of Memex supports ({})",
+            schema_version,
+            latest
+        ));
+    }
+
+    let counts = count_rows(&conn).context("Backup is not a Memex database")?;
+    if let Some(manifest) = manifest {
+        if schema_version != manifest.schema_version
+            || counts.memories != manifest.memory_count
+            || counts.sessions != manifest.session_count
+            || counts.users != manifest.user_count
+        {
+            return Err(anyhow::anyhow!(
+                "Backup does not match its manifest: expected schema {} with {} memories, {} sessions and {} users, found schema {} with {}, {} and {}",
+                manifest.schema_version,
+                manifest.memory_count,
+                manifest.session_count,
+                manifest.user_count,
+                schema_version,
+                counts.memories,
+                counts.sessions,
+                counts.users
+            ));
+        }
+    }
+    Ok((schema_version, counts))
+}
+
+/// `<path>.<suffix>`
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    PathBuf::from(format!("{}.{}", path.display(), suffix))
+}
+
+fn read_up_to(file: &mut std::fs::File, buf: &mut [u8]) -> Result<usize> {
+    let mut read = 0;
+    while read < buf.len() {
+        match file.read(&mut buf[read..])? {
+            0 => break,
+            n => read += n,
+        }
+    }
+    Ok(read)
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(data))
+}
+
+#[cfg(feature = "encryption")]
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(feature = "encryption")]
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(anyhow::anyhow!("Invalid hex string"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| {
+            u8::from_str_radix(&hex[i..i + 2], 16)
+                .map_err(|_| anyhow::anyhow!("Invalid hex string"))
+        })
+        .collect()
+}
+
+#[cfg(feature = "compression")]
+fn compress(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::encode_all(data, zstd::DEFAULT_COMPRESSION_LEVEL).context("Failed to compress backup")
+}
+
+#[cfg(feature = "compression")]
+fn decompress(data: &[u8]) -> Result<Vec<u8>> {
+    zstd::decode_all(data).context("Backup archive is corrupt: failed to decompress")
+}
+
+#[cfg(not(feature = "compression"))]
+fn compress(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!(
+        "Compressed backups require the 'compression' feature"
+    ))
+}
+
+#[cfg(not(feature = "compression"))]
+fn decompress(_data: &[u8]) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!(
+        "Compressed backups require the 'compression' feature"
+    ))
+}
+
+#[cfg(feature = "encryption")]
+fn derive_key(passphrase: &str, salt: &[u8], encryption: &BackupEncryption) -> Result<[u8; 32]> {
+    let params = argon2::Params::new(
+        encryption.memory_kib,
+        encryption.iterations,
+        encryption.parallelism,
+        Some(32),
+    )
+    .map_err(|e| anyhow::anyhow!("Invalid backup key derivation parameters: {}", e))?;
+    let mut key = [0u8; 32];
+    argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, params)
+        .hash_password_into(passphrase.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow::anyhow!("Failed to derive backup key: {}", e))?;
+    Ok(key)
+}
+
+#[cfg(feature = "encryption")]
+fn encrypt(data: &[u8], passphrase: &str) -> Result<(Vec<u8>, BackupEncryption)> {
+    use aes_gcm::aead::rand_core::RngCore;
+    use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+    use aes_gcm::Aes256Gcm;
+
+    if passphrase.is_empty() {
+        return Err(anyhow::anyhow!("Backup passphrase must not be empty"));
+    }
+
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let defaults = argon2::Params::default();
+    let encryption = BackupEncryption {
+        cipher: "aes-256-gcm".to_string(),
+        kdf: "argon2id".to_string(),
+        salt: to_hex(&salt),
+        nonce: to_hex(&nonce),
+        memory_kib: defaults.m_cost(),
+        iterations: defaults.t_cost(),
+        parallelism: defaults.p_cost(),
+    };
+
+    let key = derive_key(passphrase, &salt, &encryption)?;
+    let ciphertext = Aes256Gcm::new(&key.into())
+        .encrypt(&nonce, data)
+        .map_err(|_| anyhow::anyhow!("Failed to encrypt backup"))?;
+    Ok((ciphertext, encryption))
+}
+
+#[cfg(feature = "encryption")]
+fn decrypt(data: &[u8], passphrase: &str, encryption: &BackupEncryption) -> Result<Vec<u8>> {
+    use aes_gcm::aead::{Aead, KeyInit};
+    use aes_gcm::{Aes256Gcm, Nonce};
+
+    if encryption.cipher != "aes-256-gcm" || encryption.kdf != "argon2id" {
+        return Err(anyhow::anyhow!(
+            "Unsupported backup encryption: {} with {}",
+            encryption.cipher,
+            encryption.kdf
+        ));
+    }
+    let salt = from_hex(&encryption.salt)?;
+    let nonce = from_hex(&encryption.nonce)?;
+    if nonce.len() != 12 {
+        return Err(anyhow::anyhow!("Backup archive manifest is corrupt"));
+    }
+
+    let key = derive_key(passphrase, &salt, encryption)?;
+    Aes256Gcm::new(&key.into())
+        .decrypt(Nonce::from_slice(&nonce), data)
+        .map_err(|_| {
+            anyhow::anyhow!("Failed to decrypt backup: wrong passphrase or corrupt archive")
+        })
+}
+
+#[cfg(not(feature = "encryption"))]
+fn encrypt(_data: &[u8], _passphrase: &str) -> Result<(Vec<u8>, BackupEncryption)> {
+    Err(anyhow::anyhow!(
+        "Encrypted backups require the 'encryption' feature"
+    ))
+}
+
+#[cfg(not(feature = "encryption"))]
+fn decrypt(_data: &[u8], _passphrase: &str, _encryption: &BackupEncryption) -> Result<Vec<u8>> {
+    Err(anyhow::anyhow!(
+        "Encrypted backups require the 'encryption' feature"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_database(dir: &TempDir, name: &str) -> (Database, String) {
+        let path = dir.path().join(name).to_string_lossy().to_string();
+        let database = Database::new(DatabaseConfig {
+            path: path.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        (database, path)
+    }
+
+    fn save(database: &Database, user: &str, content: &str) -> String {
+        database
+            .save_memory(&MemoryItem {
+                user_id: user.to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    #[cfg(all(feature = "compression", feature = "encryption"))]
+    fn test_encrypted_archive_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let (database, _) = setup_test_database(&temp_dir, "source.db");
+        let id = save(&database, "alice", "Remember the milk");
+        save(&database, "bob", "Remember the eggs");
+
+        let archive = temp_dir.path().join("backup.memex");
+        let manifest = database
+            .backup_archive(
+                &archive.to_string_lossy(),
+                &BackupOptions {
+                    compress: true,
+                    passphrase: Some("correct horse".to_string()),
+                },
+            )
+            .unwrap();
+        assert_eq!(manifest.memory_count, 2);
+        assert_eq!(manifest.user_count, 2);
+        assert!(manifest.compressed && manifest.is_encrypted());
+        assert!(manifest.payload_size < manifest.database_size);
+        assert_eq!(read_manifest(&archive).unwrap(), Some(manifest.clone()));
+
+        let bytes = std::fs::read(&archive).unwrap();
+        assert!(!bytes.windows(17).any(|w| w == b"Remember the milk"));
+
+        let (_, target) = setup_test_database(&temp_dir, "target.db");
+        let live_before = std::fs::read(&target).unwrap();
+        let err = restore(&archive, &target, None).unwrap_err();
+        assert!(err.to_string().contains(PASSPHRASE_ENV), "{}", err);
+        let err = restore(&archive, &target, Some("wrong horse")).unwrap_err();
+        assert!(err.to_string().contains("wrong passphrase"), "{}", err);
+        assert_eq!(std::fs::read(&target).unwrap(), live_before);
+
+        let outcome = restore(&archive, &target, Some("correct horse")).unwrap();
+        assert_eq!(outcome.memory_count, 2);
+        assert_eq!(outcome.manifest, Some(manifest));
+        assert!(outcome.previous.unwrap().exists());
+
+        let restored = Database::new(DatabaseConfig {
+            path: target,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(
+            restored.get_memory(&id).unwrap().unwrap().content,
+            "Remember the milk"
+        );
+    }
+
+    #[test]
+    fn test_corrupt_archive_is_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let (database, _) = setup_test_database(&temp_dir, "source.db");
+        save(&database, "alice", "Kept safe");
+
+        let archive = temp_dir.path().join("backup.memex");
+        let manifest = database
+            .backup_archive(&archive.to_string_lossy(), &BackupOptions::default())
+            .unwrap();
+        assert!(!manifest.compressed && !manifest.is_encrypted());
+        assert!(database
+            .backup_archive(&archive.to_string_lossy(), &BackupOptions::default())
+            .is_err());
+
+        let mut bytes = std::fs::read(&archive).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        let corrupt = temp_dir.path().join("corrupt.memex");
+        std::fs::write(&corrupt, &bytes).unwrap();
+
+        let target = temp_dir
+            .path()
+            .join("target.db")
+            .to_string_lossy()
+            .to_string();
+        let err = restore(&corrupt, &target, None).unwrap_err();
+        assert!(err.to_string().contains("checksum mismatch"), "{}", err);
+        assert!(!Path::new(&target).exists());
+        assert!(!sibling(Path::new(&target), "restoring").exists());
+
+        // A plain file that isn't a database fails the integrity check
+        let garbage = temp_dir.path().join("garbage.db");
+        std::fs::write(&garbage, b"not a database").unwrap();
+        assert!(restore(&garbage, &target, None).is_err());
+        assert!(!Path::new(&target).exists());
+
+        let outcome = restore(&archive, &target, None).unwrap();
+        assert_eq!(outcome.memory_count, 1);
+        assert!(outcome.previous.is_none());
+    }
+
+    #[test]
+    fn test_plain_backup_restores() {
+        let temp_dir = TempDir::new().unwrap();
+        let (database, _) = setup_test_database(&temp_dir, "source.db");
+        save(&database, "alice", "Plain backup");
+        let plain = temp_dir.path().join("plain.db");
+        database.backup_to(&plain.to_string_lossy()).unwrap();
+        assert_eq!(read_manifest(&plain).unwrap(), None);
+
+        let (target_db, target) = setup_test_database(&temp_dir, "target.db");
+        drop(target_db);
+        let outcome = restore(&plain, &target, None).unwrap();
+        assert!(outcome.manifest.is_none());
+        assert_eq!(outcome.memory_count, 1);
+        assert!(outcome.previous.is_some());
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_per_day_and_week() {
+        let at = |s: &str| DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc);
+        // Newest first: two on Friday 2026-10-16, one each on Thursday and
+        // Wednesday, then one each in the three weeks before
+        let backups: Vec<BackupFile> = [
+            "2026-10-16T12:00:00Z",
+            "2026-10-16T03:00:00Z",
+            "2026-10-15T03:00:00Z",
+            "2026-10-14T03:00:00Z",
+            "2026-10-09T03:00:00Z",
+            "2026-10-02T03:00:00Z",
+            "2026-09-25T03:00:00Z",
+        ]
+        .into_iter()
+        .map(|created_at| BackupFile {
+            path: PathBuf::from(created_at),
+            created_at: at(created_at),
+            size_bytes: 0,
+        })
+        .collect();
+
+        assert_eq!(
+            retained(&backups, 2, 0),
+            [true, false, true, false, false, false, false]
+        );
+        assert_eq!(
+            retained(&backups, 1, 3),
+            [true, false, false, false, true, true, false]
+        );
+        assert_eq!(
+            retained(&backups, 7, 4),
+            [true, false, true, true, true, true, true]
+        );
+    }
+
+    #[test]
+    fn test_prune_only_touches_scheduled_backups_of_the_source() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path();
+        let now = Utc::now();
+        let name = |source: &str, days: i64, archive: bool| {
+            scheduled_file_name(source, now - chrono::Duration::days(days), archive)
+        };
+        for file in [
+            name("/data/memex.db", 0, true),
+            name("/data/memex.db", 1, false),
+            name("/data/memex.db", 2, true),
+            name("/data/other.db", 2, true),
+            "notes.txt".to_string(),
+        ] {
+            std::fs::write(dir.join(file), b"backup").unwrap();
+        }
+
+        let backups = list_scheduled(dir, "/data/memex.db").unwrap();
+        assert_eq!(backups.len(), 3);
+        assert!(backups[0].created_at > backups[1].created_at);
+
+        let pruned = prune(dir, "/data/memex.db", 2, 0).unwrap();
+        assert_eq!(pruned.len(), 1);
+        assert_eq!(pruned[0].path, dir.join(name("/data/memex.db", 2, true)));
+        assert!(dir.join(name("/data/other.db", 2, true)).exists());
+        assert!(dir.join("notes.txt").exists());
+        assert!(list_scheduled(&dir.join("missing"), "/data/memex.db")
+            .unwrap()
+            .is_empty());
+    }
+}
@@ -0,0 +1,122 @@
+//! Spelling-tolerant matching for keyword recall
+//!
+//! When a recall with `QueryFilter::fuzzy` finds no exact full-text hits,
+//! candidates sharing at least one trigram with the keywords are fetched
+//! (through the `memories_trigram` index when the database has one, with
+//! `LIKE` otherwise) and scored here by how closely their words resemble
+//! the keywords.
+
+use std::collections::HashSet;
+
+/// Most candidate rows fetched for scoring by one fuzzy recall
+pub const MAX_CANDIDATES: usize = 500;
+
+/// Keyword/word similarity a candidate needs to count as a fuzzy hit
+pub const MIN_SIMILARITY: f32 = 0.45;
+
+/// Fuzzy hits score their similarity times this, so they always rank below
+/// exact full-text hits (which score 1.0)
+pub const FUZZY_SCORE_WEIGHT: f32 = 0.8;
+
+/// Words in `keywords` long enough to have a trigram, lowercased
+pub fn search_terms(keywords: &[String]) -> Vec<String> {
+    keywords
+        .iter()
+        .flat_map(|keyword| words(keyword))
+        .filter(|word| word.chars().count() >= 3)
+        .collect()
+}
+
+/// The distinct unpadded trigrams of `terms`, for looking up candidates
+pub fn candidate_trigrams(terms: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut trigrams = Vec::new();
+    for term in terms {
+        let chars: Vec<char> = term.chars().collect();
+        for window in chars.windows(3) {
+            let trigram: String = window.iter().collect();
+            if seen.insert(trigram.clone()) {
+                trigrams.push(trigram);
+            }
+        }
+    }
+    trigrams
+}
+
+/// How closely `content` matches `terms`, between 0 and 1
+///
+/// Each term is compared with every word of the content by the Dice
+/// coefficient of their padded trigrams and keeps its best match; the
+/// score is the average over all terms.
+pub fn similarity(terms: &[String], content: &str) -> f32 {
+    if terms.is_empty() {
+        return 0.0;
+    }
+
+    let content_words: Vec<HashSet<String>> = words(content)
+        .iter()
+        .map(|word| padded_trigrams(word))
+        .collect();
+
+    let total: f32 = terms
+        .iter()
+        .map(|term| {
+            let term = padded_trigrams(term);
+            content_words
+                .iter()
+                .map(|word| dice(&term, word))
+                .fold(0.0, f32::max)
+        })
+        .sum();
+    total / terms.len() as f32
+}
+
+fn words(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+// Padding gives the start and end of a word their own trigrams, so a
+// transposed pair in the middle of a word still leaves a good overlap
+fn padded_trigrams(word: &str) -> HashSet<String> {
+    let chars: Vec<char> = format!("  {} ", word).chars().collect();
+    chars
+        .windows(3)
+        .map(|window| window.iter().collect())
+        .collect()
+}
+
+fn dice(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    2.0 * a.intersection(b).count() as f32 / (a.len() + b.len()) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_similarity_tolerates_typos() {
+        let terms = search_terms(&["Databse".to_string(), "a".to_string()]);
+        assert_eq!(terms, vec!["databse"]);
+        assert_eq!(
+            candidate_trigrams(&terms),
+            vec!["dat", "ata", "tab", "abs", "bse"]
+        );
+
+        let close = similarity(&terms, "Tuned the database indexes");
+        assert!(close >= MIN_SIMILARITY, "{}", close);
+        let transposed = similarity(&search_terms(&["reciept".to_string()]), "a receipt");
+        assert!(transposed >= MIN_SIMILARITY, "{}", transposed);
+        assert!(similarity(&terms, "Went for a walk") < MIN_SIMILARITY);
+        assert_eq!(
+            similarity(&terms, "database"),
+            similarity(&terms, "DATABASE")
+        );
+        assert_eq!(similarity(&[], "database"), 0.0);
+    }
+}
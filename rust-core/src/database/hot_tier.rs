@@ -0,0 +1,366 @@
+//! In-memory tier for hot memories
+//!
+//! With `DatabaseConfig::hot_tier_bytes` set, memories read by ID or returned
+//! by recall are kept in memory up to that budget, so looking them up again
+//! by ID doesn't touch SQLite. Recall itself still queries SQLite, since it
+//! has to see every match. Over budget, the tier evicts the memories scoring
+//! lowest on importance plus recency of access, where recency halves every
+//! [`RECENCY_HALF_LIFE`] without an access; an important memory outlasts a
+//! merely recent one.
+//!
+//! Updates made through this `Database` are written through to the cached
+//! copy once they commit, and other writes drop it. Writes from other
+//! processes aren't seen, so leave the tier off when several processes write
+//! to the same file.
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::models::MemoryItem;
+
+/// Idle time after which a memory's recency score has halved
+pub const RECENCY_HALF_LIFE: Duration = Duration::from_secs(10 * 60);
+
+/// Bookkeeping bytes counted per cached memory on top of its text
+const ENTRY_OVERHEAD_BYTES: u64 = 256;
+
+/// An over-budget tier evicts down to this share of its budget, so it
+/// doesn't have to evict again on the next insert
+const EVICT_TO: f64 = 0.9;
+
+/// Hit and size counters of the hot tier
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HotTierStats {
+    /// 0 when the tier is off
+    pub budget_bytes: u64,
+    pub bytes: u64,
+    pub entries: usize,
+    /// Lookups by ID served from memory
+    pub hits: u64,
+    /// Lookups by ID that went to SQLite
+    pub misses: u64,
+    pub evictions: u64,
+}
+
+impl HotTierStats {
+    /// Share of lookups by ID served from memory, 0 before the first
+    pub fn hit_rate(&self) -> f32 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 {
+            0.0
+        } else {
+            self.hits as f32 / lookups as f32
+        }
+    }
+}
+
+struct Entry {
+    memory: MemoryItem,
+    bytes: u64,
+    last_access: Instant,
+}
+
+impl Entry {
+    fn score(&self, now: Instant) -> f64 {
+        let idle = now.duration_since(self.last_access).as_secs_f64();
+        let recency = 0.5f64.powf(idle / RECENCY_HALF_LIFE.as_secs_f64());
+        self.memory.importance as f64 + recency
+    }
+}
+
+#[derive(Default)]
+struct TierState {
+    entries: HashMap<String, Entry>,
+    bytes: u64,
+    /// Bumped by every write, so a read that started before one doesn't
+    /// cache what it saw
+    epoch: u64,
+}
+
+impl TierState {
+    fn remove(&mut self, id: &str) {
+        if let Some(entry) = self.entries.remove(id) {
+            self.bytes -= entry.bytes;
+        }
+    }
+}
+
+/// Memories kept in memory, up to a byte budget
+pub(crate) struct HotTier {
+    budget_bytes: u64,
+    state: Mutex<TierState>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+}
+
+impl HotTier {
+    /// A tier holding up to `budget_bytes`; 0 keeps nothing
+    pub(crate) fn new(budget_bytes: u64) -> Self {
+        Self {
+            budget_bytes,
+            state: Mutex::new(TierState::default()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+            evictions: AtomicU64::new(0),
+        }
+    }
+
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.budget_bytes > 0
+    }
+
+    /// Write count to pass to [`fill`](Self::fill) with what a read that
+    /// starts now finds
+    pub(crate) fn epoch(&self) -> u64 {
+        self.state.lock().unwrap().epoch
+    }
+
+    /// The cached copy of live memory `id`, counting a hit or a miss
+    pub(crate) fn get(&self, id: &str) -> Option<MemoryItem> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        let live = match state.entries.get_mut(id) {
+            Some(entry) if entry.memory.expires_at.is_none_or(|at| at > Utc::now()) => {
+                entry.last_access = Instant::now();
+                Some(entry.memory.clone())
+            }
+            Some(_) => {
+                state.remove(id);
+                None
+            }
+            None => None,
+        };
+        let counter = if live.is_some() {
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        live
+    }
+
+    /// Cache `memories` as read from SQLite, unless something was written
+    /// since `epoch`
+    pub(crate) fn fill<'a>(&self, memories: impl IntoIterator<Item = &'a MemoryItem>, epoch: u64) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        if state.epoch != epoch {
+            return;
+        }
+        for memory in memories {
+            self.insert(&mut state, memory);
+        }
+        self.evict(&mut state);
+    }
+
+    /// Replace the cached copy of `memory` after writing it
+    pub(crate) fn write(&self, memory: &MemoryItem) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        self.insert(&mut state, memory);
+        self.evict(&mut state);
+    }
+
+    /// Drop the cached copies of `ids` after writing them
+    pub(crate) fn remove<'a>(&self, ids: impl IntoIterator<Item = &'a str>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        for id in ids {
+            state.remove(id);
+        }
+    }
+
+    /// Drop the cached memories of `session_id` after writing several of
+    /// them
+    pub(crate) fn remove_session(&self, session_id: &str) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        let ids: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|(_, entry)| entry.memory.session_id == session_id)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in ids {
+            state.remove(&id);
+        }
+    }
+
+    /// Drop everything after a write that may have touched any memory
+    pub(crate) fn clear(&self) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut state = self.state.lock().unwrap();
+        state.epoch += 1;
+        state.entries.clear();
+        state.bytes = 0;
+    }
+
+    pub(crate) fn stats(&self) -> HotTierStats {
+        let state = self.state.lock().unwrap();
+        HotTierStats {
+            budget_bytes: self.budget_bytes,
+            bytes: state.bytes,
+            entries: state.entries.len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            evictions: self.evictions.load(Ordering::Relaxed),
+        }
+    }
+
+    fn insert(&self, state: &mut TierState, memory: &MemoryItem) {
+        let bytes = ENTRY_OVERHEAD_BYTES
+            + (memory.id.len() + memory.user_id.len() + memory.session_id.len()) as u64
+            + memory.content.len() as u64
+            + memory
+                .metadata
+                .iter()
+                .map(|(key, value)| (key.len() + value.len()) as u64)
+                .sum::<u64>();
+        state.remove(&memory.id);
+        if bytes > self.budget_bytes {
+            return;
+        }
+
+        // Only what get_memory would have read from the row
+        let memory = MemoryItem {
+            #[cfg(feature = "vector-search")]
+            embedding: None,
+            #[cfg(feature = "vector-search")]
+            embedding_model: None,
+            idempotency_key: None,
+            match_score: None,
+            explanation: None,
+            ..memory.clone()
+        };
+        state.bytes += bytes;
+        state.entries.insert(
+            memory.id.clone(),
+            Entry {
+                memory,
+                bytes,
+                last_access: Instant::now(),
+            },
+        );
+    }
+
+    fn evict(&self, state: &mut TierState) {
+        if state.bytes <= self.budget_bytes {
+            return;
+        }
+
+        let now = Instant::now();
+        let mut scored: Vec<(f64, String)> = state
+            .entries
+            .iter()
+            .map(|(id, entry)| (entry.score(now), id.clone()))
+            .collect();
+        scored.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+        let target = (self.budget_bytes as f64 * EVICT_TO) as u64;
+        let mut evicted = 0;
+        for (_, id) in scored {
+            if state.bytes <= target {
+                break;
+            }
+            state.remove(&id);
+            evicted += 1;
+        }
+        self.evictions.fetch_add(evicted, Ordering::Relaxed);
+        tracing::debug!(
+            evicted = evicted,
+            bytes = state.bytes,
+            "Evicted memories from hot tier"
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn memory(id: &str, importance: f32) -> MemoryItem {
+        MemoryItem {
+            id: id.to_string(),
+            content: "x".repeat(100),
+            importance,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_eviction_keeps_important_memories() {
+        // Room for three memories
+        let tier = HotTier::new(3 * (ENTRY_OVERHEAD_BYTES + 101));
+        let epoch = tier.epoch();
+        tier.fill(
+            &[
+                memory("a", 0.9),
+                memory("b", 0.1),
+                memory("c", 0.8),
+                memory("d", 0.7),
+            ],
+            epoch,
+        );
+
+        let stats = tier.stats();
+        assert!(stats.bytes <= stats.budget_bytes);
+        assert_eq!(stats.evictions, 2);
+        assert!(tier.get("a").is_some());
+        assert!(tier.get("b").is_none());
+        assert!(tier.get("c").is_some());
+        assert_eq!(tier.stats().hits, 2);
+        assert_eq!(tier.stats().misses, 1);
+    }
+
+    #[test]
+    fn test_writes_win_over_reads_that_started_before_them() {
+        let tier = HotTier::new(1 << 20);
+        let epoch = tier.epoch();
+        tier.remove(["a"]);
+        tier.fill(&[memory("a", 0.5)], epoch);
+        assert!(tier.get("a").is_none());
+
+        tier.write(&MemoryItem {
+            content: "updated".to_string(),
+            ..memory("a", 0.5)
+        });
+        assert_eq!(tier.get("a").unwrap().content, "updated");
+
+        let expired = MemoryItem {
+            expires_at: Some(Utc::now() - chrono::Duration::seconds(1)),
+            ..memory("b", 0.5)
+        };
+        tier.write(&expired);
+        assert!(tier.get("b").is_none());
+        assert_eq!(tier.stats().entries, 1);
+
+        assert!(HotTier::new(0).get("a").is_none());
+        assert_eq!(HotTier::new(0).stats().misses, 0);
+    }
+}
@@ -0,0 +1,189 @@
+//! Memory and session ID generation
+//!
+//! IDs default to random UUIDv4s for compatibility with existing databases.
+//! ULIDs (optionally with a `mem_`/`ses_` prefix) start with their creation
+//! time, so new rows land at the end of the primary-key index instead of at
+//! random pages, and ID order matches creation order for keyset pagination
+//! (`QueryFilter::before_id`).
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+/// What an ID is being generated for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IdKind {
+    Memory,
+    Session,
+}
+
+impl IdKind {
+    /// Prefix used by [`PrefixedUlidGenerator`]
+    pub fn prefix(self) -> &'static str {
+        match self {
+            IdKind::Memory => "mem",
+            IdKind::Session => "ses",
+        }
+    }
+}
+
+/// Source of new memory and session IDs
+pub trait IdGenerator: fmt::Debug + Send + Sync {
+    fn generate(&self, kind: IdKind) -> String;
+
+    /// Whether IDs generated later sort after earlier ones
+    fn is_time_ordered(&self) -> bool {
+        false
+    }
+}
+
+/// Built-in ID generators, selected by `DatabaseConfig::id_strategy`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IdStrategy {
+    #[default]
+    Uuid,
+    Ulid,
+    /// ULIDs prefixed with their kind, e.g. `mem_01HZX3...`
+    PrefixedUlid,
+}
+
+impl IdStrategy {
+    pub fn generator(self) -> Arc<dyn IdGenerator> {
+        match self {
+            IdStrategy::Uuid => Arc::new(UuidGenerator),
+            IdStrategy::Ulid => Arc::new(UlidGenerator::default()),
+            IdStrategy::PrefixedUlid => Arc::new(PrefixedUlidGenerator::default()),
+        }
+    }
+}
+
+impl std::str::FromStr for IdStrategy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "uuid" => Ok(IdStrategy::Uuid),
+            "ulid" => Ok(IdStrategy::Ulid),
+            "prefixed_ulid" | "prefixed-ulid" => Ok(IdStrategy::PrefixedUlid),
+            other => Err(anyhow::anyhow!(
+                "unknown ID strategy '{}' (expected uuid, ulid or prefixed_ulid)",
+                other
+            )),
+        }
+    }
+}
+
+/// Random UUIDv4s
+#[derive(Debug, Default)]
+pub struct UuidGenerator;
+
+impl IdGenerator for UuidGenerator {
+    fn generate(&self, _kind: IdKind) -> String {
+        uuid::Uuid::new_v4().to_string()
+    }
+}
+
+/// Crockford base32, as used by ULIDs
+const ULID_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// ULIDs: a 48-bit millisecond timestamp and 80 random bits, as 26
+/// Crockford base32 characters
+///
+/// IDs generated within the same millisecond increment the random part, so
+/// they stay strictly ordered within a process.
+#[derive(Debug, Default)]
+pub struct UlidGenerator {
+    last: Mutex<u128>,
+}
+
+impl UlidGenerator {
+    fn next(&self) -> u128 {
+        let millis = chrono::Utc::now().timestamp_millis().max(0) as u128 & ((1 << 48) - 1);
+        let random =
+            ((fastrand::u64(..) as u128) << 16 | fastrand::u16(..) as u128) & ((1 << 80) - 1);
+        let candidate = millis << 80 | random;
+
+        let mut last = self.last.lock().unwrap_or_else(|e| e.into_inner());
+        // Same (or an earlier, after a clock step back) millisecond
+        let next = if candidate <= *last {
+            last.wrapping_add(1)
+        } else {
+            candidate
+        };
+        *last = next;
+        next
+    }
+
+    fn encode(value: u128) -> String {
+        (0..26)
+            .rev()
+            .map(|i| ULID_ALPHABET[(value >> (i * 5)) as usize & 0x1f] as char)
+            .collect()
+    }
+}
+
+impl IdGenerator for UlidGenerator {
+    fn generate(&self, _kind: IdKind) -> String {
+        Self::encode(self.next())
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        true
+    }
+}
+
+/// ULIDs prefixed with [`IdKind::prefix`], e.g. `mem_01HZX3...`
+#[derive(Debug, Default)]
+pub struct PrefixedUlidGenerator {
+    ulids: UlidGenerator,
+}
+
+impl IdGenerator for PrefixedUlidGenerator {
+    fn generate(&self, kind: IdKind) -> String {
+        format!("{}_{}", kind.prefix(), self.ulids.generate(kind))
+    }
+
+    fn is_time_ordered(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ulids_are_ordered() {
+        let generator = UlidGenerator::default();
+        let ids: Vec<String> = (0..1000)
+            .map(|_| generator.generate(IdKind::Memory))
+            .collect();
+
+        assert!(ids.iter().all(|id| id.len() == 26));
+        assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+        assert!(ids[0].chars().all(|c| ULID_ALPHABET.contains(&(c as u8))));
+    }
+
+    #[test]
+    fn test_prefixed_ulids() {
+        let generator = IdStrategy::PrefixedUlid.generator();
+        assert!(generator.generate(IdKind::Memory).starts_with("mem_"));
+        assert!(generator.generate(IdKind::Session).starts_with("ses_"));
+        assert!(generator.is_time_ordered());
+        assert!(!IdStrategy::Uuid.generator().is_time_ordered());
+    }
+
+    #[test]
+    fn test_strategy_names() {
+        assert_eq!(
+            "prefixed-ulid".parse::<IdStrategy>().unwrap(),
+            IdStrategy::PrefixedUlid
+        );
+        assert_eq!(
+            serde_json::from_str::<IdStrategy>(r#""ulid""#).unwrap(),
+            IdStrategy::Ulid
+        );
+        assert!("ksuid".parse::<IdStrategy>().is_err());
+    }
+}
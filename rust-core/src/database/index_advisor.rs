@@ -0,0 +1,494 @@
+//! Index advisor
+//!
+//! Every recall records the shape of its filter: the columns it compares for
+//! equality, the columns it bounds by a range, and the parts (keywords,
+//! metadata, JSON fields) no B-tree index on `memories` can serve. Shapes
+//! are counted in memory and added to the `query_shapes` table every
+//! [`FLUSH_EVERY`] recalls and when the last `Database` clone is dropped, so
+//! recording doesn't add a write to every read.
+//!
+//! [`advise`] turns the recorded shapes into composite indexes: the shape's
+//! equality columns followed by one range column, or by `created_at`, which
+//! recall sorts on. Shapes an existing index already serves are skipped, and
+//! indexes serving recalls that crossed the slow-query threshold come first.
+
+use anyhow::Result;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use super::models::{MemoryState, QueryFilter};
+use super::pool::ConnectionPool;
+use super::DatabaseConfig;
+
+/// Recalls counted in memory before they are written out
+pub const FLUSH_EVERY: u64 = 100;
+
+/// Columns recall compares for equality, in the order advised indexes list
+/// them
+const EQUALITY_COLUMNS: &[&str] = &[
+    "user_id",
+    "session_id",
+    "state",
+    "content_type",
+    "source_type",
+    "source_ref",
+    "ingested_by",
+];
+
+/// Columns recall bounds by a range, in order of preference for an index
+const RANGE_COLUMNS: &[&str] = &[
+    "created_at",
+    "importance",
+    "confidence",
+    "expires_at",
+    "content_bytes",
+];
+
+/// Which parts of `memories` a recall filter touches
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct QueryShape {
+    /// Compared for equality (or against a list of values)
+    pub equality: Vec<String>,
+    /// Bounded by a range
+    pub range: Vec<String>,
+    /// Filters no index on `memories` serves: `keywords`, `metadata`,
+    /// `json_fields`, `exclusions`
+    pub unindexed: Vec<String>,
+}
+
+impl QueryShape {
+    /// The shape of the query [`Database::recall_memories`] builds for
+    /// `filter`
+    ///
+    /// [`Database::recall_memories`]: super::Database::recall_memories
+    pub fn of(filter: &QueryFilter) -> Self {
+        let states = if filter.states.is_empty() {
+            1
+        } else {
+            filter.states.len()
+        };
+        let equality = [
+            ("user_id", filter.user_id.is_some()),
+            ("session_id", filter.session_id.is_some()),
+            ("state", states < MemoryState::ALL.len()),
+            ("content_type", filter.content_type.is_some()),
+            ("source_type", !filter.source_types.is_empty()),
+            ("source_ref", filter.source_ref.is_some()),
+            ("ingested_by", filter.ingested_by.is_some()),
+        ];
+        let range = [
+            (
+                "created_at",
+                filter.date_from.is_some() || filter.date_to.is_some(),
+            ),
+            ("importance", filter.min_importance.is_some()),
+            (
+                "confidence",
+                filter.min_confidence.is_some() || filter.max_confidence.is_some(),
+            ),
+            ("expires_at", filter.expiring_within.is_some()),
+            (
+                "content_bytes",
+                filter.min_content_length.is_some() || filter.max_content_length.is_some(),
+            ),
+        ];
+        let unindexed = [
+            (
+                "keywords",
+                filter.keywords.as_ref().is_some_and(|k| !k.is_empty()),
+            ),
+            ("metadata", !filter.metadata.is_empty()),
+            ("json_fields", !filter.json_fields.is_empty()),
+            (
+                "exclusions",
+                !filter.exclude_keywords.is_empty()
+                    || !filter.exclude_session_ids.is_empty()
+                    || !filter.exclude_tags.is_empty(),
+            ),
+        ];
+
+        let used = |columns: &[(&str, bool)]| {
+            columns
+                .iter()
+                .filter(|(_, used)| *used)
+                .map(|(column, _)| column.to_string())
+                .collect()
+        };
+        Self {
+            equality: used(&equality),
+            range: used(&range),
+            unindexed: used(&unindexed),
+        }
+    }
+
+    /// One-line description, e.g. `user_id= state= created_at<> +keywords`,
+    /// which also keys the shape in `query_shapes`
+    pub fn describe(&self) -> String {
+        let parts: Vec<String> = self
+            .equality
+            .iter()
+            .map(|column| format!("{}=", column))
+            .chain(self.range.iter().map(|column| format!("{}<>", column)))
+            .chain(self.unindexed.iter().map(|filter| format!("+{}", filter)))
+            .collect();
+        if parts.is_empty() {
+            "(no filters)".to_string()
+        } else {
+            parts.join(" ")
+        }
+    }
+
+    /// Columns of the index that serves this shape: its equality columns,
+    /// then the range column to seek on
+    fn index_columns(&self) -> Vec<&'static str> {
+        let known = |columns: &'static [&'static str], used: &[String]| {
+            columns
+                .iter()
+                .copied()
+                .filter(|column| used.iter().any(|u| u == column))
+                .collect::<Vec<_>>()
+        };
+        // Only known names, since `--apply` puts them into SQL
+        let mut columns = known(EQUALITY_COLUMNS, &self.equality);
+        let range = known(RANGE_COLUMNS, &self.range);
+        // created_at also serves recall's ORDER BY
+        columns.push(range.first().copied().unwrap_or("created_at"));
+        columns
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct ShapeCounts {
+    recalls: u64,
+    slow_recalls: u64,
+    total_ms: f64,
+}
+
+/// Counts recall shapes and writes them to `query_shapes` in batches
+pub(crate) struct ShapeRecorder {
+    pool: ConnectionPool,
+    slow_threshold: Option<Duration>,
+    pending: Mutex<HashMap<QueryShape, ShapeCounts>>,
+}
+
+impl ShapeRecorder {
+    pub(crate) fn new(pool: ConnectionPool, config: &DatabaseConfig) -> Self {
+        Self {
+            pool,
+            slow_threshold: (config.slow_query_threshold_ms > 0)
+                .then(|| Duration::from_millis(config.slow_query_threshold_ms)),
+            pending: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Count one recall of `shape` that took `elapsed`
+    ///
+    /// Failing to write the counts out never fails the recall.
+    pub(crate) fn record(&self, shape: QueryShape, elapsed: Duration) {
+        // A read-only database has nowhere to keep them
+        if self.pool.is_read_only() {
+            return;
+        }
+
+        let due = {
+            let mut pending = self.pending.lock().unwrap();
+            let counts = pending.entry(shape).or_default();
+            counts.recalls += 1;
+            counts.total_ms += elapsed.as_secs_f64() * 1000.0;
+            if self.slow_threshold.is_some_and(|t| elapsed >= t) {
+                counts.slow_recalls += 1;
+            }
+            pending.values().map(|c| c.recalls).sum::<u64>() >= FLUSH_EVERY
+        };
+        if due {
+            if let Err(e) = self.flush() {
+                tracing::warn!(error = %e, "Failed to record query shapes");
+            }
+        }
+    }
+
+    /// Write out the counts gathered since the last flush
+    pub(crate) fn flush(&self) -> Result<()> {
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        if pending.is_empty() {
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        self.pool.with_write_transaction(|tx| {
+            let mut stmt = tx.prepare_cached(
+                "INSERT INTO query_shapes
+                     (shape, equality_columns, range_columns, unindexed,
+                      recalls, slow_recalls, total_ms, last_seen)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+                 ON CONFLICT (shape) DO UPDATE SET
+                     recalls = recalls + excluded.recalls,
+                     slow_recalls = slow_recalls + excluded.slow_recalls,
+                     total_ms = total_ms + excluded.total_ms,
+                     last_seen = excluded.last_seen",
+            )?;
+            for (shape, counts) in &pending {
+                stmt.execute(rusqlite::params![
+                    shape.describe(),
+                    serde_json::to_string(&shape.equality)?,
+                    serde_json::to_string(&shape.range)?,
+                    serde_json::to_string(&shape.unindexed)?,
+                    counts.recalls as i64,
+                    counts.slow_recalls as i64,
+                    counts.total_ms,
+                    now,
+                ])?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl Drop for ShapeRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            tracing::warn!(error = %e, "Failed to record query shapes");
+        }
+    }
+}
+
+/// A composite index recommended by [`advise`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexAdvice {
+    pub name: String,
+    /// Indexed columns, e.g. `["user_id", "state", "created_at DESC"]`
+    pub columns: Vec<String>,
+    /// `CREATE INDEX` statement that adds it
+    pub sql: String,
+    /// Recorded shapes it would serve, as [`QueryShape::describe`]s them
+    pub shapes: Vec<String>,
+    pub recalls: u64,
+    /// Recalls that crossed the slow-query threshold
+    pub slow_recalls: u64,
+    pub avg_ms: f64,
+}
+
+/// Indexes that would serve the recorded shapes seen at least `min_recalls`
+/// times and aren't served by an existing index, slowest first
+pub fn advise(conn: &rusqlite::Connection, min_recalls: u64) -> Result<Vec<IndexAdvice>> {
+    let existing = memory_indexes(conn)?;
+
+    let mut stmt = conn.prepare(
+        "SELECT shape, equality_columns, range_columns, recalls, slow_recalls, total_ms
+         FROM query_shapes WHERE recalls >= ?1",
+    )?;
+    let rows = stmt.query_map([min_recalls as i64], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, String>(2)?,
+            ShapeCounts {
+                recalls: row.get::<_, i64>(3)? as u64,
+                slow_recalls: row.get::<_, i64>(4)? as u64,
+                total_ms: row.get(5)?,
+            },
+        ))
+    })?;
+
+    let mut advice: BTreeMap<Vec<&str>, (Vec<String>, ShapeCounts)> = BTreeMap::new();
+    for row in rows {
+        let (described, equality, range, counts) = row?;
+        let shape = QueryShape {
+            equality: serde_json::from_str(&equality).unwrap_or_default(),
+            range: serde_json::from_str(&range).unwrap_or_default(),
+            unindexed: Vec::new(),
+        };
+        let columns = shape.index_columns();
+        if is_served(&existing, &columns, columns.len() - 1) {
+            continue;
+        }
+        let (shapes, total) = advice.entry(columns).or_default();
+        shapes.push(described);
+        total.recalls += counts.recalls;
+        total.slow_recalls += counts.slow_recalls;
+        total.total_ms += counts.total_ms;
+    }
+
+    let mut advice: Vec<IndexAdvice> = advice
+        .into_iter()
+        .map(|(columns, (mut shapes, counts))| {
+            shapes.sort();
+            let name = format!("idx_memories_advised_{}", columns.join("_"));
+            let columns: Vec<String> = columns
+                .iter()
+                .map(|column| match *column {
+                    "created_at" => "created_at DESC".to_string(),
+                    column => column.to_string(),
+                })
+                .collect();
+            IndexAdvice {
+                sql: format!(
+                    "CREATE INDEX IF NOT EXISTS {} ON memories ({})",
+                    name,
+                    columns.join(", ")
+                ),
+                name,
+                columns,
+                shapes,
+                recalls: counts.recalls,
+                slow_recalls: counts.slow_recalls,
+                avg_ms: counts.total_ms / counts.recalls.max(1) as f64,
+            }
+        })
+        .collect();
+    advice.sort_by(|a, b| {
+        b.slow_recalls
+            .cmp(&a.slow_recalls)
+            .then((b.avg_ms * b.recalls as f64).total_cmp(&(a.avg_ms * a.recalls as f64)))
+            .then_with(|| a.name.cmp(&b.name))
+    });
+    Ok(advice)
+}
+
+/// Forget every recorded shape, returning how many there were
+pub fn clear(conn: &rusqlite::Connection) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM query_shapes", [])?)
+}
+
+/// Leading columns of each full (not partial) index on `memories`
+fn memory_indexes(conn: &rusqlite::Connection) -> Result<Vec<Vec<String>>> {
+    let names = {
+        let mut stmt =
+            conn.prepare("SELECT name FROM pragma_index_list('memories') WHERE partial = 0")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+
+    let mut stmt = conn.prepare("SELECT name FROM pragma_index_info(?1) ORDER BY seqno")?;
+    names
+        .iter()
+        .map(|name| {
+            let rows = stmt.query_map([name], |row| row.get::<_, Option<String>>(0))?;
+            // Expression columns have no name and end the usable prefix
+            Ok(rows
+                .map(|column| column.map(|c| c.unwrap_or_default()))
+                .collect::<rusqlite::Result<Vec<_>>>()?
+                .into_iter()
+                .take_while(|column| !column.is_empty())
+                .collect())
+        })
+        .collect()
+}
+
+/// Whether an index in `existing` starts with the `equalities` leading
+/// `columns` in any order, then the rest of `columns` in order
+fn is_served(existing: &[Vec<String>], columns: &[&str], equalities: usize) -> bool {
+    let (equality, rest) = columns.split_at(equalities.min(columns.len()));
+    existing.iter().any(|index| {
+        index.len() >= columns.len()
+            && equality
+                .iter()
+                .all(|column| index[..equality.len()].iter().any(|i| i == column))
+            && rest
+                .iter()
+                .zip(&index[equality.len()..])
+                .all(|(column, i)| i == column)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::ContentType;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(crate::database::schema::SCHEMA_SQL)
+            .unwrap();
+        conn.execute_batch(crate::database::schema::INDEXES_SQL)
+            .unwrap();
+        conn
+    }
+
+    fn record(conn: &Connection, shape: &QueryShape, recalls: i64, slow_recalls: i64) {
+        conn.execute(
+            "INSERT INTO query_shapes
+                 (shape, equality_columns, range_columns, unindexed, recalls, slow_recalls,
+                  total_ms, last_seen)
+             VALUES (?1, ?2, ?3, '[]', ?4, ?5, ?4 * 2.0, datetime('now'))",
+            rusqlite::params![
+                shape.describe(),
+                serde_json::to_string(&shape.equality).unwrap(),
+                serde_json::to_string(&shape.range).unwrap(),
+                recalls,
+                slow_recalls
+            ],
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn test_shape_of_filter() {
+        let shape = QueryShape::of(&QueryFilter {
+            user_id: Some("user1".to_string()),
+            content_type: Some(ContentType::Json),
+            min_importance: Some(0.5),
+            keywords: Some(vec!["coffee".to_string()]),
+            ..Default::default()
+        });
+        assert_eq!(shape.equality, vec!["user_id", "state", "content_type"]);
+        assert_eq!(shape.range, vec!["importance"]);
+        assert_eq!(
+            shape.describe(),
+            "user_id= state= content_type= importance<> +keywords"
+        );
+
+        let every_state = QueryShape::of(&QueryFilter {
+            states: MemoryState::ALL.to_vec(),
+            ..Default::default()
+        });
+        assert_eq!(every_state.describe(), "(no filters)");
+    }
+
+    #[test]
+    fn test_advice_skips_served_shapes_and_ranks_slow_ones_first() {
+        let conn = setup();
+        let shape = |equality: &[&str], range: &[&str]| QueryShape {
+            equality: equality.iter().map(|c| c.to_string()).collect(),
+            range: range.iter().map(|c| c.to_string()).collect(),
+            unindexed: Vec::new(),
+        };
+        // Served by idx_memories_user_created
+        record(&conn, &shape(&["user_id"], &[]), 500, 50);
+        record(&conn, &shape(&["user_id", "state"], &[]), 20, 0);
+        record(&conn, &shape(&["user_id", "state"], &["created_at"]), 30, 0);
+        record(
+            &conn,
+            &shape(&["session_id", "ingested_by"], &["confidence"]),
+            15,
+            3,
+        );
+        // Too rare to matter
+        record(&conn, &shape(&["source_ref"], &[]), 2, 2);
+
+        let advice = advise(&conn, 10).unwrap();
+        assert_eq!(advice.len(), 2);
+        assert_eq!(
+            advice[0].sql,
+            "CREATE INDEX IF NOT EXISTS idx_memories_advised_session_id_ingested_by_confidence \
+             ON memories (session_id, ingested_by, confidence)"
+        );
+        assert_eq!(advice[0].slow_recalls, 3);
+        assert_eq!(
+            advice[1].columns,
+            vec!["user_id", "state", "created_at DESC"]
+        );
+        assert_eq!(advice[1].recalls, 50);
+        assert_eq!(advice[1].shapes.len(), 2);
+        assert_eq!(advice[1].avg_ms, 2.0);
+
+        conn.execute_batch(&advice[1].sql).unwrap();
+        assert_eq!(advise(&conn, 10).unwrap().len(), 1);
+        assert_eq!(clear(&conn).unwrap(), 5);
+        assert!(advise(&conn, 0).unwrap().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+//! Advisory maintenance lock shared by processes opening the same file
+//!
+//! Every `Database` registers itself in `instance_locks` under a random
+//! instance ID. Only the holder of the maintenance lock runs decay, so two
+//! processes with overlapping schedulers don't both clean up the same data.
+//! The holder refreshes its heartbeat on every acquire; whether another
+//! instance may take the lock over is set by `DatabaseConfig::lock_takeover`.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+
+/// Name of the lock guarding decay and other scheduled maintenance
+pub const MAINTENANCE_LOCK: &str = "maintenance";
+
+/// When an instance may take the lock from another holder
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LockTakeover {
+    /// Wait for the holder to release it
+    Never,
+    /// Take it once the holder's heartbeat is older than
+    /// `DatabaseConfig::lock_stale_after_secs`
+    #[default]
+    IfStale,
+    /// Always take it
+    Always,
+}
+
+/// The instance currently holding a lock
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub instance_id: String,
+    pub pid: u32,
+    pub hostname: String,
+    pub acquired_at: DateTime<Utc>,
+    pub heartbeat_at: DateTime<Utc>,
+}
+
+impl LockHolder {
+    /// This process, identified as `instance_id`
+    pub fn current(instance_id: &str) -> Self {
+        let now = Utc::now();
+        Self {
+            instance_id: instance_id.to_string(),
+            pid: std::process::id(),
+            hostname: hostname(),
+            acquired_at: now,
+            heartbeat_at: now,
+        }
+    }
+
+    pub fn is_stale(&self, stale_after: chrono::Duration, now: DateTime<Utc>) -> bool {
+        now - self.heartbeat_at > stale_after
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| {
+            std::fs::read_to_string("/etc/hostname")
+                .ok()
+                .map(|name| name.trim().to_string())
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+/// Take or refresh lock `name` for `candidate`; returns whoever holds it
+/// afterwards
+pub fn acquire(
+    conn: &rusqlite::Connection,
+    name: &str,
+    candidate: &LockHolder,
+    takeover: LockTakeover,
+    stale_after: chrono::Duration,
+) -> Result<LockHolder> {
+    let now = Utc::now();
+    let current = holder(conn, name)?;
+
+    let take = match &current {
+        None => true,
+        Some(holder) if holder.instance_id == candidate.instance_id => {
+            conn.execute(
+                "UPDATE instance_locks SET heartbeat_at = ?1 WHERE name = ?2",
+                rusqlite::params![now, name],
+            )?;
+            return Ok(LockHolder {
+                heartbeat_at: now,
+                ..holder.clone()
+            });
+        }
+        Some(holder) => match takeover {
+            LockTakeover::Never => false,
+            LockTakeover::IfStale => holder.is_stale(stale_after, now),
+            LockTakeover::Always => true,
+        },
+    };
+
+    match current {
+        Some(holder) if !take => Ok(holder),
+        previous => {
+            if let Some(previous) = previous {
+                tracing::warn!(
+                    lock = name,
+                    previous_pid = previous.pid,
+                    previous_host = previous.hostname.as_str(),
+                    heartbeat_at = %previous.heartbeat_at,
+                    "Taking over instance lock"
+                );
+            }
+            conn.execute(
+                "INSERT OR REPLACE INTO instance_locks
+                 (name, instance_id, pid, hostname, acquired_at, heartbeat_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?5)",
+                rusqlite::params![
+                    name,
+                    candidate.instance_id,
+                    candidate.pid,
+                    candidate.hostname,
+                    now
+                ],
+            )?;
+            Ok(LockHolder {
+                acquired_at: now,
+                heartbeat_at: now,
+                ..candidate.clone()
+            })
+        }
+    }
+}
+
+/// Release every lock `instance_id` holds
+pub fn release_all(conn: &rusqlite::Connection, instance_id: &str) -> Result<usize> {
+    Ok(conn.execute(
+        "DELETE FROM instance_locks WHERE instance_id = ?1",
+        [instance_id],
+    )?)
+}
+
+/// Who holds lock `name`, if anyone
+pub fn holder(conn: &rusqlite::Connection, name: &str) -> Result<Option<LockHolder>> {
+    Ok(conn
+        .query_row(
+            "SELECT instance_id, pid, hostname, acquired_at, heartbeat_at
+             FROM instance_locks WHERE name = ?1",
+            [name],
+            |row| {
+                Ok(LockHolder {
+                    instance_id: row.get(0)?,
+                    pid: row.get(1)?,
+                    hostname: row.get(2)?,
+                    acquired_at: row.get(3)?,
+                    heartbeat_at: row.get(4)?,
+                })
+            },
+        )
+        .optional()?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema;
+
+    fn setup() -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open_in_memory().unwrap();
+        conn.execute_batch(schema::SCHEMA_SQL).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_takeover_behaviour() {
+        let conn = setup();
+        let stale_after = chrono::Duration::minutes(5);
+        let first = LockHolder::current("first");
+        let second = LockHolder::current("second");
+
+        let held = acquire(
+            &conn,
+            MAINTENANCE_LOCK,
+            &first,
+            LockTakeover::IfStale,
+            stale_after,
+        )
+        .unwrap();
+        assert_eq!(held.instance_id, "first");
+
+        // A live holder keeps the lock unless takeover is forced
+        for takeover in [LockTakeover::Never, LockTakeover::IfStale] {
+            let held = acquire(&conn, MAINTENANCE_LOCK, &second, takeover, stale_after).unwrap();
+            assert_eq!(held.instance_id, "first");
+        }
+
+        conn.execute(
+            "UPDATE instance_locks SET heartbeat_at = ?1",
+            [Utc::now() - chrono::Duration::minutes(10)],
+        )
+        .unwrap();
+        let held = acquire(
+            &conn,
+            MAINTENANCE_LOCK,
+            &second,
+            LockTakeover::Never,
+            stale_after,
+        )
+        .unwrap();
+        assert_eq!(held.instance_id, "first");
+        let held = acquire(
+            &conn,
+            MAINTENANCE_LOCK,
+            &second,
+            LockTakeover::IfStale,
+            stale_after,
+        )
+        .unwrap();
+        assert_eq!(held.instance_id, "second");
+
+        let held = acquire(
+            &conn,
+            MAINTENANCE_LOCK,
+            &first,
+            LockTakeover::Always,
+            stale_after,
+        )
+        .unwrap();
+        assert_eq!(held.instance_id, "first");
+
+        assert_eq!(release_all(&conn, "second").unwrap(), 0);
+        assert_eq!(release_all(&conn, "first").unwrap(), 1);
+        assert!(holder(&conn, MAINTENANCE_LOCK).unwrap().is_none());
+    }
+}
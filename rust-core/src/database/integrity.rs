@@ -0,0 +1,252 @@
+//! Tamper-evident memory hashes
+//!
+//! With sealing enabled, every write to a memory stores a hash of the row in
+//! `integrity_hash`, in the same transaction as the write. The hash covers
+//! the memory's IDs, content, metadata, importance, confidence, TTL, content
+//! type, lifecycle state, provenance and timestamps. [`verify`] recomputes
+//! it for each row, so a change made to the SQLite file by anything other
+//! than Memex shows up as a mismatch.
+//!
+//! When `MEMEX_INTEGRITY_KEY` is set the hash is an HMAC-SHA256 under that
+//! key, which someone editing the file can't recompute without the key.
+//! Without it the hash is a plain SHA-256; that catches accidental edits and
+//! corruption, but a deliberate tamperer could recompute it. Hashes are
+//! stored with their algorithm (`hmac-sha256:` or `sha256:`), and a row
+//! sealed one way fails verification the other way, so dropping the key
+//! can't be used to pass a forged plain hash off as signed.
+//!
+//! A deleted row takes its hash with it, so deletions don't show up here;
+//! the audit log records those made through Memex.
+
+use anyhow::{Context, Result};
+use hmac::{Hmac, Mac};
+use rusqlite::types::ValueRef;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+/// Environment variable holding the HMAC key
+pub const KEY_ENV: &str = "MEMEX_INTEGRITY_KEY";
+
+/// Columns covered by the hash, in hashing order
+const SEALED_COLUMNS: &str = "id, user_id, session_id, content, metadata, importance, confidence, \
+     ttl_hours, content_type, state, source_type, source_ref, ingested_by, created_at, updated_at, \
+     expires_at";
+const SEALED_COLUMN_COUNT: usize = 16;
+
+/// Computes the hashes stored in `integrity_hash`
+#[derive(Clone)]
+pub struct Sealer {
+    key: Option<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Sealer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Sealer")
+            .field("algorithm", &self.algorithm())
+            .finish()
+    }
+}
+
+impl Sealer {
+    /// HMAC-SHA256 under `key`, or plain SHA-256 without one
+    pub fn new(key: Option<&[u8]>) -> Self {
+        Self {
+            key: key.map(<[u8]>::to_vec),
+        }
+    }
+
+    /// A sealer keyed with `MEMEX_INTEGRITY_KEY`, if it's set
+    pub fn from_env() -> Self {
+        let key = std::env::var(KEY_ENV).ok().filter(|key| !key.is_empty());
+        Self::new(key.as_deref().map(str::as_bytes))
+    }
+
+    /// `hmac-sha256` or `sha256`
+    pub fn algorithm(&self) -> &'static str {
+        if self.key.is_some() {
+            "hmac-sha256"
+        } else {
+            "sha256"
+        }
+    }
+
+    /// Hash of one memory row, whose first columns are [`SEALED_COLUMNS`]
+    fn hash_row(&self, row: &rusqlite::Row) -> rusqlite::Result<String> {
+        let mut encoded = Vec::new();
+        for index in 0..SEALED_COLUMN_COUNT {
+            // Tagged and length-prefixed, so no two rows encode the same
+            match row.get_ref(index)? {
+                ValueRef::Null => encoded.push(b'n'),
+                ValueRef::Integer(value) => {
+                    encoded.push(b'i');
+                    encoded.extend_from_slice(&value.to_be_bytes());
+                }
+                ValueRef::Real(value) => {
+                    encoded.push(b'r');
+                    encoded.extend_from_slice(&value.to_bits().to_be_bytes());
+                }
+                ValueRef::Text(bytes) | ValueRef::Blob(bytes) => {
+                    encoded.push(b't');
+                    encoded.extend_from_slice(&(bytes.len() as u64).to_be_bytes());
+                    encoded.extend_from_slice(bytes);
+                }
+            }
+        }
+
+        let digest = match &self.key {
+            Some(key) => {
+                let mut mac =
+                    Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+                mac.update(&encoded);
+                mac.finalize().into_bytes().to_vec()
+            }
+            None => Sha256::digest(&encoded).to_vec(),
+        };
+        let hex: String = digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+        Ok(format!("{}:{}", self.algorithm(), hex))
+    }
+
+    /// Store the hash of memory `id` as it now stands
+    pub fn seal(&self, conn: &rusqlite::Connection, id: &str) -> Result<()> {
+        let hash = conn.query_row(
+            &format!("SELECT {} FROM memories WHERE id = ?1", SEALED_COLUMNS),
+            [id],
+            |row| self.hash_row(row),
+        )?;
+        conn.execute(
+            "UPDATE memories SET integrity_hash = ?2 WHERE id = ?1",
+            rusqlite::params![id, hash],
+        )?;
+        Ok(())
+    }
+}
+
+/// What [`verify`] found
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct IntegrityReport {
+    /// `hmac-sha256` or `sha256`
+    pub algorithm: String,
+    /// Memories checked, sealed or not
+    pub checked: usize,
+    /// Memories with no hash, saved before sealing was enabled or inserted
+    /// behind Memex's back
+    pub unsealed: Vec<String>,
+    /// Memories whose hash doesn't match their row
+    pub mismatched: Vec<IntegrityViolation>,
+}
+
+impl IntegrityReport {
+    /// Whether every checked memory matched its hash
+    pub fn is_intact(&self) -> bool {
+        self.unsealed.is_empty() && self.mismatched.is_empty()
+    }
+}
+
+/// A memory whose row no longer matches its hash
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IntegrityViolation {
+    pub memory_id: String,
+    pub user_id: String,
+    /// Algorithm of the stored hash, if it isn't the verifier's
+    pub sealed_with: Option<String>,
+}
+
+/// Recompute the hash of every memory of `user_id`, or of every memory if
+/// `None`, and compare it with the stored one
+pub fn verify(
+    conn: &rusqlite::Connection,
+    sealer: &Sealer,
+    user_id: Option<&str>,
+) -> Result<IntegrityReport> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {}, integrity_hash FROM memories WHERE ?1 IS NULL OR user_id = ?1 ORDER BY id",
+        SEALED_COLUMNS
+    ))?;
+    let rows = stmt.query_map([user_id], |row| {
+        let stored: Option<String> = row.get("integrity_hash")?;
+        let id: String = row.get("id")?;
+        let user_id: String = row.get("user_id")?;
+        Ok((id, user_id, stored, sealer.hash_row(row)?))
+    })?;
+
+    let mut report = IntegrityReport {
+        algorithm: sealer.algorithm().to_string(),
+        ..Default::default()
+    };
+    for row in rows {
+        let (id, user_id, stored, expected) = row.context("Failed to read memory")?;
+        report.checked += 1;
+        match stored {
+            None => report.unsealed.push(id),
+            Some(stored) if stored == expected => {}
+            Some(stored) => {
+                let algorithm = stored.split(':').next().unwrap_or_default();
+                report.mismatched.push(IntegrityViolation {
+                    memory_id: id,
+                    user_id,
+                    sealed_with: (algorithm != sealer.algorithm()).then(|| algorithm.to_string()),
+                });
+            }
+        }
+    }
+    Ok(report)
+}
+
+/// Seal every memory that has no hash yet; returns how many were sealed
+pub fn seal_unsealed(conn: &rusqlite::Connection, sealer: &Sealer) -> Result<usize> {
+    let ids = {
+        let mut stmt = conn.prepare("SELECT id FROM memories WHERE integrity_hash IS NULL")?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()?
+    };
+    for id in &ids {
+        sealer.seal(conn, id)?;
+    }
+    Ok(ids.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_rows_sealed_with_a_key_only_verify_with_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        let id = database
+            .save_memory(&MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: "Signed".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        let pool = database.get_connection_pool();
+        let conn = pool.get_connection().unwrap();
+        let keyed = Sealer::new(Some(b"secret"));
+        keyed.seal(&conn, &id).unwrap();
+
+        assert!(verify(&conn, &keyed, None).unwrap().is_intact());
+        let wrong_key = verify(&conn, &Sealer::new(Some(b"guess")), None).unwrap();
+        assert_eq!(wrong_key.mismatched.len(), 1);
+        assert_eq!(wrong_key.mismatched[0].sealed_with, None);
+
+        let unkeyed = verify(&conn, &Sealer::new(None), None).unwrap();
+        assert_eq!(
+            unkeyed.mismatched[0].sealed_with.as_deref(),
+            Some("hmac-sha256")
+        );
+    }
+}
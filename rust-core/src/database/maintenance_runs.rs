@@ -0,0 +1,221 @@
+//! Maintenance run history
+//!
+//! Every job the maintenance scheduler runs, whether on its schedule or on
+//! demand, leaves a row in `maintenance_runs` with its outcome. The scheduler
+//! also reads the table back to decide when each job is next due, so
+//! processes sharing a database file see each other's runs.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Older runs are pruned so the history can't grow without bound
+const MAX_RECORDED: i64 = 10_000;
+
+/// A periodic maintenance job
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MaintenanceJob {
+    /// Expire, compress and archive per the decay policy
+    Decay,
+    /// Fold the WAL into the database file and truncate it
+    Checkpoint,
+    /// Refresh the query planner's statistics
+    Analyze,
+    /// Record the current statistics in `stats_history`
+    StatsSnapshot,
+    /// Embed memories that lack an embedding
+    EmbeddingBackfill,
+    /// Back up the database and prune old backups
+    Backup,
+    /// Archive new WAL frames for point-in-time recovery
+    WalArchive,
+}
+
+impl MaintenanceJob {
+    pub const ALL: [MaintenanceJob; 7] = [
+        MaintenanceJob::Decay,
+        MaintenanceJob::Checkpoint,
+        MaintenanceJob::Analyze,
+        MaintenanceJob::StatsSnapshot,
+        MaintenanceJob::EmbeddingBackfill,
+        MaintenanceJob::Backup,
+        MaintenanceJob::WalArchive,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaintenanceJob::Decay => "decay",
+            MaintenanceJob::Checkpoint => "checkpoint",
+            MaintenanceJob::Analyze => "analyze",
+            MaintenanceJob::StatsSnapshot => "stats_snapshot",
+            MaintenanceJob::EmbeddingBackfill => "embedding_backfill",
+            MaintenanceJob::Backup => "backup",
+            MaintenanceJob::WalArchive => "wal_archive",
+        }
+    }
+}
+
+impl fmt::Display for MaintenanceJob {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MaintenanceJob {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let name = s.to_lowercase().replace('-', "_");
+        MaintenanceJob::ALL
+            .into_iter()
+            .find(|job| job.as_str() == name)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Unknown maintenance job '{}' (expected one of {})",
+                    s,
+                    MaintenanceJob::ALL.map(|job| job.as_str()).join(", ")
+                )
+            })
+    }
+}
+
+/// How a maintenance run ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MaintenanceStatus {
+    Completed,
+    Failed,
+    /// Nothing to do: another instance holds the maintenance lock, the
+    /// database is read-only or the job isn't set up
+    Skipped,
+}
+
+impl MaintenanceStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            MaintenanceStatus::Completed => "completed",
+            MaintenanceStatus::Failed => "failed",
+            MaintenanceStatus::Skipped => "skipped",
+        }
+    }
+}
+
+impl fmt::Display for MaintenanceStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for MaintenanceStatus {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s {
+            "completed" => Ok(MaintenanceStatus::Completed),
+            "failed" => Ok(MaintenanceStatus::Failed),
+            "skipped" => Ok(MaintenanceStatus::Skipped),
+            _ => Err(anyhow::anyhow!("Invalid maintenance status: {}", s)),
+        }
+    }
+}
+
+/// One recorded run of a maintenance job
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceRun {
+    /// Assigned when the run is recorded; 0 before
+    pub id: i64,
+    pub job: MaintenanceJob,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub status: MaintenanceStatus,
+    /// What the job did, e.g. its `DecayStats` or `WalCheckpoint`
+    pub detail: Option<serde_json::Value>,
+    /// Why the run failed or was skipped
+    pub message: Option<String>,
+}
+
+/// Store a run, returning its ID
+pub fn record(conn: &rusqlite::Connection, run: &MaintenanceRun) -> Result<i64> {
+    conn.execute(
+        "INSERT INTO maintenance_runs (job, started_at, completed_at, status, detail, message)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        rusqlite::params![
+            run.job.as_str(),
+            run.started_at,
+            run.completed_at,
+            run.status.as_str(),
+            run.detail.as_ref().map(|detail| detail.to_string()),
+            run.message,
+        ],
+    )?;
+    let id = conn.last_insert_rowid();
+    conn.execute(
+        "DELETE FROM maintenance_runs WHERE id <= ?1 - ?2",
+        [id, MAX_RECORDED],
+    )?;
+    Ok(id)
+}
+
+/// When `job` last started, skipped runs included
+pub fn last_started_at(
+    conn: &rusqlite::Connection,
+    job: MaintenanceJob,
+) -> Result<Option<DateTime<Utc>>> {
+    Ok(conn
+        .query_row(
+            "SELECT started_at FROM maintenance_runs WHERE job = ?1
+             ORDER BY started_at DESC, id DESC LIMIT 1",
+            [job.as_str()],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// The `limit` most recent runs, of `job` or of every job, newest first
+pub fn recent(
+    conn: &rusqlite::Connection,
+    job: Option<MaintenanceJob>,
+    limit: usize,
+) -> Result<Vec<MaintenanceRun>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, job, started_at, completed_at, status, detail, message
+         FROM maintenance_runs WHERE ?1 IS NULL OR job = ?1
+         ORDER BY id DESC LIMIT ?2",
+    )?;
+
+    let rows = stmt.query_map(
+        rusqlite::params![job.map(|job| job.as_str()), limit as i64],
+        |row| {
+            Ok((
+                row.get::<_, i64>("id")?,
+                row.get::<_, String>("job")?,
+                row.get::<_, DateTime<Utc>>("started_at")?,
+                row.get::<_, DateTime<Utc>>("completed_at")?,
+                row.get::<_, String>("status")?,
+                row.get::<_, Option<String>>("detail")?,
+                row.get::<_, Option<String>>("message")?,
+            ))
+        },
+    )?;
+
+    let mut runs = Vec::new();
+    for row in rows {
+        let (id, job, started_at, completed_at, status, detail, message) = row?;
+        runs.push(MaintenanceRun {
+            id,
+            job: job.parse()?,
+            started_at,
+            completed_at,
+            status: status.parse()?,
+            detail: detail
+                .map(|detail| serde_json::from_str(&detail))
+                .transpose()?,
+            message,
+        });
+    }
+    Ok(runs)
+}
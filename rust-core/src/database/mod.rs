@@ -1,10 +1,25 @@
 //! Database module for Memex
 //!
 //! Provides SQLite-based storage with FTS5 full-text search capabilities.
+pub mod audit;
+pub mod backup;
+pub mod fuzzy;
+pub mod hot_tier;
+pub mod ids;
+pub mod index_advisor;
+pub mod instance_lock;
+pub mod integrity;
+pub mod maintenance_runs;
 pub mod models;
 pub mod pool;
+pub mod query;
 pub mod schema;
+pub mod sharded;
 pub mod simple_db;
+pub mod slow_query;
+pub mod snapshot;
+pub mod stats_history;
+pub mod wal_archive;
 
 #[cfg(feature = "vector-search")]
 pub mod vector;
@@ -12,28 +27,217 @@ pub mod vector;
 #[cfg(feature = "async")]
 pub mod async_db;
 
+#[cfg(feature = "s3-backup")]
+pub mod s3;
+
 use anyhow::{Context, Result};
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
-use uuid::Uuid;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
 use validator::Validate;
 
-use crate::database::models::{MemoryItem, PaginatedResponse, QueryFilter};
-use crate::database::pool::ConnectionPool;
+use crate::core::analytics::{
+    SessionUsage, TopicShare, UserAnalytics, ANALYTICS_TOP_SESSIONS, ANALYTICS_TOP_TOPICS,
+    GROWTH_WINDOW_DAYS,
+};
+use crate::database::audit::AuditAction;
+use crate::database::ids::{IdGenerator, IdKind, IdStrategy};
+use crate::database::instance_lock::{LockHolder, LockTakeover, MAINTENANCE_LOCK};
+use crate::database::models::{
+    ActivityGap, DecayStats, DecayStatus, Footprint, HistogramBucket, LinkType, MemoryItem,
+    MemoryLink, MemoryState, MemoryTemplate, MetadataMigration, MetadataMigrationProgress,
+    NamespaceSchema, PaginatedResponse, QueryFilter, RankedBy, RecallExplanation, RetentionRule,
+    SaveReceipt, SessionStatus, SessionSummary, StorageUsage, Summary, SummaryGranularity, Synonym,
+    UserActivity, UserOverview, UserPurge, ValueDistribution, SESSION_SUMMARY_TYPE,
+    TAGS_METADATA_KEY, TYPE_METADATA_KEY,
+};
+use crate::database::pool::{ConnectionPool, TempStore, TuningPreset};
+use crate::database::query::{Column, Condition, Op, Order, SelectQuery};
+use crate::display::truncate_graphemes;
+
+/// Owned, heterogeneous SQL parameters for dynamically built queries
+#[cfg(feature = "vector-search")]
+type QueryParams = Vec<Box<dyn rusqlite::ToSql>>;
+
+/// Columns read by [`row_to_memory`]
+const MEMORY_COLUMNS: &str = "id, user_id, session_id, content, metadata, \
+     created_at, updated_at, expires_at, importance, ttl_hours, is_compressed, compressed_from, \
+     version, seq, content_type, state, confidence, source_type, source_ref, ingested_by";
+
+/// IDs bound per `IN (...)` query in `Database::get_memories`; well below
+/// SQLite's default limit of 999 parameters
+const GET_MEMORIES_CHUNK: usize = 500;
+
+/// Longest name given to a session created by saving a memory into it
+const AUTO_SESSION_NAME_GRAPHEMES: usize = 60;
+
+/// Weight of embedding similarity against full-text relevance in
+/// `Database::recall_semantic`
+#[cfg(feature = "vector-search")]
+const SEMANTIC_WEIGHT: f32 = 0.7;
+
+/// Every user ID with a user record, session or memory, each once; all
+/// three come from indexes or the `memory_usage` counters
+const USER_IDS_SQL: &str = "SELECT id FROM users UNION SELECT user_id FROM sessions \
+     UNION SELECT user_id FROM memory_usage";
 
 /// Database configuration with connection pooling support
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Missing fields deserialize to their defaults; unknown fields are rejected
+/// so that typos in config files surface instead of being silently ignored.
+#[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(default, deny_unknown_fields)]
+#[validate(schema(function = "validate_database_config"))]
 pub struct DatabaseConfig {
+    #[validate(length(min = 1, message = "database path must not be empty"))]
     pub path: String,
     pub enable_wal: bool,
+    /// `PRAGMA cache_size`: pages if positive, KiB if negative
     pub cache_size: i64,
+    #[validate(range(max = 600000, message = "busy_timeout must be at most 600000 ms"))]
     pub busy_timeout: u32,
     pub synchronous: String,
+    /// `PRAGMA mmap_size`: bytes of the file read through memory mapping;
+    /// 0 turns memory mapping off
+    #[validate(range(min = 0, message = "mmap_size must not be negative"))]
+    pub mmap_size: i64,
+    /// `PRAGMA temp_store`: where temporary tables and indices live
+    pub temp_store: TempStore,
+    /// `PRAGMA journal_size_limit`: bytes the WAL or rollback journal is
+    /// truncated to after a checkpoint or transaction; -1 leaves it be
+    #[validate(range(min = -1, message = "journal_size_limit must be -1 or more"))]
+    pub journal_size_limit: i64,
+    /// `PRAGMA wal_autocheckpoint`: WAL pages that trigger a checkpoint;
+    /// 0 leaves checkpoints to the application
+    pub wal_autocheckpoint: u32,
+    /// WAL file size in bytes at which periodic maintenance runs
+    /// `PRAGMA wal_checkpoint(TRUNCATE)`; 0 never truncates automatically
+    pub wal_truncate_threshold_bytes: u64,
+    #[validate(range(
+        min = 1,
+        max = 1000,
+        message = "max_connections must be between 1 and 1000"
+    ))]
     pub max_connections: u32,
     pub min_connections: u32,
     pub enable_read_replicas: bool,
     pub read_replica_paths: Vec<String>,
+    /// How many times a write transaction is retried after SQLITE_BUSY/LOCKED
+    #[validate(range(max = 100, message = "write_max_retries must be at most 100"))]
+    pub write_max_retries: u32,
+    /// First retry delay; doubles on each attempt (with jitter)
+    #[validate(range(
+        min = 1,
+        max = 60000,
+        message = "write_retry_base_delay_ms must be between 1 and 60000"
+    ))]
+    pub write_retry_base_delay_ms: u64,
+    /// Total time budget for retrying one write before giving up
+    #[validate(range(
+        max = 600000,
+        message = "write_retry_timeout_ms must be at most 600000"
+    ))]
+    pub write_retry_timeout_ms: u64,
+    /// Pooled connections older than this are closed and replaced; 0 keeps
+    /// them forever
+    pub connection_max_lifetime_secs: u64,
+    /// A connection that hasn't been checked for this long runs
+    /// `PRAGMA quick_check` on checkout (every checkout runs `SELECT 1`);
+    /// 0 disables the quick check
+    pub health_check_interval_secs: u64,
+    /// Read queries running longer than this are cancelled with a
+    /// [`pool::QueryTimeout`] error; 0 lets them run indefinitely
+    #[validate(range(
+        max = 600000,
+        message = "default_query_timeout_ms must be at most 600000"
+    ))]
+    pub default_query_timeout_ms: u64,
+    /// Queries taking at least this long are recorded in `slow_queries`;
+    /// 0 turns the slow-query log off
+    pub slow_query_threshold_ms: u64,
+    /// Also capture `EXPLAIN QUERY PLAN` output for slow queries
+    pub explain_slow_queries: bool,
+    /// Minimum time between automatic `stats_history` snapshots; 0 takes
+    /// them only on demand
+    pub stats_snapshot_interval_hours: u32,
+    /// How new memory and session IDs are generated; see [`ids`]
+    pub id_strategy: IdStrategy,
+    /// Whether this instance may take the maintenance lock from another
+    /// process; see [`instance_lock`]
+    pub lock_takeover: LockTakeover,
+    /// A lock holder that hasn't refreshed its heartbeat for this long
+    /// counts as gone
+    #[validate(range(min = 1, message = "lock_stale_after_secs must be at least 1"))]
+    pub lock_stale_after_secs: u64,
+    /// Open connections with `SQLITE_OPEN_READONLY`: writes fail with
+    /// [`pool::ReadOnlyDatabase`] and the file is never locked for writing,
+    /// while recall and keyword/vector search keep working
+    pub read_only: bool,
+    /// Keep a trigram index of memory content so fuzzy recall doesn't have
+    /// to scan with `LIKE`; costs roughly the size of the content again
+    pub trigram_index: bool,
+    /// Tokenizer of the full-text index; changing it rebuilds the index the
+    /// next time the database is opened for writing
+    pub fts_tokenizer: schema::FtsTokenizer,
+    /// SQLite extensions (such as sqlite-vec or spellfix) loaded into every
+    /// pooled connection, in order; see [`pool::load_extensions`]
+    pub extensions: Vec<PathBuf>,
+    /// Expired memories are kept this long before `cleanup_expired` deletes
+    /// them; recall skips them meanwhile, but `restore_expired_memory` can
+    /// bring them back. 0 deletes them as soon as they expire
+    pub expiry_grace_hours: u32,
+    /// Bytes of memories kept in memory so lookups by ID skip SQLite; see
+    /// [`hot_tier`]. 0 turns the tier off
+    pub hot_tier_bytes: u64,
+    /// How long a save's `MemoryItem::idempotency_key` is remembered, so a
+    /// retry within this long returns the first save's memory
+    #[validate(range(min = 1, message = "idempotency_key_ttl_hours must be at least 1"))]
+    pub idempotency_key_ttl_hours: u32,
+}
+
+/// Cross-field checks for `DatabaseConfig`
+fn validate_database_config(config: &DatabaseConfig) -> Result<(), validator::ValidationError> {
+    fn invalid(code: &'static str, message: String) -> validator::ValidationError {
+        let mut error = validator::ValidationError::new(code);
+        error.message = Some(message.into());
+        error
+    }
+
+    // Interpolated into a PRAGMA, so only the documented values are allowed
+    if !["OFF", "NORMAL", "FULL", "EXTRA"].contains(&config.synchronous.to_uppercase().as_str()) {
+        return Err(invalid(
+            "synchronous",
+            format!(
+                "synchronous must be one of OFF, NORMAL, FULL, EXTRA (got '{}')",
+                config.synchronous
+            ),
+        ));
+    }
+
+    if config.min_connections > config.max_connections {
+        return Err(invalid(
+            "min_connections",
+            format!(
+                "min_connections ({}) must not exceed max_connections ({})",
+                config.min_connections, config.max_connections
+            ),
+        ));
+    }
+
+    if config.enable_read_replicas && config.read_replica_paths.is_empty() {
+        return Err(invalid(
+            "read_replica_paths",
+            "read_replica_paths must list at least one path when read replicas are enabled"
+                .to_string(),
+        ));
+    }
+
+    Ok(())
 }
 
 impl Default for DatabaseConfig {
@@ -44,38 +248,199 @@ impl Default for DatabaseConfig {
             cache_size: -64000,  // 64MB cache
             busy_timeout: 30000, // 30 seconds
             synchronous: "NORMAL".to_string(),
+            mmap_size: 256 * 1024 * 1024, // 256MB
+            temp_store: TempStore::Memory,
+            journal_size_limit: -1,
+            wal_autocheckpoint: 1000,
+            wal_truncate_threshold_bytes: 64 * 1024 * 1024, // 64MB
             max_connections: 10,
             min_connections: 2,
             enable_read_replicas: false,
             read_replica_paths: Vec::new(),
+            write_max_retries: 5,
+            write_retry_base_delay_ms: 20,
+            write_retry_timeout_ms: 10000,
+            connection_max_lifetime_secs: 3600, // 1 hour
+            health_check_interval_secs: 300,    // 5 minutes
+            default_query_timeout_ms: 30000,    // 30 seconds
+            slow_query_threshold_ms: 500,
+            explain_slow_queries: false,
+            stats_snapshot_interval_hours: 24,
+            id_strategy: IdStrategy::Uuid,
+            lock_takeover: LockTakeover::IfStale,
+            lock_stale_after_secs: 3600, // 1 hour
+            read_only: false,
+            trigram_index: true,
+            fts_tokenizer: schema::FtsTokenizer::Unicode61,
+            extensions: Vec::new(),
+            expiry_grace_hours: 0,
+            hot_tier_bytes: 0,
+            idempotency_key_ttl_hours: 24,
+        }
+    }
+}
+
+impl DatabaseConfig {
+    /// Default configuration for `path` tuned by `preset`
+    pub fn with_preset(path: impl Into<String>, preset: TuningPreset) -> Self {
+        let mut config = Self {
+            path: path.into(),
+            ..Default::default()
+        };
+        config.apply_preset(preset);
+        config
+    }
+
+    /// Overwrite the settings `preset` tunes, leaving everything else
+    pub fn apply_preset(&mut self, preset: TuningPreset) {
+        match preset {
+            TuningPreset::LowMemory => {
+                self.cache_size = -2000; // 2MB
+                self.mmap_size = 0;
+                self.temp_store = TempStore::File;
+                self.journal_size_limit = 4 * 1024 * 1024;
+                self.wal_autocheckpoint = 1000;
+                self.wal_truncate_threshold_bytes = 8 * 1024 * 1024;
+                self.max_connections = 2;
+                self.min_connections = 1;
+            }
+            TuningPreset::HighThroughput => {
+                self.enable_wal = true;
+                self.cache_size = -256000; // 256MB
+                self.synchronous = "NORMAL".to_string();
+                self.mmap_size = 1024 * 1024 * 1024;
+                self.temp_store = TempStore::Memory;
+                self.journal_size_limit = 64 * 1024 * 1024;
+                self.wal_autocheckpoint = 10000;
+                self.wal_truncate_threshold_bytes = 512 * 1024 * 1024;
+                self.max_connections = 20;
+            }
+            TuningPreset::Durable => {
+                self.enable_wal = true;
+                self.synchronous = "FULL".to_string();
+                self.journal_size_limit = -1;
+                self.wal_autocheckpoint = 1000;
+            }
         }
     }
 }
 
+/// An update expected a different memory version than the stored one
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Memory {id} is at version {actual}, not the expected {expected}")]
+pub struct VersionConflict {
+    pub id: String,
+    pub expected: i64,
+    pub actual: i64,
+}
+
+/// A memory was asked to move to a state its current one can't become
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Memory {id} is {from} and can't become {to}")]
+pub struct InvalidStateTransition {
+    pub id: String,
+    pub from: MemoryState,
+    pub to: MemoryState,
+}
+
+/// How SQLite would run a recall, from [`Database::explain_recall`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecallPlan {
+    pub sql: String,
+    /// Bound parameters in placeholder order, e.g. `?1=text(7 bytes)`;
+    /// text is redacted to its length, numbers are shown
+    pub params: Vec<String>,
+    /// `EXPLAIN QUERY PLAN` output, one step per line, indented by depth
+    pub query_plan: String,
+    /// Plan steps that read every row of a table or index instead of
+    /// searching one
+    pub full_scans: Vec<String>,
+}
+
 /// High-performance database with connection pooling and read replicas
 pub struct Database {
     write_pool: ConnectionPool,
     read_pools: Vec<ConnectionPool>,
     config: DatabaseConfig,
     read_replica_index: std::sync::atomic::AtomicUsize,
+    id_generator: Arc<dyn IdGenerator>,
+    instance: Arc<InstanceRegistration>,
+    /// Shared by clones, so every checkpoint goes through the one archiver
+    wal_archiver: Arc<std::sync::Mutex<Option<wal_archive::WalArchiver>>>,
+    /// Set once sealing is enabled; memory writes then store a hash of the
+    /// row
+    sealer: Arc<std::sync::OnceLock<integrity::Sealer>>,
+    /// Shared by clones; the last one to go writes out the pending counts
+    query_shapes: Arc<index_advisor::ShapeRecorder>,
+    /// Shared by clones, so a write through one updates what the others
+    /// serve
+    hot_tier: Arc<hot_tier::HotTier>,
+}
+
+/// Releases an instance's locks once its last `Database` clone is dropped
+struct InstanceRegistration {
+    id: String,
+    pool: ConnectionPool,
+}
+
+impl Drop for InstanceRegistration {
+    fn drop(&mut self) {
+        // A read-only instance never takes locks
+        if self.pool.is_read_only() {
+            return;
+        }
+        let released = self
+            .pool
+            .get_connection()
+            .and_then(|conn| instance_lock::release_all(&conn, &self.id));
+        if let Err(e) = released {
+            tracing::warn!(instance_id = self.id.as_str(), error = %e, "Failed to release instance locks");
+        }
+    }
 }
 
 impl Database {
     /// Create a new database instance with connection pooling
     pub fn new(config: DatabaseConfig) -> Result<Self> {
+        config
+            .validate()
+            .context("Invalid database configuration")?;
+
         // Create write pool (primary database)
         let write_pool = ConnectionPool::new(config.clone())?;
 
-        // Initialize schema on primary database
-        write_pool.with_write_transaction(|tx| {
-            tx.execute_batch(schema::SCHEMA_SQL)
-                .context("Failed to initialize database schema")?;
-            tx.execute_batch(schema::INDEXES_SQL)
-                .context("Failed to create database indexes")?;
-            tx.execute_batch(schema::FTS_SQL)
-                .context("Failed to initialize FTS5 tables")?;
-            Ok(())
-        })?;
+        // Initialize schema on primary database; a read-only database must
+        // already have one
+        if !config.read_only {
+            write_pool.with_write_transaction(|tx| {
+                tx.execute_batch(schema::SCHEMA_SQL)
+                    .context("Failed to initialize database schema")?;
+                schema::add_missing_columns(tx).context("Failed to upgrade database schema")?;
+                schema::drop_removed_columns(tx).context("Failed to upgrade database schema")?;
+                tx.execute_batch(schema::INDEXES_SQL)
+                    .context("Failed to create database indexes")?;
+                tx.execute_batch(schema::FTS_SQL)
+                    .context("Failed to initialize FTS5 tables")?;
+                let has_usage = has_table(tx, "memory_usage")?;
+                tx.execute_batch(schema::USAGE_SQL)
+                    .context("Failed to initialize storage usage table")?;
+                if !has_usage {
+                    // Memories saved before usage was tracked
+                    tx.execute_batch(schema::USAGE_REBUILD_SQL)
+                        .context("Failed to count storage usage")?;
+                }
+                schema::set_fts_tokenizer(tx, config.fts_tokenizer)
+                    .context("Failed to change full-text tokenizer")?;
+                if config.trigram_index && !has_trigram_index(tx)? {
+                    tx.execute_batch(schema::TRIGRAM_SQL)
+                        .context("Failed to create trigram index")?;
+                    // Memories saved before the index existed
+                    tx.execute_batch(schema::TRIGRAM_REBUILD_SQL)
+                        .context("Failed to build trigram index")?;
+                }
+                Ok(())
+            })?;
+        }
 
         // Create read replica pools if enabled
         let mut read_pools = Vec::new();
@@ -90,7 +455,7 @@ impl Database {
                 let replica_pool = ConnectionPool::new(replica_config)?;
                 read_pools.push(replica_pool);
 
-                log::info!("Initialized read replica: {}", replica_path);
+                tracing::info!(path = replica_path.as_str(), "Initialized read replica");
             }
         }
 
@@ -99,17 +464,98 @@ impl Database {
             read_pools.push(write_pool.clone());
         }
 
-        log::info!(
-            "Database initialized successfully with {} read pools",
-            read_pools.len()
-        );
+        tracing::info!(read_pools = read_pools.len(), "Database initialized");
 
-        Ok(Self {
+        let instance = Arc::new(InstanceRegistration {
+            id: uuid::Uuid::new_v4().to_string(),
+            pool: write_pool.clone(),
+        });
+
+        let query_shapes = Arc::new(index_advisor::ShapeRecorder::new(
+            write_pool.clone(),
+            &config,
+        ));
+        let hot_tier = Arc::new(hot_tier::HotTier::new(config.hot_tier_bytes));
+
+        let database = Self {
             write_pool,
             read_pools,
+            id_generator: config.id_strategy.generator(),
             config,
             read_replica_index: std::sync::atomic::AtomicUsize::new(0),
-        })
+            instance,
+            wal_archiver: Arc::new(std::sync::Mutex::new(None)),
+            sealer: Arc::new(std::sync::OnceLock::new()),
+            query_shapes,
+            hot_tier,
+        };
+
+        // Read-only instances never run maintenance, so they don't compete
+        if database.config.read_only {
+            return Ok(database);
+        }
+        if let Some(holder) = database.maintenance_lock_holder()? {
+            if !holder.is_stale(database.lock_stale_after(), Utc::now()) {
+                tracing::warn!(
+                    path = database.config.path.as_str(),
+                    pid = holder.pid,
+                    host = holder.hostname.as_str(),
+                    "Database is already open in another process; it runs maintenance"
+                );
+            }
+        }
+
+        Ok(database)
+    }
+
+    /// Random ID this instance (and its clones) registers locks under
+    pub fn instance_id(&self) -> &str {
+        &self.instance.id
+    }
+
+    fn lock_stale_after(&self) -> chrono::Duration {
+        chrono::Duration::seconds(self.config.lock_stale_after_secs.min(i64::MAX as u64) as i64)
+    }
+
+    /// Take or refresh the maintenance lock, following
+    /// `DatabaseConfig::lock_takeover`; returns false if another instance
+    /// keeps it
+    pub fn acquire_maintenance_lock(&self) -> Result<bool> {
+        let candidate = LockHolder::current(self.instance_id());
+        let holder = self.write_pool.with_write_transaction(|tx| {
+            instance_lock::acquire(
+                tx,
+                MAINTENANCE_LOCK,
+                &candidate,
+                self.config.lock_takeover,
+                self.lock_stale_after(),
+            )
+        })?;
+        Ok(holder.instance_id == candidate.instance_id)
+    }
+
+    /// Give up every lock this instance holds
+    pub fn release_instance_locks(&self) -> Result<usize> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        instance_lock::release_all(&conn, self.instance_id())
+    }
+
+    /// The instance holding the maintenance lock, if any
+    pub fn maintenance_lock_holder(&self) -> Result<Option<LockHolder>> {
+        let conn = self.write_pool.get_connection()?;
+        instance_lock::holder(&conn, MAINTENANCE_LOCK)
+    }
+
+    /// Generate IDs with `generator` instead of `DatabaseConfig::id_strategy`
+    pub fn with_id_generator(mut self, generator: Arc<dyn IdGenerator>) -> Self {
+        self.id_generator = generator;
+        self
+    }
+
+    /// A new ID from this instance's generator
+    pub fn generate_id(&self, kind: IdKind) -> String {
+        self.id_generator.generate(kind)
     }
 
     /// Get a read pool using round-robin selection
@@ -126,64 +572,62 @@ impl Database {
 
     /// Save a memory item (write operation)
     pub fn save_memory(&self, memory: &MemoryItem) -> Result<String> {
+        Ok(self.save_memory_with_receipt(memory)?.id)
+    }
+
+    /// Save a memory item and report what the write did
+    pub fn save_memory_with_receipt(&self, memory: &MemoryItem) -> Result<SaveReceipt> {
         // Validate input
         memory.validate().context("Memory validation failed")?;
 
         let id = if memory.id.is_empty() {
-            Uuid::new_v4().to_string()
+            self.generate_id(IdKind::Memory)
         } else {
             memory.id.clone()
         };
 
-        let now = Utc::now();
-        let expires_at = memory
-            .ttl_hours
-            .map(|ttl| now + chrono::Duration::hours(ttl as i64));
-
-        self.write_pool.with_write_transaction(|tx| {
-            // Insert into memories table
-            tx.execute(
-                r#"
-                INSERT OR REPLACE INTO memories (
-                    id, user_id, session_id, content, content_vector, metadata,
-                    created_at, updated_at, expires_at, importance, ttl_hours,
-                    is_compressed, compressed_from
-                ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
-                "#,
-                rusqlite::params![
-                    id,
-                    memory.user_id,
-                    memory.session_id,
-                    memory.content,
-                    memory.content_vector,
-                    serde_json::to_string(&memory.metadata)?,
-                    memory.created_at,
-                    now, // updated_at
-                    expires_at,
-                    memory.importance,
-                    memory.ttl_hours,
-                    memory.is_compressed,
-                    serde_json::to_string(&memory.compressed_from)?,
-                ],
-            )?;
-
-            // Insert into FTS5 table for full-text search
-            tx.execute(
-                "INSERT OR REPLACE INTO memories_fts (rowid, content) VALUES ((SELECT rowid FROM memories WHERE id = ?1), ?2)",
-                rusqlite::params![id, memory.content],
-            )?;
-
-            // Update session last_active
-            tx.execute(
-                "UPDATE sessions SET last_active = ?1 WHERE id = ?2",
-                rusqlite::params![now, memory.session_id],
-            )?;
-
-            Ok(())
+        let receipt = self.write_pool.with_write_transaction(|tx| {
+            let now = Utc::now();
+            if let Some(key) = &memory.idempotency_key {
+                // Checked again in the transaction, for a retry racing the
+                // first attempt
+                if let Some(replayed) = replayed_save(tx, &memory.user_id, key)? {
+                    return Ok(replayed);
+                }
+            }
+            let receipt = insert_memory(tx, memory, &id, now)?;
+            if let Some(key) = &memory.idempotency_key {
+                let expires_at =
+                    now + chrono::Duration::hours(self.config.idempotency_key_ttl_hours as i64);
+                tx.execute("DELETE FROM idempotency_keys WHERE expires_at <= ?1", [now])?;
+                tx.execute(
+                    "INSERT OR REPLACE INTO idempotency_keys
+                     (user_id, key, memory_id, created_at, expires_at)
+                     VALUES (?1, ?2, ?3, ?4, ?5)",
+                    rusqlite::params![memory.user_id, key, receipt.id, now, expires_at],
+                )?;
+            }
+            self.seal(tx, &receipt.id)?;
+            audit::record(tx, AuditAction::MemorySave, &receipt.id)?;
+            Ok(receipt)
         })?;
+        if receipt.replayed {
+            tracing::debug!(
+                memory_id = receipt.id.as_str(),
+                user_id = memory.user_id.as_str(),
+                "Replayed save by idempotency key"
+            );
+            return Ok(receipt);
+        }
+        self.hot_tier.remove([receipt.id.as_str()]);
 
-        log::debug!("Saved memory: {} for user: {}", id, memory.user_id);
-        Ok(id)
+        tracing::debug!(
+            memory_id = id.as_str(),
+            user_id = memory.user_id.as_str(),
+            deduplicated = receipt.deduplicated,
+            "Saved memory row"
+        );
+        Ok(receipt)
     }
 
     /// Recall memories with pagination and filtering (read operation)
@@ -192,18 +636,55 @@ impl Database {
         filter.validate().context("Filter validation failed")?;
 
         let read_pool = self.get_read_pool();
+        let timeout = filter
+            .timeout_ms
+            .map(Duration::from_millis)
+            .or_else(|| read_pool.default_query_timeout());
 
-        read_pool.with_read_connection(|conn| {
-            let (query, count_query, params) = self.build_recall_query(filter)?;
+        let started = std::time::Instant::now();
+        let epoch = self.hot_tier.epoch();
+        let recalled = read_pool.with_read_timeout(timeout, |conn| {
+            let recall_query = self.build_recall_query(filter, KeywordMatch::FullText);
+            let slow_queries = read_pool.slow_query_log();
 
             // Get total count
             let total_count: i64 = {
+                let (count_query, params_refs) = recall_query.to_count_sql();
                 let mut stmt = conn.prepare(&count_query)?;
-                let params_refs: Vec<&dyn rusqlite::ToSql> =
-                    params.iter().map(|p| p.as_ref()).collect();
-                stmt.query_row(&params_refs[..], |row| Ok(row.get(0)?))?
+                slow_queries.track(conn, &count_query, &params_refs, || {
+                    Ok(stmt.query_row(&params_refs[..], |row| row.get(0))?)
+                })?
             };
 
+            if total_count == 0 && filter.fuzzy {
+                if let Some(hits) = self.fuzzy_recall(conn, filter)? {
+                    let offset = filter.offset.unwrap_or(0);
+                    let per_page = filter.limit.unwrap_or(hits.len().max(1));
+                    let total_pages = hits.len().div_ceil(per_page);
+                    let page = offset / per_page;
+                    let hits_total = hits.len() as i64;
+                    #[allow(unused_mut)]
+                    let mut data: Vec<MemoryItem> =
+                        hits.into_iter().skip(offset).take(per_page).collect();
+                    if filter.explain {
+                        explain_ranking(&mut data, offset, RankedBy::FuzzySimilarity, None, None);
+                    }
+                    #[cfg(feature = "vector-search")]
+                    if filter.include_embeddings {
+                        vector::load_embeddings(conn, &mut data)?;
+                    }
+                    return Ok(PaginatedResponse {
+                        total_count: hits_total,
+                        data,
+                        page,
+                        per_page,
+                        total_pages,
+                        has_next: page < total_pages.saturating_sub(1),
+                        has_prev: page > 0,
+                    });
+                }
+            }
+
             // Calculate pagination info
             let page = filter
                 .offset
@@ -213,38 +694,50 @@ impl Database {
             let total_pages = ((total_count as f64) / (per_page as f64)).ceil() as usize;
 
             // Execute main query
+            let (query, params_refs) = recall_query.to_sql();
             let mut stmt = conn.prepare(&query)?;
-            let params_refs: Vec<&dyn rusqlite::ToSql> =
-                params.iter().map(|p| p.as_ref()).collect();
-            let memory_iter = stmt.query_map(&params_refs[..], |row| {
-                Ok(MemoryItem {
-                    id: row.get("id")?,
-                    user_id: row.get("user_id")?,
-                    session_id: row.get("session_id")?,
-                    content: row.get("content")?,
-                    content_vector: row.get("content_vector")?,
-                    #[cfg(feature = "vector-search")]
-                    embedding: None,
-                    #[cfg(feature = "vector-search")]
-                    embedding_model: None,
-                    metadata: serde_json::from_str(&row.get::<_, String>("metadata")?)
-                        .unwrap_or_default(),
-                    created_at: row.get("created_at")?,
-                    updated_at: row.get("updated_at")?,
-                    expires_at: row.get("expires_at")?,
-                    importance: row.get("importance")?,
-                    ttl_hours: row.get("ttl_hours")?,
-                    is_compressed: row.get("is_compressed")?,
-                    compressed_from: serde_json::from_str(
-                        &row.get::<_, String>("compressed_from")?,
-                    )
-                    .unwrap_or_default(),
-                })
+            let mut rows = stmt.query(&params_refs[..])?;
+            let memories = slow_queries.track(conn, &query, &params_refs, || {
+                let mut memories = Vec::new();
+                while let Some(row) = rows.next()? {
+                    memories.push(row_to_memory(row)?);
+                }
+                Ok(memories)
             })?;
-
-            let mut memories = Vec::new();
-            for memory in memory_iter {
-                memories.push(memory?);
+            // Exact hits outrank any fuzzy one
+            let keyword_search = filter
+                .keywords
+                .as_deref()
+                .and_then(schema::fts_match_query)
+                .is_some();
+            #[allow(unused_mut)]
+            let mut memories: Vec<MemoryItem> = if keyword_search {
+                memories
+                    .into_iter()
+                    .map(|memory| MemoryItem {
+                        match_score: Some(1.0),
+                        ..memory
+                    })
+                    .collect()
+            } else {
+                memories
+            };
+            if filter.explain {
+                let relevance = match filter.keywords.as_deref().and_then(schema::fts_match_query) {
+                    Some(match_query) => Some(text_relevance(conn, &match_query, &memories)?),
+                    None => None,
+                };
+                explain_ranking(
+                    &mut memories,
+                    filter.offset.unwrap_or(0),
+                    RankedBy::Recency,
+                    relevance.as_ref(),
+                    None,
+                );
+            }
+            #[cfg(feature = "vector-search")]
+            if filter.include_embeddings {
+                vector::load_embeddings(conn, &mut memories)?;
             }
 
             Ok(PaginatedResponse {
@@ -256,344 +749,3377 @@ impl Database {
                 has_next: page < total_pages.saturating_sub(1),
                 has_prev: page > 0,
             })
-        })
+        });
+        self.query_shapes
+            .record(index_advisor::QueryShape::of(filter), started.elapsed());
+        if let Ok(page) = &recalled {
+            self.hot_tier.fill(&page.data, epoch);
+        }
+        recalled
+    }
+
+    /// Hand every memory matching `filter` to `f`, `batch_size` at a time
+    /// in recall order (read operation)
+    ///
+    /// Each batch is its own query resuming after the last memory of the
+    /// one before by sort key, so `f` may delete what it is handed without
+    /// ending the scan, and writers don't wait on one long read. `filter`'s
+    /// limit, offset and cursor are ignored. Returns how many memories were
+    /// scanned.
+    pub fn scan_memories<F>(
+        &self,
+        filter: &QueryFilter,
+        batch_size: usize,
+        mut f: F,
+    ) -> Result<usize>
+    where
+        F: FnMut(Vec<MemoryItem>) -> Result<()>,
+    {
+        let filter = QueryFilter {
+            limit: Some(batch_size),
+            offset: None,
+            before_id: None,
+            ..filter.clone()
+        };
+        filter.validate().context("Filter validation failed")?;
+
+        let read_pool = self.get_read_pool();
+        let mut after: Option<(DateTime<Utc>, f32, String)> = None;
+        let mut scanned = 0;
+        loop {
+            let mut query = self.build_recall_query(&filter, KeywordMatch::FullText);
+            if let Some((created_at, importance, id)) = after.take() {
+                query.filter(
+                    Condition::sql("(created_at, importance, id) < (")
+                        .param(created_at)
+                        .then(", ")
+                        .param(importance)
+                        .then(", ")
+                        .param(id)
+                        .then(")"),
+                );
+            }
+            let batch = read_pool.with_read_connection(|conn| {
+                let (sql, params_refs) = query.to_sql();
+                let mut stmt = conn.prepare(&sql)?;
+                let mut rows = stmt.query(&params_refs[..])?;
+                let mut memories = Vec::new();
+                while let Some(row) = rows.next()? {
+                    memories.push(row_to_memory(row)?);
+                }
+                Ok(memories)
+            })?;
+
+            let Some(last) = batch.last() else {
+                break;
+            };
+            after = Some((last.created_at, last.importance, last.id.clone()));
+            let done = batch.len() < batch_size;
+            scanned += batch.len();
+            f(batch)?;
+            if done {
+                break;
+            }
+        }
+        Ok(scanned)
     }
 
-    /// Build SQL query for recall with filters (helper method)
-    fn build_recall_query(
+    /// Memories resembling `filter`'s keywords, best match first, or `None`
+    /// if no keyword is long enough to compare
+    fn fuzzy_recall(
         &self,
+        conn: &rusqlite::Connection,
         filter: &QueryFilter,
-    ) -> Result<(String, String, Vec<Box<dyn rusqlite::ToSql>>)> {
-        let mut conditions = Vec::new();
-        let mut params: Vec<Box<dyn rusqlite::ToSql>> = Vec::new();
-        let mut param_index = 1;
+    ) -> Result<Option<Vec<MemoryItem>>> {
+        let terms = fuzzy::search_terms(filter.keywords.as_deref().unwrap_or_default());
+        if terms.is_empty() {
+            return Ok(None);
+        }
+        let trigrams = fuzzy::candidate_trigrams(&terms);
+        let keyword_match = if has_trigram_index(conn)? {
+            KeywordMatch::Trigram(&trigrams)
+        } else {
+            KeywordMatch::Like(&trigrams)
+        };
+
+        let candidates = QueryFilter {
+            limit: Some(fuzzy::MAX_CANDIDATES),
+            offset: None,
+            ..filter.clone()
+        };
+        let recall_query = self.build_recall_query(&candidates, keyword_match);
+        let (query, params_refs) = recall_query.to_sql();
+        let slow_queries = self.get_read_pool().slow_query_log();
+        let mut stmt = conn.prepare(&query)?;
+        let mut rows = stmt.query(&params_refs[..])?;
+        let mut hits = slow_queries.track(conn, &query, &params_refs, || {
+            let mut hits = Vec::new();
+            while let Some(row) = rows.next()? {
+                let memory = row_to_memory(row)?;
+                let similarity = fuzzy::similarity(&terms, &memory.content);
+                if similarity >= fuzzy::MIN_SIMILARITY {
+                    hits.push(MemoryItem {
+                        match_score: Some(similarity * fuzzy::FUZZY_SCORE_WEIGHT),
+                        ..memory
+                    });
+                }
+            }
+            Ok(hits)
+        })?;
+
+        // Candidates arrive newest first, and the sort is stable
+        hits.sort_by(|a, b| b.match_score.partial_cmp(&a.match_score).unwrap());
+        tracing::debug!(terms = terms.len(), hits = hits.len(), "Fuzzy recall");
+        Ok(Some(hits))
+    }
+
+    /// The SQL [`recall_memories`](Self::recall_memories) runs for `filter`,
+    /// with its redacted parameters and SQLite's plan for it, without
+    /// running it
+    ///
+    /// Only the main query is explained: the count uses the same conditions,
+    /// and the fuzzy fallback's plan depends on the words it ends up trying.
+    pub fn explain_recall(&self, filter: &QueryFilter) -> Result<RecallPlan> {
+        filter.validate().context("Filter validation failed")?;
+
+        self.get_read_pool().with_read_connection(|conn| {
+            let recall_query = self.build_recall_query(filter, KeywordMatch::FullText);
+            let (sql, params) = recall_query.to_sql();
+            let query_plan = slow_query::explain(conn, &sql, &params)?;
+            let full_scans = query_plan
+                .lines()
+                .map(str::trim)
+                .filter(|step| {
+                    step.starts_with("SCAN ")
+                        && !step.contains("VIRTUAL TABLE")
+                        && *step != "SCAN CONSTANT ROW"
+                })
+                .map(str::to_string)
+                .collect();
+            Ok(RecallPlan {
+                params: slow_query::redact_params(&params),
+                sql,
+                query_plan,
+                full_scans,
+            })
+        })
+    }
+
+    /// Build the recall query for `filter`; every filter value is bound as a
+    /// parameter (helper method)
+    fn build_recall_query(&self, filter: &QueryFilter, keyword_match: KeywordMatch) -> SelectQuery {
+        let mut query = SelectQuery::new(MEMORY_COLUMNS, "memories");
 
         // Base conditions (always filter expired and deleted)
-        conditions.push("(expires_at IS NULL OR expires_at > datetime('now'))".to_string());
-        conditions.push("is_compressed = 0".to_string());
+        query.filter(Condition::sql(
+            "(expires_at IS NULL OR expires_at > datetime('now'))",
+        ));
+        query.filter(Condition::sql("is_compressed = 0"));
 
-        // User filter
-        if let Some(user_id) = &filter.user_id {
-            conditions.push(format!("user_id = ?{}", param_index));
-            params.push(Box::new(user_id.clone()));
-            param_index += 1;
+        let states = if filter.states.is_empty() {
+            &[MemoryState::Active][..]
+        } else {
+            &filter.states[..]
+        };
+        if states.len() < MemoryState::ALL.len() {
+            query.filter(Condition::any(
+                states
+                    .iter()
+                    .map(|state| Condition::compare(Column::State, Op::Eq, state.as_str()))
+                    .collect(),
+            ));
         }
 
-        // Session filter
+        if let Some(user_id) = &filter.user_id {
+            query.filter(Condition::compare(Column::UserId, Op::Eq, user_id.clone()));
+        }
         if let Some(session_id) = &filter.session_id {
-            conditions.push(format!("session_id = ?{}", param_index));
-            params.push(Box::new(session_id.clone()));
-            param_index += 1;
+            query.filter(Condition::compare(
+                Column::SessionId,
+                Op::Eq,
+                session_id.clone(),
+            ));
+        }
+
+        // Provenance filters
+        if !filter.source_types.is_empty() {
+            query.filter(Condition::any(
+                filter
+                    .source_types
+                    .iter()
+                    .map(|source| Condition::compare(Column::SourceType, Op::Eq, source.as_str()))
+                    .collect(),
+            ));
+        }
+        if let Some(source_ref) = &filter.source_ref {
+            query.filter(Condition::compare(
+                Column::SourceRef,
+                Op::Eq,
+                source_ref.clone(),
+            ));
+        }
+        if let Some(ingested_by) = &filter.ingested_by {
+            query.filter(Condition::compare(
+                Column::IngestedBy,
+                Op::Eq,
+                ingested_by.clone(),
+            ));
         }
 
         // Date range filters
         if let Some(date_from) = filter.date_from {
-            conditions.push(format!("created_at >= ?{}", param_index));
-            params.push(Box::new(date_from));
-            param_index += 1;
+            query.filter(Condition::compare(Column::CreatedAt, Op::Ge, date_from));
         }
-
         if let Some(date_to) = filter.date_to {
-            conditions.push(format!("created_at <= ?{}", param_index));
-            params.push(Box::new(date_to));
-            param_index += 1;
+            query.filter(Condition::compare(Column::CreatedAt, Op::Le, date_to));
         }
 
-        // Importance filter
         if let Some(min_importance) = filter.min_importance {
-            conditions.push(format!("importance >= ?{}", param_index));
-            params.push(Box::new(min_importance));
-            param_index += 1;
-        }
-
-        let base_table = if let Some(keywords) = &filter.keywords {
-            if !keywords.is_empty() {
-                // Use FTS5 for full-text search
-                let search_query = keywords.join(" OR ");
-                conditions.push(format!("memories.rowid IN (SELECT rowid FROM memories_fts WHERE memories_fts MATCH ?{})", param_index));
-                params.push(Box::new(search_query));
-                param_index += 1;
-                "memories"
-            } else {
-                "memories"
+            query.filter(Condition::compare(
+                Column::Importance,
+                Op::Ge,
+                min_importance,
+            ));
+        }
+        if let Some(min_confidence) = filter.min_confidence {
+            query.filter(Condition::compare(
+                Column::Confidence,
+                Op::Ge,
+                min_confidence,
+            ));
+        }
+        if let Some(max_confidence) = filter.max_confidence {
+            query.filter(Condition::compare(
+                Column::Confidence,
+                Op::Le,
+                max_confidence,
+            ));
+        }
+
+        // Expiring soon; the base condition already leaves out expired ones
+        if let Some(within) = filter.expiring_within {
+            query.filter(
+                Condition::sql("(expires_at IS NOT NULL AND expires_at <= ")
+                    .param(Utc::now() + within)
+                    .then(")"),
+            );
+        }
+
+        match keyword_match {
+            // Use FTS5 for full-text search
+            KeywordMatch::FullText => {
+                let search_query = filter.keywords.as_deref().and_then(schema::fts_match_query);
+                if let Some(search_query) = search_query {
+                    query.filter(
+                        Condition::sql(
+                            "memories.rowid IN (SELECT rowid FROM memories_fts WHERE memories_fts MATCH ",
+                        )
+                        .param(search_query)
+                        .then(")"),
+                    );
+                }
             }
-        } else {
-            "memories"
-        };
+            KeywordMatch::Trigram(trigrams) => {
+                query.filter(
+                    Condition::sql(
+                        "memories.rowid IN (SELECT rowid FROM memories_trigram WHERE memories_trigram MATCH ",
+                    )
+                    .param(schema::fts_match_query(trigrams).unwrap_or_default())
+                    .then(")"),
+                );
+            }
+            KeywordMatch::Like(trigrams) => {
+                let likes = trigrams
+                    .iter()
+                    .map(|trigram| {
+                        let escaped = trigram
+                            .replace('\\', "\\\\")
+                            .replace('%', "\\%")
+                            .replace('_', "\\_");
+                        Condition::sql("content LIKE ")
+                            .param(format!("%{}%", escaped))
+                            .then(" ESCAPE '\\'")
+                    })
+                    .collect();
+                query.filter(Condition::any(likes));
+            }
+        }
 
-        let where_clause = if conditions.is_empty() {
-            "".to_string()
-        } else {
-            format!("WHERE {}", conditions.join(" AND "))
-        };
+        // Exclusions
+        let excluded_words = schema::fts_match_query(&filter.exclude_keywords);
+        if let Some(excluded_words) = excluded_words {
+            query.filter(
+                Condition::sql(
+                    "memories.rowid NOT IN (SELECT rowid FROM memories_fts WHERE memories_fts MATCH ",
+                )
+                .param(excluded_words)
+                .then(")"),
+            );
+        }
+        if !filter.exclude_session_ids.is_empty() {
+            let mut excluded = Condition::sql("session_id NOT IN (");
+            for (i, session_id) in filter.exclude_session_ids.iter().enumerate() {
+                if i > 0 {
+                    excluded = excluded.then(", ");
+                }
+                excluded = excluded.param(session_id.clone());
+            }
+            query.filter(excluded.then(")"));
+        }
+        for tag in &filter.exclude_tags {
+            // Tags are comma-separated in metadata; untagged memories stay
+            query.filter(
+                Condition::sql("coalesce(instr(',' || replace(json_extract(metadata, ")
+                    .param(json_key_path(TAGS_METADATA_KEY))
+                    .then("), ' ', '') || ',', ',' || replace(")
+                    .param(tag.clone())
+                    .then(", ' ', '') || ','), 0) = 0"),
+            );
+        }
 
-        // Main query with pagination
-        let mut query = format!(
-            r#"
-            SELECT id, user_id, session_id, content, content_vector, metadata,
-                   created_at, updated_at, expires_at, importance, ttl_hours,
-                   is_compressed, compressed_from
-            FROM {} {}
-            ORDER BY created_at DESC, importance DESC
-            "#,
-            base_table, where_clause
-        );
+        if let Some(content_type) = filter.content_type {
+            query.filter(Condition::compare(
+                Column::ContentType,
+                Op::Eq,
+                content_type.as_str(),
+            ));
+        }
+        // content_bytes is kept on every write, so this never reads content.
+        // Lengths past i64::MAX are clamped to it, as no content is longer
+        let byte_count = |length: usize| i64::try_from(length).unwrap_or(i64::MAX);
+        if let Some(min_length) = filter.min_content_length {
+            query.filter(Condition::compare(
+                Column::ContentBytes,
+                Op::Ge,
+                byte_count(min_length),
+            ));
+        }
+        if let Some(max_length) = filter.max_content_length {
+            query.filter(Condition::compare(
+                Column::ContentBytes,
+                Op::Le,
+                byte_count(max_length),
+            ));
+        }
+
+        // Sorted so the same filter always builds the same SQL
+        let mut metadata: Vec<_> = filter.metadata.iter().collect();
+        metadata.sort();
+        for (key, value) in metadata {
+            query.filter(
+                Condition::sql("json_extract(metadata, ")
+                    .param(json_key_path(key))
+                    .then(") = ")
+                    .param(value.clone()),
+            );
+        }
+
+        let mut json_fields: Vec<_> = filter.json_fields.iter().collect();
+        json_fields.sort_by(|a, b| a.0.cmp(b.0));
+        if !json_fields.is_empty() {
+            query.filter(Condition::sql(
+                "content_type = 'json' AND json_valid(content)",
+            ));
+        }
+        for (path, value) in json_fields {
+            let path = if path.starts_with('$') {
+                path.clone()
+            } else {
+                json_key_path(path)
+            };
+            // json_extract yields SQL values for scalars and JSON text for
+            // arrays and objects; guarded in case SQLite checks it first
+            let field = |path: String| {
+                Condition::sql("(CASE WHEN json_valid(content) THEN json_extract(content, ")
+                    .param(path)
+                    .then(") END)")
+            };
+            let condition = match value {
+                serde_json::Value::Null => {
+                    Condition::sql("(CASE WHEN json_valid(content) THEN json_type(content, ")
+                        .param(path)
+                        .then(") END) = 'null'")
+                }
+                serde_json::Value::Array(_) | serde_json::Value::Object(_) => {
+                    Condition::sql("json(CASE WHEN json_valid(content) THEN json_extract(content, ")
+                        .param(path)
+                        .then(") END) = json(")
+                        .param(value.to_string())
+                        .then(")")
+                }
+                serde_json::Value::Bool(flag) => field(path).then(" = ").param(*flag as i64),
+                serde_json::Value::Number(number) => match number.as_i64() {
+                    Some(integer) => field(path).then(" = ").param(integer),
+                    None => field(path).then(" = ").param(number.as_f64()),
+                },
+                serde_json::Value::String(text) => field(path).then(" = ").param(text.clone()),
+            };
+            query.filter(condition);
+        }
 
-        // Add pagination
-        if let Some(limit) = filter.limit {
-            query.push_str(&format!(" LIMIT ?{}", param_index));
-            params.push(Box::new(limit as i64));
-            param_index += 1;
+        // Keyset cursor; matches nothing if the cursor memory is gone
+        if let Some(before_id) = &filter.before_id {
+            query.filter(
+                Condition::sql(
+                    "(created_at, importance, id) < \
+                     (SELECT created_at, importance, id FROM memories WHERE id = ",
+                )
+                .param(before_id.clone())
+                .then(")"),
+            );
         }
 
-        if let Some(offset) = filter.offset {
-            query.push_str(&format!(" OFFSET ?{}", param_index));
-            params.push(Box::new(offset as i64));
+        query
+            .order_by(Column::CreatedAt, Order::Desc)
+            .order_by(Column::Importance, Order::Desc)
+            .order_by(Column::Id, Order::Desc)
+            .paginate(filter.limit, filter.offset);
+        query
+    }
+
+    /// [`recall_memories`](Self::recall_memories) ranked by the filter's
+    /// `semantic_query`, which `model_name` embedded as `query_embedding`
+    /// (read operation)
+    ///
+    /// Every memory the rest of the filter matches is scored by its
+    /// `model_name` embedding's similarity to the query, weighted
+    /// `SEMANTIC_WEIGHT`, plus the full-text relevance of the query's words
+    /// relative to the best match. Memories less similar than
+    /// `min_similarity` are left out unless their text matches. The blend
+    /// is each memory's `match_score`.
+    #[cfg(feature = "vector-search")]
+    pub fn recall_semantic(
+        &self,
+        filter: &QueryFilter,
+        query_embedding: &[f32],
+        model_name: &str,
+        min_similarity: f32,
+    ) -> Result<PaginatedResponse<MemoryItem>> {
+        filter.validate().context("Filter validation failed")?;
+        let Some(semantic_query) = &filter.semantic_query else {
+            return self.recall_memories(filter);
+        };
+        if filter.before_id.is_some() {
+            return Err(anyhow::anyhow!(
+                "A semantic query is ranked by score, not time; page it with offset, not before_id"
+            ));
         }
 
-        // Count query
-        let count_query = format!("SELECT COUNT(*) FROM {} {}", base_table, where_clause);
+        let read_pool = self.get_read_pool();
+        let timeout = filter
+            .timeout_ms
+            .map(Duration::from_millis)
+            .or_else(|| read_pool.default_query_timeout());
+        read_pool.with_read_timeout(timeout, |conn| {
+            let candidates = self.build_recall_query(
+                &QueryFilter {
+                    limit: None,
+                    offset: None,
+                    ..filter.clone()
+                },
+                KeywordMatch::FullText,
+            );
+            let (candidates_sql, params) = candidates.to_sql();
+
+            let similarity = vector::embedding_similarities(
+                conn,
+                &candidates_sql,
+                &params,
+                query_embedding,
+                model_name,
+            )?;
+            let words: Vec<String> = semantic_query
+                .split_whitespace()
+                .map(str::to_string)
+                .collect();
+            let relevance = match schema::fts_match_query(&words) {
+                Some(match_query) => {
+                    let mut stmt = conn.prepare(&format!(
+                        "SELECT m.id, -bm25(memories_fts)
+                         FROM memories_fts
+                         INNER JOIN memories m ON m.rowid = memories_fts.rowid
+                         WHERE memories_fts MATCH ?{} AND m.id IN (SELECT id FROM ({}))",
+                        params.len() + 1,
+                        candidates_sql
+                    ))?;
+                    let mut params = params.clone();
+                    params.push(&match_query);
+                    let rows = stmt.query_map(&params[..], |row| Ok((row.get(0)?, row.get(1)?)))?;
+                    rows.collect::<rusqlite::Result<HashMap<String, f64>>>()?
+                }
+                None => HashMap::new(),
+            };
+            let best_relevance = relevance.values().copied().fold(0.0, f64::max);
+
+            let mut scored: Vec<(String, f32)> = similarity
+                .iter()
+                .filter(|(_, similarity)| **similarity >= min_similarity)
+                .map(|(id, _)| id)
+                .chain(relevance.keys())
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .map(|id| {
+                    let text = match relevance.get(id) {
+                        Some(relevance) if best_relevance > 0.0 => relevance / best_relevance,
+                        _ => 0.0,
+                    };
+                    let score = SEMANTIC_WEIGHT * similarity.get(id).copied().unwrap_or(0.0)
+                        + (1.0 - SEMANTIC_WEIGHT) * text as f32;
+                    (id.clone(), score)
+                })
+                .collect();
+            scored.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+
+            let offset = filter.offset.unwrap_or(0);
+            let per_page = filter.limit.unwrap_or(50);
+            let total_count = scored.len() as i64;
+            let total_pages = scored.len().div_ceil(per_page);
+            let page = offset / per_page;
+            let page_scores: Vec<(String, f32)> =
+                scored.into_iter().skip(offset).take(per_page).collect();
+
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} FROM memories WHERE id IN (SELECT value FROM json_each(?1))",
+                MEMORY_COLUMNS
+            ))?;
+            let ids =
+                serde_json::to_string(&page_scores.iter().map(|(id, _)| id).collect::<Vec<_>>())?;
+            let mut found: HashMap<String, MemoryItem> = stmt
+                .query_map([ids], row_to_memory)?
+                .map(|memory| memory.map(|memory| (memory.id.clone(), memory)))
+                .collect::<rusqlite::Result<_>>()?;
+            let mut memories: Vec<MemoryItem> = page_scores
+                .into_iter()
+                .filter_map(|(id, score)| {
+                    found.remove(&id).map(|memory| MemoryItem {
+                        match_score: Some(score),
+                        ..memory
+                    })
+                })
+                .collect();
+
+            if filter.explain {
+                let relevance = (!relevance.is_empty()).then_some(&relevance);
+                explain_ranking(
+                    &mut memories,
+                    offset,
+                    RankedBy::Semantic,
+                    relevance,
+                    Some(&similarity),
+                );
+            }
+            if filter.include_embeddings {
+                vector::load_embeddings(conn, &mut memories)?;
+            }
 
-        Ok((query, count_query, params))
+            Ok(PaginatedResponse {
+                data: memories,
+                total_count,
+                page,
+                per_page,
+                total_pages,
+                has_next: page < total_pages.saturating_sub(1),
+                has_prev: page > 0,
+            })
+        })
     }
 
     /// Get a memory by ID (read operation)
     pub fn get_memory(&self, id: &str) -> Result<Option<MemoryItem>> {
+        if let Some(memory) = self.hot_tier.get(id) {
+            return Ok(Some(memory));
+        }
+        let epoch = self.hot_tier.epoch();
         let read_pool = self.get_read_pool();
 
-        read_pool.with_read_connection(|conn| {
-            let mut stmt = conn.prepare(
+        let memory = read_pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
                 r#"
-                SELECT id, user_id, session_id, content, content_vector, metadata,
-                       created_at, updated_at, expires_at, importance, ttl_hours,
-                       is_compressed, compressed_from
+                SELECT {}
                 FROM memories
                 WHERE id = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))
                 "#,
-            )?;
+                MEMORY_COLUMNS
+            ))?;
 
             let memory = stmt
-                .query_row(rusqlite::params![id], |row| {
-                    Ok(MemoryItem {
-                        id: row.get("id")?,
-                        user_id: row.get("user_id")?,
-                        session_id: row.get("session_id")?,
-                        content: row.get("content")?,
-                        content_vector: row.get("content_vector")?,
-                        #[cfg(feature = "vector-search")]
-                        embedding: None,
-                        #[cfg(feature = "vector-search")]
-                        embedding_model: None,
-                        metadata: serde_json::from_str(&row.get::<_, String>("metadata")?)
-                            .unwrap_or_default(),
-                        created_at: row.get("created_at")?,
-                        updated_at: row.get("updated_at")?,
-                        expires_at: row.get("expires_at")?,
-                        importance: row.get("importance")?,
-                        ttl_hours: row.get("ttl_hours")?,
-                        is_compressed: row.get("is_compressed")?,
-                        compressed_from: serde_json::from_str(
-                            &row.get::<_, String>("compressed_from")?,
-                        )
-                        .unwrap_or_default(),
-                    })
-                })
+                .query_row(rusqlite::params![id], row_to_memory)
                 .optional()?;
 
             Ok(memory)
-        })
+        })?;
+        self.hot_tier.fill(&memory, epoch);
+        Ok(memory)
     }
 
-    /// Delete a memory by ID (write operation)
-    pub fn delete_memory(&self, id: &str) -> Result<bool> {
-        self.write_pool.with_write_transaction(|tx| {
-            // Delete from FTS table first
-            tx.execute(
-                "DELETE FROM memories_fts WHERE rowid = (SELECT rowid FROM memories WHERE id = ?1)",
-                rusqlite::params![id],
-            )?;
+    /// Fetch several memories by ID, in the order of `ids`
+    ///
+    /// IDs are looked up `GET_MEMORIES_CHUNK` at a time to stay under
+    /// SQLite's bound-parameter limit. Missing and expired memories are
+    /// left out; an ID listed twice is returned twice. IDs held by the hot
+    /// tier aren't looked up at all.
+    pub fn get_memories(&self, ids: &[String]) -> Result<Vec<MemoryItem>> {
+        let mut found: HashMap<String, MemoryItem> = HashMap::with_capacity(ids.len());
+        let mut missing = Vec::new();
+        for id in ids {
+            if found.contains_key(id) {
+                continue;
+            }
+            match self.hot_tier.get(id) {
+                Some(memory) => {
+                    found.insert(id.clone(), memory);
+                }
+                None => missing.push(id.clone()),
+            }
+        }
+        missing.sort();
+        missing.dedup();
+        if missing.is_empty() {
+            return Ok(ids.iter().filter_map(|id| found.get(id).cloned()).collect());
+        }
+
+        let epoch = self.hot_tier.epoch();
+        let read_pool = self.get_read_pool();
+        let mut read = Vec::with_capacity(missing.len());
+        read_pool.with_read_connection(|conn| {
+            for chunk in missing.chunks(GET_MEMORIES_CHUNK) {
+                let placeholders = vec!["?"; chunk.len()].join(", ");
+                let mut stmt = conn.prepare(&format!(
+                    r#"
+                    SELECT {}
+                    FROM memories
+                    WHERE id IN ({}) AND (expires_at IS NULL OR expires_at > datetime('now'))
+                    "#,
+                    MEMORY_COLUMNS, placeholders
+                ))?;
+                let rows = stmt.query_map(rusqlite::params_from_iter(chunk), row_to_memory)?;
+                for memory in rows {
+                    read.push(memory?);
+                }
+            }
+            Ok(())
+        })?;
+        self.hot_tier.fill(&read, epoch);
+        found.extend(read.into_iter().map(|memory| (memory.id.clone(), memory)));
+
+        Ok(ids.iter().filter_map(|id| found.get(id).cloned()).collect())
+    }
+
+    /// Read, modify and write back a memory in one transaction (write
+    /// operation)
+    ///
+    /// `apply` sees the stored row and may change its content, metadata,
+    /// importance and expiry; concurrent updates can't interleave with it.
+    /// Changed content is re-indexed for full-text search by the update
+    /// trigger. Every update bumps the memory's `version`; with
+    /// `expected_version` set, the update fails with [`VersionConflict`]
+    /// unless the stored version still matches. Returns the updated memory,
+    /// or `None` if there is no live memory `id`. `apply` may run more than
+    /// once if the write is retried.
+    pub fn update_memory_with<F>(
+        &self,
+        id: &str,
+        expected_version: Option<i64>,
+        mut apply: F,
+    ) -> Result<Option<MemoryItem>>
+    where
+        F: FnMut(&mut MemoryItem) -> Result<()>,
+    {
+        let updated = self.write_pool.with_write_transaction(|tx| {
+            let memory = tx
+                .query_row(
+                    &format!(
+                        r#"
+                        SELECT {}
+                        FROM memories
+                        WHERE id = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))
+                        "#,
+                        MEMORY_COLUMNS
+                    ),
+                    [id],
+                    row_to_memory,
+                )
+                .optional()?;
+            let Some(mut memory) = memory else {
+                return Ok(None);
+            };
+            if let Some(expected) = expected_version {
+                if memory.version != expected {
+                    return Err(VersionConflict {
+                        id: id.to_string(),
+                        expected,
+                        actual: memory.version,
+                    }
+                    .into());
+                }
+            }
+
+            apply(&mut memory)?;
+            memory.updated_at = Utc::now();
+            memory.version += 1;
+            memory.validate().context("Memory validation failed")?;
+
+            tx.execute(
+                r#"
+                UPDATE memories
+                SET content = ?2, metadata = ?3, importance = ?4, ttl_hours = ?5,
+                    expires_at = ?6, updated_at = ?7, version = ?8, content_bytes = ?9,
+                    confidence = ?10
+                WHERE id = ?1
+                "#,
+                rusqlite::params![
+                    id,
+                    memory.content,
+                    serde_json::to_string(&memory.metadata)?,
+                    memory.importance,
+                    memory.ttl_hours,
+                    memory.expires_at,
+                    memory.updated_at,
+                    memory.version,
+                    memory.content.len() as i64,
+                    memory.confidence,
+                ],
+            )?;
+            self.seal(tx, id)?;
+            audit::record(tx, AuditAction::MemoryUpdate, id)?;
+            Ok(Some(memory))
+        })?;
+        if let Some(memory) = &updated {
+            self.hot_tier.write(memory);
+        }
+        Ok(updated)
+    }
+
+    /// Full-text search over a user's memories, grouped by session (read
+    /// operation)
+    ///
+    /// Groups come most matches first, then most recently active. Each
+    /// holds the session's total number of matches and its newest
+    /// `per_session_limit` matching memories, all from one pass over the
+    /// full-text index.
+    pub fn search_grouped_by_session(
+        &self,
+        user_id: &str,
+        keywords: &[String],
+        per_session_limit: usize,
+    ) -> Result<Vec<models::SessionSearchGroup>> {
+        let Some(match_query) = schema::fts_match_query(keywords) else {
+            return Ok(Vec::new());
+        };
+        let read_pool = self.get_read_pool();
+
+        read_pool.with_read_connection(|conn| {
+            let hits = format!(
+                r#"
+                WITH hits AS (
+                    SELECT {},
+                           ROW_NUMBER() OVER (
+                               PARTITION BY session_id
+                               ORDER BY created_at DESC, importance DESC, id DESC
+                           ) AS rank_in_session,
+                           COUNT(*) OVER (PARTITION BY session_id) AS match_count
+                    FROM memories
+                    WHERE user_id = ?1 AND is_compressed = 0 AND state = 'active'
+                      AND (expires_at IS NULL OR expires_at > datetime('now'))
+                      AND rowid IN (SELECT rowid FROM memories_fts WHERE memories_fts MATCH ?2)
+                )
+                "#,
+                MEMORY_COLUMNS
+            );
+
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                {}
+                SELECT s.id, s.user_id, s.name, s.status, s.created_at, s.last_active,
+                       (SELECT COUNT(*) FROM memories m
+                        WHERE m.session_id = s.id
+                          AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+                       ) AS memory_count,
+                       h.match_count
+                FROM (SELECT DISTINCT session_id, match_count FROM hits) h
+                JOIN sessions s ON s.id = h.session_id
+                WHERE s.status != 'archived'
+                ORDER BY h.match_count DESC, s.last_active DESC, s.id
+                "#,
+                hits
+            ))?;
+            let rows = stmt.query_map(rusqlite::params![user_id, match_query], |row| {
+                Ok(models::SessionSearchGroup {
+                    session: models::Session {
+                        id: row.get("id")?,
+                        user_id: row.get("user_id")?,
+                        name: row.get("name")?,
+                        status: row_to_session_status(row)?,
+                        created_at: row.get("created_at")?,
+                        last_active: row.get("last_active")?,
+                        memory_count: row.get("memory_count")?,
+                        tags: Vec::new(),
+                        metadata: HashMap::new(),
+                    },
+                    match_count: row.get("match_count")?,
+                    memories: Vec::new(),
+                })
+            })?;
+            let mut groups = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                {}
+                SELECT {} FROM hits
+                WHERE rank_in_session <= ?3
+                ORDER BY rank_in_session
+                "#,
+                hits, MEMORY_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                rusqlite::params![user_id, match_query, per_session_limit as i64],
+                row_to_memory,
+            )?;
+            let positions: HashMap<String, usize> = groups
+                .iter()
+                .enumerate()
+                .map(|(index, group)| (group.session.id.clone(), index))
+                .collect();
+            for memory in rows {
+                let memory = memory?;
+                if let Some(&index) = positions.get(&memory.session_id) {
+                    groups[index].memories.push(memory);
+                }
+            }
+
+            Ok(groups)
+        })
+    }
+
+    /// Memories of a session in `seq` order, optionally limited to
+    /// `from..=to` (read operation)
+    pub fn get_session_memories_by_seq(
+        &self,
+        session_id: &str,
+        from: Option<i64>,
+        to: Option<i64>,
+    ) -> Result<Vec<MemoryItem>> {
+        let read_pool = self.get_read_pool();
+
+        read_pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT {}
+                FROM memories
+                WHERE session_id = ?1 AND seq BETWEEN ?2 AND ?3
+                  AND (expires_at IS NULL OR expires_at > datetime('now'))
+                ORDER BY seq ASC, created_at ASC
+                "#,
+                MEMORY_COLUMNS
+            ))?;
+            let rows = stmt.query_map(
+                rusqlite::params![session_id, from.unwrap_or(i64::MIN), to.unwrap_or(i64::MAX)],
+                row_to_memory,
+            )?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// Move a memory to position `to_seq` within its session (write
+    /// operation)
+    ///
+    /// The memories between its old and new position shift by one to make
+    /// room, so the session's order stays gap-free where it was before.
+    /// `to_seq` is clamped to the session's current range. Returns the
+    /// memory's new `seq`, or `None` if there is no memory `id`.
+    pub fn move_memory(&self, id: &str, to_seq: i64) -> Result<Option<i64>> {
+        let moved = self.write_pool.with_write_transaction(|tx| {
+            let current = tx
+                .query_row(
+                    "SELECT session_id, seq FROM memories WHERE id = ?1",
+                    [id],
+                    |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)),
+                )
+                .optional()?;
+            let Some((session_id, from_seq)) = current else {
+                return Ok(None);
+            };
+            let max_seq: i64 = tx.query_row(
+                "SELECT COALESCE(MAX(seq), 1) FROM memories WHERE session_id = ?1",
+                [&session_id],
+                |row| row.get(0),
+            )?;
+            let to_seq = to_seq.clamp(1, max_seq);
+
+            if to_seq < from_seq {
+                tx.execute(
+                    "UPDATE memories SET seq = seq + 1
+                     WHERE session_id = ?1 AND seq >= ?2 AND seq < ?3",
+                    rusqlite::params![session_id, to_seq, from_seq],
+                )?;
+            } else if to_seq > from_seq {
+                tx.execute(
+                    "UPDATE memories SET seq = seq - 1
+                     WHERE session_id = ?1 AND seq > ?2 AND seq <= ?3",
+                    rusqlite::params![session_id, from_seq, to_seq],
+                )?;
+            }
+            tx.execute(
+                "UPDATE memories SET seq = ?2 WHERE id = ?1",
+                rusqlite::params![id, to_seq],
+            )?;
+            audit::record(tx, AuditAction::MemoryMove, id)?;
+
+            tracing::debug!(memory_id = id, from = from_seq, to = to_seq, "Moved memory");
+            Ok(Some((session_id, to_seq)))
+        })?;
+        // Its neighbours moved too
+        Ok(moved.map(|(session_id, to_seq)| {
+            self.hot_tier.remove_session(&session_id);
+            to_seq
+        }))
+    }
 
-            // Delete from main table
+    /// Delete a memory by ID (write operation)
+    pub fn delete_memory(&self, id: &str) -> Result<bool> {
+        let deleted = self.write_pool.with_write_transaction(|tx| {
+            // memories_fts is updated by trigger
             let rows_affected =
                 tx.execute("DELETE FROM memories WHERE id = ?1", rusqlite::params![id])?;
+            if rows_affected > 0 {
+                audit::record(tx, AuditAction::MemoryDelete, id)?;
+            }
+
+            Ok(rows_affected > 0)
+        })?;
+        self.hot_tier.remove([id]);
+        Ok(deleted)
+    }
+
+    /// Delete several memories in one transaction (write operation)
+    ///
+    /// Returns the IDs that existed and were deleted, in the order of `ids`.
+    pub fn delete_memories(&self, ids: &[String]) -> Result<Vec<String>> {
+        Ok(self.delete_memories_measured(ids, false)?.0)
+    }
+
+    /// [`delete_memories`](Self::delete_memories), returning what the
+    /// deleted memories took up (write operation)
+    pub fn delete_memories_footprint(&self, ids: &[String]) -> Result<Footprint> {
+        Ok(self.delete_memories_measured(ids, true)?.1)
+    }
+
+    fn delete_memories_measured(
+        &self,
+        ids: &[String],
+        measure: bool,
+    ) -> Result<(Vec<String>, Footprint)> {
+        let (deleted, footprint) = self.write_pool.with_write_transaction(|tx| {
+            let mut stmt = tx.prepare("DELETE FROM memories WHERE id = ?1")?;
+            let mut deleted = Vec::new();
+            let mut footprint = Footprint::default();
+            for id in ids {
+                if measure {
+                    footprint += footprint_where(tx, "id = ?1", [id])?;
+                }
+                if stmt.execute([id])? > 0 {
+                    audit::record(tx, AuditAction::MemoryDelete, id)?;
+                    deleted.push(id.clone());
+                }
+            }
+            Ok((deleted, footprint))
+        })?;
+        self.hot_tier.remove(deleted.iter().map(String::as_str));
+        Ok((deleted, footprint))
+    }
+
+    /// What the memories `ids` take up (read operation)
+    pub fn memory_footprint(&self, ids: &[String]) -> Result<Footprint> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut footprint = Footprint::default();
+            for id in ids {
+                footprint += footprint_where(conn, "id = ?1", [id])?;
+            }
+            Ok(footprint)
+        })
+    }
+
+    /// Full-text index bytes per byte of memory content, for estimating
+    /// what removing content frees in the index (read operation)
+    pub fn text_index_ratio(&self) -> Result<f64> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let content_bytes: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(content_bytes), 0) FROM memory_usage",
+                [],
+                |row| row.get(0),
+            )?;
+            if content_bytes <= 0 {
+                return Ok(0.0);
+            }
+            let index_bytes: i64 = conn.query_row(
+                "SELECT COALESCE(SUM(pgsize), 0) FROM dbstat
+                 WHERE name IN ('memories_fts_data', 'memories_fts_idx',
+                                'memories_trigram_data', 'memories_trigram_idx')",
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(index_bytes as f64 / content_bytes as f64)
+        })
+    }
+
+    /// Bytes in database pages holding data, leaving out free pages the
+    /// file keeps until a vacuum (read operation)
+    pub fn used_bytes(&self) -> Result<u64> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let used: i64 = conn.query_row(
+                "SELECT (page_count - freelist_count) * page_size
+                 FROM pragma_page_count(), pragma_freelist_count(), pragma_page_size()",
+                [],
+                |row| row.get(0),
+            )?;
+            Ok(used.max(0) as u64)
+        })
+    }
+
+    /// Save `compressed` in place of `originals` (write operation)
+    ///
+    /// The originals move to `memory_archive`, where they stay until
+    /// `restore_archived_memories` brings them back or `compressed` is
+    /// deleted. Returns the compressed memory's ID.
+    pub fn save_compressed_memory(
+        &self,
+        compressed: &MemoryItem,
+        originals: &[MemoryItem],
+    ) -> Result<String> {
+        compressed.validate().context("Memory validation failed")?;
+
+        let id = if compressed.id.is_empty() {
+            self.generate_id(IdKind::Memory)
+        } else {
+            compressed.id.clone()
+        };
 
+        self.write_pool.with_write_transaction(|tx| {
+            insert_memory(tx, compressed, &id, Utc::now())?;
+            self.seal(tx, &id)?;
+
+            for original in originals {
+                tx.execute(
+                    "INSERT OR REPLACE INTO memory_archive (id, compressed_into, user_id, memory)
+                     VALUES (?1, ?2, ?3, ?4)",
+                    rusqlite::params![
+                        original.id,
+                        id,
+                        original.user_id,
+                        serde_json::to_string(original)?
+                    ],
+                )?;
+                tx.execute(
+                    "DELETE FROM memories WHERE id = ?1",
+                    rusqlite::params![original.id],
+                )?;
+            }
+
+            Ok(())
+        })?;
+        self.hot_tier.remove(
+            originals
+                .iter()
+                .map(|original| original.id.as_str())
+                .chain([id.as_str()]),
+        );
+
+        tracing::debug!(
+            memory_id = id.as_str(),
+            archived = originals.len(),
+            "Saved compressed memory"
+        );
+        Ok(id)
+    }
+
+    /// Memories archived when `compressed_id` was compressed, oldest first (read operation)
+    pub fn get_archived_memories(&self, compressed_id: &str) -> Result<Vec<MemoryItem>> {
+        let read_pool = self.get_read_pool();
+
+        read_pool.with_read_connection(|conn| {
+            let mut stmt =
+                conn.prepare("SELECT memory FROM memory_archive WHERE compressed_into = ?1")?;
+            let rows = stmt.query_map([compressed_id], |row| row.get::<_, String>(0))?;
+
+            let mut memories = Vec::new();
+            for row in rows {
+                memories.push(serde_json::from_str::<MemoryItem>(&row?)?);
+            }
+            memories.sort_by_key(|m| m.created_at);
+            Ok(memories)
+        })
+    }
+
+    /// Put the archived originals of `compressed_id` back (write operation)
+    ///
+    /// Originals keep their IDs, content and creation times; their expiry
+    /// restarts from their TTL as if newly saved. The compressed memory is
+    /// kept but marked with a `superseded_at` metadata entry and expired, so
+    /// it drops out of recall and the next cleanup deletes it. Returns the
+    /// restored IDs, oldest first; empty if nothing was archived.
+    pub fn restore_archived_memories(&self, compressed_id: &str) -> Result<Vec<String>> {
+        let restored = self.write_pool.with_write_transaction(|tx| {
+            let mut originals = Vec::new();
+            {
+                let mut stmt =
+                    tx.prepare("SELECT memory FROM memory_archive WHERE compressed_into = ?1")?;
+                let rows = stmt.query_map([compressed_id], |row| row.get::<_, String>(0))?;
+                for row in rows {
+                    originals.push(serde_json::from_str::<MemoryItem>(&row?)?);
+                }
+            }
+            if originals.is_empty() {
+                return Ok(Vec::new());
+            }
+            originals.sort_by_key(|m| m.created_at);
+
+            let now = Utc::now();
+            for original in &originals {
+                insert_memory(tx, original, &original.id, now)?;
+                self.seal(tx, &original.id)?;
+            }
+            tx.execute(
+                "DELETE FROM memory_archive WHERE compressed_into = ?1",
+                [compressed_id],
+            )?;
+            tx.execute(
+                r#"
+                UPDATE memories
+                SET metadata = json_set(COALESCE(metadata, '{}'), '$.superseded_at', ?2),
+                    -- SQL's own clock, so the row counts as expired right away
+                    expires_at = datetime('now'),
+                    updated_at = ?3,
+                    version = version + 1
+                WHERE id = ?1
+                "#,
+                rusqlite::params![compressed_id, now.to_rfc3339(), now],
+            )?;
+            self.seal(tx, compressed_id)?;
+
+            Ok(originals.into_iter().map(|m| m.id).collect::<Vec<_>>())
+        })?;
+        self.hot_tier
+            .remove(restored.iter().map(String::as_str).chain([compressed_id]));
+
+        tracing::debug!(
+            memory_id = compressed_id,
+            restored = restored.len(),
+            "Restored archived memories"
+        );
+        Ok(restored)
+    }
+
+    /// Expired memories of `user_id` still inside the grace period, most
+    /// recently expired first (read operation)
+    pub fn get_expired_memories(&self, user_id: &str) -> Result<Vec<MemoryItem>> {
+        let read_pool = self.get_read_pool();
+
+        read_pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT {}
+                FROM memories
+                WHERE user_id = ?1 AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+                  AND json_extract(metadata, '$.superseded_at') IS NULL
+                ORDER BY expires_at DESC
+                "#,
+                MEMORY_COLUMNS
+            ))?;
+
+            let memories = stmt
+                .query_map([user_id], row_to_memory)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(memories)
+        })
+    }
+
+    /// Bring back an expired memory that cleanup hasn't deleted yet (write operation)
+    ///
+    /// `ttl_hours` replaces the memory's TTL; `None` restarts its old one.
+    /// Memories superseded by `restore_archived_memories` stay expired.
+    /// Returns false if `id` isn't an expired memory.
+    pub fn restore_expired_memory(&self, id: &str, ttl_hours: Option<u32>) -> Result<bool> {
+        let restored = self.write_pool.with_write_transaction(|tx| {
+            let old_ttl = tx
+                .query_row(
+                    "SELECT ttl_hours FROM memories
+                     WHERE id = ?1 AND expires_at IS NOT NULL AND expires_at <= datetime('now')
+                       AND json_extract(metadata, '$.superseded_at') IS NULL",
+                    [id],
+                    |row| row.get::<_, Option<u32>>(0),
+                )
+                .optional()?;
+            let Some(old_ttl) = old_ttl else {
+                return Ok(false);
+            };
+
+            let now = Utc::now();
+            let ttl_hours = ttl_hours.or(old_ttl);
+            let expires_at = ttl_hours.map(|hours| now + chrono::Duration::hours(hours as i64));
+            tx.execute(
+                "UPDATE memories
+                 SET ttl_hours = ?2, expires_at = ?3, updated_at = ?4, version = version + 1
+                 WHERE id = ?1",
+                rusqlite::params![id, ttl_hours, expires_at, now],
+            )?;
+            self.seal(tx, id)?;
+            audit::record(tx, AuditAction::MemoryRestore, id)?;
+            Ok(true)
+        })?;
+
+        if restored {
+            self.hot_tier.remove([id]);
+            tracing::debug!(memory_id = id, "Restored expired memory");
+        }
+        Ok(restored)
+    }
+
+    /// Move a live memory to `state` (write operation)
+    ///
+    /// Fails with [`InvalidStateTransition`] if the memory's current state
+    /// can't become `state` (see [`MemoryState::can_become`]); returns the
+    /// state it left, or `None` if there is no live memory `id`.
+    pub fn set_memory_state(&self, id: &str, state: MemoryState) -> Result<Option<MemoryState>> {
+        let previous = self.write_pool.with_write_transaction(|tx| {
+            let previous = tx
+                .query_row(
+                    "SELECT state FROM memories
+                     WHERE id = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))",
+                    [id],
+                    |row| row.get::<_, String>(0),
+                )
+                .optional()?;
+            let Some(previous) = previous else {
+                return Ok(None);
+            };
+            let previous: MemoryState = previous.parse().unwrap_or_default();
+            if !previous.can_become(state) {
+                return Err(InvalidStateTransition {
+                    id: id.to_string(),
+                    from: previous,
+                    to: state,
+                }
+                .into());
+            }
+            if previous == state {
+                return Ok(Some(previous));
+            }
+
+            tx.execute(
+                "UPDATE memories SET state = ?2, updated_at = ?3, version = version + 1
+                 WHERE id = ?1",
+                rusqlite::params![id, state.as_str(), Utc::now()],
+            )?;
+            self.seal(tx, id)?;
+            audit::record(tx, AuditAction::MemoryState, id)?;
+            Ok(Some(previous))
+        })?;
+
+        if let Some(previous) = previous {
+            self.hot_tier.remove([id]);
+            tracing::debug!(
+                memory_id = id,
+                from = previous.as_str(),
+                to = state.as_str(),
+                "Set memory state"
+            );
+        }
+        Ok(previous)
+    }
+
+    /// Delete memories that expired more than `expiry_grace_hours` ago (write operation)
+    pub fn cleanup_expired(&self) -> Result<usize> {
+        Ok(self.cleanup_expired_footprint()?.memories)
+    }
+
+    /// [`cleanup_expired`](Self::cleanup_expired), returning what the
+    /// deleted memories took up (write operation)
+    pub fn cleanup_expired_footprint(&self) -> Result<Footprint> {
+        const EXPIRED: &str = "expires_at IS NOT NULL AND expires_at <= datetime('now', ?1)";
+        let cutoff = format!("-{} hours", self.config.expiry_grace_hours);
+        self.write_pool.with_write_transaction(|tx| {
+            let footprint = footprint_where(tx, EXPIRED, rusqlite::params![cutoff])?;
+            let rows_affected = tx.execute(
+                &format!("DELETE FROM memories WHERE {}", EXPIRED),
+                [&cutoff],
+            )?;
+
+            tracing::info!(count = rows_affected, "Cleaned up expired memories");
+            Ok(footprint)
+        })
+    }
+
+    /// Delete memories in `category` created before `cutoff`, returning
+    /// what they took up (write operation)
+    pub fn expire_category(&self, category: &str, cutoff: DateTime<Utc>) -> Result<Footprint> {
+        const IN_CATEGORY: &str = "json_extract(metadata, '$.category') = ?1 AND created_at < ?2";
+        let expired = self.write_pool.with_write_transaction(|tx| {
+            let footprint = footprint_where(tx, IN_CATEGORY, rusqlite::params![category, cutoff])?;
+            tx.execute(
+                &format!("DELETE FROM memories WHERE {}", IN_CATEGORY),
+                rusqlite::params![category, cutoff],
+            )?;
+            Ok(footprint)
+        })?;
+        // Any cached memory may have been in the category
+        if expired.memories > 0 {
+            self.hot_tier.clear();
+        }
+        Ok(expired)
+    }
+
+    /// All retention rules, by category (read operation)
+    pub fn get_retention_rules(&self) -> Result<Vec<RetentionRule>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn
+                .prepare("SELECT category, max_age_hours FROM retention_rules ORDER BY category")?;
+            let rules = stmt
+                .query_map([], |row| {
+                    Ok(RetentionRule {
+                        category: row.get("category")?,
+                        max_age_hours: row.get("max_age_hours")?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(rules)
+        })
+    }
+
+    /// Add a retention rule, replacing any existing rule for its category
+    /// (write operation)
+    pub fn set_retention_rule(&self, rule: &RetentionRule) -> Result<()> {
+        rule.validate().context("Invalid retention rule")?;
+
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute(
+                r#"
+                INSERT INTO retention_rules (category, max_age_hours, updated_at)
+                VALUES (?1, ?2, datetime('now'))
+                ON CONFLICT (category) DO UPDATE SET
+                    max_age_hours = excluded.max_age_hours,
+                    updated_at = excluded.updated_at
+                "#,
+                rusqlite::params![rule.category, rule.max_age_hours],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Remove the retention rule for `category` (write operation)
+    pub fn delete_retention_rule(&self, category: &str) -> Result<bool> {
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected = tx.execute(
+                "DELETE FROM retention_rules WHERE category = ?1",
+                rusqlite::params![category],
+            )?;
             Ok(rows_affected > 0)
         })
     }
 
-    /// Cleanup expired memories (write operation)
-    pub fn cleanup_expired(&self) -> Result<usize> {
-        self.write_pool.with_write_transaction(|tx| {
-            // First, delete from FTS table
-            tx.execute(
-                "DELETE FROM memories_fts WHERE rowid IN (SELECT rowid FROM memories WHERE expires_at IS NOT NULL AND expires_at <= datetime('now'))",
+    /// All memory templates, by ID (read operation)
+    pub fn list_templates(&self) -> Result<Vec<MemoryTemplate>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id, content, metadata, importance, ttl_hours FROM templates ORDER BY id",
+            )?;
+            let templates = stmt
+                .query_map([], row_to_template)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(templates)
+        })
+    }
+
+    /// Get a memory template by ID (read operation)
+    pub fn get_template(&self, id: &str) -> Result<Option<MemoryTemplate>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT id, content, metadata, importance, ttl_hours FROM templates WHERE id = ?1",
+                    [id],
+                    row_to_template,
+                )
+                .optional()?)
+        })
+    }
+
+    /// Add a memory template, replacing any existing one with its ID
+    /// (write operation)
+    pub fn set_template(&self, template: &MemoryTemplate) -> Result<()> {
+        template.validate().context("Invalid memory template")?;
+
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute(
+                r#"
+                INSERT INTO templates (id, content, metadata, importance, ttl_hours, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, datetime('now'))
+                ON CONFLICT (id) DO UPDATE SET
+                    content = excluded.content,
+                    metadata = excluded.metadata,
+                    importance = excluded.importance,
+                    ttl_hours = excluded.ttl_hours,
+                    updated_at = excluded.updated_at
+                "#,
+                rusqlite::params![
+                    template.id,
+                    template.content,
+                    serde_json::to_string(&template.metadata)?,
+                    template.importance,
+                    template.ttl_hours,
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Remove a memory template (write operation)
+    pub fn delete_template(&self, id: &str) -> Result<bool> {
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected = tx.execute("DELETE FROM templates WHERE id = ?1", [id])?;
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Synonym pairs, by namespace, of one namespace or all of them (read
+    /// operation)
+    pub fn list_synonyms(&self, namespace: Option<&str>) -> Result<Vec<Synonym>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT namespace, term, synonym, created_at FROM synonyms
+                 WHERE ?1 IS NULL OR namespace = ?1
+                 ORDER BY namespace, term, synonym",
+            )?;
+            let synonyms = stmt
+                .query_map([namespace], |row| {
+                    Ok(Synonym {
+                        namespace: row.get("namespace")?,
+                        term: row.get("term")?,
+                        synonym: row.get("synonym")?,
+                        created_at: row.get("created_at")?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(synonyms)
+        })
+    }
+
+    /// Words paired with any of `words` in `namespace`, compared without
+    /// case (read operation)
+    pub fn synonyms_of(&self, namespace: &str, words: &[String]) -> Result<Vec<String>> {
+        if words.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT synonym FROM synonyms WHERE namespace = ?1 AND term = ?2
+                 UNION
+                 SELECT term FROM synonyms WHERE namespace = ?1 AND synonym = ?2",
+            )?;
+            let mut synonyms = Vec::new();
+            for word in words {
+                let found = stmt
+                    .query_map(rusqlite::params![namespace, word], |row| row.get(0))?
+                    .collect::<rusqlite::Result<Vec<String>>>()?;
+                synonyms.extend(found);
+            }
+            Ok(synonyms)
+        })
+    }
+
+    /// Pair `term` with `synonym` in `namespace`; false if they already
+    /// were, in either order (write operation)
+    pub fn add_synonym(&self, namespace: &str, term: &str, synonym: &str) -> Result<bool> {
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected = tx.execute(
+                "INSERT INTO synonyms (namespace, term, synonym)
+                 SELECT ?1, ?2, ?3 WHERE NOT EXISTS (
+                     SELECT 1 FROM synonyms WHERE namespace = ?1 AND term = ?3 AND synonym = ?2
+                 )
+                 ON CONFLICT DO NOTHING",
+                rusqlite::params![namespace, term, synonym],
+            )?;
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Unpair `term` and `synonym` in `namespace`, in either order (write
+    /// operation)
+    pub fn remove_synonym(&self, namespace: &str, term: &str, synonym: &str) -> Result<bool> {
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected = tx.execute(
+                "DELETE FROM synonyms WHERE namespace = ?1
+                 AND ((term = ?2 AND synonym = ?3) OR (term = ?3 AND synonym = ?2))",
+                rusqlite::params![namespace, term, synonym],
+            )?;
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Link memories `a` and `b` with `link_type`, replacing any other link
+    /// between them; false if either is missing or they were already linked
+    /// that way (write operation)
+    pub fn link_memories(&self, a: &str, b: &str, link_type: LinkType) -> Result<bool> {
+        let (source, target) = if a <= b { (a, b) } else { (b, a) };
+        let linked = self.write_pool.with_write_transaction(|tx| {
+            let users = tx
+                .prepare("SELECT user_id FROM memories WHERE id IN (?1, ?2)")?
+                .query_map([source, target], |row| row.get::<_, String>(0))?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            if source == target || users.len() != 2 {
+                return Ok(false);
+            }
+            if users[0] != users[1] {
+                return Err(anyhow::anyhow!(
+                    "Memories {} and {} belong to different users",
+                    source,
+                    target
+                ));
+            }
+
+            tx.execute(
+                "DELETE FROM memory_links WHERE source_id = ?1 AND target_id = ?2 AND link_type != ?3",
+                rusqlite::params![source, target, link_type.as_str()],
+            )?;
+            let rows_affected = tx.execute(
+                "INSERT INTO memory_links (source_id, target_id, link_type) VALUES (?1, ?2, ?3)
+                 ON CONFLICT DO NOTHING",
+                rusqlite::params![source, target, link_type.as_str()],
+            )?;
+            if rows_affected > 0 {
+                audit::record(tx, AuditAction::MemoryLink, source)?;
+            }
+            Ok(rows_affected > 0)
+        })?;
+
+        if linked {
+            tracing::debug!(
+                source_id = source,
+                target_id = target,
+                link_type = link_type.as_str(),
+                "Linked memories"
+            );
+        }
+        Ok(linked)
+    }
+
+    /// Remove the `link_type` link between memories `a` and `b` (write
+    /// operation)
+    pub fn unlink_memories(&self, a: &str, b: &str, link_type: LinkType) -> Result<bool> {
+        let (source, target) = if a <= b { (a, b) } else { (b, a) };
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected = tx.execute(
+                "DELETE FROM memory_links WHERE source_id = ?1 AND target_id = ?2 AND link_type = ?3",
+                rusqlite::params![source, target, link_type.as_str()],
+            )?;
+            if rows_affected > 0 {
+                audit::record(tx, AuditAction::MemoryLink, source)?;
+            }
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// Links of memory `id`, from either end (read operation)
+    pub fn get_memory_links(&self, id: &str) -> Result<Vec<MemoryLink>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT source_id, target_id, link_type, created_at FROM memory_links
+                 WHERE source_id = ?1 OR target_id = ?1
+                 ORDER BY created_at, source_id, target_id",
+            )?;
+            let links = stmt
+                .query_map([id], |row| {
+                    let link_type: String = row.get("link_type")?;
+                    Ok(MemoryLink {
+                        source_id: row.get("source_id")?,
+                        target_id: row.get("target_id")?,
+                        link_type: link_type.parse().unwrap_or(LinkType::Compatible),
+                        created_at: row.get("created_at")?,
+                    })
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(links)
+        })
+    }
+
+    /// Registered JSON Schemas, by namespace (read operation)
+    pub fn list_json_schemas(&self) -> Result<Vec<NamespaceSchema>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT namespace, schema, updated_at FROM json_schemas ORDER BY namespace",
+            )?;
+            let schemas = stmt
+                .query_map([], row_to_namespace_schema)?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            Ok(schemas)
+        })
+    }
+
+    /// The JSON Schema registered for `namespace` (read operation)
+    pub fn get_json_schema(&self, namespace: &str) -> Result<Option<NamespaceSchema>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT namespace, schema, updated_at FROM json_schemas WHERE namespace = ?1",
+                    [namespace],
+                    row_to_namespace_schema,
+                )
+                .optional()?)
+        })
+    }
+
+    /// Register a JSON Schema for `namespace`, replacing any existing one
+    /// (write operation)
+    pub fn set_json_schema(&self, namespace: &str, schema: &serde_json::Value) -> Result<()> {
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute(
+                r#"
+                INSERT INTO json_schemas (namespace, schema, updated_at)
+                VALUES (?1, ?2, datetime('now'))
+                ON CONFLICT (namespace) DO UPDATE SET
+                    schema = excluded.schema,
+                    updated_at = excluded.updated_at
+                "#,
+                rusqlite::params![namespace, schema.to_string()],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// Remove the JSON Schema of `namespace` (write operation)
+    pub fn delete_json_schema(&self, namespace: &str) -> Result<bool> {
+        self.write_pool.with_write_transaction(|tx| {
+            let rows_affected =
+                tx.execute("DELETE FROM json_schemas WHERE namespace = ?1", [namespace])?;
+            Ok(rows_affected > 0)
+        })
+    }
+
+    /// When `user_id` saves memories: weekday/hour counts, average
+    /// importance per session, the longest gap and day streaks (read
+    /// operation)
+    pub fn get_user_activity(&self, user_id: &str) -> Result<UserActivity> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut activity = UserActivity::default();
+
+            // strftime('%w') counts from Sunday
+            let mut stmt = conn.prepare(
+                "SELECT (CAST(strftime('%w', created_at) AS INTEGER) + 6) % 7 AS weekday,
+                        CAST(strftime('%H', created_at) AS INTEGER) AS hour,
+                        COUNT(*)
+                 FROM memories WHERE user_id = ?1
+                 GROUP BY weekday, hour",
+            )?;
+            let cells = stmt.query_map([user_id], |row| {
+                Ok((
+                    row.get::<_, usize>(0)?,
+                    row.get::<_, usize>(1)?,
+                    row.get::<_, usize>(2)?,
+                ))
+            })?;
+            for cell in cells {
+                let (weekday, hour, count) = cell?;
+                activity.heatmap[weekday][hour] = count;
+                activity.by_weekday[weekday] += count;
+                activity.by_hour[hour] += count;
+            }
+
+            let mut stmt = conn.prepare(
+                "SELECT session_id, AVG(importance) FROM memories WHERE user_id = ?1
+                 GROUP BY session_id",
+            )?;
+            activity.avg_importance_by_session = stmt
+                .query_map([user_id], |row| {
+                    Ok((row.get(0)?, row.get::<_, f64>(1)? as f32))
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+
+            activity.longest_gap = conn
+                .query_row(
+                    "SELECT previous, created_at FROM (
+                         SELECT created_at, LAG(created_at) OVER (ORDER BY created_at) AS previous
+                         FROM memories WHERE user_id = ?1
+                     )
+                     WHERE previous IS NOT NULL
+                     ORDER BY julianday(created_at) - julianday(previous) DESC
+                     LIMIT 1",
+                    [user_id],
+                    |row| {
+                        Ok(ActivityGap {
+                            from: row.get(0)?,
+                            to: row.get(1)?,
+                        })
+                    },
+                )
+                .optional()?;
+
+            // Days minus their rank are equal within a run of consecutive days
+            let mut stmt = conn.prepare(
+                "WITH days AS (
+                     SELECT DISTINCT date(created_at) AS day FROM memories WHERE user_id = ?1
+                 ),
+                 runs AS (
+                     SELECT day, julianday(day) - ROW_NUMBER() OVER (ORDER BY day) AS run
+                     FROM days
+                 )
+                 SELECT MAX(day), COUNT(*) FROM runs GROUP BY run ORDER BY MAX(day)",
+            )?;
+            let streaks = stmt
+                .query_map([user_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            activity.longest_streak_days = streaks.iter().map(|(_, days)| *days).max().unwrap_or(0);
+            let yesterday = (Utc::now() - chrono::Duration::days(1))
+                .format("%Y-%m-%d")
+                .to_string();
+            activity.current_streak_days = streaks
+                .last()
+                .filter(|(last_day, _)| *last_day >= yesterday)
+                .map_or(0, |(_, days)| *days);
+
+            Ok(activity)
+        })
+    }
+
+    /// Storage, growth, compression, decay and topic figures for `user_id`
+    /// (read operation)
+    pub fn get_user_analytics(&self, user_id: &str) -> Result<UserAnalytics> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut analytics = UserAnalytics {
+                user_id: user_id.to_string(),
+                ..Default::default()
+            };
+
+            let window = format!("-{} days", GROWTH_WINDOW_DAYS);
+            let (memory_count, storage_bytes, recent_count, recent_bytes) = conn.query_row(
+                "SELECT COUNT(*),
+                        COALESCE(SUM(length(CAST(content AS BLOB)) + length(CAST(metadata AS BLOB))), 0),
+                        COUNT(*) FILTER (WHERE created_at > datetime('now', ?2)),
+                        COALESCE(SUM(length(CAST(content AS BLOB)) + length(CAST(metadata AS BLOB)))
+                            FILTER (WHERE created_at > datetime('now', ?2)), 0)
+                 FROM memories WHERE user_id = ?1",
+                rusqlite::params![user_id, window],
+                |row| {
+                    Ok((
+                        row.get::<_, usize>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, usize>(2)?,
+                        row.get::<_, i64>(3)?,
+                    ))
+                },
+            )?;
+            analytics.memory_count = memory_count;
+            analytics.storage_bytes = storage_bytes as u64;
+            analytics.memories_per_day = recent_count as f64 / GROWTH_WINDOW_DAYS as f64;
+            analytics.growth_bytes_per_day = recent_bytes as f64 / GROWTH_WINDOW_DAYS as f64;
+
+            analytics.session_count = conn.query_row(
+                "SELECT COUNT(*) FROM sessions WHERE user_id = ?1",
+                [user_id],
+                |row| row.get(0),
+            )?;
+
+            let (compressed, originals) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(json_array_length(compressed_from)), 0)
+                 FROM memories WHERE user_id = ?1 AND is_compressed = 1",
+                [user_id],
+                |row| Ok((row.get::<_, usize>(0)?, row.get::<_, usize>(1)?)),
+            )?;
+            analytics.compressed_memories = compressed;
+            if compressed > 0 {
+                analytics.compression_ratio = Some(originals as f32 / compressed as f32);
+            }
+
+            analytics.decay_removal_rate = conn
+                .query_row(
+                    "SELECT CAST(SUM(total_memories_before - total_memories_after) AS REAL)
+                            / SUM(total_memories_before)
+                     FROM decay_runs WHERE status = 'completed' AND total_memories_before > 0",
+                    [],
+                    |row| row.get::<_, Option<f64>>(0),
+                )?
+                .map(|rate| rate as f32);
+
+            let mut stmt = conn.prepare(
+                "SELECT m.session_id, s.name, COUNT(*) AS memory_count,
+                        SUM(length(CAST(m.content AS BLOB)) + length(CAST(m.metadata AS BLOB))) AS bytes
+                 FROM memories m LEFT JOIN sessions s ON s.id = m.session_id
+                 WHERE m.user_id = ?1
+                 GROUP BY m.session_id
+                 ORDER BY memory_count DESC, bytes DESC, m.session_id
+                 LIMIT ?2",
+            )?;
+            analytics.top_sessions = stmt
+                .query_map(rusqlite::params![user_id, ANALYTICS_TOP_SESSIONS], |row| {
+                    Ok(SessionUsage {
+                        session_id: row.get(0)?,
+                        name: row.get(1)?,
+                        memory_count: row.get(2)?,
+                        storage_bytes: row.get::<_, i64>(3)? as u64,
+                    })
+                })?
+                .collect::<rusqlite::Result<_>>()?;
+
+            let mut stmt = conn.prepare(
+                "SELECT topic, COUNT(*) AS memory_count FROM (
+                     SELECT CAST(json_extract(metadata, '$.category') AS TEXT) AS topic
+                     FROM memories WHERE user_id = ?1
+                 )
+                 WHERE topic IS NOT NULL
+                 GROUP BY topic
+                 ORDER BY memory_count DESC, topic",
+            )?;
+            let topics = stmt
+                .query_map([user_id], |row| {
+                    Ok((row.get::<_, String>(0)?, row.get::<_, usize>(1)?))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
+            let categorized: usize = topics.iter().map(|(_, count)| count).sum();
+            analytics.uncategorized_memories = memory_count - categorized;
+            analytics.topic_distribution = topics
+                .into_iter()
+                .take(ANALYTICS_TOP_TOPICS)
+                .map(|(topic, memory_count)| TopicShare {
+                    topic,
+                    memory_count,
+                    share: memory_count as f32 / analytics.memory_count as f32,
+                })
+                .collect();
+
+            Ok(analytics)
+        })
+    }
+
+    /// Keep the outcome of a decay run for later analytics (write operation)
+    pub fn record_decay_run(&self, stats: &DecayStats) -> Result<()> {
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute(
+                r#"
+                INSERT INTO decay_runs (
+                    id, started_at, completed_at, memories_expired, memories_compressed,
+                    sessions_summarized, sessions_archived, total_memories_before,
+                    total_memories_after, storage_saved_bytes, physical_bytes_saved,
+                    error_message, status
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+                "#,
+                rusqlite::params![
+                    stats.run_id,
+                    stats.started_at,
+                    stats.completed_at,
+                    stats.memories_expired,
+                    stats.memories_compressed,
+                    stats.sessions_summarized,
+                    stats.sessions_archived,
+                    stats.total_memories_before,
+                    stats.total_memories_after,
+                    stats.storage_saved_bytes,
+                    stats.physical_bytes_saved,
+                    stats.error_message,
+                    stats.status.to_string(),
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    /// The most recently started decay run, from any instance sharing this
+    /// file (read operation)
+    pub fn last_decay_run(&self) -> Result<Option<DecayStats>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let row = conn
+                .query_row(
+                    "SELECT id, started_at, completed_at, memories_expired, memories_compressed,
+                            sessions_summarized, sessions_archived, total_memories_before,
+                            total_memories_after, storage_saved_bytes, status, error_message,
+                            physical_bytes_saved
+                     FROM decay_runs ORDER BY started_at DESC LIMIT 1",
+                    [],
+                    |row| {
+                        Ok((
+                            DecayStats {
+                                run_id: row.get(0)?,
+                                started_at: row.get(1)?,
+                                completed_at: row.get(2)?,
+                                memories_expired: row.get(3)?,
+                                memories_compressed: row.get(4)?,
+                                sessions_summarized: row.get(5)?,
+                                sessions_archived: row.get(6)?,
+                                total_memories_before: row.get(7)?,
+                                total_memories_after: row.get(8)?,
+                                storage_saved_bytes: row.get(9)?,
+                                physical_bytes_saved: row
+                                    .get::<_, Option<i64>>(12)?
+                                    .unwrap_or_default(),
+                                status: DecayStatus::Running,
+                                error_message: row.get(11)?,
+                            },
+                            row.get::<_, String>(10)?,
+                        ))
+                    },
+                )
+                .optional()?;
+
+            row.map(|(mut stats, status)| {
+                stats.status = status.parse().map_err(|e: &str| anyhow::anyhow!(e))?;
+                Ok(stats)
+            })
+            .transpose()
+        })
+    }
+
+    /// Memory counts and growth for health reporting (read operation)
+    pub fn get_memory_usage(&self) -> Result<crate::core::MemoryUsage> {
+        let window = format!("-{} days", GROWTH_WINDOW_DAYS);
+        let (total, expired, recent) = self.get_read_pool().with_read_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT COUNT(*),
+                        COUNT(*) FILTER (WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')),
+                        COUNT(*) FILTER (WHERE created_at > datetime('now', ?1))
+                 FROM memories",
+                [&window],
+                |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, i64>(1)?,
+                        row.get::<_, i64>(2)?,
+                    ))
+                },
+            )?)
+        })?;
+
+        Ok(crate::core::MemoryUsage {
+            total_memories: total,
+            active_memories: total - expired,
+            expired_memories: expired,
+            database_size_bytes: self.database_size_bytes(),
+            memory_growth_rate: recent as f32 / GROWTH_WINDOW_DAYS as f32,
+        })
+    }
+
+    /// Get database statistics (read operation)
+    pub fn get_stats(&self) -> Result<serde_json::Value> {
+        let read_pool = self.get_read_pool();
+
+        read_pool.with_read_connection(|conn| {
+            // Total memories
+            let total_memories: i64 = conn.query_row(
+                "SELECT COUNT(*) FROM memories WHERE (expires_at IS NULL OR expires_at > datetime('now'))",
+                [],
+                |row| row.get(0)
+            )?;
+
+            // Memory by user
+            let mut stmt = conn.prepare("SELECT user_id, COUNT(*) FROM memories WHERE (expires_at IS NULL OR expires_at > datetime('now')) GROUP BY user_id")?;
+            let user_counts = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?;
+
+            let mut user_map = serde_json::Map::new();
+            for result in user_counts {
+                let (user_id, count) = result?;
+                user_map.insert(user_id, serde_json::Value::Number(count.into()));
+            }
+
+            // Content bytes by user, expired memories included until decay
+            // removes them
+            let mut stmt = conn.prepare(
+                "SELECT user_id, SUM(content_bytes) FROM memory_usage GROUP BY user_id",
+            )?;
+            let user_bytes = stmt
+                .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?)))?
+                .collect::<rusqlite::Result<BTreeMap<String, i64>>>()?;
+            let content_bytes: i64 = user_bytes.values().sum();
+
+            // Database and WAL file sizes
+            let file_size = self.database_size_bytes();
+            let wal_size = self.wal_size_bytes();
+
+            // Pool status
+            let write_pool_status = self.write_pool.status();
+            let read_pool_statuses: Vec<_> = self.read_pools.iter().map(|p| p.status()).collect();
+
+            let stats = serde_json::json!({
+                "total_memories": total_memories,
+                "user_counts": user_map,
+                "content_bytes": content_bytes,
+                "user_bytes": user_bytes,
+                "database_size_bytes": file_size,
+                "wal_size_bytes": wal_size,
+                "wal_truncate_threshold_bytes": self.config.wal_truncate_threshold_bytes,
+                "database_path": self.config.path,
+                "connection_pools": {
+                    "write_pool": {
+                        "connections": write_pool_status.connections,
+                        "idle_connections": write_pool_status.idle_connections,
+                        "max_connections": write_pool_status.max_connections,
+                        "utilization": write_pool_status.utilization(),
+                        "healthy": write_pool_status.is_healthy()
+                    },
+                    "read_pools": read_pool_statuses.iter().enumerate().map(|(i, status)| {
+                        serde_json::json!({
+                            "index": i,
+                            "connections": status.connections,
+                            "idle_connections": status.idle_connections,
+                            "max_connections": status.max_connections,
+                            "utilization": status.utilization(),
+                            "healthy": status.is_healthy()
+                        })
+                    }).collect::<Vec<_>>()
+                }
+            });
+
+            Ok(stats)
+        })
+    }
+
+    /// Create a new session (write operation)
+    pub fn create_session(&self, user_id: &str, session_name: Option<String>) -> Result<String> {
+        let session_id = self.generate_id(IdKind::Session);
+        let now = Utc::now();
+
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute(
+                "INSERT OR IGNORE INTO users (id) VALUES (?1)",
+                rusqlite::params![user_id],
+            )?;
+            tx.execute(
+                r#"
+                INSERT INTO sessions (id, user_id, name, created_at, last_active)
+                VALUES (?1, ?2, ?3, ?4, ?5)
+                "#,
+                rusqlite::params![session_id, user_id, session_name, now, now],
+            )?;
+            audit::record(tx, AuditAction::SessionCreate, &session_id)?;
+            Ok(())
+        })?;
+
+        tracing::debug!(
+            session_id = session_id.as_str(),
+            user_id = user_id,
+            "Created session row"
+        );
+        Ok(session_id)
+    }
+
+    /// Whether `session_id` exists and belongs to `user_id` (read operation)
+    pub fn session_exists(&self, session_id: &str, user_id: &str) -> Result<bool> {
+        self.get_read_pool().with_read_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT 1 FROM sessions WHERE id = ?1 AND user_id = ?2",
+                    [session_id, user_id],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some())
+        })
+    }
+
+    /// Get a user's active and closed sessions with pagination (read operation)
+    pub fn get_user_sessions(
+        &self,
+        user_id: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<models::Session>> {
+        self.get_user_sessions_with_status(user_id, &SessionStatus::LISTED, limit, offset)
+    }
+
+    /// Get a user's sessions in any of `statuses` with pagination (read operation)
+    pub fn get_user_sessions_with_status(
+        &self,
+        user_id: &str,
+        statuses: &[SessionStatus],
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<models::Session>> {
+        let read_pool = self.get_read_pool();
+        let statuses = statuses
+            .iter()
+            .map(|status| format!("'{}'", status.as_str()))
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        read_pool.with_read_connection(|conn| {
+            // Get total count
+            let total_count: i64 = conn.query_row(
+                &format!(
+                    "SELECT COUNT(*) FROM sessions WHERE user_id = ?1 AND status IN ({})",
+                    statuses
+                ),
+                rusqlite::params![user_id],
+                |row| row.get(0),
+            )?;
+
+            // Calculate pagination
+            let per_page = limit.unwrap_or(50);
+            let page = offset.map(|o| o / per_page).unwrap_or(0);
+            let total_pages = ((total_count as f64) / (per_page as f64)).ceil() as usize;
+
+            // Get sessions with memory counts
+            let mut stmt = conn.prepare(&format!(
+                r#"
+                SELECT s.id, s.user_id, s.name, s.status, s.created_at, s.last_active,
+                       COALESCE(m.memory_count, 0) as memory_count
+                FROM sessions s
+                LEFT JOIN (
+                    SELECT session_id, COUNT(*) as memory_count 
+                    FROM memories 
+                    WHERE expires_at IS NULL OR expires_at > datetime('now')
+                    GROUP BY session_id
+                ) m ON s.id = m.session_id
+                WHERE s.user_id = ?1 AND s.status IN ({})
+                ORDER BY s.last_active DESC
+                LIMIT ?2 OFFSET ?3
+                "#,
+                statuses
+            ))?;
+
+            let session_iter = stmt.query_map(
+                rusqlite::params![user_id, per_page, offset.unwrap_or(0)],
+                |row| {
+                    Ok(models::Session {
+                        id: row.get("id")?,
+                        user_id: row.get("user_id")?,
+                        name: row.get("name")?,
+                        status: row_to_session_status(row)?,
+                        created_at: row.get("created_at")?,
+                        last_active: row.get("last_active")?,
+                        memory_count: row.get("memory_count")?,
+                        tags: Vec::new(), // TODO: Implement tags
+                        metadata: std::collections::HashMap::new(), // TODO: Implement metadata
+                    })
+                },
+            )?;
+
+            let mut sessions = Vec::new();
+            for session in session_iter {
+                sessions.push(session?);
+            }
+
+            Ok(PaginatedResponse {
+                data: sessions,
+                total_count,
+                page,
+                per_page,
+                total_pages,
+                has_next: page < total_pages.saturating_sub(1),
+                has_prev: page > 0,
+            })
+        })
+    }
+
+    /// Move a session to `status`; false if it doesn't exist (write operation)
+    pub fn set_session_status(&self, session_id: &str, status: SessionStatus) -> Result<bool> {
+        let updated = self.write_pool.with_write_transaction(|tx| {
+            let updated = tx.execute(
+                "UPDATE sessions SET status = ?2 WHERE id = ?1",
+                [session_id, status.as_str()],
+            )?;
+            if updated > 0 {
+                audit::record(tx, AuditAction::SessionStatus, session_id)?;
+            }
+            Ok(updated)
+        })?;
+
+        if updated > 0 {
+            tracing::debug!(
+                session_id = session_id,
+                status = status.as_str(),
+                "Set session status"
+            );
+        }
+        Ok(updated > 0)
+    }
+
+    /// Sessions last active before `cutoff` with at least `min_memories`
+    /// memories and no stored summary covering them as they are now (read
+    /// operation)
+    ///
+    /// A summary stops covering its session when a memory is added, edited
+    /// or removed after it was stored, or when one expires.
+    pub fn sessions_to_summarize(
+        &self,
+        cutoff: DateTime<Utc>,
+        min_memories: usize,
+    ) -> Result<Vec<String>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT s.id
+                 FROM sessions s
+                 JOIN (SELECT session_id, COUNT(*) AS memory_count
+                       FROM memories
+                       WHERE is_compressed = 0 AND state = 'active'
+                         AND (expires_at IS NULL OR expires_at > datetime('now'))
+                         AND json_extract(metadata, '$.' || ?3) IS NOT ?4
+                       GROUP BY session_id) m ON m.session_id = s.id
+                 LEFT JOIN session_summaries ss ON ss.session_id = s.id
+                 WHERE s.last_active < ?1 AND m.memory_count >= ?2
+                   AND (ss.session_id IS NULL
+                        OR ss.memory_id IS NULL
+                        OR ss.stale = 1
+                        OR ss.memory_count != m.memory_count)
+                 ORDER BY s.last_active, s.id",
+            )?;
+            let sessions = stmt
+                .query_map(
+                    rusqlite::params![
+                        cutoff,
+                        min_memories as i64,
+                        TYPE_METADATA_KEY,
+                        SESSION_SUMMARY_TYPE
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(sessions)
+        })
+    }
+
+    /// Store `summary` of its session, replacing the session's earlier
+    /// summary, and optionally `memory` holding it in the session (write
+    /// operation)
+    ///
+    /// Each store bumps the summary's version and clears its stale flag. A
+    /// memory holding the earlier summary is deleted either way. The
+    /// session's last activity and status are left as they were, so storing
+    /// a summary memory doesn't wake an archived session or keep it from
+    /// being archived. Returns the summary as stored.
+    pub fn save_session_summary(
+        &self,
+        summary: &SessionSummary,
+        memory: Option<&MemoryItem>,
+    ) -> Result<SessionSummary> {
+        let id = match memory {
+            Some(memory) => {
+                memory.validate().context("Memory validation failed")?;
+                Some(if memory.id.is_empty() {
+                    self.generate_id(IdKind::Memory)
+                } else {
+                    memory.id.clone()
+                })
+            }
+            None => None,
+        };
+
+        let (stored, replaced) = self.write_pool.with_write_transaction(|tx| {
+            let replaced: Option<String> = tx
+                .query_row(
+                    "SELECT memory_id FROM session_summaries WHERE session_id = ?1",
+                    [&summary.session_id],
+                    |row| row.get(0),
+                )
+                .optional()?
+                .flatten();
+            if let Some(replaced) = &replaced {
+                if tx.execute("DELETE FROM memories WHERE id = ?1", [replaced])? > 0 {
+                    audit::record(tx, AuditAction::MemoryDelete, replaced)?;
+                }
+            }
+
+            if let (Some(memory), Some(id)) = (memory, &id) {
+                // As stored, whatever format wrote it
+                let session: Option<(rusqlite::types::Value, String)> = tx
+                    .query_row(
+                        "SELECT last_active, status FROM sessions WHERE id = ?1",
+                        [&summary.session_id],
+                        |row| Ok((row.get(0)?, row.get(1)?)),
+                    )
+                    .optional()?;
+                insert_memory(tx, memory, id, Utc::now())?;
+                self.seal(tx, id)?;
+                audit::record(tx, AuditAction::MemorySave, id)?;
+                if let Some((last_active, status)) = session {
+                    tx.execute(
+                        "UPDATE sessions SET last_active = ?2, status = ?3 WHERE id = ?1",
+                        rusqlite::params![summary.session_id, last_active, status],
+                    )?;
+                }
+            }
+
+            tx.execute(
+                r#"
+                INSERT INTO session_summaries (
+                    id, session_id, user_id, summary_text, key_topics, memory_count,
+                    date_range_start, date_range_end, importance_score, memory_id,
+                    version, generator, generated_at, stale, created_at, updated_at
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, ?11, ?12, 0, ?13, ?14)
+                ON CONFLICT (session_id) DO UPDATE SET
+                    user_id = excluded.user_id,
+                    summary_text = excluded.summary_text,
+                    key_topics = excluded.key_topics,
+                    memory_count = excluded.memory_count,
+                    date_range_start = excluded.date_range_start,
+                    date_range_end = excluded.date_range_end,
+                    importance_score = excluded.importance_score,
+                    memory_id = excluded.memory_id,
+                    version = session_summaries.version + 1,
+                    generator = excluded.generator,
+                    generated_at = excluded.generated_at,
+                    stale = 0,
+                    updated_at = excluded.updated_at
+                "#,
+                rusqlite::params![
+                    uuid::Uuid::new_v4().to_string(),
+                    summary.session_id,
+                    summary.user_id,
+                    summary.summary_text,
+                    serde_json::to_string(&summary.key_topics)?,
+                    summary.memory_count as i64,
+                    summary.date_range.0,
+                    summary.date_range.1,
+                    summary.importance_score,
+                    id,
+                    summary.generator.as_str(),
+                    summary.generated_at,
+                    summary.created_at,
+                    summary.updated_at,
+                ],
+            )?;
+
+            let stored =
+                query_session_summaries(tx, "WHERE session_id = ?1", [&summary.session_id])?
+                    .pop()
+                    .context("Stored session summary not found")?;
+            Ok((stored, replaced))
+        })?;
+
+        self.hot_tier
+            .remove(id.iter().chain(replaced.iter()).map(String::as_str));
+        tracing::debug!(
+            session_id = stored.session_id.as_str(),
+            version = stored.version,
+            memory_count = stored.memory_count,
+            "Stored session summary"
+        );
+        Ok(stored)
+    }
+
+    /// The stored summary of a session, stale or not (read operation)
+    pub fn get_session_summary(&self, session_id: &str) -> Result<Option<SessionSummary>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            Ok(query_session_summaries(conn, "WHERE session_id = ?1", [session_id])?.pop())
+        })
+    }
+
+    /// A user's stored session summaries, most recently generated first
+    /// (read operation)
+    pub fn get_user_session_summaries(&self, user_id: &str) -> Result<Vec<SessionSummary>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            query_session_summaries(
+                conn,
+                "WHERE user_id = ?1 ORDER BY generated_at DESC, session_id",
+                [user_id],
+            )
+        })
+    }
+
+    /// Store day or topic `summaries` of a user, replacing stored ones with
+    /// the same key; with `exclusive`, the user's other summaries at that
+    /// granularity are deleted (write operation)
+    ///
+    /// Replacing a summary bumps its version. Returns the summaries as
+    /// stored.
+    pub fn save_summaries(
+        &self,
+        user_id: &str,
+        granularity: SummaryGranularity,
+        summaries: &[Summary],
+        exclusive: bool,
+    ) -> Result<Vec<Summary>> {
+        if granularity == SummaryGranularity::Session {
+            return Err(anyhow::anyhow!(
+                "Session summaries are stored with save_session_summary"
+            ));
+        }
+        if let Some(summary) = summaries
+            .iter()
+            .find(|summary| summary.user_id != user_id || summary.granularity != granularity)
+        {
+            return Err(anyhow::anyhow!(
+                "Summary `{}` is not a {} summary of user {}",
+                summary.key,
+                granularity,
+                user_id
+            ));
+        }
+
+        let stored = self.write_pool.with_write_transaction(|tx| {
+            if exclusive {
+                let keys: Vec<&str> = summaries
+                    .iter()
+                    .map(|summary| summary.key.as_str())
+                    .collect();
+                tx.execute(
+                    "DELETE FROM summaries
+                     WHERE user_id = ?1 AND granularity = ?2
+                       AND key NOT IN (SELECT value FROM json_each(?3))",
+                    rusqlite::params![user_id, granularity.as_str(), serde_json::to_string(&keys)?],
+                )?;
+            }
+
+            let now = Utc::now();
+            let mut stmt = tx.prepare(
+                r#"
+                INSERT INTO summaries (
+                    id, user_id, granularity, key, summary_text, key_topics, memory_count,
+                    date_range_start, date_range_end, importance_score, version, generator,
+                    generated_at, stale, created_at, updated_at
+                )
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, 1, ?11, ?12, 0, ?13, ?13)
+                ON CONFLICT (user_id, granularity, key) DO UPDATE SET
+                    summary_text = excluded.summary_text,
+                    key_topics = excluded.key_topics,
+                    memory_count = excluded.memory_count,
+                    date_range_start = excluded.date_range_start,
+                    date_range_end = excluded.date_range_end,
+                    importance_score = excluded.importance_score,
+                    version = summaries.version + 1,
+                    generator = excluded.generator,
+                    generated_at = excluded.generated_at,
+                    stale = 0,
+                    updated_at = excluded.updated_at
+                "#,
+            )?;
+            for summary in summaries {
+                stmt.execute(rusqlite::params![
+                    uuid::Uuid::new_v4().to_string(),
+                    user_id,
+                    granularity.as_str(),
+                    summary.key,
+                    summary.summary_text,
+                    serde_json::to_string(&summary.key_topics)?,
+                    summary.memory_count as i64,
+                    summary.date_range.0,
+                    summary.date_range.1,
+                    summary.importance_score,
+                    summary.generator.as_str(),
+                    summary.generated_at,
+                    now,
+                ])?;
+            }
+            drop(stmt);
+
+            let keys: Vec<&str> = summaries
+                .iter()
+                .map(|summary| summary.key.as_str())
+                .collect();
+            query_summaries(
+                tx,
+                "WHERE user_id = ?1 AND granularity = ?2
+                   AND key IN (SELECT value FROM json_each(?3))",
+                rusqlite::params![user_id, granularity.as_str(), serde_json::to_string(&keys)?],
+            )
+        })?;
+
+        tracing::debug!(
+            user_id = user_id,
+            granularity = granularity.as_str(),
+            count = stored.len(),
+            "Stored summaries"
+        );
+        Ok(stored)
+    }
+
+    /// A user's stored day or topic summaries, stale or not, oldest first
+    /// (read operation)
+    pub fn get_summaries(
+        &self,
+        user_id: &str,
+        granularity: SummaryGranularity,
+    ) -> Result<Vec<Summary>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            query_summaries(
+                conn,
+                "WHERE user_id = ?1 AND granularity = ?2",
+                rusqlite::params![user_id, granularity.as_str()],
+            )
+        })
+    }
+
+    /// Archive every session last active before `cutoff` (write operation)
+    pub fn archive_inactive_sessions(&self, cutoff: DateTime<Utc>) -> Result<usize> {
+        self.write_pool.with_write_transaction(|tx| {
+            Ok(tx.execute(
+                "UPDATE sessions SET status = 'archived'
+                 WHERE status != 'archived' AND last_active < ?1",
+                [cutoff],
+            )?)
+        })
+    }
+
+    /// Rebuild the database file to reclaim free pages (write operation)
+    pub fn vacuum(&self) -> Result<()> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        conn.execute_batch("VACUUM")
+            .context("Failed to vacuum database")?;
+        tracing::info!(path = self.config.path.as_str(), "Vacuumed database");
+        Ok(())
+    }
+
+    /// Checkpoint the WAL into the database file (write operation)
+    ///
+    /// `CheckpointMode::Truncate` also shrinks the WAL file to zero bytes
+    /// unless a reader is still using it, which shows as `busy`.
+    ///
+    /// With WAL archiving on, new frames are archived first. The archiver's
+    /// read transaction keeps a checkpoint from copying frames it hasn't
+    /// archived, so a full checkpoint and a second archiving run come before
+    /// a restart or truncate, which otherwise would wait on that reader.
+    pub fn checkpoint(&self, mode: pool::CheckpointMode) -> Result<pool::WalCheckpoint> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        let mut archiver = self.wal_archiver.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(archiver) = archiver.as_mut() {
+            archiver.run()?;
+            if matches!(
+                mode,
+                pool::CheckpointMode::Restart | pool::CheckpointMode::Truncate
+            ) {
+                pool::checkpoint(&conn, &self.config.path, pool::CheckpointMode::Full)?;
+                archiver.run()?;
+            }
+        }
+        let checkpoint = pool::checkpoint(&conn, &self.config.path, mode)?;
+        tracing::info!(
+            mode = %mode,
+            busy = checkpoint.busy,
+            frames = checkpoint.checkpointed_frames,
+            wal_size_before = checkpoint.wal_size_before,
+            wal_size_after = checkpoint.wal_size_after,
+            "Checkpointed WAL"
+        );
+        Ok(checkpoint)
+    }
+
+    /// Truncate the WAL if it has grown past `wal_truncate_threshold_bytes`;
+    /// returns the checkpoint if one ran
+    pub fn checkpoint_if_due(&self) -> Result<Option<pool::WalCheckpoint>> {
+        let threshold = self.config.wal_truncate_threshold_bytes;
+        if threshold == 0 || self.config.read_only || self.wal_size_bytes() < threshold {
+            return Ok(None);
+        }
+        self.checkpoint(pool::CheckpointMode::Truncate).map(Some)
+    }
+
+    /// Refresh the query planner's statistics (write operation)
+    pub fn analyze(&self) -> Result<()> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        conn.execute_batch("ANALYZE")
+            .context("Failed to analyze database")?;
+        tracing::info!(path = self.config.path.as_str(), "Analyzed database");
+        Ok(())
+    }
+
+    /// Path of the database file
+    pub fn path(&self) -> &str {
+        &self.config.path
+    }
+
+    /// Start archiving the WAL for point-in-time recovery; frames are
+    /// archived by [`archive_wal`](Self::archive_wal) and before every
+    /// checkpoint
+    pub fn enable_wal_archive(&self, config: wal_archive::WalArchiveConfig) -> Result<()> {
+        self.write_pool.ensure_writable()?;
+        if !self.config.enable_wal {
+            return Err(anyhow::anyhow!(
+                "WAL archiving needs the database in WAL mode (enable_wal)"
+            ));
+        }
+        let archiver = wal_archive::WalArchiver::new(&self.config.path, config)?;
+        *self.wal_archiver.lock().unwrap_or_else(|e| e.into_inner()) = Some(archiver);
+        Ok(())
+    }
+
+    /// Store a tamper-evident hash with every memory written from now on;
+    /// see [`integrity`]. Signed with `MEMEX_INTEGRITY_KEY` when it is set.
+    pub fn enable_integrity(&self) -> Result<()> {
+        self.write_pool.ensure_writable()?;
+        self.sealer.get_or_init(integrity::Sealer::from_env);
+        Ok(())
+    }
+
+    /// Whether memory writes store an integrity hash
+    pub fn integrity_enabled(&self) -> bool {
+        self.sealer.get().is_some()
+    }
+
+    /// Seal memory `id` in `tx` if sealing is enabled
+    fn seal(&self, tx: &rusqlite::Connection, id: &str) -> Result<()> {
+        match self.sealer.get() {
+            Some(sealer) => sealer.seal(tx, id),
+            None => Ok(()),
+        }
+    }
+
+    /// The configured sealer, or one keyed from the environment for
+    /// instances that verify without sealing
+    fn verifier(&self) -> integrity::Sealer {
+        self.sealer
+            .get()
+            .cloned()
+            .unwrap_or_else(integrity::Sealer::from_env)
+    }
+
+    /// Check the integrity hash of every memory of `user_id`, or of all
+    /// memories if `None` (read operation)
+    pub fn verify_integrity(&self, user_id: Option<&str>) -> Result<integrity::IntegrityReport> {
+        let verifier = self.verifier();
+        self.get_read_pool()
+            .with_read_connection(|conn| integrity::verify(conn, &verifier, user_id))
+    }
+
+    /// Seal memories saved before sealing was enabled; returns how many
+    /// were sealed (write operation)
+    pub fn seal_unsealed_memories(&self) -> Result<usize> {
+        let sealer = self.verifier();
+        let sealed = self
+            .write_pool
+            .with_write_transaction(|tx| integrity::seal_unsealed(tx, &sealer))?;
+        tracing::info!(sealed = sealed, "Sealed memories without an integrity hash");
+        Ok(sealed)
+    }
+
+    /// Archive frames committed since the last run; `None` unless WAL
+    /// archiving is enabled
+    pub fn archive_wal(&self) -> Result<Option<wal_archive::ArchiveRun>> {
+        let mut archiver = self.wal_archiver.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(archiver) = archiver.as_mut() else {
+            return Ok(None);
+        };
+        let run = archiver.run()?;
+        tracing::info!(
+            segment = run.segment.is_some(),
+            base = run.base.is_some(),
+            new_chain = run.new_chain,
+            "Archived WAL"
+        );
+        Ok(Some(run))
+    }
+
+    /// Directory the WAL is archived to; `None` unless WAL archiving is
+    /// enabled
+    pub fn wal_archive_dir(&self) -> Option<std::path::PathBuf> {
+        self.wal_archiver
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|archiver| archiver.directory().to_path_buf())
+    }
+
+    /// Size of the WAL file; 0 when there is none
+    pub fn wal_size_bytes(&self) -> u64 {
+        pool::wal_size_bytes(&self.config.path)
+    }
+
+    /// WAL size past which checkpoints truncate it; 0 when they never do
+    pub fn wal_truncate_threshold_bytes(&self) -> u64 {
+        self.config.wal_truncate_threshold_bytes
+    }
+
+    /// Write a consistent copy of the database to `path`
+    pub fn backup_to(&self, path: &str) -> Result<()> {
+        if Path::new(path).exists() {
+            return Err(anyhow::anyhow!("Backup target already exists: {}", path));
+        }
+
+        let conn = self.write_pool.get_connection()?;
+        conn.execute("VACUUM INTO ?1", rusqlite::params![path])
+            .with_context(|| format!("Failed to back up database to {}", path))?;
+        tracing::info!(path = path, "Backed up database");
+        Ok(())
+    }
+
+    /// Write a consistent copy of the database to `path` as an archive,
+    /// compressed and/or encrypted per `options`, with a manifest to check it
+    /// against on restore
+    pub fn backup_archive(
+        &self,
+        path: &str,
+        options: &backup::BackupOptions,
+    ) -> Result<backup::BackupManifest> {
+        let conn = self.write_pool.get_connection()?;
+        let manifest = backup::create(&conn, &self.config.path, Path::new(path), options)?;
+        tracing::info!(
+            path = path,
+            compressed = manifest.compressed,
+            encrypted = manifest.is_encrypted(),
+            size_bytes = manifest.payload_size,
+            "Backed up database"
+        );
+        Ok(manifest)
+    }
+
+    /// Directory holding this database's snapshots
+    pub fn snapshot_dir(&self) -> std::path::PathBuf {
+        std::path::PathBuf::from(format!("{}.snapshots", self.config.path))
+    }
+
+    /// Write a consistent copy of the database as snapshot `label`, with a
+    /// manifest describing it
+    pub fn snapshot(&self, label: &str) -> Result<snapshot::SnapshotManifest> {
+        let conn = self.write_pool.get_connection()?;
+        let manifest = snapshot::create(&conn, &self.config.path, &self.snapshot_dir(), label)?;
+        tracing::info!(
+            label = label,
+            memories = manifest.memory_count,
+            size_bytes = manifest.size_bytes,
+            "Created snapshot"
+        );
+        Ok(manifest)
+    }
+
+    /// Manifests of this database's snapshots, oldest first
+    pub fn list_snapshots(&self) -> Result<Vec<snapshot::SnapshotManifest>> {
+        snapshot::list(&self.snapshot_dir())
+    }
+
+    /// Memories added, removed and changed per user from snapshot `from` to
+    /// snapshot `to`
+    pub fn diff_snapshots(&self, from: &str, to: &str) -> Result<snapshot::SnapshotDiff> {
+        snapshot::diff(&self.snapshot_dir(), from, to)
+    }
+
+    /// Hit and size counters of the hot tier
+    pub fn hot_tier_stats(&self) -> hot_tier::HotTierStats {
+        self.hot_tier.stats()
+    }
+
+    /// Most recent entries of the slow-query log
+    pub fn get_slow_queries(&self, limit: usize) -> Result<Vec<slow_query::SlowQuery>> {
+        let conn = self.write_pool.get_connection()?;
+        slow_query::recent(&conn, limit)
+    }
+
+    /// Empty the slow-query log, returning how many entries were removed
+    pub fn clear_slow_queries(&self) -> Result<usize> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        slow_query::clear(&conn)
+    }
+
+    /// Composite indexes that would serve recall filter shapes seen at
+    /// least `min_recalls` times, slowest first
+    pub fn get_index_advice(&self, min_recalls: u64) -> Result<Vec<index_advisor::IndexAdvice>> {
+        self.query_shapes.flush()?;
+        let conn = self.write_pool.get_connection()?;
+        index_advisor::advise(&conn, min_recalls)
+    }
+
+    /// Create the indexes in `advice` (write operation)
+    pub fn apply_index_advice(&self, advice: &[index_advisor::IndexAdvice]) -> Result<()> {
+        self.write_pool.with_write_transaction(|tx| {
+            for index in advice {
+                tx.execute_batch(&index.sql)
+                    .with_context(|| format!("Failed to create index {}", index.name))?;
+            }
+            Ok(())
+        })?;
+        for index in advice {
+            tracing::info!(index = index.name.as_str(), "Created advised index");
+        }
+        Ok(())
+    }
+
+    /// Forget the recorded recall filter shapes, returning how many there
+    /// were
+    pub fn clear_query_shapes(&self) -> Result<usize> {
+        self.write_pool.ensure_writable()?;
+        self.query_shapes.flush()?;
+        let conn = self.write_pool.get_connection()?;
+        index_advisor::clear(&conn)
+    }
+
+    /// Record the current statistics in the history (write operation)
+    pub fn snapshot_stats(&self) -> Result<stats_history::StatsSnapshot> {
+        self.write_pool.ensure_writable()?;
+        let conn = self.write_pool.get_connection()?;
+        let snapshot = stats_history::capture(&conn, self.database_size_bytes())?;
+        stats_history::record(&conn, &snapshot)?;
+        Ok(snapshot)
+    }
+
+    /// Record a snapshot if the last one is older than
+    /// `stats_snapshot_interval_hours`; returns it if one was taken
+    pub fn snapshot_stats_if_due(&self) -> Result<Option<stats_history::StatsSnapshot>> {
+        if self.config.stats_snapshot_interval_hours == 0 || self.config.read_only {
+            return Ok(None);
+        }
+
+        let interval = chrono::Duration::hours(self.config.stats_snapshot_interval_hours as i64);
+        let last = {
+            let conn = self.write_pool.get_connection()?;
+            stats_history::last_recorded_at(&conn)?
+        };
+
+        match last {
+            Some(last) if Utc::now() - last < interval => Ok(None),
+            _ => self.snapshot_stats().map(Some),
+        }
+    }
+
+    /// Snapshots taken since `since`, oldest first
+    pub fn get_stats_history(
+        &self,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<stats_history::StatsSnapshot>> {
+        let conn = self.write_pool.get_connection()?;
+        stats_history::since(&conn, since)
+    }
+
+    /// Keep the outcome of a maintenance job run, returning its ID (write
+    /// operation)
+    pub fn record_maintenance_run(&self, run: &maintenance_runs::MaintenanceRun) -> Result<i64> {
+        self.write_pool
+            .with_write_transaction(|tx| maintenance_runs::record(tx, run))
+    }
+
+    /// When `job` last ran, on any instance sharing this file
+    pub fn last_maintenance_run_at(
+        &self,
+        job: maintenance_runs::MaintenanceJob,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let conn = self.write_pool.get_connection()?;
+        maintenance_runs::last_started_at(&conn, job)
+    }
+
+    /// The `limit` most recent audit log entries matching `filter`, newest
+    /// first (read operation)
+    pub fn get_audit_log(
+        &self,
+        filter: &audit::AuditFilter,
+        limit: usize,
+    ) -> Result<Vec<audit::AuditEntry>> {
+        self.get_read_pool()
+            .with_read_connection(|conn| audit::recent(conn, filter, limit))
+    }
+
+    /// The `limit` most recent maintenance runs, of `job` or of every job,
+    /// newest first
+    pub fn get_maintenance_runs(
+        &self,
+        job: Option<maintenance_runs::MaintenanceJob>,
+        limit: usize,
+    ) -> Result<Vec<maintenance_runs::MaintenanceRun>> {
+        let read_pool = self.get_read_pool();
+        read_pool.with_read_connection(|conn| maintenance_runs::recent(conn, job, limit))
+    }
+
+    /// Memories saved per user in each of the last `hours` hours, most
+    /// recent hour first
+    pub fn get_hourly_saves(&self, hours: u32) -> Result<BTreeMap<String, Vec<i64>>> {
+        if hours == 0 {
+            return Ok(BTreeMap::new());
+        }
+        let read_pool = self.get_read_pool();
+        read_pool.with_read_connection(|conn| stats_history::hourly_saves(conn, Utc::now(), hours))
+    }
+
+    /// Memories and content bytes per user, largest first (read operation)
+    pub fn user_storage(&self) -> Result<Vec<StorageUsage>> {
+        let read_pool = self.get_read_pool();
+        read_pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT user_id, SUM(memory_count), SUM(content_bytes) FROM memory_usage
+                 GROUP BY user_id
+                 ORDER BY SUM(content_bytes) DESC, user_id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(StorageUsage {
+                    user_id: row.get(0)?,
+                    session_id: None,
+                    memory_count: row.get(1)?,
+                    content_bytes: row.get::<_, i64>(2)?.max(0) as u64,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
+
+    /// A page of the users with a record, session or memory, by user ID,
+    /// with what they hold and when they were last active. Counts come
+    /// from the `memory_usage` counters and last activity from indexes, so
+    /// a page costs the same however many memories users have
+    /// (read operation)
+    pub fn list_users(
+        &self,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<UserOverview>> {
+        let total_count: i64 = self.get_read_pool().with_read_connection(|conn| {
+            Ok(conn.query_row(
+                &format!("SELECT COUNT(*) FROM ({})", USER_IDS_SQL),
                 [],
-            )?;
+                |row| row.get(0),
+            )?)
+        })?;
 
-            // Then delete from main table
-            let rows_affected = tx.execute(
-                "DELETE FROM memories WHERE expires_at IS NOT NULL AND expires_at <= datetime('now')",
-                [],
-            )?;
+        let per_page = limit.unwrap_or(50);
+        let offset = offset.unwrap_or(0);
+        let page = offset / per_page.max(1);
+        let total_pages = ((total_count as f64) / (per_page.max(1) as f64)).ceil() as usize;
+        let users = self.query_user_overviews(
+            &format!("{} ORDER BY 1 LIMIT ?1 OFFSET ?2", USER_IDS_SQL),
+            &[&(per_page as i64), &(offset as i64)],
+        )?;
 
-            log::info!("Cleaned up {} expired memories", rows_affected);
-            Ok(rows_affected)
+        Ok(PaginatedResponse {
+            data: users,
+            total_count,
+            page,
+            per_page,
+            total_pages,
+            has_next: ((offset + per_page) as i64) < total_count,
+            has_prev: offset > 0,
         })
     }
 
-    /// Get database statistics (read operation)
-    pub fn get_stats(&self) -> Result<serde_json::Value> {
-        let read_pool = self.get_read_pool();
+    /// [`list_users`](Self::list_users) for one user; `None` if nothing is
+    /// stored about them (read operation)
+    pub fn get_user_overview(&self, user_id: &str) -> Result<Option<UserOverview>> {
+        Ok(self
+            .query_user_overviews(
+                "SELECT id FROM users WHERE id = ?1
+                 UNION SELECT user_id FROM sessions WHERE user_id = ?1
+                 UNION SELECT user_id FROM memory_usage WHERE user_id = ?1",
+                &[&user_id],
+            )?
+            .pop())
+    }
 
+    fn query_user_overviews(
+        &self,
+        users_sql: &str,
+        params: &[&dyn rusqlite::ToSql],
+    ) -> Result<Vec<UserOverview>> {
+        let read_pool = self.get_read_pool();
         read_pool.with_read_connection(|conn| {
-            // Total memories
-            let total_memories: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM memories WHERE (expires_at IS NULL OR expires_at > datetime('now'))",
-                [],
-                |row| Ok(row.get(0)?)
-            )?;
-
-            // Memory by user
-            let mut stmt = conn.prepare("SELECT user_id, COUNT(*) FROM memories WHERE (expires_at IS NULL OR expires_at > datetime('now')) GROUP BY user_id")?;
-            let user_counts = stmt.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            let mut stmt = conn.prepare(&format!(
+                "SELECT u.id,
+                        COALESCE((SELECT SUM(memory_count) FROM memory_usage WHERE user_id = u.id), 0),
+                        (SELECT COUNT(*) FROM sessions WHERE user_id = u.id),
+                        COALESCE((SELECT SUM(content_bytes) FROM memory_usage WHERE user_id = u.id), 0),
+                        NULLIF(MAX(
+                            COALESCE((SELECT MAX(last_active) FROM sessions WHERE user_id = u.id), ''),
+                            COALESCE((SELECT MAX(updated_at) FROM memories WHERE user_id = u.id), '')
+                        ), '')
+                 FROM ({}) AS u
+                 ORDER BY u.id",
+                users_sql
+            ))?;
+            let rows = stmt.query_map(params, |row| {
+                Ok(UserOverview {
+                    user_id: row.get(0)?,
+                    memory_count: row.get(1)?,
+                    session_count: row.get(2)?,
+                    content_bytes: row.get::<_, i64>(3)?.max(0) as u64,
+                    last_active: row.get(4)?,
+                })
             })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })
+    }
 
-            let mut user_map = serde_json::Map::new();
-            for result in user_counts {
-                let (user_id, count) = result?;
-                user_map.insert(user_id, serde_json::Value::Number(count.into()));
+    /// Erase everything stored about `user_id`: memories with their
+    /// embeddings, links and compression originals, sessions, summaries and
+    /// the user record. Audited as `user_purge` (write operation)
+    pub fn purge_user(&self, user_id: &str) -> Result<UserPurge> {
+        let purge = self.write_pool.with_write_transaction(|tx| {
+            let memory_ids = {
+                let mut stmt = tx.prepare("SELECT id FROM memories WHERE user_id = ?1")?;
+                let ids = stmt.query_map([user_id], |row| row.get::<_, String>(0))?;
+                ids.collect::<rusqlite::Result<Vec<_>>>()?
+            };
+            tx.execute("DELETE FROM memories WHERE user_id = ?1", [user_id])?;
+            let summaries = tx.execute(
+                "DELETE FROM session_summaries WHERE user_id = ?1",
+                [user_id],
+            )? + tx
+                .execute("DELETE FROM summaries WHERE user_id = ?1", [user_id])?;
+            let sessions = tx.execute("DELETE FROM sessions WHERE user_id = ?1", [user_id])?;
+            tx.execute(
+                "DELETE FROM compressed_memories WHERE user_id = ?1",
+                [user_id],
+            )?;
+            tx.execute("DELETE FROM memory_archive WHERE user_id = ?1", [user_id])?;
+            tx.execute("DELETE FROM idempotency_keys WHERE user_id = ?1", [user_id])?;
+            let user = tx.execute("DELETE FROM users WHERE id = ?1", [user_id])?;
+
+            let purge = UserPurge {
+                user_id: user_id.to_string(),
+                memory_ids,
+                sessions,
+                summaries,
+            };
+            if purge.found() || user > 0 {
+                audit::record(tx, AuditAction::UserPurge, user_id)?;
             }
+            Ok(purge)
+        })?;
+        self.hot_tier
+            .remove(purge.memory_ids.iter().map(String::as_str));
+        Ok(purge)
+    }
 
-            // Database file size
-            let file_size = std::fs::metadata(&self.config.path)
-                .map(|m| m.len())
-                .unwrap_or(0);
+    /// The receipt of the save `user_id` already made with idempotency
+    /// `key`, if it is still remembered and its memory still exists (read
+    /// operation)
+    pub fn find_idempotent_save(&self, user_id: &str, key: &str) -> Result<Option<SaveReceipt>> {
+        self.write_pool
+            .with_read_connection(|conn| replayed_save(conn, user_id, key))
+    }
 
-            // Pool status
-            let write_pool_status = self.write_pool.status();
-            let read_pool_statuses: Vec<_> = self.read_pools.iter().map(|p| p.status()).collect();
+    /// Move a metadata key's values to another key and/or map them to new
+    /// values across every live memory that has it (write operation)
+    ///
+    /// Memories are migrated `batch_size` at a time in ID order, each batch
+    /// in its own transaction, with `on_progress` called after it commits.
+    /// A memory whose target key already holds a different value is left
+    /// alone and listed in `conflicts`. Migrated memories get a new version
+    /// and are audited as updates. Running a finished migration again
+    /// changes nothing.
+    pub fn migrate_metadata(
+        &self,
+        migration: &MetadataMigration,
+        batch_size: usize,
+        mut on_progress: impl FnMut(&MetadataMigrationProgress),
+    ) -> Result<MetadataMigrationProgress> {
+        if batch_size == 0 {
+            return Err(anyhow::anyhow!("Batch size must be at least 1"));
+        }
+        if migration.from_key.is_empty() || migration.to_key.as_deref() == Some("") {
+            return Err(anyhow::anyhow!("Metadata keys can't be empty"));
+        }
+        let to_key = migration.to_key.as_deref().unwrap_or(&migration.from_key);
+        if to_key == migration.from_key && migration.value_map.is_empty() {
+            return Err(anyhow::anyhow!(
+                "Nothing to migrate: give a different target key or values to map"
+            ));
+        }
 
-            let stats = serde_json::json!({
-                "total_memories": total_memories,
-                "user_counts": user_map,
-                "database_size_bytes": file_size,
-                "database_path": self.config.path,
-                "connection_pools": {
-                    "write_pool": {
-                        "connections": write_pool_status.connections,
-                        "idle_connections": write_pool_status.idle_connections,
-                        "max_connections": write_pool_status.max_connections,
-                        "utilization": write_pool_status.utilization(),
-                        "healthy": write_pool_status.is_healthy()
-                    },
-                    "read_pools": read_pool_statuses.iter().enumerate().map(|(i, status)| {
-                        serde_json::json!({
-                            "index": i,
-                            "connections": status.connections,
-                            "idle_connections": status.idle_connections,
-                            "max_connections": status.max_connections,
-                            "utilization": status.utilization(),
-                            "healthy": status.is_healthy()
-                        })
-                    }).collect::<Vec<_>>()
+        let select = format!(
+            "SELECT {} FROM memories
+             WHERE id > ?1 AND (?2 IS NULL OR user_id = ?2)
+               AND json_extract(metadata, ?3) IS NOT NULL
+               AND (expires_at IS NULL OR expires_at > datetime('now'))
+             ORDER BY id
+             LIMIT ?4",
+            MEMORY_COLUMNS
+        );
+        let mut progress = MetadataMigrationProgress::default();
+        let mut cursor = String::new();
+        loop {
+            let (scanned, migrated, conflicts) = self.write_pool.with_write_transaction(|tx| {
+                let batch = {
+                    let mut stmt = tx.prepare(&select)?;
+                    let rows = stmt.query_map(
+                        rusqlite::params![
+                            cursor,
+                            migration.user_id,
+                            json_key_path(&migration.from_key),
+                            batch_size as i64
+                        ],
+                        row_to_memory,
+                    )?;
+                    rows.collect::<rusqlite::Result<Vec<_>>>()?
+                };
+
+                let mut migrated = Vec::new();
+                let mut conflicts = Vec::new();
+                for mut memory in batch.iter().cloned() {
+                    match migrate_metadata_entry(&mut memory.metadata, migration, to_key) {
+                        MetadataChange::Unchanged => continue,
+                        MetadataChange::Conflict => {
+                            conflicts.push(memory.id);
+                            continue;
+                        }
+                        MetadataChange::Changed => {}
+                    }
+                    if !migration.dry_run {
+                        memory.updated_at = Utc::now();
+                        memory.version += 1;
+                        memory.validate().context("Memory validation failed")?;
+                        tx.execute(
+                            "UPDATE memories SET metadata = ?2, updated_at = ?3, version = ?4
+                             WHERE id = ?1",
+                            rusqlite::params![
+                                memory.id,
+                                serde_json::to_string(&memory.metadata)?,
+                                memory.updated_at,
+                                memory.version
+                            ],
+                        )?;
+                        self.seal(tx, &memory.id)?;
+                        audit::record(tx, AuditAction::MemoryUpdate, &memory.id)?;
+                    }
+                    migrated.push(memory);
                 }
-            });
+                Ok((batch, migrated, conflicts))
+            })?;
 
-            Ok(stats)
+            let Some(last) = scanned.last() else {
+                break;
+            };
+            cursor = last.id.clone();
+            if !migration.dry_run {
+                for memory in &migrated {
+                    self.hot_tier.write(memory);
+                }
+            }
+
+            progress.scanned += scanned.len();
+            progress.migrated += migrated.len();
+            progress.conflicts.extend(conflicts);
+            tracing::debug!(
+                from_key = migration.from_key.as_str(),
+                scanned = progress.scanned,
+                migrated = progress.migrated,
+                "Migrated metadata batch"
+            );
+            on_progress(&progress);
+        }
+        Ok(progress)
+    }
+
+    /// Memories and content bytes in each of a user's sessions, largest
+    /// first (read operation)
+    pub fn session_storage(&self, user_id: &str) -> Result<Vec<StorageUsage>> {
+        let read_pool = self.get_read_pool();
+        read_pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT session_id, memory_count, content_bytes FROM memory_usage
+                 WHERE user_id = ?1
+                 ORDER BY content_bytes DESC, session_id",
+            )?;
+            let rows = stmt.query_map([user_id], |row| {
+                Ok(StorageUsage {
+                    user_id: user_id.to_string(),
+                    session_id: Some(row.get(0)?),
+                    memory_count: row.get(1)?,
+                    content_bytes: row.get::<_, i64>(2)?.max(0) as u64,
+                })
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
         })
     }
 
-    /// Create a new session (write operation)
-    pub fn create_session(&self, user_id: &str, session_name: Option<String>) -> Result<String> {
-        let session_id = Uuid::new_v4().to_string();
-        let now = Utc::now();
+    /// Percentiles and histogram of `value`, an SQL expression over a
+    /// user's live memories (read operation)
+    ///
+    /// `edges` are the ascending lower bounds of the histogram buckets;
+    /// values below the first still count towards it. Percentiles use the
+    /// nearest-rank method, so they are always values that occur.
+    pub fn get_value_distribution(
+        &self,
+        user_id: &str,
+        value: &str,
+        edges: &[f64],
+    ) -> Result<ValueDistribution> {
+        let live = format!(
+            "SELECT {} AS v FROM memories
+             WHERE user_id = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))
+               AND is_compressed = 0",
+            value
+        );
 
-        self.write_pool.with_write_transaction(|tx| {
-            tx.execute(
-                r#"
-                INSERT INTO sessions (id, user_id, name, created_at, last_active)
-                VALUES (?1, ?2, ?3, ?4, ?5)
-                "#,
-                rusqlite::params![session_id, user_id, session_name, now, now],
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut distribution = conn.query_row(
+                &format!(
+                    "WITH ranked AS (
+                         SELECT v, ROW_NUMBER() OVER (ORDER BY v) AS rank, COUNT(*) OVER () AS n
+                         FROM ({})
+                     )
+                     SELECT MIN(v),
+                            MIN(CASE WHEN rank >= 0.5 * n THEN v END),
+                            MIN(CASE WHEN rank >= 0.9 * n THEN v END),
+                            MIN(CASE WHEN rank >= 0.99 * n THEN v END),
+                            MAX(v)
+                     FROM ranked",
+                    live
+                ),
+                [user_id],
+                |row| {
+                    let get = |i| row.get::<_, Option<f64>>(i).map(Option::unwrap_or_default);
+                    Ok(ValueDistribution {
+                        min: get(0)?,
+                        p50: get(1)?,
+                        p90: get(2)?,
+                        p99: get(3)?,
+                        max: get(4)?,
+                        histogram: Vec::new(),
+                    })
+                },
             )?;
-            Ok(())
-        })?;
 
-        log::debug!("Created session: {} for user: {}", session_id, user_id);
-        Ok(session_id)
+            distribution.histogram = edges
+                .iter()
+                .enumerate()
+                .map(|(i, &from)| HistogramBucket {
+                    from,
+                    to: edges.get(i + 1).copied(),
+                    count: 0,
+                })
+                .collect();
+            if distribution.histogram.is_empty() {
+                return Ok(distribution);
+            }
+
+            // Edges are our own numbers, so they can go into the SQL as is
+            let bucket = edges
+                .iter()
+                .enumerate()
+                .skip(1)
+                .map(|(i, edge)| format!("WHEN v < {:?} THEN {}", edge, i - 1))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let bucket = if bucket.is_empty() {
+                "0".to_string()
+            } else {
+                format!("CASE {} ELSE {} END", bucket, edges.len() - 1)
+            };
+            let mut stmt = conn.prepare(&format!(
+                "SELECT {} AS bucket, COUNT(*) FROM ({}) GROUP BY bucket",
+                bucket, live
+            ))?;
+            let counts = stmt.query_map([user_id], |row| {
+                Ok((row.get::<_, usize>(0)?, row.get::<_, i64>(1)?))
+            })?;
+            for count in counts {
+                let (bucket, count) = count?;
+                distribution.histogram[bucket].count = count.max(0) as u64;
+            }
+
+            Ok(distribution)
+        })
     }
 
-    /// Get sessions for a user with pagination (read operation)
-    pub fn get_user_sessions(
-        &self,
-        user_id: &str,
-        limit: Option<usize>,
-        offset: Option<usize>,
-    ) -> Result<PaginatedResponse<models::Session>> {
+    /// Content bytes a user holds, leaving out memory `excluding` (the one
+    /// about to be replaced or updated) if it is theirs (read operation)
+    pub fn user_content_bytes(&self, user_id: &str, excluding: Option<&str>) -> Result<u64> {
         let read_pool = self.get_read_pool();
-
         read_pool.with_read_connection(|conn| {
-            // Get total count
-            let total_count: i64 = conn.query_row(
-                "SELECT COUNT(*) FROM sessions WHERE user_id = ?1",
-                rusqlite::params![user_id],
-                |row| Ok(row.get(0)?),
+            let bytes: i64 = conn.query_row(
+                "SELECT COALESCE((SELECT SUM(content_bytes) FROM memory_usage WHERE user_id = ?1), 0)
+                      - COALESCE((SELECT content_bytes FROM memories WHERE id = ?2 AND user_id = ?1), 0)",
+                rusqlite::params![user_id, excluding],
+                |row| row.get(0),
             )?;
+            Ok(bytes.max(0) as u64)
+        })
+    }
 
-            // Calculate pagination
-            let per_page = limit.unwrap_or(50);
-            let page = offset.map(|o| o / per_page).unwrap_or(0);
-            let total_pages = ((total_count as f64) / (per_page as f64)).ceil() as usize;
-
-            // Get sessions with memory counts
+    /// Memories of `user_id` less important than `below` whose deletion
+    /// frees at least `bytes`, least important and then oldest first,
+    /// leaving out `keep`; empty if all of them together free less
+    /// (read operation)
+    pub fn eviction_candidates(
+        &self,
+        user_id: &str,
+        below: f32,
+        bytes: u64,
+        keep: &[String],
+    ) -> Result<Vec<String>> {
+        self.get_read_pool().with_read_connection(|conn| {
             let mut stmt = conn.prepare(
-                r#"
-                SELECT s.id, s.user_id, s.name, s.created_at, s.last_active,
-                       COALESCE(m.memory_count, 0) as memory_count
-                FROM sessions s
-                LEFT JOIN (
-                    SELECT session_id, COUNT(*) as memory_count 
-                    FROM memories 
-                    WHERE expires_at IS NULL OR expires_at > datetime('now')
-                    GROUP BY session_id
-                ) m ON s.id = m.session_id
-                WHERE s.user_id = ?1
-                ORDER BY s.last_active DESC
-                LIMIT ?2 OFFSET ?3
-                "#,
+                "SELECT id, content_bytes FROM memories
+                 WHERE user_id = ?1 AND importance < ?2
+                 ORDER BY importance ASC, created_at ASC",
             )?;
+            let rows = stmt.query_map(rusqlite::params![user_id, below], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?;
 
-            let session_iter = stmt.query_map(
-                rusqlite::params![user_id, per_page, offset.unwrap_or(0)],
-                |row| {
-                    Ok(models::Session {
-                        id: row.get("id")?,
-                        user_id: row.get("user_id")?,
-                        name: row.get("name")?,
-                        created_at: row.get("created_at")?,
-                        last_active: row.get("last_active")?,
-                        memory_count: row.get("memory_count")?,
-                        tags: Vec::new(), // TODO: Implement tags
-                        metadata: std::collections::HashMap::new(), // TODO: Implement metadata
-                    })
-                },
+            let mut ids = Vec::new();
+            let mut freed = 0u64;
+            for row in rows {
+                let (id, content_bytes) = row?;
+                if keep.contains(&id) {
+                    continue;
+                }
+                ids.push(id);
+                freed += content_bytes.max(0) as u64;
+                if freed >= bytes {
+                    return Ok(ids);
+                }
+            }
+            Ok(Vec::new())
+        })
+    }
+
+    /// The `count` memories of `user_id` to drop first when over a
+    /// memory limit: least important and then oldest, leaving out session
+    /// summaries (read operation)
+    pub fn excess_memories(&self, user_id: &str, count: usize) -> Result<Vec<String>> {
+        self.get_read_pool().with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT id FROM memories
+                 WHERE user_id = ?1 AND json_extract(metadata, '$.' || ?3) IS NOT ?4
+                 ORDER BY importance ASC, created_at ASC
+                 LIMIT ?2",
             )?;
+            let ids = stmt
+                .query_map(
+                    rusqlite::params![
+                        user_id,
+                        count as i64,
+                        TYPE_METADATA_KEY,
+                        SESSION_SUMMARY_TYPE
+                    ],
+                    |row| row.get(0),
+                )?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+            Ok(ids)
+        })
+    }
 
-            let mut sessions = Vec::new();
-            for session in session_iter {
-                sessions.push(session?);
+    fn database_size_bytes(&self) -> u64 {
+        std::fs::metadata(&self.config.path)
+            .map(|m| m.len())
+            .unwrap_or(0)
+    }
+
+    /// Rebuild the full-text index from the memories table (write operation)
+    pub fn rebuild_fts_index(&self) -> Result<()> {
+        self.write_pool.with_write_transaction(|tx| {
+            tx.execute_batch(schema::FTS_REBUILD_SQL)
+                .context("Failed to rebuild full-text index")?;
+            if has_trigram_index(tx)? {
+                tx.execute_batch(schema::TRIGRAM_REBUILD_SQL)
+                    .context("Failed to rebuild trigram index")?;
             }
+            Ok(())
+        })?;
+        tracing::info!(path = self.config.path.as_str(), "Rebuilt full-text index");
+        Ok(())
+    }
 
-            Ok(PaginatedResponse {
-                data: sessions,
-                total_count,
-                page,
-                per_page,
-                total_pages,
-                has_next: page < total_pages.saturating_sub(1),
-                has_prev: page > 0,
-            })
+    /// Compare the full-text index with the memories table
+    pub fn check_fts_consistency(&self) -> Result<FtsConsistency> {
+        // The integrity check is issued as an INSERT, so it needs the primary
+        let conn = self.write_pool.get_connection()?;
+
+        let count = |sql: &str| -> Result<usize> {
+            let value: i64 = conn.query_row(sql, [], |row| row.get(0))?;
+            Ok(value as usize)
+        };
+
+        // For an external-content table `memories_fts` itself reads through to
+        // `memories`, so count the index's own per-document rows instead
+        Ok(FtsConsistency {
+            memory_rows: count("SELECT COUNT(*) FROM memories")?,
+            indexed_rows: count("SELECT COUNT(*) FROM memories_fts_docsize")?,
+            missing: count(
+                "SELECT COUNT(*) FROM memories WHERE rowid NOT IN (SELECT id FROM memories_fts_docsize)",
+            )?,
+            orphaned: count(
+                "SELECT COUNT(*) FROM memories_fts_docsize WHERE id NOT IN (SELECT rowid FROM memories)",
+            )?,
+            // Can't be issued read-only; the counts above still cover drift
+            integrity_ok: self.write_pool.is_read_only()
+                || conn.execute_batch(schema::FTS_INTEGRITY_CHECK_SQL).is_ok(),
         })
     }
 
@@ -611,6 +4137,487 @@ impl Database {
     }
 }
 
+/// How `build_recall_query` matches `QueryFilter::keywords`
+enum KeywordMatch<'a> {
+    /// Any keyword, through `memories_fts`
+    FullText,
+    /// Any of these trigrams, through `memories_trigram`
+    Trigram(&'a [String]),
+    /// Any of these trigrams, by scanning with `LIKE`
+    Like(&'a [String]),
+}
+
+/// Whether the database has the `memories_trigram` index
+fn has_trigram_index(conn: &rusqlite::Connection) -> Result<bool> {
+    has_table(conn, "memories_trigram")
+}
+
+/// What the memories matching `condition` take up, embeddings included
+fn footprint_where(
+    conn: &rusqlite::Connection,
+    condition: &str,
+    params: impl rusqlite::Params + Copy,
+) -> Result<Footprint> {
+    let (memories, content_bytes, metadata_bytes): (i64, i64, i64) = conn.query_row(
+        &format!(
+            "SELECT COUNT(*), COALESCE(SUM(content_bytes), 0),
+                    COALESCE(SUM(length(CAST(metadata AS BLOB))), 0)
+             FROM memories WHERE {}",
+            condition
+        ),
+        params,
+        |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+    )?;
+    let embedding_bytes: i64 = if memories > 0 && has_table(conn, "memory_embeddings")? {
+        conn.query_row(
+            &format!(
+                "SELECT COALESCE(SUM(length(embedding)), 0) FROM memory_embeddings
+                 WHERE memory_id IN (SELECT id FROM memories WHERE {})",
+                condition
+            ),
+            params,
+            |row| row.get(0),
+        )?
+    } else {
+        0
+    };
+    Ok(Footprint {
+        memories: memories as usize,
+        content_bytes: content_bytes.max(0) as u64,
+        metadata_bytes: metadata_bytes.max(0) as u64,
+        embedding_bytes: embedding_bytes.max(0) as u64,
+    })
+}
+
+fn has_table(conn: &rusqlite::Connection, name: &str) -> Result<bool> {
+    Ok(conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = ?1",
+            [name],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some())
+}
+
+/// A session row's `status`; unknown values read as active
+fn row_to_session_status(row: &rusqlite::Row) -> rusqlite::Result<SessionStatus> {
+    Ok(row.get::<_, String>("status")?.parse().unwrap_or_default())
+}
+
+/// Insert or replace `memory` as `id`, creating its user and session if needed
+fn insert_memory(
+    tx: &rusqlite::Transaction,
+    memory: &MemoryItem,
+    id: &str,
+    now: DateTime<Utc>,
+) -> Result<SaveReceipt> {
+    let expires_at = memory
+        .ttl_hours
+        .map(|ttl| now + chrono::Duration::hours(ttl as i64));
+
+    // Make sure the owning user and session exist so foreign keys hold; a
+    // session created here is named after the memory that opened it
+    tx.execute(
+        "INSERT OR IGNORE INTO users (id) VALUES (?1)",
+        rusqlite::params![memory.user_id],
+    )?;
+    let first_line = memory.content.lines().next().unwrap_or_default().trim();
+    let session_name = if first_line.is_empty() {
+        Cow::Borrowed(memory.session_id.as_str())
+    } else {
+        truncate_graphemes(first_line, AUTO_SESSION_NAME_GRAPHEMES)
+    };
+    let session_created = tx.execute(
+        "INSERT OR IGNORE INTO sessions (id, user_id, name) VALUES (?1, ?2, ?3)",
+        rusqlite::params![memory.session_id, memory.user_id, session_name.as_ref()],
+    )? > 0;
+    let deduplicated = tx
+        .query_row("SELECT 1 FROM memories WHERE id = ?1", [id], |_| Ok(()))
+        .optional()?
+        .is_some();
+
+    // Insert into memories table. A re-save updates the row in place rather
+    // than `INSERT OR REPLACE`, whose implicit delete skips the delete
+    // triggers that keep the FTS, trigram and usage tables in step
+    tx.execute(
+        r#"
+        INSERT INTO memories (
+            id, user_id, session_id, content, metadata,
+            created_at, updated_at, expires_at, importance, ttl_hours,
+            is_compressed, compressed_from, content_type, content_bytes, state, confidence,
+            source_type, source_ref, ingested_by, version, seq
+        ) VALUES (
+            ?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19,
+            (SELECT COALESCE(MAX(version), 0) + 1 FROM memories WHERE id = ?1),
+            -- Re-saving keeps the memory's place in its session
+            COALESCE(
+                (SELECT seq FROM memories WHERE id = ?1 AND session_id = ?3),
+                (SELECT COALESCE(MAX(seq), 0) + 1 FROM memories WHERE session_id = ?3)
+            )
+        )
+        ON CONFLICT (id) DO UPDATE SET
+            user_id = excluded.user_id,
+            session_id = excluded.session_id,
+            content = excluded.content,
+            metadata = excluded.metadata,
+            created_at = excluded.created_at,
+            updated_at = excluded.updated_at,
+            expires_at = excluded.expires_at,
+            importance = excluded.importance,
+            ttl_hours = excluded.ttl_hours,
+            is_compressed = excluded.is_compressed,
+            compressed_from = excluded.compressed_from,
+            content_type = excluded.content_type,
+            content_bytes = excluded.content_bytes,
+            state = excluded.state,
+            confidence = excluded.confidence,
+            source_type = excluded.source_type,
+            source_ref = excluded.source_ref,
+            ingested_by = excluded.ingested_by,
+            version = excluded.version,
+            seq = excluded.seq,
+            integrity_hash = NULL
+        "#,
+        rusqlite::params![
+            id,
+            memory.user_id,
+            memory.session_id,
+            memory.content,
+            serde_json::to_string(&memory.metadata)?,
+            memory.created_at,
+            now, // updated_at
+            expires_at,
+            memory.importance,
+            memory.ttl_hours,
+            memory.is_compressed,
+            serde_json::to_string(&memory.compressed_from)?,
+            memory.content_type.as_str(),
+            memory.content.len() as i64,
+            memory.state.as_str(),
+            memory.confidence,
+            memory.source_type.map(|source| source.as_str()),
+            memory.source_ref,
+            memory.ingested_by,
+        ],
+    )?;
+
+    // Update session last_active; new activity brings archived sessions back
+    tx.execute(
+        "UPDATE sessions
+         SET last_active = ?1,
+             status = CASE status WHEN 'archived' THEN 'active' ELSE status END
+         WHERE id = ?2",
+        rusqlite::params![now, memory.session_id],
+    )?;
+
+    Ok(SaveReceipt {
+        id: id.to_string(),
+        created_at: memory.created_at,
+        written_at: now,
+        deduplicated,
+        session_created,
+        bytes_written: memory.content.len(),
+        replayed: false,
+    })
+}
+
+/// The receipt of an earlier save by `user_id` with idempotency `key`
+fn replayed_save(
+    conn: &rusqlite::Connection,
+    user_id: &str,
+    key: &str,
+) -> Result<Option<SaveReceipt>> {
+    Ok(conn
+        .query_row(
+            "SELECT m.id, m.created_at, m.updated_at
+             FROM idempotency_keys k
+             INNER JOIN memories m ON m.id = k.memory_id
+             WHERE k.user_id = ?1 AND k.key = ?2 AND k.expires_at > ?3",
+            rusqlite::params![user_id, key, Utc::now()],
+            |row| {
+                Ok(SaveReceipt {
+                    id: row.get(0)?,
+                    created_at: row.get(1)?,
+                    written_at: row.get(2)?,
+                    deduplicated: false,
+                    session_created: false,
+                    bytes_written: 0,
+                    replayed: true,
+                })
+            },
+        )
+        .optional()?)
+}
+
+/// Map a full `memories` row
+fn row_to_memory(row: &rusqlite::Row) -> rusqlite::Result<MemoryItem> {
+    Ok(MemoryItem {
+        id: row.get("id")?,
+        user_id: row.get("user_id")?,
+        session_id: row.get("session_id")?,
+        content: row.get("content")?,
+        #[cfg(feature = "vector-search")]
+        embedding: None,
+        #[cfg(feature = "vector-search")]
+        embedding_model: None,
+        metadata: serde_json::from_str(&row.get::<_, String>("metadata")?).unwrap_or_default(),
+        created_at: row.get("created_at")?,
+        updated_at: row.get("updated_at")?,
+        expires_at: row.get("expires_at")?,
+        importance: row.get("importance")?,
+        confidence: row.get("confidence")?,
+        ttl_hours: row.get("ttl_hours")?,
+        is_compressed: row.get("is_compressed")?,
+        compressed_from: serde_json::from_str(&row.get::<_, String>("compressed_from")?)
+            .unwrap_or_default(),
+        version: row.get("version")?,
+        seq: row.get("seq")?,
+        idempotency_key: None,
+        match_score: None,
+        explanation: None,
+        content_type: row
+            .get::<_, String>("content_type")?
+            .parse()
+            .unwrap_or_default(),
+        state: row.get::<_, String>("state")?.parse().unwrap_or_default(),
+        source_type: row
+            .get::<_, Option<String>>("source_type")?
+            .and_then(|source| source.parse().ok()),
+        source_ref: row.get("source_ref")?,
+        ingested_by: row.get("ingested_by")?,
+    })
+}
+
+/// Fill in the `explanation` of a page of recall results that starts at
+/// `offset`; `relevance` holds full-text scores and `similarity` semantic
+/// ones, by memory ID
+fn explain_ranking(
+    memories: &mut [MemoryItem],
+    offset: usize,
+    ranked_by: RankedBy,
+    relevance: Option<&HashMap<String, f64>>,
+    similarity: Option<&HashMap<String, f32>>,
+) {
+    let now = Utc::now();
+    for (index, memory) in memories.iter_mut().enumerate() {
+        memory.explanation = Some(RecallExplanation {
+            position: offset + index + 1,
+            ranked_by,
+            age_hours: (now - memory.created_at).num_seconds() as f64 / 3600.0,
+            importance: memory.importance,
+            text_relevance: relevance.and_then(|relevance| relevance.get(&memory.id).copied()),
+            fuzzy_similarity: match ranked_by {
+                RankedBy::FuzzySimilarity => memory
+                    .match_score
+                    .map(|score| score / fuzzy::FUZZY_SCORE_WEIGHT),
+                RankedBy::Recency | RankedBy::Semantic => None,
+            },
+            semantic_similarity: similarity
+                .map(|similarity| similarity.get(&memory.id).copied().unwrap_or(0.0)),
+        });
+    }
+}
+
+/// Negated bm25 score of `match_query` against each of `memories`, by ID
+fn text_relevance(
+    conn: &rusqlite::Connection,
+    match_query: &str,
+    memories: &[MemoryItem],
+) -> Result<HashMap<String, f64>> {
+    let ids = serde_json::to_string(
+        &memories
+            .iter()
+            .map(|memory| memory.id.as_str())
+            .collect::<Vec<_>>(),
+    )?;
+    let mut stmt = conn.prepare(
+        r#"
+        SELECT m.id, -bm25(memories_fts)
+        FROM memories_fts
+        INNER JOIN memories m ON m.rowid = memories_fts.rowid
+        WHERE memories_fts MATCH ?1 AND m.id IN (SELECT value FROM json_each(?2))
+        "#,
+    )?;
+    let rows = stmt.query_map(rusqlite::params![match_query, ids], |row| {
+        Ok((row.get(0)?, row.get(1)?))
+    })?;
+    Ok(rows.collect::<rusqlite::Result<_>>()?)
+}
+
+/// JSON path of top-level `key`, quoted so keys with dots or spaces work
+fn json_key_path(key: &str) -> String {
+    format!("$.\"{}\"", key)
+}
+
+/// What a metadata migration does to one memory
+enum MetadataChange {
+    Unchanged,
+    Changed,
+    /// The target key already holds a different value
+    Conflict,
+}
+
+/// Apply `migration` to one memory's `metadata`, moving the value under
+/// `from_key` to `to_key`
+fn migrate_metadata_entry(
+    metadata: &mut HashMap<String, String>,
+    migration: &MetadataMigration,
+    to_key: &str,
+) -> MetadataChange {
+    let Some(value) = metadata.get(&migration.from_key) else {
+        return MetadataChange::Unchanged;
+    };
+    let mapped = if migration.from_key == TAGS_METADATA_KEY {
+        // Untouched tag lists keep their spacing
+        let tags: Vec<&str> = value.split(',').map(str::trim).collect();
+        if tags
+            .iter()
+            .any(|tag| migration.value_map.contains_key(*tag))
+        {
+            tags.iter()
+                .map(|tag| migration.value_map.get(*tag).map_or(*tag, String::as_str))
+                .collect::<Vec<_>>()
+                .join(", ")
+        } else {
+            value.clone()
+        }
+    } else {
+        migration
+            .value_map
+            .get(value)
+            .cloned()
+            .unwrap_or_else(|| value.clone())
+    };
+
+    if to_key != migration.from_key {
+        if metadata
+            .get(to_key)
+            .is_some_and(|existing| *existing != mapped)
+        {
+            return MetadataChange::Conflict;
+        }
+        metadata.remove(&migration.from_key);
+    } else if *value == mapped {
+        return MetadataChange::Unchanged;
+    }
+    metadata.insert(to_key.to_string(), mapped);
+    MetadataChange::Changed
+}
+
+/// Map a `json_schemas` row
+fn row_to_namespace_schema(row: &rusqlite::Row) -> rusqlite::Result<NamespaceSchema> {
+    let schema: String = row.get("schema")?;
+    Ok(NamespaceSchema {
+        namespace: row.get("namespace")?,
+        schema: serde_json::from_str(&schema).unwrap_or_default(),
+        updated_at: row.get("updated_at")?,
+    })
+}
+
+/// Map a `templates` row
+fn row_to_template(row: &rusqlite::Row) -> rusqlite::Result<MemoryTemplate> {
+    Ok(MemoryTemplate {
+        id: row.get("id")?,
+        content: row.get("content")?,
+        metadata: serde_json::from_str(&row.get::<_, String>("metadata")?).unwrap_or_default(),
+        importance: row.get("importance")?,
+        ttl_hours: row.get("ttl_hours")?,
+    })
+}
+
+/// Session summaries matching `condition`, in its order
+fn query_session_summaries(
+    conn: &rusqlite::Connection,
+    condition: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<SessionSummary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT session_id, user_id, summary_text, key_topics, memory_count,
+                date_range_start, date_range_end, importance_score, memory_id, version,
+                generator, generated_at, stale, created_at, updated_at
+         FROM session_summaries {}",
+        condition
+    ))?;
+    let summaries = stmt
+        .query_map(params, row_to_session_summary)?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(summaries)
+}
+
+/// Map a `session_summaries` row
+fn row_to_session_summary(row: &rusqlite::Row) -> rusqlite::Result<SessionSummary> {
+    let key_topics: Option<String> = row.get("key_topics")?;
+    let updated_at: DateTime<Utc> = row.get("updated_at")?;
+    Ok(SessionSummary {
+        session_id: row.get("session_id")?,
+        user_id: row.get("user_id")?,
+        summary_text: row.get("summary_text")?,
+        key_topics: key_topics
+            .and_then(|topics| serde_json::from_str(&topics).ok())
+            .unwrap_or_default(),
+        memory_count: row.get::<_, i64>("memory_count")? as usize,
+        date_range: (row.get("date_range_start")?, row.get("date_range_end")?),
+        importance_score: row.get("importance_score")?,
+        created_at: row.get("created_at")?,
+        updated_at,
+        version: row.get::<_, i64>("version")? as u32,
+        generator: row
+            .get::<_, String>("generator")?
+            .parse()
+            .unwrap_or_default(),
+        generated_at: row
+            .get::<_, Option<DateTime<Utc>>>("generated_at")?
+            .unwrap_or(updated_at),
+        stale: row.get("stale")?,
+        memory_id: row.get("memory_id")?,
+    })
+}
+
+/// Day and topic summaries matching `condition`, oldest first
+fn query_summaries(
+    conn: &rusqlite::Connection,
+    condition: &str,
+    params: impl rusqlite::Params,
+) -> Result<Vec<Summary>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT user_id, granularity, key, summary_text, key_topics, memory_count,
+                date_range_start, date_range_end, importance_score, version, generator,
+                generated_at, stale
+         FROM summaries {}
+         ORDER BY date_range_start, key",
+        condition
+    ))?;
+    let summaries = stmt
+        .query_map(params, |row| {
+            let key_topics: Option<String> = row.get("key_topics")?;
+            Ok(Summary {
+                user_id: row.get("user_id")?,
+                granularity: row
+                    .get::<_, String>("granularity")?
+                    .parse()
+                    .unwrap_or(SummaryGranularity::Day),
+                key: row.get("key")?,
+                summary_text: row.get("summary_text")?,
+                key_topics: key_topics
+                    .and_then(|topics| serde_json::from_str(&topics).ok())
+                    .unwrap_or_default(),
+                memory_count: row.get::<_, i64>("memory_count")? as usize,
+                date_range: (row.get("date_range_start")?, row.get("date_range_end")?),
+                importance_score: row.get("importance_score")?,
+                version: row.get::<_, i64>("version")? as u32,
+                generator: row
+                    .get::<_, String>("generator")?
+                    .parse()
+                    .unwrap_or_default(),
+                generated_at: row.get("generated_at")?,
+                stale: row.get("stale")?,
+            })
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(summaries)
+}
+
 impl Clone for Database {
     fn clone(&self) -> Self {
         Self {
@@ -618,6 +4625,12 @@ impl Clone for Database {
             read_pools: self.read_pools.clone(),
             config: self.config.clone(),
             read_replica_index: std::sync::atomic::AtomicUsize::new(0),
+            id_generator: self.id_generator.clone(),
+            instance: self.instance.clone(),
+            wal_archiver: self.wal_archiver.clone(),
+            sealer: self.sealer.clone(),
+            query_shapes: self.query_shapes.clone(),
+            hot_tier: self.hot_tier.clone(),
         }
     }
 }
@@ -629,6 +4642,25 @@ pub struct DatabasePoolStatus {
     pub read_pools: Vec<pool::PoolStatus>,
 }
 
+/// Result of [`Database::check_fts_consistency`]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FtsConsistency {
+    pub memory_rows: usize,
+    pub indexed_rows: usize,
+    /// Memories with no index entry
+    pub missing: usize,
+    /// Index entries whose memory no longer exists
+    pub orphaned: usize,
+    /// Whether FTS5's own integrity check agrees with the content table
+    pub integrity_ok: bool,
+}
+
+impl FtsConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.missing == 0 && self.orphaned == 0 && self.integrity_ok
+    }
+}
+
 impl DatabasePoolStatus {
     pub fn is_healthy(&self) -> bool {
         self.write_pool.is_healthy() && self.read_pools.iter().all(|p| p.is_healthy())
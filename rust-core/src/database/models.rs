@@ -7,6 +7,7 @@ use validator::{Validate, ValidationError};
 
 /// A memory item stored in the database with vector support
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[serde(default)]
 pub struct MemoryItem {
     pub id: String,
 
@@ -19,12 +20,16 @@ pub struct MemoryItem {
     #[validate(length(min = 1, max = 1000000))] // 1MB max content
     pub content: String,
 
-    pub content_vector: Option<String>, // For future vector embeddings
+    /// How `content` is formatted; JSON content is checked on save
+    pub content_type: ContentType,
 
-    // Vector embedding fields (only available with vector-search feature)
+    // Vector embedding fields (only available with vector-search feature).
+    // Recall leaves them empty unless `QueryFilter::include_embeddings` is set.
     #[cfg(feature = "vector-search")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub embedding: Option<Vec<f32>>, // Vector embedding
     #[cfg(feature = "vector-search")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
     pub embedding_model: Option<String>, // Model used for embedding
 
     #[serde(default)]
@@ -37,6 +42,13 @@ pub struct MemoryItem {
     #[validate(range(min = 0.0, max = 1.0))]
     pub importance: f32,
 
+    /// How likely the memory is to be true, apart from how much it matters
+    /// (`importance`); decay ages less confident memories faster (see
+    /// `DecayPolicy::confidence_threshold`)
+    #[validate(range(min = 0.0, max = 1.0))]
+    #[serde(default = "default_confidence")]
+    pub confidence: f32,
+
     #[validate(range(min = 1, max = 8760))] // Max 1 year TTL
     pub ttl_hours: Option<u32>,
 
@@ -44,6 +56,55 @@ pub struct MemoryItem {
 
     #[serde(default)]
     pub compressed_from: Vec<String>, // IDs of original memories if this is compressed
+
+    /// Incremented by every update; pass it back as
+    /// `MemoryUpdate::expected_version` to detect concurrent edits
+    pub version: i64,
+
+    /// Position within the session: one more than the session's highest
+    /// when first saved, changed only by `Database::move_memory`
+    #[serde(default)]
+    pub seq: i64,
+
+    /// Only active memories are recalled and searched unless
+    /// `QueryFilter::states` asks for others; saved as given, changed
+    /// afterwards only by `Database::set_memory_state`
+    #[serde(default)]
+    pub state: MemoryState,
+
+    /// Who or what the memory came from; `None` for memories saved before
+    /// provenance was recorded
+    #[serde(default)]
+    pub source_type: Option<SourceType>,
+
+    /// Where in that source: a URL, message ID or file path
+    #[validate(length(min = 1, max = 2048))]
+    #[serde(default)]
+    pub source_ref: Option<String>,
+
+    /// Agent, tool or API key that saved the memory. Saves through an FFI
+    /// instance opened with an API key always record the key's name here.
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default)]
+    pub ingested_by: Option<String>,
+
+    /// Makes a save safe to retry: saving again with a key the same user
+    /// used within `DatabaseConfig::idempotency_key_ttl_hours` writes
+    /// nothing and returns the memory the first save made. Read only on
+    /// save, never stored on the memory.
+    #[validate(length(min = 1, max = 255))]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub idempotency_key: Option<String>,
+
+    /// Set by keyword recall: 1.0 for an exact full-text hit, lower for a
+    /// fuzzy one (see `QueryFilter::fuzzy`); never stored
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub match_score: Option<f32>,
+
+    /// Why recall put the memory where it is; set only when
+    /// `QueryFilter::explain` is, never stored
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<RecallExplanation>,
 }
 
 impl Default for MemoryItem {
@@ -53,7 +114,7 @@ impl Default for MemoryItem {
             user_id: String::new(),
             session_id: String::new(),
             content: String::new(),
-            content_vector: None,
+            content_type: ContentType::Text,
             #[cfg(feature = "vector-search")]
             embedding: None,
             #[cfg(feature = "vector-search")]
@@ -63,15 +124,459 @@ impl Default for MemoryItem {
             updated_at: Utc::now(),
             expires_at: None,
             importance: 0.5,
+            confidence: 1.0,
+            source_type: None,
+            source_ref: None,
+            ingested_by: None,
+            idempotency_key: None,
             ttl_hours: None,
             is_compressed: false,
             compressed_from: Vec::new(),
+            version: 1,
+            seq: 0,
+            state: MemoryState::Active,
+            match_score: None,
+            explanation: None,
+        }
+    }
+}
+
+fn default_confidence() -> f32 {
+    1.0
+}
+
+impl MemoryItem {
+    /// Whether this is a session's stored summary, which outlives the
+    /// memories it summarizes
+    pub fn is_session_summary(&self) -> bool {
+        self.metadata.get(TYPE_METADATA_KEY).map(String::as_str) == Some(SESSION_SUMMARY_TYPE)
+    }
+
+    /// Tokens the content takes up, by the process's tokenizer; see
+    /// [`crate::core::tokens`]
+    pub fn count_tokens(&self) -> usize {
+        crate::core::tokens::count_tokens(&self.content)
+    }
+
+    /// Namespace the memory belongs to: its `namespace` metadata, or
+    /// [`DEFAULT_NAMESPACE`]
+    pub fn namespace(&self) -> &str {
+        self.metadata
+            .get(NAMESPACE_METADATA_KEY)
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_NAMESPACE)
+    }
+}
+
+/// Where a memory is in its lifecycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MemoryState {
+    /// Staged, waiting to be confirmed before it is recalled
+    Draft,
+    #[default]
+    Active,
+    /// Kept, but left out of recall and search unless asked for
+    Archived,
+}
+
+impl MemoryState {
+    pub const ALL: [MemoryState; 3] = [Self::Draft, Self::Active, Self::Archived];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Draft => "draft",
+            Self::Active => "active",
+            Self::Archived => "archived",
+        }
+    }
+
+    /// Whether a memory may move from this state to `to`: drafts are
+    /// confirmed or discarded into the archive, active memories archived and
+    /// archived ones revived, but nothing goes back to being a draft
+    pub fn can_become(self, to: MemoryState) -> bool {
+        matches!(
+            (self, to),
+            (Self::Draft, Self::Active)
+                | (Self::Draft, Self::Archived)
+                | (Self::Active, Self::Archived)
+                | (Self::Archived, Self::Active)
+        ) || self == to
+    }
+}
+
+impl std::fmt::Display for MemoryState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for MemoryState {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "draft" => Ok(Self::Draft),
+            "active" => Ok(Self::Active),
+            "archived" => Ok(Self::Archived),
+            _ => Err(format!(
+                "unknown memory state `{}`; expected draft, active or archived",
+                value
+            )),
         }
     }
 }
 
+/// What put recall results in their order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RankedBy {
+    /// Newest first, then most important, then by ID. Full-text matches
+    /// are ordered this way too; their relevance is reported, not used.
+    Recency,
+    /// Fuzzy fallback: most similar spelling first, then as `Recency`
+    FuzzySimilarity,
+    /// `QueryFilter::semantic_query`: embedding similarity blended with
+    /// full-text relevance, best first
+    Semantic,
+}
+
+/// The values that decided a recalled memory's position
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecallExplanation {
+    /// 1-based position in the whole result, not just this page
+    pub position: usize,
+    pub ranked_by: RankedBy,
+    /// Hours since the memory was created, the first key for `Recency`
+    pub age_hours: f64,
+    /// Second key for `Recency`
+    pub importance: f32,
+    /// Negated FTS5 bm25 score of a full-text match, higher for a better
+    /// match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text_relevance: Option<f64>,
+    /// Spelling similarity of a fuzzy match, before it is weighted into
+    /// `MemoryItem::match_score`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fuzzy_similarity: Option<f32>,
+    /// Embedding similarity to the semantic query, before it is blended
+    /// into `MemoryItem::match_score`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub semantic_similarity: Option<f32>,
+}
+
+/// Kind of source a memory came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceType {
+    /// Stated by the user
+    User,
+    /// Inferred or written by an agent
+    Agent,
+    /// Returned by a tool call
+    Tool,
+    /// Brought in from another store or an export
+    Import,
+}
+
+impl SourceType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::User => "user",
+            Self::Agent => "agent",
+            Self::Tool => "tool",
+            Self::Import => "import",
+        }
+    }
+}
+
+impl std::fmt::Display for SourceType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SourceType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "user" => Ok(Self::User),
+            "agent" => Ok(Self::Agent),
+            "tool" => Ok(Self::Tool),
+            "import" => Ok(Self::Import),
+            _ => Err(format!(
+                "unknown source type `{}`; expected user, agent, tool or import",
+                value
+            )),
+        }
+    }
+}
+
+/// How one memory relates to another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LinkType {
+    /// The two memories state opposite things
+    Contradicts,
+    /// Reviewed as a possible conflict and found not to be one
+    Compatible,
+}
+
+impl LinkType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Contradicts => "contradicts",
+            Self::Compatible => "compatible",
+        }
+    }
+}
+
+impl std::fmt::Display for LinkType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for LinkType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "contradicts" => Ok(Self::Contradicts),
+            "compatible" => Ok(Self::Compatible),
+            _ => Err(format!(
+                "unknown link type `{}`; expected contradicts or compatible",
+                value
+            )),
+        }
+    }
+}
+
+/// A typed link between two memories of the same user. Links are
+/// symmetric and stored once, with `source_id` the smaller ID.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryLink {
+    pub source_id: String,
+    pub target_id: String,
+    pub link_type: LinkType,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Metadata key naming a memory's namespace
+pub const NAMESPACE_METADATA_KEY: &str = "namespace";
+
+/// Namespace of memories without `namespace` metadata
+pub const DEFAULT_NAMESPACE: &str = "default";
+
+/// Format of a memory's content
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentType {
+    #[default]
+    Text,
+    /// A JSON document, validated against its namespace's schema if one is
+    /// registered
+    Json,
+    Markdown,
+}
+
+impl ContentType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Text => "text",
+            Self::Json => "json",
+            Self::Markdown => "markdown",
+        }
+    }
+}
+
+impl std::fmt::Display for ContentType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for ContentType {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "text" => Ok(Self::Text),
+            "json" => Ok(Self::Json),
+            "markdown" | "md" => Ok(Self::Markdown),
+            _ => Err(format!(
+                "unknown content type `{}`; expected text, json or markdown",
+                value
+            )),
+        }
+    }
+}
+
+/// What a save wrote, so callers don't need to read the memory back
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SaveReceipt {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    /// When the row was written; also the memory's `updated_at`
+    pub written_at: DateTime<Utc>,
+    /// The id already existed, so the save replaced that memory instead of
+    /// adding a row
+    pub deduplicated: bool,
+    /// The save opened a session that didn't exist yet
+    pub session_created: bool,
+    /// Content bytes stored, as counted against the byte quota
+    pub bytes_written: usize,
+    /// The save's idempotency key was already used: nothing was written and
+    /// `id` is the memory the first save made
+    #[serde(default)]
+    pub replayed: bool,
+}
+
+/// JSON Schema that JSON memories of a namespace must match
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NamespaceSchema {
+    pub namespace: String,
+    pub schema: serde_json::Value,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Two words keyword search treats as interchangeable within a namespace
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Synonym {
+    pub namespace: String,
+    pub term: String,
+    pub synonym: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Memories and content bytes held by a user, or one of their sessions;
+/// see `Database::user_storage`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageUsage {
+    pub user_id: String,
+    /// `None` for a user's total
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub session_id: Option<String>,
+    pub memory_count: i64,
+    pub content_bytes: u64,
+}
+
+/// A user and how much they have stored; see `Database::list_users`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserOverview {
+    pub user_id: String,
+    pub memory_count: i64,
+    pub session_count: i64,
+    pub content_bytes: u64,
+    /// Latest session activity or memory write, if there was any
+    pub last_active: Option<DateTime<Utc>>,
+}
+
+/// A rename of one metadata key and its values across many memories, run by
+/// `Database::migrate_metadata`
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataMigration {
+    /// Only this user's memories; `None` migrates everyone's
+    #[serde(default)]
+    pub user_id: Option<String>,
+    pub from_key: String,
+    /// Key the value moves to; `None` leaves it under `from_key`
+    #[serde(default)]
+    pub to_key: Option<String>,
+    /// Values to replace, old to new; other values move as they are. Under
+    /// the tags key each comma-separated tag is mapped on its own.
+    #[serde(default)]
+    pub value_map: HashMap<String, String>,
+    /// Only count what would change
+    #[serde(default)]
+    pub dry_run: bool,
+}
+
+/// Progress of a metadata migration
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MetadataMigrationProgress {
+    /// Memories with the key looked at so far
+    pub scanned: usize,
+    /// Memories changed so far, or that would be in a dry run
+    pub migrated: usize,
+    /// Memories left alone because the target key already held a different
+    /// value
+    pub conflicts: Vec<String>,
+}
+
+/// What `Database::purge_user` erased
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UserPurge {
+    pub user_id: String,
+    /// IDs of the memories deleted
+    pub memory_ids: Vec<String>,
+    pub sessions: usize,
+    /// Session, day and topic summaries deleted
+    pub summaries: usize,
+}
+
+impl UserPurge {
+    /// Whether there was anything stored about the user
+    pub fn found(&self) -> bool {
+        !self.memory_ids.is_empty() || self.sessions > 0 || self.summaries > 0
+    }
+}
+
+/// Percentiles and histogram of one per-memory value; see
+/// `Database::get_value_distribution`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ValueDistribution {
+    /// All zero when there are no memories
+    pub min: f64,
+    pub p50: f64,
+    pub p90: f64,
+    pub p99: f64,
+    pub max: f64,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// Memories whose value is at least `from` and below `to`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct HistogramBucket {
+    pub from: f64,
+    /// `None` for the last, open-ended bucket
+    pub to: Option<f64>,
+    pub count: u64,
+}
+
+/// When a user saves memories, by UTC time; see `Database::get_user_activity`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserActivity {
+    /// Memories per weekday (Monday first) and hour of day
+    pub heatmap: [[usize; 24]; 7],
+    pub by_hour: [usize; 24],
+    /// Monday first
+    pub by_weekday: [usize; 7],
+    pub avg_importance_by_session: HashMap<String, f32>,
+    /// Longest stretch between two consecutive memories
+    pub longest_gap: Option<ActivityGap>,
+    /// Consecutive days with memories, up to today or yesterday
+    pub current_streak_days: usize,
+    pub longest_streak_days: usize,
+}
+
+/// Time between two consecutive memories
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ActivityGap {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl ActivityGap {
+    pub fn duration(&self) -> chrono::Duration {
+        self.to - self.from
+    }
+}
+
 /// Query filter for searching memories
 #[derive(Debug, Clone, Serialize, Deserialize, Validate)]
+#[validate(schema(function = "validate_content_length_range"))]
 pub struct QueryFilter {
     pub user_id: Option<String>,
     pub session_id: Option<String>,
@@ -87,6 +592,108 @@ pub struct QueryFilter {
 
     #[validate(range(min = 0.0, max = 1.0))]
     pub min_importance: Option<f32>,
+
+    #[validate(range(min = 0.0, max = 1.0))]
+    #[serde(default)]
+    pub min_confidence: Option<f32>,
+
+    /// Only memories at most this confident, to review shaky knowledge
+    #[validate(range(min = 0.0, max = 1.0))]
+    #[serde(default)]
+    pub max_confidence: Option<f32>,
+
+    /// Only memories that expire within this long from now, so they can be
+    /// refreshed in time; whole seconds in JSON
+    #[serde(default, with = "optional_duration_secs")]
+    #[validate(custom = "validate_positive_duration")]
+    pub expiring_within: Option<chrono::Duration>,
+
+    /// Abort the query after this long; `None` uses
+    /// `DatabaseConfig::default_query_timeout_ms`
+    #[validate(range(min = 1, max = 600000))]
+    pub timeout_ms: Option<u64>,
+
+    /// Keyset cursor: only memories after this one in recall order (the
+    /// last ID of the previous page). Cheaper than a large `offset`.
+    pub before_id: Option<String>,
+
+    pub content_type: Option<ContentType>,
+
+    /// Metadata keys that must have exactly these values
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    /// Fields of JSON content that must equal these values, by JSON path
+    /// such as `$.ticker` (a bare name means a top-level field); objects
+    /// only match content that lists their keys in sorted order
+    #[serde(default)]
+    pub json_fields: HashMap<String, serde_json::Value>,
+
+    /// If no memory matches `keywords` exactly, fall back to memories with
+    /// similarly spelled words, ranked by `MemoryItem::match_score`
+    #[serde(default)]
+    pub fuzzy: bool,
+
+    /// Fill in each memory's stored embedding and its model. Off by default
+    /// since embeddings dwarf most memories; without the `vector-search`
+    /// feature this does nothing.
+    #[serde(default)]
+    pub include_embeddings: bool,
+
+    /// Lifecycle states to recall; empty means only active memories
+    #[serde(default)]
+    pub states: Vec<MemoryState>,
+
+    /// Fill in each memory's `explanation` of its ranking
+    #[serde(default)]
+    pub explain: bool,
+
+    /// Only memories from one of these kinds of source; empty means any
+    #[serde(default)]
+    pub source_types: Vec<SourceType>,
+
+    /// Only memories with exactly this `source_ref`
+    #[serde(default)]
+    pub source_ref: Option<String>,
+
+    /// Only memories saved by this agent, tool or API key
+    #[serde(default)]
+    pub ingested_by: Option<String>,
+
+    /// Rank by meaning: with vector search and a query embedder, the text
+    /// is embedded and memories are ordered by similarity blended with its
+    /// full-text relevance, instead of by recency. Without them its words
+    /// are searched as `keywords` when none are given. Can't be combined
+    /// with `before_id`.
+    #[validate(length(min = 1, max = 1000))]
+    #[serde(default)]
+    pub semantic_query: Option<String>,
+
+    /// Leave out memories containing any of these words
+    #[validate(length(max = 100))]
+    #[serde(default)]
+    pub exclude_keywords: Vec<String>,
+
+    /// Leave out memories from these sessions
+    #[validate(length(max = 100))]
+    #[serde(default)]
+    pub exclude_session_ids: Vec<String>,
+
+    /// Leave out memories with any of these tags
+    #[validate(length(max = 100))]
+    #[serde(default)]
+    pub exclude_tags: Vec<String>,
+
+    /// Only memories whose content is at least this many UTF-8 bytes, e.g.
+    /// to skip one-word fragments
+    #[serde(default)]
+    pub min_content_length: Option<usize>,
+
+    /// Only memories whose content is at most this many UTF-8 bytes, e.g.
+    /// to skip pasted blobs too big for a prompt
+    #[validate(range(min = 1))]
+    #[serde(default)]
+    pub max_content_length: Option<usize>,
 }
 
 impl Default for QueryFilter {
@@ -100,8 +707,71 @@ impl Default for QueryFilter {
             limit: Some(50), // Default page size
             offset: Some(0),
             min_importance: None,
+            min_confidence: None,
+            max_confidence: None,
+            expiring_within: None,
+            timeout_ms: None,
+            before_id: None,
+            content_type: None,
+            metadata: HashMap::new(),
+            json_fields: HashMap::new(),
+            fuzzy: false,
+            include_embeddings: false,
+            states: Vec::new(),
+            explain: false,
+            source_types: Vec::new(),
+            source_ref: None,
+            ingested_by: None,
+            semantic_query: None,
+            exclude_keywords: Vec::new(),
+            exclude_session_ids: Vec::new(),
+            exclude_tags: Vec::new(),
+            min_content_length: None,
+            max_content_length: None,
+        }
+    }
+}
+
+fn validate_content_length_range(filter: &QueryFilter) -> Result<(), ValidationError> {
+    if let (Some(min), Some(max)) = (filter.min_content_length, filter.max_content_length) {
+        if min > max {
+            let mut error = ValidationError::new("content_length");
+            error.message = Some(
+                format!(
+                    "min_content_length ({}) must not exceed max_content_length ({})",
+                    min, max
+                )
+                .into(),
+            );
+            return Err(error);
         }
     }
+    Ok(())
+}
+
+fn validate_positive_duration(duration: &chrono::Duration) -> Result<(), ValidationError> {
+    if *duration <= chrono::Duration::zero() {
+        return Err(ValidationError::new("duration must be positive"));
+    }
+    Ok(())
+}
+
+/// `Option<chrono::Duration>` as whole seconds
+mod optional_duration_secs {
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer>(
+        duration: &Option<chrono::Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration.map(|d| d.num_seconds()).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<chrono::Duration>, D::Error> {
+        Ok(Option::<i64>::deserialize(deserializer)?.map(chrono::Duration::seconds))
+    }
 }
 
 /// Paginated response wrapper
@@ -138,12 +808,64 @@ impl<T> PaginatedResponse<T> {
     }
 }
 
+/// Where a session is in its lifecycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionStatus {
+    #[default]
+    Active,
+    /// Finished, but still listed and searched
+    Closed,
+    /// Left out of session listings and searches unless asked for; saving
+    /// a memory into the session makes it active again
+    Archived,
+}
+
+impl SessionStatus {
+    /// Statuses listed when no status is asked for
+    pub const LISTED: [SessionStatus; 2] = [Self::Active, Self::Closed];
+
+    pub const ALL: [SessionStatus; 3] = [Self::Active, Self::Closed, Self::Archived];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Active => "active",
+            Self::Closed => "closed",
+            Self::Archived => "archived",
+        }
+    }
+}
+
+impl std::fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SessionStatus {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "active" | "open" => Ok(Self::Active),
+            "closed" => Ok(Self::Closed),
+            "archived" => Ok(Self::Archived),
+            _ => Err(format!(
+                "unknown session status `{}`; expected active, closed or archived",
+                value
+            )),
+        }
+    }
+}
+
 /// Session information
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
     pub user_id: String,
     pub name: Option<String>,
+    #[serde(default)]
+    pub status: SessionStatus,
     pub created_at: DateTime<Utc>,
     pub last_active: DateTime<Utc>,
     pub memory_count: usize,
@@ -151,6 +873,57 @@ pub struct Session {
     pub metadata: HashMap<String, String>,
 }
 
+/// Search hits within one session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchGroup {
+    pub session: Session,
+    /// Matching memories in the session, possibly more than `memories`
+    pub match_count: usize,
+    /// Newest matches first, at most the requested per-session limit
+    pub memories: Vec<MemoryItem>,
+}
+
+/// What wrote a session summary's text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryGenerator {
+    /// Picked out of the memories by key topics and importance
+    #[default]
+    Heuristic,
+    /// Written by a language model and handed in by the caller
+    Llm,
+}
+
+impl SummaryGenerator {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Heuristic => "heuristic",
+            Self::Llm => "llm",
+        }
+    }
+}
+
+impl std::fmt::Display for SummaryGenerator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SummaryGenerator {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "heuristic" => Ok(Self::Heuristic),
+            "llm" => Ok(Self::Llm),
+            _ => Err(format!(
+                "unknown summary generator `{}`; expected heuristic or llm",
+                value
+            )),
+        }
+    }
+}
+
 /// Session summary
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SessionSummary {
@@ -158,11 +931,132 @@ pub struct SessionSummary {
     pub user_id: String,
     pub summary_text: String,
     pub key_topics: Vec<String>,
+    /// Memories the summary was generated from
     pub memory_count: usize,
     pub date_range: (DateTime<Utc>, DateTime<Utc>),
     pub importance_score: f32,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// Times the summary has been stored for its session; 0 until it is
+    #[serde(default)]
+    pub version: u32,
+    #[serde(default)]
+    pub generator: SummaryGenerator,
+    #[serde(default = "Utc::now")]
+    pub generated_at: DateTime<Utc>,
+    /// The session's memories changed after the summary was stored
+    #[serde(default)]
+    pub stale: bool,
+    /// The memory holding the summary in its session, if decay stored one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub memory_id: Option<String>,
+}
+
+/// How much a summary covers
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SummaryGranularity {
+    /// One summary per session
+    Session,
+    /// One per day a user saved memories, across their sessions
+    Day,
+    /// One per topic cluster among a user's recent memories
+    Topic,
+}
+
+impl SummaryGranularity {
+    /// Finest first; each level covers a user's memories in fewer, broader
+    /// summaries than the one before it
+    pub const LEVELS: [SummaryGranularity; 3] = [Self::Session, Self::Day, Self::Topic];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Session => "session",
+            Self::Day => "day",
+            Self::Topic => "topic",
+        }
+    }
+}
+
+impl std::fmt::Display for SummaryGranularity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for SummaryGranularity {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.to_ascii_lowercase().as_str() {
+            "session" => Ok(Self::Session),
+            "day" => Ok(Self::Day),
+            "topic" => Ok(Self::Topic),
+            _ => Err(format!(
+                "unknown summary granularity `{}`; expected session, day or topic",
+                value
+            )),
+        }
+    }
+}
+
+/// A stored summary at any granularity
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Summary {
+    pub user_id: String,
+    pub granularity: SummaryGranularity,
+    /// The session ID, the day as YYYY-MM-DD (UTC) or the topic's label
+    pub key: String,
+    pub summary_text: String,
+    pub key_topics: Vec<String>,
+    pub memory_count: usize,
+    pub date_range: (DateTime<Utc>, DateTime<Utc>),
+    pub importance_score: f32,
+    pub version: u32,
+    pub generator: SummaryGenerator,
+    pub generated_at: DateTime<Utc>,
+    /// Memories it covers changed after it was stored
+    pub stale: bool,
+}
+
+impl Summary {
+    /// Tokens the summary text takes up, by the process's tokenizer
+    pub fn count_tokens(&self) -> usize {
+        crate::core::tokens::count_tokens(&self.summary_text)
+    }
+}
+
+impl From<SessionSummary> for Summary {
+    fn from(summary: SessionSummary) -> Self {
+        Self {
+            user_id: summary.user_id,
+            granularity: SummaryGranularity::Session,
+            key: summary.session_id,
+            summary_text: summary.summary_text,
+            key_topics: summary.key_topics,
+            memory_count: summary.memory_count,
+            date_range: summary.date_range,
+            importance_score: summary.importance_score,
+            version: summary.version,
+            generator: summary.generator,
+            generated_at: summary.generated_at,
+            stale: summary.stale,
+        }
+    }
+}
+
+/// Summaries picked to fit a token budget
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarySelection {
+    /// The finest granularity whose summaries fit, or the coarsest stored
+    /// one when none do
+    pub granularity: SummaryGranularity,
+    /// Most important first; trimmed to the budget when even the coarsest
+    /// level doesn't fit
+    pub summaries: Vec<Summary>,
+    pub tokens: usize,
+    /// Whether summaries were left out to stay within the budget
+    pub truncated: bool,
 }
 
 /// Compressed memory metadata
@@ -194,6 +1088,24 @@ pub struct DecayPolicy {
 
     pub compression_enabled: bool,
     pub auto_summarize_sessions: bool,
+
+    /// Archive sessions with no activity for this many days; `None` never
+    /// archives automatically
+    #[serde(default)]
+    #[validate(range(min = 1, max = 3650))]
+    pub archive_sessions_after_days: Option<u32>,
+
+    /// Memories less confident than this age faster: they count as old
+    /// after `max_age_hours` scaled by `confidence / confidence_threshold`,
+    /// so a memory at half the threshold expires at half the age. 0 turns
+    /// this off.
+    #[serde(default = "default_confidence_threshold")]
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub confidence_threshold: f32,
+}
+
+fn default_confidence_threshold() -> f32 {
+    0.5
 }
 
 impl Default for DecayPolicy {
@@ -204,8 +1116,124 @@ impl Default for DecayPolicy {
             max_memories_per_user: 10000,
             compression_enabled: true,
             auto_summarize_sessions: true,
+            archive_sessions_after_days: None,
+            confidence_threshold: default_confidence_threshold(),
+        }
+    }
+}
+
+/// Metadata key that selects a memory's retention rule
+pub const CATEGORY_METADATA_KEY: &str = "category";
+
+/// Metadata key holding a memory's comma-separated tags
+pub const TAGS_METADATA_KEY: &str = "tags";
+
+/// Metadata key marking memories generated by decay, such as compressed
+/// memories and session summaries
+pub const TYPE_METADATA_KEY: &str = "type";
+
+/// `type` metadata of the memory holding a session's stored summary
+pub const SESSION_SUMMARY_TYPE: &str = "session_summary";
+
+/// Retention override for memories whose metadata `category` matches
+///
+/// The decay process expires such memories by their rule alone, ignoring the
+/// policy's age and importance threshold, and never compresses them. TTLs set
+/// on individual memories still apply.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct RetentionRule {
+    #[validate(length(min = 1, max = 100))]
+    pub category: String,
+
+    /// Expire the category's memories once they are this old; `None` keeps
+    /// them forever
+    #[validate(range(min = 1, max = 87600))] // 1 hour to 10 years
+    pub max_age_hours: Option<u32>,
+}
+
+impl RetentionRule {
+    pub fn never_expire(&self) -> bool {
+        self.max_age_hours.is_none()
+    }
+}
+
+/// Layout for memories that are saved over and over with different details
+///
+/// `content` and metadata values may contain `{{name}}` placeholders,
+/// filled in by `MemoryManager::save_from_template`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct MemoryTemplate {
+    #[validate(length(min = 1, max = 100))]
+    pub id: String,
+
+    #[validate(length(min = 1, max = 10000))]
+    pub content: String,
+
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    #[validate(range(min = 0.0, max = 1.0))]
+    #[serde(default = "default_template_importance")]
+    pub importance: f32,
+
+    #[validate(range(min = 1, max = 8760))]
+    #[serde(default)]
+    pub ttl_hours: Option<u32>,
+}
+
+fn default_template_importance() -> f32 {
+    0.5
+}
+
+impl MemoryTemplate {
+    /// Placeholder names used in the content and metadata, sorted and
+    /// without duplicates
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = std::iter::once(self.content.as_str())
+            .chain(self.metadata.values().map(String::as_str))
+            .flat_map(placeholder_names)
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+}
+
+/// Split `text` at its `{{name}}` placeholders: literal text and names
+/// alternate, starting and ending with literal text. Braces around anything
+/// other than a name made of letters, digits, `_`, `-` and `.` stay literal.
+pub fn split_placeholders(text: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut literal_start = 0;
+    let mut rest = 0;
+    while let Some(open) = text[rest..].find("{{").map(|at| rest + at) {
+        let Some(close) = text[open + 2..].find("}}").map(|at| open + 2 + at) else {
+            break;
+        };
+        let name = text[open + 2..close].trim();
+        let valid = !name.is_empty()
+            && name
+                .chars()
+                .all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'));
+        if valid {
+            parts.push(&text[literal_start..open]);
+            parts.push(name);
+            literal_start = close + 2;
+            rest = close + 2;
+        } else {
+            rest = open + 2;
         }
     }
+    parts.push(&text[literal_start..]);
+    parts
+}
+
+fn placeholder_names(text: &str) -> impl Iterator<Item = String> + '_ {
+    split_placeholders(text)
+        .into_iter()
+        .skip(1)
+        .step_by(2)
+        .map(str::to_string)
 }
 
 /// Decay process statistics
@@ -217,18 +1245,65 @@ pub struct DecayStats {
     pub memories_expired: usize,
     pub memories_compressed: usize,
     pub sessions_summarized: usize,
+    #[serde(default)]
+    pub sessions_archived: usize,
     pub total_memories_before: usize,
     pub total_memories_after: usize,
+    /// Content, metadata, embedding and estimated full-text index bytes of
+    /// the memories removed, less what their compressed replacements take
     pub storage_saved_bytes: usize,
+    /// Drop in bytes of database pages in use over the run, as the file
+    /// holds them once the WAL is checkpointed; negative when the run grew
+    /// the database, as archiving compressed originals can
+    #[serde(default)]
+    pub physical_bytes_saved: i64,
     pub status: DecayStatus,
     pub error_message: Option<String>,
 }
 
+/// Bytes a set of memories takes up in the database
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Footprint {
+    pub memories: usize,
+    pub content_bytes: u64,
+    pub metadata_bytes: u64,
+    /// Stored embedding vectors
+    pub embedding_bytes: u64,
+}
+
+impl Footprint {
+    pub fn total_bytes(&self) -> u64 {
+        self.content_bytes + self.metadata_bytes + self.embedding_bytes
+    }
+
+    /// What removing `self` and adding `added` in its place frees
+    pub fn less(&self, added: &Footprint) -> Footprint {
+        Footprint {
+            memories: self.memories,
+            content_bytes: self.content_bytes.saturating_sub(added.content_bytes),
+            metadata_bytes: self.metadata_bytes.saturating_sub(added.metadata_bytes),
+            embedding_bytes: self.embedding_bytes.saturating_sub(added.embedding_bytes),
+        }
+    }
+}
+
+impl std::ops::AddAssign for Footprint {
+    fn add_assign(&mut self, other: Footprint) {
+        self.memories += other.memories;
+        self.content_bytes += other.content_bytes;
+        self.metadata_bytes += other.metadata_bytes;
+        self.embedding_bytes += other.embedding_bytes;
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
 pub enum DecayStatus {
     Running,
     Completed,
     Failed,
+    /// Another instance holds the maintenance lock
+    Skipped,
 }
 
 impl std::fmt::Display for DecayStatus {
@@ -237,6 +1312,7 @@ impl std::fmt::Display for DecayStatus {
             DecayStatus::Running => write!(f, "running"),
             DecayStatus::Completed => write!(f, "completed"),
             DecayStatus::Failed => write!(f, "failed"),
+            DecayStatus::Skipped => write!(f, "skipped"),
         }
     }
 }
@@ -249,6 +1325,7 @@ impl std::str::FromStr for DecayStatus {
             "running" => Ok(DecayStatus::Running),
             "completed" => Ok(DecayStatus::Completed),
             "failed" => Ok(DecayStatus::Failed),
+            "skipped" => Ok(DecayStatus::Skipped),
             _ => Err("Invalid decay status"),
         }
     }
@@ -287,21 +1364,65 @@ pub struct ActivityStats {
     pub avg_importance: f32,
 }
 
+/// Size limits for memory content and metadata
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentLimits {
+    /// Maximum content size in bytes (UTF-8)
+    pub max_content_bytes: usize,
+    /// Maximum combined size of metadata keys and values in bytes
+    pub max_metadata_bytes: usize,
+    pub max_metadata_keys: usize,
+}
+
+impl Default for ContentLimits {
+    fn default() -> Self {
+        Self {
+            max_content_bytes: 1_000_000, // 1MB
+            max_metadata_bytes: 10_000,   // 10KB
+            max_metadata_keys: 100,
+        }
+    }
+}
+
 /// Validation helpers
 impl MemoryItem {
     /// Custom validation beyond derive macro
-    pub fn validate_custom(&self) -> Result<(), ValidationError> {
+    ///
+    /// Limit violations carry `field`, `limit` and `actual` params.
+    pub fn validate_custom(&self, limits: &ContentLimits) -> Result<(), ValidationError> {
         // Check content is not just whitespace
         if self.content.trim().is_empty() {
             return Err(ValidationError::new("content_empty"));
         }
 
+        if self.content.len() > limits.max_content_bytes {
+            return Err(limit_error(
+                "content_too_large",
+                "content",
+                limits.max_content_bytes,
+                self.content.len(),
+            ));
+        }
+
+        if self.metadata.len() > limits.max_metadata_keys {
+            return Err(limit_error(
+                "too_many_metadata_keys",
+                "metadata",
+                limits.max_metadata_keys,
+                self.metadata.len(),
+            ));
+        }
+
         // Check metadata size (prevent abuse)
         let metadata_size: usize = self.metadata.iter().map(|(k, v)| k.len() + v.len()).sum();
 
-        if metadata_size > 10000 {
-            // 10KB max metadata
-            return Err(ValidationError::new("metadata_too_large"));
+        if metadata_size > limits.max_metadata_bytes {
+            return Err(limit_error(
+                "metadata_too_large",
+                "metadata",
+                limits.max_metadata_bytes,
+                metadata_size,
+            ));
         }
 
         // Validate compressed_from consistency
@@ -317,6 +1438,14 @@ impl MemoryItem {
     }
 }
 
+fn limit_error(code: &'static str, field: &str, limit: usize, actual: usize) -> ValidationError {
+    let mut error = ValidationError::new(code);
+    error.add_param("field".into(), &field);
+    error.add_param("limit".into(), &limit);
+    error.add_param("actual".into(), &actual);
+    error
+}
+
 impl QueryFilter {
     /// Create a simple filter for user + keywords
     pub fn for_user_with_keywords(user_id: &str, keywords: Vec<String>) -> Self {
@@ -367,6 +1496,7 @@ mod tests {
 
     #[test]
     fn test_memory_item_validation() {
+        let limits = ContentLimits::default();
         let mut memory = MemoryItem {
             user_id: "test_user".to_string(),
             session_id: "test_session".to_string(),
@@ -376,11 +1506,11 @@ mod tests {
 
         // Should validate successfully
         assert!(memory.validate().is_ok());
-        assert!(memory.validate_custom().is_ok());
+        assert!(memory.validate_custom(&limits).is_ok());
 
         // Test empty content
         memory.content = "   ".to_string();
-        assert!(memory.validate_custom().is_err());
+        assert!(memory.validate_custom(&limits).is_err());
 
         // Test invalid importance
         memory.content = "Valid content".to_string();
@@ -390,11 +1520,51 @@ mod tests {
         // Test compressed without originals
         memory.importance = 0.8;
         memory.is_compressed = true;
-        assert!(memory.validate_custom().is_err());
+        assert!(memory.validate_custom(&limits).is_err());
 
         // Fix compressed memory
         memory.compressed_from = vec!["mem1".to_string(), "mem2".to_string()];
-        assert!(memory.validate_custom().is_ok());
+        assert!(memory.validate_custom(&limits).is_ok());
+    }
+
+    #[test]
+    fn test_content_limits() {
+        let limits = ContentLimits {
+            max_content_bytes: 8,
+            max_metadata_bytes: 10,
+            max_metadata_keys: 2,
+        };
+        let mut memory = MemoryItem {
+            user_id: "test_user".to_string(),
+            session_id: "test_session".to_string(),
+            content: "12345678".to_string(),
+            ..Default::default()
+        };
+        assert!(memory.validate_custom(&limits).is_ok());
+
+        // Limits count bytes, not characters
+        memory.content = "日本語".to_string();
+        let error = memory.validate_custom(&limits).unwrap_err();
+        assert_eq!(error.code, "content_too_large");
+        assert_eq!(error.params["field"], "content");
+        assert_eq!(error.params["limit"], 8);
+        assert_eq!(error.params["actual"], 9);
+
+        memory.content = "ok".to_string();
+        for key in ["a", "b", "c"] {
+            memory.metadata.insert(key.to_string(), "1".to_string());
+        }
+        let error = memory.validate_custom(&limits).unwrap_err();
+        assert_eq!(error.code, "too_many_metadata_keys");
+        assert_eq!(error.params["actual"], 3);
+
+        memory.metadata.clear();
+        memory
+            .metadata
+            .insert("key".to_string(), "long value".to_string());
+        let error = memory.validate_custom(&limits).unwrap_err();
+        assert_eq!(error.code, "metadata_too_large");
+        assert_eq!(error.params["actual"], 13);
     }
 
     #[test]
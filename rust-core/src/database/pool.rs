@@ -1,81 +1,447 @@
 //! Database connection pool implementation for better concurrency
 
 use anyhow::{Context, Result};
-use r2d2::{Pool, PooledConnection};
+use r2d2::event::{HandleEvent, ReleaseEvent};
+use r2d2::{HandleError, ManageConnection, Pool, PooledConnection};
 use r2d2_sqlite::SqliteConnectionManager;
-use rusqlite::OpenFlags;
-use std::path::Path;
-use std::time::Duration;
+use rusqlite::{Connection, ErrorCode, LoadExtensionGuard, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::ops::{Deref, DerefMut};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
+use super::slow_query::SlowQueryLog;
 use super::DatabaseConfig;
 
 /// Connection pool wrapper for SQLite
 pub struct ConnectionPool {
-    pool: Pool<SqliteConnectionManager>,
+    pool: Pool<ConnectionManager>,
     config: DatabaseConfig,
+    /// Shared by clones and the pool's handlers so counts cover every user
+    counters: Arc<PoolCounters>,
+    slow_queries: SlowQueryLog,
+}
+
+/// Running totals reported through [`PoolStatus`]
+#[derive(Debug, Default)]
+struct PoolCounters {
+    busy_retries: AtomicU64,
+    recycled_connections: AtomicU64,
+    health_check_failures: AtomicU64,
+    connection_errors: AtomicU64,
+}
+
+/// A pooled SQLite connection plus the bookkeeping for its health checks
+pub struct ManagedConnection {
+    conn: rusqlite::Connection,
+    last_checked: Instant,
+}
+
+impl Deref for ManagedConnection {
+    type Target = rusqlite::Connection;
+
+    fn deref(&self) -> &Self::Target {
+        &self.conn
+    }
+}
+
+impl DerefMut for ManagedConnection {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.conn
+    }
+}
+
+/// Opens connections through `SqliteConnectionManager` and checks them on
+/// checkout
+///
+/// `SELECT 1` runs on every checkout; `PRAGMA quick_check` runs when the
+/// connection hasn't been checked for `health_check_interval`. Connections
+/// that fail are dropped by the pool and replaced.
+#[derive(Debug)]
+pub struct ConnectionManager {
+    inner: SqliteConnectionManager,
+    health_check_interval: Option<Duration>,
+    counters: Arc<PoolCounters>,
+}
+
+impl ConnectionManager {
+    fn check(&self, conn: &mut ManagedConnection) -> rusqlite::Result<()> {
+        conn.query_row("SELECT 1", [], |_| Ok(()))?;
+
+        if let Some(interval) = self.health_check_interval {
+            if conn.last_checked.elapsed() >= interval {
+                let result: String = conn.query_row("PRAGMA quick_check", [], |row| row.get(0))?;
+                if result != "ok" {
+                    return Err(rusqlite::Error::SqliteFailure(
+                        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CORRUPT),
+                        Some(format!("quick_check failed: {}", result)),
+                    ));
+                }
+                conn.last_checked = Instant::now();
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl ManageConnection for ConnectionManager {
+    type Connection = ManagedConnection;
+    type Error = rusqlite::Error;
+
+    fn connect(&self) -> Result<Self::Connection, Self::Error> {
+        Ok(ManagedConnection {
+            conn: self.inner.connect()?,
+            last_checked: Instant::now(),
+        })
+    }
+
+    fn is_valid(&self, conn: &mut Self::Connection) -> Result<(), Self::Error> {
+        self.check(conn).inspect_err(|_| {
+            self.counters
+                .health_check_failures
+                .fetch_add(1, Ordering::Relaxed);
+        })
+    }
+
+    fn has_broken(&self, conn: &mut Self::Connection) -> bool {
+        // A transaction left open (e.g. after a panic) would block every
+        // other writer, so don't hand the connection out again
+        !conn.is_autocommit()
+    }
+}
+
+/// Counts connection closes (lifetime, idle timeout or broken) and errors
+#[derive(Debug)]
+struct PoolEventHandler {
+    counters: Arc<PoolCounters>,
+}
+
+impl HandleEvent for PoolEventHandler {
+    fn handle_release(&self, event: ReleaseEvent) {
+        self.counters
+            .recycled_connections
+            .fetch_add(1, Ordering::Relaxed);
+        tracing::debug!(
+            connection_id = event.connection_id(),
+            age_ms = event.age().as_millis() as u64,
+            "Recycled database connection"
+        );
+    }
+}
+
+impl HandleError<rusqlite::Error> for PoolEventHandler {
+    fn handle_error(&self, error: rusqlite::Error) {
+        self.counters
+            .connection_errors
+            .fetch_add(1, Ordering::Relaxed);
+        tracing::warn!(error = %error, "Database connection error");
+    }
+}
+
+/// Where SQLite keeps temporary tables and indices (`PRAGMA temp_store`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TempStore {
+    /// Whatever SQLite was compiled with, usually a file
+    Default,
+    File,
+    #[default]
+    Memory,
+}
+
+impl TempStore {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TempStore::Default => "DEFAULT",
+            TempStore::File => "FILE",
+            TempStore::Memory => "MEMORY",
+        }
+    }
+}
+
+/// Named sets of connection settings; see `DatabaseConfig::apply_preset`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TuningPreset {
+    /// Small page cache, no memory mapping, temporary data on disk, a
+    /// bounded WAL and two connections, for phones and small containers
+    LowMemory,
+    /// Large cache and memory map, rarer checkpoints and more connections
+    HighThroughput,
+    /// `synchronous = FULL`, so committed writes survive power loss
+    Durable,
+}
+
+impl TuningPreset {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TuningPreset::LowMemory => "low_memory",
+            TuningPreset::HighThroughput => "high_throughput",
+            TuningPreset::Durable => "durable",
+        }
+    }
+}
+
+impl std::fmt::Display for TuningPreset {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for TuningPreset {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().replace('-', "_").as_str() {
+            "low_memory" => Ok(TuningPreset::LowMemory),
+            "high_throughput" => Ok(TuningPreset::HighThroughput),
+            "durable" => Ok(TuningPreset::Durable),
+            _ => Err(anyhow::anyhow!(
+                "Unknown tuning preset '{}' (expected low_memory, high_throughput or durable)",
+                s
+            )),
+        }
+    }
+}
+
+/// How `PRAGMA wal_checkpoint` treats readers and the WAL file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CheckpointMode {
+    /// Copy what it can without waiting for readers or writers
+    Passive,
+    /// Wait for writers, then copy every frame
+    Full,
+    /// Like `Full`, then wait for readers so the next writer restarts the WAL
+    Restart,
+    /// Like `Restart`, then truncate the WAL file to zero bytes
+    #[default]
+    Truncate,
+}
+
+impl CheckpointMode {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            CheckpointMode::Passive => "PASSIVE",
+            CheckpointMode::Full => "FULL",
+            CheckpointMode::Restart => "RESTART",
+            CheckpointMode::Truncate => "TRUNCATE",
+        }
+    }
+}
+
+impl std::fmt::Display for CheckpointMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.as_str().to_lowercase())
+    }
+}
+
+impl std::str::FromStr for CheckpointMode {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "passive" => Ok(CheckpointMode::Passive),
+            "full" => Ok(CheckpointMode::Full),
+            "restart" => Ok(CheckpointMode::Restart),
+            "truncate" => Ok(CheckpointMode::Truncate),
+            _ => Err(anyhow::anyhow!(
+                "Unknown checkpoint mode '{}' (expected passive, full, restart or truncate)",
+                s
+            )),
+        }
+    }
+}
+
+/// Outcome of one `PRAGMA wal_checkpoint`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalCheckpoint {
+    pub mode: CheckpointMode,
+    /// A reader or writer stopped the checkpoint from finishing
+    pub busy: bool,
+    /// Frames in the WAL; -1 when the database isn't in WAL mode
+    pub log_frames: i64,
+    /// Frames copied back into the database; -1 when not in WAL mode
+    pub checkpointed_frames: i64,
+    pub wal_size_before: u64,
+    pub wal_size_after: u64,
+}
+
+/// Size of the WAL file beside the database at `path`; 0 if there is none
+pub fn wal_size_bytes(path: &str) -> u64 {
+    std::fs::metadata(format!("{}-wal", path))
+        .map(|m| m.len())
+        .unwrap_or(0)
+}
+
+/// Run `PRAGMA wal_checkpoint(<mode>)` on `conn`, the database at `path`
+pub fn checkpoint(conn: &Connection, path: &str, mode: CheckpointMode) -> Result<WalCheckpoint> {
+    let wal_size_before = wal_size_bytes(path);
+    let (busy, log_frames, checkpointed_frames) = conn
+        .query_row(
+            &format!("PRAGMA wal_checkpoint({})", mode.as_str()),
+            [],
+            |row| Ok((row.get::<_, i64>(0)? != 0, row.get(1)?, row.get(2)?)),
+        )
+        .context("Failed to checkpoint the WAL")?;
+
+    Ok(WalCheckpoint {
+        mode,
+        busy,
+        log_frames,
+        checkpointed_frames,
+        wal_size_before,
+        wal_size_after: wal_size_bytes(path),
+    })
+}
+
+/// Load `extensions` into `conn` in order
+///
+/// Extension loading is only switched on while they load, so SQL run on
+/// the connection can never call `load_extension()` itself.
+pub fn load_extensions(conn: &Connection, extensions: &[PathBuf]) -> rusqlite::Result<()> {
+    if extensions.is_empty() {
+        return Ok(());
+    }
+
+    // SAFETY: the paths come from the application's own configuration, not
+    // from stored data, and no SQL runs while loading is enabled
+    unsafe {
+        let _guard = LoadExtensionGuard::new(conn)?;
+        for extension in extensions {
+            conn.load_extension(extension, None)?;
+        }
+    }
+    Ok(())
 }
 
 impl ConnectionPool {
     /// Create a new connection pool with the given configuration
     pub fn new(config: DatabaseConfig) -> Result<Self> {
-        // Ensure parent directory exists
-        if let Some(parent) = Path::new(&config.path).parent() {
-            std::fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create database directory: {:?}", parent))?;
-        }
+        // Read-only pools never create anything; the file must already exist
+        let flags = if config.read_only {
+            OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX
+        } else {
+            // Ensure parent directory exists
+            if let Some(parent) = Path::new(&config.path).parent() {
+                std::fs::create_dir_all(parent).with_context(|| {
+                    format!("Failed to create database directory: {:?}", parent)
+                })?;
+            }
 
-        let flags = OpenFlags::SQLITE_OPEN_READ_WRITE
-            | OpenFlags::SQLITE_OPEN_CREATE
-            | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+            OpenFlags::SQLITE_OPEN_READ_WRITE
+                | OpenFlags::SQLITE_OPEN_CREATE
+                | OpenFlags::SQLITE_OPEN_NO_MUTEX
+        };
+
+        // A bad extension would otherwise only show up as the pool timing
+        // out while it waits for its first connections
+        if !config.extensions.is_empty() {
+            let probe = Connection::open_with_flags(&config.path, flags)
+                .with_context(|| format!("Failed to open database: {}", config.path))?;
+            for extension in &config.extensions {
+                load_extensions(&probe, std::slice::from_ref(extension)).with_context(|| {
+                    format!("Failed to load SQLite extension {}", extension.display())
+                })?;
+            }
+        }
 
         // Clone config values to avoid move issues
-        let enable_wal = config.enable_wal;
+        let read_only = config.read_only;
+        let enable_wal = config.enable_wal && !read_only;
         let cache_size = config.cache_size;
         let busy_timeout = config.busy_timeout;
         let synchronous = config.synchronous.clone();
+        let mmap_size = config.mmap_size;
+        let temp_store = config.temp_store;
+        let journal_size_limit = config.journal_size_limit;
+        let wal_autocheckpoint = config.wal_autocheckpoint;
+        let extensions = config.extensions.clone();
 
-        let manager = SqliteConnectionManager::file(&config.path)
+        let sqlite_manager = SqliteConnectionManager::file(&config.path)
             .with_flags(flags)
             .with_init(move |conn| {
-                // Apply configuration to each connection
+                load_extensions(conn, &extensions)?;
+
+                // Apply configuration to each connection. Several of these PRAGMAs
+                // report their new value as a row, so they go through execute_batch.
                 if enable_wal {
-                    conn.execute("PRAGMA journal_mode = WAL", [])?;
+                    conn.execute_batch("PRAGMA journal_mode = WAL")?;
                 }
 
-                conn.execute(&format!("PRAGMA cache_size = {}", cache_size), [])?;
-                conn.execute(&format!("PRAGMA busy_timeout = {}", busy_timeout), [])?;
-                conn.execute(&format!("PRAGMA synchronous = {}", synchronous), [])?;
-                conn.execute("PRAGMA temp_store = memory", [])?;
-                conn.execute("PRAGMA mmap_size = 268435456", [])?; // 256MB mmap
-                conn.execute("PRAGMA foreign_keys = ON", [])?;
+                conn.execute_batch(&format!(
+                    "PRAGMA cache_size = {};
+                     PRAGMA busy_timeout = {};
+                     PRAGMA synchronous = {};
+                     PRAGMA temp_store = {};
+                     PRAGMA mmap_size = {};
+                     PRAGMA journal_size_limit = {};
+                     PRAGMA wal_autocheckpoint = {};
+                     PRAGMA foreign_keys = ON;",
+                    cache_size,
+                    busy_timeout,
+                    synchronous,
+                    temp_store.as_str(),
+                    mmap_size,
+                    journal_size_limit,
+                    wal_autocheckpoint
+                ))?;
+
+                // Also refuses writes SQLite would otherwise attempt, such as
+                // to a temporary database
+                if read_only {
+                    conn.execute_batch("PRAGMA query_only = ON")?;
+                }
 
                 Ok(())
             });
 
+        let counters = Arc::new(PoolCounters::default());
+        let manager = ConnectionManager {
+            inner: sqlite_manager,
+            health_check_interval: seconds(config.health_check_interval_secs),
+            counters: counters.clone(),
+        };
+
         let pool = Pool::builder()
             .max_size(config.max_connections)
             .min_idle(Some(config.min_connections))
             .connection_timeout(Duration::from_secs(30))
             .idle_timeout(Some(Duration::from_secs(600))) // 10 minutes
-            .max_lifetime(Some(Duration::from_secs(3600))) // 1 hour
+            .max_lifetime(seconds(config.connection_max_lifetime_secs))
+            .test_on_check_out(true)
+            .event_handler(Box::new(PoolEventHandler {
+                counters: counters.clone(),
+            }))
+            .error_handler(Box::new(PoolEventHandler {
+                counters: counters.clone(),
+            }))
             .build(manager)
             .context("Failed to create connection pool")?;
 
-        log::info!(
-            "Database connection pool created: {} max connections",
-            config.max_connections
+        tracing::info!(
+            max_connections = config.max_connections,
+            "Database connection pool created"
         );
 
-        Ok(Self { pool, config })
+        Ok(Self {
+            pool,
+            slow_queries: SlowQueryLog::from_config(&config),
+            config,
+            counters,
+        })
     }
 
     /// Get a connection from the pool
-    pub fn get_connection(&self) -> Result<PooledConnection<SqliteConnectionManager>> {
+    pub fn get_connection(&self) -> Result<PooledConnection<ConnectionManager>> {
         self.pool
             .get()
             .context("Failed to get connection from pool")
             .map_err(|e| {
-                log::error!("Connection pool error: {}", e);
+                tracing::error!(error = %e, "Connection pool error");
                 e
             })
     }
@@ -88,11 +454,27 @@ impl ConnectionPool {
             idle_connections: state.idle_connections,
             max_connections: self.config.max_connections,
             min_connections: self.config.min_connections,
+            busy_retries: self.busy_retries(),
+            recycled_connections: self.counters.recycled_connections.load(Ordering::Relaxed),
+            health_check_failures: self.counters.health_check_failures.load(Ordering::Relaxed),
+            connection_errors: self.counters.connection_errors.load(Ordering::Relaxed),
         }
     }
 
-    /// Execute a read-only query with automatic retry
-    pub fn with_read_connection<F, R>(&self, mut f: F) -> Result<R>
+    /// Execute a read-only query with automatic retry, cancelling it after
+    /// `default_query_timeout_ms`
+    pub fn with_read_connection<F, R>(&self, f: F) -> Result<R>
+    where
+        F: FnMut(&rusqlite::Connection) -> Result<R>,
+    {
+        self.with_read_timeout(self.default_query_timeout(), f)
+    }
+
+    /// Execute a read-only query with automatic retry, cancelling it once it
+    /// has run for `timeout`
+    ///
+    /// A cancelled query fails with [`QueryTimeout`] and is not retried.
+    pub fn with_read_timeout<F, R>(&self, timeout: Option<Duration>, mut f: F) -> Result<R>
     where
         F: FnMut(&rusqlite::Connection) -> Result<R>,
     {
@@ -103,14 +485,14 @@ impl ConnectionPool {
             attempts += 1;
             let conn = self.get_connection()?;
 
-            match f(&*conn) {
+            match run_with_timeout(&conn, timeout, &mut f) {
                 Ok(result) => return Ok(result),
-                Err(e) if attempts < max_attempts => {
-                    log::warn!(
-                        "Read query failed, retrying (attempt {}/{}): {}",
-                        attempts,
-                        max_attempts,
-                        e
+                Err(e) if attempts < max_attempts && !e.is::<QueryTimeout>() => {
+                    tracing::warn!(
+                        attempt = attempts,
+                        max_attempts = max_attempts,
+                        error = %e,
+                        "Read query failed, retrying"
                     );
                     std::thread::sleep(Duration::from_millis(100 * attempts as u64));
                     continue;
@@ -120,52 +502,190 @@ impl ConnectionPool {
         }
     }
 
-    /// Execute a write query with automatic retry and transaction
+    /// Run `f` in a write transaction, retrying while the database is busy
+    ///
+    /// Only SQLITE_BUSY/SQLITE_LOCKED failures are retried; any other error
+    /// rolls back and returns immediately. Retries back off exponentially from
+    /// `write_retry_base_delay_ms` with jitter, and stop after
+    /// `write_max_retries` attempts or once `write_retry_timeout_ms` would be
+    /// exceeded.
     pub fn with_write_transaction<F, R>(&self, mut f: F) -> Result<R>
     where
         F: FnMut(&rusqlite::Transaction) -> Result<R>,
     {
-        let mut attempts = 0;
-        let max_attempts = 3;
+        self.ensure_writable()?;
+
+        let started = Instant::now();
+        let timeout = Duration::from_millis(self.config.write_retry_timeout_ms);
+        let mut retries = 0;
 
         loop {
-            attempts += 1;
-            let conn = self.get_connection()?;
+            // Dropping an uncommitted transaction rolls it back
+            let result = self.get_connection().and_then(|conn| {
+                let tx = conn.unchecked_transaction()?;
+                let value = f(&tx)?;
+                tx.commit()?;
+                Ok(value)
+            });
 
-            let tx_result = conn.unchecked_transaction();
-            match tx_result {
-                Ok(tx) => {
-                    match f(&tx) {
-                        Ok(result) => match tx.commit() {
-                            Ok(()) => return Ok(result),
-                            Err(e) if attempts < max_attempts => {
-                                log::warn!("Transaction commit failed, retrying: {}", e);
-                                std::thread::sleep(Duration::from_millis(100 * attempts as u64));
-                                continue;
-                            }
-                            Err(e) => return Err(e.into()),
-                        },
-                        Err(e) => {
-                            let _ = tx.rollback(); // Ignore rollback errors
-                            if attempts < max_attempts {
-                                log::warn!("Transaction failed, retrying: {}", e);
-                                std::thread::sleep(Duration::from_millis(100 * attempts as u64));
-                                continue;
-                            } else {
-                                return Err(e);
-                            }
-                        }
-                    }
-                }
-                Err(e) if attempts < max_attempts => {
-                    log::warn!("Failed to start transaction, retrying: {}", e);
-                    std::thread::sleep(Duration::from_millis(100 * attempts as u64));
-                    continue;
-                }
-                Err(e) => return Err(e.into()),
+            let error = match result {
+                Err(e) if is_busy(&e) => e,
+                result => return result,
+            };
+
+            let delay = self.retry_delay(retries);
+            if retries >= self.config.write_max_retries || started.elapsed() + delay > timeout {
+                return Err(error.context(format!(
+                    "Database stayed busy after {} retries ({} ms)",
+                    retries,
+                    started.elapsed().as_millis()
+                )));
             }
+
+            retries += 1;
+            self.counters.busy_retries.fetch_add(1, Ordering::Relaxed);
+            tracing::warn!(
+                delay_ms = delay.as_millis() as u64,
+                retry = retries,
+                max_retries = self.config.write_max_retries,
+                error = %error,
+                "Database busy, retrying write"
+            );
+            std::thread::sleep(delay);
+        }
+    }
+
+    /// Backoff before retry number `retry` (0-based): half the exponential
+    /// step plus a random share of the other half, so competing writers
+    /// don't wake in lockstep
+    fn retry_delay(&self, retry: u32) -> Duration {
+        let step = self
+            .config
+            .write_retry_base_delay_ms
+            .saturating_mul(1u64 << retry.min(16))
+            .min(self.config.write_retry_timeout_ms.max(1));
+        let half = step / 2;
+        Duration::from_millis(half + jitter(step - half))
+    }
+
+    /// Fail with [`ReadOnlyDatabase`] if this pool was opened read-only
+    pub fn ensure_writable(&self) -> Result<()> {
+        if self.config.read_only {
+            return Err(ReadOnlyDatabase {
+                path: self.config.path.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.config.read_only
+    }
+
+    /// Records queries that exceed `slow_query_threshold_ms`
+    pub fn slow_query_log(&self) -> &SlowQueryLog {
+        &self.slow_queries
+    }
+
+    /// Timeout applied to reads that don't ask for their own
+    pub fn default_query_timeout(&self) -> Option<Duration> {
+        (self.config.default_query_timeout_ms > 0)
+            .then(|| Duration::from_millis(self.config.default_query_timeout_ms))
+    }
+
+    /// Number of write retries caused by SQLITE_BUSY/LOCKED since the pool was created
+    pub fn busy_retries(&self) -> u64 {
+        self.counters.busy_retries.load(Ordering::Relaxed)
+    }
+}
+
+/// A query was cancelled because it ran past its timeout
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Query cancelled after exceeding its {timeout_ms} ms timeout")]
+pub struct QueryTimeout {
+    pub timeout_ms: u64,
+}
+
+/// A write was attempted on a database opened with
+/// `DatabaseConfig::read_only`
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("Database {path} is open read-only")]
+pub struct ReadOnlyDatabase {
+    pub path: String,
+}
+
+/// VM instructions between deadline checks; small enough to react within
+/// milliseconds, large enough not to slow queries down
+const PROGRESS_CHECK_OPS: i32 = 1000;
+
+/// Run `f` with a progress handler that interrupts it after `timeout`
+fn run_with_timeout<F, R>(
+    conn: &rusqlite::Connection,
+    timeout: Option<Duration>,
+    f: &mut F,
+) -> Result<R>
+where
+    F: FnMut(&rusqlite::Connection) -> Result<R>,
+{
+    let timeout = match timeout {
+        Some(timeout) => timeout,
+        None => return f(conn),
+    };
+
+    /// Removes the handler even if `f` panics, so a pooled connection never
+    /// carries a stale deadline
+    struct ClearHandler<'a>(&'a rusqlite::Connection);
+
+    impl Drop for ClearHandler<'_> {
+        fn drop(&mut self) {
+            self.0.progress_handler(0, None::<fn() -> bool>);
         }
     }
+
+    let deadline = Instant::now() + timeout;
+    conn.progress_handler(PROGRESS_CHECK_OPS, Some(move || Instant::now() >= deadline));
+    let _guard = ClearHandler(conn);
+
+    f(conn).map_err(|e| {
+        let interrupted = e.chain().any(|cause| {
+            matches!(
+                cause.downcast_ref::<rusqlite::Error>(),
+                Some(rusqlite::Error::SqliteFailure(e, _))
+                    if e.code == ErrorCode::OperationInterrupted
+            )
+        });
+
+        if interrupted && Instant::now() >= deadline {
+            QueryTimeout {
+                timeout_ms: timeout.as_millis() as u64,
+            }
+            .into()
+        } else {
+            e
+        }
+    })
+}
+
+/// `secs` as a duration, with 0 meaning "never"
+fn seconds(secs: u64) -> Option<Duration> {
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Whether `error` was caused by another connection holding a lock
+fn is_busy(error: &anyhow::Error) -> bool {
+    error.chain().any(|cause| {
+        matches!(
+            cause.downcast_ref::<rusqlite::Error>(),
+            Some(rusqlite::Error::SqliteFailure(e, _))
+                if matches!(e.code, ErrorCode::DatabaseBusy | ErrorCode::DatabaseLocked)
+        )
+    })
+}
+
+/// Random value in `0..=max`
+fn jitter(max: u64) -> u64 {
+    fastrand::u64(0..=max)
 }
 
 impl Clone for ConnectionPool {
@@ -173,6 +693,8 @@ impl Clone for ConnectionPool {
         Self {
             pool: self.pool.clone(),
             config: self.config.clone(),
+            counters: self.counters.clone(),
+            slow_queries: self.slow_queries.clone(),
         }
     }
 }
@@ -184,6 +706,18 @@ pub struct PoolStatus {
     pub idle_connections: u32,
     pub max_connections: u32,
     pub min_connections: u32,
+    /// Write retries caused by SQLITE_BUSY/LOCKED
+    #[serde(default)]
+    pub busy_retries: u64,
+    /// Connections closed for age, idleness or being left broken
+    #[serde(default)]
+    pub recycled_connections: u64,
+    /// Checkouts that failed `SELECT 1` or `PRAGMA quick_check`
+    #[serde(default)]
+    pub health_check_failures: u64,
+    /// Failures opening or validating connections
+    #[serde(default)]
+    pub connection_errors: u64,
 }
 
 impl PoolStatus {
@@ -323,4 +857,251 @@ mod tests {
 
         assert_eq!(count, 10);
     }
+
+    /// Hold the write lock from a connection outside the pool
+    fn lock_database(config: &DatabaseConfig) -> rusqlite::Connection {
+        let conn = rusqlite::Connection::open(&config.path).unwrap();
+        conn.execute_batch("BEGIN IMMEDIATE").unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_write_retries_while_busy() {
+        let (mut config, _temp_dir) = test_config();
+        config.busy_timeout = 0;
+        config.write_max_retries = 20;
+        let pool = ConnectionPool::new(config.clone()).unwrap();
+        pool.with_write_transaction(|tx| Ok(tx.execute_batch("CREATE TABLE test (id INTEGER)")?))
+            .unwrap();
+
+        let lock = lock_database(&config);
+        let releaser = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            lock.execute_batch("COMMIT").unwrap();
+        });
+
+        pool.with_write_transaction(|tx| Ok(tx.execute("INSERT INTO test VALUES (1)", [])?))
+            .unwrap();
+        releaser.join().unwrap();
+
+        assert!(pool.busy_retries() > 0);
+        assert_eq!(pool.status().busy_retries, pool.busy_retries());
+    }
+
+    #[test]
+    fn test_write_gives_up_after_max_retries() {
+        let (mut config, _temp_dir) = test_config();
+        config.busy_timeout = 0;
+        config.write_max_retries = 2;
+        config.write_retry_base_delay_ms = 1;
+        let pool = ConnectionPool::new(config.clone()).unwrap();
+
+        let _lock = lock_database(&config);
+        let error = pool
+            .with_write_transaction(|tx| Ok(tx.execute_batch("CREATE TABLE test (id INTEGER)")?))
+            .unwrap_err();
+
+        assert!(is_busy(&error));
+        assert!(error.to_string().contains("after 2 retries"));
+        assert_eq!(pool.busy_retries(), 2);
+    }
+
+    #[test]
+    fn test_write_does_not_retry_other_errors() {
+        let (config, _temp_dir) = test_config();
+        let pool = ConnectionPool::new(config).unwrap();
+
+        let mut attempts = 0;
+        let result: Result<()> = pool.with_write_transaction(|_| {
+            attempts += 1;
+            Err(anyhow::anyhow!("not a lock error"))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+        assert_eq!(pool.busy_retries(), 0);
+    }
+
+    #[test]
+    fn test_retry_delay_backs_off() {
+        let (config, _temp_dir) = test_config();
+        let pool = ConnectionPool::new(config).unwrap();
+
+        for retry in 0..4 {
+            let step = 20 << retry;
+            let delay = pool.retry_delay(retry).as_millis() as u64;
+            assert!(
+                delay >= step / 2 && delay <= step,
+                "retry {}: {}",
+                retry,
+                delay
+            );
+        }
+
+        // Never longer than the whole retry budget
+        assert!(pool.retry_delay(30) <= Duration::from_millis(10000));
+    }
+
+    #[test]
+    fn test_connection_left_in_transaction_is_recycled() {
+        let (config, _temp_dir) = test_config();
+        let pool = ConnectionPool::new(config).unwrap();
+
+        let conn = pool.get_connection().unwrap();
+        conn.execute_batch("BEGIN").unwrap();
+        drop(conn);
+
+        assert_eq!(pool.status().recycled_connections, 1);
+
+        // Connections come back clean
+        let conn = pool.get_connection().unwrap();
+        assert!(conn.is_autocommit());
+    }
+
+    #[test]
+    fn test_stale_connection_runs_quick_check_on_checkout() {
+        let (config, _temp_dir) = test_config();
+        let pool = ConnectionPool::new(config).unwrap();
+
+        let stale = Instant::now() - Duration::from_secs(3600);
+        let mut conn = pool.get_connection().unwrap();
+        conn.last_checked = stale;
+        drop(conn);
+
+        // The pool hands back the most recently returned connection
+        let conn = pool.get_connection().unwrap();
+        assert!(conn.last_checked > stale);
+
+        let status = pool.status();
+        assert_eq!(status.health_check_failures, 0);
+        assert_eq!(status.connection_errors, 0);
+    }
+
+    /// Counts to a billion; takes far longer than any test timeout
+    const SLOW_QUERY: &str = "WITH RECURSIVE n(x) AS (SELECT 1 UNION ALL SELECT x + 1 FROM n WHERE x < 1000000000) SELECT COUNT(*) FROM n";
+
+    #[test]
+    fn test_read_timeout_cancels_query() {
+        let (config, _temp_dir) = test_config();
+        let pool = ConnectionPool::new(config).unwrap();
+
+        let started = Instant::now();
+        let mut attempts = 0;
+        let error = pool
+            .with_read_timeout(Some(Duration::from_millis(50)), |conn| {
+                attempts += 1;
+                Ok(conn.query_row(SLOW_QUERY, [], |row| row.get::<_, i64>(0))?)
+            })
+            .unwrap_err();
+
+        assert_eq!(
+            error.downcast_ref::<QueryTimeout>(),
+            Some(&QueryTimeout { timeout_ms: 50 })
+        );
+        assert_eq!(attempts, 1);
+        assert!(started.elapsed() < Duration::from_secs(5));
+
+        // The handler doesn't outlive the query
+        let value: i64 = pool
+            .with_read_timeout(None, |conn| {
+                Ok(conn.query_row("SELECT 42", [], |row| row.get(0))?)
+            })
+            .unwrap();
+        assert_eq!(value, 42);
+    }
+
+    #[test]
+    fn test_default_query_timeout() {
+        let (mut config, _temp_dir) = test_config();
+        config.default_query_timeout_ms = 50;
+        let pool = ConnectionPool::new(config.clone()).unwrap();
+        assert_eq!(
+            pool.default_query_timeout(),
+            Some(Duration::from_millis(50))
+        );
+
+        let error = pool
+            .with_read_connection(|conn| {
+                Ok(conn.query_row(SLOW_QUERY, [], |row| row.get::<_, i64>(0))?)
+            })
+            .unwrap_err();
+        assert!(error.is::<QueryTimeout>());
+
+        config.default_query_timeout_ms = 0;
+        let pool = ConnectionPool::new(config).unwrap();
+        assert_eq!(pool.default_query_timeout(), None);
+    }
+
+    #[test]
+    fn test_pragmas_follow_config() {
+        let (config, _temp_dir) = test_config();
+        let pragmas = |config: DatabaseConfig| {
+            let pool = ConnectionPool::new(config).unwrap();
+            pool.with_read_connection(|conn| {
+                let pragma = |name: &str| -> rusqlite::Result<i64> {
+                    conn.query_row(&format!("PRAGMA {}", name), [], |row| row.get(0))
+                };
+                Ok([
+                    pragma("mmap_size")?,
+                    pragma("temp_store")?,
+                    pragma("journal_size_limit")?,
+                    pragma("wal_autocheckpoint")?,
+                    pragma("synchronous")?,
+                ])
+            })
+            .unwrap()
+        };
+
+        // temp_store reads back as 0 (default), 1 (file) or 2 (memory);
+        // synchronous as 1 (normal) or 2 (full)
+        assert_eq!(pragmas(config.clone()), [256 * 1024 * 1024, 2, -1, 1000, 1]);
+
+        let mut low_memory = config.clone();
+        low_memory.apply_preset(TuningPreset::LowMemory);
+        assert_eq!(pragmas(low_memory), [0, 1, 4 * 1024 * 1024, 1000, 1]);
+
+        let mut durable = config;
+        durable.apply_preset(TuningPreset::Durable);
+        durable.wal_autocheckpoint = 0;
+        assert_eq!(pragmas(durable)[3..], [0, 2]);
+
+        assert_eq!(
+            "high-throughput".parse::<TuningPreset>().unwrap(),
+            TuningPreset::HighThroughput
+        );
+        assert!("turbo".parse::<TuningPreset>().is_err());
+    }
+
+    #[test]
+    fn test_extension_loading() {
+        let (mut config, temp_dir) = test_config();
+        let missing = temp_dir.path().join("missing_extension");
+
+        config.extensions = vec![missing.clone()];
+        let start = Instant::now();
+        let error = ConnectionPool::new(config.clone()).err().unwrap();
+        assert!(
+            format!("{:#}", error).contains("missing_extension"),
+            "{:#}",
+            error
+        );
+        assert!(
+            start.elapsed() < Duration::from_secs(5),
+            "fails without waiting for the pool"
+        );
+
+        // Loading stays switched off for SQL, with or without extensions
+        config.extensions.clear();
+        let pool = ConnectionPool::new(config).unwrap();
+        let error = pool
+            .with_read_connection(|conn| {
+                Ok(conn.query_row(
+                    "SELECT load_extension(?1)",
+                    [missing.to_string_lossy()],
+                    |_| Ok(()),
+                )?)
+            })
+            .unwrap_err();
+        assert!(error.to_string().contains("not authorized"), "{}", error);
+    }
 }
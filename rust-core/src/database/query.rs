@@ -0,0 +1,350 @@
+//! Typed builder for dynamically filtered SELECTs
+//!
+//! SQL text is assembled only from `&'static str` fragments and the fixed
+//! names of [`Column`]s; every value, however it was produced, is bound as a
+//! parameter. A caller can't put a runtime string into the statement even by
+//! mistake, since there is no way to pass one as SQL. Placeholders are
+//! numbered when the statement is rendered, so conditions are written with
+//! [`Condition::param`] instead of hand-counted `?N`s.
+
+use rusqlite::ToSql;
+
+/// Columns of `memories` recall filters and sorts on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Column {
+    Id,
+    RowId,
+    UserId,
+    SessionId,
+    Content,
+    Metadata,
+    CreatedAt,
+    ExpiresAt,
+    Importance,
+    Confidence,
+    SourceType,
+    SourceRef,
+    IngestedBy,
+    IsCompressed,
+    ContentType,
+    ContentBytes,
+    State,
+}
+
+impl Column {
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            Column::Id => "id",
+            Column::RowId => "memories.rowid",
+            Column::UserId => "user_id",
+            Column::SessionId => "session_id",
+            Column::Content => "content",
+            Column::Metadata => "metadata",
+            Column::CreatedAt => "created_at",
+            Column::ExpiresAt => "expires_at",
+            Column::Importance => "importance",
+            Column::Confidence => "confidence",
+            Column::SourceType => "source_type",
+            Column::SourceRef => "source_ref",
+            Column::IngestedBy => "ingested_by",
+            Column::IsCompressed => "is_compressed",
+            Column::ContentType => "content_type",
+            Column::ContentBytes => "content_bytes",
+            Column::State => "state",
+        }
+    }
+}
+
+/// Comparison between a column and a bound value
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Op {
+    fn as_sql(self) -> &'static str {
+        match self {
+            Op::Eq => " = ",
+            Op::Lt => " < ",
+            Op::Le => " <= ",
+            Op::Gt => " > ",
+            Op::Ge => " >= ",
+        }
+    }
+}
+
+/// Sort direction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+enum Part {
+    Sql(&'static str),
+    Param(Box<dyn ToSql>),
+}
+
+/// One WHERE condition: fixed SQL interleaved with bound values
+pub struct Condition {
+    parts: Vec<Part>,
+}
+
+impl Condition {
+    /// Fixed SQL with no values
+    pub fn sql(sql: &'static str) -> Self {
+        Self {
+            parts: vec![Part::Sql(sql)],
+        }
+    }
+
+    /// `column op ?`
+    pub fn compare(column: Column, op: Op, value: impl ToSql + 'static) -> Self {
+        Self::sql(column.as_sql()).then(op.as_sql()).param(value)
+    }
+
+    /// Append fixed SQL
+    pub fn then(mut self, sql: &'static str) -> Self {
+        self.parts.push(Part::Sql(sql));
+        self
+    }
+
+    /// Append a placeholder bound to `value`
+    pub fn param(mut self, value: impl ToSql + 'static) -> Self {
+        self.parts.push(Part::Param(Box::new(value)));
+        self
+    }
+
+    /// True when any of `conditions` is; false when there are none
+    pub fn any(conditions: Vec<Condition>) -> Self {
+        if conditions.is_empty() {
+            return Self::sql("0");
+        }
+        let mut any = Self::sql("(");
+        for (i, condition) in conditions.into_iter().enumerate() {
+            if i > 0 {
+                any = any.then(" OR ");
+            }
+            any.parts.extend(condition.parts);
+        }
+        any.then(")")
+    }
+
+    fn render<'a>(&'a self, sql: &mut String, params: &mut Vec<&'a dyn ToSql>) {
+        for part in &self.parts {
+            match part {
+                Part::Sql(fragment) => sql.push_str(fragment),
+                Part::Param(value) => {
+                    params.push(value.as_ref());
+                    sql.push('?');
+                    sql.push_str(&params.len().to_string());
+                }
+            }
+        }
+    }
+}
+
+/// A SELECT over one table with ANDed conditions, ordering and pagination
+pub struct SelectQuery {
+    columns: &'static str,
+    table: &'static str,
+    conditions: Vec<Condition>,
+    order_by: Vec<(Column, Order)>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+impl SelectQuery {
+    pub fn new(columns: &'static str, table: &'static str) -> Self {
+        Self {
+            columns,
+            table,
+            conditions: Vec::new(),
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    /// AND `condition` into the WHERE clause
+    pub fn filter(&mut self, condition: Condition) -> &mut Self {
+        self.conditions.push(condition);
+        self
+    }
+
+    /// Sort by `column` after any earlier sort keys
+    pub fn order_by(&mut self, column: Column, order: Order) -> &mut Self {
+        self.order_by.push((column, order));
+        self
+    }
+
+    /// Page through the results; an offset without a limit skips rows and
+    /// returns all the rest
+    pub fn paginate(&mut self, limit: Option<usize>, offset: Option<usize>) -> &mut Self {
+        // SQLite only accepts OFFSET after a LIMIT, and -1 means no limit
+        self.limit =
+            (limit.is_some() || offset.is_some()).then(|| limit.map_or(-1, |limit| limit as i64));
+        self.offset = offset.map(|offset| offset as i64);
+        self
+    }
+
+    /// The SELECT and its parameters, in placeholder order
+    pub fn to_sql(&self) -> (String, Vec<&dyn ToSql>) {
+        let mut sql = format!("SELECT {} FROM {}", self.columns, self.table);
+        let mut params = Vec::new();
+        self.render_where(&mut sql, &mut params);
+
+        for (i, (column, order)) in self.order_by.iter().enumerate() {
+            sql.push_str(if i == 0 { " ORDER BY " } else { ", " });
+            sql.push_str(column.as_sql());
+            sql.push_str(match order {
+                Order::Asc => " ASC",
+                Order::Desc => " DESC",
+            });
+        }
+        if let Some(limit) = &self.limit {
+            params.push(limit);
+            sql.push_str(&format!(" LIMIT ?{}", params.len()));
+        }
+        if let Some(offset) = &self.offset {
+            params.push(offset);
+            sql.push_str(&format!(" OFFSET ?{}", params.len()));
+        }
+        (sql, params)
+    }
+
+    /// `SELECT COUNT(*)` over the same rows, ignoring pagination
+    pub fn to_count_sql(&self) -> (String, Vec<&dyn ToSql>) {
+        let mut sql = format!("SELECT COUNT(*) FROM {}", self.table);
+        let mut params = Vec::new();
+        self.render_where(&mut sql, &mut params);
+        (sql, params)
+    }
+
+    fn render_where<'a>(&'a self, sql: &mut String, params: &mut Vec<&'a dyn ToSql>) {
+        for (i, condition) in self.conditions.iter().enumerate() {
+            sql.push_str(if i == 0 { " WHERE " } else { " AND " });
+            condition.render(sql, params);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::QueryFilter;
+    use crate::database::{Database, DatabaseConfig, KeywordMatch};
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    /// Values a hostile caller might pass, each marked with `pwned`; the
+    /// marker must never reach the SQL text
+    const HOSTILE: &[&str] = &[
+        "pwned'; DROP TABLE memories; --",
+        "pwned\" OR 1=1 --",
+        "pwned') UNION SELECT * FROM users --",
+        "pwned ?1",
+    ];
+
+    #[test]
+    fn test_placeholders_are_numbered_in_order() {
+        let mut query = SelectQuery::new("id", "memories");
+        query
+            .filter(Condition::sql("is_compressed = 0"))
+            .filter(Condition::compare(Column::UserId, Op::Eq, "alice"))
+            .filter(Condition::any(vec![
+                Condition::sql("content LIKE ").param("%a%"),
+                Condition::sql("content LIKE ").param("%b%"),
+            ]))
+            .order_by(Column::CreatedAt, Order::Desc)
+            .order_by(Column::Id, Order::Desc)
+            .paginate(Some(10), Some(20));
+
+        let (sql, params) = query.to_sql();
+        assert_eq!(
+            sql,
+            "SELECT id FROM memories WHERE is_compressed = 0 AND user_id = ?1 \
+             AND (content LIKE ?2 OR content LIKE ?3) \
+             ORDER BY created_at DESC, id DESC LIMIT ?4 OFFSET ?5"
+        );
+        assert_eq!(params.len(), 5);
+
+        let (sql, params) = query.to_count_sql();
+        assert_eq!(
+            sql,
+            "SELECT COUNT(*) FROM memories WHERE is_compressed = 0 AND user_id = ?1 \
+             AND (content LIKE ?2 OR content LIKE ?3)"
+        );
+        assert_eq!(params.len(), 3);
+
+        let mut query = SelectQuery::new("id", "memories");
+        query
+            .filter(Condition::any(Vec::new()))
+            .paginate(None, Some(5));
+        assert_eq!(
+            query.to_sql().0,
+            "SELECT id FROM memories WHERE 0 LIMIT ?1 OFFSET ?2"
+        );
+    }
+
+    #[test]
+    fn test_recall_query_binds_every_filter_value() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("query.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+
+        for &hostile in HOSTILE {
+            let filter = QueryFilter {
+                user_id: Some(hostile.to_string()),
+                session_id: Some(hostile.to_string()),
+                keywords: Some(vec![hostile.to_string()]),
+                metadata: HashMap::from([(hostile.to_string(), hostile.to_string())]),
+                json_fields: HashMap::from([
+                    (hostile.to_string(), serde_json::json!(hostile)),
+                    (format!("$.{}", hostile), serde_json::Value::Null),
+                ]),
+                before_id: Some(hostile.to_string()),
+                limit: Some(10),
+                offset: Some(5),
+                ..Default::default()
+            };
+            let trigrams = vec![hostile.to_string()];
+
+            for keyword_match in [
+                KeywordMatch::FullText,
+                KeywordMatch::Trigram(&trigrams),
+                KeywordMatch::Like(&trigrams),
+            ] {
+                let query = database.build_recall_query(&filter, keyword_match);
+                let conn = database.get_connection_pool().get_connection().unwrap();
+
+                let (sql, params) = query.to_sql();
+                assert!(!sql.contains("pwned") && !sql.contains("DROP"), "{}", sql);
+                assert_eq!(sql.matches('?').count(), params.len(), "{}", sql);
+                // The statement is well-formed and matches nothing
+                let mut stmt = conn.prepare(&sql).unwrap();
+                assert_eq!(stmt.parameter_count(), params.len());
+                assert!(stmt.query(&params[..]).unwrap().next().unwrap().is_none());
+
+                let (sql, params) = query.to_count_sql();
+                assert!(!sql.contains("pwned") && !sql.contains("DROP"), "{}", sql);
+                let count: i64 = conn.query_row(&sql, &params[..], |row| row.get(0)).unwrap();
+                assert_eq!(count, 0);
+            }
+        }
+
+        let stats = database.get_stats().unwrap();
+        assert_eq!(stats["total_memories"], 0);
+    }
+}
@@ -1,5 +1,7 @@
 //! Database schema definitions for Memex SQLite backend
 
+use serde::{Deserialize, Serialize};
+
 /// Main database schema SQL
 pub const SCHEMA_SQL: &str = r#"
 -- Users table
@@ -15,6 +17,7 @@ CREATE TABLE IF NOT EXISTS sessions (
     id TEXT PRIMARY KEY,
     user_id TEXT NOT NULL,
     name TEXT,
+    status TEXT NOT NULL DEFAULT 'active', -- active, closed or archived
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     last_active TEXT NOT NULL DEFAULT (datetime('now')),
     tags TEXT DEFAULT '[]', -- JSON array
@@ -28,7 +31,6 @@ CREATE TABLE IF NOT EXISTS memories (
     user_id TEXT NOT NULL,
     session_id TEXT NOT NULL,
     content TEXT NOT NULL,
-    content_vector TEXT, -- For future vector search support
     metadata TEXT DEFAULT '{}', -- JSON metadata
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now')),
@@ -37,6 +39,16 @@ CREATE TABLE IF NOT EXISTS memories (
     ttl_hours INTEGER, -- Time to live in hours
     is_compressed INTEGER NOT NULL DEFAULT 0, -- Boolean flag
     compressed_from TEXT DEFAULT '[]', -- JSON array of original memory IDs
+    version INTEGER NOT NULL DEFAULT 1, -- Incremented on every update
+    seq INTEGER NOT NULL DEFAULT 0, -- Position within the session, assigned on save
+    content_type TEXT NOT NULL DEFAULT 'text', -- text, json or markdown
+    content_bytes INTEGER NOT NULL DEFAULT 0, -- UTF-8 size of content
+    integrity_hash TEXT, -- Set on every write while sealing is enabled
+    state TEXT NOT NULL DEFAULT 'active', -- draft, active or archived
+    confidence REAL NOT NULL DEFAULT 1.0 CHECK (confidence >= 0.0 AND confidence <= 1.0),
+    source_type TEXT CHECK (source_type IN ('user', 'agent', 'tool', 'import')),
+    source_ref TEXT, -- URL, message ID or file path within the source
+    ingested_by TEXT, -- Agent, tool or API key that saved the memory
     FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
     FOREIGN KEY (session_id) REFERENCES sessions (id) ON DELETE CASCADE
 );
@@ -58,6 +70,21 @@ CREATE TABLE IF NOT EXISTS compressed_memories (
     FOREIGN KEY (session_id) REFERENCES sessions (id) ON DELETE CASCADE
 );
 
+-- Originals of compressed memories, kept so compression can be undone
+CREATE TABLE IF NOT EXISTS memory_archive (
+    id TEXT PRIMARY KEY, -- ID of the original memory
+    compressed_into TEXT NOT NULL, -- ID of the compressed memory that replaced it
+    user_id TEXT NOT NULL,
+    memory TEXT NOT NULL, -- The original memory as JSON
+    archived_at TEXT NOT NULL DEFAULT (datetime('now')),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+-- Deleting a compressed memory drops the originals it could be restored to
+CREATE TRIGGER IF NOT EXISTS memory_archive_cleanup AFTER DELETE ON memories BEGIN
+    DELETE FROM memory_archive WHERE compressed_into = old.id;
+END;
+
 -- Session summaries table
 CREATE TABLE IF NOT EXISTS session_summaries (
     id TEXT PRIMARY KEY,
@@ -69,12 +96,80 @@ CREATE TABLE IF NOT EXISTS session_summaries (
     date_range_start TEXT NOT NULL,
     date_range_end TEXT NOT NULL,
     importance_score REAL NOT NULL DEFAULT 0.5,
+    memory_id TEXT, -- the memory holding the summary, if stored as one
+    version INTEGER NOT NULL DEFAULT 1, -- bumped each time it is regenerated
+    generator TEXT NOT NULL DEFAULT 'heuristic' CHECK (generator IN ('heuristic', 'llm')),
+    generated_at TEXT,
+    stale INTEGER NOT NULL DEFAULT 0, -- the session's memories changed since
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now')),
     FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE,
     FOREIGN KEY (session_id) REFERENCES sessions (id) ON DELETE CASCADE
 );
 
+-- A session's stored summary goes stale once its memories change; its own
+-- summary memory doesn't count
+CREATE TRIGGER IF NOT EXISTS session_summaries_stale_insert AFTER INSERT ON memories
+WHEN json_extract(new.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE session_summaries SET stale = 1 WHERE session_id = new.session_id AND stale = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_summaries_stale_update AFTER UPDATE OF content ON memories
+WHEN json_extract(new.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE session_summaries SET stale = 1 WHERE session_id = new.session_id AND stale = 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS session_summaries_stale_delete AFTER DELETE ON memories
+WHEN json_extract(old.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE session_summaries SET stale = 1 WHERE session_id = old.session_id AND stale = 0;
+END;
+
+-- Day and topic summaries of a user's memories, the levels above session
+-- summaries; a day's key is its UTC date, a topic's its label
+CREATE TABLE IF NOT EXISTS summaries (
+    id TEXT PRIMARY KEY,
+    user_id TEXT NOT NULL,
+    granularity TEXT NOT NULL CHECK (granularity IN ('day', 'topic')),
+    key TEXT NOT NULL,
+    summary_text TEXT NOT NULL,
+    key_topics TEXT, -- JSON array
+    memory_count INTEGER NOT NULL DEFAULT 0,
+    date_range_start TEXT NOT NULL,
+    date_range_end TEXT NOT NULL,
+    importance_score REAL NOT NULL DEFAULT 0.5,
+    version INTEGER NOT NULL DEFAULT 1,
+    generator TEXT NOT NULL DEFAULT 'heuristic' CHECK (generator IN ('heuristic', 'llm')),
+    generated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    stale INTEGER NOT NULL DEFAULT 0,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+    UNIQUE (user_id, granularity, key),
+    FOREIGN KEY (user_id) REFERENCES users (id) ON DELETE CASCADE
+);
+
+-- Any change to a user's memories may move its topics, but only touches the
+-- summary of the day it was saved on
+CREATE TRIGGER IF NOT EXISTS summaries_stale_insert AFTER INSERT ON memories
+WHEN json_extract(new.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE summaries SET stale = 1
+    WHERE user_id = new.user_id AND stale = 0
+      AND (granularity = 'topic' OR key = substr(new.created_at, 1, 10));
+END;
+
+CREATE TRIGGER IF NOT EXISTS summaries_stale_update AFTER UPDATE OF content ON memories
+WHEN json_extract(new.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE summaries SET stale = 1
+    WHERE user_id = new.user_id AND stale = 0
+      AND (granularity = 'topic' OR key = substr(new.created_at, 1, 10));
+END;
+
+CREATE TRIGGER IF NOT EXISTS summaries_stale_delete AFTER DELETE ON memories
+WHEN json_extract(old.metadata, '$.type') IS NOT 'session_summary' BEGIN
+    UPDATE summaries SET stale = 1
+    WHERE user_id = old.user_id AND stale = 0
+      AND (granularity = 'topic' OR key = substr(old.created_at, 1, 10));
+END;
+
 -- Decay statistics table
 CREATE TABLE IF NOT EXISTS decay_runs (
     id TEXT PRIMARY KEY,
@@ -83,9 +178,11 @@ CREATE TABLE IF NOT EXISTS decay_runs (
     memories_expired INTEGER DEFAULT 0,
     memories_compressed INTEGER DEFAULT 0,
     sessions_summarized INTEGER DEFAULT 0,
+    sessions_archived INTEGER DEFAULT 0,
     total_memories_before INTEGER DEFAULT 0,
     total_memories_after INTEGER DEFAULT 0,
     storage_saved_bytes INTEGER DEFAULT 0,
+    physical_bytes_saved INTEGER DEFAULT 0,
     error_message TEXT,
     status TEXT NOT NULL DEFAULT 'running' CHECK (status IN ('running', 'completed', 'failed'))
 );
@@ -97,6 +194,129 @@ CREATE TABLE IF NOT EXISTS system_config (
     created_at TEXT NOT NULL DEFAULT (datetime('now')),
     updated_at TEXT NOT NULL DEFAULT (datetime('now'))
 );
+
+-- Queries that exceeded the slow-query threshold
+CREATE TABLE IF NOT EXISTS slow_queries (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    sql TEXT NOT NULL,
+    params TEXT NOT NULL DEFAULT '', -- Summary of bound parameters
+    duration_ms REAL NOT NULL,
+    query_plan TEXT, -- EXPLAIN QUERY PLAN output, if captured
+    failed INTEGER NOT NULL DEFAULT 0, -- Boolean flag
+    recorded_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Filter shapes seen by recall, counted for the index advisor
+CREATE TABLE IF NOT EXISTS query_shapes (
+    shape TEXT PRIMARY KEY, -- e.g. "user_id= state= created_at<> +keywords"
+    equality_columns TEXT NOT NULL DEFAULT '[]', -- JSON array
+    range_columns TEXT NOT NULL DEFAULT '[]', -- JSON array
+    unindexed TEXT NOT NULL DEFAULT '[]', -- JSON array of filters no index serves
+    recalls INTEGER NOT NULL DEFAULT 0,
+    slow_recalls INTEGER NOT NULL DEFAULT 0, -- Over the slow-query threshold
+    total_ms REAL NOT NULL DEFAULT 0,
+    last_seen TEXT NOT NULL
+);
+
+-- Periodic snapshots of database statistics
+CREATE TABLE IF NOT EXISTS stats_history (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recorded_at TEXT NOT NULL,
+    total_memories INTEGER NOT NULL,
+    total_sessions INTEGER NOT NULL,
+    database_size_bytes INTEGER NOT NULL,
+    user_counts TEXT NOT NULL DEFAULT '{}' -- JSON object of user_id -> memory count
+);
+
+-- Outcome of each scheduled or on-demand maintenance job run
+CREATE TABLE IF NOT EXISTS maintenance_runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    job TEXT NOT NULL, -- decay, checkpoint, analyze, stats_snapshot, embedding_backfill
+    started_at TEXT NOT NULL,
+    completed_at TEXT NOT NULL,
+    status TEXT NOT NULL CHECK (status IN ('completed', 'failed', 'skipped')),
+    detail TEXT, -- JSON summary of what the job did
+    message TEXT -- Why the run failed or was skipped
+);
+
+-- Memory and session writes made inside a request context
+CREATE TABLE IF NOT EXISTS audit_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    recorded_at TEXT NOT NULL,
+    action TEXT NOT NULL, -- memory_save, memory_update, memory_delete, session_create, ...
+    target_id TEXT NOT NULL, -- Memory or session ID
+    actor TEXT,
+    trace_id TEXT,
+    source TEXT
+);
+
+-- Per-category retention overrides for the decay process
+CREATE TABLE IF NOT EXISTS retention_rules (
+    category TEXT PRIMARY KEY, -- Matched against the memory's metadata "category"
+    max_age_hours INTEGER, -- NULL means never expire
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Reusable memory layouts with {{name}} placeholders
+CREATE TABLE IF NOT EXISTS templates (
+    id TEXT PRIMARY KEY,
+    content TEXT NOT NULL,
+    metadata TEXT NOT NULL DEFAULT '{}', -- JSON; values may hold placeholders too
+    importance REAL NOT NULL DEFAULT 0.5 CHECK (importance >= 0.0 AND importance <= 1.0),
+    ttl_hours INTEGER, -- TTL of memories saved from the template
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- JSON Schemas that JSON memories of a namespace must match
+CREATE TABLE IF NOT EXISTS json_schemas (
+    namespace TEXT PRIMARY KEY, -- Matched against the memory's metadata "namespace"
+    schema TEXT NOT NULL,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    updated_at TEXT NOT NULL DEFAULT (datetime('now'))
+);
+
+-- Words treated as interchangeable by keyword search, in either direction
+CREATE TABLE IF NOT EXISTS synonyms (
+    namespace TEXT NOT NULL, -- Applies to searches scoped to this namespace
+    term TEXT NOT NULL COLLATE NOCASE,
+    synonym TEXT NOT NULL COLLATE NOCASE,
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (namespace, term, synonym)
+);
+
+-- Typed links between two memories, stored once with source_id < target_id
+CREATE TABLE IF NOT EXISTS memory_links (
+    source_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    link_type TEXT NOT NULL, -- contradicts or compatible
+    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+    PRIMARY KEY (source_id, target_id, link_type),
+    FOREIGN KEY (source_id) REFERENCES memories (id) ON DELETE CASCADE,
+    FOREIGN KEY (target_id) REFERENCES memories (id) ON DELETE CASCADE
+);
+
+-- Advisory locks held by processes that opened this file
+CREATE TABLE IF NOT EXISTS instance_locks (
+    name TEXT PRIMARY KEY,
+    instance_id TEXT NOT NULL, -- Random ID of the holding Database instance
+    pid INTEGER NOT NULL,
+    hostname TEXT NOT NULL,
+    acquired_at TEXT NOT NULL,
+    heartbeat_at TEXT NOT NULL
+);
+
+-- Idempotency keys of recent saves, so a retried save returns the memory
+-- the first attempt made instead of saving it twice
+CREATE TABLE IF NOT EXISTS idempotency_keys (
+    user_id TEXT NOT NULL,
+    key TEXT NOT NULL,
+    memory_id TEXT NOT NULL,
+    created_at TEXT NOT NULL,
+    expires_at TEXT NOT NULL,
+    PRIMARY KEY (user_id, key)
+);
 "#;
 
 /// Database indexes for performance optimization
@@ -110,7 +330,12 @@ CREATE INDEX IF NOT EXISTS idx_memories_importance ON memories (importance);
 CREATE INDEX IF NOT EXISTS idx_memories_user_created ON memories (user_id, created_at DESC);
 CREATE INDEX IF NOT EXISTS idx_memories_session_created ON memories (session_id, created_at DESC);
 CREATE INDEX IF NOT EXISTS idx_memories_user_importance ON memories (user_id, importance DESC);
-CREATE INDEX IF NOT EXISTS idx_memories_active ON memories (expires_at) WHERE expires_at IS NULL OR expires_at > datetime('now');
+CREATE INDEX IF NOT EXISTS idx_memories_user_updated ON memories (user_id, updated_at DESC);
+CREATE INDEX IF NOT EXISTS idx_memories_session_seq ON memories (session_id, seq);
+CREATE INDEX IF NOT EXISTS idx_memories_user_content_bytes ON memories (user_id, content_bytes);
+-- A partial index on datetime('now') made inserts with an expiry fail; drop it
+-- from databases created before it was removed
+DROP INDEX IF EXISTS idx_memories_active;
 
 -- Indexes for sessions table
 CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions (user_id);
@@ -121,6 +346,7 @@ CREATE INDEX IF NOT EXISTS idx_sessions_user_active ON sessions (user_id, last_a
 CREATE INDEX IF NOT EXISTS idx_compressed_user_id ON compressed_memories (user_id);
 CREATE INDEX IF NOT EXISTS idx_compressed_session_id ON compressed_memories (session_id);
 CREATE INDEX IF NOT EXISTS idx_compressed_at ON compressed_memories (compressed_at);
+CREATE INDEX IF NOT EXISTS idx_memory_archive_compressed_into ON memory_archive (compressed_into);
 
 -- Indexes for session_summaries table
 CREATE INDEX IF NOT EXISTS idx_summaries_user_id ON session_summaries (user_id);
@@ -129,9 +355,30 @@ CREATE INDEX IF NOT EXISTS idx_summaries_created ON session_summaries (created_a
 -- Indexes for decay_runs table
 CREATE INDEX IF NOT EXISTS idx_decay_runs_started ON decay_runs (started_at DESC);
 CREATE INDEX IF NOT EXISTS idx_decay_runs_status ON decay_runs (status);
+
+-- Indexes for stats_history table
+CREATE INDEX IF NOT EXISTS idx_stats_history_recorded ON stats_history (recorded_at);
+
+-- Indexes for maintenance_runs table
+CREATE INDEX IF NOT EXISTS idx_maintenance_runs_job ON maintenance_runs (job, started_at DESC);
+
+-- Indexes for audit_log table
+CREATE INDEX IF NOT EXISTS idx_audit_log_actor ON audit_log (actor, id DESC);
+CREATE INDEX IF NOT EXISTS idx_audit_log_trace ON audit_log (trace_id);
+CREATE INDEX IF NOT EXISTS idx_audit_log_target ON audit_log (target_id, id DESC);
+
+-- Indexes for memory_links table
+CREATE INDEX IF NOT EXISTS idx_memory_links_target ON memory_links (target_id);
+
+-- Indexes for idempotency_keys table
+CREATE INDEX IF NOT EXISTS idx_idempotency_keys_expires ON idempotency_keys (expires_at);
 "#;
 
 /// FTS5 full-text search setup
+///
+/// `memories_fts` is an external-content index over `memories`: it stores no
+/// text of its own and is maintained only by the triggers below. Application
+/// code must never write to it directly, or the index drifts from the table.
 pub const FTS_SQL: &str = r#"
 -- Create FTS5 virtual table for full-text search on memory content
 CREATE VIRTUAL TABLE IF NOT EXISTS memories_fts USING fts5(
@@ -149,7 +396,8 @@ CREATE TRIGGER IF NOT EXISTS memories_fts_delete AFTER DELETE ON memories BEGIN
     INSERT INTO memories_fts(memories_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
 END;
 
-CREATE TRIGGER IF NOT EXISTS memories_fts_update AFTER UPDATE ON memories BEGIN
+-- Only content is indexed, so importance/TTL updates don't touch the index
+CREATE TRIGGER IF NOT EXISTS memories_fts_update AFTER UPDATE OF content ON memories BEGIN
     INSERT INTO memories_fts(memories_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
     INSERT INTO memories_fts(rowid, content) VALUES (new.rowid, new.content);
 END;
@@ -181,6 +429,415 @@ CREATE TRIGGER IF NOT EXISTS summaries_fts_update AFTER UPDATE ON session_summar
 END;
 "#;
 
+/// Memory count and content size per user and session
+///
+/// Kept up to date by triggers on `memories`, like the full-text index, so
+/// every write path counts. Expired memories count until decay removes them.
+pub const USAGE_SQL: &str = r#"
+CREATE TABLE IF NOT EXISTS memory_usage (
+    user_id TEXT NOT NULL,
+    session_id TEXT NOT NULL,
+    memory_count INTEGER NOT NULL DEFAULT 0,
+    content_bytes INTEGER NOT NULL DEFAULT 0,
+    PRIMARY KEY (user_id, session_id)
+) WITHOUT ROWID;
+
+-- Re-saves now upsert, which the update trigger counts; this trigger
+-- would take the replaced row out a second time
+DROP TRIGGER IF EXISTS memory_usage_replace;
+
+CREATE TRIGGER IF NOT EXISTS memory_usage_insert AFTER INSERT ON memories BEGIN
+    INSERT INTO memory_usage (user_id, session_id, memory_count, content_bytes)
+    VALUES (new.user_id, new.session_id, 1, new.content_bytes)
+    ON CONFLICT (user_id, session_id) DO UPDATE SET
+        memory_count = memory_count + 1,
+        content_bytes = content_bytes + excluded.content_bytes;
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_usage_delete AFTER DELETE ON memories BEGIN
+    UPDATE memory_usage
+    SET memory_count = memory_count - 1,
+        content_bytes = content_bytes - old.content_bytes
+    WHERE user_id = old.user_id AND session_id = old.session_id;
+    DELETE FROM memory_usage
+    WHERE user_id = old.user_id AND session_id = old.session_id AND memory_count <= 0;
+END;
+
+CREATE TRIGGER IF NOT EXISTS memory_usage_update
+AFTER UPDATE OF user_id, session_id, content_bytes ON memories BEGIN
+    UPDATE memory_usage
+    SET memory_count = memory_count - 1,
+        content_bytes = content_bytes - old.content_bytes
+    WHERE user_id = old.user_id AND session_id = old.session_id;
+    INSERT INTO memory_usage (user_id, session_id, memory_count, content_bytes)
+    VALUES (new.user_id, new.session_id, 1, new.content_bytes)
+    ON CONFLICT (user_id, session_id) DO UPDATE SET
+        memory_count = memory_count + 1,
+        content_bytes = content_bytes + excluded.content_bytes;
+    DELETE FROM memory_usage
+    WHERE user_id = old.user_id AND session_id = old.session_id AND memory_count <= 0;
+END;
+"#;
+
+/// Recount `memory_usage` from the `memories` table
+pub const USAGE_REBUILD_SQL: &str = r#"
+DELETE FROM memory_usage;
+INSERT INTO memory_usage (user_id, session_id, memory_count, content_bytes)
+SELECT user_id, session_id, COUNT(*), SUM(content_bytes)
+FROM memories
+GROUP BY user_id, session_id;
+"#;
+
+/// Rebuild `memories_fts` from the `memories` table
+pub const FTS_REBUILD_SQL: &str = "INSERT INTO memories_fts(memories_fts) VALUES ('rebuild');";
+
+/// Trigram index over memory content for fuzzy recall
+///
+/// Created on open unless `DatabaseConfig::trigram_index` is off; without
+/// it fuzzy recall finds its candidates with `LIKE` instead.
+pub const TRIGRAM_SQL: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS memories_trigram USING fts5(
+    content,
+    content='memories',
+    content_rowid='rowid',
+    tokenize='trigram'
+);
+
+CREATE TRIGGER IF NOT EXISTS memories_trigram_insert AFTER INSERT ON memories BEGIN
+    INSERT INTO memories_trigram(rowid, content) VALUES (new.rowid, new.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memories_trigram_delete AFTER DELETE ON memories BEGIN
+    INSERT INTO memories_trigram(memories_trigram, rowid, content) VALUES ('delete', old.rowid, old.content);
+END;
+
+CREATE TRIGGER IF NOT EXISTS memories_trigram_update AFTER UPDATE OF content ON memories BEGIN
+    INSERT INTO memories_trigram(memories_trigram, rowid, content) VALUES ('delete', old.rowid, old.content);
+    INSERT INTO memories_trigram(rowid, content) VALUES (new.rowid, new.content);
+END;
+"#;
+
+/// Re-index every memory in `memories_trigram`
+pub const TRIGRAM_REBUILD_SQL: &str =
+    "INSERT INTO memories_trigram(memories_trigram) VALUES ('rebuild');";
+
+/// Verify `memories_fts` against the `memories` table; fails with
+/// `SQLITE_CORRUPT_VTAB` if they disagree
+pub const FTS_INTEGRITY_CHECK_SQL: &str =
+    "INSERT INTO memories_fts(memories_fts, rank) VALUES ('integrity-check', 1);";
+
+/// How the `memories_fts` index splits content into searchable terms
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FtsTokenizer {
+    /// Whole words, ignoring case and diacritics
+    #[default]
+    Unicode61,
+    /// English word stems, so "trading" also matches "trade", "traded" and
+    /// "trades"
+    Porter,
+}
+
+impl FtsTokenizer {
+    /// The FTS5 `tokenize` option
+    pub fn as_sql(self) -> &'static str {
+        match self {
+            FtsTokenizer::Unicode61 => "unicode61",
+            FtsTokenizer::Porter => "porter unicode61",
+        }
+    }
+}
+
+/// The `tokenize` option `memories_fts` was created with; `None` if the
+/// table doesn't exist
+pub fn fts_tokenizer(conn: &rusqlite::Connection) -> rusqlite::Result<Option<String>> {
+    use rusqlite::OptionalExtension;
+
+    let sql: Option<String> = conn
+        .query_row(
+            "SELECT sql FROM sqlite_master WHERE type = 'table' AND name = 'memories_fts'",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+    Ok(sql.map(|sql| {
+        // tokenize='...' or tokenize="..."; FTS5 defaults to unicode61
+        sql.split_once("tokenize")
+            .and_then(|(_, rest)| {
+                let rest = rest.trim_start().strip_prefix('=')?.trim_start();
+                let quote = rest.chars().next().filter(|c| *c == '\'' || *c == '"')?;
+                rest[1..].split(quote).next().map(str::to_string)
+            })
+            .unwrap_or_else(|| FtsTokenizer::Unicode61.as_sql().to_string())
+    }))
+}
+
+/// Recreate `memories_fts` with `tokenizer` and re-index every memory if it
+/// was built with another one; returns whether it was rebuilt
+///
+/// The sync triggers refer to the table by name, so they keep working.
+pub fn set_fts_tokenizer(
+    conn: &rusqlite::Connection,
+    tokenizer: FtsTokenizer,
+) -> rusqlite::Result<bool> {
+    let current = fts_tokenizer(conn)?;
+    if current.as_deref() == Some(tokenizer.as_sql()) {
+        return Ok(false);
+    }
+
+    conn.execute_batch(&format!(
+        "DROP TABLE IF EXISTS memories_fts;
+         CREATE VIRTUAL TABLE memories_fts USING fts5(
+             content,
+             content='memories',
+             content_rowid='rowid',
+             tokenize='{}'
+         );
+         {}",
+        tokenizer.as_sql(),
+        FTS_REBUILD_SQL
+    ))?;
+    tracing::info!(
+        from = current.as_deref().unwrap_or("none"),
+        to = tokenizer.as_sql(),
+        "Rebuilt full-text index with new tokenizer"
+    );
+    Ok(true)
+}
+
+/// Build an FTS5 `MATCH` expression that matches any of `keywords`
+///
+/// Each keyword becomes a quoted string, so FTS5 operators (`AND`, `NEAR`,
+/// `*`, `^`, column filters, ...) and stray quotes are searched for as text
+/// instead of being parsed as query syntax. FTS5 stops reading the
+/// expression at a NUL, so those become separators. Blank keywords are
+/// dropped; `None` means there is nothing to search for.
+pub fn fts_match_query(keywords: &[String]) -> Option<String> {
+    let terms: Vec<String> = keywords
+        .iter()
+        .map(|keyword| keyword.replace('\0', " "))
+        .filter(|keyword| !keyword.trim().is_empty())
+        .map(|keyword| format!("\"{}\"", keyword.trim().replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" OR "))
+    }
+}
+
+/// A column added to an existing table after it was first created
+#[derive(Debug, Clone, Copy)]
+pub struct AddedColumn {
+    pub table: &'static str,
+    pub column: &'static str,
+    pub definition: &'static str,
+    /// Run once right after the column is added, to fill it in for the
+    /// rows that were already there
+    pub backfill: Option<&'static str>,
+}
+
+/// Columns added to existing tables after they were first created
+///
+/// `CREATE TABLE IF NOT EXISTS` leaves databases created before then
+/// without them, so [`add_missing_columns`] adds them when a database is
+/// opened or migrated.
+pub const ADDED_COLUMNS: &[AddedColumn] = &[
+    AddedColumn {
+        table: "memories",
+        column: "version",
+        definition: "INTEGER NOT NULL DEFAULT 1",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "seq",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+        // Number existing memories in creation order within their session
+        backfill: Some(
+            "UPDATE memories SET seq = (
+                 SELECT COUNT(*) FROM memories AS earlier
+                 WHERE earlier.session_id = memories.session_id
+                   AND (earlier.created_at < memories.created_at
+                        OR (earlier.created_at = memories.created_at
+                            AND earlier.rowid <= memories.rowid))
+             )",
+        ),
+    },
+    AddedColumn {
+        table: "memories",
+        column: "content_type",
+        definition: "TEXT NOT NULL DEFAULT 'text'",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "content_bytes",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+        backfill: Some("UPDATE memories SET content_bytes = length(CAST(content AS BLOB))"),
+    },
+    AddedColumn {
+        table: "memories",
+        column: "integrity_hash",
+        definition: "TEXT",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "state",
+        definition: "TEXT NOT NULL DEFAULT 'active'",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "confidence",
+        definition: "REAL NOT NULL DEFAULT 1.0 CHECK (confidence >= 0.0 AND confidence <= 1.0)",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "source_type",
+        definition: "TEXT CHECK (source_type IN ('user', 'agent', 'tool', 'import'))",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "source_ref",
+        definition: "TEXT",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "memories",
+        column: "ingested_by",
+        definition: "TEXT",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "sessions",
+        column: "status",
+        definition: "TEXT NOT NULL DEFAULT 'active'",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "decay_runs",
+        column: "sessions_archived",
+        definition: "INTEGER DEFAULT 0",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "session_summaries",
+        column: "memory_id",
+        definition: "TEXT",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "session_summaries",
+        column: "version",
+        definition: "INTEGER NOT NULL DEFAULT 1",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "session_summaries",
+        column: "generator",
+        definition: "TEXT NOT NULL DEFAULT 'heuristic' CHECK (generator IN ('heuristic', 'llm'))",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "session_summaries",
+        column: "generated_at",
+        definition: "TEXT",
+        backfill: Some("UPDATE session_summaries SET generated_at = updated_at"),
+    },
+    AddedColumn {
+        table: "session_summaries",
+        column: "stale",
+        definition: "INTEGER NOT NULL DEFAULT 0",
+        backfill: None,
+    },
+    AddedColumn {
+        table: "decay_runs",
+        column: "physical_bytes_saved",
+        definition: "INTEGER DEFAULT 0",
+        backfill: None,
+    },
+];
+
+/// Add any of [`ADDED_COLUMNS`] an existing table lacks; returns how many
+/// were added
+pub fn add_missing_columns(conn: &rusqlite::Connection) -> rusqlite::Result<usize> {
+    let mut added = 0;
+    for added_column in ADDED_COLUMNS {
+        let AddedColumn {
+            table,
+            column,
+            definition,
+            backfill,
+        } = *added_column;
+        let existing = {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+        };
+        // Tables that don't exist yet get the column from their CREATE TABLE
+        if existing.is_empty() || existing.iter().any(|name| name == column) {
+            continue;
+        }
+
+        conn.execute_batch(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))?;
+        if let Some(backfill) = backfill {
+            conn.execute_batch(backfill)?;
+        }
+        tracing::info!(table = table, column = column, "Added column");
+        added += 1;
+    }
+    Ok(added)
+}
+
+/// A column removed from a table that older databases still have
+#[derive(Debug, Clone, Copy)]
+pub struct DroppedColumn {
+    pub table: &'static str,
+    pub column: &'static str,
+}
+
+/// Columns no longer part of the schema, dropped by
+/// [`drop_removed_columns`] when a database is opened
+pub const DROPPED_COLUMNS: &[DroppedColumn] = &[
+    // Never read by vector search, which keeps embeddings in
+    // `memory_embeddings`
+    DroppedColumn {
+        table: "memories",
+        column: "content_vector",
+    },
+];
+
+/// Drop any of [`DROPPED_COLUMNS`] an existing table still has; returns
+/// how many were dropped
+pub fn drop_removed_columns(conn: &rusqlite::Connection) -> rusqlite::Result<usize> {
+    let mut dropped = 0;
+    for &DroppedColumn { table, column } in DROPPED_COLUMNS {
+        let exists = {
+            let mut stmt = conn.prepare(&format!("PRAGMA table_info({})", table))?;
+            let rows = stmt.query_map([], |row| row.get::<_, String>("name"))?;
+            rows.collect::<rusqlite::Result<Vec<_>>>()?
+                .iter()
+                .any(|name| name == column)
+        };
+        if !exists {
+            continue;
+        }
+
+        conn.execute_batch(&format!("ALTER TABLE {} DROP COLUMN {}", table, column))?;
+        tracing::info!(table = table, column = column, "Dropped column");
+        dropped += 1;
+    }
+    Ok(dropped)
+}
+
 /// Migration utilities
 pub struct Migration {
     pub version: u32,
@@ -197,14 +854,43 @@ pub fn get_migrations() -> Vec<Migration> {
             description: "Initial schema".to_string(),
             up_sql: format!("{}\n{}\n{}", SCHEMA_SQL, INDEXES_SQL, FTS_SQL),
             down_sql: r#"
+                DROP TABLE IF EXISTS memory_links;
+                DROP TABLE IF EXISTS synonyms;
+                DROP TABLE IF EXISTS json_schemas;
+                DROP TABLE IF EXISTS templates;
+                DROP TABLE IF EXISTS instance_locks;
+                DROP TABLE IF EXISTS retention_rules;
+                DROP TABLE IF EXISTS audit_log;
+                DROP TABLE IF EXISTS maintenance_runs;
+                DROP TABLE IF EXISTS stats_history;
+                DROP TABLE IF EXISTS query_shapes;
+                DROP TABLE IF EXISTS slow_queries;
                 DROP TABLE IF EXISTS decay_runs;
                 DROP TABLE IF EXISTS system_config;
+                DROP TRIGGER IF EXISTS summaries_stale_delete;
+                DROP TRIGGER IF EXISTS summaries_stale_update;
+                DROP TRIGGER IF EXISTS summaries_stale_insert;
+                DROP TABLE IF EXISTS summaries;
+                DROP TRIGGER IF EXISTS session_summaries_stale_delete;
+                DROP TRIGGER IF EXISTS session_summaries_stale_update;
+                DROP TRIGGER IF EXISTS session_summaries_stale_insert;
                 DROP TABLE IF EXISTS session_summaries;
+                DROP TRIGGER IF EXISTS memory_archive_cleanup;
+                DROP TABLE IF EXISTS memory_archive;
                 DROP TABLE IF EXISTS compressed_memories;
+                DROP TRIGGER IF EXISTS memory_usage_update;
+                DROP TRIGGER IF EXISTS memory_usage_delete;
+                DROP TRIGGER IF EXISTS memory_usage_insert;
+                DROP TRIGGER IF EXISTS memory_usage_replace;
+                DROP TABLE IF EXISTS memory_usage;
                 DROP TRIGGER IF EXISTS summaries_fts_update;
                 DROP TRIGGER IF EXISTS summaries_fts_delete;
                 DROP TRIGGER IF EXISTS summaries_fts_insert;
                 DROP TABLE IF EXISTS summaries_fts;
+                DROP TRIGGER IF EXISTS memories_trigram_update;
+                DROP TRIGGER IF EXISTS memories_trigram_delete;
+                DROP TRIGGER IF EXISTS memories_trigram_insert;
+                DROP TABLE IF EXISTS memories_trigram;
                 DROP TRIGGER IF EXISTS memories_fts_update;
                 DROP TRIGGER IF EXISTS memories_fts_delete;
                 DROP TRIGGER IF EXISTS memories_fts_insert;
@@ -215,6 +901,30 @@ pub fn get_migrations() -> Vec<Migration> {
             "#
             .to_string(),
         },
+        Migration {
+            version: 2,
+            description: "Maintain memories FTS index through triggers only".to_string(),
+            // Earlier versions also wrote to memories_fts by hand, which
+            // double-indexed rows; recreate the triggers and rebuild
+            up_sql: format!(
+                r#"
+                DROP TRIGGER IF EXISTS memories_fts_update;
+                DROP TRIGGER IF EXISTS memories_fts_delete;
+                DROP TRIGGER IF EXISTS memories_fts_insert;
+                {}
+                {}
+                "#,
+                FTS_SQL, FTS_REBUILD_SQL
+            ),
+            down_sql: r#"
+                DROP TRIGGER IF EXISTS memories_fts_update;
+                CREATE TRIGGER memories_fts_update AFTER UPDATE ON memories BEGIN
+                    INSERT INTO memories_fts(memories_fts, rowid, content) VALUES ('delete', old.rowid, old.content);
+                    INSERT INTO memories_fts(rowid, content) VALUES (new.rowid, new.content);
+                END;
+            "#
+            .to_string(),
+        },
         // Future migrations can be added here
     ]
 }
@@ -270,26 +980,25 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
     let latest_version = migrations.iter().map(|m| m.version).max().unwrap_or(0);
 
     if current_version >= latest_version {
-        log::info!(
-            "Database schema is up to date (version {})",
-            current_version
-        );
+        add_missing_columns(conn)?;
+        drop_removed_columns(conn)?;
+        tracing::info!(version = current_version, "Database schema is up to date");
         return Ok(());
     }
 
-    log::info!(
-        "Migrating database from version {} to {}",
-        current_version,
-        latest_version
+    tracing::info!(
+        from = current_version,
+        to = latest_version,
+        "Migrating database"
     );
 
     // Run migrations in order
     for migration in migrations {
         if migration.version > current_version {
-            log::info!(
-                "Running migration {}: {}",
-                migration.version,
-                migration.description
+            tracing::info!(
+                version = migration.version,
+                description = migration.description.as_str(),
+                "Running migration"
             );
 
             let tx = conn.unchecked_transaction()?;
@@ -302,11 +1011,13 @@ pub fn run_migrations(conn: &rusqlite::Connection) -> anyhow::Result<()> {
 
             tx.commit()?;
 
-            log::info!("Migration {} completed successfully", migration.version);
+            tracing::info!(version = migration.version, "Migration completed");
         }
     }
 
-    log::info!("All migrations completed successfully");
+    add_missing_columns(conn)?;
+    drop_removed_columns(conn)?;
+    tracing::info!("All migrations completed successfully");
     Ok(())
 }
 
@@ -338,8 +1049,11 @@ mod tests {
             "decay_runs",
             "memories",
             "memories_fts",
+            "query_shapes",
             "session_summaries",
             "sessions",
+            "slow_queries",
+            "summaries",
             "summaries_fts",
             "system_config",
             "users",
@@ -363,6 +1077,12 @@ mod tests {
         conn.execute_batch(FTS_SQL).unwrap();
 
         // Insert test data
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('user1');
+             INSERT INTO sessions (id, user_id) VALUES ('session1', 'user1');",
+        )
+        .unwrap();
+
         conn.execute(
             "INSERT INTO memories (id, user_id, session_id, content) VALUES ('1', 'user1', 'session1', 'This is about trading stocks')",
             [],
@@ -393,6 +1113,280 @@ mod tests {
             .unwrap();
 
         assert_eq!(count, 1);
+
+        // Updates and deletes are reindexed by the triggers
+        conn.execute(
+            "UPDATE memories SET content = 'This is about bonds' WHERE id = '1'",
+            [],
+        )
+        .unwrap();
+        conn.execute("DELETE FROM memories WHERE id = '2'", [])
+            .unwrap();
+
+        let count: i64 = conn
+            .query_row(
+                "SELECT COUNT(*) FROM memories_fts WHERE memories_fts MATCH 'trading'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 0);
+
+        conn.execute_batch(FTS_INTEGRITY_CHECK_SQL).unwrap();
+    }
+
+    #[test]
+    fn test_fts_match_query_escapes_syntax() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        conn.execute_batch(FTS_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('user1');
+             INSERT INTO sessions (id, user_id) VALUES ('session1', 'user1');
+             INSERT INTO memories (id, user_id, session_id, content)
+             VALUES ('1', 'user1', 'session1', 'Buy AND sell \"quoted\" c++ near: the open');",
+        )
+        .unwrap();
+
+        let keywords = |words: &[&str]| words.iter().map(|w| w.to_string()).collect::<Vec<_>>();
+        assert_eq!(
+            fts_match_query(&keywords(&["a\"b", "c"])).unwrap(),
+            "\"a\"\"b\" OR \"c\""
+        );
+        assert_eq!(fts_match_query(&keywords(&["", "  ", "\0"])), None);
+        assert_eq!(fts_match_query(&keywords(&["a\0b"])).unwrap(), "\"a b\"");
+
+        let count = |query: String| -> rusqlite::Result<i64> {
+            conn.query_row(
+                "SELECT COUNT(*) FROM memories_fts WHERE memories_fts MATCH ?1",
+                [query],
+                |row| row.get(0),
+            )
+        };
+
+        // Each of these is a syntax error as a raw MATCH expression but is
+        // found as plain text once quoted
+        for raw in [
+            "AND", "c++", "\"quoted", "near:", "(open", "AND sell", "the:",
+        ] {
+            assert!(count(raw.to_string()).is_err(), "{}", raw);
+            let query = fts_match_query(&keywords(&[raw])).unwrap();
+            assert_eq!(count(query).unwrap(), 1, "{}", raw);
+        }
+        assert_eq!(
+            count(fts_match_query(&keywords(&["missing", "open"])).unwrap()).unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_fts_migration_repairs_drift() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(&get_migrations()[0].up_sql).unwrap();
+        set_schema_version(&conn, 1).unwrap();
+
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('user1');
+             INSERT INTO sessions (id, user_id) VALUES ('session1', 'user1');
+             INSERT INTO memories (id, user_id, session_id, content) VALUES ('1', 'user1', 'session1', 'drifting');
+             INSERT INTO memories_fts(memories_fts, rowid, content) VALUES ('delete', 1, 'drifting');",
+        )
+        .unwrap();
+        assert!(conn.execute_batch(FTS_INTEGRITY_CHECK_SQL).is_err());
+
+        run_migrations(&conn).unwrap();
+
+        conn.execute_batch(FTS_INTEGRITY_CHECK_SQL).unwrap();
+    }
+
+    #[test]
+    fn test_changing_fts_tokenizer_rebuilds_index() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        conn.execute_batch(FTS_SQL).unwrap();
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('user1');
+             INSERT INTO sessions (id, user_id) VALUES ('session1', 'user1');
+             INSERT INTO memories (id, user_id, session_id, content)
+             VALUES ('1', 'user1', 'session1', 'Traded the breakout');",
+        )
+        .unwrap();
+        let count = |query: &str| -> i64 {
+            conn.query_row(
+                "SELECT COUNT(*) FROM memories_fts WHERE memories_fts MATCH ?1",
+                [query],
+                |row| row.get(0),
+            )
+            .unwrap()
+        };
+
+        assert_eq!(fts_tokenizer(&conn).unwrap().as_deref(), Some("unicode61"));
+        assert!(!set_fts_tokenizer(&conn, FtsTokenizer::Unicode61).unwrap());
+        assert_eq!(count("trading"), 0);
+
+        assert!(set_fts_tokenizer(&conn, FtsTokenizer::Porter).unwrap());
+        assert_eq!(
+            fts_tokenizer(&conn).unwrap().as_deref(),
+            Some("porter unicode61")
+        );
+        assert!(!set_fts_tokenizer(&conn, FtsTokenizer::Porter).unwrap());
+        assert_eq!(count("trading"), 1);
+        assert_eq!(count("trades"), 1);
+
+        // The triggers keep the new table in sync
+        conn.execute(
+            "UPDATE memories SET content = 'Watching the chart' WHERE id = '1'",
+            [],
+        )
+        .unwrap();
+        assert_eq!(count("trade"), 0);
+        assert_eq!(count("watch"), 1);
+        conn.execute_batch(FTS_INTEGRITY_CHECK_SQL).unwrap();
+
+        assert!(set_fts_tokenizer(&conn, FtsTokenizer::Unicode61).unwrap());
+        assert_eq!(count("watch"), 0);
+        assert_eq!(count("watching"), 1);
+    }
+
+    #[test]
+    fn test_added_columns_reach_old_databases() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE memories (
+                 id TEXT PRIMARY KEY, session_id TEXT NOT NULL,
+                 content TEXT NOT NULL, created_at TEXT NOT NULL
+             );
+             INSERT INTO memories VALUES ('c', 's1', 'third', '2024-01-03');
+             INSERT INTO memories VALUES ('a', 's1', 'first', '2024-01-01');
+             INSERT INTO memories VALUES ('b', 's1', 'second', '2024-01-02');
+             INSERT INTO memories VALUES ('x', 's2', 'other', '2024-01-02');",
+        )
+        .unwrap();
+
+        assert_eq!(add_missing_columns(&conn).unwrap(), 10);
+        assert_eq!(add_missing_columns(&conn).unwrap(), 0);
+        let rows: Vec<(String, i64, i64, i64)> = conn
+            .prepare("SELECT id, version, seq, content_bytes FROM memories ORDER BY id")
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                ("a".to_string(), 1, 1, 5),
+                ("b".to_string(), 1, 2, 6),
+                ("c".to_string(), 1, 3, 5),
+                ("x".to_string(), 1, 1, 5),
+            ]
+        );
+
+        // Missing tables are left to their CREATE TABLE
+        let fresh = Connection::open_in_memory().unwrap();
+        assert_eq!(add_missing_columns(&fresh).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_removed_columns_are_dropped() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE memories (
+                 id TEXT PRIMARY KEY, content TEXT NOT NULL, content_vector TEXT
+             );
+             INSERT INTO memories VALUES ('a', 'kept', '[0.1, 0.2]');",
+        )
+        .unwrap();
+
+        assert_eq!(drop_removed_columns(&conn).unwrap(), 1);
+        assert_eq!(drop_removed_columns(&conn).unwrap(), 0);
+        let row: (String, String) = conn
+            .query_row("SELECT * FROM memories", [], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .unwrap();
+        assert_eq!(row, ("a".to_string(), "kept".to_string()));
+
+        let fresh = Connection::open_in_memory().unwrap();
+        assert_eq!(drop_removed_columns(&fresh).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_usage_follows_memories() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(SCHEMA_SQL).unwrap();
+        // Users and sessions don't matter here
+        conn.execute_batch("PRAGMA foreign_keys = OFF").unwrap();
+        conn.execute_batch(
+            "INSERT INTO memories (id, user_id, session_id, content, content_bytes)
+             VALUES ('old', 'alice', 's1', 'saved before tracking', 21);",
+        )
+        .unwrap();
+        conn.execute_batch(USAGE_SQL).unwrap();
+        conn.execute_batch(USAGE_REBUILD_SQL).unwrap();
+
+        let usage = || -> Vec<(String, String, i64, i64)> {
+            conn.prepare(
+                "SELECT user_id, session_id, memory_count, content_bytes FROM memory_usage
+                 ORDER BY user_id, session_id",
+            )
+            .unwrap()
+            .query_map([], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap()
+            .collect::<rusqlite::Result<_>>()
+            .unwrap()
+        };
+        let row = |user: &str, session: &str, count, bytes| {
+            (user.to_string(), session.to_string(), count, bytes)
+        };
+        assert_eq!(usage(), vec![row("alice", "s1", 1, 21)]);
+
+        conn.execute_batch(
+            "INSERT INTO memories (id, user_id, session_id, content, content_bytes)
+             VALUES ('a', 'alice', 's1', 'hello', 5), ('b', 'bob', 's2', 'hi', 2);",
+        )
+        .unwrap();
+        assert_eq!(
+            usage(),
+            vec![row("alice", "s1", 2, 26), row("bob", "s2", 1, 2)]
+        );
+
+        // Re-saving a memory swaps its bytes instead of adding them
+        conn.execute_batch(
+            "INSERT INTO memories (id, user_id, session_id, content, content_bytes)
+             VALUES ('a', 'alice', 's1', 'hello there', 11)
+             ON CONFLICT (id) DO UPDATE SET
+                 content = excluded.content, content_bytes = excluded.content_bytes;",
+        )
+        .unwrap();
+        assert_eq!(usage()[0], row("alice", "s1", 2, 32));
+
+        // Moving a memory to another session moves its bytes too
+        conn.execute_batch(
+            "UPDATE memories SET session_id = 's3', content = 'hey', content_bytes = 3
+             WHERE id = 'a'",
+        )
+        .unwrap();
+        assert_eq!(
+            usage(),
+            vec![
+                row("alice", "s1", 1, 21),
+                row("alice", "s3", 1, 3),
+                row("bob", "s2", 1, 2)
+            ]
+        );
+
+        // Emptied sessions drop out
+        conn.execute_batch("DELETE FROM memories WHERE user_id = 'bob'")
+            .unwrap();
+        assert_eq!(
+            usage(),
+            vec![row("alice", "s1", 1, 21), row("alice", "s3", 1, 3)]
+        );
     }
 
     #[test]
@@ -0,0 +1,375 @@
+//! Multi-file router: one SQLite database per user or per hashed namespace
+//!
+//! `ShardedDatabase` maps each user ID to a shard name with a shard function
+//! and opens `<directory>/<shard>.db` on first use, so very large installs
+//! can spread users over many files. Every memory and session belongs to one
+//! user, so per-user operations go to exactly one shard; recall has to name
+//! a user, since there is no cross-shard query.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::database::models::{MemoryItem, PaginatedResponse, QueryFilter, Session};
+use crate::database::{Database, DatabaseConfig};
+
+/// File extension of shard databases
+const SHARD_EXTENSION: &str = "db";
+
+/// Built-in ways of mapping a user ID to a shard
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ShardStrategy {
+    /// A file per user, named after the (escaped) user ID
+    #[default]
+    PerUser,
+    /// A fixed number of files; users are assigned by a stable hash
+    Hashed { shards: u32 },
+}
+
+impl ShardStrategy {
+    /// Shard name for `user_id`, before escaping for the file system
+    pub fn shard_for(&self, user_id: &str) -> String {
+        match self {
+            ShardStrategy::PerUser => user_id.to_string(),
+            ShardStrategy::Hashed { shards } => {
+                format!("shard-{:04}", fnv1a(user_id) % (*shards).max(1) as u64)
+            }
+        }
+    }
+}
+
+impl std::str::FromStr for ShardStrategy {
+    type Err = anyhow::Error;
+
+    /// `per-user` or `hashed:<count>`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "per-user" | "per_user" | "user" => Ok(ShardStrategy::PerUser),
+            other => match other.strip_prefix("hashed:").map(str::parse::<u32>) {
+                Some(Ok(shards)) if shards > 0 => Ok(ShardStrategy::Hashed { shards }),
+                _ => Err(anyhow::anyhow!(
+                    "unknown shard strategy '{}' (expected per-user or hashed:<count>)",
+                    s
+                )),
+            },
+        }
+    }
+}
+
+/// 64-bit FNV-1a; unlike `DefaultHasher` it never changes between releases,
+/// which would move users to other files
+fn fnv1a(value: &str) -> u64 {
+    value.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
+
+/// Make `name` safe as a file name: ASCII letters, digits, `-` and `_` are
+/// kept and every other byte becomes `%XX`
+fn escape_shard_name(name: &str) -> String {
+    let mut escaped = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            escaped.push(byte as char);
+        } else {
+            escaped.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    if escaped.is_empty() {
+        escaped.push_str("%00");
+    }
+    escaped
+}
+
+/// Custom mapping from user ID to shard name
+pub type ShardFn = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// Where shards live and how users map to them
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShardedDatabaseConfig {
+    /// Directory holding one `<shard>.db` file per shard
+    pub directory: String,
+    pub strategy: ShardStrategy,
+    /// Settings every shard is opened with; `path` is replaced per shard
+    pub database: DatabaseConfig,
+}
+
+impl Default for ShardedDatabaseConfig {
+    fn default() -> Self {
+        Self {
+            directory: "shards".to_string(),
+            strategy: ShardStrategy::default(),
+            database: DatabaseConfig::default(),
+        }
+    }
+}
+
+/// A shard file on disk
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShardInfo {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    /// Whether this router currently has it open
+    pub open: bool,
+}
+
+/// Routes per-user operations to one `Database` per shard
+pub struct ShardedDatabase {
+    config: ShardedDatabaseConfig,
+    shard_fn: ShardFn,
+    shards: Mutex<HashMap<String, Database>>,
+}
+
+impl ShardedDatabase {
+    pub fn new(config: ShardedDatabaseConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.directory)
+            .with_context(|| format!("Failed to create shard directory: {}", config.directory))?;
+
+        let strategy = config.strategy;
+        Ok(Self {
+            config,
+            shard_fn: Arc::new(move |user_id| strategy.shard_for(user_id)),
+            shards: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Map users with `shard_fn` instead of the configured strategy
+    pub fn with_shard_fn(mut self, shard_fn: ShardFn) -> Self {
+        self.shard_fn = shard_fn;
+        self
+    }
+
+    pub fn config(&self) -> &ShardedDatabaseConfig {
+        &self.config
+    }
+
+    /// Name of the shard holding `user_id`'s data, escaped so it is a safe
+    /// file name
+    pub fn shard_name(&self, user_id: &str) -> String {
+        escape_shard_name(&(self.shard_fn)(user_id))
+    }
+
+    fn shard_path(&self, shard: &str) -> PathBuf {
+        Path::new(&self.config.directory).join(format!("{}.{}", shard, SHARD_EXTENSION))
+    }
+
+    /// The database holding `user_id`'s data, opened (and created) on first
+    /// use; the full `Database` API works on it
+    pub fn for_user(&self, user_id: &str) -> Result<Database> {
+        self.open_shard(&self.shard_name(user_id))
+    }
+
+    /// Open shard `name`, creating its file if needed
+    pub fn open_shard(&self, name: &str) -> Result<Database> {
+        let mut shards = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(database) = shards.get(name) {
+            return Ok(database.clone());
+        }
+
+        let database = Database::new(DatabaseConfig {
+            path: self.shard_path(name).to_string_lossy().to_string(),
+            ..self.config.database.clone()
+        })
+        .with_context(|| format!("Failed to open shard '{}'", name))?;
+        tracing::info!(shard = name, "Opened shard");
+
+        shards.insert(name.to_string(), database.clone());
+        Ok(database)
+    }
+
+    /// Shard files in the directory, by name
+    pub fn list_shards(&self) -> Result<Vec<ShardInfo>> {
+        let open = self.shards.lock().unwrap_or_else(|e| e.into_inner());
+        let mut shards = Vec::new();
+
+        for entry in std::fs::read_dir(&self.config.directory)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some(SHARD_EXTENSION) {
+                continue;
+            }
+            let Some(name) = path.file_stem().and_then(|s| s.to_str()) else {
+                continue;
+            };
+            shards.push(ShardInfo {
+                name: name.to_string(),
+                path: path.to_string_lossy().to_string(),
+                size_bytes: std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0),
+                open: open.contains_key(name),
+            });
+        }
+
+        shards.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(shards)
+    }
+
+    /// Close shard `name` (its file stays); it reopens on next use
+    pub fn close_shard(&self, name: &str) -> bool {
+        self.shards
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .remove(name)
+            .is_some()
+    }
+
+    /// Open every shard file in the directory
+    pub fn open_all(&self) -> Result<Vec<(String, Database)>> {
+        self.list_shards()?
+            .into_iter()
+            .map(|shard| Ok((shard.name.clone(), self.open_shard(&shard.name)?)))
+            .collect()
+    }
+
+    pub fn save_memory(&self, memory: &MemoryItem) -> Result<String> {
+        self.for_user(&memory.user_id)?.save_memory(memory)
+    }
+
+    /// Recall from the shard of `filter.user_id`, which is required
+    pub fn recall_memories(&self, filter: &QueryFilter) -> Result<PaginatedResponse<MemoryItem>> {
+        let user_id = filter
+            .user_id
+            .as_deref()
+            .context("Sharded recall needs a user_id; cross-shard queries aren't supported")?;
+        self.for_user(user_id)?.recall_memories(filter)
+    }
+
+    pub fn get_memory(&self, user_id: &str, id: &str) -> Result<Option<MemoryItem>> {
+        Ok(self
+            .for_user(user_id)?
+            .get_memory(id)?
+            .filter(|memory| memory.user_id == user_id))
+    }
+
+    pub fn delete_memory(&self, user_id: &str, id: &str) -> Result<bool> {
+        match self.get_memory(user_id, id)? {
+            Some(_) => self.for_user(user_id)?.delete_memory(id),
+            None => Ok(false),
+        }
+    }
+
+    pub fn create_session(&self, user_id: &str, session_name: Option<String>) -> Result<String> {
+        self.for_user(user_id)?
+            .create_session(user_id, session_name)
+    }
+
+    pub fn get_user_sessions(
+        &self,
+        user_id: &str,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<PaginatedResponse<Session>> {
+        self.for_user(user_id)?
+            .get_user_sessions(user_id, limit, offset)
+    }
+
+    /// Remove expired memories from every shard
+    pub fn cleanup_expired(&self) -> Result<usize> {
+        let mut removed = 0;
+        for (_, database) in self.open_all()? {
+            removed += database.cleanup_expired()?;
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn setup(strategy: ShardStrategy) -> (ShardedDatabase, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let sharded = ShardedDatabase::new(ShardedDatabaseConfig {
+            directory: temp_dir.path().to_string_lossy().to_string(),
+            strategy,
+            ..Default::default()
+        })
+        .unwrap();
+        (sharded, temp_dir)
+    }
+
+    fn memory(user_id: &str, content: &str) -> MemoryItem {
+        MemoryItem {
+            user_id: user_id.to_string(),
+            session_id: format!("{}-session", user_id),
+            content: content.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_per_user_shards_are_isolated() {
+        let (sharded, _temp_dir) = setup(ShardStrategy::PerUser);
+
+        let alice_id = sharded
+            .save_memory(&memory("alice", "Alice's note"))
+            .unwrap();
+        sharded
+            .save_memory(&memory("bob/../x", "Bob's note"))
+            .unwrap();
+
+        let names: Vec<String> = sharded
+            .list_shards()
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["alice", "bob%2F%2E%2E%2Fx"]);
+
+        let recalled = sharded
+            .recall_memories(&QueryFilter {
+                user_id: Some("alice".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(recalled.total_count, 1);
+        assert_eq!(recalled.data[0].content, "Alice's note");
+
+        // IDs don't leak across users or shards
+        assert!(sharded.get_memory("alice", &alice_id).unwrap().is_some());
+        assert!(sharded.get_memory("bob/../x", &alice_id).unwrap().is_none());
+        assert!(!sharded.delete_memory("bob/../x", &alice_id).unwrap());
+
+        assert!(sharded.recall_memories(&QueryFilter::default()).is_err());
+    }
+
+    #[test]
+    fn test_hashed_and_custom_shard_functions() {
+        let (sharded, _temp_dir) = setup(ShardStrategy::Hashed { shards: 4 });
+        for i in 0..20 {
+            sharded
+                .save_memory(&memory(&format!("user-{}", i), "note"))
+                .unwrap();
+        }
+        let shards = sharded.list_shards().unwrap();
+        assert!(!shards.is_empty() && shards.len() <= 4);
+        assert_eq!(
+            sharded.shard_name("user-3"),
+            ShardStrategy::Hashed { shards: 4 }.shard_for("user-3")
+        );
+
+        let sharded = sharded.with_shard_fn(Arc::new(|user_id: &str| {
+            user_id.split(':').next().unwrap_or_default().to_string()
+        }));
+        assert_eq!(sharded.shard_name("acme:alice"), "acme");
+        assert_eq!(sharded.shard_name("acme:bob"), "acme");
+    }
+
+    #[test]
+    fn test_strategy_parsing() {
+        assert_eq!(
+            "per-user".parse::<ShardStrategy>().unwrap(),
+            ShardStrategy::PerUser
+        );
+        assert_eq!(
+            "hashed:16".parse::<ShardStrategy>().unwrap(),
+            ShardStrategy::Hashed { shards: 16 }
+        );
+        assert!("hashed:0".parse::<ShardStrategy>().is_err());
+        assert!("random".parse::<ShardStrategy>().is_err());
+    }
+}
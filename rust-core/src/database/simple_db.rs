@@ -4,6 +4,7 @@ use anyhow::{Context, Result};
 use rusqlite::{Connection, OpenFlags, OptionalExtension};
 use std::path::Path;
 use std::sync::{Arc, Mutex};
+use validator::Validate;
 
 use super::{models::*, DatabaseConfig};
 
@@ -16,6 +17,10 @@ pub struct SimpleDatabase {
 impl SimpleDatabase {
     /// Create a new simple database instance
     pub fn new(config: DatabaseConfig) -> Result<Self> {
+        config
+            .validate()
+            .context("Invalid database configuration")?;
+
         // Ensure parent directory exists
         if let Some(parent) = Path::new(&config.path).parent() {
             std::fs::create_dir_all(parent)
@@ -27,7 +32,16 @@ impl SimpleDatabase {
         let conn = Connection::open_with_flags(&config.path, flags)
             .with_context(|| format!("Failed to open database at: {}", config.path))?;
 
-        // Minimal pragmas for testing
+        // Per-connection settings; pool sizes and replicas don't apply here
+        if config.enable_wal {
+            conn.execute_batch("PRAGMA journal_mode = WAL")
+                .context("Failed to enable WAL mode")?;
+        }
+        conn.execute_batch(&format!(
+            "PRAGMA cache_size = {}; PRAGMA busy_timeout = {}; PRAGMA synchronous = {};",
+            config.cache_size, config.busy_timeout, config.synchronous
+        ))
+        .context("Failed to apply database settings")?;
 
         // Initialize basic schema for testing - minimal version
         conn.execute(
@@ -110,7 +124,7 @@ impl SimpleDatabase {
                     user_id: row.get(1)?,
                     session_id: row.get(2)?,
                     content: row.get(3)?,
-                    content_vector: None,
+                    content_type: ContentType::Text,
                     #[cfg(feature = "vector-search")]
                     embedding: None,
                     #[cfg(feature = "vector-search")]
@@ -128,9 +142,19 @@ impl SimpleDatabase {
                             .map(|dt| dt.with_timezone(&chrono::Utc))
                     }),
                     importance: row.get(5)?,
+                    confidence: 1.0,
+                    source_type: None,
+                    source_ref: None,
+                    ingested_by: None,
                     ttl_hours: row.get::<_, Option<i64>>(9)?.map(|ttl| ttl as u32),
                     is_compressed: row.get::<_, i64>(10)? != 0,
                     compressed_from: Vec::new(),
+                    version: 1,
+                    seq: 0,
+                    state: MemoryState::Active,
+                    idempotency_key: None,
+                    match_score: None,
+                    explanation: None,
                 })
             })
             .optional()?;
@@ -204,7 +228,7 @@ impl SimpleDatabase {
                 user_id: row.get(1)?,
                 session_id: row.get(2)?,
                 content: row.get(3)?,
-                content_vector: None,
+                content_type: ContentType::Text,
                 #[cfg(feature = "vector-search")]
                 embedding: None,
                 #[cfg(feature = "vector-search")]
@@ -222,9 +246,19 @@ impl SimpleDatabase {
                         .map(|dt| dt.with_timezone(&chrono::Utc))
                 }),
                 importance: row.get(5)?,
+                confidence: 1.0,
+                source_type: None,
+                source_ref: None,
+                ingested_by: None,
                 ttl_hours: row.get::<_, Option<i64>>(9)?.map(|ttl| ttl as u32),
                 is_compressed: row.get::<_, i64>(10)? != 0,
                 compressed_from: Vec::new(),
+                version: 1,
+                seq: 0,
+                state: MemoryState::Active,
+                idempotency_key: None,
+                match_score: None,
+                explanation: None,
             })
         })?;
 
@@ -262,11 +296,11 @@ impl SimpleDatabase {
         let total_count: i64 = count_stmt.query_row(&param_refs[..], |row| row.get(0))?;
 
         let page = offset / limit;
-        let total_pages = (total_count as usize + limit - 1) / limit;
+        let total_pages = (total_count as usize).div_ceil(limit);
 
         Ok(PaginatedResponse {
             data: memories,
-            total_count: total_count,
+            total_count,
             page,
             per_page: limit,
             total_pages,
@@ -0,0 +1,335 @@
+//! Slow-query log
+//!
+//! Queries that run longer than `DatabaseConfig::slow_query_threshold_ms` are
+//! written to the `slow_queries` table together with a summary of their bound
+//! parameters and, if enabled, their `EXPLAIN QUERY PLAN` output.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::types::{ToSqlOutput, ValueRef};
+use rusqlite::ToSql;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
+
+use super::DatabaseConfig;
+use crate::display::truncate_graphemes;
+
+/// Longest text parameter value kept in the summary
+const MAX_PARAM_CHARS: usize = 40;
+
+/// Older entries are pruned so the log can't grow without bound
+const MAX_RECORDED: i64 = 1000;
+
+/// One recorded slow query
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlowQuery {
+    pub id: i64,
+    pub sql: String,
+    /// Bound parameters, e.g. `?1=text("user_1") ?2=int(50)`
+    pub params: String,
+    pub duration_ms: f64,
+    pub query_plan: Option<String>,
+    /// Whether the query returned an error (including a timeout)
+    pub failed: bool,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Times queries and records the slow ones
+#[derive(Debug, Clone)]
+pub struct SlowQueryLog {
+    threshold: Option<Duration>,
+    explain: bool,
+}
+
+impl SlowQueryLog {
+    pub fn from_config(config: &DatabaseConfig) -> Self {
+        Self {
+            // A read-only database has nowhere to record them
+            threshold: (config.slow_query_threshold_ms > 0 && !config.read_only)
+                .then(|| Duration::from_millis(config.slow_query_threshold_ms)),
+            explain: config.explain_slow_queries,
+        }
+    }
+
+    /// Run `f`, which executes `sql` with `params` on `conn`, and record it if
+    /// it was slow
+    ///
+    /// Failing to record never fails the query itself.
+    pub fn track<R>(
+        &self,
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: &[&dyn ToSql],
+        f: impl FnOnce() -> Result<R>,
+    ) -> Result<R> {
+        let threshold = match self.threshold {
+            Some(threshold) => threshold,
+            None => return f(),
+        };
+
+        let started = Instant::now();
+        let result = f();
+        let elapsed = started.elapsed();
+
+        if elapsed >= threshold {
+            if let Err(e) = self.record(conn, sql, params, elapsed, result.is_err()) {
+                tracing::warn!(error = %e, "Failed to record slow query");
+            }
+        }
+
+        result
+    }
+
+    fn record(
+        &self,
+        conn: &rusqlite::Connection,
+        sql: &str,
+        params: &[&dyn ToSql],
+        elapsed: Duration,
+        failed: bool,
+    ) -> Result<()> {
+        let sql = normalize_sql(sql);
+        let summary = summarize_params(params);
+        let duration_ms = elapsed.as_secs_f64() * 1000.0;
+
+        tracing::warn!(
+            duration_ms = duration_ms,
+            failed = failed,
+            sql = sql.as_str(),
+            params = summary.as_str(),
+            "Slow query"
+        );
+
+        let query_plan = if self.explain {
+            Some(explain(conn, &sql, params)?)
+        } else {
+            None
+        };
+
+        conn.execute(
+            "INSERT INTO slow_queries (sql, params, duration_ms, query_plan, failed, recorded_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            rusqlite::params![sql, summary, duration_ms, query_plan, failed, Utc::now()],
+        )?;
+        conn.execute(
+            "DELETE FROM slow_queries WHERE id <= last_insert_rowid() - ?1",
+            [MAX_RECORDED],
+        )?;
+        Ok(())
+    }
+}
+
+/// Most recent slow queries first
+pub fn recent(conn: &rusqlite::Connection, limit: usize) -> Result<Vec<SlowQuery>> {
+    let mut stmt = conn.prepare(
+        "SELECT id, sql, params, duration_ms, query_plan, failed, recorded_at
+         FROM slow_queries ORDER BY id DESC LIMIT ?1",
+    )?;
+
+    let rows = stmt.query_map([limit as i64], |row| {
+        Ok(SlowQuery {
+            id: row.get("id")?,
+            sql: row.get("sql")?,
+            params: row.get("params")?,
+            duration_ms: row.get("duration_ms")?,
+            query_plan: row.get("query_plan")?,
+            failed: row.get("failed")?,
+            recorded_at: row.get("recorded_at")?,
+        })
+    })?;
+
+    Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+}
+
+/// Delete every recorded slow query, returning how many there were
+pub fn clear(conn: &rusqlite::Connection) -> Result<usize> {
+    Ok(conn.execute("DELETE FROM slow_queries", [])?)
+}
+
+/// `EXPLAIN QUERY PLAN` output, one step per line, indented by depth
+pub(crate) fn explain(
+    conn: &rusqlite::Connection,
+    sql: &str,
+    params: &[&dyn ToSql],
+) -> Result<String> {
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+    let rows = stmt.query_map(params, |row| {
+        Ok((
+            row.get::<_, i64>("id")?,
+            row.get::<_, i64>("parent")?,
+            row.get::<_, String>("detail")?,
+        ))
+    })?;
+
+    let mut depths = std::collections::HashMap::new();
+    let mut lines = Vec::new();
+    for row in rows {
+        let (id, parent, detail) = row?;
+        let depth = depths.get(&parent).map_or(0, |d| d + 1);
+        depths.insert(id, depth);
+        lines.push(format!("{}{}", "  ".repeat(depth), detail));
+    }
+
+    Ok(lines.join("\n"))
+}
+
+/// Collapse the whitespace of an indented SQL literal onto one line
+fn normalize_sql(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Type and (shortened) value of each parameter; blobs show only their size
+fn summarize_params(params: &[&dyn ToSql]) -> String {
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let value = match param.to_sql() {
+                Ok(output) => describe(&output),
+                Err(_) => "?".to_string(),
+            };
+            format!("?{}={}", i + 1, value)
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Type of each parameter, with text and blobs cut down to their length
+/// so user IDs, keywords and metadata values stay out of the output
+pub(crate) fn redact_params(params: &[&dyn ToSql]) -> Vec<String> {
+    params
+        .iter()
+        .enumerate()
+        .map(|(i, param)| {
+            let value = match param.to_sql() {
+                Ok(ToSqlOutput::Borrowed(ValueRef::Text(bytes))) => {
+                    format!("text({} bytes)", bytes.len())
+                }
+                Ok(ToSqlOutput::Owned(rusqlite::types::Value::Text(text))) => {
+                    format!("text({} bytes)", text.len())
+                }
+                Ok(output) => describe(&output),
+                Err(_) => "?".to_string(),
+            };
+            format!("?{}={}", i + 1, value)
+        })
+        .collect()
+}
+
+fn describe(output: &ToSqlOutput<'_>) -> String {
+    let value = match output {
+        ToSqlOutput::Borrowed(value) => *value,
+        ToSqlOutput::Owned(value) => value.into(),
+        _ => return "?".to_string(),
+    };
+
+    match value {
+        ValueRef::Null => "null".to_string(),
+        ValueRef::Integer(i) => format!("int({})", i),
+        ValueRef::Real(f) => format!("real({})", f),
+        ValueRef::Text(bytes) => format!(
+            "text({:?})",
+            truncate_graphemes(&String::from_utf8_lossy(bytes), MAX_PARAM_CHARS)
+        ),
+        ValueRef::Blob(bytes) => format!("blob({} bytes)", bytes.len()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(crate::database::schema::SCHEMA_SQL)
+            .unwrap();
+        conn
+    }
+
+    fn log(threshold_ms: u64, explain: bool) -> SlowQueryLog {
+        SlowQueryLog::from_config(&DatabaseConfig {
+            slow_query_threshold_ms: threshold_ms,
+            explain_slow_queries: explain,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_records_slow_queries() {
+        let conn = setup();
+        let sql = "SELECT id FROM memories\n    WHERE user_id = ?1 LIMIT ?2";
+        let user = "user_1".to_string();
+        let params: [&dyn ToSql; 2] = [&user, &50i64];
+
+        log(1, true)
+            .track(&conn, sql, &params, || {
+                std::thread::sleep(Duration::from_millis(5));
+                Ok(())
+            })
+            .unwrap();
+
+        let queries = recent(&conn, 10).unwrap();
+        assert_eq!(queries.len(), 1);
+        assert_eq!(
+            queries[0].sql,
+            "SELECT id FROM memories WHERE user_id = ?1 LIMIT ?2"
+        );
+        assert_eq!(queries[0].params, "?1=text(\"user_1\") ?2=int(50)");
+        assert!(queries[0].duration_ms >= 5.0);
+        assert!(!queries[0].failed);
+        assert!(queries[0]
+            .query_plan
+            .as_deref()
+            .unwrap()
+            .contains("memories"));
+
+        assert_eq!(clear(&conn).unwrap(), 1);
+        assert!(recent(&conn, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_fast_and_disabled_queries_are_not_recorded() {
+        let conn = setup();
+
+        log(60_000, false)
+            .track(&conn, "SELECT 1", &[], || Ok(()))
+            .unwrap();
+        log(0, false)
+            .track(&conn, "SELECT 1", &[], || {
+                std::thread::sleep(Duration::from_millis(2));
+                Ok(())
+            })
+            .unwrap();
+
+        assert!(recent(&conn, 10).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_failed_queries_are_recorded_and_error_kept() {
+        let conn = setup();
+
+        let result: Result<()> = log(1, false).track(&conn, "SELECT 1", &[], || {
+            std::thread::sleep(Duration::from_millis(2));
+            Err(anyhow::anyhow!("interrupted"))
+        });
+
+        assert!(result.is_err());
+        let queries = recent(&conn, 10).unwrap();
+        assert!(queries[0].failed);
+        assert!(queries[0].query_plan.is_none());
+    }
+
+    #[test]
+    fn test_param_summary() {
+        let long = "x".repeat(100);
+        let blob = vec![0u8; 16];
+        let params: [&dyn ToSql; 4] = [&long, &blob, &0.5f64, &Option::<String>::None];
+
+        let summary = summarize_params(&params);
+        assert!(summary.starts_with(&format!("?1=text(\"{}…\")", "x".repeat(39))));
+        assert!(summary.contains("?2=blob(16 bytes)"));
+        assert!(summary.contains("?3=real(0.5)"));
+        assert!(summary.ends_with("?4=null"));
+    }
+}
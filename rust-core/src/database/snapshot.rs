@@ -0,0 +1,325 @@
+//! Point-in-time snapshots of the database and diffs between them
+//!
+//! `Database::snapshot` writes a consistent copy of the database with
+//! `VACUUM INTO` next to a JSON manifest describing it, both in the
+//! database's snapshot directory (`<database file>.snapshots`). Two
+//! snapshots can then be compared memory by memory, for example to see what
+//! an agent run changed before deciding whether to roll it back.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::OpenFlags;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::schema;
+
+/// Longest snapshot label, in characters
+pub const MAX_LABEL_LENGTH: usize = 64;
+
+/// Describes one snapshot; stored as `<label>.json` beside `<label>.db`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub label: String,
+    pub created_at: DateTime<Utc>,
+    /// Database the snapshot was taken of
+    pub source: String,
+    /// Snapshot database file
+    pub file: String,
+    pub schema_version: u32,
+    pub size_bytes: u64,
+    pub memory_count: i64,
+    /// Memories per user, expired ones included
+    pub user_counts: BTreeMap<String, i64>,
+}
+
+/// Memories that differ between two snapshots for one user
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UserSnapshotDiff {
+    /// Only in the later snapshot
+    pub added: Vec<String>,
+    /// Only in the earlier snapshot
+    pub removed: Vec<String>,
+    /// In both, with different content, metadata, importance, session,
+    /// expiry or version
+    pub changed: Vec<String>,
+}
+
+impl UserSnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Result of `Database::diff_snapshots`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub from: String,
+    pub to: String,
+    /// Users with at least one difference; memory IDs are sorted
+    pub users: BTreeMap<String, UserSnapshotDiff>,
+}
+
+impl SnapshotDiff {
+    pub fn is_empty(&self) -> bool {
+        self.users.is_empty()
+    }
+}
+
+/// Reject labels that are empty, too long or unsafe as file names
+pub fn validate_label(label: &str) -> Result<()> {
+    let valid = !label.is_empty()
+        && label.chars().count() <= MAX_LABEL_LENGTH
+        && !label.starts_with('.')
+        && label
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'));
+    if !valid {
+        return Err(anyhow::anyhow!(
+            "Invalid snapshot label '{}': use up to {} letters, digits, '-', '_' or '.', not starting with '.'",
+            label,
+            MAX_LABEL_LENGTH
+        ));
+    }
+    Ok(())
+}
+
+/// Snapshot database file for `label` in `dir`
+pub fn snapshot_path(dir: &Path, label: &str) -> PathBuf {
+    dir.join(format!("{}.db", label))
+}
+
+fn manifest_path(dir: &Path, label: &str) -> PathBuf {
+    dir.join(format!("{}.json", label))
+}
+
+/// Copy the database open on `conn` into `dir` as snapshot `label` and
+/// write its manifest
+pub fn create(
+    conn: &rusqlite::Connection,
+    source: &str,
+    dir: &Path,
+    label: &str,
+) -> Result<SnapshotManifest> {
+    validate_label(label)?;
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create snapshot directory {}", dir.display()))?;
+
+    let file = snapshot_path(dir, label);
+    let manifest_file = manifest_path(dir, label);
+    if file.exists() || manifest_file.exists() {
+        return Err(anyhow::anyhow!("Snapshot '{}' already exists", label));
+    }
+
+    let created_at = Utc::now();
+    conn.execute(
+        "VACUUM INTO ?1",
+        rusqlite::params![file.to_string_lossy().as_ref()],
+    )
+    .with_context(|| format!("Failed to write snapshot to {}", file.display()))?;
+
+    // Described from the copy itself, so the manifest matches it exactly
+    let snapshot = rusqlite::Connection::open(&file)?;
+    let schema_version = schema::get_schema_version(&snapshot)?;
+    let mut stmt = snapshot
+        .prepare("SELECT user_id, COUNT(*) FROM memories GROUP BY user_id ORDER BY user_id")?;
+    let user_counts = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<BTreeMap<String, i64>>>()?;
+
+    let manifest = SnapshotManifest {
+        label: label.to_string(),
+        created_at,
+        source: source.to_string(),
+        file: file.to_string_lossy().to_string(),
+        schema_version,
+        size_bytes: std::fs::metadata(&file).map(|m| m.len()).unwrap_or(0),
+        memory_count: user_counts.values().sum(),
+        user_counts,
+    };
+    std::fs::write(&manifest_file, serde_json::to_string_pretty(&manifest)?)
+        .with_context(|| format!("Failed to write {}", manifest_file.display()))?;
+
+    Ok(manifest)
+}
+
+/// Manifest of snapshot `label` in `dir`
+pub fn load(dir: &Path, label: &str) -> Result<SnapshotManifest> {
+    validate_label(label)?;
+    let path = manifest_path(dir, label);
+    let manifest = std::fs::read_to_string(&path)
+        .map_err(|_| anyhow::anyhow!("Snapshot '{}' not found", label))?;
+    serde_json::from_str(&manifest).with_context(|| format!("Invalid manifest {}", path.display()))
+}
+
+/// Manifests of every snapshot in `dir`, oldest first
+pub fn list(dir: &Path) -> Result<Vec<SnapshotManifest>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut manifests = Vec::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "json") {
+            let manifest = std::fs::read_to_string(&path)?;
+            match serde_json::from_str::<SnapshotManifest>(&manifest) {
+                Ok(manifest) => manifests.push(manifest),
+                Err(e) => {
+                    tracing::warn!(path = %path.display(), error = %e, "Skipping invalid snapshot manifest")
+                }
+            }
+        }
+    }
+    manifests.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.label.cmp(&b.label)));
+    Ok(manifests)
+}
+
+/// Memories added, removed and changed from snapshot `from` to `to`
+pub fn diff(dir: &Path, from: &str, to: &str) -> Result<SnapshotDiff> {
+    let from_manifest = load(dir, from)?;
+    let to_manifest = load(dir, to)?;
+
+    let conn = rusqlite::Connection::open_with_flags(
+        &from_manifest.file,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("Failed to open snapshot {}", from_manifest.file))?;
+    conn.execute("ATTACH DATABASE ?1 AS later", [&to_manifest.file])
+        .with_context(|| format!("Failed to open snapshot {}", to_manifest.file))?;
+
+    let mut users: BTreeMap<String, UserSnapshotDiff> = BTreeMap::new();
+    let mut collect = |sql: &str, kind: fn(&mut UserSnapshotDiff) -> &mut Vec<String>| {
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| Ok((row.get::<_, String>(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (user_id, memory_id) = row?;
+            kind(users.entry(user_id).or_default()).push(memory_id);
+        }
+        anyhow::Ok(())
+    };
+
+    collect(
+        "SELECT b.user_id, b.id FROM later.memories b
+         WHERE NOT EXISTS (SELECT 1 FROM main.memories a WHERE a.id = b.id)
+         ORDER BY b.id",
+        |diff| &mut diff.added,
+    )?;
+    collect(
+        "SELECT a.user_id, a.id FROM main.memories a
+         WHERE NOT EXISTS (SELECT 1 FROM later.memories b WHERE b.id = a.id)
+         ORDER BY a.id",
+        |diff| &mut diff.removed,
+    )?;
+    collect(
+        "SELECT b.user_id, b.id FROM main.memories a JOIN later.memories b ON b.id = a.id
+         WHERE a.content IS NOT b.content
+            OR a.metadata IS NOT b.metadata
+            OR a.importance IS NOT b.importance
+            OR a.session_id IS NOT b.session_id
+            OR a.expires_at IS NOT b.expires_at
+            OR a.version IS NOT b.version
+         ORDER BY b.id",
+        |diff| &mut diff.changed,
+    )?;
+
+    Ok(SnapshotDiff {
+        from: from.to_string(),
+        to: to.to_string(),
+        users,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn setup_test_database() -> (Database, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let database = Database::new(DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            ..Default::default()
+        })
+        .unwrap();
+        (database, temp_dir)
+    }
+
+    #[test]
+    fn test_snapshot_and_diff() {
+        let (database, _temp_dir) = setup_test_database();
+        let save = |user: &str, content: &str| {
+            database
+                .save_memory(&MemoryItem {
+                    user_id: user.to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap()
+        };
+        let kept = save("alice", "kept");
+        let edited = save("alice", "edited");
+        let deleted = save("bob", "deleted");
+
+        let before = database.snapshot("before").unwrap();
+        assert_eq!(before.memory_count, 3);
+        assert_eq!(before.user_counts["alice"], 2);
+        assert!(before.size_bytes > 0);
+        assert!(Path::new(&before.file).exists());
+        assert!(database.snapshot("before").is_err());
+        assert!(database.snapshot("../before").is_err());
+
+        database
+            .update_memory_with(&edited, None, |memory| {
+                memory.content = "edited again".to_string();
+                Ok(())
+            })
+            .unwrap();
+        assert!(database.delete_memory(&deleted).unwrap());
+        let added = save("carol", "added");
+        database.snapshot("after").unwrap();
+
+        let labels: Vec<_> = database
+            .list_snapshots()
+            .unwrap()
+            .into_iter()
+            .map(|m| m.label)
+            .collect();
+        assert_eq!(labels, vec!["before", "after"]);
+
+        let diff = database.diff_snapshots("before", "after").unwrap();
+        assert_eq!(diff.users.len(), 3);
+        assert_eq!(diff.users["alice"].changed, vec![edited.clone()]);
+        assert!(diff.users["alice"].added.is_empty());
+        assert_eq!(diff.users["bob"].removed, vec![deleted]);
+        assert_eq!(diff.users["carol"].added, vec![added]);
+        assert!(!diff.users.values().any(|d| d.changed.contains(&kept)));
+
+        assert!(database
+            .diff_snapshots("before", "before")
+            .unwrap()
+            .is_empty());
+        assert!(database.diff_snapshots("before", "missing").is_err());
+    }
+
+    #[test]
+    fn test_validate_label() {
+        for label in ["before-run", "run_2026.10.16", "a"] {
+            assert!(validate_label(label).is_ok(), "{}", label);
+        }
+        let too_long = "x".repeat(MAX_LABEL_LENGTH + 1);
+        for label in ["", "../escape", ".hidden", "with space", "a/b", &too_long] {
+            assert!(validate_label(label).is_err(), "{}", label);
+        }
+    }
+}
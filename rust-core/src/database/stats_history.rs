@@ -0,0 +1,220 @@
+//! Database statistics history
+//!
+//! Snapshots of the headline numbers from `Database::get_stats` are kept in
+//! the `stats_history` table so growth can be charted without an external
+//! monitoring stack. They are taken on demand and, when
+//! `DatabaseConfig::stats_snapshot_interval_hours` is set, at most once per
+//! interval by `Database::snapshot_stats_if_due` (called by each decay run).
+//! Hourly save counts for ingestion anomaly detection are read straight from
+//! `memories`, since snapshots are too coarse for them.
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Older snapshots are pruned so the history can't grow without bound
+const MAX_RECORDED: i64 = 10_000;
+
+/// One point of the statistics history
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StatsSnapshot {
+    pub recorded_at: DateTime<Utc>,
+    pub total_memories: i64,
+    pub total_sessions: i64,
+    pub database_size_bytes: u64,
+    /// Active memories per user
+    pub user_counts: BTreeMap<String, i64>,
+}
+
+/// Current statistics, read on `conn`
+pub fn capture(conn: &rusqlite::Connection, database_size_bytes: u64) -> Result<StatsSnapshot> {
+    let mut stmt = conn.prepare(
+        "SELECT user_id, COUNT(*) FROM memories
+         WHERE expires_at IS NULL OR expires_at > datetime('now')
+         GROUP BY user_id",
+    )?;
+    let user_counts = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .collect::<rusqlite::Result<BTreeMap<String, i64>>>()?;
+
+    let total_sessions = conn.query_row("SELECT COUNT(*) FROM sessions", [], |row| row.get(0))?;
+
+    Ok(StatsSnapshot {
+        recorded_at: Utc::now(),
+        total_memories: user_counts.values().sum(),
+        total_sessions,
+        database_size_bytes,
+        user_counts,
+    })
+}
+
+/// Store a snapshot
+pub fn record(conn: &rusqlite::Connection, snapshot: &StatsSnapshot) -> Result<()> {
+    conn.execute(
+        "INSERT INTO stats_history
+             (recorded_at, total_memories, total_sessions, database_size_bytes, user_counts)
+         VALUES (?1, ?2, ?3, ?4, ?5)",
+        rusqlite::params![
+            snapshot.recorded_at,
+            snapshot.total_memories,
+            snapshot.total_sessions,
+            snapshot.database_size_bytes as i64,
+            serde_json::to_string(&snapshot.user_counts)?,
+        ],
+    )?;
+    conn.execute(
+        "DELETE FROM stats_history WHERE id <= last_insert_rowid() - ?1",
+        [MAX_RECORDED],
+    )?;
+    Ok(())
+}
+
+/// When the most recent snapshot was taken
+pub fn last_recorded_at(conn: &rusqlite::Connection) -> Result<Option<DateTime<Utc>>> {
+    Ok(conn
+        .query_row(
+            "SELECT recorded_at FROM stats_history ORDER BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?)
+}
+
+/// Snapshots taken at or after `since`, oldest first
+pub fn since(conn: &rusqlite::Connection, since: DateTime<Utc>) -> Result<Vec<StatsSnapshot>> {
+    let mut stmt = conn.prepare(
+        "SELECT recorded_at, total_memories, total_sessions, database_size_bytes, user_counts
+         FROM stats_history WHERE recorded_at >= ?1 ORDER BY id",
+    )?;
+
+    let rows = stmt.query_map([since], |row| {
+        Ok((
+            StatsSnapshot {
+                recorded_at: row.get("recorded_at")?,
+                total_memories: row.get("total_memories")?,
+                total_sessions: row.get("total_sessions")?,
+                database_size_bytes: row.get::<_, i64>("database_size_bytes")? as u64,
+                user_counts: BTreeMap::new(),
+            },
+            row.get::<_, String>("user_counts")?,
+        ))
+    })?;
+
+    let mut snapshots = Vec::new();
+    for row in rows {
+        let (mut snapshot, user_counts) = row?;
+        snapshot.user_counts = serde_json::from_str(&user_counts)?;
+        snapshots.push(snapshot);
+    }
+    Ok(snapshots)
+}
+
+/// Memories saved per user in each of the `hours` hours before `now`;
+/// index 0 is the most recent hour. Deleted memories aren't counted.
+pub fn hourly_saves(
+    conn: &rusqlite::Connection,
+    now: DateTime<Utc>,
+    hours: u32,
+) -> Result<BTreeMap<String, Vec<i64>>> {
+    let mut stmt = conn.prepare(
+        "SELECT user_id,
+                CAST((julianday(?1) - julianday(created_at)) * 24 AS INTEGER) AS hours_ago,
+                COUNT(*)
+         FROM memories
+         WHERE created_at >= ?2 AND created_at <= ?1
+         GROUP BY user_id, hours_ago",
+    )?;
+    let since = now - chrono::Duration::hours(hours as i64);
+    let rows = stmt.query_map(rusqlite::params![now, since], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, i64>(1)?,
+            row.get::<_, i64>(2)?,
+        ))
+    })?;
+
+    let mut saves: BTreeMap<String, Vec<i64>> = BTreeMap::new();
+    for row in rows {
+        let (user_id, hours_ago, count) = row?;
+        // The window's oldest instant rounds to `hours`
+        let index = (hours_ago.max(0) as usize).min(hours as usize - 1);
+        saves
+            .entry(user_id)
+            .or_insert_with(|| vec![0; hours as usize])[index] += count;
+    }
+    Ok(saves)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rusqlite::Connection;
+
+    fn setup() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(crate::database::schema::SCHEMA_SQL)
+            .unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_capture_record_and_read_back() {
+        let conn = setup();
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('alice'), ('bob');
+             INSERT INTO sessions (id, user_id) VALUES ('s1', 'alice'), ('s2', 'bob');
+             INSERT INTO memories (id, user_id, session_id, content) VALUES
+                 ('m1', 'alice', 's1', 'one'),
+                 ('m2', 'alice', 's1', 'two'),
+                 ('m3', 'bob', 's2', 'three');
+             INSERT INTO memories (id, user_id, session_id, content, expires_at) VALUES
+                 ('m4', 'bob', 's2', 'expired', '2000-01-01 00:00:00');",
+        )
+        .unwrap();
+        assert!(last_recorded_at(&conn).unwrap().is_none());
+
+        let snapshot = capture(&conn, 4096).unwrap();
+        assert_eq!(snapshot.total_memories, 3);
+        assert_eq!(snapshot.total_sessions, 2);
+        assert_eq!(snapshot.user_counts["alice"], 2);
+        assert_eq!(snapshot.user_counts["bob"], 1);
+
+        record(&conn, &snapshot).unwrap();
+        assert_eq!(last_recorded_at(&conn).unwrap(), Some(snapshot.recorded_at));
+
+        let history = since(&conn, snapshot.recorded_at - chrono::Duration::days(1)).unwrap();
+        assert_eq!(history, vec![snapshot.clone()]);
+
+        let future = snapshot.recorded_at + chrono::Duration::seconds(1);
+        assert!(since(&conn, future).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_hourly_saves() {
+        let conn = setup();
+        let now = Utc::now();
+        conn.execute_batch(
+            "INSERT INTO users (id) VALUES ('alice');
+             INSERT INTO sessions (id, user_id) VALUES ('s1', 'alice');",
+        )
+        .unwrap();
+        for (id, minutes_ago) in [("m1", 10), ("m2", 20), ("m3", 130), ("m4", 60 * 30)] {
+            conn.execute(
+                "INSERT INTO memories (id, user_id, session_id, content, created_at)
+                 VALUES (?1, 'alice', 's1', 'content', ?2)",
+                rusqlite::params![id, now - chrono::Duration::minutes(minutes_ago)],
+            )
+            .unwrap();
+        }
+
+        let saves = hourly_saves(&conn, now, 24).unwrap();
+        let alice = &saves["alice"];
+        assert_eq!(alice.len(), 24);
+        assert_eq!(alice[0], 2);
+        assert_eq!(alice[2], 1);
+        // Older than the window
+        assert_eq!(alice.iter().sum::<i64>(), 3);
+    }
+}
@@ -1,19 +1,267 @@
 //! Vector search implementation for semantic memory retrieval
 
 use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use rusqlite::OptionalExtension;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+use std::process::{Command, Stdio};
+use validator::Validate;
 
-use super::models::MemoryItem;
-use super::ConnectionPool;
+use super::models::TAGS_METADATA_KEY;
+use super::{ConnectionPool, QueryParams};
+
+/// Nearest neighbours of a query embedding for one model; `{similarity}`
+/// compares against `?1` under the metric in `?5`
+const SEARCH_SIMILAR_SQL: &str = r#"
+SELECT
+    m.id, m.user_id, m.session_id, m.content, m.importance,
+    m.created_at, m.updated_at,
+    {similarity} as similarity
+FROM memories m
+INNER JOIN memory_embeddings e ON m.id = e.memory_id
+WHERE e.model_name = ?2
+    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+    AND m.state = 'active'
+    AND {similarity} >= ?3
+    {filters}
+ORDER BY similarity DESC
+LIMIT ?4
+"#;
+
+/// Vector similarity blended with an FTS match; `{similarity}` compares
+/// against `?1` under the metric in `?8`
+const HYBRID_SEARCH_SQL: &str = r#"
+SELECT
+    m.id, m.user_id, m.session_id, m.content, m.importance,
+    m.created_at, m.updated_at,
+    {similarity} as vector_similarity,
+    CASE
+        WHEN fts.content IS NOT NULL THEN 1.0
+        ELSE 0.0
+    END as text_match,
+    (?2 * CASE WHEN fts.content IS NOT NULL THEN 1.0 ELSE 0.0 END +
+     ?3 * {similarity}) as combined_score
+FROM memories m
+INNER JOIN memory_embeddings e ON m.id = e.memory_id
+LEFT JOIN memories_fts fts ON m.rowid = fts.rowid AND fts MATCH ?4
+WHERE e.model_name = ?5
+    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+    AND m.state = 'active'
+    AND (?2 * CASE WHEN fts.content IS NOT NULL THEN 1.0 ELSE 0.0 END +
+         ?3 * {similarity}) >= ?6
+    {filters}
+ORDER BY combined_score DESC
+LIMIT ?7
+"#;
+
+/// Next batch of live memories without an embedding, after a rowid cursor.
+/// `memory_embeddings` holds one embedding per memory, so a memory embedded
+/// by any model counts as done.
+const MISSING_EMBEDDINGS_SQL: &str = r#"
+SELECT m.rowid, m.id, m.content
+FROM memories m
+WHERE m.rowid > ?1
+    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+    AND NOT EXISTS (SELECT 1 FROM memory_embeddings e WHERE e.memory_id = m.id)
+ORDER BY m.rowid
+LIMIT ?2
+"#;
+
+/// Embeddings whose memory no longer exists
+const PURGE_ORPHANED_SQL: &str = r#"
+DELETE FROM memory_embeddings
+WHERE NOT EXISTS (SELECT 1 FROM memories m WHERE m.id = memory_embeddings.memory_id)
+"#;
+
+/// Embedded live memories of one session for one model
+const SESSION_MEMBERS_SQL: &str = r#"
+SELECT m.user_id, m.importance, e.embedding
+FROM memories m
+INNER JOIN memory_embeddings e ON m.id = e.memory_id
+WHERE m.session_id = ?1
+    AND e.model_name = ?2
+    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+"#;
+
+/// A user's sessions that have embedded memories but no aggregate, either
+/// because they predate session embeddings or because a change to one of
+/// their memories invalidated it
+const STALE_SESSIONS_SQL: &str = r#"
+SELECT DISTINCT m.session_id
+FROM memories m
+INNER JOIN memory_embeddings e ON m.id = e.memory_id
+WHERE m.user_id = ?1
+    AND e.model_name = ?2
+    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+    AND NOT EXISTS (
+        SELECT 1 FROM session_embeddings s
+        WHERE s.session_id = m.session_id AND s.model_name = ?2
+    )
+"#;
+
+/// Weight of a memory in its session's embedding, so memories with zero
+/// importance still count a little
+const MIN_SESSION_WEIGHT: f32 = 0.05;
+
+/// Live embeddings decoded to estimate norms and load time
+const DIAGNOSTIC_SAMPLE_SIZE: usize = 10_000;
+
+/// Restricts vector and hybrid searches; every field that is set must match
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(default)]
+pub struct VectorFilter {
+    pub user_id: Option<String>,
+    pub session_id: Option<String>,
+
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub min_importance: Option<f32>,
+
+    #[validate(range(min = 0.0, max = 1.0))]
+    pub min_confidence: Option<f32>,
+
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+
+    /// Tags the memory must all carry in its comma-separated `tags` metadata
+    pub tags: Vec<String>,
+}
+
+impl VectorFilter {
+    /// Only memories belonging to `user_id`
+    pub fn for_user(user_id: &str) -> Self {
+        Self {
+            user_id: Some(user_id.to_string()),
+            ..Default::default()
+        }
+    }
+
+    /// `AND ...` conditions on the `m` (memories) alias, with placeholders
+    /// numbered from `first_param`
+    fn sql_conditions(&self, first_param: usize) -> (String, QueryParams) {
+        let mut conditions = Vec::new();
+        let mut params: QueryParams = Vec::new();
+        let mut push = |condition: &str, value: Box<dyn rusqlite::ToSql>| {
+            let index = first_param + params.len();
+            conditions.push(condition.replace("?", &format!("?{}", index)));
+            params.push(value);
+        };
+
+        if let Some(user_id) = &self.user_id {
+            push("m.user_id = ?", Box::new(user_id.clone()));
+        }
+        if let Some(session_id) = &self.session_id {
+            push("m.session_id = ?", Box::new(session_id.clone()));
+        }
+        if let Some(min_importance) = self.min_importance {
+            push("m.importance >= ?", Box::new(min_importance));
+        }
+        if let Some(min_confidence) = self.min_confidence {
+            push("m.confidence >= ?", Box::new(min_confidence));
+        }
+        if let Some(date_from) = self.date_from {
+            push("m.created_at >= ?", Box::new(date_from));
+        }
+        if let Some(date_to) = self.date_to {
+            push("m.created_at <= ?", Box::new(date_to));
+        }
+        let tags_sql = format!(
+            "instr(',' || replace(json_extract(m.metadata, '$.{}'), ' ', '') || ',', \
+             ',' || replace(?, ' ', '') || ',') > 0",
+            TAGS_METADATA_KEY
+        );
+        for tag in &self.tags {
+            push(&tags_sql, Box::new(tag.clone()));
+        }
+
+        let sql = conditions
+            .iter()
+            .map(|condition| format!("AND {}", condition))
+            .collect::<Vec<_>>()
+            .join("\n    ");
+        (sql, params)
+    }
+}
+
+/// How embeddings are compared; every metric scores higher for closer vectors
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DistanceMetric {
+    /// Cosine of the angle between the vectors, in [-1, 1]. Embeddings are
+    /// normalized when stored.
+    #[default]
+    Cosine,
+    /// Dot product of the raw vectors
+    Dot,
+    /// Euclidean distance, scored as `1 / (1 + distance)`
+    L2,
+}
+
+impl DistanceMetric {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::L2 => "l2",
+        }
+    }
+
+    /// Similarity of two vectors of equal length; 0 if the lengths differ
+    pub fn score(self, a: &[f32], b: &[f32]) -> f32 {
+        if a.len() != b.len() {
+            return 0.0;
+        }
+
+        match self {
+            Self::Cosine => cosine_similarity(a, b),
+            Self::Dot => a.iter().zip(b).map(|(x, y)| x * y).sum(),
+            Self::L2 => {
+                let distance = a
+                    .iter()
+                    .zip(b)
+                    .map(|(x, y)| (x - y) * (x - y))
+                    .sum::<f32>()
+                    .sqrt();
+                1.0 / (1.0 + distance)
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for DistanceMetric {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for DistanceMetric {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "cosine" => Ok(Self::Cosine),
+            "dot" => Ok(Self::Dot),
+            "l2" | "euclidean" => Ok(Self::L2),
+            _ => Err(anyhow::anyhow!(
+                "Unknown distance metric '{}' (expected cosine, dot or l2)",
+                s
+            )),
+        }
+    }
+}
 
 /// Vector embedding configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct VectorConfig {
     pub dimension: usize,
     pub similarity_threshold: f32,
     pub max_results: usize,
     pub enable_approximate_search: bool,
+    /// Fixed per model once it has embeddings; see `embedding_models`
+    pub metric: DistanceMetric,
 }
 
 impl Default for VectorConfig {
@@ -23,11 +271,13 @@ impl Default for VectorConfig {
             similarity_threshold: 0.7,
             max_results: 50,
             enable_approximate_search: true,
+            metric: DistanceMetric::Cosine,
         }
     }
 }
 
 /// Vector search engine for semantic memory retrieval
+#[derive(Clone)]
 pub struct VectorSearchEngine {
     pool: ConnectionPool,
     config: VectorConfig,
@@ -38,8 +288,17 @@ impl VectorSearchEngine {
         Self { pool, config }
     }
 
+    pub fn config(&self) -> &VectorConfig {
+        &self.config
+    }
+
     /// Initialize vector search tables and indexes
     pub fn initialize_schema(&self) -> Result<()> {
+        // A read-only database is searched with whatever tables it has
+        if self.pool.is_read_only() {
+            return Ok(());
+        }
+
         self.pool.with_write_transaction(|tx| {
             // Create vector embeddings table
             tx.execute(
@@ -62,6 +321,125 @@ impl VectorSearchEngine {
                 [],
             )?;
 
+            // The foreign key already cascades deletes, but only on connections
+            // that enforce foreign keys; a content change makes the embedding
+            // stale, so it's dropped for `backfill` to regenerate
+            tx.execute_batch(
+                r#"
+                CREATE TRIGGER IF NOT EXISTS memory_embeddings_delete AFTER DELETE ON memories BEGIN
+                    DELETE FROM memory_embeddings WHERE memory_id = old.id;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS memory_embeddings_update AFTER UPDATE OF content ON memories
+                WHEN new.content IS NOT old.content BEGIN
+                    DELETE FROM memory_embeddings WHERE memory_id = old.id;
+                END;
+                "#,
+            )?;
+
+            // Clean up after deletes made before the triggers existed
+            let orphaned = tx.execute(PURGE_ORPHANED_SQL, [])?;
+            if orphaned > 0 {
+                tracing::info!(count = orphaned, "Removed orphaned embeddings");
+            }
+
+            // Scores from different metrics aren't comparable, so each model
+            // keeps the metric its first embedding was stored under. Models
+            // predating the table were always scored by cosine.
+            tx.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS embedding_models (
+                    model_name TEXT PRIMARY KEY,
+                    metric TEXT NOT NULL,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now'))
+                );
+
+                INSERT OR IGNORE INTO embedding_models (model_name, metric)
+                SELECT DISTINCT model_name, 'cosine' FROM memory_embeddings;
+                "#,
+            )?;
+
+            // Embeddings by content hash and model, so identical content
+            // (agent boilerplate, repeated facts) is sent to the model once.
+            // Each entry is stored raw, before normalization, so it serves
+            // any metric.
+            tx.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS embedding_cache (
+                    content_hash TEXT NOT NULL,
+                    model_name TEXT NOT NULL,
+                    embedding BLOB NOT NULL,
+                    hits INTEGER NOT NULL DEFAULT 0,
+                    created_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    last_used_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (content_hash, model_name)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_embedding_cache_last_used
+                    ON embedding_cache (last_used_at);
+                "#,
+            )?;
+
+            // Per-session aggregates: the importance-weighted sum of the
+            // session's memory embeddings, kept up to date as embeddings are
+            // stored. Anything that changes a member drops the aggregate and
+            // `search_sessions` rebuilds it from scratch.
+            tx.execute_batch(
+                r#"
+                CREATE TABLE IF NOT EXISTS session_embeddings (
+                    session_id TEXT NOT NULL,
+                    model_name TEXT NOT NULL,
+                    user_id TEXT NOT NULL,
+                    weighted_sum BLOB NOT NULL,
+                    total_weight REAL NOT NULL,
+                    memory_count INTEGER NOT NULL,
+                    updated_at TEXT NOT NULL DEFAULT (datetime('now')),
+                    PRIMARY KEY (session_id, model_name)
+                );
+
+                CREATE INDEX IF NOT EXISTS idx_session_embeddings_user
+                    ON session_embeddings (user_id, model_name);
+
+                CREATE TRIGGER IF NOT EXISTS session_embeddings_memory_delete AFTER DELETE ON memories BEGIN
+                    DELETE FROM session_embeddings WHERE session_id = old.session_id;
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS session_embeddings_memory_update
+                AFTER UPDATE OF importance, session_id ON memories BEGIN
+                    DELETE FROM session_embeddings WHERE session_id IN (old.session_id, new.session_id);
+                END;
+
+                CREATE TRIGGER IF NOT EXISTS session_embeddings_embedding_delete
+                AFTER DELETE ON memory_embeddings BEGIN
+                    DELETE FROM session_embeddings
+                    WHERE session_id = (SELECT session_id FROM memories WHERE id = old.memory_id);
+                END;
+                "#,
+            )?;
+
+            tx.create_scalar_function(
+                "vector_similarity",
+                3,
+                rusqlite::functions::FunctionFlags::SQLITE_UTF8
+                    | rusqlite::functions::FunctionFlags::SQLITE_DETERMINISTIC,
+                move |ctx| {
+                    let vec1 = deserialize_vector(&ctx.get::<Vec<u8>>(0)?).map_err(|_| {
+                        rusqlite::Error::UserFunctionError("Invalid vector 1".into())
+                    })?;
+                    let vec2 = deserialize_vector(&ctx.get::<Vec<u8>>(1)?).map_err(|_| {
+                        rusqlite::Error::UserFunctionError("Invalid vector 2".into())
+                    })?;
+                    let metric: DistanceMetric = ctx
+                        .get::<String>(2)?
+                        .parse()
+                        .map_err(|e: anyhow::Error| {
+                            rusqlite::Error::UserFunctionError(e.into())
+                        })?;
+
+                    Ok(metric.score(&vec1, &vec2))
+                },
+            )?;
+
             // Create vector similarity function (using SQLite extension or custom implementation)
             // Note: In production, you might want to use a specialized vector database like Qdrant or Weaviate
             tx.create_scalar_function(
@@ -89,13 +467,37 @@ impl VectorSearchEngine {
         })
     }
 
-    /// Store embedding for a memory
-    pub fn store_embedding(
-        &self,
-        memory_id: &str,
-        embedding: &[f32],
-        model_name: &str,
-    ) -> Result<()> {
+    /// SQL scoring `e.embedding` against the query embedding in `?1`, with
+    /// the metric's name bound to `?{metric_param}`
+    ///
+    /// With the `sqlite-vec` feature, cosine and L2 searches on connections
+    /// that loaded sqlite-vec use its native distance functions, which
+    /// read the same little-endian `f32` blobs; dot products have no
+    /// sqlite-vec equivalent.
+    #[cfg_attr(not(feature = "sqlite-vec"), allow(unused_variables))]
+    fn similarity_sql(&self, conn: &rusqlite::Connection, metric_param: usize) -> String {
+        #[cfg(feature = "sqlite-vec")]
+        if self.config.metric != DistanceMetric::Dot && sqlite_vec_version(conn).is_some() {
+            return format!(
+                "(CASE ?{0} WHEN 'l2' THEN 1.0 / (1.0 + vec_distance_l2(e.embedding, ?1)) \
+                 ELSE 1.0 - vec_distance_cosine(e.embedding, ?1) END)",
+                metric_param
+            );
+        }
+        format!("vector_similarity(e.embedding, ?1, ?{})", metric_param)
+    }
+
+    /// Version of the sqlite-vec extension loaded into the pool's
+    /// connections, if any; see `DatabaseConfig::extensions`
+    #[cfg(feature = "sqlite-vec")]
+    pub fn sqlite_vec_version(&self) -> Result<Option<String>> {
+        self.pool
+            .with_read_connection(|conn| Ok(sqlite_vec_version(conn)))
+    }
+
+    /// Check an embedding's dimension and serialize it, normalized when the
+    /// metric is cosine
+    fn prepare_embedding(&self, embedding: &[f32]) -> Result<Vec<u8>> {
         if embedding.len() != self.config.dimension {
             return Err(anyhow::anyhow!(
                 "Embedding dimension {} doesn't match configured dimension {}",
@@ -104,28 +506,224 @@ impl VectorSearchEngine {
             ));
         }
 
-        let embedding_blob = serialize_vector(embedding)?;
+        if self.config.metric == DistanceMetric::Cosine {
+            let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+            if norm > 0.0 {
+                let normalized: Vec<f32> = embedding.iter().map(|x| x / norm).collect();
+                return serialize_vector(&normalized);
+            }
+        }
+        serialize_vector(embedding)
+    }
+
+    /// Fail unless `model_name` is unknown or was stored under this engine's
+    /// metric
+    fn check_model_metric(&self, conn: &rusqlite::Connection, model_name: &str) -> Result<()> {
+        let recorded: Option<String> = conn
+            .query_row(
+                "SELECT metric FROM embedding_models WHERE model_name = ?1",
+                [model_name],
+                |row| row.get(0),
+            )
+            .optional()?;
+
+        match recorded {
+            Some(metric) if metric != self.config.metric.as_str() => Err(anyhow::anyhow!(
+                "Model '{}' uses the {} metric, but vector search is configured for {}",
+                model_name,
+                metric,
+                self.config.metric
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    /// Record this engine's metric for `model_name` if it has none yet
+    fn register_model(&self, tx: &rusqlite::Transaction, model_name: &str) -> Result<()> {
+        self.check_model_metric(tx, model_name)?;
+        tx.execute(
+            "INSERT OR IGNORE INTO embedding_models (model_name, metric) VALUES (?1, ?2)",
+            [model_name, self.config.metric.as_str()],
+        )?;
+        Ok(())
+    }
+
+    /// Store embedding for a memory
+    pub fn store_embedding(
+        &self,
+        memory_id: &str,
+        embedding: &[f32],
+        model_name: &str,
+    ) -> Result<()> {
+        let embedding_blob = self.prepare_embedding(embedding)?;
 
         self.pool.with_write_transaction(|tx| {
+            self.register_model(tx, model_name)?;
+            let replaced = tx
+                .query_row(
+                    "SELECT 1 FROM memory_embeddings WHERE memory_id = ?1",
+                    [memory_id],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some();
             tx.execute(
                 r#"
-                INSERT OR REPLACE INTO memory_embeddings 
+                INSERT OR REPLACE INTO memory_embeddings
                 (memory_id, embedding, model_name, dimension, created_at)
                 VALUES (?1, ?2, ?3, ?4, datetime('now'))
                 "#,
                 rusqlite::params![memory_id, embedding_blob, model_name, self.config.dimension],
             )?;
-            Ok(())
+            self.update_session_embedding(tx, memory_id, model_name, &embedding_blob, replaced)
         })
     }
 
-    /// Search for similar memories using vector similarity
-    pub fn search_similar(
+    /// Fold a newly stored memory embedding into its session's aggregate
+    ///
+    /// A replaced embedding's old contribution can't be subtracted reliably,
+    /// so the session is rebuilt instead, as it is when it has no aggregate.
+    fn update_session_embedding(
+        &self,
+        tx: &rusqlite::Transaction,
+        memory_id: &str,
+        model_name: &str,
+        embedding_blob: &[u8],
+        replaced: bool,
+    ) -> Result<()> {
+        let memory: Option<(String, f32)> = tx
+            .query_row(
+                "SELECT session_id, importance FROM memories WHERE id = ?1",
+                [memory_id],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((session_id, importance)) = memory else {
+            return Ok(());
+        };
+
+        if replaced {
+            tx.execute(
+                "DELETE FROM session_embeddings WHERE session_id = ?1",
+                [&session_id],
+            )?;
+            return self.rebuild_session_embedding(tx, &session_id, model_name);
+        }
+
+        let aggregate: Option<(Vec<u8>, f32)> = tx
+            .query_row(
+                "SELECT weighted_sum, total_weight FROM session_embeddings
+                 WHERE session_id = ?1 AND model_name = ?2",
+                [&session_id, model_name],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((sum_blob, total_weight)) = aggregate else {
+            return self.rebuild_session_embedding(tx, &session_id, model_name);
+        };
+
+        let mut weighted_sum = deserialize_vector(&sum_blob)?;
+        let embedding = deserialize_vector(embedding_blob)?;
+        if weighted_sum.len() != embedding.len() {
+            return self.rebuild_session_embedding(tx, &session_id, model_name);
+        }
+        let weight = importance.max(MIN_SESSION_WEIGHT);
+        for (sum, value) in weighted_sum.iter_mut().zip(&embedding) {
+            *sum += weight * value;
+        }
+
+        tx.execute(
+            r#"
+            UPDATE session_embeddings
+            SET weighted_sum = ?3, total_weight = ?4, memory_count = memory_count + 1,
+                updated_at = datetime('now')
+            WHERE session_id = ?1 AND model_name = ?2
+            "#,
+            rusqlite::params![
+                session_id,
+                model_name,
+                serialize_vector(&weighted_sum)?,
+                total_weight + weight
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// Recompute a session's aggregate for one model from its memories
+    fn rebuild_session_embedding(
+        &self,
+        tx: &rusqlite::Transaction,
+        session_id: &str,
+        model_name: &str,
+    ) -> Result<()> {
+        let mut stmt = tx.prepare(SESSION_MEMBERS_SQL)?;
+        let members = stmt
+            .query_map([session_id, model_name], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, f32>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut user_id = None;
+        let mut weighted_sum = vec![0.0f32; self.config.dimension];
+        let mut total_weight = 0.0f32;
+        let mut memory_count = 0i64;
+        for (member_user_id, importance, blob) in members {
+            let embedding = deserialize_vector(&blob)?;
+            // Embeddings of another dimension are never searchable anyway
+            if embedding.len() != self.config.dimension {
+                continue;
+            }
+            let weight = importance.max(MIN_SESSION_WEIGHT);
+            for (sum, value) in weighted_sum.iter_mut().zip(&embedding) {
+                *sum += weight * value;
+            }
+            total_weight += weight;
+            memory_count += 1;
+            user_id = Some(member_user_id);
+        }
+
+        match user_id {
+            Some(user_id) => tx.execute(
+                r#"
+                INSERT OR REPLACE INTO session_embeddings
+                (session_id, model_name, user_id, weighted_sum, total_weight, memory_count, updated_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, datetime('now'))
+                "#,
+                rusqlite::params![
+                    session_id,
+                    model_name,
+                    user_id,
+                    serialize_vector(&weighted_sum)?,
+                    total_weight,
+                    memory_count
+                ],
+            )?,
+            None => tx.execute(
+                "DELETE FROM session_embeddings WHERE session_id = ?1 AND model_name = ?2",
+                [session_id, model_name],
+            )?,
+        };
+        Ok(())
+    }
+
+    /// Rank a user's sessions by how close their aggregated embedding is to
+    /// `query_embedding`
+    ///
+    /// A session's embedding is the mean of its memory embeddings, weighted
+    /// by importance. Averaging pulls scores towards each other, so the
+    /// similarity threshold isn't applied; the best `limit` sessions are
+    /// returned whatever their score.
+    pub fn search_sessions(
         &self,
+        user_id: &str,
         query_embedding: &[f32],
         model_name: &str,
         limit: Option<usize>,
-    ) -> Result<Vec<VectorSearchResult>> {
+    ) -> Result<Vec<SessionSearchResult>> {
         if query_embedding.len() != self.config.dimension {
             return Err(anyhow::anyhow!(
                 "Query embedding dimension {} doesn't match configured dimension {}",
@@ -134,36 +732,129 @@ impl VectorSearchEngine {
             ));
         }
 
-        let query_blob = serialize_vector(query_embedding)?;
         let limit = limit
             .unwrap_or(self.config.max_results)
             .min(self.config.max_results);
 
+        // Read-only databases rank sessions by the aggregates already stored
+        if self.pool.is_read_only() {
+            self.pool
+                .with_read_connection(|conn| self.check_model_metric(conn, model_name))?;
+        } else {
+            self.pool.with_write_transaction(|tx| {
+                self.check_model_metric(tx, model_name)?;
+
+                let stale = {
+                    let mut stmt = tx.prepare(STALE_SESSIONS_SQL)?;
+                    let rows =
+                        stmt.query_map([user_id, model_name], |row| row.get::<_, String>(0))?;
+                    rows.collect::<rusqlite::Result<Vec<_>>>()?
+                };
+                for session_id in &stale {
+                    self.rebuild_session_embedding(tx, session_id, model_name)?;
+                }
+                if !stale.is_empty() {
+                    tracing::debug!(
+                        count = stale.len(),
+                        model = model_name,
+                        "Rebuilt session embeddings"
+                    );
+                }
+                Ok(())
+            })?;
+        }
+
         self.pool.with_read_connection(|conn| {
             let mut stmt = conn.prepare(
                 r#"
-                SELECT 
-                    m.id, m.user_id, m.session_id, m.content, m.importance,
-                    m.created_at, m.updated_at,
-                    cosine_similarity(e.embedding, ?1) as similarity
-                FROM memories m
-                INNER JOIN memory_embeddings e ON m.id = e.memory_id
-                WHERE e.model_name = ?2 
-                    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
-                    AND cosine_similarity(e.embedding, ?1) >= ?3
-                ORDER BY similarity DESC
-                LIMIT ?4
+                SELECT e.session_id, e.weighted_sum, e.total_weight, e.memory_count, s.name
+                FROM session_embeddings e
+                LEFT JOIN sessions s ON s.id = e.session_id
+                WHERE e.user_id = ?1 AND e.model_name = ?2 AND e.total_weight > 0
+                  AND COALESCE(s.status, 'active') != 'archived'
                 "#,
             )?;
+            let rows = stmt
+                .query_map([user_id, model_name], |row| {
+                    Ok((
+                        row.get::<_, String>(0)?,
+                        row.get::<_, Vec<u8>>(1)?,
+                        row.get::<_, f32>(2)?,
+                        row.get::<_, i64>(3)?,
+                        row.get::<_, Option<String>>(4)?,
+                    ))
+                })?
+                .collect::<rusqlite::Result<Vec<_>>>()?;
 
-            let results = stmt.query_map(
-                rusqlite::params![
-                    query_blob,
-                    model_name,
-                    self.config.similarity_threshold,
-                    limit
-                ],
-                |row| {
+            let mut results = Vec::with_capacity(rows.len());
+            for (session_id, sum_blob, total_weight, memory_count, name) in rows {
+                let mean: Vec<f32> = deserialize_vector(&sum_blob)?
+                    .into_iter()
+                    .map(|value| value / total_weight)
+                    .collect();
+                results.push(SessionSearchResult {
+                    session_id,
+                    user_id: user_id.to_string(),
+                    name,
+                    similarity: self.config.metric.score(&mean, query_embedding),
+                    memory_count: memory_count as usize,
+                    metric: self.config.metric,
+                });
+            }
+
+            results.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+            results.truncate(limit);
+            Ok(results)
+        })
+    }
+
+    /// Search for similar memories using vector similarity
+    ///
+    /// Only memories matching `filter` are considered; the filter is applied
+    /// in SQL, so `limit` counts matching results.
+    pub fn search_similar(
+        &self,
+        query_embedding: &[f32],
+        model_name: &str,
+        limit: Option<usize>,
+        filter: &VectorFilter,
+    ) -> Result<Vec<VectorSearchResult>> {
+        if query_embedding.len() != self.config.dimension {
+            return Err(anyhow::anyhow!(
+                "Query embedding dimension {} doesn't match configured dimension {}",
+                query_embedding.len(),
+                self.config.dimension
+            ));
+        }
+        filter.validate().context("Filter validation failed")?;
+
+        let query_blob = serialize_vector(query_embedding)?;
+        let limit = limit
+            .unwrap_or(self.config.max_results)
+            .min(self.config.max_results);
+
+        let (filter_sql, filter_params) = filter.sql_conditions(6);
+
+        let metric = self.config.metric.as_str();
+
+        self.pool.with_read_connection(|conn| {
+            self.check_model_metric(conn, model_name)?;
+            let sql = SEARCH_SIMILAR_SQL
+                .replace("{filters}", &filter_sql)
+                .replace("{similarity}", &self.similarity_sql(conn, 5));
+            let mut stmt = conn.prepare(&sql)?;
+
+            let mut params: Vec<&dyn rusqlite::ToSql> = vec![
+                &query_blob,
+                &model_name,
+                &self.config.similarity_threshold,
+                &limit,
+                &metric,
+            ];
+            params.extend(filter_params.iter().map(|p| p.as_ref()));
+
+            self.pool.slow_query_log().track(conn, &sql, &params, || {
+                let results = stmt.query_map(&params[..], |row| {
                     Ok(VectorSearchResult {
                         memory_id: row.get("id")?,
                         user_id: row.get("user_id")?,
@@ -172,20 +863,18 @@ impl VectorSearchEngine {
                         importance: row.get("importance")?,
                         similarity: row.get("similarity")?,
                         created_at: row.get("created_at")?,
+                        metric: self.config.metric,
                     })
-                },
-            )?;
-
-            let mut search_results = Vec::new();
-            for result in results {
-                search_results.push(result?);
-            }
+                })?;
 
-            Ok(search_results)
+                Ok(results.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
         })
     }
 
-    /// Hybrid search combining text and vector search
+    /// Hybrid search combining text and vector search, restricted to
+    /// memories matching `filter`
+    #[allow(clippy::too_many_arguments)]
     pub fn hybrid_search(
         &self,
         text_query: &str,
@@ -194,51 +883,43 @@ impl VectorSearchEngine {
         text_weight: f32,
         vector_weight: f32,
         limit: Option<usize>,
+        filter: &VectorFilter,
     ) -> Result<Vec<HybridSearchResult>> {
+        filter.validate().context("Filter validation failed")?;
+
         let limit = limit
             .unwrap_or(self.config.max_results)
             .min(self.config.max_results);
         let query_blob = serialize_vector(vector_query)?;
 
+        let (filter_sql, filter_params) = filter.sql_conditions(9);
+
+        let metric = self.config.metric.as_str();
+
         self.pool.with_read_connection(|conn| {
-            let mut stmt = conn.prepare(
-                r#"
-                SELECT 
-                    m.id, m.user_id, m.session_id, m.content, m.importance,
-                    m.created_at, m.updated_at,
-                    cosine_similarity(e.embedding, ?1) as vector_similarity,
-                    CASE 
-                        WHEN fts.content IS NOT NULL THEN 1.0 
-                        ELSE 0.0 
-                    END as text_match,
-                    (?2 * CASE WHEN fts.content IS NOT NULL THEN 1.0 ELSE 0.0 END + 
-                     ?3 * cosine_similarity(e.embedding, ?1)) as combined_score
-                FROM memories m
-                INNER JOIN memory_embeddings e ON m.id = e.memory_id
-                LEFT JOIN memories_fts fts ON m.rowid = fts.rowid AND fts MATCH ?4
-                WHERE e.model_name = ?5 
-                    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
-                    AND (?2 * CASE WHEN fts.content IS NOT NULL THEN 1.0 ELSE 0.0 END + 
-                         ?3 * cosine_similarity(e.embedding, ?1)) >= ?6
-                ORDER BY combined_score DESC
-                LIMIT ?7
-                "#,
-            )?;
+            self.check_model_metric(conn, model_name)?;
+            let sql = HYBRID_SEARCH_SQL
+                .replace("{filters}", &filter_sql)
+                .replace("{similarity}", &self.similarity_sql(conn, 8));
+            let mut stmt = conn.prepare(&sql)?;
 
             let min_combined_score =
                 text_weight * 0.5 + vector_weight * self.config.similarity_threshold;
 
-            let results = stmt.query_map(
-                rusqlite::params![
-                    query_blob,
-                    text_weight,
-                    vector_weight,
-                    text_query,
-                    model_name,
-                    min_combined_score,
-                    limit
-                ],
-                |row| {
+            let mut params: Vec<&dyn rusqlite::ToSql> = vec![
+                &query_blob,
+                &text_weight,
+                &vector_weight,
+                &text_query,
+                &model_name,
+                &min_combined_score,
+                &limit,
+                &metric,
+            ];
+            params.extend(filter_params.iter().map(|p| p.as_ref()));
+
+            self.pool.slow_query_log().track(conn, &sql, &params, || {
+                let results = stmt.query_map(&params[..], |row| {
                     Ok(HybridSearchResult {
                         memory_id: row.get("id")?,
                         user_id: row.get("user_id")?,
@@ -249,17 +930,61 @@ impl VectorSearchEngine {
                         text_match: row.get("text_match")?,
                         combined_score: row.get("combined_score")?,
                         created_at: row.get("created_at")?,
+                        metric: self.config.metric,
                     })
-                },
+                })?;
+
+                Ok(results.collect::<rusqlite::Result<Vec<_>>>()?)
+            })
+        })
+    }
+
+    /// Embeddings of a user's live memories from the model that embedded most
+    /// of them
+    ///
+    /// Embeddings from other models or of another dimension can't be compared
+    /// with these and are left out. None if the user has no usable embeddings.
+    pub fn user_embeddings(&self, user_id: &str) -> Result<Option<ModelEmbeddings>> {
+        let rows = self.pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                r#"
+                SELECT e.memory_id, e.model_name, e.embedding
+                FROM memory_embeddings e
+                INNER JOIN memories m ON m.id = e.memory_id
+                WHERE m.user_id = ?1
+                    AND (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+                "#,
             )?;
+            let rows = stmt.query_map([user_id], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Vec<u8>>(2)?,
+                ))
+            })?;
+            Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+        })?;
 
-            let mut search_results = Vec::new();
-            for result in results {
-                search_results.push(result?);
+        let mut by_model: HashMap<String, HashMap<String, Vec<f32>>> = HashMap::new();
+        for (memory_id, model_name, blob) in rows {
+            let embedding = deserialize_vector(&blob)?;
+            if embedding.len() == self.config.dimension {
+                by_model
+                    .entry(model_name)
+                    .or_default()
+                    .insert(memory_id, embedding);
             }
+        }
 
-            Ok(search_results)
-        })
+        Ok(by_model
+            .into_iter()
+            .max_by(|(a_model, a), (b_model, b)| {
+                a.len().cmp(&b.len()).then_with(|| b_model.cmp(a_model))
+            })
+            .map(|(model_name, embeddings)| ModelEmbeddings {
+                model_name,
+                embeddings,
+            }))
     }
 
     /// Get embedding for a memory if it exists
@@ -271,7 +996,7 @@ impl VectorSearchEngine {
 
             let embedding_blob: Option<Vec<u8>> = stmt
                 .query_row(rusqlite::params![memory_id, model_name], |row| {
-                    Ok(row.get("embedding")?)
+                    row.get("embedding")
                 })
                 .optional()?;
 
@@ -296,32 +1021,409 @@ impl VectorSearchEngine {
         })
     }
 
-    /// Get vector search statistics
-    pub fn get_vector_stats(&self) -> Result<VectorStats> {
-        self.pool.with_read_connection(|conn| {
-            let total_embeddings: i64 =
-                conn.query_row("SELECT COUNT(*) FROM memory_embeddings", [], |row| {
-                    Ok(row.get(0)?)
-                })?;
+    /// Replace a memory's embedding with one generated by `embedder`
+    ///
+    /// Whatever model produced the current embedding, the new one takes its
+    /// place. Returns false if the memory doesn't exist or has expired.
+    pub fn reembed(&self, memory_id: &str, embedder: &dyn Embedder) -> Result<bool> {
+        let content: Option<String> = self.pool.with_read_connection(|conn| {
+            Ok(conn
+                .query_row(
+                    "SELECT content FROM memories
+                     WHERE id = ?1 AND (expires_at IS NULL OR expires_at > datetime('now'))",
+                    [memory_id],
+                    |row| row.get(0),
+                )
+                .optional()?)
+        })?;
+        let Some(content) = content else {
+            return Ok(false);
+        };
 
-            let mut stmt = conn.prepare(
-                "SELECT model_name, COUNT(*) FROM memory_embeddings GROUP BY model_name",
-            )?;
+        let embedding = self
+            .embed_cached(embedder, &[content.as_str()])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Embedder returned no embedding"))?;
+        self.store_embedding(memory_id, &embedding, embedder.model_name())?;
 
-            let model_counts = stmt.query_map([], |row| {
-                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
-            })?;
+        tracing::debug!(
+            memory_id = memory_id,
+            model = embedder.model_name(),
+            "Re-embedded memory"
+        );
+        Ok(true)
+    }
 
-            let mut models = HashMap::new();
-            for result in model_counts {
-                let (model, count) = result?;
-                models.insert(model, count);
-            }
+    /// Delete embeddings whose memory no longer exists, returning how many
+    /// were removed
+    pub fn purge_orphaned_embeddings(&self) -> Result<usize> {
+        self.pool
+            .with_write_transaction(|tx| Ok(tx.execute(PURGE_ORPHANED_SQL, [])?))
+    }
+
+    /// Embed `texts` with `embedder`, taking embeddings of content the
+    /// model embedded before from the cache and caching the rest
+    ///
+    /// Identical texts in one call are embedded once. Cached embeddings of
+    /// another length than the configured dimension are embedded again.
+    pub fn embed_cached(&self, embedder: &dyn Embedder, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let model_name = embedder.model_name();
+        let hashes: Vec<String> = texts.iter().map(|text| content_hash(text)).collect();
+
+        let mut embeddings: HashMap<String, Vec<f32>> = self.pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT embedding FROM embedding_cache
+                 WHERE content_hash = ?1 AND model_name = ?2",
+            )?;
+            let mut cached = HashMap::new();
+            for hash in &hashes {
+                if cached.contains_key(hash) {
+                    continue;
+                }
+                let blob: Option<Vec<u8>> = stmt
+                    .query_row(rusqlite::params![hash, model_name], |row| row.get(0))
+                    .optional()?;
+                if let Some(Ok(embedding)) = blob.map(|blob| deserialize_vector(&blob)) {
+                    if embedding.len() == self.config.dimension {
+                        cached.insert(hash.clone(), embedding);
+                    }
+                }
+            }
+            Ok(cached)
+        })?;
+
+        // Every occurrence of a cached text is a hit, and so is every
+        // repeat of a missing one within the call
+        let mut misses = Vec::new();
+        let mut hits: HashMap<&str, i64> = HashMap::new();
+        let mut seen = HashSet::new();
+        for (hash, text) in hashes.iter().zip(texts) {
+            if !embeddings.contains_key(hash) && seen.insert(hash.as_str()) {
+                misses.push((hash.as_str(), *text));
+            } else {
+                *hits.entry(hash.as_str()).or_default() += 1;
+            }
+        }
+
+        let generated = if misses.is_empty() {
+            Vec::new()
+        } else {
+            let texts: Vec<&str> = misses.iter().map(|(_, text)| *text).collect();
+            let generated = embedder.embed(&texts)?;
+            if generated.len() != texts.len() {
+                return Err(anyhow::anyhow!(
+                    "Embedder returned {} embeddings for {} texts",
+                    generated.len(),
+                    texts.len()
+                ));
+            }
+            generated
+        };
+
+        if !self.pool.is_read_only() {
+            self.pool.with_write_transaction(|tx| {
+                let mut insert = tx.prepare(
+                    "INSERT OR REPLACE INTO embedding_cache (content_hash, model_name, embedding)
+                     VALUES (?1, ?2, ?3)",
+                )?;
+                for ((hash, _), embedding) in misses.iter().zip(&generated) {
+                    insert.execute(rusqlite::params![
+                        hash,
+                        model_name,
+                        serialize_vector(embedding)?
+                    ])?;
+                }
+                let mut hit = tx.prepare(
+                    "UPDATE embedding_cache SET hits = hits + ?3, last_used_at = datetime('now')
+                     WHERE content_hash = ?1 AND model_name = ?2",
+                )?;
+                for (hash, count) in &hits {
+                    hit.execute(rusqlite::params![hash, model_name, count])?;
+                }
+                Ok(())
+            })?;
+        }
+        tracing::debug!(
+            model = model_name,
+            hits = hits.values().sum::<i64>(),
+            misses = misses.len(),
+            "Embedded through the cache"
+        );
+
+        for ((hash, _), embedding) in misses.iter().zip(generated) {
+            embeddings.insert(hash.to_string(), embedding);
+        }
+        Ok(hashes.iter().map(|hash| embeddings[hash].clone()).collect())
+    }
+
+    /// Size and hit rate of the embedding cache; empty for databases never
+    /// opened writable with vector search
+    pub fn embedding_cache_stats(&self) -> Result<EmbeddingCacheStats> {
+        self.pool.with_read_connection(|conn| {
+            let has_table = conn
+                .query_row(
+                    "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'embedding_cache'",
+                    [],
+                    |_| Ok(()),
+                )
+                .optional()?
+                .is_some();
+            if !has_table {
+                return Ok(EmbeddingCacheStats::default());
+            }
+
+            let (entries, bytes, hits): (i64, i64, i64) = conn.query_row(
+                "SELECT COUNT(*), COALESCE(SUM(length(embedding) + length(content_hash)), 0),
+                        COALESCE(SUM(hits), 0)
+                 FROM embedding_cache",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+            )?;
+            Ok(EmbeddingCacheStats {
+                entries,
+                bytes,
+                hits,
+                hit_rate: if entries + hits > 0 {
+                    hits as f32 / (entries + hits) as f32
+                } else {
+                    0.0
+                },
+            })
+        })
+    }
+
+    /// Drop cached embeddings, only `model_name`'s if given and only those
+    /// last used before `unused_since` if given, returning how many
+    pub fn purge_embedding_cache(
+        &self,
+        model_name: Option<&str>,
+        unused_since: Option<DateTime<Utc>>,
+    ) -> Result<usize> {
+        let purged = self.pool.with_write_transaction(|tx| {
+            Ok(tx.execute(
+                "DELETE FROM embedding_cache
+                 WHERE (?1 IS NULL OR model_name = ?1) AND (?2 IS NULL OR last_used_at < ?2)",
+                rusqlite::params![model_name, unused_since],
+            )?)
+        })?;
+        tracing::info!(count = purged, model = model_name, "Purged embedding cache");
+        Ok(purged)
+    }
+
+    /// Generate and store embeddings for every memory that lacks one.
+    ///
+    /// Memories are embedded `batch_size` at a time and each batch is stored in
+    /// its own transaction, with `on_progress` called after it commits. Only
+    /// memories still missing an embedding are picked up, so an interrupted
+    /// backfill resumes where it stopped when run again.
+    pub fn backfill(
+        &self,
+        embedder: &dyn Embedder,
+        batch_size: usize,
+        mut on_progress: impl FnMut(&BackfillProgress),
+    ) -> Result<BackfillProgress> {
+        if batch_size == 0 {
+            return Err(anyhow::anyhow!("Batch size must be at least 1"));
+        }
+
+        let total = self.pool.with_read_connection(|conn| {
+            Ok(conn.query_row(
+                "SELECT COUNT(*) FROM memories m
+                 WHERE (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+                     AND NOT EXISTS (SELECT 1 FROM memory_embeddings e WHERE e.memory_id = m.id)",
+                [],
+                |row| row.get::<_, i64>(0),
+            )? as usize)
+        })?;
+
+        let mut progress = BackfillProgress { total, embedded: 0 };
+        let mut cursor = 0i64;
+
+        loop {
+            let batch = self.pool.with_read_connection(|conn| {
+                let mut stmt = conn.prepare(MISSING_EMBEDDINGS_SQL)?;
+                let rows = stmt.query_map(rusqlite::params![cursor, batch_size as i64], |row| {
+                    Ok((
+                        row.get::<_, i64>(0)?,
+                        row.get::<_, String>(1)?,
+                        row.get::<_, String>(2)?,
+                    ))
+                })?;
+                Ok(rows.collect::<rusqlite::Result<Vec<_>>>()?)
+            })?;
+
+            let Some((last_rowid, _, _)) = batch.last() else {
+                break;
+            };
+            cursor = *last_rowid;
+
+            let texts: Vec<&str> = batch
+                .iter()
+                .map(|(_, _, content)| content.as_str())
+                .collect();
+            let embeddings = self.embed_cached(embedder, &texts)?;
+            let blobs = embeddings
+                .iter()
+                .map(|embedding| self.prepare_embedding(embedding))
+                .collect::<Result<Vec<_>>>()?;
+
+            self.pool.with_write_transaction(|tx| {
+                self.register_model(tx, embedder.model_name())?;
+                let mut stmt = tx.prepare(
+                    r#"
+                    INSERT OR REPLACE INTO memory_embeddings
+                    (memory_id, embedding, model_name, dimension, created_at)
+                    VALUES (?1, ?2, ?3, ?4, datetime('now'))
+                    "#,
+                )?;
+                for ((_, memory_id, _), blob) in batch.iter().zip(&blobs) {
+                    stmt.execute(rusqlite::params![
+                        memory_id,
+                        blob,
+                        embedder.model_name(),
+                        self.config.dimension
+                    ])?;
+                    self.update_session_embedding(
+                        tx,
+                        memory_id,
+                        embedder.model_name(),
+                        blob,
+                        false,
+                    )?;
+                }
+                Ok(())
+            })?;
+
+            progress.embedded += batch.len();
+            tracing::debug!(
+                model = embedder.model_name(),
+                embedded = progress.embedded,
+                total = progress.total,
+                "Backfilled embedding batch"
+            );
+            on_progress(&progress);
+        }
+
+        Ok(progress)
+    }
+
+    /// Get vector search statistics
+    ///
+    /// Embeddings of expired memories that decay hasn't removed yet aren't
+    /// counted. Norms and the load time are measured on a sample of up to
+    /// `DIAGNOSTIC_SAMPLE_SIZE` embeddings and extrapolated, so they're
+    /// approximate on large databases.
+    pub fn get_vector_stats(&self) -> Result<VectorStats> {
+        let embedding_cache = self.embedding_cache_stats()?;
+        self.pool.with_read_connection(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT e.model_name, COUNT(*) FROM memory_embeddings e
+                 INNER JOIN memories m ON m.id = e.memory_id
+                 WHERE m.expires_at IS NULL OR m.expires_at > datetime('now')
+                 GROUP BY e.model_name",
+            )?;
+
+            let model_counts = stmt.query_map([], |row| {
+                Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+            })?;
+
+            let mut models = HashMap::new();
+            let mut total_embeddings = 0;
+            for result in model_counts {
+                let (model, count) = result?;
+                total_embeddings += count;
+                models.insert(model, count);
+            }
+
+            let orphaned_embeddings = conn.query_row(
+                "SELECT COUNT(*) FROM memory_embeddings e
+                 WHERE NOT EXISTS (SELECT 1 FROM memories m WHERE m.id = e.memory_id)",
+                [],
+                |row| row.get(0),
+            )?;
+
+            let dimension_mismatches = conn.query_row(
+                "SELECT COUNT(*) FROM memory_embeddings
+                 WHERE dimension != ?1 OR length(embedding) != ?1 * 4",
+                [self.config.dimension as i64],
+                |row| row.get(0),
+            )?;
+
+            let mut stmt = conn.prepare(
+                "SELECT model_name FROM embedding_models WHERE metric != ?1 ORDER BY model_name",
+            )?;
+            let metric_mismatches = stmt
+                .query_map([self.config.metric.as_str()], |row| row.get(0))?
+                .collect::<rusqlite::Result<Vec<String>>>()?;
+
+            let missing_embeddings = conn.query_row(
+                "SELECT COUNT(*) FROM memories m
+                 WHERE (m.expires_at IS NULL OR m.expires_at > datetime('now'))
+                     AND NOT EXISTS (SELECT 1 FROM memory_embeddings e WHERE e.memory_id = m.id)",
+                [],
+                |row| row.get(0),
+            )?;
+
+            let index_memory_bytes = conn.query_row(
+                "SELECT COALESCE(SUM(length(e.embedding) + length(e.memory_id)), 0)
+                 FROM memory_embeddings e
+                 INNER JOIN memories m ON m.id = e.memory_id
+                 WHERE m.expires_at IS NULL OR m.expires_at > datetime('now')",
+                [],
+                |row| row.get(0),
+            )?;
+
+            // Read and decode the sample the way building an in-memory index
+            // would, timing it
+            let started = std::time::Instant::now();
+            let mut stmt = conn.prepare(
+                "SELECT e.embedding FROM memory_embeddings e
+                 INNER JOIN memories m ON m.id = e.memory_id
+                 WHERE m.expires_at IS NULL OR m.expires_at > datetime('now')
+                 LIMIT ?1",
+            )?;
+            let mut rows = stmt.query([DIAGNOSTIC_SAMPLE_SIZE as i64])?;
+            let mut sampled_embeddings = 0;
+            let mut zero_norm_embeddings = 0;
+            let mut norm_sum = 0.0f64;
+            while let Some(row) = rows.next()? {
+                let Ok(embedding) = deserialize_vector(&row.get::<_, Vec<u8>>(0)?) else {
+                    continue;
+                };
+                let norm = embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+                if norm == 0.0 {
+                    zero_norm_embeddings += 1;
+                }
+                norm_sum += norm as f64;
+                sampled_embeddings += 1;
+            }
+            let sample_ms = started.elapsed().as_secs_f64() * 1000.0;
+
+            let (average_norm, index_build_ms) = if sampled_embeddings == 0 {
+                (None, 0.0)
+            } else {
+                (
+                    Some((norm_sum / sampled_embeddings as f64) as f32),
+                    sample_ms * total_embeddings as f64 / sampled_embeddings as f64,
+                )
+            };
 
             Ok(VectorStats {
                 total_embeddings,
                 models,
                 dimension: self.config.dimension,
+                metric: self.config.metric,
+                orphaned_embeddings,
+                metric_mismatches,
+                dimension_mismatches,
+                missing_embeddings,
+                sampled_embeddings,
+                average_norm,
+                zero_norm_embeddings,
+                index_build_ms,
+                index_memory_bytes,
+                embedding_cache: embedding_cache.clone(),
             })
         })
     }
@@ -337,6 +1439,8 @@ pub struct VectorSearchResult {
     pub importance: f32,
     pub similarity: f32,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// How `similarity` was computed
+    pub metric: DistanceMetric,
 }
 
 /// Hybrid search result combining text and vector search
@@ -351,14 +1455,289 @@ pub struct HybridSearchResult {
     pub text_match: f32,
     pub combined_score: f32,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    /// How `vector_similarity` was computed
+    pub metric: DistanceMetric,
 }
 
-/// Vector search statistics
+/// One model's embeddings, keyed by memory ID
+#[derive(Debug, Clone, Default)]
+pub struct ModelEmbeddings {
+    pub model_name: String,
+    pub embeddings: HashMap<String, Vec<f32>>,
+}
+
+/// Session ranked by its aggregated embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSearchResult {
+    pub session_id: String,
+    pub user_id: String,
+    /// None if the session has memories but no `sessions` row
+    pub name: Option<String>,
+    pub similarity: f32,
+    /// Embedded memories the session's embedding averages
+    pub memory_count: usize,
+    /// How `similarity` was computed
+    pub metric: DistanceMetric,
+}
+
+/// Entries in the embedding cache and how often they saved calling the
+/// model
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbeddingCacheStats {
+    pub entries: i64,
+    /// Bytes of embeddings and content hashes
+    pub bytes: i64,
+    /// Embeddings served from the cache instead of the model
+    pub hits: i64,
+    /// Hits over all lookups, counting one miss per entry; purging resets
+    /// the count of what it drops
+    pub hit_rate: f32,
+}
+
+/// Vector search statistics and index health
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct VectorStats {
     pub total_embeddings: i64,
     pub models: HashMap<String, i64>,
     pub dimension: usize,
+    pub metric: DistanceMetric,
+    /// Embeddings whose memory no longer exists
+    pub orphaned_embeddings: i64,
+    /// Models stored under a different metric; searching them fails
+    pub metric_mismatches: Vec<String>,
+    /// Embeddings whose length isn't the configured dimension; searches
+    /// never match them
+    pub dimension_mismatches: i64,
+    /// Live memories without an embedding
+    pub missing_embeddings: i64,
+    /// Embeddings decoded for the norm and load time figures
+    pub sampled_embeddings: usize,
+    /// Mean L2 norm of the sampled embeddings
+    pub average_norm: Option<f32>,
+    /// Sampled embeddings with a zero norm; their similarity is always 0
+    pub zero_norm_embeddings: usize,
+    /// Estimated time to load every embedding into an in-memory index
+    pub index_build_ms: f64,
+    /// Bytes of embeddings and memory IDs such an index would hold
+    pub index_memory_bytes: i64,
+    pub embedding_cache: EmbeddingCacheStats,
+}
+
+impl VectorStats {
+    /// Human-readable descriptions of anything that looks wrong
+    pub fn anomalies(&self) -> Vec<String> {
+        let mut anomalies = Vec::new();
+        if self.orphaned_embeddings > 0 {
+            anomalies.push(format!(
+                "{} embeddings belong to deleted memories",
+                self.orphaned_embeddings
+            ));
+        }
+        if self.dimension_mismatches > 0 {
+            anomalies.push(format!(
+                "{} embeddings don't have the configured dimension {}",
+                self.dimension_mismatches, self.dimension
+            ));
+        }
+        if !self.metric_mismatches.is_empty() {
+            anomalies.push(format!(
+                "Models stored under another metric than {}: {}",
+                self.metric,
+                self.metric_mismatches.join(", ")
+            ));
+        }
+        if self.zero_norm_embeddings > 0 {
+            anomalies.push(format!(
+                "{} of {} sampled embeddings are all zeros",
+                self.zero_norm_embeddings, self.sampled_embeddings
+            ));
+        }
+        if self.missing_embeddings > 0 {
+            anomalies.push(format!(
+                "{} memories have no embedding",
+                self.missing_embeddings
+            ));
+        }
+        if self.models.len() > 1 {
+            anomalies.push(format!(
+                "Embeddings come from {} models; searches only compare within one",
+                self.models.len()
+            ));
+        }
+        anomalies
+    }
+}
+
+/// Source of embeddings for [`VectorSearchEngine::backfill`]
+pub trait Embedder {
+    /// Model name stored alongside the generated embeddings
+    fn model_name(&self) -> &str;
+
+    /// Embed each text, returning one vector per input in the same order
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>>;
+}
+
+/// Embeds by running an external command once per batch
+pub struct CommandEmbedder {
+    pub model: String,
+    /// Shell command reading a JSON array of texts on stdin and writing a
+    /// JSON array of embeddings on stdout
+    pub command: String,
+}
+
+impl Embedder for CommandEmbedder {
+    fn model_name(&self) -> &str {
+        &self.model
+    }
+
+    fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let (shell, flag) = if cfg!(windows) {
+            ("cmd", "/C")
+        } else {
+            ("sh", "-c")
+        };
+        let mut child = Command::new(shell)
+            .arg(flag)
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run embedding command: {}", self.command))?;
+
+        let input = serde_json::to_vec(texts)?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(&input)?;
+        }
+
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(anyhow::anyhow!(
+                "Embedding command exited with {}",
+                output.status
+            ));
+        }
+        serde_json::from_slice(&output.stdout).context("Invalid embeddings JSON from command")
+    }
+}
+
+/// Progress of an embeddings backfill
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct BackfillProgress {
+    /// Memories lacking an embedding when the backfill started
+    pub total: usize,
+    /// Memories embedded so far
+    pub embedded: usize,
+}
+
+/// Similarity to `query` of the `model_name` embedding of each memory
+/// `memories_sql` selects, under the metric the model is stored with, by
+/// memory ID; memories without one are left out
+///
+/// `memories_sql` selects memories with an `id` column and binds `params`
+/// from `?1` on.
+pub(crate) fn embedding_similarities(
+    conn: &rusqlite::Connection,
+    memories_sql: &str,
+    params: &[&dyn rusqlite::ToSql],
+    query: &[f32],
+    model_name: &str,
+) -> Result<HashMap<String, f32>> {
+    let has_table = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'embedding_models'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if !has_table {
+        return Ok(HashMap::new());
+    }
+    let metric: Option<String> = conn
+        .query_row(
+            "SELECT metric FROM embedding_models WHERE model_name = ?1",
+            [model_name],
+            |row| row.get(0),
+        )
+        .optional()?;
+    let Some(metric) = metric else {
+        return Ok(HashMap::new());
+    };
+    let metric: DistanceMetric = metric.parse()?;
+
+    let mut stmt = conn.prepare(&format!(
+        "SELECT e.memory_id, e.embedding FROM memory_embeddings e
+         WHERE e.model_name = ?{} AND e.memory_id IN (SELECT id FROM ({}))",
+        params.len() + 1,
+        memories_sql
+    ))?;
+    let mut params = params.to_vec();
+    params.push(&model_name);
+    let mut rows = stmt.query(&params[..])?;
+    let mut similarities = HashMap::new();
+    while let Some(row) = rows.next()? {
+        let Ok(embedding) = deserialize_vector(&row.get::<_, Vec<u8>>(1)?) else {
+            continue;
+        };
+        similarities.insert(row.get(0)?, metric.score(query, &embedding));
+    }
+    Ok(similarities)
+}
+
+/// Fill in `embedding` and `embedding_model` of `memories` that have a
+/// stored embedding
+pub(crate) fn load_embeddings(
+    conn: &rusqlite::Connection,
+    memories: &mut [super::models::MemoryItem],
+) -> Result<()> {
+    // Databases never opened with vector search have no table to read
+    let has_table = conn
+        .query_row(
+            "SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = 'memory_embeddings'",
+            [],
+            |_| Ok(()),
+        )
+        .optional()?
+        .is_some();
+    if memories.is_empty() || !has_table {
+        return Ok(());
+    }
+
+    let placeholders = vec!["?"; memories.len()].join(", ");
+    let mut stmt = conn.prepare(&format!(
+        "SELECT memory_id, embedding, model_name FROM memory_embeddings WHERE memory_id IN ({})",
+        placeholders
+    ))?;
+    let mut embeddings = stmt
+        .query_map(
+            rusqlite::params_from_iter(memories.iter().map(|memory| &memory.id)),
+            |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    (row.get::<_, Vec<u8>>(1)?, row.get::<_, String>(2)?),
+                ))
+            },
+        )?
+        .collect::<rusqlite::Result<HashMap<_, _>>>()?;
+
+    for memory in memories {
+        if let Some((bytes, model_name)) = embeddings.remove(&memory.id) {
+            memory.embedding = Some(deserialize_vector(&bytes)?);
+            memory.embedding_model = Some(model_name);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(feature = "sqlite-vec")]
+fn sqlite_vec_version(conn: &rusqlite::Connection) -> Option<String> {
+    conn.query_row("SELECT vec_version()", [], |row| row.get(0))
+        .ok()
+}
+
+/// Key of `content` in the embedding cache: its SHA-256, in hex
+fn content_hash(content: &str) -> String {
+    format!("{:x}", Sha256::digest(content.as_bytes()))
 }
 
 /// Serialize vector to binary format for database storage
@@ -372,7 +1751,7 @@ fn serialize_vector(vector: &[f32]) -> Result<Vec<u8>> {
 
 /// Deserialize vector from binary format
 fn deserialize_vector(bytes: &[u8]) -> Result<Vec<f32>> {
-    if bytes.len() % 4 != 0 {
+    if !bytes.len().is_multiple_of(4) {
         return Err(anyhow::anyhow!("Invalid vector byte length"));
     }
 
@@ -406,33 +1785,6 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
-    fn setup_vector_engine() -> (VectorSearchEngine, TempDir) {
-        let temp_dir = TempDir::new().unwrap();
-        let config = super::super::DatabaseConfig {
-            path: temp_dir
-                .path()
-                .join("test.db")
-                .to_string_lossy()
-                .to_string(),
-            max_connections: 2,
-            min_connections: 1,
-            ..Default::default()
-        };
-
-        let pool = ConnectionPool::new(config).unwrap();
-        let vector_config = VectorConfig {
-            dimension: 4, // Small dimension for testing
-            similarity_threshold: 0.5,
-            max_results: 10,
-            enable_approximate_search: false,
-        };
-
-        let engine = VectorSearchEngine::new(pool, vector_config);
-        engine.initialize_schema().unwrap();
-
-        (engine, temp_dir)
-    }
-
     #[test]
     fn test_vector_serialization() {
         let vector = vec![1.0, -0.5, 0.25, 0.0];
@@ -458,19 +1810,20 @@ mod tests {
 
     #[test]
     fn test_store_and_retrieve_embedding() {
-        let (engine, _temp_dir) = setup_vector_engine();
+        // Dot stores vectors as given; cosine would normalize them
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
 
         let embedding = vec![0.1, 0.2, 0.3, 0.4];
-        let memory_id = "test_memory_1";
+        let memory_id = save_test_memory(&database, "embedded");
         let model_name = "test_model";
 
         // Store embedding
         engine
-            .store_embedding(memory_id, &embedding, model_name)
+            .store_embedding(&memory_id, &embedding, model_name)
             .unwrap();
 
         // Retrieve embedding
-        let retrieved = engine.get_embedding(memory_id, model_name).unwrap();
+        let retrieved = engine.get_embedding(&memory_id, model_name).unwrap();
         assert_eq!(retrieved, Some(embedding));
 
         // Test non-existent embedding
@@ -480,17 +1833,20 @@ mod tests {
 
     #[test]
     fn test_vector_stats() {
-        let (engine, _temp_dir) = setup_vector_engine();
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Cosine);
+        let mem1 = save_test_memory(&database, "first");
+        let mem2 = save_test_memory(&database, "second");
+        let mem3 = save_test_memory(&database, "third");
 
         // Add some embeddings
         engine
-            .store_embedding("mem1", &vec![0.1, 0.2, 0.3, 0.4], "model1")
+            .store_embedding(&mem1, &vec![0.1, 0.2, 0.3, 0.4], "model1")
             .unwrap();
         engine
-            .store_embedding("mem2", &vec![0.5, 0.6, 0.7, 0.8], "model1")
+            .store_embedding(&mem2, &vec![0.5, 0.6, 0.7, 0.8], "model1")
             .unwrap();
         engine
-            .store_embedding("mem3", &vec![0.9, 1.0, 1.1, 1.2], "model2")
+            .store_embedding(&mem3, &vec![0.9, 1.0, 1.1, 1.2], "model2")
             .unwrap();
 
         let stats = engine.get_vector_stats().unwrap();
@@ -499,4 +1855,687 @@ mod tests {
         assert_eq!(stats.models.get("model1"), Some(&2));
         assert_eq!(stats.models.get("model2"), Some(&1));
     }
+
+    /// Embeds each text as its length, failing after `fail_after` calls
+    struct StubEmbedder {
+        calls: std::cell::Cell<usize>,
+        fail_after: usize,
+    }
+
+    impl Embedder for StubEmbedder {
+        fn model_name(&self) -> &str {
+            "stub"
+        }
+
+        fn embed(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+            if self.calls.get() == self.fail_after {
+                return Err(anyhow::anyhow!("embedder unavailable"));
+            }
+            self.calls.set(self.calls.get() + 1);
+            Ok(texts
+                .iter()
+                .map(|text| vec![text.len() as f32, 1.0, 0.0, 0.0])
+                .collect())
+        }
+    }
+
+    /// Engine over a full database with a single connection, so
+    /// `vector_similarity` is registered wherever the searches run
+    fn setup_engine_with_database(
+        metric: DistanceMetric,
+    ) -> (super::super::Database, VectorSearchEngine, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let database = super::super::Database::new(super::super::DatabaseConfig {
+            path: temp_dir
+                .path()
+                .join("test.db")
+                .to_string_lossy()
+                .to_string(),
+            max_connections: 1,
+            min_connections: 1,
+            ..Default::default()
+        })
+        .unwrap();
+        let engine = VectorSearchEngine::new(
+            database.get_connection_pool(),
+            VectorConfig {
+                dimension: 4,
+                similarity_threshold: 0.5,
+                metric,
+                ..Default::default()
+            },
+        );
+        engine.initialize_schema().unwrap();
+        (database, engine, temp_dir)
+    }
+
+    /// Embeddings reference their memory, so tests save one first
+    fn save_test_memory(database: &super::super::Database, content: &str) -> String {
+        database
+            .save_memory(&super::super::models::MemoryItem {
+                user_id: "user1".to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_backfill_resumes_after_failure() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
+
+        let mut ids = Vec::new();
+        for content in ["one", "three", "fifteen", "twenty", "thirty"] {
+            ids.push(
+                database
+                    .save_memory(&super::super::models::MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "session1".to_string(),
+                        content: content.to_string(),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
+        }
+        engine
+            .store_embedding(&ids[0], &[9.0, 9.0, 9.0, 9.0], "other")
+            .unwrap();
+
+        // The second batch fails, leaving the first committed
+        let failing = StubEmbedder {
+            calls: std::cell::Cell::new(0),
+            fail_after: 1,
+        };
+        let mut reported = Vec::new();
+        assert!(engine
+            .backfill(&failing, 2, |progress| reported.push(progress.clone()))
+            .is_err());
+        assert_eq!(
+            reported,
+            vec![BackfillProgress {
+                total: 4,
+                embedded: 2
+            }]
+        );
+
+        let embedder = StubEmbedder {
+            calls: std::cell::Cell::new(0),
+            fail_after: usize::MAX,
+        };
+        let progress = engine.backfill(&embedder, 2, |_| {}).unwrap();
+        assert_eq!(
+            progress,
+            BackfillProgress {
+                total: 2,
+                embedded: 2
+            }
+        );
+
+        // Existing embeddings are left alone
+        assert_eq!(
+            engine.get_embedding(&ids[0], "other").unwrap(),
+            Some(vec![9.0, 9.0, 9.0, 9.0])
+        );
+        assert_eq!(
+            engine.get_embedding(&ids[4], "stub").unwrap(),
+            Some(vec![6.0, 1.0, 0.0, 0.0])
+        );
+        assert_eq!(engine.backfill(&embedder, 2, |_| {}).unwrap().total, 0);
+    }
+
+    #[test]
+    fn test_embedding_cache_reuses_identical_content() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
+        let embedder = StubEmbedder {
+            calls: std::cell::Cell::new(0),
+            fail_after: usize::MAX,
+        };
+
+        // The repeat within the call is embedded once
+        let embeddings = engine
+            .embed_cached(&embedder, &["boilerplate", "hello", "boilerplate"])
+            .unwrap();
+        assert_eq!(embeddings[0], vec![11.0, 1.0, 0.0, 0.0]);
+        assert_eq!(embeddings[0], embeddings[2]);
+        assert_eq!(embedder.calls.get(), 1);
+
+        // Backfilling memories whose content is cached doesn't call the model
+        for content in ["boilerplate", "hello"] {
+            database
+                .save_memory(&super::super::models::MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        assert_eq!(engine.backfill(&embedder, 10, |_| {}).unwrap().embedded, 2);
+        assert_eq!(embedder.calls.get(), 1);
+
+        let stats = engine.get_vector_stats().unwrap().embedding_cache;
+        assert_eq!((stats.entries, stats.hits), (2, 3));
+        assert_eq!(stats.hit_rate, 0.6);
+
+        assert_eq!(
+            engine.purge_embedding_cache(Some("other"), None).unwrap(),
+            0
+        );
+        let an_hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(
+            engine
+                .purge_embedding_cache(None, Some(an_hour_ago))
+                .unwrap(),
+            0
+        );
+        assert_eq!(engine.purge_embedding_cache(None, None).unwrap(), 2);
+        engine.embed_cached(&embedder, &["hello"]).unwrap();
+        assert_eq!(embedder.calls.get(), 2);
+    }
+
+    #[test]
+    fn test_search_respects_filter() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Cosine);
+
+        let memories = [
+            ("alice", "s1", 0.9, "rust,databases"),
+            ("alice", "s2", 0.3, "rust"),
+            ("bob", "s3", 0.9, "rust, databases"),
+        ];
+        for (user_id, session_id, importance, tags) in memories {
+            let memory_id = database
+                .save_memory(&super::super::models::MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: session_id.to_string(),
+                    content: format!("{} notes", user_id),
+                    importance,
+                    metadata: HashMap::from([(TAGS_METADATA_KEY.to_string(), tags.to_string())]),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &[1.0, 0.0, 0.0, 0.0], "model")
+                .unwrap();
+        }
+
+        let search = |filter: VectorFilter| {
+            let mut results = engine
+                .search_similar(&[1.0, 0.0, 0.0, 0.0], "model", None, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|result| (result.user_id, result.session_id))
+                .collect::<Vec<_>>();
+            results.sort();
+            results
+        };
+        let pair = |user_id: &str, session_id: &str| (user_id.to_string(), session_id.to_string());
+
+        assert_eq!(search(VectorFilter::default()).len(), 3);
+        assert_eq!(
+            search(VectorFilter::for_user("alice")),
+            vec![pair("alice", "s1"), pair("alice", "s2")]
+        );
+        assert_eq!(
+            search(VectorFilter {
+                session_id: Some("s2".to_string()),
+                ..Default::default()
+            }),
+            vec![pair("alice", "s2")]
+        );
+        assert_eq!(
+            search(VectorFilter {
+                min_importance: Some(0.5),
+                tags: vec!["databases".to_string()],
+                ..Default::default()
+            }),
+            vec![pair("alice", "s1"), pair("bob", "s3")]
+        );
+        assert_eq!(
+            search(VectorFilter {
+                tags: vec!["rust".to_string(), "data".to_string()],
+                ..Default::default()
+            }),
+            vec![]
+        );
+        assert!(search(VectorFilter {
+            date_to: Some(Utc::now() - chrono::Duration::days(1)),
+            ..Default::default()
+        })
+        .is_empty());
+
+        let invalid = VectorFilter {
+            min_importance: Some(2.0),
+            ..Default::default()
+        };
+        assert!(engine
+            .search_similar(&[1.0, 0.0, 0.0, 0.0], "model", None, &invalid)
+            .is_err());
+    }
+
+    #[test]
+    fn test_embeddings_follow_memory_lifecycle() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
+        let pool = database.get_connection_pool();
+
+        let mut ids = Vec::new();
+        for content in ["deleted", "edited", "expired"] {
+            let memory_id = database
+                .save_memory(&super::super::models::MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &[1.0, 0.0, 0.0, 0.0], "model")
+                .unwrap();
+            ids.push(memory_id);
+        }
+        let stored = || {
+            pool.with_read_connection(|conn| {
+                Ok(
+                    conn.query_row("SELECT COUNT(*) FROM memory_embeddings", [], |row| {
+                        row.get::<_, i64>(0)
+                    })?,
+                )
+            })
+            .unwrap()
+        };
+        assert_eq!(engine.get_vector_stats().unwrap().total_embeddings, 3);
+
+        assert!(database.delete_memory(&ids[0]).unwrap());
+        assert_eq!(engine.get_embedding(&ids[0], "model").unwrap(), None);
+
+        // Changing the content invalidates the embedding
+        pool.with_write_transaction(|tx| {
+            tx.execute(
+                "UPDATE memories SET content = 'rewritten' WHERE id = ?1",
+                [&ids[1]],
+            )?;
+            tx.execute(
+                "UPDATE memories SET expires_at = datetime('now', '-1 hour') WHERE id = ?1",
+                [&ids[2]],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(engine.get_embedding(&ids[1], "model").unwrap(), None);
+
+        // Expired memories stop counting before decay removes them
+        assert_eq!(stored(), 1);
+        assert_eq!(engine.get_vector_stats().unwrap().total_embeddings, 0);
+        assert_eq!(database.cleanup_expired().unwrap(), 1);
+        assert_eq!(stored(), 0);
+
+        let embedder = StubEmbedder {
+            calls: std::cell::Cell::new(0),
+            fail_after: usize::MAX,
+        };
+        assert!(engine.reembed(&ids[1], &embedder).unwrap());
+        assert_eq!(
+            engine.get_embedding(&ids[1], "stub").unwrap(),
+            Some(vec![9.0, 1.0, 0.0, 0.0])
+        );
+        assert!(!engine.reembed(&ids[0], &embedder).unwrap());
+
+        // A delete that bypassed both the foreign key and the trigger
+        {
+            let conn = pool.get_connection().unwrap();
+            conn.execute_batch(&format!(
+                "PRAGMA foreign_keys = OFF;
+                 DROP TRIGGER memory_embeddings_delete;
+                 DELETE FROM memories WHERE id = '{}';
+                 PRAGMA foreign_keys = ON;",
+                ids[1]
+            ))
+            .unwrap();
+        }
+        assert_eq!(stored(), 1);
+        assert_eq!(engine.purge_orphaned_embeddings().unwrap(), 1);
+        assert_eq!(stored(), 0);
+        engine.initialize_schema().unwrap();
+    }
+
+    #[test]
+    fn test_recall_includes_embeddings_on_request() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Cosine);
+        let mut ids = Vec::new();
+        for content in ["embedded note", "plain note"] {
+            ids.push(
+                database
+                    .save_memory(&super::super::models::MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "session1".to_string(),
+                        content: content.to_string(),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
+        }
+        engine
+            .store_embedding(&ids[0], &[0.0, 1.0, 0.0, 0.0], "model")
+            .unwrap();
+
+        let recall = |include_embeddings: bool, keywords: Option<&str>| {
+            let filter = super::super::models::QueryFilter {
+                user_id: Some("user1".to_string()),
+                keywords: keywords.map(|k| vec![k.to_string()]),
+                include_embeddings,
+                ..Default::default()
+            };
+            let mut memories = database.recall_memories(&filter).unwrap().data;
+            memories.sort_by(|a, b| a.content.cmp(&b.content));
+            memories
+        };
+
+        let memories = recall(false, None);
+        assert!(memories.iter().all(|m| m.embedding.is_none()));
+        let json = serde_json::to_string(&memories[0]).unwrap();
+        assert!(!json.contains("embedding"), "{}", json);
+
+        let memories = recall(true, None);
+        assert_eq!(memories[0].embedding, Some(vec![0.0, 1.0, 0.0, 0.0]));
+        assert_eq!(memories[0].embedding_model.as_deref(), Some("model"));
+        assert_eq!(memories[1].embedding, None);
+
+        let memories = recall(true, Some("embedded"));
+        assert_eq!(memories.len(), 1);
+        assert!(memories[0].embedding.is_some());
+    }
+
+    #[test]
+    fn test_vector_stats_diagnostics() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
+        let pool = database.get_connection_pool();
+
+        let mut ids = Vec::new();
+        for content in ["scaled", "zero", "resized", "orphaned", "missing"] {
+            ids.push(
+                database
+                    .save_memory(&super::super::models::MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "session1".to_string(),
+                        content: content.to_string(),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
+        }
+        engine
+            .store_embedding(&ids[0], &[3.0, 4.0, 0.0, 0.0], "model")
+            .unwrap();
+        engine
+            .store_embedding(&ids[1], &[0.0, 0.0, 0.0, 0.0], "model")
+            .unwrap();
+        engine
+            .store_embedding(&ids[2], &[0.0, 0.0, 0.0, 1.0], "model")
+            .unwrap();
+        engine
+            .store_embedding(&ids[3], &[1.0, 0.0, 0.0, 0.0], "model")
+            .unwrap();
+
+        let stats = engine.get_vector_stats().unwrap();
+        assert_eq!(stats.total_embeddings, 4);
+        assert_eq!(stats.missing_embeddings, 1);
+        assert_eq!(stats.sampled_embeddings, 4);
+        assert_eq!(stats.zero_norm_embeddings, 1);
+        assert_eq!(stats.average_norm, Some(7.0 / 4.0));
+        assert_eq!(stats.anomalies().len(), 2);
+
+        // An embedding written under another dimension, and one whose memory
+        // was deleted behind the foreign key's back
+        {
+            let conn = pool.get_connection().unwrap();
+            conn.execute(
+                "UPDATE memory_embeddings SET embedding = x'0000803f', dimension = 1
+                 WHERE memory_id = ?1",
+                [&ids[2]],
+            )
+            .unwrap();
+            conn.execute_batch(&format!(
+                "PRAGMA foreign_keys = OFF;
+                 DROP TRIGGER memory_embeddings_delete;
+                 DELETE FROM memories WHERE id = '{}';
+                 PRAGMA foreign_keys = ON;",
+                ids[3]
+            ))
+            .unwrap();
+        }
+
+        let stats = engine.get_vector_stats().unwrap();
+        assert_eq!(stats.total_embeddings, 3);
+        assert_eq!(stats.orphaned_embeddings, 1);
+        assert_eq!(stats.dimension_mismatches, 1);
+        assert_eq!(stats.anomalies().len(), 4);
+        assert!(stats.index_memory_bytes > 0);
+    }
+
+    #[test]
+    fn test_distance_metrics() {
+        let a = [1.0, 2.0, 2.0];
+        let b = [1.0, 0.0, 0.0];
+        assert!((DistanceMetric::Cosine.score(&a, &b) - 1.0 / 3.0).abs() < 1e-6);
+        assert_eq!(DistanceMetric::Dot.score(&a, &b), 1.0);
+        assert_eq!(DistanceMetric::L2.score(&a, &b), 1.0 / (1.0 + 8f32.sqrt()));
+        assert_eq!(DistanceMetric::L2.score(&a, &a), 1.0);
+        assert_eq!(
+            "Euclidean".parse::<DistanceMetric>().unwrap(),
+            DistanceMetric::L2
+        );
+        assert!("manhattan".parse::<DistanceMetric>().is_err());
+    }
+
+    #[cfg(feature = "sqlite-vec")]
+    #[test]
+    fn test_search_uses_sqlite_vec_when_loaded() {
+        use rusqlite::functions::FunctionFlags;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Cosine);
+        for (content, embedding) in [
+            ("near", [1.0, 0.1, 0.0, 0.0]),
+            ("far", [0.0, 1.0, 0.0, 0.0]),
+        ] {
+            let memory_id = database
+                .save_memory(&super::super::models::MemoryItem {
+                    user_id: "user1".to_string(),
+                    session_id: "session1".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &embedding, "model")
+                .unwrap();
+        }
+        let search = || {
+            engine
+                .search_similar(
+                    &[1.0, 0.0, 0.0, 0.0],
+                    "model",
+                    None,
+                    &VectorFilter::default(),
+                )
+                .unwrap()
+                .into_iter()
+                .map(|result| (result.content, result.similarity))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(engine.sqlite_vec_version().unwrap(), None);
+        let builtin = search();
+
+        // Stand-ins for the extension's functions on the pool's only connection
+        let calls = Arc::new(AtomicUsize::new(0));
+        engine
+            .pool
+            .with_read_connection(|conn| {
+                let flags = || FunctionFlags::SQLITE_UTF8 | FunctionFlags::SQLITE_DETERMINISTIC;
+                conn.create_scalar_function("vec_version", 0, flags(), |_| Ok("v0.1.0"))?;
+                let counter = calls.clone();
+                conn.create_scalar_function("vec_distance_cosine", 2, flags(), move |ctx| {
+                    counter.fetch_add(1, Ordering::Relaxed);
+                    let a = deserialize_vector(&ctx.get::<Vec<u8>>(0)?).unwrap();
+                    let b = deserialize_vector(&ctx.get::<Vec<u8>>(1)?).unwrap();
+                    Ok(1.0 - cosine_similarity(&a, &b))
+                })?;
+                conn.create_scalar_function("vec_distance_l2", 2, flags(), |_| Ok(0.0))?;
+                Ok(())
+            })
+            .unwrap();
+
+        assert_eq!(
+            engine.sqlite_vec_version().unwrap().as_deref(),
+            Some("v0.1.0")
+        );
+        let native = search();
+        assert!(calls.load(Ordering::Relaxed) > 0);
+        assert_eq!(native.len(), builtin.len());
+        for ((content, similarity), (expected_content, expected)) in native.iter().zip(&builtin) {
+            assert_eq!(content, expected_content);
+            assert!((similarity - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_metric_is_fixed_per_model() {
+        let (database, cosine, _temp_dir) = setup_engine_with_database(DistanceMetric::Cosine);
+        let mut ids = Vec::new();
+        for content in ["near", "far"] {
+            ids.push(
+                database
+                    .save_memory(&super::super::models::MemoryItem {
+                        user_id: "user1".to_string(),
+                        session_id: "session1".to_string(),
+                        content: content.to_string(),
+                        ..Default::default()
+                    })
+                    .unwrap(),
+            );
+        }
+
+        // Cosine embeddings are stored normalized
+        cosine
+            .store_embedding(&ids[0], &[3.0, 4.0, 0.0, 0.0], "cosine-model")
+            .unwrap();
+        assert_eq!(
+            cosine.get_embedding(&ids[0], "cosine-model").unwrap(),
+            Some(vec![0.6, 0.8, 0.0, 0.0])
+        );
+
+        let l2 = VectorSearchEngine::new(
+            database.get_connection_pool(),
+            VectorConfig {
+                dimension: 4,
+                similarity_threshold: 0.1,
+                metric: DistanceMetric::L2,
+                ..Default::default()
+            },
+        );
+        l2.initialize_schema().unwrap();
+
+        // A model keeps the metric it was first stored under
+        assert!(l2
+            .store_embedding(&ids[1], &[1.0, 0.0, 0.0, 0.0], "cosine-model")
+            .is_err());
+        assert!(l2
+            .search_similar(
+                &[1.0, 0.0, 0.0, 0.0],
+                "cosine-model",
+                None,
+                &VectorFilter::default()
+            )
+            .is_err());
+
+        l2.store_embedding(&ids[0], &[1.0, 1.0, 0.0, 0.0], "l2-model")
+            .unwrap();
+        l2.store_embedding(&ids[1], &[4.0, 5.0, 0.0, 0.0], "l2-model")
+            .unwrap();
+        assert_eq!(
+            l2.get_embedding(&ids[1], "l2-model").unwrap(),
+            Some(vec![4.0, 5.0, 0.0, 0.0])
+        );
+
+        let results = l2
+            .search_similar(
+                &[1.0, 1.0, 0.0, 0.0],
+                "l2-model",
+                None,
+                &VectorFilter::default(),
+            )
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].memory_id, ids[0]);
+        assert_eq!(results[0].similarity, 1.0);
+        assert_eq!(results[1].similarity, 1.0 / 6.0);
+        assert!(results
+            .iter()
+            .all(|result| result.metric == DistanceMetric::L2));
+    }
+
+    #[test]
+    fn test_session_embeddings() {
+        let (database, engine, _temp_dir) = setup_engine_with_database(DistanceMetric::Dot);
+
+        let memories = [
+            ("alice", "s1", 1.0, [1.0, 0.0, 0.0, 0.0]),
+            ("alice", "s1", 0.0, [0.0, 1.0, 0.0, 0.0]),
+            ("alice", "s2", 0.5, [0.0, 0.0, 1.0, 0.0]),
+            ("bob", "s3", 1.0, [1.0, 0.0, 0.0, 0.0]),
+        ];
+        let mut ids = Vec::new();
+        for (user_id, session_id, importance, embedding) in memories {
+            let memory_id = database
+                .save_memory(&super::super::models::MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: session_id.to_string(),
+                    content: format!("{} notes", user_id),
+                    importance,
+                    ..Default::default()
+                })
+                .unwrap();
+            engine
+                .store_embedding(&memory_id, &embedding, "model")
+                .unwrap();
+            ids.push(memory_id);
+        }
+
+        // The zero-importance memory only counts with the minimum weight
+        let results = engine
+            .search_sessions("alice", &[1.0, 0.0, 0.0, 0.0], "model", None)
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].session_id, "s1");
+        assert_eq!(results[0].memory_count, 2);
+        assert!((results[0].similarity - 1.0 / 1.05).abs() < 1e-5);
+        assert_eq!(results[1].session_id, "s2");
+        assert_eq!(results[1].similarity, 0.0);
+
+        // Deleting a memory drops it from the session's embedding
+        assert!(database.delete_memory(&ids[0]).unwrap());
+        let results = engine
+            .search_sessions("alice", &[0.0, 1.0, 0.0, 0.0], "model", Some(1))
+            .unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].session_id, "s1");
+        assert_eq!(results[0].memory_count, 1);
+        assert!((results[0].similarity - 1.0).abs() < 1e-5);
+
+        // So does replacing an embedding
+        engine
+            .store_embedding(&ids[2], &[0.0, 0.0, 0.0, 2.0], "model")
+            .unwrap();
+        let results = engine
+            .search_sessions("alice", &[0.0, 0.0, 0.0, 1.0], "model", None)
+            .unwrap();
+        assert_eq!(results[0].session_id, "s2");
+        assert!((results[0].similarity - 2.0).abs() < 1e-5);
+
+        assert!(engine
+            .search_sessions("alice", &[1.0, 0.0], "model", None)
+            .is_err());
+    }
 }
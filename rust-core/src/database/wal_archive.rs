@@ -0,0 +1,908 @@
+//! Continuous WAL archiving for point-in-time recovery
+//!
+//! The archive directory (`<database file>.wal-archive` unless configured)
+//! holds base backups, WAL segments and a `catalog.json` describing both:
+//!
+//! - A base backup is a page-for-page copy of the database made with
+//!   SQLite's online backup API, so WAL frames apply to it unchanged.
+//! - A segment is a byte range of one WAL generation (the WAL between two
+//!   restarts, identified by its header salts) that ends on a commit frame.
+//!   Frames are checked with SQLite's own checksums before they're archived.
+//!
+//! Between runs the archiver keeps a read transaction open on the database.
+//! A checkpoint can't copy frames past an open reader's snapshot, and SQLite
+//! only restarts the WAL once every frame has been copied, so no frame can be
+//! overwritten before a run has archived it. [`WalArchiver::run`] opens the
+//! next read transaction before releasing the previous one, so the guarantee
+//! never lapses while the archiver is alive. A process that starts archiving
+//! can't know what happened while nothing held the database open, so unless
+//! the WAL is still the generation it archived last it starts a new chain
+//! with a fresh base backup.
+//!
+//! [`reconstruct`] copies the newest base backup at or before the requested
+//! time and replays the segments archived up to then, letting SQLite recover
+//! each generation as a WAL file and checkpoint it. The recovery point is the
+//! last run at or before the requested time, so the archive interval bounds
+//! how much can be lost.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Duration, Utc};
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use validator::Validate;
+
+/// Catalog of an archive directory
+pub const CATALOG_FILE: &str = "catalog.json";
+
+const WAL_MAGIC: u32 = 0x377f_0682;
+const WAL_HEADER_SIZE: usize = 32;
+const FRAME_HEADER_SIZE: usize = 24;
+
+/// Where and how often WAL archiving keeps base backups
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Validate)]
+pub struct WalArchiveConfig {
+    /// Archive directory; `<database file>.wal-archive` when unset
+    #[serde(default)]
+    pub directory: Option<String>,
+    /// Take a new base backup once the newest is this old
+    #[validate(range(min = 1, max = 8760))]
+    #[serde(default = "default_base_backup_interval_hours")]
+    pub base_backup_interval_hours: u32,
+    /// Base backups kept; segments only older ones need go with them
+    #[validate(range(min = 1, max = 1000))]
+    #[serde(default = "default_keep_base_backups")]
+    pub keep_base_backups: usize,
+}
+
+impl Default for WalArchiveConfig {
+    fn default() -> Self {
+        Self {
+            directory: None,
+            base_backup_interval_hours: default_base_backup_interval_hours(),
+            keep_base_backups: default_keep_base_backups(),
+        }
+    }
+}
+
+impl WalArchiveConfig {
+    /// Archive directory for the database at `source`
+    pub fn directory_for(&self, source: &str) -> PathBuf {
+        match &self.directory {
+            Some(directory) => PathBuf::from(directory),
+            None => default_directory(source),
+        }
+    }
+}
+
+fn default_base_backup_interval_hours() -> u32 {
+    24
+}
+
+fn default_keep_base_backups() -> usize {
+    7
+}
+
+/// Archive directory used for the database at `source` unless configured
+pub fn default_directory(source: &str) -> PathBuf {
+    PathBuf::from(format!("{}.wal-archive", source))
+}
+
+/// One WAL between restarts
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Generation {
+    pub salt1: u32,
+    pub salt2: u32,
+    pub checkpoint_seq: u32,
+}
+
+/// A page-for-page copy of the database
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BaseBackup {
+    pub file: String,
+    pub created_at: DateTime<Utc>,
+    /// Chain of segments that apply to it
+    pub chain: u32,
+    /// First segment to replay onto it: the start of the WAL generation
+    /// current when it was taken
+    pub replay_from: u64,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+/// Bytes `start..end` of one WAL generation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WalSegment {
+    pub seq: u64,
+    pub file: String,
+    pub archived_at: DateTime<Utc>,
+    pub chain: u32,
+    pub generation: Generation,
+    /// 0 for the first segment of a generation, which holds the WAL header
+    pub start: u64,
+    pub end: u64,
+    /// Frames in the generation up to `end`
+    pub frames: u64,
+    pub sha256: String,
+}
+
+/// `catalog.json`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WalCatalog {
+    pub source: String,
+    /// Chain new segments join; a new one starts whenever frames may have
+    /// been missed
+    pub chain: u32,
+    pub next_seq: u64,
+    pub next_base: u64,
+    pub bases: Vec<BaseBackup>,
+    pub segments: Vec<WalSegment>,
+}
+
+impl WalCatalog {
+    /// Read the catalog in `dir`; empty if there is none yet
+    pub fn load(dir: &Path) -> Result<Self> {
+        let path = dir.join(CATALOG_FILE);
+        match std::fs::read(&path) {
+            Ok(data) => serde_json::from_slice(&data)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(e) => Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(CATALOG_FILE);
+        let partial = dir.join(format!("{}.partial", CATALOG_FILE));
+        std::fs::write(&partial, serde_json::to_vec_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Newest segment of the current chain
+    fn position(&self) -> Option<&WalSegment> {
+        self.segments
+            .iter()
+            .rev()
+            .find(|segment| segment.chain == self.chain)
+    }
+}
+
+/// What one archiving run did
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveRun {
+    pub segment: Option<WalSegment>,
+    pub base: Option<BaseBackup>,
+    /// Frames may have been missed, so a new chain was started
+    pub new_chain: bool,
+    /// Base backups and segments deleted by retention
+    pub pruned_files: usize,
+}
+
+/// Archives the WAL of one database; see the module docs
+pub struct WalArchiver {
+    config: WalArchiveConfig,
+    source: String,
+    directory: PathBuf,
+    /// Connection holding a read transaction since the last run
+    held: Option<Connection>,
+}
+
+impl WalArchiver {
+    pub fn new(source: &str, config: WalArchiveConfig) -> Result<Self> {
+        config
+            .validate()
+            .context("Invalid WAL archive configuration")?;
+        Ok(Self {
+            directory: config.directory_for(source),
+            config,
+            source: source.to_string(),
+            held: None,
+        })
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Archive frames committed since the last run, taking a base backup
+    /// when there is none for the current chain or the newest is due
+    pub fn run(&mut self) -> Result<ArchiveRun> {
+        std::fs::create_dir_all(&self.directory).with_context(|| {
+            format!(
+                "Failed to create WAL archive directory {}",
+                self.directory.display()
+            )
+        })?;
+        let mut catalog = WalCatalog::load(&self.directory)?;
+        catalog.source = self.source.clone();
+
+        // Opened before the previous read transaction is released; see the
+        // module docs
+        let complete = self.held.is_some();
+        let held = Connection::open(&self.source)
+            .with_context(|| format!("Failed to open database: {}", self.source))?;
+        held.busy_timeout(std::time::Duration::from_secs(30))?;
+        held.execute_batch("BEGIN")?;
+        held.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| {
+            row.get::<_, i64>(0)
+        })?;
+        self.held = Some(held);
+
+        let wal = match std::fs::read(format!("{}-wal", self.source)) {
+            Ok(wal) => wal,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Vec::new(),
+            Err(e) => return Err(e).context("Failed to read the WAL"),
+        };
+        let scan = scan_wal(&wal);
+
+        let position = catalog.position().cloned();
+        let same_generation = match (&position, &scan) {
+            (Some(last), Some(scan)) => {
+                last.generation == scan.generation && last.end <= scan.committed_end as u64
+            }
+            _ => false,
+        };
+        let chain_started =
+            position.is_some() || catalog.bases.iter().any(|base| base.chain == catalog.chain);
+        let new_chain = chain_started && !same_generation && !complete;
+        if new_chain {
+            catalog.chain += 1;
+            tracing::warn!(
+                path = self.source.as_str(),
+                chain = catalog.chain,
+                "WAL frames may have been missed; starting a new archive chain"
+            );
+        }
+
+        let mut segment = None;
+        if let Some(scan) = &scan {
+            let start = match &position {
+                Some(last) if same_generation && !new_chain => last.end as usize,
+                _ => 0,
+            };
+            if scan.committed_end > start.max(WAL_HEADER_SIZE) {
+                let seq = catalog.next_seq;
+                let file = format!("{:010}.wal", seq);
+                let bytes = &wal[start..scan.committed_end];
+                write_new(&self.directory.join(&file), bytes)?;
+                let archived = WalSegment {
+                    seq,
+                    file,
+                    archived_at: Utc::now(),
+                    chain: catalog.chain,
+                    generation: scan.generation,
+                    start: start as u64,
+                    end: scan.committed_end as u64,
+                    frames: scan.committed_frames,
+                    sha256: format!("{:x}", Sha256::digest(bytes)),
+                };
+                catalog.next_seq += 1;
+                catalog.segments.push(archived.clone());
+                segment = Some(archived);
+            }
+        }
+
+        let newest_base = catalog
+            .bases
+            .iter()
+            .filter(|base| base.chain == catalog.chain)
+            .map(|base| base.created_at)
+            .max();
+        let base_due = newest_base.is_none_or(|created_at| {
+            Utc::now() - created_at
+                >= Duration::hours(self.config.base_backup_interval_hours as i64)
+        });
+        let base = if base_due {
+            let base = self.base_backup(&mut catalog, scan.as_ref())?;
+            catalog.bases.push(base.clone());
+            Some(base)
+        } else {
+            None
+        };
+
+        let pruned_files = self.prune(&mut catalog)?;
+        catalog.save(&self.directory)?;
+        Ok(ArchiveRun {
+            segment,
+            base,
+            new_chain,
+            pruned_files,
+        })
+    }
+
+    /// Copy the database as of the held read transaction
+    fn base_backup(&self, catalog: &mut WalCatalog, scan: Option<&WalScan>) -> Result<BaseBackup> {
+        let held = self.held.as_ref().expect("read transaction is held");
+        let file = format!("base-{:06}.db", catalog.next_base);
+        let path = self.directory.join(&file);
+        let partial = self.directory.join(format!("{}.partial", file));
+        let _ = std::fs::remove_file(&partial);
+        {
+            let mut copy = Connection::open(&partial)?;
+            let backup = rusqlite::backup::Backup::new(held, &mut copy)?;
+            if backup.step(-1)? != rusqlite::backup::StepResult::Done {
+                return Err(anyhow::anyhow!("Base backup was interrupted"));
+            }
+        }
+        std::fs::rename(&partial, &path)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        // Replaying the generation from its start is harmless: frames the
+        // copy already has are overwritten with the same pages
+        let replay_from = scan
+            .and_then(|scan| {
+                catalog.segments.iter().find(|segment| {
+                    segment.chain == catalog.chain
+                        && segment.generation == scan.generation
+                        && segment.start == 0
+                })
+            })
+            .map_or(catalog.next_seq, |segment| segment.seq);
+        catalog.next_base += 1;
+        tracing::info!(
+            path = path.to_string_lossy().as_ref(),
+            "Took WAL archive base backup"
+        );
+        Ok(BaseBackup {
+            file,
+            created_at: Utc::now(),
+            chain: catalog.chain,
+            replay_from,
+            size_bytes: std::fs::metadata(&path)?.len(),
+            sha256: file_sha256(&path)?,
+        })
+    }
+
+    /// Keep the newest `keep_base_backups` bases and the segments they need
+    fn prune(&self, catalog: &mut WalCatalog) -> Result<usize> {
+        if catalog.bases.len() <= self.config.keep_base_backups {
+            return Ok(0);
+        }
+        catalog.bases.sort_by_key(|base| base.created_at);
+        let dropped: Vec<BaseBackup> = catalog
+            .bases
+            .drain(..catalog.bases.len() - self.config.keep_base_backups)
+            .collect();
+        let needed = catalog
+            .bases
+            .iter()
+            .map(|base| base.replay_from)
+            .min()
+            .unwrap_or(catalog.next_seq);
+        let (kept, old): (Vec<_>, Vec<_>) = std::mem::take(&mut catalog.segments)
+            .into_iter()
+            .partition(|segment| segment.seq >= needed);
+        catalog.segments = kept;
+
+        let files: Vec<&str> = dropped
+            .iter()
+            .map(|base| base.file.as_str())
+            .chain(old.iter().map(|segment| segment.file.as_str()))
+            .collect();
+        for file in &files {
+            let path = self.directory.join(file);
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to delete {}", path.display()))?;
+        }
+        Ok(files.len())
+    }
+}
+
+/// Summary of an archive directory, and any problems found checking it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ArchiveReport {
+    pub directory: String,
+    pub bases: usize,
+    pub segments: usize,
+    pub size_bytes: u64,
+    /// Earliest time a restore can target
+    pub earliest: Option<DateTime<Utc>>,
+    /// Latest recovery point
+    pub latest: Option<DateTime<Utc>>,
+    /// Missing or corrupt files and missing WAL ranges
+    pub problems: Vec<String>,
+}
+
+/// Describe the archive in `dir`, checking every file's checksum when
+/// `check_contents` and that segments follow each other either way
+pub fn verify(dir: &Path, check_contents: bool) -> Result<ArchiveReport> {
+    let catalog = WalCatalog::load(dir)?;
+    let mut problems = Vec::new();
+    let mut size_bytes = 0;
+
+    let files = catalog
+        .bases
+        .iter()
+        .map(|base| (&base.file, &base.sha256))
+        .chain(
+            catalog
+                .segments
+                .iter()
+                .map(|segment| (&segment.file, &segment.sha256)),
+        );
+    for (file, sha256) in files {
+        let path = dir.join(file);
+        match std::fs::metadata(&path) {
+            Ok(metadata) => size_bytes += metadata.len(),
+            Err(_) => {
+                problems.push(format!("{} is missing", file));
+                continue;
+            }
+        }
+        if check_contents && &file_sha256(&path)? != sha256 {
+            problems.push(format!("{} does not match its checksum", file));
+        }
+    }
+
+    let mut previous: Option<&WalSegment> = None;
+    for segment in &catalog.segments {
+        let continues = previous.filter(|previous| {
+            previous.chain == segment.chain && previous.generation == segment.generation
+        });
+        let expected_start = continues.map_or(0, |previous| previous.end);
+        if segment.start != expected_start {
+            problems.push(format!(
+                "{} starts at byte {} of its WAL, not {}",
+                segment.file, segment.start, expected_start
+            ));
+        }
+        previous = Some(segment);
+    }
+
+    for base in &catalog.bases {
+        let replayable = base.replay_from == catalog.next_seq
+            || catalog.segments.iter().any(|segment| {
+                segment.seq == base.replay_from && segment.chain == base.chain && segment.start == 0
+            })
+            || catalog
+                .segments
+                .iter()
+                .all(|segment| segment.seq < base.replay_from || segment.chain != base.chain);
+        if !replayable {
+            problems.push(format!(
+                "{} needs segment {} which is missing",
+                base.file, base.replay_from
+            ));
+        }
+    }
+
+    let earliest = catalog.bases.iter().map(|base| base.created_at).min();
+    let latest = earliest.map(|earliest| {
+        catalog
+            .segments
+            .iter()
+            .map(|segment| segment.archived_at)
+            .chain(catalog.bases.iter().map(|base| base.created_at))
+            .fold(earliest, DateTime::max)
+    });
+    Ok(ArchiveReport {
+        directory: dir.to_string_lossy().to_string(),
+        bases: catalog.bases.len(),
+        segments: catalog.segments.len(),
+        size_bytes,
+        earliest,
+        latest,
+        problems,
+    })
+}
+
+/// How a database was rebuilt from the archive
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Reconstruction {
+    pub base_created_at: DateTime<Utc>,
+    /// Time of the last archived state applied
+    pub recovered_to: DateTime<Utc>,
+    pub segments_applied: usize,
+    pub frames_applied: u64,
+}
+
+/// Rebuild the database as it was at `at` from the archive in `dir` into a
+/// new file at `output`
+pub fn reconstruct(dir: &Path, at: DateTime<Utc>, output: &Path) -> Result<Reconstruction> {
+    let catalog = WalCatalog::load(dir)?;
+    let base = catalog
+        .bases
+        .iter()
+        .filter(|base| base.created_at <= at)
+        .max_by_key(|base| base.created_at)
+        .ok_or_else(|| match catalog.bases.iter().map(|b| b.created_at).min() {
+            Some(earliest) => anyhow::anyhow!(
+                "No base backup at or before {}; the archive starts at {}",
+                at,
+                earliest
+            ),
+            None => anyhow::anyhow!("WAL archive {} has no base backups", dir.display()),
+        })?;
+    let segments: Vec<&WalSegment> = catalog
+        .segments
+        .iter()
+        .filter(|segment| {
+            segment.chain == base.chain
+                && segment.seq >= base.replay_from
+                && segment.archived_at <= at
+        })
+        .collect();
+
+    let wal = PathBuf::from(format!("{}-wal", output.display()));
+    let shm = PathBuf::from(format!("{}-shm", output.display()));
+    for path in [output, wal.as_path(), shm.as_path()] {
+        let _ = std::fs::remove_file(path);
+    }
+    let data = read_verified(dir, &base.file, &base.sha256)?;
+    write_new(output, &data)?;
+    Connection::open(output)?.query_row("PRAGMA journal_mode = WAL", [], |_| Ok(()))?;
+
+    let mut frames_applied = 0;
+    for generation in segments.chunk_by(|a, b| a.generation == b.generation) {
+        let mut bytes = Vec::new();
+        let mut end = 0;
+        for segment in generation {
+            if segment.start != end {
+                return Err(anyhow::anyhow!(
+                    "WAL archive is missing bytes {}..{} before {}",
+                    end,
+                    segment.start,
+                    segment.file
+                ));
+            }
+            bytes.extend(read_verified(dir, &segment.file, &segment.sha256)?);
+            end = segment.end;
+        }
+        let expected = generation.last().expect("chunks are non-empty").frames;
+
+        write_new(&wal, &bytes)?;
+        let _ = std::fs::remove_file(&shm);
+        // Closing the only connection folds what's left in and deletes the
+        // WAL, making room for the next generation
+        let conn = Connection::open(output)?;
+        let (log_frames, checkpointed): (i64, i64) =
+            conn.query_row("PRAGMA wal_checkpoint(FULL)", [], |row| {
+                Ok((row.get(1)?, row.get(2)?))
+            })?;
+        drop(conn);
+        if log_frames != expected as i64 || checkpointed != log_frames {
+            return Err(anyhow::anyhow!(
+                "WAL replay recovered {} of {} frames from {}",
+                checkpointed.max(0),
+                expected,
+                generation[0].file
+            ));
+        }
+        frames_applied += expected;
+    }
+
+    Ok(Reconstruction {
+        base_created_at: base.created_at,
+        recovered_to: segments.last().map_or(base.created_at, |segment| {
+            segment.archived_at.max(base.created_at)
+        }),
+        segments_applied: segments.len(),
+        frames_applied,
+    })
+}
+
+/// Replace the database file at `target` with its state at `at`, rebuilt
+/// from the archive in `dir` and checked like any other restore
+pub fn restore(
+    dir: &Path,
+    target: &str,
+    at: DateTime<Utc>,
+) -> Result<(Reconstruction, super::backup::RestoreOutcome)> {
+    let rebuilt = PathBuf::from(format!("{}.pitr", target));
+    let result = reconstruct(dir, at, &rebuilt)
+        .and_then(|rebuilt_at| Ok((rebuilt_at, super::backup::restore(&rebuilt, target, None)?)));
+    for suffix in ["", "-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{}{}", rebuilt.display(), suffix));
+    }
+    result
+}
+
+/// Where a WAL file's committed frames end
+#[derive(Debug, Clone, PartialEq)]
+struct WalScan {
+    generation: Generation,
+    /// Byte offset after the last valid commit frame
+    committed_end: usize,
+    committed_frames: u64,
+}
+
+/// Walk the frames of a WAL file the way SQLite's recovery does: stop at the
+/// first frame with another generation's salts or a bad checksum
+fn scan_wal(wal: &[u8]) -> Option<WalScan> {
+    if wal.len() < WAL_HEADER_SIZE {
+        return None;
+    }
+    let magic = be_u32(wal, 0);
+    if magic & !1 != WAL_MAGIC {
+        return None;
+    }
+    let big_endian = magic & 1 == 1;
+    let page_size = match be_u32(wal, 8) {
+        1 => 65536,
+        size => size as usize,
+    };
+    let mut checksum = wal_checksum(big_endian, &wal[..24], (0, 0));
+    if checksum != (be_u32(wal, 24), be_u32(wal, 28)) {
+        return None;
+    }
+
+    let frame_size = FRAME_HEADER_SIZE + page_size;
+    let mut scan = WalScan {
+        generation: Generation {
+            checkpoint_seq: be_u32(wal, 12),
+            salt1: be_u32(wal, 16),
+            salt2: be_u32(wal, 20),
+        },
+        committed_end: WAL_HEADER_SIZE,
+        committed_frames: 0,
+    };
+    let mut offset = WAL_HEADER_SIZE;
+    let mut frames = 0;
+    while let Some(frame) = wal.get(offset..offset + frame_size) {
+        if frame[8..16] != wal[16..24] || be_u32(frame, 0) == 0 {
+            break;
+        }
+        let next = wal_checksum(big_endian, &frame[..8], checksum);
+        let next = wal_checksum(big_endian, &frame[FRAME_HEADER_SIZE..], next);
+        if next != (be_u32(frame, 16), be_u32(frame, 20)) {
+            break;
+        }
+        checksum = next;
+        frames += 1;
+        offset += frame_size;
+        // A commit frame records the database size in pages
+        if be_u32(frame, 4) != 0 {
+            scan.committed_end = offset;
+            scan.committed_frames = frames;
+        }
+    }
+    Some(scan)
+}
+
+fn wal_checksum(big_endian: bool, data: &[u8], (mut s1, mut s2): (u32, u32)) -> (u32, u32) {
+    for words in data.chunks_exact(8) {
+        let word = |i: usize| {
+            let bytes = [words[i], words[i + 1], words[i + 2], words[i + 3]];
+            if big_endian {
+                u32::from_be_bytes(bytes)
+            } else {
+                u32::from_le_bytes(bytes)
+            }
+        };
+        s1 = s1.wrapping_add(word(0)).wrapping_add(s2);
+        s2 = s2.wrapping_add(word(4)).wrapping_add(s1);
+    }
+    (s1, s2)
+}
+
+fn be_u32(data: &[u8], offset: usize) -> u32 {
+    u32::from_be_bytes([
+        data[offset],
+        data[offset + 1],
+        data[offset + 2],
+        data[offset + 3],
+    ])
+}
+
+/// Write `data` to `path` through a `.partial` file, synced before the rename
+fn write_new(path: &Path, data: &[u8]) -> Result<()> {
+    use std::io::Write;
+
+    let partial = PathBuf::from(format!("{}.partial", path.display()));
+    let mut file = std::fs::File::create(&partial)
+        .with_context(|| format!("Failed to create {}", partial.display()))?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    std::fs::rename(&partial, path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn read_verified(dir: &Path, file: &str, sha256: &str) -> Result<Vec<u8>> {
+    let path = dir.join(file);
+    let data =
+        std::fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if format!("{:x}", Sha256::digest(&data)) != sha256 {
+        return Err(anyhow::anyhow!(
+            "{} does not match its checksum; the WAL archive is corrupt",
+            path.display()
+        ));
+    }
+    Ok(data)
+}
+
+fn file_sha256(path: &Path) -> Result<String> {
+    let mut file =
+        std::fs::File::open(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::MemoryItem;
+    use crate::database::pool::CheckpointMode;
+    use crate::database::{Database, DatabaseConfig};
+    use tempfile::TempDir;
+
+    fn open(temp_dir: &TempDir) -> Database {
+        Database::new(DatabaseConfig {
+            path: temp_dir.path().join("wal.db").to_string_lossy().to_string(),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn save(database: &Database, content: &str) {
+        database
+            .save_memory(&MemoryItem {
+                user_id: "alice".to_string(),
+                session_id: "session1".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+    }
+
+    fn contents(path: &Path) -> Vec<String> {
+        let conn = Connection::open(path).unwrap();
+        let mut stmt = conn
+            .prepare("SELECT content FROM memories ORDER BY content")
+            .unwrap();
+        let contents = stmt
+            .query_map([], |row| row.get(0))
+            .unwrap()
+            .collect::<rusqlite::Result<Vec<String>>>()
+            .unwrap();
+        contents
+    }
+
+    #[test]
+    fn test_scan_matches_sqlite_recovery() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = open(&temp_dir);
+        save(&database, "one");
+        save(&database, "two");
+
+        let mut wal = std::fs::read(format!("{}-wal", database.path())).unwrap();
+        let scan = scan_wal(&wal).unwrap();
+        let conn = Connection::open(database.path()).unwrap();
+        let log_frames: i64 = conn
+            .query_row("PRAGMA wal_checkpoint(PASSIVE)", [], |row| row.get(1))
+            .unwrap();
+        assert_eq!(scan.committed_frames as i64, log_frames);
+        assert_eq!(scan.committed_end, wal.len());
+
+        // A torn last frame ends the WAL at the commit before it
+        let last = wal.len() - 1;
+        wal[last] ^= 0xff;
+        let torn = scan_wal(&wal).unwrap();
+        assert!(torn.committed_frames < scan.committed_frames);
+        assert_eq!(torn.generation, scan.generation);
+
+        assert!(scan_wal(&wal[..WAL_HEADER_SIZE - 1]).is_none());
+        wal[0] ^= 0xff;
+        assert!(scan_wal(&wal).is_none());
+    }
+
+    #[test]
+    fn test_restore_to_points_in_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = open(&temp_dir);
+        let dir = temp_dir.path().join("archive");
+        database
+            .enable_wal_archive(WalArchiveConfig {
+                directory: Some(dir.to_string_lossy().to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(database.wal_archive_dir(), Some(dir.clone()));
+
+        save(&database, "a");
+        let first = database.archive_wal().unwrap().unwrap();
+        assert!(first.base.is_some() && first.segment.is_some());
+        let at_a = Utc::now();
+
+        save(&database, "b");
+        let second = database.archive_wal().unwrap().unwrap();
+        assert!(second.base.is_none() && !second.new_chain);
+        assert_eq!(second.segment.unwrap().start, first.segment.unwrap().end);
+        let at_b = Utc::now();
+
+        // The checkpoint archives "c" before the WAL restarts, and "d" starts
+        // a new generation
+        save(&database, "c");
+        let checkpoint = database.checkpoint(CheckpointMode::Truncate).unwrap();
+        assert!(!checkpoint.busy);
+        assert_eq!(checkpoint.wal_size_after, 0);
+        save(&database, "d");
+        let last = database.archive_wal().unwrap().unwrap();
+        assert!(!last.new_chain);
+        assert_eq!(last.segment.unwrap().start, 0);
+        let at_d = Utc::now();
+
+        let report = verify(&dir, true).unwrap();
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        assert_eq!(report.bases, 1);
+
+        let output = temp_dir.path().join("rebuilt.db");
+        for (at, expected) in [
+            (at_a, vec!["a"]),
+            (at_b, vec!["a", "b"]),
+            (at_d, vec!["a", "b", "c", "d"]),
+        ] {
+            let rebuilt = reconstruct(&dir, at, &output).unwrap();
+            assert!(rebuilt.recovered_to <= at);
+            assert_eq!(contents(&output), expected);
+        }
+        assert!(reconstruct(&dir, at_a - Duration::hours(1), &output).is_err());
+
+        let target = temp_dir.path().join("restored.db");
+        let (rebuilt, outcome) = restore(&dir, &target.to_string_lossy(), at_b).unwrap();
+        assert_eq!(outcome.memory_count, 2);
+        assert_eq!(rebuilt.segments_applied, 2);
+        assert!(!temp_dir.path().join("restored.db.pitr").exists());
+    }
+
+    #[test]
+    fn test_corrupt_segment_is_refused() {
+        let temp_dir = TempDir::new().unwrap();
+        let database = open(&temp_dir);
+        database
+            .enable_wal_archive(WalArchiveConfig::default())
+            .unwrap();
+        let dir = default_directory(database.path());
+        save(&database, "a");
+        let run = database.archive_wal().unwrap().unwrap();
+
+        let segment = dir.join(run.segment.unwrap().file);
+        let mut data = std::fs::read(&segment).unwrap();
+        let middle = data.len() / 2;
+        data[middle] ^= 0xff;
+        std::fs::write(&segment, data).unwrap();
+
+        let report = verify(&dir, true).unwrap();
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        assert!(verify(&dir, false).unwrap().problems.is_empty());
+        let error = reconstruct(&dir, Utc::now(), &temp_dir.path().join("out.db")).unwrap_err();
+        assert!(error.to_string().contains("checksum"), "{}", error);
+    }
+
+    #[test]
+    fn test_restart_starts_a_new_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("archive");
+        let config = WalArchiveConfig {
+            directory: Some(dir.to_string_lossy().to_string()),
+            keep_base_backups: 1,
+            ..Default::default()
+        };
+
+        let database = open(&temp_dir);
+        database.enable_wal_archive(config.clone()).unwrap();
+        save(&database, "a");
+        database.archive_wal().unwrap();
+        drop(database);
+
+        // Closing the last connection checkpointed and removed the WAL, so
+        // a new process can't tell whether frames went unarchived
+        let database = open(&temp_dir);
+        database.enable_wal_archive(config).unwrap();
+        save(&database, "b");
+        let run = database.archive_wal().unwrap().unwrap();
+        assert!(run.new_chain && run.base.is_some());
+        // The old base and its segment are pruned
+        assert_eq!(run.pruned_files, 2);
+
+        let catalog = WalCatalog::load(&dir).unwrap();
+        assert_eq!(catalog.chain, 1);
+        assert_eq!(catalog.bases.len(), 1);
+        assert!(catalog.segments.iter().all(|segment| segment.chain == 1));
+        let output = temp_dir.path().join("rebuilt.db");
+        reconstruct(&dir, Utc::now(), &output).unwrap();
+        assert_eq!(contents(&output), vec!["a", "b"]);
+    }
+}
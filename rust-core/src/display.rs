@@ -0,0 +1,214 @@
+//! Text helpers for terminal output and snippets
+//!
+//! Memory content is arbitrary UTF-8, so byte slicing (`&s[..n]`) can panic
+//! in the middle of a character. Everything here works on grapheme clusters
+//! and, where columns matter, on display width (CJK and emoji take two cells).
+
+use std::borrow::Cow;
+
+use unicode_segmentation::UnicodeSegmentation;
+
+/// Appended to truncated text
+pub const ELLIPSIS: &str = "…";
+
+/// The first `count` grapheme clusters of `s`
+pub fn take_graphemes(s: &str, count: usize) -> &str {
+    match s.grapheme_indices(true).nth(count) {
+        Some((end, _)) => &s[..end],
+        None => s,
+    }
+}
+
+/// Shorten `s` to at most `max` grapheme clusters, ending with an ellipsis
+/// when anything was cut
+pub fn truncate_graphemes(s: &str, max: usize) -> Cow<'_, str> {
+    if s.graphemes(true).nth(max).is_none() {
+        return Cow::Borrowed(s);
+    }
+
+    let kept = take_graphemes(s, max.saturating_sub(1));
+    Cow::Owned(format!("{}{}", kept, ELLIPSIS))
+}
+
+/// Number of terminal cells `s` occupies
+pub fn display_width(s: &str) -> usize {
+    s.graphemes(true).map(grapheme_width).sum()
+}
+
+/// Shorten `s` to at most `width` terminal cells, ending with an ellipsis
+/// when anything was cut
+pub fn truncate_width(s: &str, width: usize) -> Cow<'_, str> {
+    if display_width(s) <= width {
+        return Cow::Borrowed(s);
+    }
+
+    let budget = width.saturating_sub(1);
+    let mut used = 0;
+    let mut end = 0;
+    for (index, grapheme) in s.grapheme_indices(true) {
+        let w = grapheme_width(grapheme);
+        if used + w > budget {
+            break;
+        }
+        used += w;
+        end = index + grapheme.len();
+    }
+
+    Cow::Owned(format!("{}{}", &s[..end], ELLIPSIS))
+}
+
+/// Left-align `s` in a column `width` cells wide
+pub fn pad_to_width(s: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(s));
+    format!("{}{}", s, " ".repeat(padding))
+}
+
+/// Greedy word wrap by display width
+///
+/// Words wider than `width` (including unspaced CJK runs) are split between
+/// grapheme clusters.
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut current_line = String::new();
+    let mut current_width = 0;
+
+    for word in text.split_whitespace() {
+        let word_width = display_width(word);
+
+        if current_width > 0 && current_width + 1 + word_width <= width {
+            current_line.push(' ');
+            current_line.push_str(word);
+            current_width += 1 + word_width;
+            continue;
+        }
+
+        if current_width > 0 {
+            lines.push(std::mem::take(&mut current_line));
+            current_width = 0;
+        }
+
+        for grapheme in word.graphemes(true) {
+            let w = grapheme_width(grapheme);
+            if current_width > 0 && current_width + w > width {
+                lines.push(std::mem::take(&mut current_line));
+                current_width = 0;
+            }
+            current_line.push_str(grapheme);
+            current_width += w;
+        }
+    }
+
+    if !current_line.is_empty() {
+        lines.push(current_line);
+    }
+
+    lines
+}
+
+fn grapheme_width(grapheme: &str) -> usize {
+    let mut chars = grapheme.chars();
+    let first = match chars.next() {
+        Some(c) => c,
+        None => return 0,
+    };
+
+    if first.is_control() {
+        0
+    } else if is_wide(first) || grapheme.contains('\u{FE0F}') {
+        // U+FE0F requests emoji presentation, which terminals draw double-width
+        2
+    } else {
+        1
+    }
+}
+
+/// East Asian Wide/Fullwidth ranges and the common emoji blocks
+fn is_wide(c: char) -> bool {
+    matches!(
+        c as u32,
+        0x1100..=0x115F
+            | 0x2E80..=0x303E
+            | 0x3041..=0x33FF
+            | 0x3400..=0x4DBF
+            | 0x4E00..=0x9FFF
+            | 0xA000..=0xA4CF
+            | 0xAC00..=0xD7A3
+            | 0xF900..=0xFAFF
+            | 0xFE30..=0xFE4F
+            | 0xFF00..=0xFF60
+            | 0xFFE0..=0xFFE6
+            | 0x1F300..=0x1F64F
+            | 0x1F680..=0x1F6FF
+            | 0x1F900..=0x1F9FF
+            | 0x1FA70..=0x1FAFF
+            | 0x20000..=0x3FFFD
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_graphemes() {
+        assert_eq!(truncate_graphemes("short", 10), "short");
+        assert_eq!(truncate_graphemes("exactly10!", 10), "exactly10!");
+        assert_eq!(truncate_graphemes("hello world", 6), "hello…");
+
+        // Multi-byte and combining characters are never split
+        assert_eq!(truncate_graphemes("日本語のテキスト", 4), "日本語…");
+        assert_eq!(truncate_graphemes("cafe\u{301} au lait", 5), "cafe\u{301}…");
+        assert_eq!(truncate_graphemes("👨‍👩‍👧 family", 2), "👨‍👩‍👧…");
+
+        assert_eq!(truncate_graphemes("abc", 0), "…");
+        assert_eq!(truncate_graphemes("", 0), "");
+    }
+
+    #[test]
+    fn test_take_graphemes() {
+        assert_eq!(take_graphemes("abc", 8), "abc");
+        assert_eq!(take_graphemes("Москва", 3), "Мос");
+    }
+
+    #[test]
+    fn test_display_width() {
+        assert_eq!(display_width("abc"), 3);
+        assert_eq!(display_width("世界"), 4);
+        assert_eq!(display_width("🚀 go"), 5);
+        assert_eq!(display_width("e\u{301}"), 1);
+    }
+
+    #[test]
+    fn test_truncate_and_pad_width() {
+        assert_eq!(truncate_width("世界世界", 5), "世界…");
+        assert_eq!(truncate_width("abcdef", 4), "abc…");
+        assert_eq!(truncate_width("abc", 4), "abc");
+
+        assert_eq!(pad_to_width("世界", 6), "世界  ");
+        assert_eq!(pad_to_width("toolong", 3), "toolong");
+    }
+
+    #[test]
+    fn test_wrap_text() {
+        let text = "This is a long line of text that should be wrapped";
+        let wrapped = wrap_text(text, 20);
+
+        assert!(wrapped.len() > 1);
+        for line in &wrapped {
+            assert!(display_width(line) <= 20);
+        }
+        assert_eq!(wrapped.join(" "), text);
+    }
+
+    #[test]
+    fn test_wrap_cjk() {
+        let wrapped = wrap_text("这是一段没有空格的中文文本", 10);
+
+        assert_eq!(wrapped.len(), 3);
+        for line in &wrapped {
+            assert!(display_width(line) <= 10);
+        }
+        assert_eq!(wrapped.concat(), "这是一段没有空格的中文文本");
+    }
+}
@@ -0,0 +1,255 @@
+//! Role checks for instances opened with a `token`
+//!
+//! [`call`](super::call) notes which extern function is running, and
+//! [`get_instance`](super::get_instance) checks the instance's principal
+//! against the role that function needs. Functions not listed here need
+//! [`Role::Admin`], so anything destructive or new is denied to lesser keys
+//! until it is deliberately opened up.
+//!
+//! Functions that write memories of a known namespace are checked twice:
+//! once up front against the key's highest role, then by the function
+//! itself, through [`MemexHandle::authorize_namespace`], against its role in
+//! the namespace actually written.
+//!
+//! Keys with `analytics_privacy` may call only [`ANALYTICS_OPERATIONS`], and
+//! get their results aggregated. So may any call made under a request
+//! context carrying `analytics_privacy`, which is how the Node server passes
+//! on the mode of the key it authenticated.
+
+use serde::Deserialize;
+use std::cell::{Cell, RefCell};
+
+use crate::core::access::{AccessDenied, Principal, Role};
+use crate::core::privacy::AnalyticsPrivacy;
+use crate::database::models::MemoryItem;
+
+use super::error::{FfiError, FfiErrorCode};
+use super::MemexHandle;
+
+thread_local! {
+    /// Extern function running on this thread
+    static CURRENT_OP: Cell<&'static str> = const { Cell::new("") };
+    /// Privacy mode of the request context set on this thread
+    static CONTEXT_PRIVACY: RefCell<Option<AnalyticsPrivacy>> = const { RefCell::new(None) };
+}
+
+/// Whether a function's role is needed everywhere or only in the
+/// namespaces it writes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    Global,
+    Namespace,
+}
+
+/// Role each extern function needs; everything else needs `Admin`
+const OPERATIONS: &[(&str, Role, Scope)] = &[
+    ("memex_get_memory", Role::ReadOnly, Scope::Global),
+    ("memex_get_memories", Role::ReadOnly, Scope::Global),
+    ("memex_recall", Role::ReadOnly, Scope::Global),
+    ("memex_recall_encoded", Role::ReadOnly, Scope::Global),
+    ("memex_recall_begin", Role::ReadOnly, Scope::Global),
+    ("memex_search", Role::ReadOnly, Scope::Global),
+    ("memex_search_grouped", Role::ReadOnly, Scope::Global),
+    ("memex_vector_search", Role::ReadOnly, Scope::Global),
+    ("memex_hybrid_search", Role::ReadOnly, Scope::Global),
+    ("memex_list_synonyms", Role::ReadOnly, Scope::Global),
+    ("memex_list_templates", Role::ReadOnly, Scope::Global),
+    ("memex_get_user_sessions", Role::ReadOnly, Scope::Global),
+    ("memex_summarize_session", Role::ReadOnly, Scope::Global),
+    ("memex_search_sessions", Role::ReadOnly, Scope::Global),
+    (
+        "memex_search_sessions_semantic",
+        Role::ReadOnly,
+        Scope::Global,
+    ),
+    ("memex_decay_analyze", Role::ReadOnly, Scope::Global),
+    ("memex_maintenance_history", Role::ReadOnly, Scope::Global),
+    ("memex_get_stats", Role::ReadOnly, Scope::Global),
+    ("memex_get_counters", Role::ReadOnly, Scope::Global),
+    ("memex_health", Role::ReadOnly, Scope::Global),
+    ("memex_export_user_memories", Role::ReadOnly, Scope::Global),
+    (
+        "memex_export_user_memories_encoded",
+        Role::ReadOnly,
+        Scope::Global,
+    ),
+    ("memex_find_conflicts", Role::ReadOnly, Scope::Global),
+    ("memex_get_user_stats", Role::ReadOnly, Scope::Global),
+    ("memex_get_user_analytics", Role::ReadOnly, Scope::Global),
+    ("memex_get_session_analytics", Role::ReadOnly, Scope::Global),
+    ("memex_save", Role::Writer, Scope::Namespace),
+    ("memex_save_idempotent", Role::Writer, Scope::Namespace),
+    ("memex_save_with_receipt", Role::Writer, Scope::Namespace),
+    ("memex_save_batch", Role::Writer, Scope::Namespace),
+    (
+        "memex_save_batch_with_policy",
+        Role::Writer,
+        Scope::Namespace,
+    ),
+    ("memex_update_memory", Role::Writer, Scope::Namespace),
+    ("memex_extend_ttl", Role::Writer, Scope::Namespace),
+    ("memex_set_memory_state", Role::Writer, Scope::Namespace),
+    ("memex_link_memories", Role::Writer, Scope::Namespace),
+    ("memex_add_synonym", Role::Writer, Scope::Namespace),
+    ("memex_remove_synonym", Role::Writer, Scope::Namespace),
+    ("memex_save_from_template", Role::Writer, Scope::Global),
+    ("memex_set_template", Role::Writer, Scope::Global),
+    ("memex_create_session", Role::Writer, Scope::Global),
+    ("memex_set_session_status", Role::Writer, Scope::Global),
+    ("memex_delete_memory", Role::Admin, Scope::Namespace),
+    ("memex_set_json_schema", Role::Admin, Scope::Namespace),
+];
+
+/// Functions open to keys in privacy mode; those reporting on memories
+/// aggregate their results through [`MemexHandle::analytics_privacy`]
+const ANALYTICS_OPERATIONS: &[&str] = &[
+    "memex_get_stats",
+    "memex_get_user_stats",
+    "memex_get_user_analytics",
+    "memex_get_session_analytics",
+    "memex_get_counters",
+    "memex_health",
+];
+
+/// Role `op` needs and where
+fn requirement(op: &str) -> (Role, Scope) {
+    OPERATIONS
+        .iter()
+        .find(|(name, ..)| *name == op)
+        .map(|&(_, role, scope)| (role, scope))
+        .unwrap_or((Role::Admin, Scope::Global))
+}
+
+/// Run `body` as extern function `op`
+pub(super) fn as_operation<T>(op: &'static str, body: impl FnOnce() -> T) -> T {
+    let previous = CURRENT_OP.with(|current| current.replace(op));
+    let result = body();
+    CURRENT_OP.with(|current| current.set(previous));
+    result
+}
+
+/// The `analytics_privacy` field of a request context's JSON
+#[derive(Debug, Default, Deserialize)]
+pub struct ContextPrivacy {
+    #[serde(default)]
+    pub analytics_privacy: Option<AnalyticsPrivacy>,
+}
+
+/// Aggregate analytics read on this thread from now on; `None` clears it
+pub(super) fn set_context_privacy(privacy: Option<AnalyticsPrivacy>) {
+    CONTEXT_PRIVACY.with(|current| *current.borrow_mut() = privacy);
+}
+
+/// Fail if the thread's request context is in privacy mode and the current
+/// function isn't open to it
+pub(super) fn authorize_context() -> Result<(), FfiError> {
+    if CONTEXT_PRIVACY.with(|current| current.borrow().is_some()) {
+        authorize_privacy_mode("This request")?;
+    }
+    Ok(())
+}
+
+/// Fail if the current function isn't open to privacy mode keys
+pub(super) fn authorize_privacy_mode(name: &str) -> Result<(), FfiError> {
+    let op = CURRENT_OP.with(Cell::get);
+    if ANALYTICS_OPERATIONS.contains(&op) {
+        return Ok(());
+    }
+    Err(FfiError::new(
+        FfiErrorCode::PermissionDenied,
+        format!("{} may only read aggregated analytics", name),
+    ))
+}
+
+/// Fail unless `principal` may run the current function at all
+pub(super) fn authorize(principal: &Principal) -> Result<(), FfiError> {
+    if principal.analytics_privacy.is_some() {
+        authorize_privacy_mode(&principal.name)?;
+    }
+    match requirement(CURRENT_OP.with(Cell::get)) {
+        (required, Scope::Global) => principal.check(required, None)?,
+        (required, Scope::Namespace) if principal.max_role() < required => {
+            return Err(AccessDenied {
+                principal: principal.name.clone(),
+                role: principal.max_role(),
+                required,
+                namespace: None,
+            }
+            .into())
+        }
+        (_, Scope::Namespace) => {}
+    }
+    Ok(())
+}
+
+impl MemexHandle {
+    /// How analytics should be aggregated for this call: by the instance
+    /// key's privacy mode, else by the request context's, else not at all
+    pub fn analytics_privacy(&self) -> Option<AnalyticsPrivacy> {
+        self.principal
+            .as_ref()
+            .and_then(|principal| principal.analytics_privacy.clone())
+            .or_else(|| CONTEXT_PRIVACY.with(|current| current.borrow().clone()))
+    }
+
+    /// Fail unless the instance's key may run the current function on
+    /// memories of `namespace`; instances opened without a token may do
+    /// anything
+    pub fn authorize_namespace(&self, namespace: &str) -> Result<(), FfiError> {
+        if let Some(principal) = &self.principal {
+            let (required, _) = requirement(CURRENT_OP.with(Cell::get));
+            principal.check(required, Some(namespace))?;
+        }
+        Ok(())
+    }
+
+    /// Record the instance's key as the memory's `ingested_by`, replacing
+    /// whatever the caller claimed; instances opened without a token keep
+    /// the caller's value
+    pub fn attribute(&self, memory: &mut MemoryItem) {
+        if let Some(principal) = &self.principal {
+            memory.ingested_by = Some(principal.name.clone());
+        }
+    }
+
+    /// [`authorize_namespace`](Self::authorize_namespace) for the namespace
+    /// of memory `memory_id`, or the key's default role if there's no such
+    /// memory
+    pub fn authorize_memory(&self, memory_id: &str) -> Result<(), FfiError> {
+        let Some(principal) = &self.principal else {
+            return Ok(());
+        };
+        let (required, _) = requirement(CURRENT_OP.with(Cell::get));
+        let memory = self.database.get_memory(memory_id)?;
+        principal.check(required, memory.as_ref().map(|memory| memory.namespace()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::error::FfiErrorCode;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_unlisted_functions_need_admin() {
+        let writer = Principal {
+            name: "agent".to_string(),
+            role: Role::Writer,
+            namespaces: BTreeMap::new(),
+            analytics_privacy: None,
+        };
+
+        assert!(as_operation("memex_save", || authorize(&writer)).is_ok());
+        assert!(as_operation("memex_recall", || authorize(&writer)).is_ok());
+        for op in [
+            "memex_delete_session",
+            "memex_update_decay_policy",
+            "memex_not_yet_written",
+        ] {
+            let error = as_operation(op, || authorize(&writer)).unwrap_err();
+            assert_eq!(error.code, FfiErrorCode::PermissionDenied);
+        }
+    }
+}
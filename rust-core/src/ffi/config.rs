@@ -0,0 +1,284 @@
+//! Configuration JSON accepted by `memex_init_with_config`
+//!
+//! The top level holds `MemexConfig` fields. Storage settings go in an
+//! optional nested `database` object whose fields mirror `DatabaseConfig`:
+//!
+//! ```json
+//! {
+//!   "database_path": "./memex.db",
+//!   "max_memories_per_user": 5000,
+//!   "database": { "cache_size": -32000, "max_connections": 4, "enable_wal": true }
+//! }
+//! ```
+//!
+//! `database.path` wins over `database_path` when both are given; either way
+//! the two end up equal in the returned configs. `database.preset` names a
+//! `TuningPreset` (`low_memory`, `high_throughput` or `durable`) to start
+//! from; fields given next to it override the preset's values. With the `vector-search`
+//! feature a nested `vector` object (fields of `VectorConfig`) turns on
+//! semantic search for the instance. A top-level `token` opens the instance
+//! as one of the API keys in `access.api_keys`, limited to that key's role.
+
+use serde_json::Value;
+use validator::Validate;
+
+use crate::core::MemexConfig;
+use crate::database::pool::TuningPreset;
+use crate::database::DatabaseConfig;
+use crate::ffi::error::{FfiError, FfiErrorCode};
+
+#[cfg(feature = "vector-search")]
+use crate::database::vector::VectorConfig;
+
+/// Everything needed to build an FFI instance
+#[derive(Debug, Clone, Default)]
+pub struct FfiConfig {
+    pub memex: MemexConfig,
+    pub database: DatabaseConfig,
+    /// API key the instance acts as; `None` leaves it unrestricted
+    pub token: Option<String>,
+    /// Vector search settings; `None` leaves vector search disabled
+    #[cfg(feature = "vector-search")]
+    pub vector: Option<VectorConfig>,
+}
+
+/// Parse FFI configuration JSON
+pub fn parse_config(json: &str) -> Result<FfiConfig, FfiError> {
+    let mut root: Value = serde_json::from_str(json)
+        .map_err(|e| invalid(format!("config is not valid JSON: {}", e)))?;
+
+    let object = root
+        .as_object_mut()
+        .ok_or_else(|| invalid("config must be a JSON object".to_string()))?;
+
+    let mut database = match object.remove("database") {
+        None | Some(Value::Null) => Value::Object(Default::default()),
+        Some(value @ Value::Object(_)) => value,
+        Some(_) => return Err(invalid("`database` must be a JSON object".to_string())),
+    };
+    let has_path = database.get("path").is_some();
+    if let Some(preset) = database.as_object_mut().and_then(|db| db.remove("preset")) {
+        database = with_preset(preset, database)?;
+    }
+
+    let token = match object.remove("token") {
+        None | Some(Value::Null) => None,
+        Some(Value::String(token)) => Some(token),
+        Some(_) => return Err(invalid("`token` must be a string".to_string())),
+    };
+
+    #[cfg(feature = "vector-search")]
+    let vector = match object.remove("vector") {
+        None | Some(Value::Null) => None,
+        Some(value @ Value::Object(_)) => Some(
+            serde_json::from_value::<VectorConfig>(value)
+                .map_err(|e| invalid(format!("invalid `vector` config: {}", e)))?,
+        ),
+        Some(_) => return Err(invalid("`vector` must be a JSON object".to_string())),
+    };
+
+    let mut memex_config: MemexConfig =
+        serde_json::from_value(root).map_err(|e| invalid(format!("invalid config: {}", e)))?;
+
+    let mut db_config: DatabaseConfig = serde_json::from_value(database)
+        .map_err(|e| invalid(format!("invalid `database` config: {}", e)))?;
+
+    if has_path {
+        memex_config.database_path = db_config.path.clone();
+    } else {
+        db_config.path = memex_config.database_path.clone();
+    }
+
+    memex_config
+        .validate()
+        .map_err(|e| invalid(format!("invalid config: {}", e)))?;
+    db_config
+        .validate()
+        .map_err(|e| invalid(format!("invalid `database` config: {}", e)))?;
+
+    Ok(FfiConfig {
+        memex: memex_config,
+        database: db_config,
+        token,
+        #[cfg(feature = "vector-search")]
+        vector,
+    })
+}
+
+/// `overrides` on top of the defaults tuned by `preset`
+fn with_preset(preset: Value, overrides: Value) -> Result<Value, FfiError> {
+    let preset: TuningPreset = serde_json::from_value(preset)
+        .map_err(|e| invalid(format!("invalid `database.preset`: {}", e)))?;
+    let mut config = serde_json::to_value(DatabaseConfig::with_preset("", preset))
+        .map_err(|e| invalid(format!("invalid `database.preset`: {}", e)))?;
+
+    if let (Some(config), Value::Object(overrides)) = (config.as_object_mut(), overrides) {
+        config.extend(overrides);
+    }
+    Ok(config)
+}
+
+fn invalid(message: String) -> FfiError {
+    FfiError::new(FfiErrorCode::InvalidConfig, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(feature = "vector-search")]
+    use crate::database::vector::DistanceMetric;
+    use crate::logging::LogFormat;
+
+    #[test]
+    fn test_top_level_only() {
+        let FfiConfig {
+            memex,
+            database: db,
+            ..
+        } = parse_config(r#"{"database_path": "/tmp/a.db"}"#).unwrap();
+
+        assert_eq!(memex.database_path, "/tmp/a.db");
+        assert_eq!(db.path, "/tmp/a.db");
+        assert_eq!(
+            db.max_connections,
+            DatabaseConfig::default().max_connections
+        );
+        assert_eq!(memex.max_batch_size, MemexConfig::default().max_batch_size);
+    }
+
+    #[test]
+    fn test_nested_database_fields() {
+        let FfiConfig {
+            memex,
+            database: db,
+            ..
+        } = parse_config(
+            r#"{
+                "database_path": "/tmp/a.db",
+                "database": {
+                    "path": "/tmp/b.db",
+                    "enable_wal": false,
+                    "cache_size": -2000,
+                    "busy_timeout": 1000,
+                    "synchronous": "full",
+                    "max_connections": 4,
+                    "min_connections": 1,
+                    "enable_read_replicas": true,
+                    "read_replica_paths": ["/tmp/r1.db"]
+                }
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(db.path, "/tmp/b.db");
+        assert_eq!(memex.database_path, "/tmp/b.db");
+        assert!(!db.enable_wal);
+        assert_eq!(db.cache_size, -2000);
+        assert_eq!(db.busy_timeout, 1000);
+        assert_eq!(db.synchronous, "full");
+        assert_eq!(db.max_connections, 4);
+        assert_eq!(db.min_connections, 1);
+        assert!(db.enable_read_replicas);
+        assert_eq!(db.read_replica_paths, vec!["/tmp/r1.db".to_string()]);
+    }
+
+    #[test]
+    fn test_database_preset() {
+        let FfiConfig {
+            memex,
+            database: db,
+            ..
+        } = parse_config(
+            r#"{
+                "database_path": "/tmp/a.db",
+                "database": { "preset": "low_memory", "max_connections": 3 }
+            }"#,
+        )
+        .unwrap();
+
+        let preset = DatabaseConfig::with_preset("/tmp/a.db", TuningPreset::LowMemory);
+        assert_eq!(db.path, "/tmp/a.db");
+        assert_eq!(memex.database_path, "/tmp/a.db");
+        assert_eq!(db.mmap_size, 0);
+        assert_eq!(db.cache_size, preset.cache_size);
+        assert_eq!(db.temp_store, preset.temp_store);
+        assert_eq!(db.max_connections, 3, "explicit fields win over the preset");
+
+        let err = parse_config(r#"{"database": {"preset": "turbo"}}"#).unwrap_err();
+        assert!(err.message.contains("database.preset"), "{}", err.message);
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_vector_section() {
+        let config = parse_config(r#"{}"#).unwrap();
+        assert!(config.vector.is_none());
+
+        let config = parse_config(r#"{"vector": {"dimension": 8}}"#).unwrap();
+        let vector = config.vector.unwrap();
+        assert_eq!(vector.dimension, 8);
+        assert_eq!(vector.metric, DistanceMetric::Cosine);
+
+        let config = parse_config(r#"{"vector": {"metric": "l2"}}"#).unwrap();
+        assert_eq!(config.vector.unwrap().metric, DistanceMetric::L2);
+
+        assert!(parse_config(r#"{"vector": true}"#).is_err());
+        assert!(parse_config(r#"{"vector": {"metric": "manhattan"}}"#).is_err());
+    }
+
+    #[test]
+    fn test_logging_fields() {
+        let config = parse_config(r#"{}"#).unwrap();
+        assert_eq!(config.memex.log_level, "warn");
+        assert_eq!(config.memex.log_format, LogFormat::Text);
+
+        let config =
+            parse_config(r#"{"log_level": "info,memex_core::ffi=debug", "log_format": "json"}"#)
+                .unwrap();
+        assert_eq!(config.memex.log_level, "info,memex_core::ffi=debug");
+        assert_eq!(config.memex.log_format, LogFormat::Json);
+    }
+
+    #[test]
+    fn test_unknown_database_field_is_named() {
+        let err = parse_config(r#"{"database": {"cache_sise": 10}}"#).unwrap_err();
+
+        assert_eq!(err.code, FfiErrorCode::InvalidConfig);
+        assert!(err.message.contains("cache_sise"), "{}", err.message);
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        let cases = [
+            r#"{"database": {"synchronous": "NORMAL; DROP TABLE memories"}}"#,
+            r#"{"database": {"max_connections": 0}}"#,
+            r#"{"database": {"max_connections": 2, "min_connections": 5}}"#,
+            r#"{"database": {"enable_read_replicas": true}}"#,
+            r#"{"database": {"path": ""}}"#,
+            r#"{"database": 5}"#,
+            r#"{"importance_threshold": 2.0}"#,
+            r#"{"log_level": "memex_core=loud"}"#,
+            r#"{"log_format": "xml"}"#,
+            r#"[1, 2]"#,
+            "not json",
+        ];
+
+        for case in cases {
+            let err = parse_config(case).unwrap_err();
+            assert_eq!(err.code, FfiErrorCode::InvalidConfig, "{}", case);
+        }
+    }
+
+    #[test]
+    fn test_validation_message_is_helpful() {
+        let err = parse_config(r#"{"database": {"max_connections": 2, "min_connections": 5}}"#)
+            .unwrap_err();
+
+        assert!(
+            err.message
+                .contains("min_connections (5) must not exceed max_connections (2)"),
+            "{}",
+            err.message
+        );
+    }
+}
@@ -0,0 +1,351 @@
+//! Last-error registry for FFI callers
+//!
+//! Extern functions can only signal failure through their return value (a
+//! null pointer, `0` or `false`), so the details are parked here, errno-style,
+//! per calling thread. Callers read them back with `memex_get_last_error` and
+//! `memex_error_message`.
+
+use std::cell::RefCell;
+
+/// Stable error codes exposed across the FFI boundary
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FfiErrorCode {
+    Success = 0,
+    InvalidArgument = 1,
+    InvalidConfig = 2,
+    InvalidHandle = 3,
+    DatabaseError = 4,
+    SerializationError = 5,
+    Panic = 6,
+    NotFound = 7,
+    InvalidUtf8 = 8,
+    LimitExceeded = 9,
+    RateLimited = 10,
+    Timeout = 11,
+    ReadOnly = 12,
+    Conflict = 13,
+    PermissionDenied = 14,
+}
+
+impl FfiErrorCode {
+    pub fn from_code(code: i32) -> Option<Self> {
+        match code {
+            0 => Some(Self::Success),
+            1 => Some(Self::InvalidArgument),
+            2 => Some(Self::InvalidConfig),
+            3 => Some(Self::InvalidHandle),
+            4 => Some(Self::DatabaseError),
+            5 => Some(Self::SerializationError),
+            6 => Some(Self::Panic),
+            7 => Some(Self::NotFound),
+            8 => Some(Self::InvalidUtf8),
+            9 => Some(Self::LimitExceeded),
+            10 => Some(Self::RateLimited),
+            11 => Some(Self::Timeout),
+            12 => Some(Self::ReadOnly),
+            13 => Some(Self::Conflict),
+            14 => Some(Self::PermissionDenied),
+            _ => None,
+        }
+    }
+
+    /// Generic description used when no detailed message is available
+    pub fn description(self) -> &'static str {
+        match self {
+            Self::Success => "Success",
+            Self::InvalidArgument => "Invalid argument",
+            Self::InvalidConfig => "Invalid configuration",
+            Self::InvalidHandle => "Invalid or destroyed handle",
+            Self::DatabaseError => "Database error",
+            Self::SerializationError => "Serialization error",
+            Self::Panic => "Internal panic",
+            Self::NotFound => "Not found",
+            Self::InvalidUtf8 => "Invalid UTF-8 in string argument",
+            Self::LimitExceeded => "Size limit exceeded",
+            Self::RateLimited => "Rate limit exceeded",
+            Self::Timeout => "Query timed out",
+            Self::ReadOnly => "Database is read-only",
+            Self::Conflict => "Version conflict",
+            Self::PermissionDenied => "Permission denied",
+        }
+    }
+}
+
+/// An error recorded for the current thread
+#[derive(Debug, Clone, PartialEq)]
+pub struct FfiError {
+    pub code: FfiErrorCode,
+    pub message: String,
+    /// Offending field, when known
+    pub field: Option<String>,
+    /// Allowed maximum and the actual size, for `LimitExceeded`
+    pub limit: Option<usize>,
+    pub actual: Option<usize>,
+    /// Milliseconds to wait before retrying, for `RateLimited`
+    pub retry_after_ms: Option<u64>,
+}
+
+impl FfiError {
+    pub fn new(code: FfiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            field: None,
+            limit: None,
+            actual: None,
+            retry_after_ms: None,
+        }
+    }
+
+    /// Attach the offending field's name
+    pub fn with_field(mut self, field: impl Into<String>) -> Self {
+        self.field = Some(field.into());
+        self
+    }
+
+    /// Attach the field name and limit that were violated
+    pub fn with_limit(mut self, field: impl Into<String>, limit: usize, actual: usize) -> Self {
+        self.field = Some(field.into());
+        self.limit = Some(limit);
+        self.actual = Some(actual);
+        self
+    }
+
+    /// JSON form returned by `memex_last_error_json`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "code": self.code as i32,
+            "description": self.code.description(),
+            "message": self.message,
+            "field": self.field,
+            "limit": self.limit,
+            "actual": self.actual,
+            "retry_after_ms": self.retry_after_ms,
+        })
+    }
+}
+
+impl std::fmt::Display for FfiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.code.description(), self.message)
+    }
+}
+
+impl std::error::Error for FfiError {}
+
+impl From<crate::core::access::AccessDenied> for FfiError {
+    fn from(denied: crate::core::access::AccessDenied) -> Self {
+        Self::new(FfiErrorCode::PermissionDenied, denied.to_string())
+    }
+}
+
+impl From<anyhow::Error> for FfiError {
+    /// Rejected input maps to a validation code, cancelled queries to
+    /// `Timeout`, writes to a read-only database to `ReadOnly` and stale
+    /// `expected_version`s and disallowed state changes to `Conflict`;
+    /// anything else is treated as a storage failure
+    fn from(error: anyhow::Error) -> Self {
+        use crate::core::ValidationError;
+        use crate::database::pool::{QueryTimeout, ReadOnlyDatabase};
+        use crate::database::{InvalidStateTransition, VersionConflict};
+
+        let message = format!("{:#}", error);
+        if error.chain().any(|cause| cause.is::<QueryTimeout>()) {
+            return Self::new(FfiErrorCode::Timeout, message);
+        }
+        if error.chain().any(|cause| cause.is::<ReadOnlyDatabase>()) {
+            return Self::new(FfiErrorCode::ReadOnly, message);
+        }
+        if error
+            .chain()
+            .any(|cause| cause.is::<VersionConflict>() || cause.is::<InvalidStateTransition>())
+        {
+            return Self::new(FfiErrorCode::Conflict, message);
+        }
+
+        let validation = error
+            .chain()
+            .find_map(|cause| cause.downcast_ref::<ValidationError>());
+
+        match validation {
+            Some(ValidationError::RateLimitExceeded { retry_after }) => Self {
+                retry_after_ms: Some(retry_after.as_millis().min(u64::MAX as u128) as u64),
+                ..Self::new(FfiErrorCode::RateLimited, message)
+            },
+            Some(ValidationError::BatchSizeExceeded { size, max }) => {
+                Self::new(FfiErrorCode::LimitExceeded, message).with_limit("batch", *max, *size)
+            }
+            Some(ValidationError::UserQuotaExceeded { current, max }) => {
+                Self::new(FfiErrorCode::LimitExceeded, message)
+                    .with_limit("memories", *max, *current)
+            }
+            Some(ValidationError::ByteQuotaExceeded { requested, max }) => Self::new(
+                FfiErrorCode::LimitExceeded,
+                message,
+            )
+            .with_limit("bytes", *max as usize, *requested as usize),
+            Some(ValidationError::UnknownSession { .. }) => Self {
+                field: Some("session_id".to_string()),
+                ..Self::new(FfiErrorCode::NotFound, message)
+            },
+            Some(ValidationError::InvalidInput {
+                field: Some(field),
+                limit: Some(limit),
+                actual: Some(actual),
+                ..
+            }) => Self::new(FfiErrorCode::LimitExceeded, message).with_limit(
+                field.clone(),
+                *limit,
+                *actual,
+            ),
+            Some(ValidationError::InvalidInput { field, .. }) => Self {
+                field: field.clone(),
+                ..Self::new(FfiErrorCode::InvalidArgument, message)
+            },
+            None if error
+                .chain()
+                .any(|cause| cause.is::<validator::ValidationErrors>()) =>
+            {
+                Self::new(FfiErrorCode::InvalidArgument, message)
+            }
+            None => Self::new(FfiErrorCode::DatabaseError, message),
+        }
+    }
+}
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<FfiError>> = const { RefCell::new(None) };
+}
+
+/// Record an error for the current thread, replacing any previous one
+pub fn set_last_error(error: FfiError) {
+    tracing::debug!(code = ?error.code, "FFI error recorded: {}", error);
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = Some(error));
+}
+
+/// Forget the current thread's error; called at the start of a successful call
+pub fn clear_last_error() {
+    LAST_ERROR.with(|slot| *slot.borrow_mut() = None);
+}
+
+/// The current thread's last error, if any
+pub fn last_error() -> Option<FfiError> {
+    LAST_ERROR.with(|slot| slot.borrow().clone())
+}
+
+/// Error code of the current thread's last error, `Success` if none
+pub fn last_error_code() -> FfiErrorCode {
+    last_error()
+        .map(|e| e.code)
+        .unwrap_or(FfiErrorCode::Success)
+}
+
+/// Message for `code`: the detailed message if it is the current thread's
+/// last error, otherwise the generic description
+pub fn error_message(code: i32) -> String {
+    match last_error() {
+        Some(error) if error.code as i32 == code => error.to_string(),
+        _ => FfiErrorCode::from_code(code)
+            .map(|c| c.description())
+            .unwrap_or("Unknown error")
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_and_clear() {
+        clear_last_error();
+        assert_eq!(last_error_code(), FfiErrorCode::Success);
+
+        set_last_error(FfiError::new(FfiErrorCode::InvalidConfig, "bad json"));
+        assert_eq!(last_error_code(), FfiErrorCode::InvalidConfig);
+        assert_eq!(
+            error_message(FfiErrorCode::InvalidConfig as i32),
+            "Invalid configuration: bad json"
+        );
+
+        // Other codes still get their generic description
+        assert_eq!(
+            error_message(FfiErrorCode::InvalidHandle as i32),
+            "Invalid or destroyed handle"
+        );
+        assert_eq!(error_message(999), "Unknown error");
+
+        clear_last_error();
+        assert!(last_error().is_none());
+    }
+
+    #[test]
+    fn test_from_anyhow() {
+        use crate::core::ValidationError;
+
+        let error: FfiError = anyhow::Error::new(ValidationError::RateLimitExceeded {
+            retry_after: std::time::Duration::from_millis(1500),
+        })
+        .context("Failed to save")
+        .into();
+        assert_eq!(error.code, FfiErrorCode::RateLimited);
+        assert!(error.message.starts_with("Failed to save: Rate limit"));
+        assert_eq!(error.retry_after_ms, Some(1500));
+        assert_eq!(error.to_json()["retry_after_ms"], 1500);
+
+        let error: FfiError = anyhow::Error::new(ValidationError::InvalidInput {
+            message: "too big".to_string(),
+            field: Some("content".to_string()),
+            limit: Some(10),
+            actual: Some(20),
+        })
+        .into();
+        assert_eq!(error.code, FfiErrorCode::LimitExceeded);
+        assert_eq!(error.field.as_deref(), Some("content"));
+        assert_eq!((error.limit, error.actual), (Some(10), Some(20)));
+
+        let error: FfiError = anyhow::Error::new(ValidationError::invalid_input("bad")).into();
+        assert_eq!(error.code, FfiErrorCode::InvalidArgument);
+
+        let error: FfiError = anyhow::Error::new(ValidationError::UnknownSession {
+            session_id: "s1".to_string(),
+            user_id: "u1".to_string(),
+        })
+        .into();
+        assert_eq!(error.code, FfiErrorCode::NotFound);
+        assert_eq!(error.field.as_deref(), Some("session_id"));
+
+        let error: FfiError =
+            anyhow::Error::new(crate::database::pool::QueryTimeout { timeout_ms: 50 })
+                .context("Failed to recall memories")
+                .into();
+        assert_eq!(error.code, FfiErrorCode::Timeout);
+
+        let error: FfiError = anyhow::Error::new(crate::database::pool::ReadOnlyDatabase {
+            path: "reports.db".to_string(),
+        })
+        .into();
+        assert_eq!(error.code, FfiErrorCode::ReadOnly);
+
+        let error: FfiError = anyhow::Error::new(crate::database::VersionConflict {
+            id: "m1".to_string(),
+            expected: 1,
+            actual: 2,
+        })
+        .into();
+        assert_eq!(error.code, FfiErrorCode::Conflict);
+
+        let error: FfiError = anyhow::anyhow!("disk I/O error").into();
+        assert_eq!(error.code, FfiErrorCode::DatabaseError);
+    }
+
+    #[test]
+    fn test_errors_are_per_thread() {
+        set_last_error(FfiError::new(FfiErrorCode::DatabaseError, "locked"));
+
+        let other = std::thread::spawn(last_error_code).join().unwrap();
+        assert_eq!(other, FfiErrorCode::Success);
+        assert_eq!(last_error_code(), FfiErrorCode::DatabaseError);
+    }
+}
@@ -1,84 +1,620 @@
 //! Foreign Function Interface (FFI) module for Node.js bindings
+//!
+//! The `memex_*` extern functions in `lib.rs` all operate on a
+//! [`MemexHandle`], looked up by the integer handle `memex_init*` returned.
+//! This module owns that registry plus the small helpers the extern functions
+//! share for argument conversion, error recording and panic safety.
 
+pub mod access;
+pub mod config;
+pub mod error;
+pub mod recall_stream;
+
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::sync::Mutex;
+use std::ffi::{CStr, CString};
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::Context;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
 
+use crate::core::access::Principal;
+use crate::core::context::RequestContext;
+use crate::core::counters::EventCounters;
 use crate::core::decay::DecayEngine;
+use crate::core::maintenance::{MaintenanceScheduler, SchedulerThread};
 use crate::core::memory::MemoryManager;
 use crate::core::session::SessionManager;
 use crate::core::{MemexConfig, RequestValidator};
-use crate::database::models::*;
-use crate::database::{Database, DatabaseConfig};
+use crate::database::models::QueryFilter;
+use crate::database::Database;
+use crate::logging;
+use crate::wire::WireFormat;
+
+#[cfg(feature = "vector-search")]
+use crate::database::vector::{VectorFilter, VectorSearchEngine};
+
+use config::FfiConfig;
+use error::{FfiError, FfiErrorCode};
+
+thread_local! {
+    /// Set by `memex_set_request_context`; calls on the thread run inside it
+    static REQUEST_CONTEXT: RefCell<Option<RequestContext>> = const { RefCell::new(None) };
+}
 
 // Global state for FFI instances
 static INSTANCE_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
-static INSTANCES: once_cell::sync::Lazy<Mutex<HashMap<usize, MemexHandle>>> =
+static INSTANCES: once_cell::sync::Lazy<Mutex<HashMap<usize, Arc<MemexHandle>>>> =
     once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
 
-/// Basic Memex FFI handle
-#[allow(dead_code)]
+/// Everything one FFI instance works with
 pub struct MemexHandle {
-    memory_manager: MemoryManager,
-    session_manager: SessionManager,
-    decay_engine: DecayEngine,
-    config: MemexConfig,
+    pub config: MemexConfig,
+    pub database: Database,
+    pub memory_manager: MemoryManager,
+    pub session_manager: SessionManager,
+    /// Behind a mutex because policy updates need `&mut`; shared with the
+    /// maintenance scheduler so scheduled decay follows the same policy
+    pub decay_engine: Arc<Mutex<DecayEngine>>,
+    pub maintenance: Arc<MaintenanceScheduler>,
+    /// Runs due maintenance jobs while `maintenance.enabled` is set; stops
+    /// when the instance is dropped
+    _maintenance_thread: Option<SchedulerThread>,
+    /// Operation counts shared by both managers
+    pub counters: Arc<EventCounters>,
+    /// Key the instance was opened with; `None` if it was opened without a
+    /// token and may do anything
+    pub principal: Option<Principal>,
+    #[cfg(feature = "vector-search")]
+    pub vector_engine: Option<VectorSearchEngine>,
+}
+
+impl MemexHandle {
+    /// Open the database and wire up the managers
+    pub fn new(config: FfiConfig) -> anyhow::Result<Self> {
+        let database = Database::new(config.database).context("Failed to open database")?;
+        if let Some(wal_archive) = &config.memex.maintenance.wal_archive {
+            database.enable_wal_archive(wal_archive.clone())?;
+        }
+        if config.memex.seal_memories {
+            database.enable_integrity()?;
+        }
+        let validator = RequestValidator::new(&config.memex);
+
+        #[allow(unused_mut)]
+        let mut memory_manager = MemoryManager::new(database.clone(), validator.clone());
+        let mut session_manager = SessionManager::new(database.clone(), validator.clone());
+        let counters = memory_manager.counters();
+        session_manager.use_counters(counters.clone());
+
+        #[cfg(feature = "vector-search")]
+        let vector_engine = match config.vector {
+            Some(vector_config) => {
+                let engine = VectorSearchEngine::new(database.get_connection_pool(), vector_config);
+                engine
+                    .initialize_schema()
+                    .context("Failed to initialize vector search")?;
+                memory_manager.enable_vector_search(engine.clone());
+                if let Some(backfill) = &config.memex.maintenance.embedding_backfill {
+                    memory_manager.set_query_embedder(Arc::new(backfill.embedder()));
+                }
+                session_manager.enable_vector_search(engine.clone());
+                Some(engine)
+            }
+            None => None,
+        };
+
+        let decay_engine = Arc::new(Mutex::new(DecayEngine::new(
+            database.clone(),
+            validator,
+            config.memex.decay_policy(),
+        )));
+        let mut maintenance = MaintenanceScheduler::new(
+            database.clone(),
+            decay_engine.clone(),
+            config.memex.maintenance_schedules(),
+            config.memex.maintenance.embedding_backfill.clone(),
+        );
+        if let Some(backup) = &config.memex.maintenance.backup {
+            maintenance.enable_backups(backup.clone());
+        }
+        #[cfg(feature = "vector-search")]
+        if let Some(engine) = &vector_engine {
+            maintenance.enable_vector_search(engine.clone());
+        }
+        let maintenance = Arc::new(maintenance);
+        let maintenance_thread = if config.memex.maintenance.enabled {
+            let poll_interval =
+                std::time::Duration::from_secs(config.memex.maintenance.poll_interval_secs);
+            Some(maintenance.clone().spawn(poll_interval)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            memory_manager,
+            session_manager,
+            decay_engine,
+            maintenance,
+            _maintenance_thread: maintenance_thread,
+            counters,
+            principal: None,
+            #[cfg(feature = "vector-search")]
+            vector_engine,
+            config: config.memex,
+            database,
+        })
+    }
+
+    /// Exclusive access to the decay engine
+    pub fn decay_engine(&self) -> MutexGuard<'_, DecayEngine> {
+        self.decay_engine
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The vector search engine, if the instance was configured with one
+    #[cfg(feature = "vector-search")]
+    pub fn vector_engine(&self) -> Result<&VectorSearchEngine, FfiError> {
+        self.vector_engine.as_ref().ok_or_else(|| {
+            FfiError::new(
+                FfiErrorCode::InvalidConfig,
+                "vector search is not enabled; pass a `vector` object in the config",
+            )
+        })
+    }
 }
 
-// Main initialization function
-pub fn create_memex_instance(
-    config: MemexConfig,
-) -> Result<usize, Box<dyn std::error::Error>> {
-    init_with_config(config)
+/// Arguments of `memex_vector_search`
+#[cfg(feature = "vector-search")]
+#[derive(Debug, serde::Deserialize)]
+pub struct VectorSearchRequest {
+    pub embedding: Vec<f32>,
+    pub model: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub filter: VectorFilter,
 }
 
-fn init_with_config(config: MemexConfig) -> Result<usize, Box<dyn std::error::Error>> {
-    // Initialize database
-    let db_config = DatabaseConfig {
-        path: config.database_path.clone(),
-        ..Default::default()
-    };
+/// Arguments of `memex_hybrid_search`
+#[cfg(feature = "vector-search")]
+#[derive(Debug, serde::Deserialize)]
+pub struct HybridSearchRequest {
+    pub text: String,
+    pub embedding: Vec<f32>,
+    pub model: String,
+    #[serde(default = "default_search_weight")]
+    pub text_weight: f32,
+    #[serde(default = "default_search_weight")]
+    pub vector_weight: f32,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    #[serde(default)]
+    pub filter: VectorFilter,
+}
+
+/// Arguments of `memex_search_sessions_semantic`
+#[cfg(feature = "vector-search")]
+#[derive(Debug, serde::Deserialize)]
+pub struct SessionSearchRequest {
+    pub user_id: String,
+    pub embedding: Vec<f32>,
+    pub model: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[cfg(feature = "vector-search")]
+fn default_search_weight() -> f32 {
+    0.5
+}
+
+/// Reject a vector search filter with out-of-range fields
+#[cfg(feature = "vector-search")]
+pub fn validate_filter(filter: &VectorFilter) -> Result<(), FfiError> {
+    use validator::Validate;
+
+    filter.validate().map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            format!("`filter` is not valid: {}", e),
+        )
+    })
+}
+
+fn instances() -> MutexGuard<'static, HashMap<usize, Arc<MemexHandle>>> {
+    // Entries are only inserted or removed under the lock, so a panic
+    // elsewhere can't leave the map half-updated
+    INSTANCES
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
 
-    let database = Database::new(db_config)?;
-    let validator = RequestValidator::new(&config);
-
-    // Initialize core components
-    let memory_manager = MemoryManager::new(database.clone(), validator.clone());
-    let session_manager = SessionManager::new(database.clone(), validator.clone());
-    let decay_policy = DecayPolicy::default();
-    let decay_engine = DecayEngine::new(database, validator, decay_policy);
-
-    // Create handle
-    let handle = MemexHandle {
-        memory_manager,
-        session_manager,
-        decay_engine,
-        config,
+/// Build an instance and register it, returning its handle
+///
+/// Also (re)configures logging from the config's `log_level` and `log_format`;
+/// the logger is process-wide, so the most recently created instance wins.
+/// A config with a `token` opens an instance restricted to that key's role.
+pub fn create_instance(config: FfiConfig) -> Result<usize, FfiError> {
+    logging::init(&config.memex.log_level, config.memex.log_format)
+        .map_err(|e| FfiError::new(FfiErrorCode::InvalidConfig, format!("{:#}", e)))?;
+
+    let principal = match &config.token {
+        None => None,
+        Some(_) if !config.memex.access.is_enabled() => {
+            return Err(FfiError::new(
+                FfiErrorCode::InvalidConfig,
+                "a token was given but `access.api_keys` is empty",
+            ))
+        }
+        Some(token) => Some(config.memex.access.authenticate(token).ok_or_else(|| {
+            FfiError::new(
+                FfiErrorCode::PermissionDenied,
+                "token doesn't match any configured API key",
+            )
+        })?),
     };
 
-    // Generate unique instance ID
-    let instance_id = INSTANCE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    let mut handle = MemexHandle::new(config).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::DatabaseError,
+            format!("failed to open database: {:#}", e),
+        )
+    })?;
+    handle.principal = principal;
 
-    // Store instance
-    let mut instances = INSTANCES.lock().unwrap();
-    instances.insert(instance_id, handle);
+    let instance_id = INSTANCE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    instances().insert(instance_id, Arc::new(handle));
 
-    log::debug!("Created Memex instance {}", instance_id);
+    tracing::debug!(handle = instance_id, "Created Memex instance");
     Ok(instance_id)
 }
 
-// Helper functions for internal use
-pub fn get_instance(
-    handle: usize,
-) -> Option<std::sync::MutexGuard<'static, HashMap<usize, MemexHandle>>> {
-    if handle == 0 {
-        return None;
+/// Look up a live instance
+///
+/// The returned `Arc` keeps the instance usable for the rest of the call even
+/// if another thread destroys the handle meanwhile. Fails with
+/// `PermissionDenied` if the instance was opened with a token whose role
+/// doesn't allow the running extern function.
+pub fn get_instance(handle: usize) -> Result<Arc<MemexHandle>, FfiError> {
+    let instance = instances().get(&handle).cloned().ok_or_else(|| {
+        FfiError::new(
+            FfiErrorCode::InvalidHandle,
+            format!("no Memex instance with handle {}", handle),
+        )
+    })?;
+    if let Some(principal) = &instance.principal {
+        access::authorize(principal)?;
+    }
+    access::authorize_context()?;
+    Ok(instance)
+}
+
+/// Whether `handle` refers to a live instance
+pub fn is_valid(handle: usize) -> bool {
+    instances().contains_key(&handle)
+}
+
+/// Unregister an instance; returns false if it didn't exist
+pub fn destroy_instance(handle: usize) -> bool {
+    let removed = instances().remove(&handle).is_some();
+    if removed {
+        recall_stream::end_for_instance(handle);
+        tracing::debug!(handle = handle, "Destroyed Memex instance");
+    }
+    removed
+}
+
+/// Run the body of an extern function
+///
+/// Clears the thread's last error, catches panics and records any failure so
+/// callers can fetch it with `memex_get_last_error`. `None` means the call
+/// failed and the extern function should return its failure value. Records
+/// logged during the call carry `op = name`, and the call runs inside the
+/// thread's request context, if one is set.
+pub fn call<T>(name: &'static str, body: impl FnOnce() -> Result<T, FfiError>) -> Option<T> {
+    error::clear_last_error();
+    let _context = REQUEST_CONTEXT.with(|context| context.borrow().as_ref().map(|c| c.enter()));
+    let _span = logging::span(name);
+
+    match access::as_operation(name, || panic::catch_unwind(AssertUnwindSafe(body))) {
+        Ok(Ok(value)) => Some(value),
+        Ok(Err(error)) => {
+            error::set_last_error(error);
+            None
+        }
+        Err(payload) => {
+            let detail = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            error::set_last_error(FfiError::new(
+                FfiErrorCode::Panic,
+                format!("panic in {}: {}", name, detail),
+            ));
+            None
+        }
+    }
+}
+
+/// Run later calls on this thread inside `context`, aggregating the
+/// analytics they read if `privacy` is set; `None` clears either
+pub fn set_request_context(
+    context: Option<RequestContext>,
+    privacy: Option<crate::core::privacy::AnalyticsPrivacy>,
+) {
+    REQUEST_CONTEXT.with(|current| *current.borrow_mut() = context);
+    access::set_context_privacy(privacy);
+}
+
+/// Borrow a required string argument
+///
+/// # Safety
+///
+/// `ptr` must be null or point to a NUL-terminated string that outlives `'a`.
+pub unsafe fn str_arg<'a>(ptr: *const c_char, name: &str) -> Result<&'a str, FfiError> {
+    optional_str_arg(ptr, name)?.ok_or_else(|| {
+        FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            format!("`{}` must not be null", name),
+        )
+    })
+}
+
+/// Borrow an optional string argument; null maps to `None`
+///
+/// # Safety
+///
+/// Same requirements as [`str_arg`].
+pub unsafe fn optional_str_arg<'a>(
+    ptr: *const c_char,
+    name: &str,
+) -> Result<Option<&'a str>, FfiError> {
+    if ptr.is_null() {
+        return Ok(None);
     }
-    let instances = INSTANCES.lock().unwrap();
-    if instances.contains_key(&handle) {
-        Some(instances)
-    } else {
-        None
+
+    CStr::from_ptr(ptr).to_str().map(Some).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::InvalidUtf8,
+            format!("`{}` is not valid UTF-8: {}", name, e),
+        )
+    })
+}
+
+/// Deserialize a JSON argument
+pub fn json_arg<T: DeserializeOwned>(json: &str, name: &str) -> Result<T, FfiError> {
+    serde_json::from_str(json).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            format!("`{}` is not valid: {}", name, e),
+        )
+    })
+}
+
+/// Deserialize a `QueryFilter` argument
+///
+/// Unknown keys and values of the wrong type are `InvalidArgument` errors
+/// naming the offending field. With `lenient`, unknown keys are ignored and
+/// a filter that still doesn't parse falls back to `QueryFilter::default()`.
+pub fn filter_arg(json: &str, name: &str, lenient: bool) -> Result<QueryFilter, FfiError> {
+    if lenient {
+        return Ok(serde_json::from_str(json).unwrap_or_else(|e| {
+            tracing::warn!(argument = name, error = %e, "Ignoring filter that doesn't parse");
+            QueryFilter::default()
+        }));
+    }
+
+    let value: serde_json::Value = json_arg(json, name)?;
+    let fields = value.as_object().ok_or_else(|| {
+        FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            format!("`{}` must be a JSON object", name),
+        )
+    })?;
+
+    let known = match serde_json::to_value(QueryFilter::default()) {
+        Ok(serde_json::Value::Object(defaults)) => defaults,
+        _ => serde_json::Map::new(),
+    };
+    if let Some(unknown) = fields.keys().find(|key| !known.contains_key(*key)) {
+        let expected: Vec<&str> = known.keys().map(String::as_str).collect();
+        return Err(FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            format!(
+                "`{}` has unknown field `{}`; expected one of {}",
+                name,
+                unknown,
+                expected.join(", ")
+            ),
+        )
+        .with_field(unknown));
     }
+
+    serde_json::from_value(value.clone()).map_err(|e| {
+        // Every field is optional, so the first one that fails on its own
+        // is the culprit
+        let culprit = fields.iter().find_map(|(key, field)| {
+            let single = serde_json::json!({ key: field });
+            serde_json::from_value::<QueryFilter>(single)
+                .err()
+                .map(|e| (key, e))
+        });
+        match culprit {
+            Some((key, e)) => FfiError::new(
+                FfiErrorCode::InvalidArgument,
+                format!("`{}` field `{}` is not valid: {}", name, key, e),
+            )
+            .with_field(key),
+            None => FfiError::new(
+                FfiErrorCode::InvalidArgument,
+                format!("`{}` is not valid: {}", name, e),
+            ),
+        }
+    })
+}
+
+/// Serialize a result into a string owned by the caller
+///
+/// Free it with `memex_free_string`.
+pub fn json_result<T: Serialize>(value: &T) -> Result<*mut c_char, FfiError> {
+    let json = serde_json::to_string(value).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::SerializationError,
+            format!("failed to serialize result: {}", e),
+        )
+    })?;
+    string_result(json)
+}
+
+/// Encode a result in `format` into a buffer owned by the caller, storing
+/// its length in `out_len`
+///
+/// Free it with `memex_free_buffer`.
+pub fn encoded_result<T: Serialize>(
+    format: WireFormat,
+    value: &T,
+    out_len: Option<&mut usize>,
+) -> Result<*mut u8, FfiError> {
+    let out_len = out_len.ok_or_else(|| {
+        FfiError::new(FfiErrorCode::InvalidArgument, "`out_len` must not be null")
+    })?;
+    let bytes = format.encode(value).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::SerializationError,
+            format!("failed to serialize result: {:#}", e),
+        )
+    })?;
+
+    *out_len = bytes.len();
+    Ok(Box::into_raw(bytes.into_boxed_slice()) as *mut u8)
+}
+
+/// Hand a string over to the caller; free it with `memex_free_string`
+pub fn string_result(value: String) -> Result<*mut c_char, FfiError> {
+    CString::new(value).map(CString::into_raw).map_err(|e| {
+        FfiError::new(
+            FfiErrorCode::SerializationError,
+            format!("result contains a NUL byte: {}", e),
+        )
+    })
+}
+
+/// Map a C `limit`/`offset` to an optional count; negative means "default"
+pub fn optional_count(value: i32) -> Option<usize> {
+    usize::try_from(value).ok()
 }
 
-// FFI functions are defined in lib.rs
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn test_config(temp_dir: &TempDir) -> FfiConfig {
+        let mut config = FfiConfig::default();
+        config.database.path = temp_dir.path().join("ffi.db").to_string_lossy().to_string();
+        config.memex.database_path = config.database.path.clone();
+        config
+    }
+
+    #[test]
+    fn test_instance_lifecycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let handle = create_instance(test_config(&temp_dir)).unwrap();
+
+        assert!(is_valid(handle));
+        let instance = get_instance(handle).unwrap();
+
+        assert!(destroy_instance(handle));
+        assert!(!is_valid(handle));
+        assert!(!destroy_instance(handle));
+        assert_eq!(
+            get_instance(handle).err().unwrap().code,
+            FfiErrorCode::InvalidHandle
+        );
+
+        // Calls already in flight keep a working instance
+        assert!(instance.database.get_pool_status().is_healthy());
+    }
+
+    #[test]
+    fn test_call_records_errors_and_panics() {
+        let result: Option<()> = call("test", || {
+            Err(FfiError::new(FfiErrorCode::NotFound, "missing"))
+        });
+        assert!(result.is_none());
+        assert_eq!(error::last_error_code(), FfiErrorCode::NotFound);
+
+        assert_eq!(call("test", || Ok(5)), Some(5));
+        assert_eq!(error::last_error_code(), FfiErrorCode::Success);
+
+        let result: Option<()> = call("test", || panic!("boom"));
+        assert!(result.is_none());
+        let error = error::last_error().unwrap();
+        assert_eq!(error.code, FfiErrorCode::Panic);
+        assert!(error.message.contains("boom"));
+    }
+
+    #[test]
+    fn test_string_args() {
+        let value = CString::new("hello").unwrap();
+        assert_eq!(
+            unsafe { str_arg(value.as_ptr(), "value") }.unwrap(),
+            "hello"
+        );
+        assert_eq!(
+            unsafe { optional_str_arg(std::ptr::null(), "value") }.unwrap(),
+            None
+        );
+
+        let error = unsafe { str_arg(std::ptr::null(), "user_id") }.unwrap_err();
+        assert_eq!(error.code, FfiErrorCode::InvalidArgument);
+        assert!(error.message.contains("user_id"));
+
+        let invalid = [0xffu8, 0xfe, 0];
+        let error = unsafe { str_arg(invalid.as_ptr() as *const c_char, "content") }.unwrap_err();
+        assert_eq!(error.code, FfiErrorCode::InvalidUtf8);
+    }
+
+    #[test]
+    fn test_filter_args() {
+        let filter =
+            filter_arg(r#"{"user_id": "alice", "limit": 5}"#, "filter_json", false).unwrap();
+        assert_eq!(filter.user_id.as_deref(), Some("alice"));
+        assert_eq!(filter.limit, Some(5));
+
+        let error = filter_arg(r#"{"userId": "alice"}"#, "filter_json", false).unwrap_err();
+        assert_eq!(error.code, FfiErrorCode::InvalidArgument);
+        assert_eq!(error.field.as_deref(), Some("userId"));
+        assert!(error.message.contains("unknown field `userId`"));
+        assert!(error.message.contains("user_id"));
+
+        let error = filter_arg(
+            r#"{"user_id": "alice", "limit": "ten"}"#,
+            "filter_json",
+            false,
+        )
+        .unwrap_err();
+        assert_eq!(error.field.as_deref(), Some("limit"));
+        assert!(error.message.contains("field `limit` is not valid"));
+
+        assert!(filter_arg("[1, 2]", "filter_json", false).is_err());
+        assert!(filter_arg("{", "filter_json", false).is_err());
+
+        // Lenient parsing ignores unknown fields and falls back to no filter
+        let filter = filter_arg(
+            r#"{"userId": "x", "user_id": "alice"}"#,
+            "filter_json",
+            true,
+        )
+        .unwrap();
+        assert_eq!(filter.user_id.as_deref(), Some("alice"));
+        let filter = filter_arg(r#"{"limit": "ten"}"#, "filter_json", true).unwrap();
+        assert_eq!(filter.limit, QueryFilter::default().limit);
+    }
+}
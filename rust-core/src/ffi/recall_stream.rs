@@ -0,0 +1,264 @@
+//! Chunked recall for result sets too large to hand over as one string
+//!
+//! `memex_recall_begin` registers a stream for a filter, each
+//! `memex_recall_next_chunk` serializes only the next page, and
+//! `memex_recall_end` drops the stream. Pages are fetched with the recall
+//! keyset cursor (`QueryFilter::before_id`), so neither side ever holds the
+//! whole result set.
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use anyhow::Context;
+use validator::Validate;
+
+use super::error::{FfiError, FfiErrorCode};
+use super::get_instance;
+use crate::database::models::{MemoryItem, QueryFilter};
+use crate::wire::WireFormat;
+
+/// Memories per chunk when the caller doesn't pick a size
+pub const DEFAULT_CHUNK_SIZE: usize = 500;
+
+/// Largest chunk; the most one recall page may hold
+pub const MAX_CHUNK_SIZE: usize = 1000;
+
+static STREAM_COUNTER: AtomicUsize = AtomicUsize::new(1);
+static STREAMS: once_cell::sync::Lazy<Mutex<HashMap<usize, Arc<Mutex<RecallStream>>>>> =
+    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Where a stream is in its result set
+#[derive(Debug)]
+struct RecallStream {
+    /// Instance the stream reads from
+    handle: usize,
+    /// Filter for the next page; `before_id` advances after each chunk
+    filter: QueryFilter,
+    chunk_size: usize,
+    /// Memories still to send when the filter had a `limit`
+    remaining: Option<usize>,
+    finished: bool,
+}
+
+fn streams() -> MutexGuard<'static, HashMap<usize, Arc<Mutex<RecallStream>>>> {
+    STREAMS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Register a stream over the memories `filter` matches, returning its id
+///
+/// The filter's `limit` caps the whole stream rather than one chunk, and its
+/// `offset` skips memories before the first chunk. Fuzzy recall can't be
+/// streamed since its matches aren't in keyset order.
+pub fn begin(handle: usize, mut filter: QueryFilter, chunk_size: usize) -> Result<usize, FfiError> {
+    get_instance(handle)?;
+    if filter.fuzzy {
+        return Err(FfiError::new(
+            FfiErrorCode::InvalidArgument,
+            "fuzzy recall can't be streamed",
+        )
+        .with_field("fuzzy"));
+    }
+
+    let remaining = filter.limit.take();
+    filter.limit = Some(chunk_size.clamp(1, MAX_CHUNK_SIZE));
+    filter
+        .validate()
+        .context("Filter validation failed")
+        .map_err(FfiError::from)?;
+
+    let stream_id = STREAM_COUNTER.fetch_add(1, Ordering::Relaxed);
+    streams().insert(
+        stream_id,
+        Arc::new(Mutex::new(RecallStream {
+            handle,
+            chunk_size: chunk_size.clamp(1, MAX_CHUNK_SIZE),
+            finished: remaining == Some(0),
+            filter,
+            remaining,
+        })),
+    );
+
+    tracing::debug!(stream = stream_id, handle = handle, "Began recall stream");
+    Ok(stream_id)
+}
+
+/// The stream's next memories, in recall order; empty once it's exhausted
+pub fn next_chunk(stream_id: usize) -> Result<Vec<MemoryItem>, FfiError> {
+    let stream = streams()
+        .get(&stream_id)
+        .cloned()
+        .ok_or_else(|| unknown_stream(stream_id))?;
+    // Chunks of one stream are read in turn; other streams aren't blocked
+    let mut stream = stream
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if stream.finished {
+        return Ok(Vec::new());
+    }
+
+    let instance = get_instance(stream.handle)?;
+    let page_size = stream.remaining.map_or(stream.chunk_size, |remaining| {
+        remaining.min(stream.chunk_size)
+    });
+    let mut filter = stream.filter.clone();
+    filter.limit = Some(page_size);
+    let memories = instance.memory_manager.recall_memories(filter)?.data;
+
+    // Later pages continue from the last memory sent, not from an offset
+    stream.filter.offset = None;
+    if let Some(last) = memories.last() {
+        stream.filter.before_id = Some(last.id.clone());
+    }
+    if let Some(remaining) = &mut stream.remaining {
+        *remaining -= memories.len();
+    }
+    stream.finished = memories.len() < page_size || stream.remaining == Some(0);
+
+    Ok(memories)
+}
+
+/// Response format of the instance a stream reads from
+pub fn response_format(stream_id: usize) -> Result<WireFormat, FfiError> {
+    let handle = match streams().get(&stream_id) {
+        Some(stream) => {
+            stream
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .handle
+        }
+        None => return Err(unknown_stream(stream_id)),
+    };
+    Ok(get_instance(handle)?.config.response_format)
+}
+
+fn unknown_stream(stream_id: usize) -> FfiError {
+    FfiError::new(
+        FfiErrorCode::InvalidHandle,
+        format!("no recall stream with id {}", stream_id),
+    )
+}
+
+/// Drop a stream; false if it didn't exist
+pub fn end(stream_id: usize) -> bool {
+    let removed = streams().remove(&stream_id).is_some();
+    if removed {
+        tracing::debug!(stream = stream_id, "Ended recall stream");
+    }
+    removed
+}
+
+/// Drop every stream reading from `handle`, when the instance goes away
+pub fn end_for_instance(handle: usize) {
+    streams().retain(|_, stream| {
+        let stream = stream
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        stream.handle != handle
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ffi::{create_instance, destroy_instance, FfiConfig};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_stream_in_chunks() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = FfiConfig::default();
+        config.database.path = temp_dir
+            .path()
+            .join("stream.db")
+            .to_string_lossy()
+            .to_string();
+        config.memex.database_path = config.database.path.clone();
+        let handle = create_instance(config).unwrap();
+
+        let instance = get_instance(handle).unwrap();
+        for i in 0..7 {
+            instance
+                .memory_manager
+                .save_memory(MemoryItem {
+                    user_id: "alice".to_string(),
+                    session_id: "s1".to_string(),
+                    content: format!("Memory {}", i),
+                    ..Default::default()
+                })
+                .unwrap();
+        }
+        let filter = QueryFilter {
+            user_id: Some("alice".to_string()),
+            limit: None,
+            offset: None,
+            ..Default::default()
+        };
+
+        // All seven in chunks of three, in recall order
+        let stream = begin(handle, filter.clone(), 3).unwrap();
+        let mut sizes = Vec::new();
+        let mut streamed = Vec::new();
+        loop {
+            let chunk = next_chunk(stream).unwrap();
+            if chunk.is_empty() {
+                break;
+            }
+            sizes.push(chunk.len());
+            streamed.extend(chunk.into_iter().map(|m| m.id));
+        }
+        assert_eq!(sizes, vec![3, 3, 1]);
+        let recalled: Vec<_> = instance
+            .memory_manager
+            .recall_memories(QueryFilter {
+                limit: Some(10),
+                ..filter.clone()
+            })
+            .unwrap()
+            .data
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(streamed, recalled);
+        assert!(end(stream));
+        assert!(!end(stream));
+        assert_eq!(
+            next_chunk(stream).unwrap_err().code,
+            FfiErrorCode::InvalidHandle
+        );
+
+        // The limit caps the stream and the offset skips its start
+        let stream = begin(
+            handle,
+            QueryFilter {
+                limit: Some(4),
+                offset: Some(2),
+                ..filter.clone()
+            },
+            3,
+        )
+        .unwrap();
+        let first = next_chunk(stream).unwrap();
+        let second = next_chunk(stream).unwrap();
+        assert_eq!(first.len(), 3);
+        assert_eq!(second.len(), 1);
+        assert_eq!(first[0].id, recalled[2]);
+        assert!(next_chunk(stream).unwrap().is_empty());
+
+        assert!(begin(
+            handle,
+            QueryFilter {
+                fuzzy: true,
+                ..filter.clone()
+            },
+            3
+        )
+        .is_err());
+
+        // Streams go away with their instance
+        assert!(destroy_instance(handle));
+        assert!(!end(stream));
+    }
+}
@@ -6,7 +6,10 @@
 pub mod cli;
 pub mod core;
 pub mod database;
+pub mod display;
 pub mod ffi;
+pub mod logging;
+pub mod wire;
 
 #[cfg(feature = "async")]
 pub mod async_db {
@@ -26,20 +29,20 @@ pub use database::async_db::AsyncDatabase;
 // Re-export vector types when feature is enabled
 #[cfg(feature = "vector-search")]
 pub use database::vector::{
-    HybridSearchResult, VectorConfig, VectorSearchEngine, VectorSearchResult,
+    DistanceMetric, HybridSearchResult, SessionSearchResult, VectorConfig, VectorFilter,
+    VectorSearchEngine, VectorSearchResult,
 };
 
-// FFI implementations using actual database
+// FFI entry points; instance registry and helpers live in `ffi`
 use std::collections::HashMap;
-use std::ffi::{CStr, CString};
+use std::ffi::CString;
 use std::os::raw::c_char;
 use std::ptr;
-use std::sync::Mutex;
 
-// Global instance storage for FFI
-static INSTANCE_COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(1);
-static INSTANCES: once_cell::sync::Lazy<Mutex<HashMap<usize, SimpleDatabase>>> =
-    once_cell::sync::Lazy::new(|| Mutex::new(HashMap::new()));
+#[cfg(feature = "vector-search")]
+use crate::core::counters::CounterEvent;
+use crate::core::memory::MemoryUpdate;
+use ffi::error::{FfiError, FfiErrorCode};
 
 #[no_mangle]
 pub extern "C" fn memex_init() -> usize {
@@ -47,87 +50,28 @@ pub extern "C" fn memex_init() -> usize {
 }
 
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn memex_init_with_config(config_json: *const c_char) -> usize {
-    let result = std::panic::catch_unwind(|| -> Option<usize> {
+    ffi::call("memex_init_with_config", || {
         // Parse config if provided, otherwise use default
-        let config = if config_json.is_null() {
-            DatabaseConfig::default()
-        } else {
-            let config_str = unsafe {
-                match CStr::from_ptr(config_json).to_str() {
-                    Ok(s) => s,
-                    Err(e) => {
-                        eprintln!("Error converting config string: {}", e);
-                        return None;
-                    }
-                }
-            };
-
-            let parsed_config: MemexConfig = match serde_json::from_str(config_str) {
-                Ok(config) => config,
-                Err(e) => {
-                    eprintln!("Error parsing config JSON: {}", e);
-                    return None;
-                }
-            };
-
-            DatabaseConfig {
-                path: parsed_config.database_path,
-                ..Default::default()
-            }
-        };
-
-        // Create database instance using SimpleDatabase
-        let database = match SimpleDatabase::new(config) {
-            Ok(db) => db,
-            Err(e) => {
-                eprintln!("Error creating database: {}", e);
-                return None;
-            }
+        let config = match unsafe { ffi::optional_str_arg(config_json, "config_json")? } {
+            Some(json) => ffi::config::parse_config(json)?,
+            None => ffi::config::FfiConfig::default(),
         };
 
-        // Generate unique instance ID
-        let instance_id = INSTANCE_COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
-
-        // Store instance
-        match INSTANCES.lock() {
-            Ok(mut instances) => {
-                instances.insert(instance_id, database);
-                Some(instance_id)
-            }
-            Err(e) => {
-                eprintln!("Error storing instance: {}", e);
-                None
-            }
-        }
-    });
-
-    match result {
-        Ok(Some(id)) => id,
-        Ok(None) => 0,
-        Err(e) => {
-            eprintln!("Panic in memex_init_with_config: {:?}", e);
-            0
-        }
-    }
+        ffi::create_instance(config)
+    })
+    .unwrap_or(0)
 }
 
 #[no_mangle]
 pub extern "C" fn memex_is_valid(handle: usize) -> bool {
-    if handle == 0 {
-        return false;
-    }
-    let instances = INSTANCES.lock().unwrap();
-    instances.contains_key(&handle)
+    ffi::is_valid(handle)
 }
 
 #[no_mangle]
 pub extern "C" fn memex_destroy(handle: usize) {
-    if handle == 0 {
-        return;
-    }
-    let mut instances = INSTANCES.lock().unwrap();
-    instances.remove(&handle);
+    ffi::destroy_instance(handle);
 }
 
 #[no_mangle]
@@ -141,282 +85,1359 @@ pub extern "C" fn memex_save(
     ttl_hours: i32,
     metadata_json: *const c_char,
 ) -> *mut c_char {
-    let result = std::panic::catch_unwind(|| {
-        if handle == 0 {
-            return None;
-        }
+    ffi::call("memex_save", || {
+        let instance = ffi::get_instance(handle)?;
+        let mut memory = unsafe {
+            save_memory_arg(
+                user_id,
+                session_id,
+                content,
+                importance,
+                ttl_hours,
+                metadata_json,
+            )?
+        };
+        instance.authorize_namespace(memory.namespace())?;
+        instance.attribute(&mut memory);
 
-        // Get database instance
-        let instances = INSTANCES.lock().unwrap();
-        let database = instances.get(&handle)?;
+        let memory_id = instance.memory_manager.save_memory(memory)?;
+        ffi::string_result(memory_id)
+    })
+    .unwrap_or(ptr::null_mut())
+}
 
-        // Parse parameters
-        let user_id_str = if user_id.is_null() {
-            return None;
-        } else {
-            unsafe { CStr::from_ptr(user_id).to_str().ok()? }
+/// Like `memex_save`, made safe to retry by `idempotency_key`: a save the
+/// same user already made with the key (within
+/// `DatabaseConfig::idempotency_key_ttl_hours`) returns that memory's id
+/// without saving again. A null key saves as `memex_save` does. Batch items
+/// take the key as their `idempotency_key` field.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref, clippy::too_many_arguments)]
+pub extern "C" fn memex_save_idempotent(
+    handle: usize,
+    user_id: *const c_char,
+    session_id: *const c_char,
+    content: *const c_char,
+    importance: f32,
+    ttl_hours: i32,
+    metadata_json: *const c_char,
+    idempotency_key: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_save_idempotent", || {
+        let instance = ffi::get_instance(handle)?;
+        let mut memory = unsafe {
+            save_memory_arg(
+                user_id,
+                session_id,
+                content,
+                importance,
+                ttl_hours,
+                metadata_json,
+            )?
         };
+        memory.idempotency_key =
+            unsafe { ffi::optional_str_arg(idempotency_key, "idempotency_key")? }
+                .map(str::to_string);
+        instance.authorize_namespace(memory.namespace())?;
+        instance.attribute(&mut memory);
 
-        let session_id_str = if session_id.is_null() {
-            return None;
-        } else {
-            unsafe { CStr::from_ptr(session_id).to_str().ok()? }
-        };
+        let memory_id = instance.memory_manager.save_memory(memory)?;
+        ffi::string_result(memory_id)
+    })
+    .unwrap_or(ptr::null_mut())
+}
 
-        let content_str = if content.is_null() {
-            return None;
-        } else {
-            unsafe { CStr::from_ptr(content).to_str().ok()? }
+/// Like `memex_save`, but returns a JSON `SaveReceipt` with the id, write
+/// timestamps, whether the save replaced an existing memory or opened a new
+/// session, and the content bytes written
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_save_with_receipt(
+    handle: usize,
+    user_id: *const c_char,
+    session_id: *const c_char,
+    content: *const c_char,
+    importance: f32,
+    ttl_hours: i32,
+    metadata_json: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_save_with_receipt", || {
+        let instance = ffi::get_instance(handle)?;
+        let mut memory = unsafe {
+            save_memory_arg(
+                user_id,
+                session_id,
+                content,
+                importance,
+                ttl_hours,
+                metadata_json,
+            )?
         };
+        instance.authorize_namespace(memory.namespace())?;
+        instance.attribute(&mut memory);
 
-        let metadata = if metadata_json.is_null() {
-            std::collections::HashMap::new()
-        } else {
-            let metadata_str = unsafe { CStr::from_ptr(metadata_json).to_str().ok()? };
-            serde_json::from_str(metadata_str).unwrap_or_default()
+        let receipt = instance.memory_manager.save_memory_with_receipt(memory)?;
+        ffi::json_result(&receipt)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_save_batch(
+    handle: usize,
+    memories_json: *const c_char,
+    fail_on_error: bool,
+) -> *mut c_char {
+    ffi::call("memex_save_batch", || {
+        let items = unsafe { batch_items_arg(handle, memories_json)? };
+        save_batch(handle, items, fail_on_error, QuotaPolicy::default())
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// [`memex_save_batch`], handling items over their user's storage quota as
+/// `quota_policy` says: `reject` (the default, for null), `stop_at_quota`,
+/// `evict_lowest_importance` or `overflow_to_archive`. Each result's
+/// `quota_action` reports what was done with an item over quota
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_save_batch_with_policy(
+    handle: usize,
+    memories_json: *const c_char,
+    fail_on_error: bool,
+    quota_policy: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_save_batch_with_policy", || {
+        let items = unsafe { batch_items_arg(handle, memories_json)? };
+        let quota_policy = match unsafe { ffi::optional_str_arg(quota_policy, "quota_policy")? } {
+            Some(policy) => policy
+                .parse()
+                .map_err(|e: String| FfiError::new(FfiErrorCode::InvalidArgument, e))?,
+            None => QuotaPolicy::default(),
         };
+        save_batch(handle, items, fail_on_error, quota_policy)
+    })
+    .unwrap_or(ptr::null_mut())
+}
 
-        // Create memory item
-        let memory = MemoryItem {
-            id: String::new(), // Will be generated by database
-            user_id: user_id_str.to_string(),
-            session_id: session_id_str.to_string(),
-            content: content_str.to_string(),
-            content_vector: None,
-            #[cfg(feature = "vector-search")]
-            embedding: None,
-            #[cfg(feature = "vector-search")]
-            embedding_model: None,
-            metadata,
-            created_at: chrono::Utc::now(),
-            updated_at: chrono::Utc::now(),
-            expires_at: if ttl_hours > 0 {
-                Some(chrono::Utc::now() + chrono::Duration::hours(ttl_hours as i64))
-            } else {
-                None
-            },
-            importance: importance.clamp(0.0, 1.0),
-            ttl_hours: if ttl_hours > 0 {
-                Some(ttl_hours as u32)
-            } else {
-                None
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_memory(handle: usize, memory_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_get_memory", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+
+        match instance.memory_manager.get_memory(memory_id)? {
+            Some(memory) => ffi::json_result(&memory),
+            None => Err(not_found("memory", memory_id)),
+        }
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Several memories by ID; `ids_json` is a JSON array of IDs. Returns the
+/// memories found, in the order requested
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_memories(handle: usize, ids_json: *const c_char) -> *mut c_char {
+    ffi::call("memex_get_memories", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(ids_json, "ids_json")? };
+        let ids: Vec<String> = ffi::json_arg(json, "ids_json")?;
+
+        let memories = instance.memory_manager.get_memories(&ids)?;
+        ffi::json_result(&memories)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_recall(handle: usize, filter_json: *const c_char) -> *mut c_char {
+    ffi::call("memex_recall", || {
+        let instance = ffi::get_instance(handle)?;
+        let filter = unsafe { recall_filter_arg(&instance, filter_json)? };
+
+        let response = instance.memory_manager.recall_memories(filter)?;
+        ffi::json_result(&response)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// `memex_recall` encoded in the instance's `response_format`; the buffer's
+/// length goes to `out_len`. Free it with `memex_free_buffer`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_recall_encoded(
+    handle: usize,
+    filter_json: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    ffi::call("memex_recall_encoded", || {
+        let instance = ffi::get_instance(handle)?;
+        let filter = unsafe { recall_filter_arg(&instance, filter_json)? };
+
+        let response = instance.memory_manager.recall_memories(filter)?;
+        ffi::encoded_result(instance.config.response_format, &response, unsafe {
+            out_len.as_mut()
+        })
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Start streaming the memories `filter_json` matches in chunks of
+/// `chunk_size` (at most 1000; zero or less picks 500), returning a stream id
+/// or 0 on failure
+///
+/// The filter's `limit` caps the whole stream. Fetch chunks with
+/// `memex_recall_next_chunk` and release the stream with `memex_recall_end`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_recall_begin(
+    handle: usize,
+    filter_json: *const c_char,
+    chunk_size: i32,
+) -> usize {
+    ffi::call("memex_recall_begin", || {
+        let instance = ffi::get_instance(handle)?;
+        let filter = match unsafe { ffi::optional_str_arg(filter_json, "filter_json")? } {
+            Some(json) => ffi::filter_arg(json, "filter_json", instance.config.lenient_filters)?,
+            None => QueryFilter {
+                limit: None,
+                ..Default::default()
             },
-            is_compressed: false,
-            compressed_from: Vec::new(),
         };
+        let chunk_size = ffi::optional_count(chunk_size)
+            .filter(|&size| size > 0)
+            .unwrap_or(ffi::recall_stream::DEFAULT_CHUNK_SIZE);
 
-        // Save to database
-        let memory_id = database.save_memory(&memory).ok()?;
-        Some(memory_id)
-    });
+        ffi::recall_stream::begin(handle, filter, chunk_size)
+    })
+    .unwrap_or(0)
+}
 
-    match result.unwrap_or(None) {
-        Some(id) => match CString::new(id) {
-            Ok(cstring) => cstring.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        None => ptr::null_mut(),
-    }
+/// The stream's next chunk as a JSON array of memories; `[]` once the stream
+/// is exhausted, null on failure
+#[no_mangle]
+pub extern "C" fn memex_recall_next_chunk(stream_id: usize) -> *mut c_char {
+    ffi::call("memex_recall_next_chunk", || {
+        let memories = ffi::recall_stream::next_chunk(stream_id)?;
+        ffi::json_result(&memories)
+    })
+    .unwrap_or(ptr::null_mut())
 }
 
+/// `memex_recall_next_chunk` encoded in the instance's `response_format`;
+/// the buffer's length goes to `out_len`. Free it with `memex_free_buffer`.
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub extern "C" fn memex_get_memory(handle: usize, memory_id: *const c_char) -> *mut c_char {
-    let result = std::panic::catch_unwind(|| {
-        if handle == 0 || memory_id.is_null() {
-            return None;
-        }
+pub extern "C" fn memex_recall_next_chunk_encoded(
+    stream_id: usize,
+    out_len: *mut usize,
+) -> *mut u8 {
+    ffi::call("memex_recall_next_chunk_encoded", || {
+        let format = ffi::recall_stream::response_format(stream_id)?;
+        let memories = ffi::recall_stream::next_chunk(stream_id)?;
+        ffi::encoded_result(format, &memories, unsafe { out_len.as_mut() })
+    })
+    .unwrap_or(ptr::null_mut())
+}
 
-        let instances = INSTANCES.lock().unwrap();
-        let database = instances.get(&handle)?;
+/// Release a recall stream; false if it didn't exist
+#[no_mangle]
+pub extern "C" fn memex_recall_end(stream_id: usize) -> bool {
+    ffi::call("memex_recall_end", || {
+        Ok(ffi::recall_stream::end(stream_id))
+    })
+    .unwrap_or(false)
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_search(
+    handle: usize,
+    user_id: *const c_char,
+    query: *const c_char,
+    limit: i32,
+    offset: i32,
+) -> *mut c_char {
+    ffi::call("memex_search", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let query = unsafe { ffi::str_arg(query, "query")? };
 
-        let memory_id_str = unsafe { CStr::from_ptr(memory_id).to_str().ok()? };
-        let memory = database.get_memory(memory_id_str).ok()??;
+        let response = instance.memory_manager.search_memories(
+            user_id,
+            query,
+            ffi::optional_count(limit).filter(|&limit| limit > 0),
+            ffi::optional_count(offset),
+        )?;
+        ffi::json_result(&response)
+    })
+    .unwrap_or(ptr::null_mut())
+}
 
-        let json = serde_json::to_string(&memory).ok()?;
-        Some(json)
-    });
+/// Full-text search bucketed by session: the sessions with matches, their
+/// match counts and up to `per_session_limit` hits each
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_search_grouped(
+    handle: usize,
+    user_id: *const c_char,
+    query: *const c_char,
+    per_session_limit: i32,
+) -> *mut c_char {
+    ffi::call("memex_search_grouped", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let query = unsafe { ffi::str_arg(query, "query")? };
 
-    match result.unwrap_or(None) {
-        Some(json) => match CString::new(json) {
-            Ok(cstring) => cstring.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        None => ptr::null_mut(),
-    }
+        let groups = instance.memory_manager.search_grouped_by_session(
+            user_id,
+            query,
+            ffi::optional_count(per_session_limit).unwrap_or(0),
+        )?;
+        ffi::json_result(&groups)
+    })
+    .unwrap_or(ptr::null_mut())
 }
 
+/// Nearest memories to an embedding; `request_json` is
+/// `{"embedding": [...], "model": "...", "limit": 10, "filter": {...}}`
+#[cfg(feature = "vector-search")]
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub extern "C" fn memex_recall(handle: usize, filter_json: *const c_char) -> *mut c_char {
-    let result = std::panic::catch_unwind(|| {
-        if handle == 0 {
-            return None;
+pub extern "C" fn memex_vector_search(handle: usize, request_json: *const c_char) -> *mut c_char {
+    ffi::call("memex_vector_search", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(request_json, "request_json")? };
+        let request: ffi::VectorSearchRequest = ffi::json_arg(json, "request_json")?;
+        ffi::validate_filter(&request.filter)?;
+
+        let results = instance.vector_engine()?.search_similar(
+            &request.embedding,
+            &request.model,
+            request.limit,
+            &request.filter,
+        );
+        instance.counters.record(CounterEvent::Search, &results);
+        ffi::json_result(&results?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Text and vector search combined; `request_json` additionally takes `text`
+/// and optional `text_weight`/`vector_weight` (0.5 each by default)
+#[cfg(feature = "vector-search")]
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_hybrid_search(handle: usize, request_json: *const c_char) -> *mut c_char {
+    ffi::call("memex_hybrid_search", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(request_json, "request_json")? };
+        let request: ffi::HybridSearchRequest = ffi::json_arg(json, "request_json")?;
+        ffi::validate_filter(&request.filter)?;
+
+        let results = instance.vector_engine()?.hybrid_search(
+            &request.text,
+            &request.embedding,
+            &request.model,
+            request.text_weight,
+            request.vector_weight,
+            request.limit,
+            &request.filter,
+        );
+        instance.counters.record(CounterEvent::Search, &results);
+        ffi::json_result(&results?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Apply a JSON `MemoryUpdate`; if it sets `expected_version` and the memory
+/// has moved on, the call fails with `FfiErrorCode::Conflict`
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_update_memory(
+    handle: usize,
+    memory_id: *const c_char,
+    updates_json: *const c_char,
+) -> bool {
+    ffi::call("memex_update_memory", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+        let json = unsafe { ffi::str_arg(updates_json, "updates_json")? };
+        let updates: MemoryUpdate = ffi::json_arg(json, "updates_json")?;
+        instance.authorize_memory(memory_id)?;
+        if let Some(namespace) = updates.namespace() {
+            instance.authorize_namespace(namespace)?;
         }
 
-        let instances = INSTANCES.lock().unwrap();
-        let database = instances.get(&handle)?;
+        if instance.memory_manager.update_memory(memory_id, updates)? {
+            Ok(true)
+        } else {
+            Err(not_found("memory", memory_id))
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Push a memory's expiry back by `hours`; memories without a TTL are left
+/// as they are
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_extend_ttl(handle: usize, memory_id: *const c_char, hours: u32) -> bool {
+    ffi::call("memex_extend_ttl", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+        instance.authorize_memory(memory_id)?;
 
-        let filter = if filter_json.is_null() {
-            QueryFilter::default()
+        if instance.memory_manager.extend_ttl(memory_id, hours)? {
+            Ok(true)
         } else {
-            let filter_str = unsafe { CStr::from_ptr(filter_json).to_str().ok()? };
-            serde_json::from_str(filter_str).unwrap_or_default()
-        };
+            Err(not_found("memory", memory_id))
+        }
+    })
+    .unwrap_or(false)
+}
 
-        let response = database.recall_memories(&filter).ok()?;
-        let json = serde_json::to_string(&response).ok()?;
-        Some(json)
-    });
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_delete_memory(handle: usize, memory_id: *const c_char) -> bool {
+    ffi::call("memex_delete_memory", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+        instance.authorize_memory(memory_id)?;
 
-    match result.unwrap_or(None) {
-        Some(json) => match CString::new(json) {
-            Ok(cstring) => cstring.into_raw(),
-            Err(_) => ptr::null_mut(),
-        },
-        None => {
-            // Return empty response structure instead of null
-            let empty_response = serde_json::json!({
-                "data": [],
-                "total_count": 0,
-                "page": 0,
-                "per_page": 50,
-                "total_pages": 0,
-                "has_next": false,
-                "has_prev": false
-            });
-            match CString::new(empty_response.to_string()) {
-                Ok(cstring) => cstring.into_raw(),
-                Err(_) => ptr::null_mut(),
-            }
+        if instance.memory_manager.delete_memory(memory_id)? {
+            Ok(true)
+        } else {
+            Err(not_found("memory", memory_id))
         }
-    }
+    })
+    .unwrap_or(false)
 }
 
+/// Register the JSON Schema that JSON memories of `namespace` must match
 #[no_mangle]
-pub extern "C" fn memex_get_last_error() -> i32 {
-    0 // No error for now - could implement error tracking later
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_json_schema(
+    handle: usize,
+    namespace: *const c_char,
+    schema_json: *const c_char,
+) -> bool {
+    ffi::call("memex_set_json_schema", || {
+        let instance = ffi::get_instance(handle)?;
+        let namespace = unsafe { ffi::str_arg(namespace, "namespace")? };
+        instance.authorize_namespace(namespace)?;
+        let json = unsafe { ffi::str_arg(schema_json, "schema_json")? };
+        let schema: serde_json::Value = ffi::json_arg(json, "schema_json")?;
+
+        instance
+            .memory_manager
+            .register_json_schema(namespace, schema)?;
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
 
+/// Pair `term` with `synonym` for keyword searches in `namespace` (the
+/// default namespace if null); succeeds if the pair already exists
 #[no_mangle]
-pub extern "C" fn memex_error_message(error_code: i32) -> *mut c_char {
-    let message = match error_code {
-        0 => "Success",
-        _ => "Unknown error",
-    };
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_add_synonym(
+    handle: usize,
+    namespace: *const c_char,
+    term: *const c_char,
+    synonym: *const c_char,
+) -> bool {
+    ffi::call("memex_add_synonym", || {
+        let instance = ffi::get_instance(handle)?;
+        let namespace =
+            unsafe { ffi::optional_str_arg(namespace, "namespace")? }.unwrap_or(DEFAULT_NAMESPACE);
+        instance.authorize_namespace(namespace)?;
+        let term = unsafe { ffi::str_arg(term, "term")? };
+        let synonym = unsafe { ffi::str_arg(synonym, "synonym")? };
 
-    match CString::new(message) {
-        Ok(cstring) => cstring.into_raw(),
-        Err(_) => ptr::null_mut(),
-    }
+        instance
+            .memory_manager
+            .add_synonym(namespace, term, synonym)?;
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
 
+/// Remove a synonym pair from `namespace` (the default namespace if null)
 #[no_mangle]
 #[allow(clippy::not_unsafe_ptr_arg_deref)]
-pub extern "C" fn memex_free_string(ptr: *mut c_char) {
-    if !ptr.is_null() {
-        unsafe {
-            let _ = CString::from_raw(ptr);
+pub extern "C" fn memex_remove_synonym(
+    handle: usize,
+    namespace: *const c_char,
+    term: *const c_char,
+    synonym: *const c_char,
+) -> bool {
+    ffi::call("memex_remove_synonym", || {
+        let instance = ffi::get_instance(handle)?;
+        let namespace =
+            unsafe { ffi::optional_str_arg(namespace, "namespace")? }.unwrap_or(DEFAULT_NAMESPACE);
+        instance.authorize_namespace(namespace)?;
+        let term = unsafe { ffi::str_arg(term, "term")? };
+        let synonym = unsafe { ffi::str_arg(synonym, "synonym")? };
+
+        if instance
+            .memory_manager
+            .remove_synonym(namespace, term, synonym)?
+        {
+            Ok(true)
+        } else {
+            Err(not_found("synonym", &format!("{} <-> {}", term, synonym)))
         }
-    }
+    })
+    .unwrap_or(false)
 }
 
+/// Synonym pairs of `namespace`, or of every namespace if null
 #[no_mangle]
-pub extern "C" fn memex_version() -> *mut c_char {
-    match CString::new(env!("CARGO_PKG_VERSION")) {
-        Ok(cstring) => cstring.into_raw(),
-        Err(_) => ptr::null_mut(),
-    }
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_list_synonyms(handle: usize, namespace: *const c_char) -> *mut c_char {
+    ffi::call("memex_list_synonyms", || {
+        let instance = ffi::get_instance(handle)?;
+        let namespace = unsafe { ffi::optional_str_arg(namespace, "namespace")? };
+        ffi::json_result(&instance.memory_manager.list_synonyms(namespace)?)
+    })
+    .unwrap_or(ptr::null_mut())
 }
 
-// Stub implementations for functions not yet implemented
+/// Add or replace a memory template; `template_json` is a `MemoryTemplate`
 #[no_mangle]
-pub extern "C" fn memex_save_batch(_h: usize, _m: *const c_char, _f: bool) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_template(handle: usize, template_json: *const c_char) -> bool {
+    ffi::call("memex_set_template", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(template_json, "template_json")? };
+        let template: MemoryTemplate = ffi::json_arg(json, "template_json")?;
+
+        instance.memory_manager.set_template(template)?;
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
+
 #[no_mangle]
-pub extern "C" fn memex_search(
-    _h: usize,
-    _u: *const c_char,
-    _q: *const c_char,
-    _l: i32,
-    _o: i32,
+pub extern "C" fn memex_list_templates(handle: usize) -> *mut c_char {
+    ffi::call("memex_list_templates", || {
+        let instance = ffi::get_instance(handle)?;
+        ffi::json_result(&instance.memory_manager.list_templates()?)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Render a template with `vars_json` (an object of strings) and save it;
+/// returns the new memory's ID
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_save_from_template(
+    handle: usize,
+    template_id: *const c_char,
+    user_id: *const c_char,
+    session_id: *const c_char,
+    vars_json: *const c_char,
 ) -> *mut c_char {
-    ptr::null_mut()
+    ffi::call("memex_save_from_template", || {
+        let instance = ffi::get_instance(handle)?;
+        let template_id = unsafe { ffi::str_arg(template_id, "template_id")? };
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let session_id = unsafe { ffi::str_arg(session_id, "session_id")? };
+        let vars: HashMap<String, String> =
+            match unsafe { ffi::optional_str_arg(vars_json, "vars_json")? } {
+                Some(json) => ffi::json_arg(json, "vars_json")?,
+                None => HashMap::new(),
+            };
+
+        if instance.memory_manager.get_template(template_id)?.is_none() {
+            return Err(not_found("template", template_id));
+        }
+        let mut memory =
+            instance
+                .memory_manager
+                .render_template(template_id, user_id, session_id, &vars)?;
+        instance.attribute(&mut memory);
+        let memory_id = instance.memory_manager.save_memory(memory)?;
+        ffi::string_result(memory_id)
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
 #[no_mangle]
-pub extern "C" fn memex_update_memory(_h: usize, _m: *const c_char, _u: *const c_char) -> bool {
-    false
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_create_session(
+    handle: usize,
+    user_id: *const c_char,
+    name: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_create_session", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let name = unsafe { ffi::optional_str_arg(name, "name")? };
+
+        let session_id = instance
+            .session_manager
+            .create_session(user_id, name.map(str::to_string))?;
+        ffi::string_result(session_id)
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
 #[no_mangle]
-pub extern "C" fn memex_delete_memory(_h: usize, _m: *const c_char) -> bool {
-    false
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_user_sessions(
+    handle: usize,
+    user_id: *const c_char,
+    limit: i32,
+    offset: i32,
+) -> *mut c_char {
+    ffi::call("memex_get_user_sessions", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let response = instance.session_manager.get_user_sessions(
+            user_id,
+            ffi::optional_count(limit).filter(|&limit| limit > 0),
+            ffi::optional_count(offset),
+        )?;
+        ffi::json_result(&response)
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
+/// Mark a session `active`, `closed` or `archived`; archived sessions are
+/// left out of session listings and searches
 #[no_mangle]
-pub extern "C" fn memex_create_session(
-    _h: usize,
-    _u: *const c_char,
-    _n: *const c_char,
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_session_status(
+    handle: usize,
+    session_id: *const c_char,
+    status: *const c_char,
+) -> bool {
+    ffi::call("memex_set_session_status", || {
+        let instance = ffi::get_instance(handle)?;
+        let session_id = unsafe { ffi::str_arg(session_id, "session_id")? };
+        let status = unsafe { ffi::str_arg(status, "status")? }
+            .parse()
+            .map_err(|e: String| FfiError::new(FfiErrorCode::InvalidArgument, e))?;
+
+        if instance
+            .session_manager
+            .set_session_status(session_id, status)?
+        {
+            Ok(true)
+        } else {
+            Err(not_found("session", session_id))
+        }
+    })
+    .unwrap_or(false)
+}
+
+/// Move a memory to `draft`, `active` or `archived`, returning the state it
+/// left as a JSON string. Only active memories are recalled; drafts can be
+/// confirmed or archived, active memories archived and archived ones made
+/// active again, and other moves fail with `Conflict`
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_memory_state(
+    handle: usize,
+    memory_id: *const c_char,
+    state: *const c_char,
 ) -> *mut c_char {
-    ptr::null_mut()
+    ffi::call("memex_set_memory_state", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+        let state = unsafe { ffi::str_arg(state, "state")? }
+            .parse()
+            .map_err(|e: String| {
+                FfiError::new(FfiErrorCode::InvalidArgument, e).with_field("state")
+            })?;
+        instance.authorize_memory(memory_id)?;
+
+        match instance.memory_manager.set_memory_state(memory_id, state)? {
+            Some(previous) => ffi::json_result(&previous),
+            None => Err(not_found("memory", memory_id)),
+        }
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
+/// Memories of `user_id` that may contradict memory `memory_id`, as a JSON
+/// array of conflicts, confirmed ones first
 #[no_mangle]
-pub extern "C" fn memex_get_user_sessions(
-    _h: usize,
-    _u: *const c_char,
-    _l: i32,
-    _o: i32,
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_find_conflicts(
+    handle: usize,
+    user_id: *const c_char,
+    memory_id: *const c_char,
 ) -> *mut c_char {
-    ptr::null_mut()
+    ffi::call("memex_find_conflicts", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+
+        match instance.memory_manager.find_conflicts(user_id, memory_id)? {
+            Some(conflicts) => ffi::json_result(&conflicts),
+            None => Err(not_found("memory", memory_id)),
+        }
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
+/// Link two memories of the same user as `contradicts` (a confirmed
+/// conflict) or `compatible` (a dismissed one), replacing any other link
+/// between them; with `unlink`, remove that link instead
 #[no_mangle]
-pub extern "C" fn memex_summarize_session(_h: usize, _s: *const c_char) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_link_memories(
+    handle: usize,
+    memory_id: *const c_char,
+    other_id: *const c_char,
+    link_type: *const c_char,
+    unlink: bool,
+) -> bool {
+    ffi::call("memex_link_memories", || {
+        let instance = ffi::get_instance(handle)?;
+        let memory_id = unsafe { ffi::str_arg(memory_id, "memory_id")? };
+        let other_id = unsafe { ffi::str_arg(other_id, "other_id")? };
+        let link_type = unsafe { ffi::str_arg(link_type, "link_type")? }
+            .parse()
+            .map_err(|e: String| {
+                FfiError::new(FfiErrorCode::InvalidArgument, e).with_field("link_type")
+            })?;
+        if memory_id == other_id {
+            return Err(FfiError::new(
+                FfiErrorCode::InvalidArgument,
+                "A memory can't be linked to itself",
+            )
+            .with_field("other_id"));
+        }
+        instance.authorize_memory(memory_id)?;
+        instance.authorize_memory(other_id)?;
+
+        if unlink {
+            if instance
+                .memory_manager
+                .unlink_memories(memory_id, other_id, link_type)?
+            {
+                return Ok(true);
+            }
+            return Err(not_found(
+                "link",
+                &format!("{} <-> {}", memory_id, other_id),
+            ));
+        }
+        if !instance
+            .memory_manager
+            .link_memories(memory_id, other_id, link_type)?
+        {
+            for id in [memory_id, other_id] {
+                if instance.memory_manager.get_memory(id)?.is_none() {
+                    return Err(not_found("memory", id));
+                }
+            }
+        }
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
+
 #[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_summarize_session(handle: usize, session_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_summarize_session", || {
+        let instance = ffi::get_instance(handle)?;
+        let session_id = unsafe { ffi::str_arg(session_id, "session_id")? };
+
+        let summary = instance
+            .session_manager
+            .generate_session_summary(session_id)?;
+        ffi::json_result(&summary)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 pub extern "C" fn memex_search_sessions(
-    _h: usize,
-    _u: *const c_char,
-    _k: *const c_char,
+    handle: usize,
+    user_id: *const c_char,
+    keywords_json: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_search_sessions", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+        let json = unsafe { ffi::str_arg(keywords_json, "keywords_json")? };
+        let keywords: Vec<String> = ffi::json_arg(json, "keywords_json")?;
+
+        let sessions = instance
+            .session_manager
+            .search_sessions(user_id, keywords)?;
+        ffi::json_result(&sessions)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// A user's sessions ranked by their aggregated embedding; `request_json` is
+/// `{"user_id": "...", "embedding": [...], "model": "...", "limit": 10}`
+#[cfg(feature = "vector-search")]
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_search_sessions_semantic(
+    handle: usize,
+    request_json: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_search_sessions_semantic", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(request_json, "request_json")? };
+        let request: ffi::SessionSearchRequest = ffi::json_arg(json, "request_json")?;
+        // Fail with the structured error when vector search is off
+        instance.vector_engine()?;
+
+        let results = instance.session_manager.search_sessions_semantic(
+            &request.user_id,
+            &request.embedding,
+            &request.model,
+            request.limit,
+        )?;
+        ffi::json_result(&results)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_delete_session(
+    handle: usize,
+    session_id: *const c_char,
+    delete_memories: bool,
+) -> bool {
+    ffi::call("memex_delete_session", || {
+        let instance = ffi::get_instance(handle)?;
+        let session_id = unsafe { ffi::str_arg(session_id, "session_id")? };
+
+        if instance
+            .session_manager
+            .delete_session(session_id, delete_memories)?
+        {
+            Ok(true)
+        } else {
+            Err(not_found("session", session_id))
+        }
+    })
+    .unwrap_or(false)
+}
+
+#[no_mangle]
+pub extern "C" fn memex_decay(handle: usize) -> *mut c_char {
+    ffi::call("memex_decay", || {
+        let instance = ffi::get_instance(handle)?;
+        let stats = instance.decay_engine().run_decay()?;
+        ffi::json_result(&stats)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+pub extern "C" fn memex_decay_analyze(handle: usize) -> *mut c_char {
+    ffi::call("memex_decay_analyze", || {
+        let instance = ffi::get_instance(handle)?;
+        let recommendations = instance.decay_engine().get_decay_recommendations()?;
+        ffi::json_result(&recommendations)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_update_decay_policy(handle: usize, policy_json: *const c_char) -> bool {
+    ffi::call("memex_update_decay_policy", || {
+        let instance = ffi::get_instance(handle)?;
+        let json = unsafe { ffi::str_arg(policy_json, "policy_json")? };
+        let policy: DecayPolicy = ffi::json_arg(json, "policy_json")?;
+
+        instance.decay_engine().update_policy(policy)?;
+        Ok(true)
+    })
+    .unwrap_or(false)
+}
+
+/// Run maintenance job `job` (e.g. `"analyze"`) now, returning the recorded run
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_maintenance_run(handle: usize, job: *const c_char) -> *mut c_char {
+    ffi::call("memex_maintenance_run", || {
+        let instance = ffi::get_instance(handle)?;
+        let job = unsafe { ffi::str_arg(job, "job")? };
+        let job = maintenance_job_arg(job)?;
+
+        let run = instance.maintenance.run_job(job)?;
+        ffi::json_result(&run)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Run every maintenance job whose schedule is due, returning the runs
+#[no_mangle]
+pub extern "C" fn memex_maintenance_run_due(handle: usize) -> *mut c_char {
+    ffi::call("memex_maintenance_run_due", || {
+        let instance = ffi::get_instance(handle)?;
+        let runs = instance.maintenance.run_due(chrono::Utc::now())?;
+        ffi::json_result(&runs)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Most recent maintenance runs, newest first; a null `job` lists every job
+/// and a negative `limit` means 50
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_maintenance_history(
+    handle: usize,
+    job: *const c_char,
+    limit: i32,
+) -> *mut c_char {
+    ffi::call("memex_maintenance_history", || {
+        let instance = ffi::get_instance(handle)?;
+        let job = unsafe { ffi::optional_str_arg(job, "job")? }
+            .map(maintenance_job_arg)
+            .transpose()?;
+
+        let runs = instance
+            .database
+            .get_maintenance_runs(job, ffi::optional_count(limit).unwrap_or(50))?;
+        ffi::json_result(&runs)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+fn maintenance_job_arg(job: &str) -> Result<database::maintenance_runs::MaintenanceJob, FfiError> {
+    job.parse().map_err(|e: anyhow::Error| {
+        FfiError::new(FfiErrorCode::InvalidArgument, e.to_string()).with_field("job")
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn memex_get_stats(handle: usize) -> *mut c_char {
+    ffi::call("memex_get_stats", || {
+        let instance = ffi::get_instance(handle)?;
+        let mut stats = instance.database.get_stats()?;
+        if let Some(privacy) = instance.analytics_privacy() {
+            stats = privacy.stats(stats);
+        }
+        ffi::json_result(&stats)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Saves, recalls, searches and errors counted since the instance was
+/// created, with the time of the last of each
+#[no_mangle]
+pub extern "C" fn memex_get_counters(handle: usize) -> *mut c_char {
+    ffi::call("memex_get_counters", || {
+        let instance = ffi::get_instance(handle)?;
+        ffi::json_result(&instance.counters.snapshot())
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Structured `HealthStatus`: database connectivity, pool health, WAL size,
+/// the last decay run and error counts. An unreachable database is reported
+/// as "unhealthy" rather than as an error
+#[no_mangle]
+pub extern "C" fn memex_health(handle: usize) -> *mut c_char {
+    ffi::call("memex_health", || {
+        let instance = ffi::get_instance(handle)?;
+        let health = HealthStatus::check(
+            &instance.database,
+            &instance.counters,
+            instance.memory_manager.get_performance_metrics(),
+            instance.config.anomaly_policy(),
+        );
+        ffi::json_result(&health)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// The `limit` (at most 1000) most recent audit log entries, newest first;
+/// `filter_json` may narrow them by `actor`, `trace_id` or `target_id`
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_audit_log(
+    handle: usize,
+    filter_json: *const c_char,
+    limit: i32,
 ) -> *mut c_char {
-    ptr::null_mut()
+    ffi::call("memex_get_audit_log", || {
+        let instance = ffi::get_instance(handle)?;
+        let filter = match unsafe { ffi::optional_str_arg(filter_json, "filter_json")? } {
+            Some(json) => ffi::json_arg(json, "filter_json")?,
+            None => database::audit::AuditFilter::default(),
+        };
+        let limit = ffi::optional_count(limit).unwrap_or(100).min(1000);
+
+        let entries = instance.database.get_audit_log(&filter, limit)?;
+        ffi::json_result(&entries)
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
+/// The API key in the instance's `access.api_keys` whose secret is `token`,
+/// as JSON with its `name`, `role` and per-namespace `namespaces` roles;
+/// fails with `PermissionDenied` if no key matches
 #[no_mangle]
-pub extern "C" fn memex_delete_session(_h: usize, _s: *const c_char, _d: bool) -> bool {
-    false
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_authenticate(handle: usize, token: *const c_char) -> *mut c_char {
+    ffi::call("memex_authenticate", || {
+        let instance = ffi::get_instance(handle)?;
+        let token = unsafe { ffi::str_arg(token, "token")? };
+
+        let principal = instance.config.access.authenticate(token).ok_or_else(|| {
+            FfiError::new(
+                FfiErrorCode::PermissionDenied,
+                "token doesn't match any configured API key",
+            )
+        })?;
+        ffi::json_result(&principal)
+    })
+    .unwrap_or(ptr::null_mut())
 }
+
+/// Checkpoint the WAL; `mode` is passive, full, restart or truncate, and
+/// null means truncate
 #[no_mangle]
-pub extern "C" fn memex_decay(_h: usize) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_checkpoint(handle: usize, mode: *const c_char) -> *mut c_char {
+    ffi::call("memex_checkpoint", || {
+        let instance = ffi::get_instance(handle)?;
+        let mode = match unsafe { ffi::optional_str_arg(mode, "mode")? } {
+            Some(mode) => mode.parse().map_err(|e: anyhow::Error| {
+                FfiError::new(FfiErrorCode::InvalidArgument, e.to_string())
+            })?,
+            None => database::pool::CheckpointMode::default(),
+        };
+
+        let checkpoint = instance.database.checkpoint(mode)?;
+        ffi::json_result(&checkpoint)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Check the integrity hashes of `user_id`'s memories, or of every memory
+/// if null; returns an `IntegrityReport` listing unsealed and mismatched
+/// memories
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_verify_integrity(handle: usize, user_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_verify_integrity", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::optional_str_arg(user_id, "user_id")? };
+
+        let report = instance.database.verify_integrity(user_id)?;
+        ffi::json_result(&report)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_export_user_memories(handle: usize, user_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_export_user_memories", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let memories = instance.memory_manager.export_user_memories(user_id)?;
+        ffi::json_result(&memories)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// `memex_export_user_memories` encoded in the instance's
+/// `response_format`; the buffer's length goes to `out_len`. Free it with
+/// `memex_free_buffer`.
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_export_user_memories_encoded(
+    handle: usize,
+    user_id: *const c_char,
+    out_len: *mut usize,
+) -> *mut u8 {
+    ffi::call("memex_export_user_memories_encoded", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let memories = instance.memory_manager.export_user_memories(user_id)?;
+        ffi::encoded_result(instance.config.response_format, &memories, unsafe {
+            out_len.as_mut()
+        })
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_user_stats(handle: usize, user_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_get_user_stats", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let mut stats = instance.memory_manager.get_user_memory_stats(user_id)?;
+        if let Some(privacy) = instance.analytics_privacy() {
+            stats = privacy.user_memory_stats(stats);
+        }
+        ffi::json_result(&stats)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_user_analytics(handle: usize, user_id: *const c_char) -> *mut c_char {
+    ffi::call("memex_get_user_analytics", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let mut analytics = instance.memory_manager.get_user_analytics(user_id)?;
+        if let Some(privacy) = instance.analytics_privacy() {
+            analytics = privacy.user_analytics(analytics);
+        }
+        ffi::json_result(&analytics)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+#[no_mangle]
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_get_session_analytics(
+    handle: usize,
+    user_id: *const c_char,
+) -> *mut c_char {
+    ffi::call("memex_get_session_analytics", || {
+        let instance = ffi::get_instance(handle)?;
+        let user_id = unsafe { ffi::str_arg(user_id, "user_id")? };
+
+        let mut analytics = instance.session_manager.get_session_analytics(user_id)?;
+        if let Some(privacy) = instance.analytics_privacy() {
+            analytics = privacy.session_analytics(analytics);
+        }
+        ffi::json_result(&analytics)
+    })
+    .unwrap_or(ptr::null_mut())
+}
+
+/// Error code of the calling thread's last failed call (0 if none)
+#[no_mangle]
+pub extern "C" fn memex_get_last_error() -> i32 {
+    ffi::error::last_error_code() as i32
+}
+
+/// Describe `error_code`; for the calling thread's last error this includes
+/// the detailed message
+#[no_mangle]
+pub extern "C" fn memex_error_message(error_code: i32) -> *mut c_char {
+    let message = ffi::error::error_message(error_code);
+
+    match CString::new(message) {
+        Ok(cstring) => cstring.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
 }
+
+/// The calling thread's last error as JSON (`code`, `description`,
+/// `message`, `field`, `limit`, `actual`, `retry_after_ms`), or null if the
+/// last call succeeded
 #[no_mangle]
-pub extern "C" fn memex_decay_analyze(_h: usize) -> *mut c_char {
-    ptr::null_mut()
+pub extern "C" fn memex_last_error_json() -> *mut c_char {
+    match ffi::error::last_error() {
+        Some(error) => match CString::new(error.to_json().to_string()) {
+            Ok(cstring) => cstring.into_raw(),
+            Err(_) => ptr::null_mut(),
+        },
+        None => ptr::null_mut(),
+    }
 }
+
+/// Attribute later calls on the calling thread to a request context, given
+/// as JSON with optional `actor`, `trace_id` and `source`: writes they make
+/// are recorded in the audit log with it and their log lines carry its
+/// fields. An `analytics_privacy` object limits them to aggregated analytics,
+/// as for an API key in privacy mode. Null clears it
 #[no_mangle]
-pub extern "C" fn memex_update_decay_policy(_h: usize, _p: *const c_char) -> bool {
-    false
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_request_context(context_json: *const c_char) -> bool {
+    ffi::call("memex_set_request_context", || {
+        let (context, privacy) = match unsafe {
+            ffi::optional_str_arg(context_json, "context_json")?
+        } {
+            Some(json) => {
+                let context: core::context::RequestContext = ffi::json_arg(json, "context_json")?;
+                let privacy: ffi::access::ContextPrivacy = ffi::json_arg(json, "context_json")?;
+                let invalid = |e: validator::ValidationErrors| {
+                    FfiError::new(
+                        FfiErrorCode::InvalidArgument,
+                        format!("`context_json` is not valid: {}", e),
+                    )
+                };
+                validator::Validate::validate(&context).map_err(invalid)?;
+                if let Some(privacy) = &privacy.analytics_privacy {
+                    validator::Validate::validate(privacy).map_err(invalid)?;
+                }
+                (Some(context), privacy.analytics_privacy)
+            }
+            None => (None, None),
+        };
+
+        ffi::set_request_context(context, privacy);
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
+
+/// Change which log events are written, e.g. `"debug"` or
+/// `"warn,memex_core::database=trace"`; applies to every instance
 #[no_mangle]
-pub extern "C" fn memex_get_stats(_h: usize) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_set_log_level(filter: *const c_char) -> bool {
+    ffi::call("memex_set_log_level", || {
+        let filter = unsafe { ffi::str_arg(filter, "filter")? };
+
+        logging::set_filter(filter).map_err(|e| {
+            FfiError::new(
+                FfiErrorCode::InvalidArgument,
+                format!("`filter` is not valid: {:#}", e),
+            )
+        })?;
+        Ok(true)
+    })
+    .unwrap_or(false)
 }
+
 #[no_mangle]
-pub extern "C" fn memex_export_user_memories(_h: usize, _u: *const c_char) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_free_string(ptr: *mut c_char) {
+    if !ptr.is_null() {
+        unsafe {
+            let _ = CString::from_raw(ptr);
+        }
+    }
 }
+
+/// Free a buffer returned by a `memex_*_encoded` function; `len` is the
+/// length it was returned with
 #[no_mangle]
-pub extern "C" fn memex_get_user_stats(_h: usize, _u: *const c_char) -> *mut c_char {
-    ptr::null_mut()
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
+pub extern "C" fn memex_free_buffer(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        unsafe {
+            let _ = Box::from_raw(ptr::slice_from_raw_parts_mut(ptr, len));
+        }
+    }
 }
+
 #[no_mangle]
-pub extern "C" fn memex_get_session_analytics(_h: usize, _u: *const c_char) -> *mut c_char {
-    ptr::null_mut()
+pub extern "C" fn memex_version() -> *mut c_char {
+    match CString::new(env!("CARGO_PKG_VERSION")) {
+        Ok(cstring) => cstring.into_raw(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// The recall filter `filter_json` describes; null means no filter
+///
+/// # Safety
+/// `filter_json` must be null or point to a NUL-terminated string.
+unsafe fn recall_filter_arg(
+    instance: &ffi::MemexHandle,
+    filter_json: *const c_char,
+) -> Result<QueryFilter, FfiError> {
+    match ffi::optional_str_arg(filter_json, "filter_json")? {
+        Some(json) => ffi::filter_arg(json, "filter_json", instance.config.lenient_filters),
+        None => Ok(QueryFilter::default()),
+    }
+}
+
+/// Build the memory a save call describes; a `ttl_hours` of zero or less
+/// means no TTL
+///
+/// # Safety
+/// Pointers must be null or point to NUL-terminated strings.
+unsafe fn save_memory_arg(
+    user_id: *const c_char,
+    session_id: *const c_char,
+    content: *const c_char,
+    importance: f32,
+    ttl_hours: i32,
+    metadata_json: *const c_char,
+) -> Result<MemoryItem, FfiError> {
+    let user_id = ffi::str_arg(user_id, "user_id")?;
+    let session_id = ffi::str_arg(session_id, "session_id")?;
+    let content = ffi::str_arg(content, "content")?;
+    let metadata = match ffi::optional_str_arg(metadata_json, "metadata_json")? {
+        Some(json) => ffi::json_arg(json, "metadata_json")?,
+        None => HashMap::new(),
+    };
+
+    Ok(MemoryItem {
+        user_id: user_id.to_string(),
+        session_id: session_id.to_string(),
+        content: content.to_string(),
+        metadata,
+        importance: importance.clamp(0.0, 1.0),
+        ttl_hours: u32::try_from(ttl_hours).ok().filter(|&hours| hours > 0),
+        ..Default::default()
+    })
+}
+
+/// Memories of a batch save, checked against the caller's namespaces and
+/// attributed to it
+///
+/// # Safety
+/// `memories_json` must be null or point to a NUL-terminated string.
+unsafe fn batch_items_arg(
+    handle: usize,
+    memories_json: *const c_char,
+) -> Result<Vec<MemoryItem>, FfiError> {
+    let instance = ffi::get_instance(handle)?;
+    let json = ffi::str_arg(memories_json, "memories_json")?;
+    let mut items: Vec<MemoryItem> = ffi::json_arg(json, "memories_json")?;
+    for item in &mut items {
+        instance.authorize_namespace(item.namespace())?;
+        instance.attribute(item);
+    }
+    Ok(items)
+}
+
+fn save_batch(
+    handle: usize,
+    items: Vec<MemoryItem>,
+    fail_on_error: bool,
+    quota_policy: QuotaPolicy,
+) -> Result<*mut c_char, FfiError> {
+    let instance = ffi::get_instance(handle)?;
+    let response = instance.memory_manager.save_memories_batch(BatchRequest {
+        items,
+        fail_on_error,
+        quota_policy,
+    })?;
+    ffi::json_result(&response)
+}
+
+fn not_found(kind: &str, id: &str) -> FfiError {
+    FfiError::new(
+        FfiErrorCode::NotFound,
+        format!("{} not found: {}", kind, id),
+    )
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::ffi::CStr;
     use validator::Validate;
 
     #[test]
@@ -431,6 +1452,23 @@ mod tests {
         assert_eq!(memory.importance, 0.5);
     }
 
+    #[test]
+    fn test_set_log_level() {
+        let filter = CString::new("warn,memex_core::database=debug").unwrap();
+        assert!(memex_set_log_level(filter.as_ptr()));
+        assert_eq!(memex_get_last_error(), 0);
+
+        let filter = CString::new("memex_core=loud").unwrap();
+        assert!(!memex_set_log_level(filter.as_ptr()));
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidArgument as i32);
+
+        assert!(!memex_set_log_level(ptr::null()));
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidArgument as i32);
+
+        let filter = CString::new("warn").unwrap();
+        assert!(memex_set_log_level(filter.as_ptr()));
+    }
+
     #[test]
     fn test_ffi_basic() {
         let handle = memex_init();
@@ -438,4 +1476,243 @@ mod tests {
         assert!(memex_is_valid(handle));
         memex_destroy(handle);
     }
+
+    #[test]
+    fn test_init_with_invalid_config_sets_last_error() {
+        let config = CString::new(r#"{"database": {"max_connections": 0}}"#).unwrap();
+        assert_eq!(memex_init_with_config(config.as_ptr()), 0);
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidConfig as i32);
+
+        let message_ptr = memex_error_message(memex_get_last_error());
+        let message = unsafe { CStr::from_ptr(message_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        memex_free_string(message_ptr);
+        assert!(message.contains("max_connections"), "{}", message);
+    }
+
+    #[test]
+    fn test_init_with_nested_database_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("nested.db");
+        let config = CString::new(
+            serde_json::json!({
+                "database": {
+                    "path": path.to_string_lossy(),
+                    "enable_wal": false,
+                    "busy_timeout": 1234
+                }
+            })
+            .to_string(),
+        )
+        .unwrap();
+
+        let handle = memex_init_with_config(config.as_ptr());
+        assert_ne!(handle, 0);
+        assert_eq!(memex_get_last_error(), 0);
+        assert!(path.exists());
+        memex_destroy(handle);
+    }
+
+    #[test]
+    fn test_invalid_handle_sets_last_error() {
+        let memory_id = CString::new("missing").unwrap();
+        assert!(memex_get_memory(usize::MAX, memory_id.as_ptr()).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidHandle as i32);
+
+        assert!(memex_decay(usize::MAX).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidHandle as i32);
+    }
+
+    #[test]
+    fn test_save_get_and_delete() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = CString::new(
+            serde_json::json!({
+                "database_path": temp_dir.path().join("ffi.db").to_string_lossy()
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let handle = memex_init_with_config(config.as_ptr());
+        assert_ne!(handle, 0);
+
+        let user_id = CString::new("user1").unwrap();
+        let session_id = CString::new("session1").unwrap();
+        let content = CString::new("Remember the milk").unwrap();
+        let id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.7,
+            24,
+            ptr::null(),
+        );
+        assert!(!id_ptr.is_null());
+        let memory_id = unsafe { CStr::from_ptr(id_ptr) }.to_owned();
+        memex_free_string(id_ptr);
+
+        let json_ptr = memex_get_memory(handle, memory_id.as_ptr());
+        assert!(!json_ptr.is_null());
+        let memory: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(json_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(json_ptr);
+        assert_eq!(memory["content"], "Remember the milk");
+
+        assert!(memex_delete_memory(handle, memory_id.as_ptr()));
+        assert!(memex_get_memory(handle, memory_id.as_ptr()).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::NotFound as i32);
+
+        memex_destroy(handle);
+    }
+
+    #[test]
+    fn test_save_reports_typed_errors() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = CString::new(
+            serde_json::json!({
+                "database_path": temp_dir.path().join("ffi.db").to_string_lossy(),
+                "max_content_bytes": 16
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let handle = memex_init_with_config(config.as_ptr());
+        assert_ne!(handle, 0);
+
+        let user_id = CString::new("user1").unwrap();
+        let session_id = CString::new("session1").unwrap();
+        let content = CString::new("this content is longer than sixteen bytes").unwrap();
+        let save = |content: *const c_char| {
+            memex_save(
+                handle,
+                user_id.as_ptr(),
+                session_id.as_ptr(),
+                content,
+                0.5,
+                -1,
+                ptr::null(),
+            )
+        };
+
+        assert!(save(content.as_ptr()).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::LimitExceeded as i32);
+
+        let json_ptr = memex_last_error_json();
+        let details: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(json_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(json_ptr);
+        assert_eq!(details["field"], "content");
+        assert_eq!(details["limit"], 16);
+        assert_eq!(details["actual"], 41);
+
+        let invalid_utf8 = [b'h', b'i', 0xff, 0];
+        assert!(save(invalid_utf8.as_ptr() as *const c_char).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidUtf8 as i32);
+
+        let content = CString::new("short").unwrap();
+        let id_ptr = save(content.as_ptr());
+        assert!(!id_ptr.is_null());
+        memex_free_string(id_ptr);
+        assert!(memex_last_error_json().is_null());
+
+        memex_destroy(handle);
+    }
+
+    #[cfg(feature = "vector-search")]
+    #[test]
+    fn test_vector_search_filters() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let config = CString::new(
+            serde_json::json!({
+                "database": {
+                    "path": temp_dir.path().join("ffi.db").to_string_lossy(),
+                    "max_connections": 1,
+                    "min_connections": 1
+                },
+                "vector": {"dimension": 3, "similarity_threshold": 0.5}
+            })
+            .to_string(),
+        )
+        .unwrap();
+        let handle = memex_init_with_config(config.as_ptr());
+        assert_ne!(handle, 0);
+
+        let instance = ffi::get_instance(handle).unwrap();
+        for user_id in ["alice", "bob"] {
+            let memory_id = instance
+                .database
+                .save_memory(&MemoryItem {
+                    user_id: user_id.to_string(),
+                    session_id: format!("{}-session", user_id),
+                    content: format!("{} likes rust", user_id),
+                    ..Default::default()
+                })
+                .unwrap();
+            instance
+                .vector_engine()
+                .unwrap()
+                .store_embedding(&memory_id, &[1.0, 0.0, 0.0], "model")
+                .unwrap();
+        }
+
+        let search = |request: serde_json::Value| {
+            let request = CString::new(request.to_string()).unwrap();
+            let json_ptr = memex_vector_search(handle, request.as_ptr());
+            if json_ptr.is_null() {
+                return None;
+            }
+            let results: serde_json::Value =
+                serde_json::from_str(unsafe { CStr::from_ptr(json_ptr) }.to_str().unwrap())
+                    .unwrap();
+            memex_free_string(json_ptr);
+            Some(results)
+        };
+
+        let results = search(serde_json::json!({
+            "embedding": [1.0, 0.0, 0.0],
+            "model": "model",
+            "filter": {"user_id": "alice"}
+        }))
+        .unwrap();
+        assert_eq!(results.as_array().unwrap().len(), 1);
+        assert_eq!(results[0]["user_id"], "alice");
+
+        let results = search(serde_json::json!({"embedding": [1.0, 0.0, 0.0], "model": "model"}));
+        assert_eq!(results.unwrap().as_array().unwrap().len(), 2);
+
+        assert!(search(serde_json::json!({
+            "embedding": [1.0, 0.0, 0.0],
+            "model": "model",
+            "filter": {"min_importance": 3.0}
+        }))
+        .is_none());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidArgument as i32);
+
+        let request =
+            CString::new(r#"{"user_id": "alice", "embedding": [1.0, 0.0, 0.0], "model": "model"}"#)
+                .unwrap();
+        let json_ptr = memex_search_sessions_semantic(handle, request.as_ptr());
+        assert!(!json_ptr.is_null());
+        let sessions: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(json_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(json_ptr);
+        assert_eq!(sessions.as_array().unwrap().len(), 1);
+        assert_eq!(sessions[0]["session_id"], "alice-session");
+
+        memex_destroy(handle);
+
+        // Instances without a `vector` config reject vector searches
+        let handle = memex_init();
+        let request = CString::new(r#"{"embedding": [1.0], "model": "model"}"#).unwrap();
+        assert!(memex_vector_search(handle, request.as_ptr()).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidConfig as i32);
+        let request =
+            CString::new(r#"{"user_id": "alice", "embedding": [1.0], "model": "model"}"#).unwrap();
+        assert!(memex_search_sessions_semantic(handle, request.as_ptr()).is_null());
+        assert_eq!(memex_get_last_error(), FfiErrorCode::InvalidConfig as i32);
+        memex_destroy(handle);
+    }
 }
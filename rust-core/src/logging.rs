@@ -0,0 +1,586 @@
+//! Structured logging
+//!
+//! Logging goes through `tracing`. Events carry key-value fields
+//! (`tracing::info!(memory_id = id, "...")`) and are printed with the fields
+//! of the [`span`]s they happen in, so a line logged deep in the database
+//! layer still says which operation, user and session it belongs to.
+//! Dependencies that log through the `log` crate are bridged in with
+//! `tracing-log`.
+//!
+//! [`init`] installs a subscriber that prints those fields to stderr as text
+//! or JSON lines. Its filter uses `RUST_LOG`-style directives
+//! (`"info,memex_core::database=debug"`) and can be replaced at runtime with
+//! [`set_filter`], which is what `memex_set_log_level` calls.
+
+use anyhow::{anyhow, Result};
+use chrono::{SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+use std::io::Write;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::{Event, Metadata, Subscriber};
+use tracing_log::NormalizeEvent;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::registry::{LookupSpan, Registry};
+use tracing_subscriber::reload;
+
+/// Fields a span can carry; [`Span::with`] and [`Span::record`] take only
+/// these, as `tracing` fixes a span's fields when it is opened
+pub const SPAN_FIELDS: &[&str] = &[
+    "op",
+    "user_id",
+    "session_id",
+    "memory_id",
+    "other_id",
+    "run_id",
+    "from_key",
+    "actor",
+    "trace_id",
+    "source",
+];
+
+/// How log lines are written
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    /// `2025-01-01T00:00:00.000Z INFO memex_core::core::memory: Saved memory op=save_memory ...`
+    #[default]
+    Text,
+    /// One JSON object per line with `timestamp`, `level`, `target`,
+    /// `message` and `fields`
+    Json,
+}
+
+/// Guard for an operation's `tracing` span, entered until it is dropped
+///
+/// Fields recorded on it are attached to every event logged on this thread
+/// while it is entered. Spans nest; an inner field replaces an outer one
+/// with the same key.
+#[must_use = "the span is exited when it is dropped"]
+pub struct Span(tracing::span::EnteredSpan);
+
+// Spans are ERROR level so they stay enabled, and their fields reach the
+// event, whatever level the filter lets through
+macro_rules! context_span {
+    ($name:literal, $op:expr) => {
+        tracing::error_span!(
+            $name,
+            op = $op,
+            user_id = tracing::field::Empty,
+            session_id = tracing::field::Empty,
+            memory_id = tracing::field::Empty,
+            other_id = tracing::field::Empty,
+            run_id = tracing::field::Empty,
+            from_key = tracing::field::Empty,
+            actor = tracing::field::Empty,
+            trace_id = tracing::field::Empty,
+            source = tracing::field::Empty,
+        )
+    };
+}
+
+/// Open a span for operation `op`
+pub fn span(op: &'static str) -> Span {
+    Span(context_span!("operation", op).entered())
+}
+
+/// Open a span that adds fields to the enclosing operation's
+pub fn scope() -> Span {
+    Span(context_span!("scope", tracing::field::Empty).entered())
+}
+
+impl Span {
+    /// Add a field to the span
+    pub fn with(self, key: &'static str, value: impl Display) -> Self {
+        self.record(key, value);
+        self
+    }
+
+    /// Add a field once its value is known, e.g. the id of a new memory
+    pub fn record(&self, key: &'static str, value: impl Display) {
+        debug_assert!(
+            SPAN_FIELDS.contains(&key),
+            "`{}` is not one of logging::SPAN_FIELDS",
+            key
+        );
+        self.0.record(key, tracing::field::display(value));
+    }
+}
+
+/// Which events are logged, parsed from directives like
+/// `"warn,memex_core::database=debug"`
+///
+/// A bare level sets the default; `target=level` applies to that module path
+/// and everything below it, the longest matching target winning.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    directives: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// Most verbose level `target` logs at
+    pub fn level_for(&self, target: &str) -> LevelFilter {
+        self.directives
+            .iter()
+            .filter(|(prefix, _)| {
+                target == prefix
+                    || (target.starts_with(prefix.as_str())
+                        && target[prefix.len()..].starts_with("::"))
+            })
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// Most verbose level of any directive
+    pub fn max_level(&self) -> LevelFilter {
+        self.directives
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, Ord::max)
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::INFO,
+            directives: Vec::new(),
+        }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(spec: &str) -> Result<Self> {
+        let parse_level = |level: &str| {
+            LevelFilter::from_str(level.trim())
+                .map_err(|_| anyhow!("invalid log level `{}`", level.trim()))
+        };
+
+        let mut filter = Self::default();
+        for directive in spec.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+            match directive.split_once('=') {
+                Some((target, level)) => {
+                    let target = target.trim();
+                    if target.is_empty() {
+                        return Err(anyhow!("missing target in `{}`", directive));
+                    }
+                    filter
+                        .directives
+                        .retain(|(existing, _)| existing.as_str() != target);
+                    filter
+                        .directives
+                        .push((target.to_string(), parse_level(level)?));
+                }
+                None => filter.default = parse_level(directive)?,
+            }
+        }
+
+        Ok(filter)
+    }
+}
+
+impl<S: Subscriber> Layer<S> for LogFilter {
+    fn enabled(&self, metadata: &Metadata<'_>, _ctx: Context<'_, S>) -> bool {
+        *metadata.level() <= self.level_for(metadata.target())
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level())
+    }
+}
+
+/// The subscriber [`init`] installs, built on first use so [`set_filter`]
+/// works before it
+struct Logger {
+    filter: reload::Handle<LogFilter, Registry>,
+    format: Arc<RwLock<LogFormat>>,
+    /// Taken by the first [`init`]
+    dispatch: Mutex<Option<tracing::Dispatch>>,
+}
+
+static LOGGER: once_cell::sync::Lazy<Logger> = once_cell::sync::Lazy::new(|| {
+    let format = Arc::new(RwLock::new(LogFormat::default()));
+    let (filter, handle) = reload::Layer::new(LogFilter::default());
+    let subscriber =
+        Registry::default()
+            .with(filter)
+            .with(FormatLayer::new(format.clone(), |line| {
+                let _ = writeln!(std::io::stderr().lock(), "{}", line);
+            }));
+    Logger {
+        filter: handle,
+        format,
+        dispatch: Mutex::new(Some(subscriber.into())),
+    }
+});
+
+/// Install the subscriber with `filter` directives and `format`
+///
+/// Calling it again only updates the filter and format. If the host process
+/// installed a different subscriber or `log` logger first, that one keeps
+/// receiving events.
+pub fn init(filter: &str, format: LogFormat) -> Result<()> {
+    let filter: LogFilter = filter.parse()?;
+    *LOGGER
+        .format
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = format;
+    set_log_filter(filter)?;
+
+    let dispatch = LOGGER
+        .dispatch
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .take();
+    if let Some(dispatch) = dispatch {
+        // Both fail only when the host got there first
+        let _ = tracing::dispatcher::set_global_default(dispatch);
+        let _ = tracing_log::LogTracer::init();
+    }
+    Ok(())
+}
+
+/// Replace the filter directives, e.g. `"debug"` or `"info,memex_core::ffi=trace"`
+pub fn set_filter(spec: &str) -> Result<()> {
+    set_log_filter(spec.parse()?)
+}
+
+fn set_log_filter(filter: LogFilter) -> Result<()> {
+    LOGGER
+        .filter
+        .reload(filter)
+        .map_err(|e| anyhow!("failed to replace log filter: {}", e))
+}
+
+/// Layer that writes each event as a line with its spans' fields
+struct FormatLayer<W> {
+    format: Arc<RwLock<LogFormat>>,
+    write: W,
+}
+
+impl<W: Fn(&str)> FormatLayer<W> {
+    fn new(format: Arc<RwLock<LogFormat>>, write: W) -> Self {
+        Self { format, write }
+    }
+
+    fn format_event(
+        &self,
+        metadata: &Metadata<'_>,
+        message: &str,
+        fields: Vec<(&'static str, FieldValue)>,
+    ) -> String {
+        let timestamp = Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true);
+        let format = *self
+            .format
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match format {
+            LogFormat::Text => {
+                let mut line = format!(
+                    "{} {} {}: {}",
+                    timestamp,
+                    metadata.level(),
+                    metadata.target(),
+                    message
+                );
+                for (key, value) in &fields {
+                    line.push_str(&format!(" {}={}", key, value));
+                }
+                line
+            }
+            LogFormat::Json => {
+                let fields = fields
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value.into_json()))
+                    .collect::<serde_json::Map<_, _>>();
+                serde_json::json!({
+                    "timestamp": timestamp,
+                    "level": metadata.level().as_str(),
+                    "target": metadata.target(),
+                    "message": message,
+                    "fields": fields,
+                })
+                .to_string()
+            }
+        }
+    }
+}
+
+impl<S, W> Layer<S> for FormatLayer<W>
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+    W: Fn(&str) + Send + Sync + 'static,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut fields = SpanFields(Vec::new());
+        attrs.record(&mut FieldVisitor::new(&mut fields.0));
+        span.extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        if let Some(fields) = extensions.get_mut::<SpanFields>() {
+            values.record(&mut FieldVisitor::new(&mut fields.0));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let mut fields = Vec::new();
+        if let Some(scope) = ctx.event_scope(event) {
+            for span in scope.from_root() {
+                if let Some(span_fields) = span.extensions().get::<SpanFields>() {
+                    for (key, value) in &span_fields.0 {
+                        set_field(&mut fields, key, value.clone());
+                    }
+                }
+            }
+        }
+        let mut message = String::new();
+        event.record(&mut FieldVisitor {
+            fields: &mut fields,
+            message: Some(&mut message),
+        });
+
+        // Events bridged from `log` carry their real target in fields
+        let normalized = event.normalized_metadata();
+        let metadata = normalized.as_ref().unwrap_or_else(|| event.metadata());
+        (self.write)(&self.format_event(metadata, &message, fields));
+    }
+}
+
+/// Fields recorded on a span, kept in its extensions
+struct SpanFields(Vec<(&'static str, FieldValue)>);
+
+/// A field value; numbers and booleans stay typed in JSON output
+#[derive(Clone)]
+enum FieldValue {
+    Text(String),
+    Json(serde_json::Value),
+}
+
+impl FieldValue {
+    fn into_json(self) -> serde_json::Value {
+        match self {
+            FieldValue::Text(text) => serde_json::Value::String(text),
+            FieldValue::Json(value) => value,
+        }
+    }
+}
+
+impl Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Text(text) if text.is_empty() || text.contains(char::is_whitespace) => {
+                write!(f, "{:?}", text)
+            }
+            FieldValue::Text(text) => f.write_str(text),
+            FieldValue::Json(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+/// Set `key`, replacing an earlier value so inner fields win
+fn set_field(fields: &mut Vec<(&'static str, FieldValue)>, key: &'static str, value: FieldValue) {
+    match fields.iter_mut().find(|(k, _)| *k == key) {
+        Some(field) => field.1 = value,
+        None => fields.push((key, value)),
+    }
+}
+
+/// Collects span or event fields, and an event's message
+struct FieldVisitor<'a> {
+    fields: &'a mut Vec<(&'static str, FieldValue)>,
+    message: Option<&'a mut String>,
+}
+
+impl<'a> FieldVisitor<'a> {
+    fn new(fields: &'a mut Vec<(&'static str, FieldValue)>) -> Self {
+        Self {
+            fields,
+            message: None,
+        }
+    }
+
+    fn set(&mut self, field: &Field, value: FieldValue) {
+        // `log.target` and friends are read back by `normalized_metadata`
+        if !field.name().starts_with("log.") {
+            set_field(self.fields, field.name(), value);
+        }
+    }
+}
+
+impl Visit for FieldVisitor<'_> {
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.set(field, FieldValue::Json(value.into()));
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.set(field, FieldValue::Json(value.into()));
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.set(field, FieldValue::Json(value.into()));
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.set(field, FieldValue::Json(value.into()));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.set(field, FieldValue::Text(value.to_string()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match &mut self.message {
+            Some(message) if field.name() == "message" => **message = format!("{:?}", value),
+            _ => self.set(field, FieldValue::Text(format!("{:?}", value))),
+        }
+    }
+}
+
+/// A subscriber that keeps the lines it would print, for tests that check
+/// what reaches the log
+#[cfg(test)]
+pub(crate) fn capture(
+    filter: &str,
+    format: LogFormat,
+) -> (tracing::Dispatch, Arc<Mutex<Vec<String>>>) {
+    let lines = Arc::new(Mutex::new(Vec::new()));
+    let sink = lines.clone();
+    let subscriber = Registry::default()
+        .with(filter.parse::<LogFilter>().unwrap())
+        .with(FormatLayer::new(
+            Arc::new(RwLock::new(format)),
+            move |line: &str| sink.lock().unwrap().push(line.to_string()),
+        ));
+    (subscriber.into(), lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spans_nest_and_unwind() {
+        let (dispatch, lines) = capture("info", LogFormat::Text);
+        tracing::dispatcher::with_default(&dispatch, || {
+            let outer = span("save_memory").with("user_id", "user1");
+            {
+                let inner = span("create_session").with("session_id", "s1");
+                inner.record("user_id", "user2");
+                tracing::info!("Inner");
+            }
+            outer.record("memory_id", 42);
+            tracing::info!("Outer");
+            drop(outer);
+            tracing::info!("Outside");
+        });
+
+        let lines = lines.lock().unwrap();
+        assert!(
+            lines[0].ends_with(": Inner op=create_session user_id=user2 session_id=s1"),
+            "{}",
+            lines[0]
+        );
+        assert!(
+            lines[1].ends_with(": Outer op=save_memory user_id=user1 memory_id=42"),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[2].ends_with(": Outside"), "{}", lines[2]);
+    }
+
+    #[test]
+    fn test_filter_directives() {
+        let filter: LogFilter = "warn, memex_core::database=debug,memex_core::database::pool=error"
+            .parse()
+            .unwrap();
+
+        assert_eq!(
+            filter.level_for("memex_core::core::memory"),
+            LevelFilter::WARN
+        );
+        assert_eq!(filter.level_for("memex_core::database"), LevelFilter::DEBUG);
+        assert_eq!(
+            filter.level_for("memex_core::database::schema"),
+            LevelFilter::DEBUG
+        );
+        assert_eq!(
+            filter.level_for("memex_core::database::pool"),
+            LevelFilter::ERROR
+        );
+        // A prefix only matches whole path segments
+        assert_eq!(filter.level_for("memex_core::databases"), LevelFilter::WARN);
+        assert_eq!(filter.max_level(), LevelFilter::DEBUG);
+
+        assert_eq!("".parse::<LogFilter>().unwrap(), LogFilter::default());
+        assert_eq!(
+            "OFF".parse::<LogFilter>().unwrap().max_level(),
+            LevelFilter::OFF
+        );
+
+        for invalid in ["loud", "memex_core=loud", "=debug", "info,memex_core"] {
+            assert!(invalid.parse::<LogFilter>().is_err(), "{}", invalid);
+        }
+
+        // Events below a target's level are dropped, but spans stay open
+        // so events that do pass keep their fields
+        let (dispatch, lines) = capture("warn,memex_core::logging=error", LogFormat::Text);
+        tracing::dispatcher::with_default(&dispatch, || {
+            let _span = span("recall").with("user_id", "user1");
+            tracing::warn!("Dropped");
+            tracing::error!("Kept");
+        });
+        let lines = lines.lock().unwrap();
+        assert_eq!(lines.len(), 1);
+        assert!(
+            lines[0].ends_with(": Kept op=recall user_id=user1"),
+            "{}",
+            lines[0]
+        );
+    }
+
+    #[test]
+    fn test_event_formats() {
+        let emit = |format| {
+            let (dispatch, lines) = capture("info", format);
+            tracing::dispatcher::with_default(&dispatch, || {
+                let _span = span("recall").with("user_id", "user 1");
+                tracing::info!(count = 3u64, user_id = "u2", "Recalled memories");
+            });
+            let line = lines.lock().unwrap().pop().unwrap();
+            line
+        };
+
+        let line = emit(LogFormat::Text);
+        assert!(
+            line.ends_with(
+                " INFO memex_core::logging::tests: Recalled memories op=recall user_id=u2 count=3"
+            ),
+            "{}",
+            line
+        );
+
+        let json: serde_json::Value = serde_json::from_str(&emit(LogFormat::Json)).unwrap();
+        assert_eq!(json["level"], "INFO");
+        assert_eq!(json["target"], "memex_core::logging::tests");
+        assert_eq!(json["message"], "Recalled memories");
+        assert_eq!(json["fields"]["op"], "recall");
+        assert_eq!(json["fields"]["user_id"], "u2");
+        assert_eq!(json["fields"]["count"], 3);
+    }
+}
@@ -0,0 +1,7 @@
+//! Memex CLI binary
+
+use memex_core::cli::CliBuilder;
+
+fn main() {
+    CliBuilder::new().main();
+}
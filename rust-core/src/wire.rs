@@ -0,0 +1,191 @@
+//! Wire formats for responses and export files
+//!
+//! JSON stays the default everywhere. CBOR carries the same serde data model
+//! in a compact binary encoding, which is much cheaper to produce and parse
+//! for bulk results such as recall pages and full exports.
+
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+/// How results are encoded
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WireFormat {
+    #[default]
+    Json,
+    Cbor,
+}
+
+impl WireFormat {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            WireFormat::Json => "json",
+            WireFormat::Cbor => "cbor",
+        }
+    }
+
+    /// MIME type of encoded data
+    pub fn content_type(self) -> &'static str {
+        match self {
+            WireFormat::Json => "application/json",
+            WireFormat::Cbor => "application/cbor",
+        }
+    }
+
+    /// Encode `value` in this format
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            WireFormat::Json => serde_json::to_vec(value).context("Failed to encode JSON"),
+            WireFormat::Cbor => {
+                let mut bytes = Vec::new();
+                ciborium::ser::into_writer(value, &mut bytes).context("Failed to encode CBOR")?;
+                Ok(bytes)
+            }
+        }
+    }
+
+    /// Decode a value encoded in this format
+    pub fn decode<T: DeserializeOwned>(self, bytes: &[u8]) -> Result<T> {
+        match self {
+            WireFormat::Json => serde_json::from_slice(bytes).context("Failed to decode JSON"),
+            WireFormat::Cbor => ciborium::de::from_reader(bytes).context("Failed to decode CBOR"),
+        }
+    }
+}
+
+impl fmt::Display for WireFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for WireFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(WireFormat::Json),
+            "cbor" => Ok(WireFormat::Cbor),
+            _ => Err(anyhow::anyhow!(
+                "Unknown wire format '{}' (expected json or cbor)",
+                s
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::models::{
+        ContentType, MemoryItem, PaginatedResponse, QueryFilter, Session, SessionStatus,
+    };
+    use chrono::{TimeZone, Utc};
+    use std::collections::HashMap;
+
+    const FORMATS: [WireFormat; 2] = [WireFormat::Json, WireFormat::Cbor];
+
+    fn sample_memory() -> MemoryItem {
+        MemoryItem {
+            id: "m1".to_string(),
+            user_id: "alice".to_string(),
+            session_id: "s1".to_string(),
+            content: "Bought AAPL at 150 — café ☕".to_string(),
+            metadata: HashMap::from([("ticker".to_string(), "AAPL".to_string())]),
+            created_at: Utc.with_ymd_and_hms(2026, 3, 1, 9, 30, 0).unwrap(),
+            updated_at: Utc.with_ymd_and_hms(2026, 3, 2, 10, 0, 0).unwrap(),
+            expires_at: Some(Utc.with_ymd_and_hms(2026, 4, 1, 9, 30, 0).unwrap()),
+            importance: 0.75,
+            ttl_hours: Some(24 * 31),
+            compressed_from: vec!["a".to_string(), "b".to_string()],
+            version: 3,
+            match_score: Some(0.5),
+            content_type: ContentType::Markdown,
+            ..Default::default()
+        }
+    }
+
+    fn round_trip<T: Serialize + DeserializeOwned>(format: WireFormat, value: &T) -> T {
+        let bytes = format.encode(value).unwrap();
+        format.decode(&bytes).unwrap()
+    }
+
+    /// Values compared through their JSON form, for types without `PartialEq`
+    fn as_json<T: Serialize>(value: &T) -> serde_json::Value {
+        serde_json::to_value(value).unwrap()
+    }
+
+    #[test]
+    fn test_memory_round_trip() {
+        let memories = vec![sample_memory(), MemoryItem::default()];
+        for format in FORMATS {
+            let decoded = round_trip(format, &memories);
+            assert_eq!(as_json(&decoded), as_json(&memories), "{}", format);
+            assert_eq!(decoded[0].created_at, memories[0].created_at);
+        }
+
+        let json = WireFormat::Json.encode(&memories).unwrap();
+        let cbor = WireFormat::Cbor.encode(&memories).unwrap();
+        assert!(cbor.len() < json.len());
+    }
+
+    #[test]
+    fn test_response_and_filter_round_trip() {
+        let page = PaginatedResponse {
+            data: vec![sample_memory()],
+            total_count: 41,
+            page: 2,
+            per_page: 20,
+            total_pages: 3,
+            has_next: true,
+            has_prev: true,
+        };
+        let filter = QueryFilter {
+            user_id: Some("alice".to_string()),
+            keywords: Some(vec!["aapl".to_string()]),
+            min_importance: Some(0.25),
+            expiring_within: Some(chrono::Duration::hours(6)),
+            json_fields: HashMap::from([("$.ticker".to_string(), serde_json::json!("AAPL"))]),
+            ..Default::default()
+        };
+        let session = Session {
+            id: "s1".to_string(),
+            user_id: "alice".to_string(),
+            name: Some("Trading".to_string()),
+            created_at: Utc.with_ymd_and_hms(2026, 3, 1, 9, 0, 0).unwrap(),
+            last_active: Utc.with_ymd_and_hms(2026, 3, 2, 9, 0, 0).unwrap(),
+            memory_count: 4,
+            tags: vec!["desk".to_string()],
+            status: SessionStatus::Archived,
+            metadata: HashMap::new(),
+        };
+
+        for format in FORMATS {
+            let decoded = round_trip(format, &page);
+            assert_eq!(as_json(&decoded), as_json(&page));
+            assert_eq!(decoded.total_count, 41);
+            assert!(decoded.has_next);
+
+            let decoded = round_trip(format, &filter);
+            assert_eq!(decoded.user_id, filter.user_id);
+            assert_eq!(decoded.expiring_within, filter.expiring_within);
+            assert_eq!(decoded.json_fields, filter.json_fields);
+
+            let decoded = round_trip(format, &session);
+            assert_eq!(decoded.status, SessionStatus::Archived);
+            assert_eq!(decoded.last_active, session.last_active);
+        }
+    }
+
+    #[test]
+    fn test_decode_rejects_other_format() {
+        let cbor = WireFormat::Cbor.encode(&sample_memory()).unwrap();
+        assert!(WireFormat::Json.decode::<MemoryItem>(&cbor).is_err());
+        assert_eq!("CBOR".parse::<WireFormat>().unwrap(), WireFormat::Cbor);
+        assert!("msgpack".parse::<WireFormat>().is_err());
+    }
+}
@@ -98,6 +98,25 @@ fn test_ffi_memory_operations() {
     assert!(!memory_id.is_empty(), "Memory ID should not be empty");
     println!("Saved memory with ID: {}", memory_id);
 
+    // Test save with receipt
+    let receipt_ptr = memex_save_with_receipt(
+        handle,
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        content.as_ptr(),
+        0.5,
+        -1,
+        ptr::null(),
+    );
+    assert!(!receipt_ptr.is_null(), "Save should return a receipt");
+    let receipt: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(receipt_ptr) }.to_str().unwrap()).unwrap();
+    assert_ne!(receipt["id"], memory_id);
+    assert_eq!(receipt["session_created"], false);
+    assert_eq!(receipt["deduplicated"], false);
+    assert_eq!(receipt["bytes_written"], "FFI test memory content".len());
+    memex_free_string(receipt_ptr);
+
     // Keep a copy of the memory ID for later use
     let memory_id_copy = CString::new(memory_id).unwrap();
 
@@ -144,9 +163,49 @@ fn test_ffi_memory_operations() {
 
     assert_eq!(updated_memory["content"], "Updated FFI test content");
     assert_eq!(updated_memory["importance"], 0.9);
+    assert_eq!(updated_memory["version"], 2);
 
     memex_free_string(updated_ptr);
 
+    // A stale expected_version is rejected with a conflict
+    let stale = CString::new(r#"{"content": "Stale edit", "expected_version": 1}"#).unwrap();
+    assert!(!memex_update_memory(
+        handle,
+        memory_id_copy.as_ptr(),
+        stale.as_ptr()
+    ));
+    assert_eq!(memex_get_last_error(), 13);
+
+    // TTLs can be set, extended and cleared
+    let get = || {
+        let ptr = memex_get_memory(handle, memory_id_copy.as_ptr());
+        let memory: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(ptr);
+        memory
+    };
+    let set_ttl = CString::new(r#"{"ttl_hours": 2}"#).unwrap();
+    assert!(memex_update_memory(
+        handle,
+        memory_id_copy.as_ptr(),
+        set_ttl.as_ptr()
+    ));
+    assert!(memex_extend_ttl(handle, memory_id_copy.as_ptr(), 3));
+    let memory = get();
+    assert_eq!(memory["ttl_hours"], 5);
+    assert!(memory["expires_at"].is_string());
+    let clear_ttl = CString::new(r#"{"ttl_hours": null}"#).unwrap();
+    assert!(memex_update_memory(
+        handle,
+        memory_id_copy.as_ptr(),
+        clear_ttl.as_ptr()
+    ));
+    let memory = get();
+    assert!(memory["ttl_hours"].is_null());
+    assert!(memory["expires_at"].is_null());
+    let missing = CString::new("missing").unwrap();
+    assert!(!memex_extend_ttl(handle, missing.as_ptr(), 3));
+
     // Test delete memory
     let deleted = memex_delete_memory(handle, memory_id_copy.as_ptr());
     assert!(deleted, "Memory deletion should succeed");
@@ -371,7 +430,10 @@ fn test_ffi_session_operations() {
         let summary_json = summary_cstr.to_str().unwrap();
         let summary_response: serde_json::Value = serde_json::from_str(summary_json).unwrap();
 
-        assert_eq!(summary_response["session_id"], session_id);
+        assert_eq!(
+            summary_response["session_id"],
+            session_id_copy.to_str().unwrap()
+        );
         assert_eq!(summary_response["memory_count"], 5);
         assert!(!summary_response["summary_text"]
             .as_str()
@@ -405,6 +467,28 @@ fn test_ffi_session_operations() {
 
     memex_free_string(search_sessions_ptr);
 
+    // Test session status: archived sessions leave the default listing
+    let archived = CString::new("archived").unwrap();
+    let bogus = CString::new("frozen").unwrap();
+    assert!(memex_set_session_status(
+        handle,
+        session_id_copy.as_ptr(),
+        archived.as_ptr()
+    ));
+    assert!(!memex_set_session_status(
+        handle,
+        session_id_copy.as_ptr(),
+        bogus.as_ptr()
+    ));
+
+    let sessions_result_ptr = memex_get_user_sessions(handle, user_id.as_ptr(), 10, 0);
+    let sessions_json = unsafe { CStr::from_ptr(sessions_result_ptr) }
+        .to_str()
+        .unwrap();
+    let sessions_response: serde_json::Value = serde_json::from_str(sessions_json).unwrap();
+    assert!(sessions_response["data"].as_array().unwrap().is_empty());
+    memex_free_string(sessions_result_ptr);
+
     // Test delete session
     let deleted = memex_delete_session(handle, session_id_copy.as_ptr(), true);
     assert!(deleted, "Session deletion should succeed");
@@ -471,7 +555,7 @@ fn test_ffi_decay_operations() {
     let decay_response: serde_json::Value = serde_json::from_str(decay_json).unwrap();
 
     assert!(decay_response["total_memories_before"].as_u64().unwrap() >= 3);
-    assert!(decay_response["total_memories_after"].as_u64().unwrap() >= 0);
+    assert!(decay_response["total_memories_after"].as_u64().is_some());
     assert!(
         decay_response["status"].as_str().unwrap() == "completed"
             || decay_response["status"].as_str().unwrap() == "failed"
@@ -552,6 +636,20 @@ fn test_ffi_statistics_and_analytics() {
 
     memex_free_string(session_analytics_ptr);
 
+    // Test user analytics
+    let user_analytics_ptr = memex_get_user_analytics(handle, user_id.as_ptr());
+    assert!(!user_analytics_ptr.is_null(), "Should get user analytics");
+
+    let user_analytics_cstr = unsafe { CStr::from_ptr(user_analytics_ptr) };
+    let user_analytics_response: serde_json::Value =
+        serde_json::from_str(user_analytics_cstr.to_str().unwrap()).unwrap();
+
+    assert_eq!(user_analytics_response["memory_count"], 10);
+    assert_eq!(user_analytics_response["session_count"], 1);
+    assert!(user_analytics_response["storage_bytes"].as_u64().unwrap() > 0);
+
+    memex_free_string(user_analytics_ptr);
+
     // Test export
     let export_result_ptr = memex_export_user_memories(handle, user_id.as_ptr());
     assert!(!export_result_ptr.is_null(), "Should export user memories");
@@ -566,6 +664,670 @@ fn test_ffi_statistics_and_analytics() {
     memex_destroy(handle);
 }
 
+#[test]
+#[serial]
+fn test_ffi_get_memories() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("bulk_user").unwrap();
+    let session_id = CString::new("bulk_session").unwrap();
+    let metadata = CString::new("{}").unwrap();
+
+    let mut ids = Vec::new();
+    for i in 0..5 {
+        let content = CString::new(format!("Bulk memory {}", i)).unwrap();
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            metadata.as_ptr(),
+        );
+        assert!(!memory_id_ptr.is_null());
+        ids.push(
+            unsafe { CStr::from_ptr(memory_id_ptr) }
+                .to_str()
+                .unwrap()
+                .to_string(),
+        );
+        memex_free_string(memory_id_ptr);
+    }
+
+    let requested = serde_json::json!([ids[4], "unknown", ids[0], ids[2]]);
+    let requested = CString::new(requested.to_string()).unwrap();
+    let result_ptr = memex_get_memories(handle, requested.as_ptr());
+    assert!(!result_ptr.is_null());
+    let memories: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(result_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(result_ptr);
+
+    let contents: Vec<&str> = memories
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|memory| memory["content"].as_str().unwrap())
+        .collect();
+    assert_eq!(
+        contents,
+        ["Bulk memory 4", "Bulk memory 0", "Bulk memory 2"]
+    );
+
+    let invalid = CString::new(r#"{"ids": []}"#).unwrap();
+    assert!(memex_get_memories(handle, invalid.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_recall_stream() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("stream.db").to_string_lossy()
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("stream_user").unwrap();
+    let session_id = CString::new("stream_session").unwrap();
+    for i in 0..5 {
+        let content = CString::new(format!("Streamed memory {}", i)).unwrap();
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            ptr::null(),
+        );
+        assert!(!memory_id_ptr.is_null());
+        memex_free_string(memory_id_ptr);
+    }
+
+    let filter = CString::new(r#"{"user_id": "stream_user"}"#).unwrap();
+    let stream_id = memex_recall_begin(handle, filter.as_ptr(), 2);
+    assert_ne!(stream_id, 0);
+
+    let mut sizes = Vec::new();
+    loop {
+        let chunk_ptr = memex_recall_next_chunk(stream_id);
+        assert!(!chunk_ptr.is_null());
+        let chunk: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(chunk_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(chunk_ptr);
+        let chunk = chunk.as_array().unwrap();
+        if chunk.is_empty() {
+            break;
+        }
+        sizes.push(chunk.len());
+    }
+    assert_eq!(sizes, [2, 2, 1]);
+    assert!(memex_recall_end(stream_id));
+    assert!(memex_recall_next_chunk(stream_id).is_null());
+
+    let invalid = CString::new(r#"{"userId": "stream_user"}"#).unwrap();
+    assert_eq!(memex_recall_begin(handle, invalid.as_ptr(), 2), 0);
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_encoded_responses() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("encoded.db").to_string_lossy(),
+        "response_format": "cbor"
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("cbor_user").unwrap();
+    let session_id = CString::new("cbor_session").unwrap();
+    for i in 0..3 {
+        let content = CString::new(format!("Encoded memory {}", i)).unwrap();
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            ptr::null(),
+        );
+        assert!(!memory_id_ptr.is_null());
+        memex_free_string(memory_id_ptr);
+    }
+
+    let take = |buffer: *mut u8, len: usize| {
+        assert!(!buffer.is_null());
+        let bytes = unsafe { std::slice::from_raw_parts(buffer, len) }.to_vec();
+        memex_free_buffer(buffer, len);
+        bytes
+    };
+    let mut len = 0;
+
+    let filter = CString::new(r#"{"user_id": "cbor_user"}"#).unwrap();
+    let bytes = take(memex_recall_encoded(handle, filter.as_ptr(), &mut len), len);
+    let page: PaginatedResponse<MemoryItem> = wire::WireFormat::Cbor.decode(&bytes).unwrap();
+    assert_eq!(page.total_count, 3);
+
+    let bytes = take(
+        memex_export_user_memories_encoded(handle, user_id.as_ptr(), &mut len),
+        len,
+    );
+    let exported: Vec<MemoryItem> = wire::WireFormat::Cbor.decode(&bytes).unwrap();
+    assert_eq!(exported.len(), 3);
+
+    let stream_id = memex_recall_begin(handle, filter.as_ptr(), 2);
+    let bytes = take(memex_recall_next_chunk_encoded(stream_id, &mut len), len);
+    let chunk: Vec<MemoryItem> = wire::WireFormat::Cbor.decode(&bytes).unwrap();
+    assert_eq!(chunk.len(), 2);
+    assert!(memex_recall_end(stream_id));
+
+    // The length has to go somewhere
+    assert!(memex_recall_encoded(handle, filter.as_ptr(), ptr::null_mut()).is_null());
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_counters() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("counter_user").unwrap();
+    let session_id = CString::new("counter_session").unwrap();
+    let metadata = CString::new("{}").unwrap();
+
+    for content in ["Counted memory one", "Counted memory two", ""] {
+        let content = CString::new(content).unwrap();
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            metadata.as_ptr(),
+        );
+        if !memory_id_ptr.is_null() {
+            memex_free_string(memory_id_ptr);
+        }
+    }
+
+    let filter = CString::new(r#"{"user_id": "counter_user"}"#).unwrap();
+    memex_free_string(memex_recall(handle, filter.as_ptr()));
+    let query = CString::new("Counted").unwrap();
+    memex_free_string(memex_search(
+        handle,
+        user_id.as_ptr(),
+        query.as_ptr(),
+        10,
+        0,
+    ));
+
+    let counters_ptr = memex_get_counters(handle);
+    assert!(!counters_ptr.is_null());
+    let counters: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(counters_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(counters_ptr);
+
+    assert_eq!(counters["saves"], 2);
+    assert_eq!(counters["recalls"], 1);
+    assert_eq!(counters["searches"], 1);
+    assert_eq!(counters["errors"], 1, "empty content is rejected");
+    assert!(counters["last_search_at"].is_string());
+    assert!(counters["since"].is_string());
+
+    memex_destroy(handle);
+    assert!(memex_get_counters(handle).is_null());
+}
+
+#[test]
+#[serial]
+fn test_ffi_checkpoint() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("wal.db").to_string_lossy(),
+        "enable_request_limits": false
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("wal_user").unwrap();
+    let session_id = CString::new("wal_session").unwrap();
+    let content = CString::new("Written through the WAL").unwrap();
+    let memory_id_ptr = memex_save(
+        handle,
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        content.as_ptr(),
+        0.5,
+        -1,
+        ptr::null(),
+    );
+    assert!(!memory_id_ptr.is_null());
+    memex_free_string(memory_id_ptr);
+
+    let checkpoint_ptr = memex_checkpoint(handle, ptr::null());
+    assert!(!checkpoint_ptr.is_null());
+    let checkpoint: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(checkpoint_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(checkpoint_ptr);
+    assert_eq!(checkpoint["mode"], "truncate");
+    assert_eq!(checkpoint["busy"], false);
+    assert_eq!(checkpoint["wal_size_after"], 0);
+
+    let stats_ptr = memex_get_stats(handle);
+    let stats: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(stats_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(stats_ptr);
+    assert_eq!(stats["wal_size_bytes"], 0);
+
+    let invalid = CString::new("sideways").unwrap();
+    assert!(memex_checkpoint(handle, invalid.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_health() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("health.db").to_string_lossy(),
+        "enable_request_limits": false
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let health = |handle| {
+        let health_ptr = memex_health(handle);
+        assert!(!health_ptr.is_null());
+        let health: serde_json::Value =
+            serde_json::from_str(unsafe { CStr::from_ptr(health_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(health_ptr);
+        health
+    };
+
+    let before = health(handle);
+    assert_eq!(before["status"], "healthy");
+    assert_eq!(before["database_status"], "connected");
+    assert!(before["last_decay_run"].is_null());
+    assert_eq!(before["counters"]["errors"], 0);
+    assert!(before["pool_status"]["write_pool"].is_object());
+    assert!(before["wal_size_bytes"].is_u64());
+
+    let user_id = CString::new("health_user").unwrap();
+    let session_id = CString::new("health_session").unwrap();
+    let empty = CString::new("").unwrap();
+    assert!(memex_save(
+        handle,
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        empty.as_ptr(),
+        0.5,
+        -1,
+        ptr::null(),
+    )
+    .is_null());
+    memex_free_string(memex_decay(handle));
+
+    let after = health(handle);
+    assert_eq!(after["status"], "healthy");
+    assert_eq!(after["counters"]["errors"], 1, "empty content is rejected");
+    assert_eq!(after["last_decay_run"]["status"], "completed");
+
+    memex_destroy(handle);
+    assert!(memex_health(handle).is_null());
+}
+
+#[test]
+#[serial]
+fn test_ffi_request_context_audit() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("audit.db").to_string_lossy(),
+        "enable_request_limits": false
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let save = |content: &str| {
+        let user_id = CString::new("audit_user").unwrap();
+        let session_id = CString::new("audit_session").unwrap();
+        let content = CString::new(content).unwrap();
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            ptr::null(),
+        );
+        assert!(!memory_id_ptr.is_null());
+        memex_free_string(memory_id_ptr);
+    };
+    let audit_log = |filter: Option<&str>| {
+        let filter = filter.map(|filter| CString::new(filter).unwrap());
+        let log_ptr = memex_get_audit_log(
+            handle,
+            filter
+                .as_ref()
+                .map_or(ptr::null(), |filter| filter.as_ptr()),
+            -1,
+        );
+        assert!(!log_ptr.is_null());
+        let log: Vec<serde_json::Value> =
+            serde_json::from_str(unsafe { CStr::from_ptr(log_ptr) }.to_str().unwrap()).unwrap();
+        memex_free_string(log_ptr);
+        log
+    };
+
+    save("Before any context");
+    assert!(audit_log(None).is_empty());
+
+    let context =
+        CString::new(r#"{"actor": "planner", "trace_id": "trace-1", "source": "ffi-test"}"#)
+            .unwrap();
+    assert!(memex_set_request_context(context.as_ptr()));
+    save("Planned");
+    assert!(memex_set_request_context(ptr::null()));
+    save("After clearing");
+
+    let log = audit_log(None);
+    assert_eq!(log.len(), 1);
+    assert_eq!(log[0]["action"], "memory_save");
+    assert_eq!(log[0]["actor"], "planner");
+    assert_eq!(log[0]["trace_id"], "trace-1");
+    assert_eq!(log[0]["source"], "ffi-test");
+    assert!(audit_log(Some(r#"{"actor": "someone else"}"#)).is_empty());
+
+    let invalid = CString::new(r#"{"actor": ""}"#).unwrap();
+    assert!(!memex_set_request_context(invalid.as_ptr()));
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_access_token() {
+    use memex_core::core::access::hash_key;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = |token: Option<&str>| {
+        let config = serde_json::json!({
+            "database_path": temp_dir.path().join("access.db").to_string_lossy(),
+            "enable_request_limits": false,
+            "token": token,
+            "access": {
+                "api_keys": [{
+                    "name": "planner",
+                    "key_sha256": hash_key("planner-secret"),
+                    "role": "read_only",
+                    "namespaces": {"planner": "writer"}
+                }]
+            }
+        });
+        CString::new(config.to_string()).unwrap()
+    };
+
+    assert_eq!(memex_init_with_config(config(Some("wrong")).as_ptr()), 0);
+    assert_eq!(memex_get_last_error(), 14);
+
+    let handle = memex_init_with_config(config(Some("planner-secret")).as_ptr());
+    assert_ne!(handle, 0);
+
+    let save = |metadata: Option<&str>| {
+        let user_id = CString::new("access_user").unwrap();
+        let session_id = CString::new("access_session").unwrap();
+        let content = CString::new("Plan the week").unwrap();
+        let metadata = metadata.map(|metadata| CString::new(metadata).unwrap());
+        let memory_id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            metadata
+                .as_ref()
+                .map_or(ptr::null(), |metadata| metadata.as_ptr()),
+        );
+        if memory_id_ptr.is_null() {
+            return None;
+        }
+        let memory_id = unsafe { CStr::from_ptr(memory_id_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        memex_free_string(memory_id_ptr);
+        Some(memory_id)
+    };
+
+    assert!(save(None).is_none(), "read-only outside its namespace");
+    assert_eq!(memex_get_last_error(), 14);
+    let memory_id = save(Some(r#"{"namespace": "planner"}"#)).expect("writer in its namespace");
+
+    let filter = CString::new(r#"{"user_id": "access_user"}"#).unwrap();
+    let recalled = memex_recall(handle, filter.as_ptr());
+    assert!(!recalled.is_null());
+    memex_free_string(recalled);
+
+    let memory_id = CString::new(memory_id).unwrap();
+    let memory_ptr = memex_get_memory(handle, memory_id.as_ptr());
+    assert!(!memory_ptr.is_null());
+    let memory: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(memory_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(memory_ptr);
+    assert_eq!(memory["ingested_by"], "planner", "saves record the key");
+
+    assert!(!memex_delete_memory(handle, memory_id.as_ptr()));
+    assert_eq!(memex_get_last_error(), 14);
+    let policy = CString::new(r#"{"max_age_hours": 1}"#).unwrap();
+    assert!(!memex_update_decay_policy(handle, policy.as_ptr()));
+    assert_eq!(memex_get_last_error(), 14);
+    memex_destroy(handle);
+
+    // Without a token the instance is unrestricted and can look keys up
+    let handle = memex_init_with_config(config(None).as_ptr());
+    assert_ne!(handle, 0);
+
+    let token = CString::new("planner-secret").unwrap();
+    let principal_ptr = memex_authenticate(handle, token.as_ptr());
+    assert!(!principal_ptr.is_null());
+    let principal: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(principal_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(principal_ptr);
+    assert_eq!(principal["name"], "planner");
+    assert_eq!(principal["role"], "read_only");
+    assert_eq!(principal["namespaces"]["planner"], "writer");
+
+    let wrong = CString::new("wrong").unwrap();
+    assert!(memex_authenticate(handle, wrong.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 14);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_maintenance() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("maintenance.db").to_string_lossy(),
+        "maintenance": {
+            "enabled": true,
+            "poll_interval_secs": 1,
+            "schedules": {"analyze": "@every 1s"}
+        }
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let job = CString::new("stats_snapshot").unwrap();
+    let run_ptr = memex_maintenance_run(handle, job.as_ptr());
+    assert!(!run_ptr.is_null());
+    let run: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(run_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(run_ptr);
+    assert_eq!(run["job"], "stats_snapshot");
+    assert_eq!(run["status"], "completed");
+    assert_eq!(run["detail"]["total_memories"], 0);
+
+    // The background thread runs analyze once it's due
+    std::thread::sleep(std::time::Duration::from_millis(2500));
+    let history_ptr = memex_maintenance_history(handle, ptr::null(), -1);
+    assert!(!history_ptr.is_null());
+    let history: Vec<serde_json::Value> =
+        serde_json::from_str(unsafe { CStr::from_ptr(history_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(history_ptr);
+    assert!(history.iter().any(|run| run["job"] == "analyze"));
+    assert!(history.iter().any(|run| run["job"] == "stats_snapshot"));
+
+    let history_ptr = memex_maintenance_history(handle, job.as_ptr(), 10);
+    let history: Vec<serde_json::Value> =
+        serde_json::from_str(unsafe { CStr::from_ptr(history_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(history_ptr);
+    assert_eq!(history.len(), 1);
+
+    let invalid = CString::new("webhooks").unwrap();
+    assert!(memex_maintenance_run(handle, invalid.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 1);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_idempotent_save() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("idempotent.db").to_string_lossy(),
+        "enable_request_limits": false
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("idempotent_user").unwrap();
+    let session_id = CString::new("idempotent_session").unwrap();
+    let content = CString::new("Order 1001 placed").unwrap();
+    let key = CString::new("order-1001").unwrap();
+    let save = |key: *const std::os::raw::c_char| {
+        let id_ptr = memex_save_idempotent(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            ptr::null(),
+            key,
+        );
+        assert!(!id_ptr.is_null());
+        let id = unsafe { CStr::from_ptr(id_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        memex_free_string(id_ptr);
+        id
+    };
+
+    let first = save(key.as_ptr());
+    assert_eq!(
+        save(key.as_ptr()),
+        first,
+        "Retry should return the first id"
+    );
+    assert_ne!(save(ptr::null()), first, "No key saves a new memory");
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_idempotent_save_with_token() {
+    use memex_core::core::access::hash_key;
+
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("idempotent_token.db").to_string_lossy(),
+        "enable_request_limits": false,
+        "token": "planner-secret",
+        "access": {
+            "api_keys": [{
+                "name": "planner",
+                "key_sha256": hash_key("planner-secret"),
+                "role": "read_only",
+                "namespaces": {"planner": "writer"}
+            }]
+        }
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("idempotent_user").unwrap();
+    let session_id = CString::new("idempotent_session").unwrap();
+    let content = CString::new("Plan the week").unwrap();
+    let key = CString::new("plan-week-1").unwrap();
+    let save = |metadata: Option<&str>| {
+        let metadata = metadata.map(|metadata| CString::new(metadata).unwrap());
+        let id_ptr = memex_save_idempotent(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            -1,
+            metadata
+                .as_ref()
+                .map_or(ptr::null(), |metadata| metadata.as_ptr()),
+            key.as_ptr(),
+        );
+        if id_ptr.is_null() {
+            return None;
+        }
+        let id = unsafe { CStr::from_ptr(id_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        memex_free_string(id_ptr);
+        Some(id)
+    };
+
+    assert!(save(None).is_none(), "read-only outside its namespace");
+    assert_eq!(memex_get_last_error(), 14);
+    let metadata = r#"{"namespace": "planner"}"#;
+    let first = save(Some(metadata)).expect("writer in its namespace");
+    assert_eq!(
+        save(Some(metadata)),
+        Some(first),
+        "Retry should return the first id"
+    );
+
+    memex_destroy(handle);
+}
+
 #[test]
 #[serial]
 fn test_ffi_error_handling() {
@@ -704,8 +1466,7 @@ fn test_ffi_unicode_handling() {
 
     // Test Unicode search
     let search_query = CString::new("世界").unwrap();
-    let search_result_ptr =
-        memex_search(handle, user_id.as_ptr(), search_query.as_ptr(), 10, 0);
+    let search_result_ptr = memex_search(handle, user_id.as_ptr(), search_query.as_ptr(), 10, 0);
 
     assert!(!search_result_ptr.is_null(), "Should find Unicode content");
 
@@ -871,3 +1632,422 @@ fn test_ffi_multiple_instances() {
     memex_destroy(handle2);
     assert!(!memex_is_valid(handle2));
 }
+
+#[test]
+#[serial]
+fn test_ffi_search_grouped() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("grouped.db").to_string_lossy()
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("grouped_user").unwrap();
+    let metadata = CString::new("{}").unwrap();
+    for (session, count) in [("grouped_a", 1), ("grouped_b", 3)] {
+        let session_id = CString::new(session).unwrap();
+        for i in 0..count {
+            let content = CString::new(format!("Grouped lighthouse note {}", i)).unwrap();
+            let memory_id_ptr = memex_save(
+                handle,
+                user_id.as_ptr(),
+                session_id.as_ptr(),
+                content.as_ptr(),
+                0.5,
+                -1,
+                metadata.as_ptr(),
+            );
+            assert!(!memory_id_ptr.is_null());
+            memex_free_string(memory_id_ptr);
+        }
+    }
+
+    let query = CString::new("lighthouse").unwrap();
+    let result_ptr = memex_search_grouped(handle, user_id.as_ptr(), query.as_ptr(), 2);
+    assert!(!result_ptr.is_null());
+    let groups: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(result_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(result_ptr);
+
+    let groups = groups.as_array().unwrap();
+    assert_eq!(groups.len(), 2);
+    assert_eq!(groups[0]["session"]["id"], "grouped_b");
+    assert_eq!(groups[0]["match_count"], 3);
+    assert_eq!(groups[0]["memories"].as_array().unwrap().len(), 2);
+    assert_eq!(groups[1]["match_count"], 1);
+
+    assert!(memex_search_grouped(handle, user_id.as_ptr(), query.as_ptr(), 0).is_null());
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_synonyms() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let namespace = CString::new("ffi_trading").unwrap();
+    let term = CString::new("AAPL").unwrap();
+    let synonym = CString::new("Apple").unwrap();
+    assert!(memex_add_synonym(
+        handle,
+        namespace.as_ptr(),
+        term.as_ptr(),
+        synonym.as_ptr()
+    ));
+    // Already paired, in the other order
+    assert!(memex_add_synonym(
+        handle,
+        namespace.as_ptr(),
+        synonym.as_ptr(),
+        term.as_ptr()
+    ));
+    assert!(!memex_add_synonym(
+        handle,
+        namespace.as_ptr(),
+        term.as_ptr(),
+        term.as_ptr()
+    ));
+
+    let list_ptr = memex_list_synonyms(handle, namespace.as_ptr());
+    assert!(!list_ptr.is_null());
+    let synonyms: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(list_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(list_ptr);
+    assert_eq!(synonyms.as_array().unwrap().len(), 1);
+    assert_eq!(synonyms[0]["term"], "AAPL");
+    assert_eq!(synonyms[0]["synonym"], "Apple");
+
+    assert!(memex_remove_synonym(
+        handle,
+        namespace.as_ptr(),
+        synonym.as_ptr(),
+        term.as_ptr()
+    ));
+    assert!(!memex_remove_synonym(
+        handle,
+        ptr::null(),
+        term.as_ptr(),
+        synonym.as_ptr()
+    ));
+    assert_eq!(memex_get_last_error(), 7);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_memory_templates() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let template = CString::new(
+        r#"{"id": "task_done", "content": "Finished {{task}}", "metadata": {"status": "done"}}"#,
+    )
+    .unwrap();
+    assert!(memex_set_template(handle, template.as_ptr()));
+
+    let list_ptr = memex_list_templates(handle);
+    assert!(!list_ptr.is_null());
+    let templates: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(list_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(list_ptr);
+    assert!(templates
+        .as_array()
+        .unwrap()
+        .iter()
+        .any(|template| template["id"] == "task_done" && template["importance"] == 0.5));
+
+    let template_id = CString::new("task_done").unwrap();
+    let user_id = CString::new("template_user").unwrap();
+    let session_id = CString::new("template_session").unwrap();
+    let vars = CString::new(r#"{"task": "the report"}"#).unwrap();
+    let memory_id_ptr = memex_save_from_template(
+        handle,
+        template_id.as_ptr(),
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        vars.as_ptr(),
+    );
+    assert!(!memory_id_ptr.is_null());
+
+    let memory_ptr = memex_get_memory(handle, memory_id_ptr);
+    memex_free_string(memory_id_ptr);
+    let memory: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(memory_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(memory_ptr);
+    assert_eq!(memory["content"], "Finished the report");
+    assert_eq!(memory["metadata"]["status"], "done");
+
+    // Missing variable and unknown template
+    assert!(memex_save_from_template(
+        handle,
+        template_id.as_ptr(),
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        ptr::null(),
+    )
+    .is_null());
+    let unknown = CString::new("unknown").unwrap();
+    assert!(memex_save_from_template(
+        handle,
+        unknown.as_ptr(),
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        vars.as_ptr(),
+    )
+    .is_null());
+    assert_eq!(memex_get_last_error(), 7);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_memory_state() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("state_user").unwrap();
+    let session_id = CString::new("state_session").unwrap();
+    let content = CString::new("Archive me later").unwrap();
+    let memory_id_ptr = memex_save(
+        handle,
+        user_id.as_ptr(),
+        session_id.as_ptr(),
+        content.as_ptr(),
+        0.5,
+        0,
+        ptr::null(),
+    );
+    assert!(!memory_id_ptr.is_null());
+
+    let archived = CString::new("archived").unwrap();
+    let previous_ptr = memex_set_memory_state(handle, memory_id_ptr, archived.as_ptr());
+    assert!(!previous_ptr.is_null());
+    assert_eq!(
+        unsafe { CStr::from_ptr(previous_ptr) }.to_str().unwrap(),
+        r#""active""#
+    );
+    memex_free_string(previous_ptr);
+
+    // Archived memories can't go back to being drafts
+    let draft = CString::new("draft").unwrap();
+    assert!(memex_set_memory_state(handle, memory_id_ptr, draft.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 13);
+    memex_free_string(memory_id_ptr);
+
+    let unknown = CString::new("no-such-memory").unwrap();
+    assert!(memex_set_memory_state(handle, unknown.as_ptr(), archived.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 7);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_memory_conflicts() {
+    let handle = memex_init();
+    assert_ne!(handle, 0);
+
+    let user_id = CString::new("conflict_user").unwrap();
+    let session_id = CString::new("conflict_session").unwrap();
+    let save = |content: &str| {
+        let content = CString::new(content).unwrap();
+        let id_ptr = memex_save(
+            handle,
+            user_id.as_ptr(),
+            session_id.as_ptr(),
+            content.as_ptr(),
+            0.5,
+            0,
+            ptr::null(),
+        );
+        assert!(!id_ptr.is_null());
+        let id = unsafe { CStr::from_ptr(id_ptr) }
+            .to_str()
+            .unwrap()
+            .to_string();
+        memex_free_string(id_ptr);
+        CString::new(id).unwrap()
+    };
+    let likes = save("Carol likes window seats on long flights");
+    let dislikes = save("Carol does not like window seats on long flights");
+
+    let conflicts_ptr = memex_find_conflicts(handle, user_id.as_ptr(), likes.as_ptr());
+    assert!(!conflicts_ptr.is_null());
+    let conflicts: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(conflicts_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(conflicts_ptr);
+    assert_eq!(conflicts[0]["memory"]["id"], dislikes.to_str().unwrap());
+    assert_eq!(conflicts[0]["reasons"][0]["kind"], "negation");
+    assert_eq!(conflicts[0]["confirmed"], false);
+
+    let contradicts = CString::new("contradicts").unwrap();
+    assert!(memex_link_memories(
+        handle,
+        likes.as_ptr(),
+        dislikes.as_ptr(),
+        contradicts.as_ptr(),
+        false,
+    ));
+    assert!(memex_link_memories(
+        handle,
+        dislikes.as_ptr(),
+        likes.as_ptr(),
+        contradicts.as_ptr(),
+        true,
+    ));
+    assert!(!memex_link_memories(
+        handle,
+        likes.as_ptr(),
+        dislikes.as_ptr(),
+        contradicts.as_ptr(),
+        true,
+    ));
+    assert_eq!(memex_get_last_error(), 7);
+
+    let unknown = CString::new("no-such-memory").unwrap();
+    assert!(memex_find_conflicts(handle, user_id.as_ptr(), unknown.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 7);
+
+    memex_destroy(handle);
+}
+
+#[test]
+#[serial]
+fn test_ffi_json_memories() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("json.db").to_string_lossy()
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let namespace = CString::new("ffi_orders").unwrap();
+    let invalid_schema = CString::new(r#"{"type": 12}"#).unwrap();
+    assert!(!memex_set_json_schema(
+        handle,
+        namespace.as_ptr(),
+        invalid_schema.as_ptr()
+    ));
+    let schema = CString::new(r#"{"type": "object", "required": ["order"]}"#).unwrap();
+    assert!(memex_set_json_schema(
+        handle,
+        namespace.as_ptr(),
+        schema.as_ptr()
+    ));
+
+    let memories = serde_json::json!([
+        {
+            "user_id": "json_user",
+            "session_id": "json_session",
+            "content": r#"{"order": 42, "status": "shipped"}"#,
+            "content_type": "json",
+            "metadata": {"namespace": "ffi_orders"}
+        },
+        {
+            "user_id": "json_user",
+            "session_id": "json_session",
+            "content": r#"{"status": "shipped"}"#,
+            "content_type": "json",
+            "metadata": {"namespace": "ffi_orders"}
+        }
+    ]);
+    let memories = CString::new(memories.to_string()).unwrap();
+    let batch_ptr = memex_save_batch(handle, memories.as_ptr(), false);
+    assert!(!batch_ptr.is_null());
+    let batch: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(batch_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(batch_ptr);
+    assert_eq!(batch["success_count"], 1);
+    assert_eq!(batch["error_count"], 1);
+
+    let filter = CString::new(
+        r#"{"user_id": "json_user", "json_fields": {"order": 42}, "content_type": "json"}"#,
+    )
+    .unwrap();
+    let recall_ptr = memex_recall(handle, filter.as_ptr());
+    assert!(!recall_ptr.is_null());
+    let response: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(recall_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(recall_ptr);
+    assert_eq!(response["data"].as_array().unwrap().len(), 1);
+    assert_eq!(response["data"][0]["content_type"], "json");
+
+    memex_destroy(handle);
+}
+
+#[test]
+fn test_ffi_analytics_privacy() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("privacy.db").to_string_lossy(),
+        "enable_request_limits": false
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let context =
+        CString::new(r#"{"actor": "dashboard", "analytics_privacy": {"min_group_size": 10}}"#)
+            .unwrap();
+    assert!(memex_set_request_context(context.as_ptr()));
+
+    let filter = CString::new(r#"{"user_id": "privacy_user"}"#).unwrap();
+    assert!(memex_recall(handle, filter.as_ptr()).is_null());
+    assert_eq!(memex_get_last_error(), 14);
+
+    let stats_ptr = memex_get_stats(handle);
+    assert!(!stats_ptr.is_null());
+    let stats: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(stats_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(stats_ptr);
+    assert!(stats.get("database_path").is_none());
+    assert!(stats["total_memories"].is_u64());
+
+    assert!(memex_set_request_context(ptr::null()));
+    let recalled = memex_recall(handle, filter.as_ptr());
+    assert!(!recalled.is_null());
+    memex_free_string(recalled);
+
+    memex_destroy(handle);
+}
+
+#[test]
+fn test_ffi_batch_quota_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = serde_json::json!({
+        "database_path": temp_dir.path().join("quota.db").to_string_lossy(),
+        "max_bytes_per_user": 20
+    });
+    let config = CString::new(config.to_string()).unwrap();
+    let handle = memex_init_with_config(config.as_ptr());
+    assert_ne!(handle, 0);
+
+    let memories = serde_json::json!([
+        {"user_id": "quota_user", "session_id": "s1", "content": "0123456789"},
+        {"user_id": "quota_user", "session_id": "s1", "content": "abcdefghijk"}
+    ]);
+    let memories = CString::new(memories.to_string()).unwrap();
+
+    let policy = CString::new("sometimes").unwrap();
+    let batch_ptr = memex_save_batch_with_policy(handle, memories.as_ptr(), false, policy.as_ptr());
+    assert!(batch_ptr.is_null());
+    assert_eq!(memex_get_last_error(), 1);
+
+    let policy = CString::new("overflow_to_archive").unwrap();
+    let batch_ptr = memex_save_batch_with_policy(handle, memories.as_ptr(), false, policy.as_ptr());
+    assert!(!batch_ptr.is_null());
+    let batch: serde_json::Value =
+        serde_json::from_str(unsafe { CStr::from_ptr(batch_ptr) }.to_str().unwrap()).unwrap();
+    memex_free_string(batch_ptr);
+    assert_eq!(batch["success_count"], 2);
+    assert_eq!(batch["results"][1]["quota_action"]["action"], "archived");
+
+    memex_destroy(handle);
+}
@@ -102,6 +102,7 @@ mod async_vector_tests {
         let batch_request = crate::core::BatchRequest {
             items: memories,
             fail_on_error: false,
+            quota_policy: Default::default(),
         };
 
         let response = manager.save_memories_batch(batch_request).await.unwrap();
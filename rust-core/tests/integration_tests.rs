@@ -40,6 +40,7 @@ impl TestEnvironment {
             enable_request_limits: false, // Disable for testing
             max_requests_per_minute: 1000,
             max_batch_size: 100,
+            ..Default::default()
         };
 
         let db_config = DatabaseConfig {
@@ -59,6 +60,7 @@ impl TestEnvironment {
             max_memories_per_user: config.max_memories_per_user,
             compression_enabled: config.enable_compression,
             auto_summarize_sessions: true,
+            ..DecayPolicy::default()
         };
 
         let decay_engine = DecayEngine::new(database, validator, decay_policy);
@@ -128,6 +130,7 @@ fn test_full_memory_lifecycle() {
         importance: Some(0.9),
         metadata: None,
         ttl_hours: None,
+        ..Default::default()
     };
 
     let updated = env
@@ -597,6 +600,7 @@ fn test_batch_operations_workflow() {
     let batch_request = crate::core::BatchRequest {
         items: batch_memories.clone(),
         fail_on_error: false,
+        quota_policy: Default::default(),
     };
 
     let batch_response = env
@@ -624,6 +628,7 @@ fn test_batch_operations_workflow() {
     let fail_fast_request = crate::core::BatchRequest {
         items: batch_memories,
         fail_on_error: true,
+        quota_policy: Default::default(),
     };
 
     let fail_fast_response = env
@@ -1031,6 +1036,7 @@ fn test_error_recovery_and_resilience() {
         importance: None,
         metadata: None,
         ttl_hours: None,
+        ..Default::default()
     };
     assert!(!env
         .memory_manager